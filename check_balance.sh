@@ -14,13 +14,25 @@ echo "========================================="
 echo "Address: $ADDRESS"
 echo ""
 
+# Balances are scanned with the unlocked wallet, under its session token
+SESSION=$(curl -s -X POST http://localhost:18000 \
+  -H "Content-Type: application/json" \
+  -H "Authorization: Bearer ${ADMIN_KEY}" \
+  -d "{
+    \"jsonrpc\":\"2.0\",
+    \"method\":\"wallet_unlock\",
+    \"params\":{\"passphrase\":\"${WALLET_PASSPHRASE:-test-passphrase}\"},
+    \"id\":1
+  }" | python3 -c 'import json, sys; print(json.load(sys.stdin).get("result", {}).get("session", ""))')
+
 # Check balance via RPC
 RESULT=$(curl -s -X POST http://localhost:18000 \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer ${ADMIN_KEY}" \
   -d "{
     \"jsonrpc\":\"2.0\",
     \"method\":\"get_l1_balance\",
-    \"params\":{\"address\":\"$ADDRESS\"},
+    \"params\":{\"session\":\"$SESSION\",\"address\":\"$ADDRESS\"},
     \"id\":1
   }")
 
@@ -0,0 +1,841 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tracing::debug;
+use tari_l2_common::{Hash, PublicKey, crypto::KeyPair};
+use tari_l2_state_channel::{OrderStatus, StateUpdate, update::SignedStateUpdate};
+use crate::error::{ClientError, Result};
+use crate::signing;
+use crate::transport::Transport;
+use crate::types::*;
+
+/// Methods that only read state, so any transient failure can be retried
+const READ_ONLY_METHODS: &[&str] = &[
+    "get_node_info",
+    "get_l1_status",
+    "get_health",
+    "get_alerts",
+    "get_audit_log",
+    "attest_identity",
+    "prove_reserves",
+    "verify_reserve_proof",
+    "list_channels",
+    "list_peers",
+    "get_channel_info",
+    "replay_channel",
+    "verify_checkpoint",
+    "list_channel_requests",
+    "get_balance",
+    "get_spending_limits",
+    "list_awaiting_approval",
+    "get_listings",
+    "list_drafts",
+    "get_seller_catalog",
+    "get_orders",
+    "get_order",
+    "get_receipt",
+    "list_receipts",
+    "get_purchase_history",
+    "verify_receipt",
+    "get_escrow",
+    "list_escrows",
+    "get_l1_balance",
+    "list_contacts",
+    "get_contact",
+    "list_saved_searches",
+    "get_saved_search_results",
+    "list_invoices",
+    "get_payout_preference",
+];
+
+/// Timeout and retry behaviour
+#[derive(Clone, Debug)]
+pub struct ClientConfig {
+    /// Per-attempt deadline
+    pub timeout: Duration,
+
+    /// Extra attempts after a transient failure. Calls that change state
+    /// are only retried when the node was never reached.
+    pub max_retries: u32,
+
+    /// Delay before the first retry, doubled for each one after
+    pub retry_backoff: Duration,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+/// Typed client for a node's JSON-RPC API.
+///
+/// With a signer, listings, orders, escrow moves and transfers acting for
+/// the signer's key carry an `authorization` signed locally, and [`L2Client::propose_update`]
+/// signs channel updates before handing them to the node to co-sign.
+pub struct L2Client {
+    transport: Arc<dyn Transport>,
+    config: ClientConfig,
+    signer: Option<Arc<KeyPair>>,
+    next_id: AtomicU64,
+}
+
+impl L2Client {
+    /// Client for the node at `endpoint` (`host:port` or an `http://` URL)
+    #[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+    pub fn new(endpoint: &str) -> Self {
+        Self::with_transport(Arc::new(crate::transport::HttpTransport::new(endpoint)))
+    }
+
+    pub fn with_transport(transport: Arc<dyn Transport>) -> Self {
+        Self {
+            transport,
+            config: ClientConfig::default(),
+            signer: None,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn with_config(mut self, config: ClientConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sign actions locally with `keypair`
+    pub fn with_signer(mut self, keypair: Arc<KeyPair>) -> Self {
+        self.signer = Some(keypair);
+        self
+    }
+
+    pub fn signer(&self) -> Option<PublicKey> {
+        self.signer.as_ref().map(|kp| kp.public_key())
+    }
+
+    /// Call `method` with `params`, retrying transient failures
+    pub async fn call<P: Serialize, R: DeserializeOwned>(&self, method: &str, params: P) -> Result<R> {
+        let params = serde_json::to_value(params).map_err(|e| ClientError::Decode(e.to_string()))?;
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+        });
+        let body = serde_json::to_vec(&request).map_err(|e| ClientError::Decode(e.to_string()))?;
+        let read_only = READ_ONLY_METHODS.contains(&method);
+
+        let mut attempt = 0;
+        let result = loop {
+            match self.call_once(&body).await {
+                Err(e) if attempt < self.config.max_retries && retryable(&e, read_only) => {
+                    let delay = self.config.retry_backoff * 2u32.saturating_pow(attempt);
+                    debug!("{} failed ({}), retrying in {:?}", method, e, delay);
+                    crate::rt::sleep(delay).await;
+                    attempt += 1;
+                }
+                other => break other?,
+            }
+        };
+
+        serde_json::from_value(result)
+            .map_err(|e| ClientError::Decode(format!("{} result: {}", method, e)))
+    }
+
+    async fn call_once(&self, body: &[u8]) -> Result<Value> {
+        let bytes = crate::rt::timeout(self.config.timeout, self.transport.post(body.to_vec()))
+            .await
+            .ok_or(ClientError::Timeout)??;
+        let response: Value = serde_json::from_slice(&bytes).map_err(|e| ClientError::Decode(e.to_string()))?;
+
+        match response.get("error") {
+            Some(error) if !error.is_null() => Err(ClientError::Rpc {
+                code: error["code"].as_i64().unwrap_or(0) as i32,
+                message: error["message"].as_str().unwrap_or("unknown error").to_string(),
+                category: error["data"]["category"].as_str().and_then(|c| c.parse().ok()),
+            }),
+            _ => Ok(response.get("result").cloned().unwrap_or(Value::Null)),
+        }
+    }
+
+    /// Attach an `authorization` for `method` when the signer is the key
+    /// acting in `params`
+    fn authorize(&self, method: &str, params: Value, actor: &PublicKey) -> Result<Value> {
+        match self.signer.as_ref().filter(|kp| kp.public_key() == *actor) {
+            Some(signer) => signing::authorize(method, params, signer),
+            None => Ok(params),
+        }
+    }
+
+    /// Attach an `authorization` for `method` signed by the signer, if there
+    /// is one, so the node checks the signer's role rather than its own
+    fn authorize_as_signer(&self, method: &str, params: Value) -> Result<Value> {
+        match &self.signer {
+            Some(signer) => signing::authorize(method, params, signer),
+            None => Ok(params),
+        }
+    }
+
+    // ===== Node =====
+
+    pub async fn get_node_info(&self) -> Result<NodeInfo> {
+        self.call("get_node_info", Value::Null).await
+    }
+
+    pub async fn get_l1_status(&self) -> Result<L1Status> {
+        self.call("get_l1_status", Value::Null).await
+    }
+
+    /// Whether the node is healthy or degraded, with its disk usage
+    pub async fn get_health(&self) -> Result<Health> {
+        self.call("get_health", Value::Null).await
+    }
+
+    /// Recent operator alerts with an ID above `since` (0 for all), oldest
+    /// first. Poll with the last ID seen to follow new alerts.
+    pub async fn get_alerts(&self, since: u64) -> Result<Vec<Alert>> {
+        self.call("get_alerts", json!({ "since": since })).await
+    }
+
+    /// Up to `limit` audit log entries from `from`, as verified by the node
+    pub async fn get_audit_log(&self, from: u64, limit: Option<usize>) -> Result<AuditLog> {
+        self.call("get_audit_log", json!({ "from": from, "limit": limit })).await
+    }
+
+    /// Ask the node to sign `nonce`, proving it holds its key
+    pub async fn attest_identity(&self, nonce: &[u8]) -> Result<Attestation> {
+        self.call("attest_identity", json!({ "nonce": hex::encode(nonce) })).await
+    }
+
+    /// Ask a seller's node to sign `nonce` over the collateral locked on L1
+    /// for `channel_id`
+    pub async fn prove_reserves(&self, nonce: &[u8], channel_id: &Hash) -> Result<ReserveProofInfo> {
+        self.call("prove_reserves", json!({
+            "nonce": hex::encode(nonce),
+            "channel_id": channel_id
+        })).await
+    }
+
+    /// Check a seller's reserve proof answers `challenge` and covers `min_amount`
+    pub async fn verify_reserve_proof(
+        &self,
+        proof: &ReserveProofInfo,
+        challenge: &[u8],
+        min_amount: u64,
+    ) -> Result<ReserveVerification> {
+        self.call("verify_reserve_proof", json!({
+            "proof": proof,
+            "challenge": hex::encode(challenge),
+            "min_amount": min_amount
+        })).await
+    }
+
+    pub async fn list_peers(&self) -> Result<PeerList> {
+        self.call("list_peers", Value::Null).await
+    }
+
+    /// Connected peers advertising `capability`, e.g. `watchtower`
+    pub async fn find_peers(&self, capability: &str) -> Result<Vec<PeerInfo>> {
+        let peers: PeerList = self.call("list_peers", json!({ "capability": capability })).await?;
+        Ok(peers.table)
+    }
+
+    // ===== Channels =====
+
+    pub async fn list_channels(&self) -> Result<Vec<ChannelInfo>> {
+        self.call("list_channels", Value::Null).await
+    }
+
+    /// Open a two-party channel with `collateral` split evenly
+    pub async fn create_channel(&self, participant1: PublicKey, participant2: PublicKey, collateral: u64) -> Result<ChannelCreated> {
+        self.call("create_channel", json!({
+            "participant1": participant1,
+            "participant2": participant2,
+            "collateral": collateral,
+        })).await
+    }
+
+    pub async fn get_channel_info(&self, channel_id: &Hash) -> Result<ChannelInfo> {
+        self.call("get_channel_info", json!({ "channel_id": channel_id })).await
+    }
+
+    /// Re-apply the channel's stored history and report any divergence
+    pub async fn replay_channel(&self, channel_id: &Hash) -> Result<ReplayReport> {
+        self.call("replay_channel", json!({ "channel_id": channel_id })).await
+    }
+
+    /// Whether `state_root` of the channel is anchored in an L1 checkpoint,
+    /// whichever participant submitted it
+    pub async fn verify_checkpoint(&self, channel_id: &Hash, state_root: &Hash) -> Result<CheckpointVerification> {
+        self.call("verify_checkpoint", json!({ "channel_id": channel_id, "state_root": state_root })).await
+    }
+
+    pub async fn activate_channel(&self, channel_id: &Hash) -> Result<Created> {
+        self.call("activate_channel", json!({ "channel_id": channel_id })).await
+    }
+
+    pub async fn close_channel(&self, channel_id: &Hash) -> Result<StatusReply> {
+        self.call("close_channel", json!({ "channel_id": channel_id })).await
+    }
+
+    /// Close without the counterparties by publishing the node's latest
+    /// state of the channel to L1
+    pub async fn force_close_channel(&self, channel_id: &Hash) -> Result<ForceClosed> {
+        self.call("force_close_channel", json!({ "channel_id": channel_id })).await
+    }
+
+    pub async fn get_balance(&self, channel_id: &Hash, participant: &PublicKey) -> Result<u64> {
+        self.call("get_balance", json!({ "channel_id": channel_id, "participant": participant })).await
+    }
+
+    /// Cap what the node will sign away from a channel; default limits
+    /// return it to the node's configured caps
+    pub async fn set_spending_limits(&self, channel_id: &Hash, limits: &SpendingLimits) -> Result<StatusReply> {
+        self.call("set_spending_limits", json!({
+            "channel_id": channel_id,
+            "max_transfer": limits.max_transfer,
+            "max_daily": limits.max_daily,
+        })).await
+    }
+
+    pub async fn get_spending_limits(&self, channel_id: &Hash) -> Result<SpendingInfo> {
+        self.call("get_spending_limits", json!({ "channel_id": channel_id })).await
+    }
+
+    /// Counterparty proposals the node holds because they move its funds
+    /// beyond what it signs unattended
+    pub async fn list_awaiting_approval(&self, channel_id: &Hash) -> Result<Vec<SignedStateUpdate>> {
+        self.call("list_awaiting_approval", json!({ "channel_id": channel_id })).await
+    }
+
+    /// Have the node co-sign a held proposal
+    pub async fn approve_update(&self, channel_id: &Hash, nonce: u64) -> Result<UpdateApplied> {
+        self.call("approve_update", json!({ "channel_id": channel_id, "nonce": nonce })).await
+    }
+
+    /// Drop a held proposal without signing it
+    pub async fn decline_update(&self, channel_id: &Hash, nonce: u64) -> Result<StatusReply> {
+        self.call("decline_update", json!({ "channel_id": channel_id, "nonce": nonce })).await
+    }
+
+    /// Our latest state of a channel, sealed for a watchtower
+    pub async fn export_watch_blob(&self, channel_id: &Hash) -> Result<WatchBlob> {
+        self.call("export_watch_blob", json!({ "channel_id": channel_id })).await
+    }
+
+    /// Leave a watch blob with a watchtower node
+    pub async fn submit_watch_blob(&self, blob: &WatchBlob) -> Result<StatusReply> {
+        self.call("submit_watch_blob", json!({ "blob": blob })).await
+    }
+
+    /// Tell a watchtower a channel is closing; it checks the close on L1
+    pub async fn report_channel_close(&self, channel_id: &Hash) -> Result<CloseResponse> {
+        self.call("report_channel_close", json!({ "channel_id": channel_id })).await
+    }
+
+    pub async fn list_channel_requests(&self) -> Result<Vec<ChannelRequestInfo>> {
+        self.call("list_channel_requests", Value::Null).await
+    }
+
+    pub async fn approve_channel_request(&self, channel_id: &Hash) -> Result<Created> {
+        self.call("approve_channel_request", json!({ "channel_id": channel_id })).await
+    }
+
+    pub async fn reject_channel_request(&self, channel_id: &Hash) -> Result<StatusReply> {
+        self.call("reject_channel_request", json!({ "channel_id": channel_id })).await
+    }
+
+    /// Hand the node an update already signed for our side; it adds its
+    /// own signature and applies it once every participant has signed
+    pub async fn submit_state_update(&self, channel_id: &Hash, update: &SignedStateUpdate) -> Result<UpdateApplied> {
+        self.call("submit_state_update", json!({ "channel_id": channel_id, "update": update })).await
+    }
+
+    /// Sign `update` as the channel's next state with the local signer and
+    /// submit it for the node to co-sign
+    pub async fn propose_update(&self, channel_id: &Hash, update: StateUpdate) -> Result<UpdateApplied> {
+        let signer = self.signer.clone()
+            .ok_or_else(|| ClientError::Signing("no signer configured".to_string()))?;
+        let nonce = self.get_channel_info(channel_id).await?.nonce + 1;
+        let signed = signing::sign_update(update, nonce, &signer);
+        self.submit_state_update(channel_id, &signed).await
+    }
+
+    pub async fn transfer(&self, channel_id: &Hash, from: PublicKey, to: PublicKey, amount: u64) -> Result<UpdateApplied> {
+        let params = self.authorize("transfer", json!({
+            "channel_id": channel_id,
+            "from": from,
+            "to": to,
+            "amount": amount,
+        }), &from)?;
+        self.call("transfer", params).await
+    }
+
+    // ===== Invoices =====
+
+    /// Issue an invoice payable to the node, valid for `expires_in` seconds
+    /// (an hour if `None`)
+    pub async fn create_invoice(&self, amount: u64, memo: &str, listing_id: Option<Hash>, expires_in: Option<u64>) -> Result<InvoiceCreated> {
+        self.call("create_invoice", json!({
+            "amount": amount,
+            "memo": memo,
+            "listing_id": listing_id,
+            "expires_in": expires_in,
+        })).await
+    }
+
+    /// Invoices the node has issued
+    pub async fn list_invoices(&self) -> Result<Vec<Invoice>> {
+        self.call("list_invoices", Value::Null).await
+    }
+
+    /// Have the node pay an invoice through a channel it shares with the seller
+    pub async fn pay_invoice(&self, invoice: &Invoice) -> Result<InvoicePaid> {
+        self.call("pay_invoice", json!({ "invoice": invoice })).await
+    }
+
+    /// Pay an invoice given as the string its seller shared
+    pub async fn pay_encoded_invoice(&self, encoded: &str) -> Result<InvoicePaid> {
+        self.call("pay_invoice", json!({ "invoice": encoded })).await
+    }
+
+    // ===== Listings and orders =====
+
+    pub async fn create_listing(&self, mut listing: NewListing) -> Result<ListingCreated> {
+        if listing.seller.is_none() {
+            listing.seller = self.signer();
+        }
+        let params = serde_json::to_value(&listing).map_err(|e| ClientError::Decode(e.to_string()))?;
+        let params = match listing.seller {
+            Some(seller) => self.authorize("create_listing", params, &seller)?,
+            None => params,
+        };
+        self.call("create_listing", params).await
+    }
+
+    pub async fn get_listings(&self) -> Result<Vec<ListingInfo>> {
+        self.call("get_listings", Value::Null).await
+    }
+
+    /// Listings with approximate prices in `currency` (ISO 4217 code)
+    pub async fn get_listings_in(&self, currency: &str) -> Result<Vec<ListingInfo>> {
+        self.call("get_listings", json!({ "currency": currency })).await
+    }
+
+    /// Reprice one of the seller's listings. Orders already placed keep their price.
+    pub async fn update_listing_price(&self, listing_id: &Hash, seller: PublicKey, price: u64) -> Result<ListingInfo> {
+        let params = self.authorize("update_listing_price", json!({
+            "listing_id": listing_id,
+            "price": price,
+        }), &seller)?;
+        self.call("update_listing_price", params).await
+    }
+
+    /// Listings saved as drafts on the node
+    pub async fn list_drafts(&self) -> Result<Vec<ListingInfo>> {
+        self.call("list_drafts", Value::Null).await
+    }
+
+    /// Sign and broadcast one of the seller's drafts
+    pub async fn publish_listing(&self, listing_id: &Hash, seller: PublicKey) -> Result<ListingInfo> {
+        let params = self.authorize("publish_listing", json!({ "listing_id": listing_id }), &seller)?;
+        self.call("publish_listing", params).await
+    }
+
+    /// Withdraw one of the seller's listings from the network, keeping it as a draft
+    pub async fn unpublish_listing(&self, listing_id: &Hash, seller: PublicKey) -> Result<ListingInfo> {
+        let params = self.authorize("unpublish_listing", json!({ "listing_id": listing_id }), &seller)?;
+        self.call("unpublish_listing", params).await
+    }
+
+    /// A seller's profile, reputation, active listings and recent reviews
+    pub async fn get_seller_catalog(&self, seller: PublicKey) -> Result<SellerCatalogInfo> {
+        self.call("get_seller_catalog", json!({ "seller": seller })).await
+    }
+
+    /// Review the seller of a completed order as its buyer
+    pub async fn add_review(&self, order_id: &Hash, reviewer: PublicKey, rating: u8, comment: &str) -> Result<Review> {
+        let params = self.authorize("add_review", json!({
+            "order_id": order_id,
+            "reviewer": reviewer,
+            "rating": rating,
+            "comment": comment,
+        }), &reviewer)?;
+        self.call("add_review", params).await
+    }
+
+    pub async fn create_order(&self, channel_id: &Hash, listing_id: &Hash, buyer: PublicKey) -> Result<OrderCreated> {
+        let params = self.authorize("create_order", json!({
+            "channel_id": channel_id,
+            "listing_id": listing_id,
+            "buyer": buyer,
+        }), &buyer)?;
+        self.call("create_order", params).await
+    }
+
+    pub async fn get_orders(&self) -> Result<Vec<OrderInfo>> {
+        self.call("get_orders", Value::Null).await
+    }
+
+    /// An order with the escrow securing it
+    pub async fn get_order(&self, order_id: &Hash) -> Result<OrderInfo> {
+        self.call("get_order", json!({ "order_id": order_id })).await
+    }
+
+    /// Move an order along its lifecycle as the configured signer, which
+    /// must be the order's buyer or seller, or as the node without one
+    pub async fn update_order_status(&self, channel_id: &Hash, order_id: &Hash, status: OrderStatus) -> Result<OrderUpdated> {
+        let params = json!({
+            "channel_id": channel_id,
+            "order_id": order_id,
+            "status": status,
+        });
+        let params = self.authorize_as_signer("update_order_status", params)?;
+        self.call("update_order_status", params).await
+    }
+
+    /// Signed receipt for a completed order or closed channel, by its ID.
+    /// [`Receipt::verify`] checks it without asking the node.
+    pub async fn get_receipt(&self, id: &Hash) -> Result<Receipt> {
+        self.call("get_receipt", json!({ "id": id })).await
+    }
+
+    pub async fn list_receipts(&self) -> Result<Vec<Receipt>> {
+        self.call("list_receipts", Value::Null).await
+    }
+
+    /// A page of `buyer`'s orders across channels with their escrows,
+    /// payouts and receipts
+    pub async fn get_purchase_history(&self, buyer: &PublicKey, query: &HistoryQuery) -> Result<PurchaseHistoryPage> {
+        self.call("get_purchase_history", json!({
+            "buyer": buyer,
+            "since": query.since,
+            "until": query.until,
+            "offset": query.offset,
+            "limit": query.limit,
+        })).await
+    }
+
+    /// Have the node check a receipt, e.g. one exported by a counterparty
+    pub async fn verify_receipt(&self, receipt: &Receipt) -> Result<ReceiptVerification> {
+        self.call("verify_receipt", json!({ "receipt": receipt })).await
+    }
+
+    // ===== Address book =====
+
+    pub async fn list_contacts(&self) -> Result<Vec<Contact>> {
+        self.call("list_contacts", Value::Null).await
+    }
+
+    pub async fn get_contact(&self, label: &str) -> Result<Contact> {
+        self.call("get_contact", json!({ "label": label })).await
+    }
+
+    /// Name `public_key` as `label`, so channel and transfer calls can use
+    /// the label in place of the key
+    pub async fn add_contact(&self, label: &str, public_key: PublicKey, tari_address: Option<&str>) -> Result<Contact> {
+        self.call("add_contact", json!({
+            "label": label,
+            "public_key": public_key,
+            "tari_address": tari_address,
+        })).await
+    }
+
+    pub async fn update_contact(&self, label: &str, public_key: PublicKey, tari_address: Option<&str>) -> Result<StatusReply> {
+        self.call("update_contact", json!({
+            "label": label,
+            "public_key": public_key,
+            "tari_address": tari_address,
+        })).await
+    }
+
+    pub async fn remove_contact(&self, label: &str) -> Result<StatusReply> {
+        self.call("remove_contact", json!({ "label": label })).await
+    }
+
+    // ===== Saved searches =====
+
+    /// Save `query`; listings from the network that match it are kept for
+    /// [`L2Client::get_saved_search_results`] and POSTed to `webhook`
+    pub async fn save_search(&self, query: &SearchQuery, webhook: Option<&str>) -> Result<SavedSearch> {
+        self.call("save_search", json!({
+            "keywords": query.keywords,
+            "category": query.category,
+            "max_price": query.max_price,
+            "webhook": webhook,
+        })).await
+    }
+
+    pub async fn list_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        self.call("list_saved_searches", Value::Null).await
+    }
+
+    pub async fn get_saved_search_results(&self, search_id: &Hash) -> Result<SavedSearchResults> {
+        self.call("get_saved_search_results", json!({ "search_id": search_id })).await
+    }
+
+    pub async fn delete_saved_search(&self, search_id: &Hash) -> Result<StatusReply> {
+        self.call("delete_saved_search", json!({ "search_id": search_id })).await
+    }
+
+    // ===== Payout preferences =====
+
+    /// Set where escrow payouts and refunds owed to the signer go, or those
+    /// owed to the node without one
+    pub async fn set_payout_preference(&self, address: Option<&str>, method: PayoutMethod) -> Result<PayoutPreference> {
+        let params = json!({ "address": address, "method": method });
+        let params = self.authorize_as_signer("set_payout_preference", params)?;
+        self.call("set_payout_preference", params).await
+    }
+
+    /// A key's payout preference, the node's when `public_key` is `None`
+    pub async fn get_payout_preference(&self, public_key: Option<&PublicKey>) -> Result<Option<PayoutPreference>> {
+        self.call("get_payout_preference", json!({ "public_key": public_key })).await
+    }
+
+    // ===== Escrow =====
+
+    pub async fn create_escrow(&self, escrow: &NewEscrow) -> Result<Created> {
+        self.call("create_escrow", escrow).await
+    }
+
+    pub async fn fund_escrow(&self, escrow_id: &Hash, l1_tx_id: &str) -> Result<StatusReply> {
+        let params = self.authorize_as_signer("fund_escrow", json!({ "escrow_id": escrow_id, "l1_tx_id": l1_tx_id }))?;
+        self.call("fund_escrow", params).await
+    }
+
+    /// Fund an escrow from the buyer's channel balance instead of an L1
+    /// transaction. The escrow counts as funded once the lock is co-signed.
+    pub async fn fund_escrow_from_channel(&self, escrow_id: &Hash) -> Result<StatusReply> {
+        let params = self.authorize_as_signer("fund_escrow_from_channel", json!({ "escrow_id": escrow_id }))?;
+        self.call("fund_escrow_from_channel", params).await
+    }
+
+    pub async fn ship_order(&self, escrow_id: &Hash, tracking_info: Option<&str>) -> Result<StatusReply> {
+        let params = self.authorize_as_signer("ship_order", json!({ "escrow_id": escrow_id, "tracking_info": tracking_info }))?;
+        self.call("ship_order", params).await
+    }
+
+    /// Record the L1 address the signer, or the node without one, is paid
+    /// at when an escrow funded on L1 settles
+    pub async fn set_escrow_payout_address(&self, escrow_id: &Hash, address: &str) -> Result<StatusReply> {
+        let params = self.authorize_as_signer("set_escrow_payout_address", json!({ "escrow_id": escrow_id, "address": address }))?;
+        self.call("set_escrow_payout_address", params).await
+    }
+
+    pub async fn confirm_delivery(&self, escrow_id: &Hash) -> Result<EscrowSettled> {
+        let params = self.authorize_as_signer("confirm_delivery", json!({ "escrow_id": escrow_id }))?;
+        self.call("confirm_delivery", params).await
+    }
+
+    pub async fn request_refund(&self, escrow_id: &Hash, reason: &str) -> Result<StatusReply> {
+        let params = self.authorize_as_signer("request_refund", json!({ "escrow_id": escrow_id, "reason": reason }))?;
+        self.call("request_refund", params).await
+    }
+
+    pub async fn approve_refund(&self, escrow_id: &Hash) -> Result<EscrowSettled> {
+        let params = self.authorize_as_signer("approve_refund", json!({ "escrow_id": escrow_id }))?;
+        self.call("approve_refund", params).await
+    }
+
+    /// Take a refund for an escrow the seller did not ship by its deadline
+    pub async fn refund_unshipped(&self, escrow_id: &Hash) -> Result<EscrowSettled> {
+        let params = self.authorize_as_signer("refund_unshipped", json!({ "escrow_id": escrow_id }))?;
+        self.call("refund_unshipped", params).await
+    }
+
+    pub async fn raise_dispute(&self, escrow_id: &Hash, reason: &str) -> Result<StatusReply> {
+        let params = self.authorize_as_signer("raise_dispute", json!({ "escrow_id": escrow_id, "reason": reason }))?;
+        self.call("raise_dispute", params).await
+    }
+
+    pub async fn get_escrow(&self, escrow_id: &Hash) -> Result<EscrowInfo> {
+        self.call("get_escrow", json!({ "escrow_id": escrow_id })).await
+    }
+
+    pub async fn list_escrows(&self) -> Result<Vec<EscrowInfo>> {
+        self.call("list_escrows", Value::Null).await
+    }
+
+    // ===== Wallet =====
+
+    /// Create a wallet in the node's keystore, encrypted under `passphrase`.
+    /// Back up its seed phrase with [`Self::wallet_export`].
+    pub async fn wallet_create(&self, passphrase: &str) -> Result<WalletInfo> {
+        self.call("wallet_create", json!({ "passphrase": passphrase })).await
+    }
+
+    /// Import a wallet into the node's keystore, encrypted under `passphrase`
+    pub async fn wallet_import_seed(&self, seed_phrase: &str, passphrase: &str) -> Result<WalletInfo> {
+        self.call("wallet_import_seed", json!({ "seed_phrase": seed_phrase, "passphrase": passphrase })).await
+    }
+
+    pub async fn wallet_import_key(&self, private_key: &str, passphrase: &str) -> Result<WalletInfo> {
+        self.call("wallet_import_key", json!({ "private_key": private_key, "passphrase": passphrase })).await
+    }
+
+    /// Reveal the keystore wallet's seed phrase and private key. Needs an
+    /// admin key or owner signature.
+    pub async fn wallet_export(&self, passphrase: &str) -> Result<WalletExport> {
+        self.call("wallet_export", json!({ "passphrase": passphrase, "confirm": true })).await
+    }
+
+    /// Unlock the node's keystore until [`Self::wallet_lock`] or
+    /// `idle_timeout` seconds without use. The returned session token is
+    /// needed by every call that uses the wallet.
+    pub async fn wallet_unlock(&self, passphrase: &str, idle_timeout: Option<u64>) -> Result<WalletUnlocked> {
+        self.call("wallet_unlock", json!({ "passphrase": passphrase, "idle_timeout": idle_timeout })).await
+    }
+
+    pub async fn wallet_lock(&self, session: &str) -> Result<StatusReply> {
+        self.call("wallet_lock", json!({ "session": session })).await
+    }
+
+    /// Sign `message` with the wallet unlocked under `session`
+    pub async fn wallet_sign(&self, session: &str, message: &str) -> Result<WalletSignature> {
+        self.call("wallet_sign", json!({ "session": session, "message": message })).await
+    }
+
+    /// L1 balance of the wallet unlocked under `session`
+    pub async fn get_l1_balance(&self, session: &str) -> Result<L1Balance> {
+        self.call("get_l1_balance", json!({ "session": session })).await
+    }
+}
+
+fn retryable(error: &ClientError, read_only: bool) -> bool {
+    match error {
+        ClientError::Connect(_) => true,
+        e => read_only && e.is_transient(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+    use tari_l2_common::error::ErrorCategory;
+    use tari_l2_marketplace::{SignedAction, rpc_call_payload};
+
+    /// Replays canned outcomes and records every request body
+    struct MockTransport {
+        replies: Mutex<VecDeque<Result<Value>>>,
+        requests: Mutex<Vec<Value>>,
+    }
+
+    impl MockTransport {
+        fn new(replies: Vec<Result<Value>>) -> Arc<Self> {
+            Arc::new(Self {
+                replies: Mutex::new(replies.into()),
+                requests: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn requests(&self) -> Vec<Value> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl Transport for MockTransport {
+        async fn post(&self, body: Vec<u8>) -> Result<Vec<u8>> {
+            self.requests.lock().unwrap().push(serde_json::from_slice(&body).unwrap());
+            let reply = self.replies.lock().unwrap().pop_front().expect("unexpected request")?;
+            Ok(serde_json::to_vec(&reply).unwrap())
+        }
+    }
+
+    fn ok(result: Value) -> Result<Value> {
+        Ok(json!({ "jsonrpc": "2.0", "result": result, "error": null, "id": 1 }))
+    }
+
+    fn client(transport: Arc<MockTransport>) -> L2Client {
+        L2Client::with_transport(transport).with_config(ClientConfig {
+            retry_backoff: Duration::from_millis(1),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_typed_call() {
+        let pk = KeyPair::generate().public_key();
+        let transport = MockTransport::new(vec![ok(json!(1_500))]);
+        let channel_id = Hash::random();
+
+        let balance = client(transport.clone()).get_balance(&channel_id, &pk).await.unwrap();
+
+        assert_eq!(balance, 1_500);
+        let request = &transport.requests()[0];
+        assert_eq!(request["method"], "get_balance");
+        assert_eq!(request["params"]["participant"], serde_json::to_value(pk).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_rpc_error_keeps_code_and_category() {
+        let transport = MockTransport::new(vec![Ok(json!({
+            "jsonrpc": "2.0",
+            "result": null,
+            "error": { "code": -32004, "message": "Channel not found: x", "data": { "category": "not_found" } },
+            "id": 1
+        }))]);
+
+        let err = client(transport).get_channel_info(&Hash::random()).await.unwrap_err();
+        assert!(matches!(err, ClientError::Rpc { code: -32004, .. }));
+        assert_eq!(err.category(), Some(ErrorCategory::NotFound));
+        assert!(!err.is_transient());
+    }
+
+    #[tokio::test]
+    async fn test_retries_only_when_safe() {
+        // Reads retry any transient failure
+        let transport = MockTransport::new(vec![
+            Err(ClientError::Transport("reset".into())),
+            Err(ClientError::Timeout),
+            ok(json!([])),
+        ]);
+        assert!(client(transport.clone()).get_orders().await.unwrap().is_empty());
+        assert_eq!(transport.requests().len(), 3);
+
+        // Writes retry only when the node was never reached
+        let transport = MockTransport::new(vec![
+            Err(ClientError::Connect("refused".into())),
+            Err(ClientError::Transport("reset".into())),
+        ]);
+        let err = client(transport.clone()).activate_channel(&Hash::random()).await.unwrap_err();
+        assert!(matches!(err, ClientError::Transport(_)));
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_signer_authorizes_its_own_actions() {
+        let keypair = Arc::new(KeyPair::generate());
+        let other = KeyPair::generate().public_key();
+        let channel_id = Hash::random();
+        let applied = json!({ "channel_id": channel_id, "nonce": 1, "status": "applied" });
+        let transport = MockTransport::new(vec![ok(applied.clone()), ok(applied)]);
+        let client = client(transport.clone()).with_signer(keypair.clone());
+
+        client.transfer(&channel_id, keypair.public_key(), other, 10).await.unwrap();
+        client.transfer(&channel_id, other, keypair.public_key(), 10).await.unwrap();
+
+        let requests = transport.requests();
+        let mut params = requests[0]["params"].clone();
+        let auth = params.as_object_mut().unwrap().remove("authorization").unwrap();
+        let action = SignedAction {
+            payload: rpc_call_payload("transfer", &params),
+            public_key: serde_json::from_value(auth["public_key"].clone()).unwrap(),
+            signature: serde_json::from_value(auth["signature"].clone()).unwrap(),
+            timestamp: auth["timestamp"].as_u64().unwrap(),
+        };
+        assert!(action.verify().is_ok());
+
+        // Not acting as the signer: sent as a plain operator call
+        assert!(requests[1]["params"].get("authorization").is_none());
+    }
+}
@@ -0,0 +1,56 @@
+use thiserror::Error;
+use tari_l2_common::error::ErrorCategory;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    /// The node could not be reached; the request was never delivered
+    #[error("Connection failed: {0}")]
+    Connect(String),
+
+    /// The request may have reached the node but no response arrived
+    #[error("Transport error: {0}")]
+    Transport(String),
+
+    #[error("Request timed out")]
+    Timeout,
+
+    /// The node requires an API key and ours was missing or unknown
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// The node answered with a JSON-RPC error
+    #[error("RPC error {code}: {message}")]
+    Rpc {
+        code: i32,
+        message: String,
+        category: Option<ErrorCategory>,
+    },
+
+    #[error("Invalid response: {0}")]
+    Decode(String),
+
+    #[error("Signing failed: {0}")]
+    Signing(String),
+}
+
+impl ClientError {
+    /// Whether retrying could succeed. Only `Connect` failures are safe to
+    /// retry for calls that change state; the rest may have been applied.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ClientError::Connect(_) | ClientError::Transport(_) | ClientError::Timeout => true,
+            ClientError::Rpc { category, .. } => *category == Some(ErrorCategory::Unavailable),
+            ClientError::Unauthorized(_) | ClientError::Decode(_) | ClientError::Signing(_) => false,
+        }
+    }
+
+    /// Category reported by the node, for RPC errors
+    pub fn category(&self) -> Option<ErrorCategory> {
+        match self {
+            ClientError::Rpc { category, .. } => *category,
+            _ => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
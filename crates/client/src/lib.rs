@@ -0,0 +1,25 @@
+//! Typed async client for the Tari L2 node JSON-RPC API.
+//!
+//! ```no_run
+//! # async fn run() -> tari_l2_client::Result<()> {
+//! use tari_l2_client::L2Client;
+//!
+//! let client = L2Client::new("127.0.0.1:18000");
+//! let info = client.get_node_info().await?;
+//! println!("{} on {}", info.version, info.network);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod client;
+pub mod error;
+pub mod signing;
+pub mod transport;
+pub mod types;
+mod rt;
+
+pub use client::{ClientConfig, L2Client};
+pub use error::{ClientError, Result};
+pub use transport::Transport;
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub use transport::HttpTransport;
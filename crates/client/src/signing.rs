@@ -0,0 +1,60 @@
+//! Local signing of marketplace actions and channel updates, so keys never
+//! have to leave the caller
+
+use serde_json::{json, Value};
+use tari_l2_common::crypto::KeyPair;
+use tari_l2_marketplace::{SignedAction, rpc_call_payload};
+use tari_l2_state_channel::{StateUpdate, update::SignedStateUpdate};
+use crate::error::{ClientError, Result};
+
+/// Sign a call to `method` with `params` and attach the `authorization`
+/// object the node's RPC server verifies for listings, orders and transfers
+pub fn authorize(method: &str, params: Value, keypair: &KeyPair) -> Result<Value> {
+    if !params.is_object() {
+        return Err(ClientError::Signing("params must be a JSON object".to_string()));
+    }
+    let action = SignedAction::new(rpc_call_payload(method, &params), keypair.public_key(), |message| keypair.sign(message))
+        .map_err(ClientError::Signing)?;
+
+    let mut signed = params;
+    signed["authorization"] = json!({
+        "public_key": action.public_key,
+        "signature": action.signature,
+        "timestamp": action.timestamp,
+    });
+    Ok(signed)
+}
+
+/// Sign `update` as channel state `nonce`, ready for `submit_state_update`
+pub fn sign_update(update: StateUpdate, nonce: u64, keypair: &KeyPair) -> SignedStateUpdate {
+    let mut signed = SignedStateUpdate::new(update, nonce);
+    signed.add_signature(keypair.public_key(), keypair.sign(&signed.signing_message()));
+    signed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorization_verifies_over_method_and_remaining_params() {
+        let keypair = KeyPair::generate();
+        let mut params = authorize("create_listing", json!({ "title": "Lamp", "price": 40 }), &keypair).unwrap();
+        let auth = params.as_object_mut().unwrap().remove("authorization").unwrap();
+
+        let mut action = SignedAction {
+            payload: rpc_call_payload("create_listing", &params),
+            public_key: serde_json::from_value(auth["public_key"].clone()).unwrap(),
+            signature: serde_json::from_value(auth["signature"].clone()).unwrap(),
+            timestamp: auth["timestamp"].as_u64().unwrap(),
+        };
+        assert!(action.verify().is_ok());
+        assert_eq!(action.public_key, keypair.public_key());
+
+        // The same signature does not authorize another method
+        action.payload = rpc_call_payload("publish_listing", &params);
+        assert!(action.verify().is_err());
+
+        assert!(authorize("create_listing", json!([1, 2]), &keypair).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use async_trait::async_trait;
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+use hyper::{Body, Client, Method, Request, client::HttpConnector};
+use crate::error::Result;
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+use crate::error::ClientError;
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+use std::sync::Arc;
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+use tari_l2_common::crypto::KeyPair;
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+use tari_l2_marketplace::{rpc_request_payload, SignedAction};
+
+/// Carries an encoded JSON-RPC request to the node and returns the raw
+/// response body. Browser futures are not `Send`, so on wasm32 neither are
+/// a transport's.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Transport: Send + Sync {
+    async fn post(&self, body: Vec<u8>) -> Result<Vec<u8>>;
+}
+
+/// JSON-RPC over HTTP POST, as served by the node's RPC server
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub struct HttpTransport {
+    client: Client<HttpConnector>,
+    url: String,
+    api_key: Option<String>,
+    owner: Option<Arc<KeyPair>>,
+}
+
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+impl HttpTransport {
+    /// `endpoint` is `host:port` or a full `http://` URL
+    pub fn new(endpoint: &str) -> Self {
+        let url = if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+            endpoint.to_string()
+        } else {
+            format!("http://{}/", endpoint)
+        };
+        Self { client: Client::new(), url, api_key: None, owner: None }
+    }
+
+    /// Send `key` as a bearer token, for nodes that require API keys
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// Sign each request with `keypair`, for nodes that list its public key
+    /// under `rpc.api_keys.owners`
+    pub fn with_owner_key(mut self, keypair: Arc<KeyPair>) -> Self {
+        self.owner = Some(keypair);
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn post(&self, body: Vec<u8>) -> Result<Vec<u8>> {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.url)
+            .header("Content-Type", "application/json");
+        if let Some(key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", key));
+        }
+        if let Some(owner) = &self.owner {
+            let uri = self.url.parse::<hyper::Uri>().map_err(|e| ClientError::Transport(e.to_string()))?;
+            let payload = rpc_request_payload("POST", uri.path(), &body);
+            let action = SignedAction::new(payload, owner.public_key(), |message| owner.sign(message))
+                .map_err(ClientError::Signing)?;
+            request = request
+                .header("X-L2-Public-Key", action.public_key.to_string())
+                .header("X-L2-Timestamp", action.timestamp.to_string())
+                .header("X-L2-Signature", action.signature.to_string());
+        }
+        let request = request
+            .body(Body::from(body))
+            .map_err(|e| ClientError::Transport(e.to_string()))?;
+
+        let response = self.client.request(request).await.map_err(|e| {
+            if e.is_connect() {
+                ClientError::Connect(e.to_string())
+            } else {
+                ClientError::Transport(e.to_string())
+            }
+        })?;
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body()).await
+            .map_err(|e| ClientError::Transport(e.to_string()))?;
+
+        if status == hyper::StatusCode::UNAUTHORIZED {
+            return Err(ClientError::Unauthorized(String::from_utf8_lossy(&bytes).into_owned()));
+        }
+        if !status.is_success() {
+            return Err(ClientError::Transport(format!(
+                "HTTP {}: {}", status, String::from_utf8_lossy(&bytes)
+            )));
+        }
+        Ok(bytes.to_vec())
+    }
+}
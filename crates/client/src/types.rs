@@ -0,0 +1,470 @@
+//! Request parameters and responses of the node's JSON-RPC methods
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{Hash, PublicKey, Signature};
+use tari_l2_state_channel::OrderStatus;
+
+pub use tari_l2_state_channel::channel::ChannelInfo;
+pub use tari_l2_state_channel::replay::{Divergence, ReplayReport};
+pub use tari_l2_state_channel::state::FiatReference;
+pub use tari_l2_marketplace::profile::{Review, SellerReputation, UserProfile};
+pub use tari_l2_marketplace::auth::ReserveSource;
+pub use tari_l2_marketplace::receipt::{Receipt, ReceiptKind, ReceiptTerms};
+pub use tari_l2_marketplace::alert::{Alert, AlertKind, AlertSeverity};
+pub use tari_l2_marketplace::audit::{AuditEntry, AuditEvent, L1Action};
+pub use tari_l2_marketplace::disk::{DiskLevel, DiskStatus};
+pub use tari_l2_marketplace::spending::SpendingLimits;
+pub use tari_l2_marketplace::contacts::Contact;
+pub use tari_l2_marketplace::saved_search::{SavedSearch, SearchQuery};
+pub use tari_l2_marketplace::invoice::{Invoice, InvoiceTerms};
+pub use tari_l2_marketplace::payout::{PayoutMethod, PayoutPreference};
+pub use tari_l2_marketplace::watch::WatchBlob;
+pub use tari_l2_marketplace::catalog::CatalogIntegrity;
+pub use tari_l2_marketplace::history::HistoryQuery;
+
+/// A range of the node's audit log. Check it independently with
+/// [`tari_l2_marketplace::audit::verify_chain`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditLog {
+    pub entries: Vec<AuditEntry>,
+    /// Whether every entry is intact and links to the one before
+    pub verified: bool,
+    /// First entry that failed verification
+    #[serde(default)]
+    pub broken_at: Option<u64>,
+    /// Latest entry in the whole log, to record for later audits
+    #[serde(default)]
+    pub head_seq: Option<u64>,
+    #[serde(default)]
+    pub head_hash: Option<Hash>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeInfo {
+    pub public_key: PublicKey,
+    pub address: String,
+    pub version: String,
+    pub network: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct L1Status {
+    pub connected: bool,
+    pub network: String,
+    pub endpoint: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Health {
+    /// "ok", or "degraded" while disk space is low
+    pub status: String,
+    pub l1_connected: bool,
+    pub disk: DiskStatus,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Attestation {
+    pub public_key: PublicKey,
+    pub peer_id: Option<String>,
+    /// Hex-encoded challenge echoed back
+    pub nonce: String,
+    pub timestamp: u64,
+    pub signature: Signature,
+}
+
+/// Signed statement of a node's L1 reserves, as returned by `prove_reserves`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReserveProofInfo {
+    pub public_key: PublicKey,
+    pub source: ReserveSource,
+    pub amount: u64,
+    pub block_height: u64,
+    /// Hex-encoded challenge echoed back
+    pub nonce: String,
+    pub timestamp: u64,
+    pub signature: Signature,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReceiptVerification {
+    pub valid: bool,
+    pub id: Hash,
+    pub issuer: PublicKey,
+}
+
+/// Whether a channel state root is anchored on L1
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointVerification {
+    pub channel_id: Hash,
+    pub state_root: Hash,
+    pub anchored: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReserveVerification {
+    pub valid: bool,
+    pub public_key: PublicKey,
+    pub amount: u64,
+    /// Why the proof was rejected
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PeerList {
+    pub local_id: Option<String>,
+    pub peers: Vec<PublicKey>,
+    /// Identified peers with what they advertise
+    #[serde(default)]
+    pub table: Vec<PeerInfo>,
+}
+
+/// A peer and the capabilities it advertises, e.g. `watchtower` or
+/// `accepts-channel-opens`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PeerInfo {
+    pub peer_id: String,
+    pub public_key: Option<PublicKey>,
+    pub protocol_version: u32,
+    pub capabilities: Vec<String>,
+}
+
+impl PeerInfo {
+    pub fn offers(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChannelCreated {
+    pub id: Hash,
+    pub status: String,
+    pub participant1: PublicKey,
+    pub participant2: PublicKey,
+    pub collateral: u64,
+}
+
+/// Acknowledgement naming the object a call acted on
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Created {
+    pub id: Hash,
+    pub status: String,
+}
+
+/// A channel's spending limits and what counted against them in the last day
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpendingInfo {
+    pub channel_id: Hash,
+    pub max_transfer: Option<u64>,
+    pub max_daily: Option<u64>,
+    pub spent_last_24h: u64,
+}
+
+/// Challenges a watchtower filed against a reported close
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CloseResponse {
+    pub channel_id: Hash,
+    /// L1 dispute transaction IDs, empty when the close was not stale
+    pub disputes: Vec<String>,
+}
+
+/// Reply to a force close
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ForceClosed {
+    pub status: String,
+    /// L1 transaction publishing the state
+    pub tx_id: String,
+    /// Counterparties can counter with a later state until then
+    pub challenge_deadline: u64,
+}
+
+/// Acknowledgement carrying only the resulting status
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatusReply {
+    pub status: String,
+}
+
+/// Reply to settling an escrow
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EscrowSettled {
+    pub status: String,
+    /// L1 payout transaction, when the escrow was funded on L1
+    #[serde(default)]
+    pub payout_tx_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpdateApplied {
+    pub channel_id: Hash,
+    pub nonce: u64,
+    /// "applied", or "proposed" while other participants have yet to sign
+    pub status: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderUpdated {
+    pub channel_id: Hash,
+    pub order_id: Hash,
+    pub order_status: OrderStatus,
+    pub nonce: u64,
+    /// "applied", or "proposed" while other participants have yet to sign
+    pub status: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InvoiceCreated {
+    pub invoice: Invoice,
+    /// The invoice as a single string for sharing with the buyer
+    pub encoded: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InvoicePaid {
+    pub invoice_id: Hash,
+    pub channel_id: Hash,
+    pub nonce: u64,
+    /// "applied", or "proposed" while the seller has yet to sign
+    pub status: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChannelRequestInfo {
+    pub channel_id: Hash,
+    pub initiator: PublicKey,
+    pub participants: Vec<PublicKey>,
+    pub collateral: u64,
+    pub challenge_period: u64,
+    pub received_at: u64,
+    pub review_reason: Option<String>,
+}
+
+/// Parameters for `create_listing`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct NewListing {
+    /// Defaults to the client's signer, or the node's key if there is none
+    #[serde(rename = "seller_pubkey", default, skip_serializing_if = "Option::is_none")]
+    pub seller: Option<PublicKey>,
+    pub title: String,
+    pub description: String,
+    pub price: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipfs_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Seller's reference price in a fiat currency, shown alongside the µT price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fiat_reference: Option<FiatReference>,
+    /// Keep the listing as a local draft instead of broadcasting it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub draft: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListingCreated {
+    pub id: Hash,
+    pub title: String,
+    pub price: u64,
+    pub seller: PublicKey,
+    pub status: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListingInfo {
+    pub id: Hash,
+    /// Placeholder `get_listings` still sends; global listings belong to
+    /// no channel
+    #[serde(default)]
+    pub channel_id: Option<Hash>,
+    pub seller: PublicKey,
+    pub title: String,
+    pub description: String,
+    pub price: u64,
+    pub ipfs_hash: String,
+    pub active: bool,
+    pub category: String,
+    #[serde(default)]
+    pub fiat_reference: Option<FiatReference>,
+    /// Approximate fiat price from the node's exchange rates, if known
+    #[serde(default)]
+    pub fiat_value: Option<FiatValue>,
+    /// Hash of the listing's canonical encoding
+    #[serde(default)]
+    pub content_hash: Option<Hash>,
+}
+
+/// Listings that matched a saved search, newest first
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedSearchResults {
+    pub search_id: Hash,
+    pub results: Vec<SavedSearchHit>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedSearchHit {
+    pub matched_at: tari_l2_common::Timestamp,
+    /// The listing as it is now
+    pub listing: tari_l2_state_channel::state::Listing,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FiatValue {
+    pub currency: String,
+    pub amount: f64,
+}
+
+/// A seller's storefront page in one response
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SellerCatalogInfo {
+    pub seller: PublicKey,
+    pub profile: Option<UserProfile>,
+    pub reputation: SellerReputation,
+    pub listings: Vec<ListingInfo>,
+    /// Newest first
+    pub recent_reviews: Vec<Review>,
+    /// Whether the listings match the catalog root the seller announced
+    #[serde(default)]
+    pub integrity: CatalogIntegrity,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderCreated {
+    pub id: Hash,
+    pub listing_id: Hash,
+    pub status: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderInfo {
+    pub id: Hash,
+    pub channel_id: Hash,
+    pub listing_id: Hash,
+    pub buyer: PublicKey,
+    pub seller: PublicKey,
+    pub amount: u64,
+    pub status: String,
+    /// Escrow securing the order, if one was created
+    #[serde(default)]
+    pub escrow: Option<EscrowInfo>,
+}
+
+/// An order the buyer placed, with its escrow and receipt
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PurchaseInfo {
+    #[serde(flatten)]
+    pub order: OrderInfo,
+    /// When the order was placed, if known
+    pub placed_at: Option<u64>,
+    /// When the order settled, if it has
+    pub settled_at: Option<u64>,
+    pub receipt: Option<Receipt>,
+}
+
+/// A page of a buyer's purchase history, newest first
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PurchaseHistoryPage {
+    pub purchases: Vec<PurchaseInfo>,
+    /// Purchases in the date range across all pages
+    pub total: usize,
+}
+
+/// Parameters for `create_escrow`; parties and amount come from the order
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NewEscrow {
+    pub channel_id: Hash,
+    pub order_id: Hash,
+    /// Seconds before the escrow times out (node default: 24 hours)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_period: Option<u64>,
+    /// Seconds after funding the seller has to ship before the buyer can
+    /// take a refund on their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ship_within: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EscrowInfo {
+    pub id: Hash,
+    pub listing_id: Hash,
+    pub order_id: Hash,
+    pub channel_id: Hash,
+    pub buyer: PublicKey,
+    pub seller: PublicKey,
+    pub amount: u64,
+    pub status: String,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub timeout_period: u64,
+    pub l1_tx_id: Option<String>,
+    /// Funds are locked from the buyer's channel balance rather than on L1
+    #[serde(default)]
+    pub funded_in_channel: bool,
+    #[serde(default)]
+    pub ship_within: Option<u64>,
+    /// Ship-by deadline, set once the escrow is funded
+    #[serde(default)]
+    pub ship_by: Option<u64>,
+    pub tracking_info: Option<String>,
+    pub dispute_reason: Option<String>,
+    #[serde(default)]
+    pub seller_address: Option<String>,
+    #[serde(default)]
+    pub buyer_address: Option<String>,
+    /// L1 transaction that paid out an escrow funded on L1
+    #[serde(default)]
+    pub payout_tx_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WalletInfo {
+    pub address: String,
+    pub address_hex: String,
+    pub public_key: String,
+    /// Encrypted keystore the wallet was saved to
+    #[serde(default)]
+    pub keystore: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// A wallet's secrets, from `wallet_export`. `Debug` leaves them out.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WalletExport {
+    pub address: String,
+    pub public_key: String,
+    pub private_key: String,
+    /// Absent for wallets imported from a private key
+    #[serde(default)]
+    pub seed_phrase: Option<String>,
+}
+
+impl std::fmt::Debug for WalletExport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("WalletExport")
+            .field("address", &self.address)
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WalletUnlocked {
+    pub address: String,
+    pub public_key: String,
+    /// Token the wallet calls must present
+    pub session: String,
+    /// Seconds without use before the wallet locks again
+    pub idle_timeout: u64,
+    pub status: String,
+}
+
+/// A message signed by the unlocked wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WalletSignature {
+    pub public_key: PublicKey,
+    pub signature: Signature,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct L1Balance {
+    pub balance: u64,
+    pub source: String,
+}
@@ -0,0 +1,270 @@
+//! Checksummed, human-friendly encoding for L2 public keys.
+//!
+//! Addresses use bech32m (BIP-350) with a human-readable prefix per network,
+//! e.g. `tl2e1q...` on Esmeralda. The first data character carries the key's
+//! signature scheme, followed by the 32 key bytes. A single mistyped
+//! character is always caught by the checksum, unlike bare hex.
+
+use crate::error::{L2Error, Result};
+use crate::types::{PublicKey, SignatureScheme};
+use std::fmt;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+const CHECKSUM_LENGTH: usize = 6;
+const SEPARATOR: char = '1';
+
+/// Network an address belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AddressNetwork {
+    Mainnet,
+    Esmeralda,
+    Nextnet,
+    Localnet,
+}
+
+impl AddressNetwork {
+    pub const ALL: [AddressNetwork; 4] = [
+        AddressNetwork::Mainnet,
+        AddressNetwork::Esmeralda,
+        AddressNetwork::Nextnet,
+        AddressNetwork::Localnet,
+    ];
+
+    /// Human-readable prefix used for this network
+    pub fn hrp(&self) -> &'static str {
+        match self {
+            AddressNetwork::Mainnet => "tl2",
+            AddressNetwork::Esmeralda => "tl2e",
+            AddressNetwork::Nextnet => "tl2n",
+            AddressNetwork::Localnet => "tl2l",
+        }
+    }
+
+    pub fn from_hrp(hrp: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|network| network.hrp() == hrp)
+    }
+}
+
+impl fmt::Display for AddressNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.hrp())
+    }
+}
+
+/// Encode a public key as an address for `network`
+pub fn encode_address(network: AddressNetwork, key: &PublicKey) -> String {
+    let mut data = vec![scheme_version(key.scheme())];
+    data.extend(convert_bits(key.as_bytes(), 8, 5, true).expect("padding is allowed"));
+    bech32m_encode(network.hrp(), &data)
+}
+
+/// Decode an address into its network and public key
+pub fn decode_address(address: &str) -> Result<(AddressNetwork, PublicKey)> {
+    let (hrp, data) = bech32m_decode(address)?;
+    let network = AddressNetwork::from_hrp(&hrp)
+        .ok_or_else(|| invalid(format!("unknown network prefix '{}'", hrp)))?;
+
+    let (version, payload) = data.split_first()
+        .ok_or_else(|| invalid("missing key data"))?;
+    let scheme = match version {
+        0 => SignatureScheme::Ed25519,
+        1 => SignatureScheme::Schnorr,
+        v => return Err(invalid(format!("unsupported key version {}", v))),
+    };
+
+    let bytes = convert_bits(payload, 5, 8, false)?;
+    let key = PublicKey::from_slice(&bytes).map_err(invalid)?;
+    Ok((network, PublicKey::new_with_scheme(*key.as_bytes(), scheme)))
+}
+
+/// Whether a string looks like an L2 address rather than hex
+pub fn is_address(s: &str) -> bool {
+    s.rsplit_once(SEPARATOR)
+        .is_some_and(|(hrp, _)| AddressNetwork::from_hrp(&hrp.to_ascii_lowercase()).is_some())
+}
+
+fn scheme_version(scheme: SignatureScheme) -> u8 {
+    match scheme {
+        SignatureScheme::Ed25519 => 0,
+        SignatureScheme::Schnorr => 1,
+    }
+}
+
+fn invalid(msg: impl fmt::Display) -> L2Error {
+    L2Error::InvalidParameter(format!("Invalid address: {}", msg))
+}
+
+fn polymod(values: impl IntoIterator<Item = u8>) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a_57b2, 0x2650_8e6d, 0x1ea1_19fa, 0x3d42_33dd, 0x2a14_62b3];
+
+    let mut chk: u32 = 1;
+    for value in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x01ff_ffff) << 5) ^ value as u32;
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let bytes = hrp.as_bytes();
+    bytes.iter().map(|b| b >> 5)
+        .chain(std::iter::once(0))
+        .chain(bytes.iter().map(|b| b & 0x1f))
+        .collect()
+}
+
+fn bech32m_encode(hrp: &str, data: &[u8]) -> String {
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(data);
+    values.extend_from_slice(&[0u8; CHECKSUM_LENGTH]);
+    let pm = polymod(values) ^ BECH32M_CONST;
+
+    let mut out = String::with_capacity(hrp.len() + 1 + data.len() + CHECKSUM_LENGTH);
+    out.push_str(hrp);
+    out.push(SEPARATOR);
+    for d in data {
+        out.push(CHARSET[*d as usize] as char);
+    }
+    for i in 0..CHECKSUM_LENGTH {
+        let d = (pm >> (5 * (5 - i))) & 0x1f;
+        out.push(CHARSET[d as usize] as char);
+    }
+    out
+}
+
+fn bech32m_decode(s: &str) -> Result<(String, Vec<u8>)> {
+    if s.len() > 90 {
+        return Err(invalid("too long"));
+    }
+    if s.chars().any(|c| c.is_ascii_lowercase()) && s.chars().any(|c| c.is_ascii_uppercase()) {
+        return Err(invalid("mixed case"));
+    }
+    let s = s.to_ascii_lowercase();
+
+    let (hrp, rest) = s.rsplit_once(SEPARATOR)
+        .ok_or_else(|| invalid("missing separator"))?;
+    if hrp.is_empty() || rest.len() < CHECKSUM_LENGTH {
+        return Err(invalid("too short"));
+    }
+    if hrp.bytes().any(|b| !(33..=126).contains(&b)) {
+        return Err(invalid("invalid prefix character"));
+    }
+
+    let data = rest.bytes()
+        .map(|c| {
+            CHARSET.iter().position(|&x| x == c)
+                .map(|p| p as u8)
+                .ok_or_else(|| invalid(format!("invalid character '{}'", c as char)))
+        })
+        .collect::<Result<Vec<u8>>>()?;
+
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(&data);
+    if polymod(values) != BECH32M_CONST {
+        return Err(invalid("checksum mismatch"));
+    }
+
+    Ok((hrp.to_string(), data[..data.len() - CHECKSUM_LENGTH].to_vec()))
+}
+
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>> {
+    let mut acc: u32 = 0;
+    let mut bits: u32 = 0;
+    let max_value = (1u32 << to) - 1;
+    let mut out = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+
+    for &value in data {
+        if (value as u32) >> from != 0 {
+            return Err(invalid("data out of range"));
+        }
+        acc = (acc << from) | value as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & max_value) as u8);
+        }
+    }
+
+    if pad {
+        if bits > 0 {
+            out.push(((acc << (to - bits)) & max_value) as u8);
+        }
+    } else if bits >= from || (acc << (to - bits)) & max_value != 0 {
+        return Err(invalid("non-zero padding"));
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    #[test]
+    fn test_bip350_vectors() {
+        assert!(bech32m_decode("A1LQFN3A").is_ok());
+        assert!(bech32m_decode("a1lqfn3a").is_ok());
+        assert!(bech32m_decode("abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx").is_ok());
+        assert!(bech32m_decode("split1checkupstagehandshakeupstreamerranterredcaperredlc445v").is_ok());
+
+        // Bech32 (not bech32m) checksum must be rejected
+        assert!(bech32m_decode("a12uel5l").is_err());
+        assert!(bech32m_decode("A1lqfn3a").is_err());
+    }
+
+    #[test]
+    fn test_address_vector() {
+        let key = PublicKey::new([0u8; 32]);
+        assert_eq!(
+            encode_address(AddressNetwork::Mainnet, &key),
+            "tl21qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqcys907"
+        );
+
+        let mut bytes = [0u8; 32];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let key = PublicKey::new_with_scheme(bytes, SignatureScheme::Schnorr);
+        let address = "tl2e1pqqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0s7nqptw";
+        assert_eq!(encode_address(AddressNetwork::Esmeralda, &key), address);
+        assert_eq!(decode_address(address).unwrap(), (AddressNetwork::Esmeralda, key));
+    }
+
+    #[test]
+    fn test_address_roundtrip() {
+        for kp in [KeyPair::generate(), KeyPair::generate_ristretto()] {
+            for network in AddressNetwork::ALL {
+                let address = encode_address(network, &kp.public_key());
+                assert!(address.starts_with(network.hrp()));
+                assert!(is_address(&address));
+
+                let (decoded_network, key) = decode_address(&address).unwrap();
+                assert_eq!(decoded_network, network);
+                assert_eq!(key, kp.public_key());
+                assert_eq!(key.scheme(), kp.scheme());
+                assert_eq!(address.to_uppercase().parse::<PublicKey>().unwrap(), key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_typo_detected() {
+        let kp = KeyPair::generate();
+        let address = encode_address(AddressNetwork::Esmeralda, &kp.public_key());
+
+        let mut chars: Vec<char> = address.chars().collect();
+        let i = address.len() - 10;
+        chars[i] = if chars[i] == 'q' { 'p' } else { 'q' };
+        let typo: String = chars.into_iter().collect();
+
+        assert!(decode_address(&typo).is_err());
+        assert!(!is_address(&kp.public_key().to_string()));
+    }
+}
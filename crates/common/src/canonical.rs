@@ -0,0 +1,68 @@
+//! Canonical encoding of anything hashed or signed.
+//!
+//! Two honest nodes must get the same bytes, and so the same hash, for the
+//! same value. bincode fixes field order, integer widths and byte order, but
+//! a `HashMap` serializes in iteration order, which differs from one
+//! process to the next. Maps in hashed or signed types are therefore
+//! serialized with [`sorted_map`], which writes entries ordered by their
+//! encoded key, and hashing and signing go through [`encode`] and [`hash`]
+//! so the format is defined in one place.
+
+use std::collections::HashMap;
+use serde::{Serialize, Serializer};
+use crate::crypto;
+use crate::types::Hash;
+
+/// Canonical bytes of `value`: bincode, with maps marked [`sorted_map`] in
+/// key order
+pub fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, String> {
+    bincode::serialize(value).map_err(|e| e.to_string())
+}
+
+/// Hash of the canonical bytes of `value`
+pub fn hash<T: Serialize + ?Sized>(value: &T) -> Result<Hash, String> {
+    encode(value).map(|bytes| crypto::hash_data(&bytes))
+}
+
+/// `serialize_with` for a `HashMap`, writing its entries ordered by the
+/// canonical bytes of their keys. The output is still an ordinary map, so
+/// data stored or sent before decodes the same.
+pub fn sorted_map<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Serialize,
+    V: Serialize,
+    S: Serializer,
+{
+    let mut entries = map.iter()
+        .map(|(key, value)| encode(key).map(|bytes| (bytes, key, value)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(serde::ser::Error::custom)?;
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    serializer.collect_map(entries.into_iter().map(|(_, key, value)| (key, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize)]
+    struct Balances {
+        #[serde(serialize_with = "sorted_map")]
+        balances: HashMap<Hash, u64>,
+    }
+
+    #[test]
+    fn test_map_encoding_ignores_insertion_order() {
+        let keys: Vec<Hash> = (0..32u8).map(|i| Hash::new([i.wrapping_mul(37); 32])).collect();
+        let forward = Balances { balances: keys.iter().map(|k| (*k, 1)).collect() };
+        let backward = Balances { balances: keys.iter().rev().map(|k| (*k, 1)).collect() };
+
+        assert_eq!(encode(&forward).unwrap(), encode(&backward).unwrap());
+        assert_eq!(hash(&forward).unwrap(), hash(&backward).unwrap());
+
+        // Still an ordinary map to decode
+        let decoded: Balances = bincode::deserialize(&encode(&forward).unwrap()).unwrap();
+        assert_eq!(decoded.balances, forward.balances);
+    }
+}
@@ -0,0 +1,122 @@
+//! Injectable time source.
+//!
+//! Time-dependent logic (escrow timeouts, listing expiry, signed action
+//! freshness) takes a [`Clock`] instead of reading the system time directly,
+//! so it can be driven by a [`ManualClock`] in tests. Timestamps supplied by
+//! peers are checked against a [`TimestampWindow`] that tolerates some skew
+//! between the two clocks.
+
+use crate::error::{L2Error, Result};
+use crate::types::Timestamp;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Default tolerance for peer clocks running ahead of ours (seconds)
+pub const DEFAULT_MAX_CLOCK_SKEW: u64 = 30;
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Timestamp;
+}
+
+/// Shared handle to a clock
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Wall clock time
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::now()
+    }
+}
+
+/// Clock that only moves when told to
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    secs: AtomicU64,
+}
+
+impl ManualClock {
+    pub fn new(start: Timestamp) -> Self {
+        Self { secs: AtomicU64::new(start.as_secs()) }
+    }
+
+    pub fn set(&self, now: Timestamp) {
+        self.secs.store(now.as_secs(), Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, secs: u64) {
+        self.secs.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::from_secs(self.secs.load(Ordering::SeqCst))
+    }
+}
+
+/// Acceptable range for a peer-supplied timestamp relative to our clock
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimestampWindow {
+    /// How far in the past a timestamp may be (seconds)
+    pub max_age: u64,
+
+    /// How far in the future a timestamp may be (seconds)
+    pub max_skew: u64,
+}
+
+impl TimestampWindow {
+    pub const fn new(max_age: u64, max_skew: u64) -> Self {
+        Self { max_age, max_skew }
+    }
+
+    /// Check that `timestamp` falls inside the window around `now`
+    pub fn check(&self, now: Timestamp, timestamp: Timestamp) -> Result<()> {
+        let now = now.as_secs();
+        let ts = timestamp.as_secs();
+
+        if ts > now.saturating_add(self.max_skew) {
+            return Err(L2Error::InvalidParameter(format!(
+                "Timestamp {} is {}s in the future (max skew {}s)", ts, ts - now, self.max_skew
+            )));
+        }
+        if now.saturating_sub(ts) > self.max_age {
+            return Err(L2Error::InvalidParameter(format!(
+                "Timestamp {} is {}s old (max age {}s)", ts, now - ts, self.max_age
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(Timestamp::from_secs(1_000));
+        assert_eq!(clock.now().as_secs(), 1_000);
+        clock.advance(60);
+        assert_eq!(clock.now().as_secs(), 1_060);
+        clock.set(Timestamp::from_secs(5));
+        assert_eq!(clock.now().as_secs(), 5);
+    }
+
+    #[test]
+    fn test_timestamp_window() {
+        let window = TimestampWindow::new(300, DEFAULT_MAX_CLOCK_SKEW);
+        let now = Timestamp::from_secs(10_000);
+
+        assert!(window.check(now, now).is_ok());
+        assert!(window.check(now, Timestamp::from_secs(10_000 - 300)).is_ok());
+        assert!(window.check(now, Timestamp::from_secs(10_000 - 301)).is_err());
+        assert!(window.check(now, Timestamp::from_secs(10_000 + 30)).is_ok());
+        assert!(window.check(now, Timestamp::from_secs(10_000 + 31)).is_err());
+    }
+}
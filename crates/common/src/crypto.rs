@@ -1,49 +1,563 @@
-use crate::types::{Hash, PublicKey, Signature};
+use crate::types::{Hash, PublicKey, Signature, SignatureScheme};
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT,
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::{IsIdentity, VartimeMultiscalarMul},
+};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
 use ed25519_dalek::{Signer, Verifier, SigningKey, VerifyingKey};
+use zeroize::{Zeroize, Zeroizing};
 
-/// Keypair for signing operations
+/// Secret key material for a supported signature scheme
+enum SecretKey {
+    Ed25519(SigningKey),
+    Schnorr(Scalar),
+}
+
+/// Minimum master seed length for key derivation
+pub const MIN_SEED_LENGTH: usize = 16;
+
+/// Derivation branch separating keys used for different purposes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyBranch {
+    /// Long-lived node identity keys
+    Node,
+    /// Per-channel signing keys
+    Channel,
+    /// Per-order keys
+    Order,
+}
+
+impl KeyBranch {
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyBranch::Node => "node",
+            KeyBranch::Channel => "channel",
+            KeyBranch::Order => "order",
+        }
+    }
+}
+
+/// Purpose a signature is produced for. The tag is bound into the signed
+/// bytes so a signature for one purpose never verifies for another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SigningContext {
+    /// Channel state updates
+    StateUpdate,
+    /// Marketplace listings
+    Listing,
+    /// Channel close agreements
+    Close,
+    /// Channel open agreements over the genesis state
+    Open,
+    /// A seller's commitment to their active listings
+    CatalogRoot,
+    /// A P2P message, signed by the node sending it
+    Message,
+    /// A seller's change to a published listing, with the time it was made
+    ListingUpdate,
+    /// A node's statement of the collateral it has locked on L1
+    ReserveProof,
+    /// A party's receipt for a settled order or channel
+    Receipt,
+    /// A node's L2 key vouching for its libp2p peer ID
+    PeerBinding,
+    /// A seller's request to be paid
+    Invoice,
+    /// A message signed over RPC with the unlocked wallet
+    WalletMessage,
+    /// An owner's signature authenticating one HTTP request to the RPC server
+    RpcRequest,
+    /// An entry in the node's audit log, hashed rather than signed
+    Audit,
+    /// The key a channel's checkpoints are paid to, derived rather than signed
+    CheckpointAnchor,
+}
+
+impl SigningContext {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            SigningContext::StateUpdate => "l2.state_update.v1",
+            SigningContext::Listing => "l2.listing.v1",
+            SigningContext::Close => "l2.close.v1",
+            SigningContext::Open => "l2.open.v1",
+            SigningContext::CatalogRoot => "l2.catalog_root.v1",
+            SigningContext::Message => "l2.message.v1",
+            SigningContext::ListingUpdate => "l2.listing_update.v1",
+            SigningContext::ReserveProof => "l2.reserve_proof.v1",
+            SigningContext::Receipt => "l2.receipt.v1",
+            SigningContext::PeerBinding => "l2.peer_binding.v1",
+            SigningContext::Invoice => "l2.invoice.v1",
+            SigningContext::WalletMessage => "l2.wallet_message.v1",
+            SigningContext::RpcRequest => "l2.rpc_request.v1",
+            SigningContext::Audit => "l2.audit.v1",
+            SigningContext::CheckpointAnchor => "l2.checkpoint_anchor.v1",
+        }
+    }
+
+    /// Bytes actually signed: length-prefixed tag followed by the message
+    pub fn message(&self, message: &[u8]) -> Vec<u8> {
+        let tag = self.tag().as_bytes();
+        let mut data = Vec::with_capacity(1 + tag.len() + message.len());
+        data.push(tag.len() as u8);
+        data.extend_from_slice(tag);
+        data.extend_from_slice(message);
+        data
+    }
+}
+
+/// Keypair for signing operations. Secret material is wiped on drop.
 pub struct KeyPair {
-    signing_key: SigningKey,
+    secret: SecretKey,
+}
+
+impl Drop for KeyPair {
+    fn drop(&mut self) {
+        // Ed25519 signing keys zeroize themselves on drop
+        if let SecretKey::Schnorr(secret) = &mut self.secret {
+            secret.zeroize();
+        }
+    }
 }
 
 impl KeyPair {
-    /// Generate a new random keypair
+    /// Generate a new random Ed25519 keypair
     pub fn generate() -> Self {
-        let signing_key = SigningKey::from_bytes(&rand::random::<[u8; 32]>());
-        Self { signing_key }
+        let key_bytes = Zeroizing::new(rand::random::<[u8; 32]>());
+        let signing_key = SigningKey::from_bytes(&key_bytes);
+        Self { secret: SecretKey::Ed25519(signing_key) }
+    }
+
+    /// Generate a new random Schnorr keypair over Ristretto255
+    pub fn generate_ristretto() -> Self {
+        let mut wide = Zeroizing::new([0u8; 64]);
+        wide[..32].copy_from_slice(&rand::random::<[u8; 32]>());
+        wide[32..].copy_from_slice(&rand::random::<[u8; 32]>());
+        Self { secret: SecretKey::Schnorr(Scalar::from_bytes_mod_order_wide(&wide)) }
     }
 
-    /// Create from private key bytes (32 bytes)
+    /// Create from Ed25519 private key bytes (32 bytes)
     pub fn from_private_key(bytes: &[u8]) -> Result<Self, String> {
         if bytes.len() != 32 {
             return Err(format!("Invalid key length: expected 32 bytes, got {}", bytes.len()));
         }
-        let mut key_bytes = [0u8; 32];
+        let mut key_bytes = Zeroizing::new([0u8; 32]);
         key_bytes.copy_from_slice(bytes);
         let signing_key = SigningKey::from_bytes(&key_bytes);
-        Ok(Self { signing_key })
+        Ok(Self { secret: SecretKey::Ed25519(signing_key) })
+    }
+
+    /// Deterministically derive an Ed25519 keypair from a master seed.
+    /// The same seed, branch and index always yield the same key.
+    pub fn derive(seed: &[u8], branch: KeyBranch, index: u64) -> Result<Self, String> {
+        let key_bytes = derive_key_material(seed, branch, index)?;
+        Self::from_private_key(&key_bytes[..])
+    }
+
+    /// Deterministically derive a Schnorr (Ristretto) keypair from a master seed
+    pub fn derive_ristretto(seed: &[u8], branch: KeyBranch, index: u64) -> Result<Self, String> {
+        let key_bytes = derive_key_material(seed, branch, index)?;
+        let mut wide = Zeroizing::new([0u8; 64]);
+        wide[..32].copy_from_slice(&key_bytes[..]);
+        wide[32..].copy_from_slice(&blake3::hash(&key_bytes[..]).as_bytes()[..]);
+        Ok(Self { secret: SecretKey::Schnorr(Scalar::from_bytes_mod_order_wide(&wide)) })
+    }
+
+    /// Create from a canonical Ristretto secret scalar (32 bytes), such as a Tari wallet key
+    pub fn from_ristretto_secret(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != 32 {
+            return Err(format!("Invalid key length: expected 32 bytes, got {}", bytes.len()));
+        }
+        let mut key_bytes = Zeroizing::new([0u8; 32]);
+        key_bytes.copy_from_slice(bytes);
+        let scalar = Option::<Scalar>::from(Scalar::from_canonical_bytes(*key_bytes))
+            .ok_or_else(|| "Secret key is not a canonical Ristretto scalar".to_string())?;
+        Ok(Self { secret: SecretKey::Schnorr(scalar) })
+    }
+
+    /// Signature scheme of this keypair
+    pub fn scheme(&self) -> SignatureScheme {
+        match self.secret {
+            SecretKey::Ed25519(_) => SignatureScheme::Ed25519,
+            SecretKey::Schnorr(_) => SignatureScheme::Schnorr,
+        }
+    }
+
+    /// Ristretto secret scalar, if this is a Schnorr keypair
+    pub(crate) fn ristretto_secret(&self) -> Option<&Scalar> {
+        match &self.secret {
+            SecretKey::Ed25519(_) => None,
+            SecretKey::Schnorr(secret) => Some(secret),
+        }
+    }
+
+    /// Secret scalar for either scheme (the clamped Ed25519 scalar for Ed25519 keys)
+    pub(crate) fn secret_scalar(&self) -> Zeroizing<Scalar> {
+        match &self.secret {
+            SecretKey::Ed25519(signing_key) => Zeroizing::new(signing_key.to_scalar()),
+            SecretKey::Schnorr(secret) => Zeroizing::new(*secret),
+        }
     }
 
     /// Get the public key
     pub fn public_key(&self) -> PublicKey {
-        let verifying_key = self.signing_key.verifying_key();
-        PublicKey::new(verifying_key.to_bytes())
+        match &self.secret {
+            SecretKey::Ed25519(signing_key) => {
+                let verifying_key = signing_key.verifying_key();
+                PublicKey::new(verifying_key.to_bytes())
+            }
+            SecretKey::Schnorr(secret) => {
+                let point = RISTRETTO_BASEPOINT_POINT * secret;
+                PublicKey::new_with_scheme(point.compress().to_bytes(), SignatureScheme::Schnorr)
+            }
+        }
+    }
+
+    /// Get private key bytes, wiped when the returned buffer is dropped
+    pub fn to_bytes(&self) -> Zeroizing<[u8; 32]> {
+        match &self.secret {
+            SecretKey::Ed25519(signing_key) => Zeroizing::new(signing_key.to_bytes()),
+            SecretKey::Schnorr(secret) => Zeroizing::new(secret.to_bytes()),
+        }
     }
 
-    /// Get private key bytes
-    pub fn to_bytes(&self) -> [u8; 32] {
-        self.signing_key.to_bytes()
+    /// Sign a message under a signing context
+    pub fn sign_with_context(&self, context: SigningContext, message: &[u8]) -> Signature {
+        self.sign(&context.message(message))
     }
 
     /// Sign a message
     pub fn sign(&self, message: &[u8]) -> Signature {
-        let sig = self.signing_key.sign(message);
-        Signature::new(sig.to_bytes())
+        match &self.secret {
+            SecretKey::Ed25519(signing_key) => {
+                let sig = signing_key.sign(message);
+                Signature::new(sig.to_bytes())
+            }
+            SecretKey::Schnorr(secret) => schnorr_sign(secret, &self.public_key(), message),
+        }
+    }
+}
+
+/// Header identifying an encrypted keypair blob
+const ENCRYPTED_KEY_MAGIC: &[u8; 4] = b"TL2K";
+const ENCRYPTED_KEY_VERSION: u8 = 1;
+const ENCRYPTED_KEY_SALT_LEN: usize = 16;
+const ENCRYPTED_KEY_NONCE_LEN: usize = 12;
+const ENCRYPTED_KEY_HEADER_LEN: usize = 6;
+
+impl KeyPair {
+    /// Encrypt the secret key with a passphrase.
+    ///
+    /// Layout: magic (4) | version (1) | scheme (1) | salt (16) | nonce (12) | ciphertext.
+    /// The key is derived with Argon2id and the secret sealed with ChaCha20-Poly1305,
+    /// authenticating the header as associated data.
+    pub fn to_encrypted_bytes(&self, passphrase: &str) -> Result<Vec<u8>, String> {
+        let salt = rand::random::<[u8; ENCRYPTED_KEY_SALT_LEN]>();
+        let nonce = rand::random::<[u8; ENCRYPTED_KEY_NONCE_LEN]>();
+
+        let mut out = Vec::with_capacity(ENCRYPTED_KEY_HEADER_LEN + ENCRYPTED_KEY_SALT_LEN + ENCRYPTED_KEY_NONCE_LEN + 48);
+        out.extend_from_slice(ENCRYPTED_KEY_MAGIC);
+        out.push(ENCRYPTED_KEY_VERSION);
+        out.push(match self.scheme() {
+            SignatureScheme::Ed25519 => 0,
+            SignatureScheme::Schnorr => 1,
+        });
+
+        let cipher = key_cipher(passphrase, &salt)?;
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: &self.to_bytes()[..], aad: &out })
+            .map_err(|_| "Failed to encrypt key".to_string())?;
+
+        out.extend_from_slice(&salt);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// Decrypt a keypair produced by [`KeyPair::to_encrypted_bytes`]
+    pub fn from_encrypted_bytes(bytes: &[u8], passphrase: &str) -> Result<Self, String> {
+        let body_start = ENCRYPTED_KEY_HEADER_LEN + ENCRYPTED_KEY_SALT_LEN + ENCRYPTED_KEY_NONCE_LEN;
+        if bytes.len() <= body_start || &bytes[..4] != ENCRYPTED_KEY_MAGIC {
+            return Err("Not an encrypted keypair".to_string());
+        }
+        if bytes[4] != ENCRYPTED_KEY_VERSION {
+            return Err(format!("Unsupported encrypted key version {}", bytes[4]));
+        }
+
+        let header = &bytes[..ENCRYPTED_KEY_HEADER_LEN];
+        let salt = &bytes[ENCRYPTED_KEY_HEADER_LEN..ENCRYPTED_KEY_HEADER_LEN + ENCRYPTED_KEY_SALT_LEN];
+        let nonce = &bytes[ENCRYPTED_KEY_HEADER_LEN + ENCRYPTED_KEY_SALT_LEN..body_start];
+
+        let cipher = key_cipher(passphrase, salt)?;
+        let secret = cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: &bytes[body_start..], aad: header })
+            .map(Zeroizing::new)
+            .map_err(|_| "Wrong passphrase or corrupted key".to_string())?;
+
+        match bytes[5] {
+            0 => Self::from_private_key(&secret[..]),
+            1 => Self::from_ristretto_secret(&secret[..]),
+            other => Err(format!("Unknown key scheme {}", other)),
+        }
+    }
+}
+
+/// Build the AEAD cipher for a passphrase and salt using Argon2id
+fn key_cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305, String> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key[..])
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key[..])))
+}
+
+/// Encrypt `plaintext` under a 32-byte key with ChaCha20-Poly1305,
+/// authenticating `aad`. Output is nonce (12) | ciphertext.
+pub fn seal(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = rand::random::<[u8; ENCRYPTED_KEY_NONCE_LEN]>();
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| "Failed to encrypt".to_string())?;
+
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypt the output of [`seal`]
+pub fn open(key: &[u8; 32], sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() <= ENCRYPTED_KEY_NONCE_LEN {
+        return Err("Sealed data too short".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(ENCRYPTED_KEY_NONCE_LEN);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| "Wrong key or corrupted data".to_string())
+}
+
+/// Encrypt `plaintext` under a passphrase, stretched with Argon2id.
+/// Output is salt (16) | nonce (12) | ciphertext.
+pub fn seal_with_passphrase(passphrase: &str, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+    let salt = rand::random::<[u8; ENCRYPTED_KEY_SALT_LEN]>();
+    let nonce = rand::random::<[u8; ENCRYPTED_KEY_NONCE_LEN]>();
+    let ciphertext = key_cipher(passphrase, &salt)?
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| "Failed to encrypt".to_string())?;
+
+    let mut out = salt.to_vec();
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypt the output of [`seal_with_passphrase`]
+pub fn open_with_passphrase(passphrase: &str, sealed: &[u8], aad: &[u8]) -> Result<Zeroizing<Vec<u8>>, String> {
+    let body_start = ENCRYPTED_KEY_SALT_LEN + ENCRYPTED_KEY_NONCE_LEN;
+    if sealed.len() <= body_start {
+        return Err("Sealed data too short".to_string());
+    }
+    let (salt, rest) = sealed.split_at(ENCRYPTED_KEY_SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(ENCRYPTED_KEY_NONCE_LEN);
+    key_cipher(passphrase, salt)?
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map(Zeroizing::new)
+        .map_err(|_| "Wrong passphrase or corrupted data".to_string())
+}
+
+/// Derive 32 bytes of key material for `branch`/`index` from a master seed
+fn derive_key_material(seed: &[u8], branch: KeyBranch, index: u64) -> Result<Zeroizing<[u8; 32]>, String> {
+    if seed.len() < MIN_SEED_LENGTH {
+        return Err(format!("Seed too short: expected at least {} bytes, got {}", MIN_SEED_LENGTH, seed.len()));
     }
+    let mut material = Zeroizing::new(Vec::with_capacity(seed.len() + 32));
+    material.extend_from_slice(seed);
+    material.extend_from_slice(branch.label().as_bytes());
+    material.extend_from_slice(&index.to_be_bytes());
+    Ok(Zeroizing::new(blake3::derive_key("tari-l2 2024 hd key derivation v1", &material)))
 }
 
-/// Verify a signature
+/// Sign with Schnorr over Ristretto255. The signature is `R || s` where
+/// `s = r + e·k` and the nonce `r` is derived deterministically from the
+/// secret and message.
+fn schnorr_sign(secret: &Scalar, public_key: &PublicKey, message: &[u8]) -> Signature {
+    let nonce = hash_to_scalar(&[b"tari-l2-schnorr-nonce", secret.as_bytes(), message]);
+    let r_point = (RISTRETTO_BASEPOINT_POINT * nonce).compress();
+    let challenge = schnorr_challenge(r_point.as_bytes(), public_key.as_bytes(), message);
+    let s = nonce + challenge * secret;
+
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(r_point.as_bytes());
+    bytes[32..].copy_from_slice(s.as_bytes());
+    Signature::new(bytes)
+}
+
+fn schnorr_verify(public_key: &PublicKey, message: &[u8], signature: &Signature) -> bool {
+    let public_point = match CompressedRistretto(*public_key.as_bytes()).decompress() {
+        Some(point) => point,
+        None => return false,
+    };
+
+    let sig = signature.as_bytes();
+    let mut r_bytes = [0u8; 32];
+    r_bytes.copy_from_slice(&sig[..32]);
+    let r_point = match CompressedRistretto(r_bytes).decompress() {
+        Some(point) => point,
+        None => return false,
+    };
+
+    let mut s_bytes = [0u8; 32];
+    s_bytes.copy_from_slice(&sig[32..]);
+    let s = match Option::<Scalar>::from(Scalar::from_canonical_bytes(s_bytes)) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let challenge = schnorr_challenge(&r_bytes, public_key.as_bytes(), message);
+    RISTRETTO_BASEPOINT_POINT * s == r_point + public_point * challenge
+}
+
+pub(crate) fn schnorr_challenge(r: &[u8; 32], public_key: &[u8; 32], message: &[u8]) -> Scalar {
+    hash_to_scalar(&[b"tari-l2-schnorr-challenge", r, public_key, message])
+}
+
+/// Hash data to a uniformly distributed scalar
+pub(crate) fn hash_to_scalar(data: &[&[u8]]) -> Scalar {
+    let mut hasher = blake3::Hasher::new();
+    for item in data {
+        hasher.update(&(item.len() as u64).to_le_bytes());
+        hasher.update(item);
+    }
+    let mut wide = [0u8; 64];
+    hasher.finalize_xof().fill(&mut wide);
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+/// Verify a signature using the scheme of the public key
 pub fn verify_signature(public_key: &PublicKey, message: &[u8], signature: &Signature) -> bool {
+    match public_key.scheme() {
+        SignatureScheme::Ed25519 => verify_ed25519(public_key, message, signature),
+        SignatureScheme::Schnorr => schnorr_verify(public_key, message, signature),
+    }
+}
+
+/// Verify a signature produced with [`KeyPair::sign_with_context`]
+pub fn verify_with_context(
+    public_key: &PublicKey,
+    context: SigningContext,
+    message: &[u8],
+    signature: &Signature,
+) -> bool {
+    verify_signature(public_key, &context.message(message), signature)
+}
+
+/// Verify many signatures at once, returning true only if every one is valid.
+///
+/// Ed25519 signatures go through ed25519-dalek's batch verifier and Schnorr
+/// signatures are checked with a single randomized multiscalar multiplication,
+/// which is considerably cheaper than verifying each signature in turn. A
+/// `false` result does not say which signature failed; fall back to
+/// [`verify_signature`] to find it.
+pub fn verify_signatures_batch(
+    public_keys: &[PublicKey],
+    messages: &[&[u8]],
+    signatures: &[Signature],
+) -> bool {
+    if public_keys.len() != messages.len() || public_keys.len() != signatures.len() {
+        return false;
+    }
+
+    let mut ed25519_keys = Vec::new();
+    let mut ed25519_messages = Vec::new();
+    let mut ed25519_signatures = Vec::new();
+    let mut schnorr = Vec::new();
+
+    for ((public_key, message), signature) in public_keys.iter().zip(messages).zip(signatures) {
+        match public_key.scheme() {
+            SignatureScheme::Ed25519 => {
+                let key = match VerifyingKey::from_bytes(public_key.as_bytes()) {
+                    Ok(key) => key,
+                    Err(_) => return false,
+                };
+                let sig = match ed25519_dalek::Signature::from_slice(signature.as_bytes()) {
+                    Ok(sig) => sig,
+                    Err(_) => return false,
+                };
+                ed25519_keys.push(key);
+                ed25519_messages.push(*message);
+                ed25519_signatures.push(sig);
+            }
+            SignatureScheme::Schnorr => schnorr.push((public_key, *message, signature)),
+        }
+    }
+
+    if !ed25519_keys.is_empty()
+        && ed25519_dalek::verify_batch(&ed25519_messages, &ed25519_signatures, &ed25519_keys).is_err()
+    {
+        return false;
+    }
+
+    schnorr_verify_batch(&schnorr)
+}
+
+/// Check sum(z_i * s_i) * G == sum(z_i * R_i) + sum(z_i * e_i * P_i) for random weights z_i
+fn schnorr_verify_batch(items: &[(&PublicKey, &[u8], &Signature)]) -> bool {
+    match items {
+        [] => return true,
+        [(public_key, message, signature)] => return schnorr_verify(public_key, message, signature),
+        _ => {}
+    }
+
+    let seed = rand::random::<[u8; 32]>();
+
+    let mut scalars = Vec::with_capacity(2 * items.len() + 1);
+    let mut points = Vec::with_capacity(2 * items.len() + 1);
+    let mut s_sum = Scalar::ZERO;
+
+    for (i, (public_key, message, signature)) in items.iter().enumerate() {
+        let public_point = match CompressedRistretto(*public_key.as_bytes()).decompress() {
+            Some(point) => point,
+            None => return false,
+        };
+
+        let sig = signature.as_bytes();
+        let mut r_bytes = [0u8; 32];
+        r_bytes.copy_from_slice(&sig[..32]);
+        let r_point = match CompressedRistretto(r_bytes).decompress() {
+            Some(point) => point,
+            None => return false,
+        };
+
+        let mut s_bytes = [0u8; 32];
+        s_bytes.copy_from_slice(&sig[32..]);
+        let s = match Option::<Scalar>::from(Scalar::from_canonical_bytes(s_bytes)) {
+            Some(s) => s,
+            None => return false,
+        };
+
+        let weight = hash_to_scalar(&[b"tari-l2-batch-weight", &seed, &(i as u64).to_le_bytes()]);
+        let challenge = schnorr_challenge(&r_bytes, public_key.as_bytes(), message);
+
+        s_sum += weight * s;
+        scalars.push(weight);
+        points.push(r_point);
+        scalars.push(weight * challenge);
+        points.push(public_point);
+    }
+
+    scalars.push(-s_sum);
+    points.push(RISTRETTO_BASEPOINT_POINT);
+
+    RistrettoPoint::vartime_multiscalar_mul(scalars, points).is_identity()
+}
+
+fn verify_ed25519(public_key: &PublicKey, message: &[u8], signature: &Signature) -> bool {
     let verifying_key = match VerifyingKey::from_bytes(public_key.as_bytes()) {
         Ok(key) => key,
         Err(_) => return false,
@@ -83,6 +597,34 @@ mod tests {
         assert_eq!(pk.as_bytes().len(), 32);
     }
 
+    #[test]
+    fn test_verify_signatures_batch() {
+        let keypairs = [
+            KeyPair::generate(),
+            KeyPair::generate_ristretto(),
+            KeyPair::generate(),
+            KeyPair::generate_ristretto(),
+            KeyPair::generate_ristretto(),
+        ];
+        let messages: Vec<Vec<u8>> = (0..keypairs.len()).map(|i| format!("message {}", i).into_bytes()).collect();
+        let message_refs: Vec<&[u8]> = messages.iter().map(|m| m.as_slice()).collect();
+        let public_keys: Vec<PublicKey> = keypairs.iter().map(|kp| kp.public_key()).collect();
+        let mut signatures: Vec<Signature> = keypairs.iter().zip(&messages).map(|(kp, m)| kp.sign(m)).collect();
+
+        assert!(verify_signatures_batch(&[], &[], &[]));
+        assert!(verify_signatures_batch(&public_keys, &message_refs, &signatures));
+        assert!(!verify_signatures_batch(&public_keys[1..], &message_refs, &signatures));
+
+        // A single bad Schnorr signature fails the whole batch
+        signatures.swap(3, 4);
+        assert!(!verify_signatures_batch(&public_keys, &message_refs, &signatures));
+
+        // As does a single bad Ed25519 signature
+        signatures.swap(3, 4);
+        signatures.swap(0, 2);
+        assert!(!verify_signatures_batch(&public_keys, &message_refs, &signatures));
+    }
+
     #[test]
     fn test_sign_and_verify() {
         let kp = KeyPair::generate();
@@ -94,6 +636,75 @@ mod tests {
         assert!(!verify_signature(&public_key, b"wrong message", &signature));
     }
 
+    #[test]
+    fn test_schnorr_sign_and_verify() {
+        let kp = KeyPair::generate_ristretto();
+        let public_key = kp.public_key();
+        assert_eq!(public_key.scheme(), SignatureScheme::Schnorr);
+
+        let signature = kp.sign(b"test message");
+        assert!(verify_signature(&public_key, b"test message", &signature));
+        assert!(!verify_signature(&public_key, b"wrong message", &signature));
+
+        // Round-trip through secret bytes
+        let restored = KeyPair::from_ristretto_secret(&kp.to_bytes()[..]).unwrap();
+        assert_eq!(restored.public_key(), public_key);
+
+        // Same bytes under the wrong scheme do not verify
+        let as_ed25519 = PublicKey::new(*public_key.as_bytes());
+        assert!(!verify_signature(&as_ed25519, b"test message", &signature));
+    }
+
+    #[test]
+    fn test_key_derivation() {
+        let seed = [7u8; 32];
+
+        let node = KeyPair::derive(&seed, KeyBranch::Node, 0).unwrap();
+        let node_again = KeyPair::derive(&seed, KeyBranch::Node, 0).unwrap();
+        assert_eq!(node.public_key(), node_again.public_key());
+
+        let channel = KeyPair::derive(&seed, KeyBranch::Channel, 0).unwrap();
+        let channel_next = KeyPair::derive(&seed, KeyBranch::Channel, 1).unwrap();
+        assert_ne!(node.public_key(), channel.public_key());
+        assert_ne!(channel.public_key(), channel_next.public_key());
+
+        let order = KeyPair::derive_ristretto(&seed, KeyBranch::Order, 3).unwrap();
+        assert_eq!(order.scheme(), SignatureScheme::Schnorr);
+        assert_eq!(
+            order.public_key(),
+            KeyPair::derive_ristretto(&seed, KeyBranch::Order, 3).unwrap().public_key()
+        );
+
+        assert!(KeyPair::derive(&[1u8; 8], KeyBranch::Node, 0).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_keypair_roundtrip() {
+        for kp in [KeyPair::generate(), KeyPair::generate_ristretto()] {
+            let encrypted = kp.to_encrypted_bytes("correct horse").unwrap();
+
+            let restored = KeyPair::from_encrypted_bytes(&encrypted, "correct horse").unwrap();
+            assert_eq!(restored.public_key(), kp.public_key());
+
+            assert!(KeyPair::from_encrypted_bytes(&encrypted, "wrong").is_err());
+
+            let mut tampered = encrypted.clone();
+            tampered[5] ^= 1;
+            assert!(KeyPair::from_encrypted_bytes(&tampered, "correct horse").is_err());
+        }
+    }
+
+    #[test]
+    fn test_signing_contexts() {
+        let kp = KeyPair::generate();
+        let signature = kp.sign_with_context(SigningContext::Listing, b"payload");
+
+        assert!(verify_with_context(&kp.public_key(), SigningContext::Listing, b"payload", &signature));
+        assert!(!verify_with_context(&kp.public_key(), SigningContext::StateUpdate, b"payload", &signature));
+        assert!(!verify_with_context(&kp.public_key(), SigningContext::Close, b"payload", &signature));
+        assert!(!verify_signature(&kp.public_key(), b"payload", &signature));
+    }
+
     #[test]
     fn test_hash_data() {
         let data = b"test data";
@@ -38,6 +38,9 @@ pub enum L2Error {
     #[error("Invalid parameter: {0}")]
     InvalidParameter(String),
 
+    #[error("Resource limit exceeded: {0}")]
+    ResourceLimitExceeded(String),
+
     #[error("Timeout")]
     Timeout,
 
@@ -45,4 +48,115 @@ pub enum L2Error {
     Unknown(String),
 }
 
+/// Broad class of an error, shared by RPC, P2P and client SDKs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The caller supplied bad input
+    InvalidInput,
+    /// A referenced object does not exist
+    NotFound,
+    /// The request conflicts with current state
+    Conflict,
+    /// Signature or authorization failure
+    Unauthorized,
+    /// A configured limit was hit
+    ResourceExhausted,
+    /// Network or L1 connectivity problem
+    Unavailable,
+    /// Internal failure (storage, serialization, unknown)
+    Internal,
+}
+
+impl ErrorCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCategory::InvalidInput => "invalid_input",
+            ErrorCategory::NotFound => "not_found",
+            ErrorCategory::Conflict => "conflict",
+            ErrorCategory::Unauthorized => "unauthorized",
+            ErrorCategory::ResourceExhausted => "resource_exhausted",
+            ErrorCategory::Unavailable => "unavailable",
+            ErrorCategory::Internal => "internal",
+        }
+    }
+}
+
+impl std::str::FromStr for ErrorCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "invalid_input" => Ok(ErrorCategory::InvalidInput),
+            "not_found" => Ok(ErrorCategory::NotFound),
+            "conflict" => Ok(ErrorCategory::Conflict),
+            "unauthorized" => Ok(ErrorCategory::Unauthorized),
+            "resource_exhausted" => Ok(ErrorCategory::ResourceExhausted),
+            "unavailable" => Ok(ErrorCategory::Unavailable),
+            "internal" => Ok(ErrorCategory::Internal),
+            other => Err(format!("Unknown error category: {}", other)),
+        }
+    }
+}
+
+impl L2Error {
+    /// Stable numeric code for this error, in the JSON-RPC server error range.
+    /// Codes are part of the public API: never renumber, only append.
+    pub fn code(&self) -> i32 {
+        match self {
+            L2Error::InvalidSignature => -32001,
+            L2Error::InvalidStateTransition => -32002,
+            L2Error::InsufficientBalance { .. } => -32003,
+            L2Error::ChannelNotFound(_) => -32004,
+            L2Error::ChannelAlreadyExists(_) => -32005,
+            L2Error::InvalidChannelState => -32006,
+            L2Error::ParticipantNotFound => -32007,
+            L2Error::SerializationError(_) => -32008,
+            L2Error::NetworkError(_) => -32009,
+            L2Error::DatabaseError(_) => -32010,
+            L2Error::TariConnectionError(_) => -32011,
+            L2Error::InvalidParameter(_) => -32012,
+            L2Error::ResourceLimitExceeded(_) => -32013,
+            L2Error::Timeout => -32014,
+            L2Error::Unknown(_) => -32099,
+        }
+    }
+
+    /// Category of this error
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            L2Error::InvalidParameter(_) => ErrorCategory::InvalidInput,
+            L2Error::ChannelNotFound(_) | L2Error::ParticipantNotFound => ErrorCategory::NotFound,
+            L2Error::ChannelAlreadyExists(_)
+            | L2Error::InvalidStateTransition
+            | L2Error::InvalidChannelState
+            | L2Error::InsufficientBalance { .. } => ErrorCategory::Conflict,
+            L2Error::InvalidSignature => ErrorCategory::Unauthorized,
+            L2Error::ResourceLimitExceeded(_) => ErrorCategory::ResourceExhausted,
+            L2Error::NetworkError(_) | L2Error::TariConnectionError(_) | L2Error::Timeout => {
+                ErrorCategory::Unavailable
+            }
+            L2Error::SerializationError(_) | L2Error::DatabaseError(_) | L2Error::Unknown(_) => {
+                ErrorCategory::Internal
+            }
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, L2Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes_are_stable() {
+        assert_eq!(L2Error::InvalidSignature.code(), -32001);
+        assert_eq!(L2Error::ChannelNotFound("x".into()).code(), -32004);
+        assert_eq!(L2Error::ResourceLimitExceeded("x".into()).code(), -32013);
+        assert_eq!(L2Error::Unknown("x".into()).code(), -32099);
+
+        assert_eq!(L2Error::ChannelNotFound("x".into()).category(), ErrorCategory::NotFound);
+        assert_eq!(L2Error::Timeout.category().as_str(), "unavailable");
+        assert_eq!("unavailable".parse::<ErrorCategory>(), Ok(ErrorCategory::Unavailable));
+    }
+}
@@ -1,6 +1,14 @@
 pub mod types;
 pub mod crypto;
+pub mod musig;
+pub mod merkle;
+pub mod canonical;
+pub mod address;
+pub mod clock;
+pub mod vrf;
 pub mod error;
+pub mod limits;
 
 pub use types::*;
 pub use error::L2Error;
+pub use limits::ResourceLimits;
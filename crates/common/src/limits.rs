@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// Resource caps that protect a node from being exhausted by peers or users
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    /// Maximum number of channels held by the node
+    pub max_channels: usize,
+
+    /// Maximum number of stored marketplace listings
+    pub max_listings: usize,
+
+    /// Maximum number of unapplied state updates per channel
+    pub max_pending_updates: usize,
+
+    /// Maximum size of a single P2P message in bytes
+    pub max_message_size: usize,
+
+    /// Maximum number of P2P messages accepted from one peer per second
+    pub max_messages_per_sec: u32,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_channels: 1000,
+            max_listings: 10_000,
+            max_pending_updates: 64,
+            max_message_size: 1024 * 1024,
+            max_messages_per_sec: 50,
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Binary Merkle tree over BLAKE3.
+//!
+//! Leaves are hashed as `H(0x00 || data)` and interior nodes as
+//! `H(0x01 || left || right)`, so a leaf can never be passed off as a node.
+//! An unpaired node at the end of a level is promoted unchanged to the next
+//! level. The root of an empty tree is all zeroes.
+
+use crate::types::Hash;
+use serde::{Deserialize, Serialize};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Hash a leaf's data
+pub fn leaf_hash(data: &[u8]) -> Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(data);
+    Hash::new(*hasher.finalize().as_bytes())
+}
+
+/// Hash two child nodes
+pub fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    Hash::new(*hasher.finalize().as_bytes())
+}
+
+/// Binary Merkle tree keeping every level so proofs can be produced
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    /// levels[0] holds the leaf hashes, the last level holds the root
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    /// Build a tree from raw leaf data
+    pub fn new<T: AsRef<[u8]>>(leaves: &[T]) -> Self {
+        Self::from_leaf_hashes(leaves.iter().map(|leaf| leaf_hash(leaf.as_ref())).collect())
+    }
+
+    /// Build a tree from already hashed leaves
+    pub fn from_leaf_hashes(leaves: Vec<Hash>) -> Self {
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    /// Root hash (all zeroes for an empty tree)
+    pub fn root(&self) -> Hash {
+        self.levels.last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or_else(|| Hash::new([0u8; 32]))
+    }
+
+    /// Number of leaves
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    /// Inclusion proof for the leaf at `index`
+    pub fn prove(&self, index: usize) -> Option<MerkleProof> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let mut siblings = Vec::new();
+        let mut idx = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = idx ^ 1;
+            if sibling < level.len() {
+                siblings.push(level[sibling]);
+            }
+            idx /= 2;
+        }
+
+        Some(MerkleProof {
+            leaf_index: index as u64,
+            leaf_count: self.len() as u64,
+            siblings,
+        })
+    }
+}
+
+/// Proof that a leaf is included under a Merkle root
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// Position of the leaf
+    pub leaf_index: u64,
+
+    /// Number of leaves in the tree
+    pub leaf_count: u64,
+
+    /// Sibling hashes from the leaf level upwards
+    pub siblings: Vec<Hash>,
+}
+
+impl MerkleProof {
+    /// Verify that `leaf_data` is included under `root`
+    pub fn verify(&self, root: &Hash, leaf_data: &[u8]) -> bool {
+        self.verify_hash(root, &leaf_hash(leaf_data))
+    }
+
+    /// Verify an already hashed leaf
+    pub fn verify_hash(&self, root: &Hash, leaf: &Hash) -> bool {
+        if self.leaf_index >= self.leaf_count {
+            return false;
+        }
+
+        let mut current = *leaf;
+        let mut idx = self.leaf_index;
+        let mut width = self.leaf_count;
+        let mut siblings = self.siblings.iter();
+
+        while width > 1 {
+            let sibling = idx ^ 1;
+            if sibling < width {
+                let sibling_hash = match siblings.next() {
+                    Some(hash) => hash,
+                    None => return false,
+                };
+                current = if idx % 2 == 0 {
+                    node_hash(&current, sibling_hash)
+                } else {
+                    node_hash(sibling_hash, &current)
+                };
+            }
+            idx /= 2;
+            width = width.div_ceil(2);
+        }
+
+        siblings.next().is_none() && &current == root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex_hash(s: &str) -> Hash {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_vectors() {
+        assert_eq!(MerkleTree::new::<&[u8]>(&[]).root(), Hash::new([0u8; 32]));
+        assert_eq!(
+            MerkleTree::new(&[b"a"]).root(),
+            hex_hash("1ff621ee3430890e869728995a6cee4f2b0b61271bfc19b0092b06d778750ae8")
+        );
+        assert_eq!(
+            MerkleTree::new(&[b"a", b"b"]).root(),
+            hex_hash("6564e87d8619ea09c801c567c641d47fe817ae3b2cf80685cde2eb6557247eca")
+        );
+        assert_eq!(
+            MerkleTree::new(&[b"a", b"b", b"c"]).root(),
+            hex_hash("6c62dd52a0971b7d00a7cead004e0c3f3c0766e3f5359a0f8297768d2b02d03c")
+        );
+        assert_eq!(
+            MerkleTree::new(&[b"a", b"b", b"c", b"d", b"e"]).root(),
+            hex_hash("75a40a0808bd168c7a54cfabaa7bbc3f37d558bb83d063ad4a110aa97c6280b9")
+        );
+    }
+
+    #[test]
+    fn test_proofs_for_every_leaf() {
+        for count in 1..=9usize {
+            let leaves: Vec<Vec<u8>> = (0..count).map(|i| vec![i as u8; 3]).collect();
+            let tree = MerkleTree::new(&leaves);
+            let root = tree.root();
+
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = tree.prove(i).unwrap();
+                assert!(proof.verify(&root, leaf), "leaf {} of {}", i, count);
+                assert!(!proof.verify(&root, b"not a leaf"));
+            }
+            assert!(tree.prove(count).is_none());
+        }
+    }
+
+    #[test]
+    fn test_proof_serialization() {
+        let tree = MerkleTree::new(&[b"a", b"b", b"c", b"d", b"e"]);
+        let proof = tree.prove(4).unwrap();
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let decoded: MerkleProof = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, proof);
+        assert!(decoded.verify(&tree.root(), b"e"));
+
+        let mut tampered = proof;
+        tampered.leaf_index = 3;
+        assert!(!tampered.verify(&tree.root(), b"e"));
+    }
+}
@@ -0,0 +1,317 @@
+//! MuSig2-style Schnorr signature aggregation over Ristretto255.
+//!
+//! A group of Schnorr signers produces one signature that verifies with
+//! [`crate::crypto::verify_signature`] against their aggregate public key.
+//! Signing takes two rounds: every signer publishes a [`PublicNonce`], then
+//! each produces a [`PartialSignature`] which the session combines.
+
+use crate::crypto::{hash_to_scalar, schnorr_challenge, verify_signature, KeyPair};
+use crate::error::Result;
+use crate::types::{PublicKey, Signature, SignatureScheme};
+use crate::L2Error;
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT,
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+};
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Aggregate public key for a set of Schnorr signers
+pub struct KeyAggregation {
+    signers: Vec<PublicKey>,
+    coefficients: Vec<Scalar>,
+    aggregate: RistrettoPoint,
+}
+
+impl KeyAggregation {
+    /// Aggregate the given keys. Order does not matter; keys must be distinct Schnorr keys.
+    pub fn new(signers: &[PublicKey]) -> Result<Self> {
+        if signers.is_empty() {
+            return Err(L2Error::InvalidParameter("No signers to aggregate".to_string()));
+        }
+
+        let mut signers = signers.to_vec();
+        signers.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        if signers.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err(L2Error::InvalidParameter("Duplicate signer".to_string()));
+        }
+
+        let key_list: Vec<u8> = signers.iter().flat_map(|pk| pk.as_bytes().to_vec()).collect();
+
+        let mut coefficients = Vec::with_capacity(signers.len());
+        let mut aggregate = RistrettoPoint::default();
+        for signer in &signers {
+            if signer.scheme() != SignatureScheme::Schnorr {
+                return Err(L2Error::InvalidParameter(format!("{} is not a Schnorr key", signer)));
+            }
+            let point = decompress(signer.as_bytes())
+                .ok_or_else(|| L2Error::InvalidParameter(format!("{} is not a valid Ristretto point", signer)))?;
+            let coefficient = hash_to_scalar(&[b"tari-l2-musig-coefficient", &key_list, signer.as_bytes()]);
+            aggregate += point * coefficient;
+            coefficients.push(coefficient);
+        }
+
+        Ok(Self { signers, coefficients, aggregate })
+    }
+
+    /// Aggregate public key the final signature verifies against
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::new_with_scheme(self.aggregate.compress().to_bytes(), SignatureScheme::Schnorr)
+    }
+
+    /// Signers in canonical (sorted) order
+    pub fn signers(&self) -> &[PublicKey] {
+        &self.signers
+    }
+
+    fn coefficient(&self, signer: &PublicKey) -> Option<Scalar> {
+        self.signers.iter()
+            .position(|pk| pk == signer)
+            .map(|idx| self.coefficients[idx])
+    }
+}
+
+/// A signer's public nonce pair, shared in the first signing round
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicNonce {
+    pub r1: [u8; 32],
+    pub r2: [u8; 32],
+}
+
+/// A signer's secret nonce pair. Consumed by signing so it cannot be reused.
+pub struct SecretNonce {
+    r1: Scalar,
+    r2: Scalar,
+}
+
+impl Drop for SecretNonce {
+    fn drop(&mut self) {
+        self.r1.zeroize();
+        self.r2.zeroize();
+    }
+}
+
+impl SecretNonce {
+    /// Generate a fresh random nonce pair
+    pub fn generate() -> Self {
+        Self {
+            r1: random_scalar(),
+            r2: random_scalar(),
+        }
+    }
+
+    /// Public half to send to the other signers
+    pub fn public(&self) -> PublicNonce {
+        PublicNonce {
+            r1: (RISTRETTO_BASEPOINT_POINT * self.r1).compress().to_bytes(),
+            r2: (RISTRETTO_BASEPOINT_POINT * self.r2).compress().to_bytes(),
+        }
+    }
+}
+
+/// One signer's contribution to an aggregate signature
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialSignature {
+    pub signer: PublicKey,
+    pub s: [u8; 32],
+}
+
+/// Signing session for one message, created once all public nonces are known
+pub struct SigningSession {
+    key_agg: KeyAggregation,
+    nonce_coefficient: Scalar,
+    nonce_point: CompressedRistretto,
+    challenge: Scalar,
+}
+
+impl SigningSession {
+    /// Start a session from every signer's public nonce
+    pub fn new(key_agg: KeyAggregation, nonces: &[PublicNonce], message: &[u8]) -> Result<Self> {
+        if nonces.len() != key_agg.signers.len() {
+            return Err(L2Error::InvalidParameter(format!(
+                "Expected {} nonces, got {}", key_agg.signers.len(), nonces.len()
+            )));
+        }
+
+        let mut r1 = RistrettoPoint::default();
+        let mut r2 = RistrettoPoint::default();
+        for nonce in nonces {
+            r1 += decompress(&nonce.r1).ok_or(L2Error::InvalidSignature)?;
+            r2 += decompress(&nonce.r2).ok_or(L2Error::InvalidSignature)?;
+        }
+
+        let aggregate_key = key_agg.public_key();
+        let nonce_coefficient = hash_to_scalar(&[
+            b"tari-l2-musig-nonce",
+            aggregate_key.as_bytes(),
+            r1.compress().as_bytes(),
+            r2.compress().as_bytes(),
+            message,
+        ]);
+        let nonce_point = (r1 + r2 * nonce_coefficient).compress();
+        let challenge = schnorr_challenge(nonce_point.as_bytes(), aggregate_key.as_bytes(), message);
+
+        Ok(Self { key_agg, nonce_coefficient, nonce_point, challenge })
+    }
+
+    /// Aggregate public key of the session
+    pub fn public_key(&self) -> PublicKey {
+        self.key_agg.public_key()
+    }
+
+    /// Produce this signer's partial signature
+    pub fn partial_sign(&self, keypair: &KeyPair, nonce: SecretNonce) -> Result<PartialSignature> {
+        let secret = keypair.ristretto_secret()
+            .ok_or_else(|| L2Error::InvalidParameter("Aggregation requires a Schnorr keypair".to_string()))?;
+        let signer = keypair.public_key();
+        let coefficient = self.key_agg.coefficient(&signer)
+            .ok_or(L2Error::ParticipantNotFound)?;
+
+        let s = nonce.r1 + nonce.r2 * self.nonce_coefficient + self.challenge * coefficient * secret;
+        Ok(PartialSignature { signer, s: s.to_bytes() })
+    }
+
+    /// Check a partial signature against the signer's public nonce
+    pub fn verify_partial(&self, nonce: &PublicNonce, partial: &PartialSignature) -> bool {
+        let coefficient = match self.key_agg.coefficient(&partial.signer) {
+            Some(c) => c,
+            None => return false,
+        };
+        let (r1, r2, public_point) = match (
+            decompress(&nonce.r1),
+            decompress(&nonce.r2),
+            decompress(partial.signer.as_bytes()),
+        ) {
+            (Some(r1), Some(r2), Some(p)) => (r1, r2, p),
+            _ => return false,
+        };
+        let s = match Option::<Scalar>::from(Scalar::from_canonical_bytes(partial.s)) {
+            Some(s) => s,
+            None => return false,
+        };
+
+        RISTRETTO_BASEPOINT_POINT * s
+            == r1 + r2 * self.nonce_coefficient + public_point * (self.challenge * coefficient)
+    }
+
+    /// Combine one partial signature from every signer into the final signature
+    pub fn aggregate(&self, partials: &[PartialSignature]) -> Result<Signature> {
+        for signer in &self.key_agg.signers {
+            if partials.iter().filter(|p| &p.signer == signer).count() != 1 {
+                return Err(L2Error::InvalidParameter(format!("Need exactly one partial signature from {}", signer)));
+            }
+        }
+        if partials.len() != self.key_agg.signers.len() {
+            return Err(L2Error::InvalidParameter("Partial signature from unknown signer".to_string()));
+        }
+
+        let mut s = Scalar::ZERO;
+        for partial in partials {
+            s += Option::<Scalar>::from(Scalar::from_canonical_bytes(partial.s))
+                .ok_or(L2Error::InvalidSignature)?;
+        }
+
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(self.nonce_point.as_bytes());
+        bytes[32..].copy_from_slice(s.as_bytes());
+        Ok(Signature::new(bytes))
+    }
+}
+
+/// Aggregate signature from a subset of a group's members (k-of-n)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregateSignature {
+    /// Members whose keys were aggregated
+    pub signers: Vec<PublicKey>,
+
+    /// Schnorr signature under the aggregate of `signers`
+    pub signature: Signature,
+}
+
+impl AggregateSignature {
+    pub fn new(signers: Vec<PublicKey>, signature: Signature) -> Self {
+        Self { signers, signature }
+    }
+
+    /// Verify that at least `threshold` distinct members of `group` signed `message`
+    pub fn verify(&self, message: &[u8], group: &[PublicKey], threshold: usize) -> bool {
+        if self.signers.len() < threshold || self.signers.iter().any(|s| !group.contains(s)) {
+            return false;
+        }
+
+        match KeyAggregation::new(&self.signers) {
+            Ok(key_agg) => verify_signature(&key_agg.public_key(), message, &self.signature),
+            Err(_) => false,
+        }
+    }
+}
+
+fn decompress(bytes: &[u8; 32]) -> Option<RistrettoPoint> {
+    CompressedRistretto(*bytes).decompress()
+}
+
+fn random_scalar() -> Scalar {
+    let mut wide = Zeroizing::new([0u8; 64]);
+    wide[..32].copy_from_slice(&rand::random::<[u8; 32]>());
+    wide[32..].copy_from_slice(&rand::random::<[u8; 32]>());
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign_with(keypairs: &[&KeyPair], message: &[u8]) -> (PublicKey, Signature) {
+        let keys: Vec<PublicKey> = keypairs.iter().map(|kp| kp.public_key()).collect();
+        let nonces: Vec<SecretNonce> = keypairs.iter().map(|_| SecretNonce::generate()).collect();
+        let public_nonces: Vec<PublicNonce> = nonces.iter().map(|n| n.public()).collect();
+
+        let session = SigningSession::new(KeyAggregation::new(&keys).unwrap(), &public_nonces, message).unwrap();
+
+        let mut partials = Vec::new();
+        for ((kp, nonce), public_nonce) in keypairs.iter().zip(nonces).zip(&public_nonces) {
+            let partial = session.partial_sign(kp, nonce).unwrap();
+            assert!(session.verify_partial(public_nonce, &partial));
+            partials.push(partial);
+        }
+
+        (session.public_key(), session.aggregate(&partials).unwrap())
+    }
+
+    #[test]
+    fn test_two_of_two_aggregate() {
+        let kp1 = KeyPair::generate_ristretto();
+        let kp2 = KeyPair::generate_ristretto();
+
+        let (aggregate_key, signature) = sign_with(&[&kp1, &kp2], b"channel state");
+        assert!(verify_signature(&aggregate_key, b"channel state", &signature));
+        assert!(!verify_signature(&aggregate_key, b"other state", &signature));
+
+        // Key aggregation is order independent
+        let reversed = KeyAggregation::new(&[kp2.public_key(), kp1.public_key()]).unwrap();
+        assert_eq!(reversed.public_key(), aggregate_key);
+    }
+
+    #[test]
+    fn test_threshold_aggregate() {
+        let group: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate_ristretto()).collect();
+        let group_keys: Vec<PublicKey> = group.iter().map(|kp| kp.public_key()).collect();
+
+        let (_, signature) = sign_with(&[&group[0], &group[2]], b"checkpoint");
+        let aggregate = AggregateSignature::new(
+            vec![group_keys[0], group_keys[2]],
+            signature,
+        );
+
+        assert!(aggregate.verify(b"checkpoint", &group_keys, 2));
+        assert!(!aggregate.verify(b"checkpoint", &group_keys, 3));
+        assert!(!aggregate.verify(b"checkpoint", &group_keys[..2], 2));
+    }
+
+    #[test]
+    fn test_rejects_ed25519_keys() {
+        let kp = KeyPair::generate();
+        assert!(KeyAggregation::new(&[kp.public_key()]).is_err());
+    }
+}
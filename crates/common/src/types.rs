@@ -1,12 +1,15 @@
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
+use std::str::FromStr;
+use subtle::ConstantTimeEq;
 
-/// Hash type for all hashing operations
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Hash type for all hashing operations.
+/// Serializes as a hex string in human-readable formats (JSON) and as raw bytes otherwise.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Hash([u8; 32]);
 
 impl Hash {
-    pub fn new(data: [u8; 32]) -> Self {
+    pub const fn new(data: [u8; 32]) -> Self {
         Self(data)
     }
 
@@ -51,13 +54,84 @@ impl fmt::Display for Hash {
     }
 }
 
-/// Public key type
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct PublicKey([u8; 32]);
+impl FromStr for Hash {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).map_err(|e| format!("Invalid hash hex: {}", e))?;
+        Self::from_slice(&bytes).map_err(|e| e.to_string())
+    }
+}
+
+impl Serialize for Hash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&hex::encode(self.0))
+        } else {
+            #[derive(Serialize)]
+            #[serde(rename = "Hash")]
+            struct Raw([u8; 32]);
+            Raw(self.0).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Hash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+        } else {
+            #[derive(Deserialize)]
+            #[serde(rename = "Hash")]
+            struct Raw([u8; 32]);
+            Raw::deserialize(deserializer).map(|raw| Self(raw.0))
+        }
+    }
+}
+
+/// Signature scheme a key belongs to
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug, Default)]
+pub enum SignatureScheme {
+    /// Ed25519 (default L2 node keys)
+    #[default]
+    Ed25519,
+    /// Schnorr over Ristretto255 (Tari L1 native keys)
+    Schnorr,
+}
+
+/// Prefix marking Schnorr keys in their string form
+const SCHNORR_KEY_PREFIX: &str = "schnorr:";
+
+/// Public key type.
+/// Serializes as a hex string in human-readable formats, with Schnorr keys
+/// prefixed by `schnorr:`, and as raw bytes otherwise.
+#[derive(Clone, Copy)]
+pub struct PublicKey([u8; 32], SignatureScheme);
+
+impl PartialEq for PublicKey {
+    /// Constant-time comparison of the key bytes
+    fn eq(&self, other: &Self) -> bool {
+        bool::from(self.0.ct_eq(&other.0)) && self.1 == other.1
+    }
+}
+
+impl Eq for PublicKey {}
+
+impl std::hash::Hash for PublicKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        self.1.hash(state);
+    }
+}
 
 impl PublicKey {
     pub fn new(data: [u8; 32]) -> Self {
-        Self(data)
+        Self(data, SignatureScheme::Ed25519)
+    }
+
+    /// Create a public key for a specific signature scheme
+    pub fn new_with_scheme(data: [u8; 32], scheme: SignatureScheme) -> Self {
+        Self(data, scheme)
     }
 
     pub fn from_slice(slice: &[u8]) -> Result<Self, &'static str> {
@@ -66,20 +140,21 @@ impl PublicKey {
         }
         let mut arr = [0u8; 32];
         arr.copy_from_slice(slice);
-        Ok(Self(arr))
+        Ok(Self::new(arr))
     }
 
     pub fn as_bytes(&self) -> &[u8; 32] {
         &self.0
     }
 
-    /// Verify a signature over a message
-    /// Note: This is a placeholder implementation
-    /// In production, use proper Ed25519 verification
-    pub fn verify(&self, _message: &[u8], _signature: &[u8]) -> bool {
-        // TODO: Implement proper Ed25519 signature verification
-        // For now, just return true to allow compilation
-        true
+    /// Signature scheme this key verifies with
+    pub fn scheme(&self) -> SignatureScheme {
+        self.1
+    }
+
+    /// Verify a signature over a message using this key's scheme
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> bool {
+        crate::crypto::verify_signature(self, message, signature)
     }
 
     /// Convert public key to Tari address format (base58 with network byte)
@@ -110,19 +185,83 @@ impl PublicKey {
 
 impl fmt::Debug for PublicKey {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "PublicKey({})", hex::encode(&self.0[..8]))
+        match self.1 {
+            SignatureScheme::Ed25519 => write!(f, "PublicKey({})", hex::encode(&self.0[..8])),
+            SignatureScheme::Schnorr => write!(f, "PublicKey(schnorr:{})", hex::encode(&self.0[..8])),
+        }
     }
 }
 
 impl fmt::Display for PublicKey {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", hex::encode(&self.0))
+        match self.1 {
+            SignatureScheme::Ed25519 => write!(f, "{}", hex::encode(self.0)),
+            SignatureScheme::Schnorr => write!(f, "{}{}", SCHNORR_KEY_PREFIX, hex::encode(self.0)),
+        }
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = String;
+
+    /// Parse a hex Ed25519 key, a `schnorr:`-prefixed hex Schnorr key, or a
+    /// checksummed address (see [`crate::address`])
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if crate::address::is_address(s) {
+            return crate::address::decode_address(s)
+                .map(|(_, key)| key)
+                .map_err(|e| e.to_string());
+        }
+
+        let (hex_str, scheme) = match s.strip_prefix(SCHNORR_KEY_PREFIX) {
+            Some(rest) => (rest, SignatureScheme::Schnorr),
+            None => (s, SignatureScheme::Ed25519),
+        };
+        let bytes = hex::decode(hex_str).map_err(|e| format!("Invalid public key hex: {}", e))?;
+        let key = Self::from_slice(&bytes).map_err(|e| e.to_string())?;
+        Ok(Self(key.0, scheme))
+    }
+}
+
+impl Serialize for PublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            #[derive(Serialize)]
+            #[serde(rename = "PublicKey")]
+            struct Raw([u8; 32], SignatureScheme);
+            Raw(self.0, self.1).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+        } else {
+            #[derive(Deserialize)]
+            #[serde(rename = "PublicKey")]
+            struct Raw([u8; 32], SignatureScheme);
+            Raw::deserialize(deserializer).map(|raw| Self(raw.0, raw.1))
+        }
+    }
+}
+
+/// Signature type.
+/// Serializes as a hex string in human-readable formats and as raw bytes otherwise.
+#[derive(Clone)]
+pub struct Signature([u8; 64]);
+
+impl PartialEq for Signature {
+    /// Constant-time comparison of the signature bytes
+    fn eq(&self, other: &Self) -> bool {
+        self.0.ct_eq(&other.0).into()
     }
 }
 
-/// Signature type
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct Signature(#[serde(with = "serde_arrays")] [u8; 64]);
+impl Eq for Signature {}
 
 impl Signature {
     pub fn new(data: [u8; 64]) -> Self {
@@ -149,21 +288,75 @@ impl fmt::Debug for Signature {
     }
 }
 
-/// Amount type for representing token amounts
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl FromStr for Signature {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).map_err(|e| format!("Invalid signature hex: {}", e))?;
+        Self::from_slice(&bytes).map_err(|e| e.to_string())
+    }
+}
+
+impl Serialize for Signature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&hex::encode(self.0))
+        } else {
+            #[derive(Serialize)]
+            #[serde(rename = "Signature")]
+            struct Raw(#[serde(with = "serde_arrays")] [u8; 64]);
+            Raw(self.0).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Signature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+        } else {
+            #[derive(Deserialize)]
+            #[serde(rename = "Signature")]
+            struct Raw(#[serde(with = "serde_arrays")] [u8; 64]);
+            Raw::deserialize(deserializer).map(|raw| Self(raw.0))
+        }
+    }
+}
+
+/// Number of micro-Tari (µT) in one Tari (T)
+pub const MICRO_TARI_PER_TARI: u64 = 1_000_000;
+
+/// Amount type for representing token amounts (in µT)
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
 pub struct Amount(u64);
 
 impl Amount {
     pub const ZERO: Amount = Amount(0);
+    pub const MAX: Amount = Amount(u64::MAX);
 
     pub fn new(value: u64) -> Self {
         Self(value)
     }
 
+    /// Amount from whole Tari
+    pub fn from_tari(tari: u64) -> Option<Self> {
+        tari.checked_mul(MICRO_TARI_PER_TARI).map(Amount)
+    }
+
     pub fn value(&self) -> u64 {
         self.0
     }
 
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
     pub fn checked_add(&self, other: Amount) -> Option<Amount> {
         self.0.checked_add(other.0).map(Amount)
     }
@@ -171,11 +364,115 @@ impl Amount {
     pub fn checked_sub(&self, other: Amount) -> Option<Amount> {
         self.0.checked_sub(other.0).map(Amount)
     }
+
+    pub fn saturating_add(&self, other: Amount) -> Amount {
+        Amount(self.0.saturating_add(other.0))
+    }
+
+    pub fn saturating_sub(&self, other: Amount) -> Amount {
+        Amount(self.0.saturating_sub(other.0))
+    }
+
+    /// Portion of this amount in basis points (1/100 of a percent), rounded down
+    pub fn mul_bps(&self, bps: u32) -> Option<Amount> {
+        let value = self.0 as u128 * bps as u128 / 10_000;
+        u64::try_from(value).ok().map(Amount)
+    }
+
+    /// Fee of `bps` basis points on this amount, rounded up so fees never round to zero
+    pub fn fee_bps(&self, bps: u32) -> Option<Amount> {
+        let value = (self.0 as u128 * bps as u128).div_ceil(10_000);
+        u64::try_from(value).ok().map(Amount)
+    }
+
+    /// Amount with a fee of `bps` basis points deducted
+    pub fn minus_fee_bps(&self, bps: u32) -> Option<Amount> {
+        self.fee_bps(bps).and_then(|fee| self.checked_sub(fee))
+    }
+
+    /// Format in Tari with six decimals, e.g. `1.500000 T`
+    pub fn to_tari_string(&self) -> String {
+        format!("{}.{:06} T", self.0 / MICRO_TARI_PER_TARI, self.0 % MICRO_TARI_PER_TARI)
+    }
+
+    /// Format in micro-Tari, e.g. `1500000 µT`
+    pub fn to_micro_tari_string(&self) -> String {
+        format!("{} µT", self.0)
+    }
+}
+
+impl std::ops::Add for Amount {
+    type Output = Option<Amount>;
+
+    fn add(self, other: Amount) -> Option<Amount> {
+        self.checked_add(other)
+    }
+}
+
+impl std::ops::Sub for Amount {
+    type Output = Option<Amount>;
+
+    fn sub(self, other: Amount) -> Option<Amount> {
+        self.checked_sub(other)
+    }
+}
+
+/// Checked sum: `None` on overflow
+impl std::iter::Sum<Amount> for Option<Amount> {
+    fn sum<I: Iterator<Item = Amount>>(mut iter: I) -> Self {
+        iter.try_fold(Amount::ZERO, |acc, amount| acc.checked_add(amount))
+    }
+}
+
+impl<'a> std::iter::Sum<&'a Amount> for Option<Amount> {
+    fn sum<I: Iterator<Item = &'a Amount>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
 }
 
 impl fmt::Display for Amount {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.to_tari_string())
+    }
+}
+
+impl FromStr for Amount {
+    type Err = String;
+
+    /// Parse `1.5 T`, `1.5T`, `1500000 µT`, `1500000uT` or a bare µT integer
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(micro) = s.strip_suffix("µT").or_else(|| s.strip_suffix("uT")) {
+            return micro.trim().parse::<u64>()
+                .map(Amount)
+                .map_err(|e| format!("Invalid µT amount '{}': {}", s, e));
+        }
+
+        let tari = match s.strip_suffix('T') {
+            Some(tari) => tari.trim(),
+            None => {
+                return s.parse::<u64>()
+                    .map(Amount)
+                    .map_err(|e| format!("Invalid amount '{}': {}", s, e));
+            }
+        };
+
+        let (whole, fraction) = tari.split_once('.').unwrap_or((tari, ""));
+        if fraction.len() > 6 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("Invalid Tari amount '{}': at most 6 decimals allowed", s));
+        }
+        let whole: u64 = whole.parse()
+            .map_err(|e| format!("Invalid Tari amount '{}': {}", s, e))?;
+        let fraction: u64 = if fraction.is_empty() {
+            0
+        } else {
+            format!("{:0<6}", fraction).parse().map_err(|e| format!("Invalid Tari amount '{}': {}", s, e))?
+        };
+
+        whole.checked_mul(MICRO_TARI_PER_TARI)
+            .and_then(|micro| micro.checked_add(fraction))
+            .map(Amount)
+            .ok_or_else(|| format!("Amount '{}' overflows", s))
     }
 }
 
@@ -196,3 +493,83 @@ impl Timestamp {
         self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_serde_roundtrip() {
+        let hash = Hash::new([0xab; 32]);
+        let json = serde_json::to_string(&hash).unwrap();
+        assert_eq!(json, format!("\"{}\"", "ab".repeat(32)));
+        assert_eq!(serde_json::from_str::<Hash>(&json).unwrap(), hash);
+        assert_eq!(hash.to_string().parse::<Hash>().unwrap(), hash);
+
+        let ed_key = PublicKey::new([1; 32]);
+        let schnorr_key = PublicKey::new_with_scheme([2; 32], SignatureScheme::Schnorr);
+        for key in [ed_key, schnorr_key] {
+            let json = serde_json::to_string(&key).unwrap();
+            assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), key);
+            assert_eq!(key.to_string().parse::<PublicKey>().unwrap(), key);
+        }
+        assert!(schnorr_key.to_string().starts_with("schnorr:"));
+
+        // Keys work as JSON map keys
+        let mut balances = std::collections::HashMap::new();
+        balances.insert(ed_key, Amount::new(5));
+        let json = serde_json::to_string(&balances).unwrap();
+        assert_eq!(serde_json::from_str::<std::collections::HashMap<PublicKey, Amount>>(&json).unwrap(), balances);
+
+        let signature = Signature::new([3; 64]);
+        let json = serde_json::to_string(&signature).unwrap();
+        assert_eq!(serde_json::from_str::<Signature>(&json).unwrap(), signature);
+
+        assert!("zz".parse::<Hash>().is_err());
+        assert!("abcd".parse::<PublicKey>().is_err());
+    }
+
+    #[test]
+    fn test_amount_arithmetic() {
+        let a = Amount::new(700);
+        let b = Amount::new(500);
+
+        assert_eq!(a + b, Some(Amount::new(1200)));
+        assert_eq!(b - a, None);
+        assert_eq!(Amount::MAX + a, None);
+        assert_eq!(b.saturating_sub(a), Amount::ZERO);
+        assert_eq!(Amount::MAX.saturating_add(a), Amount::MAX);
+
+        let total: Option<Amount> = [a, b].iter().sum();
+        assert_eq!(total, Some(Amount::new(1200)));
+        let overflow: Option<Amount> = vec![Amount::MAX, a].into_iter().sum();
+        assert_eq!(overflow, None);
+    }
+
+    #[test]
+    fn test_amount_fees() {
+        let amount = Amount::new(1_001);
+        assert_eq!(amount.mul_bps(250), Some(Amount::new(25)));
+        assert_eq!(amount.fee_bps(250), Some(Amount::new(26)));
+        assert_eq!(amount.minus_fee_bps(250), Some(Amount::new(975)));
+        assert_eq!(Amount::new(1).fee_bps(1), Some(Amount::new(1)));
+    }
+
+    #[test]
+    fn test_amount_formatting_and_parsing() {
+        let amount = Amount::new(1_500_000);
+        assert_eq!(amount.to_string(), "1.500000 T");
+        assert_eq!(amount.to_micro_tari_string(), "1500000 µT");
+
+        assert_eq!("1.5 T".parse::<Amount>(), Ok(amount));
+        assert_eq!("1.5T".parse::<Amount>(), Ok(amount));
+        assert_eq!("1500000 µT".parse::<Amount>(), Ok(amount));
+        assert_eq!("1500000uT".parse::<Amount>(), Ok(amount));
+        assert_eq!("1500000".parse::<Amount>(), Ok(amount));
+        assert_eq!(amount.to_string().parse::<Amount>(), Ok(amount));
+        assert_eq!(Amount::from_tari(2), Some(Amount::new(2_000_000)));
+
+        assert!("1.0000001 T".parse::<Amount>().is_err());
+        assert!("abc".parse::<Amount>().is_err());
+    }
+}
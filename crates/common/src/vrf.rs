@@ -0,0 +1,281 @@
+//! Verifiable random function keyed by a node's signing key.
+//!
+//! A node evaluates the VRF on some public input (an escrow ID, an auction
+//! round) to get an output nobody could predict, and publishes a proof that
+//! lets counterparties check the output came from its key. Each key has
+//! exactly one valid output per input, so a node cannot grind for a
+//! favourable result.
+//!
+//! The construction follows ECVRF (RFC 9381): `Gamma = x·H(input)` plus a
+//! Chaum-Pedersen proof that `Gamma` and the public key share the discrete
+//! log `x`. Ed25519 keys use the Edwards curve with try-and-increment
+//! hash-to-curve; Schnorr keys use Ristretto255.
+
+use crate::crypto::{hash_to_scalar, KeyPair};
+use crate::types::{Hash, PublicKey, SignatureScheme};
+use curve25519_dalek::{
+    constants::{ED25519_BASEPOINT_POINT, RISTRETTO_BASEPOINT_POINT},
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::IsIdentity,
+};
+use serde::{Deserialize, Serialize};
+use std::ops::{Mul, Sub};
+
+const SUITE_EDWARDS: u8 = 0x01;
+const SUITE_RISTRETTO: u8 = 0x02;
+
+/// Pseudo-random VRF output
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct VrfOutput(pub [u8; 32]);
+
+impl VrfOutput {
+    pub fn as_hash(&self) -> Hash {
+        Hash::new(self.0)
+    }
+
+    /// Map the output to an index in `0..n`. Returns `None` if `n` is zero.
+    pub fn select_index(&self, n: usize) -> Option<usize> {
+        if n == 0 {
+            return None;
+        }
+        // 128 bits of output keep the modulo bias negligible for any realistic n
+        let mut wide = [0u8; 16];
+        wide.copy_from_slice(&self.0[..16]);
+        Some((u128::from_le_bytes(wide) % n as u128) as usize)
+    }
+}
+
+/// Proof that a VRF output was produced by a given key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VrfProof {
+    gamma: [u8; 32],
+    challenge: [u8; 32],
+    response: [u8; 32],
+}
+
+impl VrfProof {
+    /// Check the proof against the prover's public key and return the output
+    pub fn verify(&self, public_key: &PublicKey, input: &[u8]) -> Option<VrfOutput> {
+        match public_key.scheme() {
+            SignatureScheme::Ed25519 => verify_in::<EdwardsPoint>(self, public_key, input),
+            SignatureScheme::Schnorr => verify_in::<RistrettoPoint>(self, public_key, input),
+        }
+    }
+}
+
+/// Evaluate the VRF on `input`, returning the output and its proof
+pub fn prove(keypair: &KeyPair, input: &[u8]) -> (VrfOutput, VrfProof) {
+    let public_key = keypair.public_key();
+    match public_key.scheme() {
+        SignatureScheme::Ed25519 => prove_in::<EdwardsPoint>(keypair, &public_key, input),
+        SignatureScheme::Schnorr => prove_in::<RistrettoPoint>(keypair, &public_key, input),
+    }
+}
+
+/// Order `items` by the VRF output each one gets under `keypair`, e.g. to
+/// break ties between equal auction bids. Counterparties can reproduce the
+/// order from the published proofs.
+pub fn order_by_vrf<T, F>(keypair: &KeyPair, items: Vec<T>, input_for: F) -> Vec<(T, VrfOutput, VrfProof)>
+where
+    F: Fn(&T) -> Vec<u8>,
+{
+    let mut ordered: Vec<_> = items.into_iter()
+        .map(|item| {
+            let (output, proof) = prove(keypair, &input_for(&item));
+            (item, output, proof)
+        })
+        .collect();
+    ordered.sort_by(|a, b| a.1.cmp(&b.1));
+    ordered
+}
+
+/// Group operations the VRF needs from each curve
+trait VrfGroup: Copy + Mul<Scalar, Output = Self> + Sub<Output = Self> + IsIdentity {
+    const SUITE: u8;
+    fn generator() -> Self;
+    fn decompress(bytes: &[u8; 32]) -> Option<Self>;
+    fn compress(&self) -> [u8; 32];
+    fn hash_to_point(public_key: &[u8; 32], input: &[u8]) -> Self;
+    /// Bytes hashed into the output (cofactor cleared where needed)
+    fn output_bytes(&self) -> [u8; 32];
+}
+
+impl VrfGroup for EdwardsPoint {
+    const SUITE: u8 = SUITE_EDWARDS;
+
+    fn generator() -> Self {
+        ED25519_BASEPOINT_POINT
+    }
+
+    fn decompress(bytes: &[u8; 32]) -> Option<Self> {
+        CompressedEdwardsY(*bytes).decompress().filter(|point| !point.is_small_order())
+    }
+
+    fn compress(&self) -> [u8; 32] {
+        EdwardsPoint::compress(self).to_bytes()
+    }
+
+    fn hash_to_point(public_key: &[u8; 32], input: &[u8]) -> Self {
+        // Try-and-increment: about half of all candidates decode to a point
+        for counter in 0u8..=255 {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(b"tari-l2-vrf-h2c");
+            hasher.update(&[Self::SUITE, counter]);
+            hasher.update(public_key);
+            hasher.update(input);
+            let candidate = CompressedEdwardsY(*hasher.finalize().as_bytes());
+            if let Some(point) = candidate.decompress() {
+                let point = point.mul_by_cofactor();
+                if !point.is_identity() {
+                    return point;
+                }
+            }
+        }
+        unreachable!("256 hash-to-curve attempts all failed")
+    }
+
+    fn output_bytes(&self) -> [u8; 32] {
+        EdwardsPoint::compress(&self.mul_by_cofactor()).to_bytes()
+    }
+}
+
+impl VrfGroup for RistrettoPoint {
+    const SUITE: u8 = SUITE_RISTRETTO;
+
+    fn generator() -> Self {
+        RISTRETTO_BASEPOINT_POINT
+    }
+
+    fn decompress(bytes: &[u8; 32]) -> Option<Self> {
+        CompressedRistretto(*bytes).decompress()
+    }
+
+    fn compress(&self) -> [u8; 32] {
+        RistrettoPoint::compress(self).to_bytes()
+    }
+
+    fn hash_to_point(public_key: &[u8; 32], input: &[u8]) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"tari-l2-vrf-h2c");
+        hasher.update(&[Self::SUITE]);
+        hasher.update(public_key);
+        hasher.update(input);
+        let mut wide = [0u8; 64];
+        hasher.finalize_xof().fill(&mut wide);
+        RistrettoPoint::from_uniform_bytes(&wide)
+    }
+
+    fn output_bytes(&self) -> [u8; 32] {
+        RistrettoPoint::compress(self).to_bytes()
+    }
+}
+
+fn challenge<G: VrfGroup>(public_key: &[u8; 32], h: &G, gamma: &G, u: &G, v: &G) -> Scalar {
+    hash_to_scalar(&[
+        b"tari-l2-vrf-challenge",
+        &[G::SUITE],
+        public_key,
+        &h.compress(),
+        &gamma.compress(),
+        &u.compress(),
+        &v.compress(),
+    ])
+}
+
+fn output<G: VrfGroup>(gamma: &G) -> VrfOutput {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"tari-l2-vrf-output");
+    hasher.update(&[G::SUITE]);
+    hasher.update(&gamma.output_bytes());
+    VrfOutput(*hasher.finalize().as_bytes())
+}
+
+fn prove_in<G: VrfGroup>(keypair: &KeyPair, public_key: &PublicKey, input: &[u8]) -> (VrfOutput, VrfProof) {
+    let secret = keypair.secret_scalar();
+    let h = G::hash_to_point(public_key.as_bytes(), input);
+    let gamma = h * *secret;
+
+    // Deterministic nonce, as for Schnorr signatures
+    let nonce = hash_to_scalar(&[b"tari-l2-vrf-nonce", secret.as_bytes(), &h.compress()]);
+    let u = G::generator() * nonce;
+    let v = h * nonce;
+
+    let c = challenge(public_key.as_bytes(), &h, &gamma, &u, &v);
+    let s = nonce + c * *secret;
+
+    let proof = VrfProof {
+        gamma: gamma.compress(),
+        challenge: c.to_bytes(),
+        response: s.to_bytes(),
+    };
+    (output(&gamma), proof)
+}
+
+fn verify_in<G: VrfGroup>(proof: &VrfProof, public_key: &PublicKey, input: &[u8]) -> Option<VrfOutput> {
+    let y = G::decompress(public_key.as_bytes())?;
+    let gamma = G::decompress(&proof.gamma)?;
+    let c = Option::<Scalar>::from(Scalar::from_canonical_bytes(proof.challenge))?;
+    let s = Option::<Scalar>::from(Scalar::from_canonical_bytes(proof.response))?;
+
+    let h = G::hash_to_point(public_key.as_bytes(), input);
+    let u = G::generator() * s - y * c;
+    let v = h * s - gamma * c;
+
+    if challenge(public_key.as_bytes(), &h, &gamma, &u, &v) != c {
+        return None;
+    }
+    Some(output(&gamma))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prove_and_verify() {
+        for kp in [KeyPair::generate(), KeyPair::generate_ristretto()] {
+            let (out, proof) = prove(&kp, b"escrow-1");
+            assert_eq!(proof.verify(&kp.public_key(), b"escrow-1"), Some(out));
+
+            // Unique per input and deterministic
+            assert_eq!(prove(&kp, b"escrow-1").0, out);
+            assert_ne!(prove(&kp, b"escrow-2").0, out);
+
+            // Wrong input or key fails
+            assert_eq!(proof.verify(&kp.public_key(), b"escrow-2"), None);
+            let other = KeyPair::generate();
+            assert_eq!(proof.verify(&other.public_key(), b"escrow-1"), None);
+        }
+    }
+
+    #[test]
+    fn test_tampered_proof_rejected() {
+        let kp = KeyPair::generate();
+        let (_, proof) = prove(&kp, b"round");
+
+        let mut tampered = proof;
+        tampered.response[0] ^= 1;
+        assert_eq!(tampered.verify(&kp.public_key(), b"round"), None);
+
+        let (_, other_proof) = prove(&kp, b"other round");
+        let mut swapped = proof;
+        swapped.gamma = other_proof.gamma;
+        assert_eq!(swapped.verify(&kp.public_key(), b"round"), None);
+    }
+
+    #[test]
+    fn test_select_and_order() {
+        let kp = KeyPair::generate();
+        let (out, _) = prove(&kp, b"pick");
+        assert_eq!(out.select_index(0), None);
+        assert!(out.select_index(7).unwrap() < 7);
+
+        let ordered = order_by_vrf(&kp, vec![1u32, 2, 3, 4], |n| n.to_le_bytes().to_vec());
+        assert!(ordered.windows(2).all(|w| w[0].1 <= w[1].1));
+        for (item, output, proof) in &ordered {
+            assert_eq!(proof.verify(&kp.public_key(), &item.to_le_bytes()), Some(*output));
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Public checkpoint anchors.
+//!
+//! A channel's checkpoints are paid one-sided to an address derived from the
+//! channel ID alone, and so is the address's view key. Anyone who knows the
+//! channel can rebuild both, scan the chain for outputs to the address and
+//! read their payment IDs, so a counterparty confirms a checkpoint without
+//! trusting the node that submitted it. The key guards nothing but the
+//! anchors' dust.
+
+use anyhow::{anyhow, Result};
+use tari_common_types::tari_address::{TariAddress, TariAddressFeatures};
+use tari_crypto::compressed_key::CompressedKey;
+use tari_crypto::keys::{PublicKey as _, SecretKey as _};
+use tari_crypto::ristretto::{RistrettoPublicKey, RistrettoSecretKey};
+use tari_l2_common::crypto::SigningContext;
+use crate::collateral::tari_network;
+use crate::config::TariNetwork;
+
+/// Where a channel's checkpoints are anchored
+#[derive(Debug, Clone)]
+pub struct CheckpointAnchor {
+    pub channel_id: String,
+}
+
+impl CheckpointAnchor {
+    pub fn new(channel_id: String) -> Self {
+        Self { channel_id }
+    }
+
+    /// Key that decrypts the anchoring outputs, derived from the channel ID
+    pub fn view_key(&self) -> Result<RistrettoSecretKey> {
+        let mut wide = [0u8; 64];
+        blake3::Hasher::new()
+            .update(&SigningContext::CheckpointAnchor.message(self.channel_id.as_bytes()))
+            .finalize_xof()
+            .fill(&mut wide);
+        RistrettoSecretKey::from_uniform_bytes(&wide)
+            .map_err(|e| anyhow!("Failed to derive anchor key: {}", e))
+    }
+
+    /// One-sided address the channel's checkpoints are paid to
+    pub fn address(&self, network: &TariNetwork) -> Result<String> {
+        let public_key = RistrettoPublicKey::from_secret_key(&self.view_key()?);
+        let address = TariAddress::new_single_address(
+            CompressedKey::new_from_pk(public_key),
+            tari_network(network),
+            TariAddressFeatures::create_one_sided_only(),
+            None,
+        ).map_err(|e| anyhow!("Failed to build anchor address: {}", e))?;
+        Ok(address.to_base58())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchor_is_derived_from_channel_id() {
+        let anchor = CheckpointAnchor::new("ab".repeat(32));
+        let rebuilt = CheckpointAnchor::new("ab".repeat(32));
+        assert_eq!(anchor.view_key().unwrap(), rebuilt.view_key().unwrap());
+        assert_eq!(anchor.address(&TariNetwork::Esmeralda).unwrap(), rebuilt.address(&TariNetwork::Esmeralda).unwrap());
+
+        let other = CheckpointAnchor::new("cd".repeat(32));
+        assert_ne!(anchor.address(&TariNetwork::Esmeralda).unwrap(), other.address(&TariNetwork::Esmeralda).unwrap());
+    }
+}
@@ -0,0 +1,40 @@
+//! Chain state seen by L1 clients.
+//!
+//! Collateral locks, checkpoints, disputes and escrow deposits are outputs
+//! on the chain, not
+//! something one client owns. `OfflineChain` holds them for offline mode:
+//! every client attached to the same chain sees what any of them publishes,
+//! as nodes sharing one L1 would. A node that scans for disputes therefore
+//! finds a counterparty's force close even though its own client never
+//! submitted it.
+
+use std::collections::{HashMap, HashSet};
+use tokio::sync::Mutex;
+use crate::{Dispute, LockedCollateral};
+
+/// Height the simulated chain starts at
+const GENESIS_HEIGHT: u64 = 1000;
+
+/// Outputs published to the chain, by channel
+pub struct OfflineChain {
+    pub(crate) locked_collateral: Mutex<HashMap<String, LockedCollateral>>,
+    /// Payment IDs of the checkpoint outputs paid to each anchor address
+    pub(crate) anchors: Mutex<HashMap<String, Vec<Vec<u8>>>>,
+    pub(crate) disputes: Mutex<HashMap<String, Dispute>>,
+    /// Escrow deposits already paid out, by deposit transaction
+    pub(crate) spent_deposits: Mutex<HashSet<String>>,
+    /// Advances by one block per height query
+    pub(crate) height: Mutex<u64>,
+}
+
+impl Default for OfflineChain {
+    fn default() -> Self {
+        Self {
+            locked_collateral: Mutex::new(HashMap::new()),
+            anchors: Mutex::new(HashMap::new()),
+            disputes: Mutex::new(HashMap::new()),
+            spent_deposits: Mutex::new(HashSet::new()),
+            height: Mutex::new(GENESIS_HEIGHT),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! n-of-n collateral outputs.
+//!
+//! Channel collateral is paid one-sided to an address whose spend key is
+//! the MuSig aggregate of the participants' Schnorr keys. A one-sided
+//! output's script only accepts a signature under that key, so it can only
+//! be spent with every participant signing. The channel ID and challenge
+//! period are carried in the output's payment ID, so a participant scanning
+//! the chain can tell which channel an output secures.
+
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+use tari_common::configuration::Network;
+use tari_common_types::tari_address::{TariAddress, TariAddressFeatures};
+use tari_crypto::compressed_key::CompressedKey;
+use tari_crypto::ristretto::RistrettoPublicKey;
+use tari_crypto::tari_utilities::ByteArray;
+use tari_l2_common::PublicKey;
+use tari_l2_common::musig::KeyAggregation;
+use crate::config::TariNetwork;
+
+const MEMO_PREFIX: &str = "tari-l2-collateral:v1";
+
+/// The lock a channel's collateral is sent to
+#[derive(Debug, Clone)]
+pub struct CollateralLock {
+    pub channel_id: String,
+    pub participants: Vec<PublicKey>,
+    pub challenge_period: u64,
+}
+
+impl CollateralLock {
+    /// Parse the participants' keys. Every key must be a Schnorr key, since
+    /// only Ristretto keys can be aggregated into an L1 spend key.
+    pub fn new(channel_id: String, participants: &[String], challenge_period: u64) -> Result<Self> {
+        let participants = participants.iter()
+            .map(|p| PublicKey::from_str(p).map_err(|e| anyhow!("Invalid participant key {}: {}", p, e)))
+            .collect::<Result<Vec<_>>>()?;
+        let lock = Self { channel_id, participants, challenge_period };
+        lock.aggregate_key()?;
+        Ok(lock)
+    }
+
+    /// Key every participant must sign under to spend the output
+    pub fn aggregate_key(&self) -> Result<PublicKey> {
+        KeyAggregation::new(&self.participants)
+            .map(|aggregation| aggregation.public_key())
+            .map_err(|e| anyhow!("Cannot build an n-of-n lock: {}", e))
+    }
+
+    /// Payment ID embedded in the output
+    pub fn memo(&self) -> Vec<u8> {
+        format!("{}:{}:{}:{}", MEMO_PREFIX, self.channel_id, self.challenge_period, self.participants.len()).into_bytes()
+    }
+
+    /// One-sided address paying to the aggregate key
+    pub fn address(&self, network: &TariNetwork) -> Result<String> {
+        let aggregate = self.aggregate_key()?;
+        let spend_key = RistrettoPublicKey::from_canonical_bytes(aggregate.as_bytes())
+            .map_err(|e| anyhow!("Aggregate key is not a Ristretto point: {}", e))?;
+
+        let address = TariAddress::new_single_address(
+            CompressedKey::new_from_pk(spend_key),
+            tari_network(network),
+            TariAddressFeatures::create_one_sided_only(),
+            None,
+        ).map_err(|e| anyhow!("Failed to build collateral address: {}", e))?;
+        Ok(address.to_base58())
+    }
+}
+
+pub(crate) fn tari_network(network: &TariNetwork) -> Network {
+    match network {
+        TariNetwork::Mainnet => Network::MainNet,
+        TariNetwork::Esmeralda => Network::Esmeralda,
+        TariNetwork::Nextnet => Network::NextNet,
+        TariNetwork::Localnet => Network::LocalNet,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_common::crypto::KeyPair;
+
+    #[test]
+    fn test_collateral_lock_needs_every_participant() {
+        let keys: Vec<String> = (0..3).map(|_| KeyPair::generate_ristretto().public_key().to_string()).collect();
+        let lock = CollateralLock::new("ab".repeat(32), &keys, 3600).unwrap();
+
+        // Participant order does not change the lock
+        let reversed: Vec<String> = keys.iter().rev().cloned().collect();
+        let same = CollateralLock::new("ab".repeat(32), &reversed, 3600).unwrap();
+        assert_eq!(lock.aggregate_key().unwrap(), same.aggregate_key().unwrap());
+        assert_eq!(lock.address(&TariNetwork::Esmeralda).unwrap(), same.address(&TariNetwork::Esmeralda).unwrap());
+
+        // Leaving a participant out gives a different key
+        let partial = CollateralLock::new("ab".repeat(32), &keys[..2], 3600).unwrap();
+        assert_ne!(lock.aggregate_key().unwrap(), partial.aggregate_key().unwrap());
+
+        assert_eq!(lock.memo(), format!("tari-l2-collateral:v1:{}:3600:3", "ab".repeat(32)).into_bytes());
+
+        // Ed25519 keys cannot be aggregated into a spend key
+        let ed25519 = vec![keys[0].clone(), KeyPair::generate().public_key().to_string()];
+        assert!(CollateralLock::new("ab".repeat(32), &ed25519, 3600).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! Channel disputes settled against the collateral lock.
+//!
+//! A participant disputes a close by publishing the state root it claims
+//! and a digest of its `DisputeEvidence` in the payment ID of an output
+//! paid to the channel's collateral address. That opens a challenge window
+//! as long as the channel's challenge period. Until it ends, anyone holding
+//! evidence of a later state can publish it as a counter-proof. Once the
+//! window closes, the collateral is paid out according to the
+//! highest-nonce state whose evidence verified.
+
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+use tari_l2_state_channel::{DisputeEvidence, SettledState};
+use crate::LockedCollateral;
+
+const MEMO_PREFIX: &str = "tari-l2-dispute:v1";
+
+/// One state published for a dispute
+#[derive(Debug, Clone)]
+pub struct DisputeSubmission {
+    pub nonce: u64,
+    pub state_root: String,
+    /// Digest of the evidence proving the state
+    pub evidence_digest: String,
+    pub submitted_at: u64,
+    pub tx_id: String,
+}
+
+impl DisputeSubmission {
+    /// Payment ID of the output publishing the submission
+    pub fn memo(&self, channel_id: &str) -> Vec<u8> {
+        format!("{}:{}:{}:{}:{}", MEMO_PREFIX, channel_id, self.nonce, self.state_root, self.evidence_digest).into_bytes()
+    }
+}
+
+/// How a dispute's collateral was paid out
+#[derive(Debug, Clone)]
+pub struct DisputeResolution {
+    pub nonce: u64,
+    pub state_root: String,
+    /// µT paid to each participant, keyed by public key
+    pub payouts: HashMap<String, u64>,
+    pub resolved_at: u64,
+    pub tx_id: String,
+}
+
+/// A dispute over a channel's final state
+#[derive(Debug, Clone)]
+pub struct Dispute {
+    pub channel_id: String,
+    pub opened_at: u64,
+    /// Counter-proofs are accepted until this time; resolution after it
+    pub deadline: u64,
+    /// Highest-nonce state proven so far
+    pub best: SettledState,
+    /// Every accepted submission, oldest first
+    pub submissions: Vec<DisputeSubmission>,
+    pub resolution: Option<DisputeResolution>,
+}
+
+impl Dispute {
+    /// Open a dispute with its first submission; the window runs for the
+    /// lock's challenge period
+    pub fn open(lock: &LockedCollateral, best: SettledState, submission: DisputeSubmission) -> Self {
+        Self {
+            channel_id: lock.channel_id.clone(),
+            opened_at: submission.submitted_at,
+            deadline: submission.submitted_at.saturating_add(lock.challenge_period),
+            best,
+            submissions: vec![submission],
+            resolution: None,
+        }
+    }
+
+    /// Verify `evidence` and check it is for the locked channel and does not
+    /// pay out more than is locked
+    pub fn check_evidence(lock: &LockedCollateral, evidence: &DisputeEvidence) -> Result<SettledState> {
+        if evidence.channel_id.to_string() != lock.channel_id {
+            return Err(anyhow!("Evidence is for channel {}, not {}", evidence.channel_id, lock.channel_id));
+        }
+        let participants: HashSet<String> = evidence.participants.iter().map(|p| p.to_string()).collect();
+        let locked: HashSet<String> = lock.participants.iter().cloned().collect();
+        if participants != locked {
+            return Err(anyhow!("Evidence participants do not match the collateral lock"));
+        }
+        if evidence.challenge_period != lock.challenge_period {
+            return Err(anyhow!("Evidence challenge period does not match the collateral lock"));
+        }
+
+        let settled = evidence.verify().map_err(|e| anyhow!("Invalid dispute evidence: {}", e))?;
+        match settled.total() {
+            Some(total) if total.value() <= lock.amount => Ok(settled),
+            _ => Err(anyhow!("Evidence pays out more than the {} µT locked", lock.amount)),
+        }
+    }
+
+    /// Whether a counter-proof of `settled` submitted at `now` would be accepted
+    pub fn check_counter(&self, settled: &SettledState, now: u64) -> Result<()> {
+        if self.resolution.is_some() {
+            return Err(anyhow!("Dispute for channel {} is already resolved", self.channel_id));
+        }
+        if now >= self.deadline {
+            return Err(anyhow!("Challenge window for channel {} closed at {}", self.channel_id, self.deadline));
+        }
+        if settled.nonce <= self.best.nonce {
+            return Err(anyhow!(
+                "Counter-proof at nonce {} does not supersede nonce {}", settled.nonce, self.best.nonce
+            ));
+        }
+        Ok(())
+    }
+
+    /// Record an accepted counter-proof
+    pub fn counter(&mut self, settled: SettledState, submission: DisputeSubmission) -> Result<()> {
+        self.check_counter(&settled, submission.submitted_at)?;
+        self.best = settled;
+        self.submissions.push(submission);
+        Ok(())
+    }
+
+    /// µT owed to each participant under the best state
+    pub fn payout_split(&self) -> HashMap<String, u64> {
+        self.best.payouts.iter()
+            .map(|(participant, amount)| (participant.to_string(), amount.value()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_common::{Amount, Hash};
+    use tari_l2_common::crypto::KeyPair;
+
+    fn settled(nonce: u64, first: u64) -> SettledState {
+        let keys = [KeyPair::generate().public_key(), KeyPair::generate().public_key()];
+        SettledState {
+            channel_id: Hash::random(),
+            nonce,
+            state_root: Hash::random(),
+            payouts: HashMap::from([(keys[0], Amount::new(first)), (keys[1], Amount::new(1_000 - first))]),
+        }
+    }
+
+    fn submission(state: &SettledState, submitted_at: u64) -> DisputeSubmission {
+        DisputeSubmission {
+            nonce: state.nonce,
+            state_root: state.state_root.to_string(),
+            evidence_digest: Hash::random().to_string(),
+            submitted_at,
+            tx_id: format!("tx_{}", state.nonce),
+        }
+    }
+
+    #[test]
+    fn test_dispute_settles_on_highest_nonce_within_window() {
+        let lock = LockedCollateral {
+            channel_id: "ab".repeat(32),
+            amount: 1_000,
+            participants: Vec::new(),
+            challenge_period: 3600,
+            block_height: 0,
+            tx_id: "lock".to_string(),
+        };
+        let stale = settled(3, 900);
+        let mut dispute = Dispute::open(&lock, stale.clone(), submission(&stale, 1_000));
+        assert_eq!(dispute.deadline, 4_600);
+
+        // Same or older states cannot replace the one on record
+        assert!(dispute.counter(stale.clone(), submission(&stale, 1_100)).is_err());
+
+        let latest = settled(5, 400);
+        dispute.counter(latest.clone(), submission(&latest, 2_000)).unwrap();
+        assert_eq!(dispute.best.nonce, 5);
+        assert_eq!(dispute.submissions.len(), 2);
+        assert!(dispute.payout_split().values().any(|v| *v == 400));
+
+        // Nothing is accepted once the window closes
+        let late = settled(9, 0);
+        assert!(dispute.counter(late.clone(), submission(&late, 4_600)).is_err());
+        assert_eq!(dispute.best.nonce, 5);
+
+        let memo = String::from_utf8(dispute.submissions[1].memo(&lock.channel_id)).unwrap();
+        assert!(memo.starts_with(&format!("tari-l2-dispute:v1:{}:5:", lock.channel_id)));
+    }
+}
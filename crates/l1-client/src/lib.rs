@@ -2,11 +2,38 @@ use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use tokio::sync::Mutex;
-use tracing::{error, info, warn};
+use tracing::{info, instrument, warn};
 
+pub mod anchor;
+pub mod chain;
+pub mod collateral;
 pub mod config;
+pub mod dispute;
+pub use anchor::CheckpointAnchor;
+pub use chain::OfflineChain;
+pub use collateral::CollateralLock;
 pub use config::{L1Config, TariNetwork};
+pub use dispute::{Dispute, DisputeResolution, DisputeSubmission};
+
+use tari_l2_state_channel::DisputeEvidence;
+
+/// How often to poll the wallet while waiting for a transaction to confirm
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long to wait for a transaction to confirm before giving up
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// µT paid to the channel's anchor address by the output anchoring a
+/// checkpoint
+const CHECKPOINT_ANCHOR_AMOUNT: u64 = 1_000;
+
+const CHECKPOINT_MEMO_PREFIX: &str = "tari-l2-checkpoint:v1";
+
+/// Blocks back from the tip scanned for checkpoint anchors
+const ANCHOR_SCAN_DEPTH: u64 = 1000;
 
 /// Represents a locked collateral entry on L1
 #[derive(Debug, Clone)]
@@ -14,6 +41,8 @@ pub struct LockedCollateral {
     pub channel_id: String,
     pub amount: u64,
     pub participants: Vec<String>,
+    /// Seconds a close can be challenged before the collateral is released
+    pub challenge_period: u64,
     pub block_height: u64,
     pub tx_id: String,
 }
@@ -28,28 +57,38 @@ pub struct Checkpoint {
     pub tx_id: String,
 }
 
+impl Checkpoint {
+    /// Payment ID of the anchoring output, committing to the channel, its
+    /// state root and the signatures over it
+    pub fn memo(&self) -> Vec<u8> {
+        let signatures = blake3::hash(self.signatures.join(",").as_bytes());
+        let mut memo = Self::memo_prefix(&self.channel_id, &self.state_root);
+        memo.extend_from_slice(hex::encode(signatures.as_bytes()).as_bytes());
+        memo
+    }
+
+    /// Start of the payment ID of every checkpoint of `state_root`, whatever
+    /// signatures it carries
+    pub fn memo_prefix(channel_id: &str, state_root: &str) -> Vec<u8> {
+        format!("{}:{}:{}:", CHECKPOINT_MEMO_PREFIX, channel_id, state_root).into_bytes()
+    }
+}
+
 /// Main client for interacting with Tari L1 blockchain
 pub struct TariL1Client {
     config: L1Config,
     connected: Arc<Mutex<bool>>,
-    // Local tracking of locked collateral (in-memory for now)
-    locked_collateral: Arc<Mutex<HashMap<String, LockedCollateral>>>,
-    // Local tracking of checkpoints
-    checkpoints: Arc<Mutex<HashMap<String, Vec<Checkpoint>>>>,
-    // Mock chain height for offline mode
-    mock_chain_height: Arc<Mutex<u64>>,
+    // Collateral locks, checkpoints and disputes on the chain, shared with
+    // every other client attached to it
+    chain: Arc<OfflineChain>,
+    // Injected outage: every chain operation fails while set
+    outage: Arc<AtomicBool>,
 }
 
 impl TariL1Client {
     /// Create a new Tari L1 client
     pub async fn new(config: L1Config) -> Result<Self> {
-        let client = Self {
-            config,
-            connected: Arc::new(Mutex::new(false)),
-            locked_collateral: Arc::new(Mutex::new(HashMap::new())),
-            checkpoints: Arc::new(Mutex::new(HashMap::new())),
-            mock_chain_height: Arc::new(Mutex::new(1000)),
-        };
+        let client = Self::offline(config);
 
         // Attempt to connect
         client.connect().await;
@@ -57,12 +96,28 @@ impl TariL1Client {
         Ok(client)
     }
 
+    /// Create a client that never dials the base node and always uses
+    /// offline mode (mock transactions and chain height), on a chain of its
+    /// own
+    pub fn offline(config: L1Config) -> Self {
+        Self::offline_on(config, Arc::new(OfflineChain::default()))
+    }
+
+    /// Create an offline client attached to `chain`. Clients on the same
+    /// chain see each other's locks, checkpoints and disputes.
+    pub fn offline_on(config: L1Config, chain: Arc<OfflineChain>) -> Self {
+        Self {
+            config,
+            connected: Arc::new(Mutex::new(false)),
+            chain,
+            outage: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
     /// Attempt to connect to the Tari base node
     async fn connect(&self) {
         info!("🔗 Attempting to connect to Tari L1 node at {}", self.config.base_node_grpc);
 
-        // TODO: Implement actual gRPC connection to Tari base node
-        // For now, we'll attempt a basic connection check
         match self.try_connect().await {
             Ok(_) => {
                 *self.connected.lock().await = true;
@@ -96,6 +151,39 @@ impl TariL1Client {
         Ok(())
     }
 
+    /// Re-check the connection to the base node, returning the new status
+    pub async fn refresh_connection(&self) -> bool {
+        self.connect().await;
+        self.is_connected().await
+    }
+
+    /// Simulate the L1 being unreachable. While set, chain operations fail
+    /// instead of falling back to offline mode; used for fault injection.
+    pub fn set_outage(&self, down: bool) {
+        if down {
+            warn!("⚠️  L1 outage injected");
+        }
+        self.outage.store(down, Ordering::SeqCst);
+    }
+
+    fn ensure_available(&self) -> Result<()> {
+        if self.outage.load(Ordering::SeqCst) {
+            return Err(anyhow!("L1 unavailable (injected outage)"));
+        }
+        Ok(())
+    }
+
+    /// Disputes are only simulated: on a connected L1 nothing scans the
+    /// chain for other nodes' disputes, and the payout needs every
+    /// participant's MuSig signature, so each dispute call fails there
+    /// rather than acting on state only this process can see.
+    async fn ensure_simulated_disputes(&self) -> Result<()> {
+        if self.is_connected().await {
+            return Err(anyhow!("Disputes are not supported on a connected L1 yet"));
+        }
+        Ok(())
+    }
+
     /// Check if the client is connected to L1
     pub async fn is_connected(&self) -> bool {
         *self.connected.lock().await
@@ -103,10 +191,12 @@ impl TariL1Client {
 
     /// Get the current blockchain height
     pub async fn get_chain_height(&self) -> Result<u64> {
+        self.ensure_available()?;
+
         if !self.is_connected().await {
-            let height = *self.mock_chain_height.lock().await;
-            *self.mock_chain_height.lock().await += 1; // Simulate block progression
-            return Ok(height);
+            let mut height = self.chain.height.lock().await;
+            *height += 1; // Simulate block progression
+            return Ok(*height - 1);
         }
 
         // Query actual chain height from L1
@@ -122,15 +212,19 @@ impl TariL1Client {
     }
 
     /// Lock collateral on L1 for a payment channel
+    #[instrument(skip_all, fields(channel_id = %channel_id, amount = amount))]
     pub async fn lock_collateral(
         &self,
         channel_id: String,
         amount: u64,
         participants: Vec<String>,
+        challenge_period: u64,
     ) -> Result<String> {
         info!("🔒 Locking {} units of collateral for channel {} with {} participants",
               amount, channel_id, participants.len());
 
+        self.ensure_available()?;
+
         let block_height = self.get_chain_height().await?;
 
         if !self.is_connected().await {
@@ -141,43 +235,51 @@ impl TariL1Client {
                 channel_id: channel_id.clone(),
                 amount,
                 participants,
+                challenge_period,
                 block_height,
                 tx_id: tx_id.clone(),
             };
 
-            self.locked_collateral.lock().await.insert(channel_id, collateral);
+            self.chain.locked_collateral.lock().await.insert(channel_id, collateral);
 
             info!("✅ Mock collateral locked with tx_id: {}", tx_id);
             return Ok(tx_id);
         }
 
-        // TODO: Implement actual L1 transaction to lock collateral
-        // This should:
-        // 1. Create a transaction that locks funds in a multi-sig output
-        // 2. Include channel_id and participants in the transaction metadata
-        // 3. Submit the transaction to the Tari network
-        // 4. Wait for confirmation
-        // Example:
-        // let tx = create_collateral_lock_tx(amount, participants)?;
-        // let tx_id = submit_transaction(&mut wallet_client, tx).await?;
-
-        let tx_id = format!("mock_tx_{}", hex::encode(&blake3::hash(channel_id.as_bytes()).as_bytes()[..8]));
+        // Pay the collateral to an n-of-n output and wait until it is mined
+        let lock = CollateralLock::new(channel_id.clone(), &participants, challenge_period)?;
+        let address = lock.address(&self.config.network)?;
+        // A stealth output's script key is offset from the address key, so
+        // the lock must be a plain one-sided payment to the aggregate key
+        let transaction_id = self.wallet_transfer(address, amount, lock.memo(), false).await?;
+        self.wait_for_confirmation(transaction_id).await?;
+        let tx_id = transaction_id.to_string();
 
         let collateral = LockedCollateral {
             channel_id: channel_id.clone(),
             amount,
             participants,
+            challenge_period,
             block_height,
             tx_id: tx_id.clone(),
         };
 
-        self.locked_collateral.lock().await.insert(channel_id, collateral);
+        self.chain.locked_collateral.lock().await.insert(channel_id, collateral);
 
         info!("✅ Collateral locked with tx_id: {}", tx_id);
         Ok(tx_id)
     }
 
-    /// Unlock collateral on L1 after channel closure
+    /// Collateral locked for a channel, if any
+    pub async fn get_locked_collateral(&self, channel_id: &str) -> Option<LockedCollateral> {
+        self.chain.locked_collateral.lock().await.get(channel_id).cloned()
+    }
+
+    /// Unlock collateral on L1 after channel closure. Only simulated: on a
+    /// connected L1 the n-of-n output needs a MuSig signature from every
+    /// participant, which the wallet cannot produce, so this fails and the
+    /// collateral stays locked.
+    #[instrument(skip_all, fields(channel_id = %channel_id))]
     pub async fn unlock_collateral(
         &self,
         channel_id: String,
@@ -185,7 +287,9 @@ impl TariL1Client {
     ) -> Result<String> {
         info!("🔓 Unlocking collateral for channel {}", channel_id);
 
-        let collateral = self.locked_collateral.lock().await.get(&channel_id).cloned();
+        self.ensure_available()?;
+
+        let collateral = self.chain.locked_collateral.lock().await.get(&channel_id).cloned();
 
         if collateral.is_none() {
             return Err(anyhow!("No locked collateral found for channel {}", channel_id));
@@ -195,29 +299,23 @@ impl TariL1Client {
             warn!("⚠️  Offline mode: Simulating collateral unlock");
             let tx_id = format!("mock_unlock_tx_{}", hex::encode(&blake3::hash(channel_id.as_bytes()).as_bytes()[..8]));
 
-            self.locked_collateral.lock().await.remove(&channel_id);
+            self.chain.locked_collateral.lock().await.remove(&channel_id);
 
             info!("✅ Mock collateral unlocked with tx_id: {}", tx_id);
             return Ok(tx_id);
         }
 
-        // TODO: Implement actual L1 transaction to unlock collateral
-        // This should:
-        // 1. Create a transaction that spends the locked output
-        // 2. Distribute funds according to final_balances
-        // 3. Include channel_id in the transaction metadata
-        // 4. Submit the transaction to the Tari network
-        // 5. Wait for confirmation
-
-        let tx_id = format!("mock_unlock_tx_{}", hex::encode(&blake3::hash(channel_id.as_bytes()).as_bytes()[..8]));
-
-        self.locked_collateral.lock().await.remove(&channel_id);
-
-        info!("✅ Collateral unlocked with tx_id: {} (balances: {:?})", tx_id, final_balances);
-        Ok(tx_id)
+        Err(anyhow!(
+            "Releasing the collateral of channel {} on L1 is not supported yet: it needs every participant's MuSig signature (balances: {:?})",
+            channel_id, final_balances
+        ))
     }
 
-    /// Checkpoint state to L1 blockchain
+    /// Anchor a channel's state root on L1. The wallet pays a small one-sided
+    /// output to the channel's `CheckpointAnchor` address, with a payment ID
+    /// committing to the channel, root and signatures; any participant's
+    /// `verify_checkpoint` later finds it on the chain.
+    #[instrument(skip_all, fields(channel_id = %channel_id, block_height = block_height))]
     pub async fn checkpoint_state(
         &self,
         channel_id: String,
@@ -227,99 +325,320 @@ impl TariL1Client {
     ) -> Result<String> {
         info!("📌 Creating checkpoint for channel {} at block {}", channel_id, block_height);
 
-        if !self.is_connected().await {
-            warn!("⚠️  Offline mode: Simulating checkpoint");
-            let tx_id = format!("mock_checkpoint_tx_{}", hex::encode(&blake3::hash(channel_id.as_bytes()).as_bytes()[..8]));
+        self.ensure_available()?;
 
-            let checkpoint = Checkpoint {
-                channel_id: channel_id.clone(),
-                state_root,
-                block_height,
-                signatures,
-                tx_id: tx_id.clone(),
-            };
-
-            self.checkpoints
-                .lock()
-                .await
-                .entry(channel_id)
-                .or_insert_with(Vec::new)
-                .push(checkpoint);
-
-            info!("✅ Mock checkpoint created with tx_id: {}", tx_id);
-            return Ok(tx_id);
-        }
-
-        // TODO: Implement actual L1 transaction to checkpoint state
-        // This should:
-        // 1. Create a transaction with OP_RETURN or similar to anchor state
-        // 2. Include state_root, channel_id, and signatures
-        // 3. Submit the transaction to the Tari network
-        // 4. Wait for confirmation
-
-        let tx_id = format!("mock_checkpoint_tx_{}", hex::encode(&blake3::hash(channel_id.as_bytes()).as_bytes()[..8]));
-
-        let checkpoint = Checkpoint {
+        let mut checkpoint = Checkpoint {
             channel_id: channel_id.clone(),
             state_root,
             block_height,
             signatures,
-            tx_id: tx_id.clone(),
+            tx_id: String::new(),
         };
+        let memo = checkpoint.memo();
+        let address = CheckpointAnchor::new(channel_id).address(&self.config.network)?;
 
-        self.checkpoints
-            .lock()
-            .await
-            .entry(channel_id)
-            .or_insert_with(Vec::new)
-            .push(checkpoint);
+        checkpoint.tx_id = if self.is_connected().await {
+            self.wallet_transfer(address, CHECKPOINT_ANCHOR_AMOUNT, memo, false).await?.to_string()
+        } else {
+            warn!("⚠️  Offline mode: Simulating checkpoint");
+            let tx_id = format!("mock_checkpoint_tx_{}", hex::encode(&blake3::hash(&memo).as_bytes()[..8]));
+            self.chain.anchors.lock().await.entry(address).or_insert_with(Vec::new).push(memo);
+            tx_id
+        };
+        let tx_id = checkpoint.tx_id.clone();
 
         info!("✅ Checkpoint created with tx_id: {}", tx_id);
         Ok(tx_id)
     }
 
-    /// Submit a dispute to L1
+    /// Whether `state_root` of `channel_id` was anchored: an output paid to
+    /// the channel's anchor address carries a checkpoint of that root.
+    ///
+    /// Whoever submitted the checkpoint, it is found on the chain: a
+    /// connected client scans the base node's last `ANCHOR_SCAN_DEPTH`
+    /// blocks, decrypting outputs with the anchor's view key, and an offline
+    /// one looks at the outputs on its chain.
+    #[instrument(skip_all, fields(channel_id = %channel_id))]
+    pub async fn verify_checkpoint(&self, channel_id: &str, state_root: &str) -> Result<bool> {
+        self.ensure_available()?;
+
+        let anchor = CheckpointAnchor::new(channel_id.to_string());
+        let payment_ids = if self.is_connected().await {
+            let outputs = self.recent_outputs(ANCHOR_SCAN_DEPTH).await?;
+            Self::payment_ids_with_key(outputs, &anchor.view_key()?)
+        } else {
+            let address = anchor.address(&self.config.network)?;
+            self.chain.anchors.lock().await.get(&address).cloned().unwrap_or_default()
+        };
+
+        // The payment ID is stored with its own header, so look for the memo in it
+        let prefix = Checkpoint::memo_prefix(channel_id, state_root);
+        Ok(payment_ids.iter().any(|id| id.windows(prefix.len()).any(|window| window == prefix)))
+    }
+
+    /// Whether the wallet reports `transaction_id` mined and confirmed with
+    /// `memo`, if any, in its payment ID
+    async fn transaction_carries(&self, transaction_id: u64, memo: &[u8]) -> Result<bool> {
+        let wallet_grpc = self.config.wallet_grpc.as_ref()
+            .ok_or_else(|| anyhow!("Wallet gRPC not configured"))?;
+
+        use minotari_wallet_grpc_client::WalletGrpcClient;
+        use minotari_wallet_grpc_client::grpc::{GetTransactionInfoRequest, TransactionStatus};
+
+        let mut client = WalletGrpcClient::connect(wallet_grpc).await
+            .map_err(|e| anyhow!("Failed to connect to wallet: {}", e))?;
+
+        let request = GetTransactionInfoRequest { transaction_ids: vec![transaction_id] };
+        let response = client.get_transaction_info(request).await
+            .map_err(|e| anyhow!("Failed to query transaction {}: {}", transaction_id, e))?;
+        let Some(tx) = response.into_inner().transactions.into_iter().next() else {
+            return Ok(false);
+        };
+
+        // The payment ID is stored with its own header, so look for the memo in it
+        let mined = TransactionStatus::try_from(tx.status) == Ok(TransactionStatus::MinedConfirmed);
+        Ok(mined && (memo.is_empty() || tx.raw_payment_id.windows(memo.len()).any(|window| window == memo)))
+    }
+
+    /// Dispute a channel close with `evidence` of its latest state at time
+    /// `now`. The first submission opens a challenge window as long as the
+    /// channel's challenge period; later ones are counter-proofs and must
+    /// reach a higher nonce before the window closes.
+    ///
+    /// Only supported in offline mode. On a connected L1 the dispute could
+    /// not be seen by the other participants' nodes, nor paid out once its
+    /// window closed, so this fails and the channel must be settled
+    /// cooperatively.
+    #[instrument(skip_all, fields(channel_id = %channel_id, nonce = evidence.nonce()))]
     pub async fn submit_dispute(
         &self,
         channel_id: String,
-        disputed_state: String,
-        proof: Vec<u8>,
+        evidence: DisputeEvidence,
+        now: u64,
     ) -> Result<String> {
-        info!("⚠️  Submitting dispute for channel {}", channel_id);
+        info!("⚠️  Submitting dispute for channel {} at nonce {}", channel_id, evidence.nonce());
 
-        if !self.is_connected().await {
-            warn!("⚠️  Offline mode: Cannot submit dispute. L1 connection required.");
-            return Err(anyhow!("L1 connection required to submit disputes"));
+        self.ensure_available()?;
+        self.ensure_simulated_disputes().await?;
+
+        let lock = self.chain.locked_collateral.lock().await.get(&channel_id).cloned()
+            .ok_or_else(|| anyhow!("No locked collateral found for channel {}", channel_id))?;
+        let settled = Dispute::check_evidence(&lock, &evidence)?;
+        if let Some(dispute) = self.chain.disputes.lock().await.get(&channel_id) {
+            dispute.check_counter(&settled, now)?;
         }
 
-        // TODO: Implement actual L1 dispute transaction
-        // This should:
-        // 1. Create a transaction that triggers the dispute resolution contract
-        // 2. Include disputed_state and cryptographic proof
-        // 3. Submit to L1 for adjudication
-        // 4. Return dispute transaction ID
+        let digest = evidence.digest().map_err(|e| anyhow!("Failed to hash dispute evidence: {}", e))?;
+        let mut submission = DisputeSubmission {
+            nonce: settled.nonce,
+            state_root: settled.state_root.to_string(),
+            evidence_digest: digest.to_string(),
+            submitted_at: now,
+            tx_id: String::new(),
+        };
+        let memo = submission.memo(&channel_id);
 
-        let tx_id = format!("mock_dispute_tx_{}", hex::encode(&blake3::hash(channel_id.as_bytes()).as_bytes()[..8]));
+        warn!("⚠️  Offline mode: Simulating dispute submission");
+        submission.tx_id = format!("mock_dispute_tx_{}", hex::encode(&blake3::hash(&memo).as_bytes()[..8]));
+        let tx_id = submission.tx_id.clone();
 
-        error!("🚨 Dispute submitted with tx_id: {} (NOT IMPLEMENTED - requires L1 connection)", tx_id);
+        let mut disputes = self.chain.disputes.lock().await;
+        match disputes.get_mut(&channel_id) {
+            Some(dispute) => dispute.counter(settled, submission)?,
+            None => {
+                disputes.insert(channel_id.clone(), Dispute::open(&lock, settled, submission));
+            }
+        }
+
+        info!("✅ Dispute for channel {} submitted with tx_id: {}", channel_id, tx_id);
         Ok(tx_id)
     }
 
-    /// Verify a transaction exists on L1
+    /// Dispute over a channel, if one was submitted; fails on a connected
+    /// L1, see `submit_dispute`
+    pub async fn get_dispute(&self, channel_id: &str) -> Result<Option<Dispute>> {
+        self.ensure_simulated_disputes().await?;
+        Ok(self.chain.disputes.lock().await.get(channel_id).cloned())
+    }
+
+    /// Disputes on the chain whose challenge window is still open at time
+    /// `now`, whoever submitted them; for participants and watchtowers
+    /// scanning for closes at stale states. Fails on a connected L1, see
+    /// `submit_dispute`
+    pub async fn open_disputes(&self, now: u64) -> Result<Vec<Dispute>> {
+        self.ensure_simulated_disputes().await?;
+        Ok(self.chain.disputes.lock().await.values()
+            .filter(|dispute| dispute.resolution.is_none() && now < dispute.deadline)
+            .cloned()
+            .collect())
+    }
+
+    /// Pay out a disputed channel's collateral once its challenge window has
+    /// closed, split according to the highest-nonce state proven
+    #[instrument(skip_all, fields(channel_id = %channel_id))]
+    pub async fn resolve_dispute(&self, channel_id: String, now: u64) -> Result<DisputeResolution> {
+        self.ensure_simulated_disputes().await?;
+        let dispute = self.chain.disputes.lock().await.get(&channel_id).cloned()
+            .ok_or_else(|| anyhow!("No dispute found for channel {}", channel_id))?;
+        if let Some(resolution) = dispute.resolution {
+            return Ok(resolution);
+        }
+        if now < dispute.deadline {
+            return Err(anyhow!("Challenge window for channel {} is open until {}", channel_id, dispute.deadline));
+        }
+
+        let payouts = dispute.payout_split();
+        let tx_id = self.unlock_collateral(channel_id.clone(), payouts.clone()).await?;
+        let resolution = DisputeResolution {
+            nonce: dispute.best.nonce,
+            state_root: dispute.best.state_root.to_string(),
+            payouts,
+            resolved_at: now,
+            tx_id,
+        };
+
+        if let Some(dispute) = self.chain.disputes.lock().await.get_mut(&channel_id) {
+            dispute.resolution = Some(resolution.clone());
+        }
+        info!("⚖️  Resolved dispute for channel {} at nonce {}, tx: {}", channel_id, resolution.nonce, resolution.tx_id);
+        Ok(resolution)
+    }
+
+    /// Send `amount` µT from the connected wallet to `address`, with
+    /// `memo` as the payment ID
+    #[instrument(skip_all, fields(amount = amount, memo = %memo))]
+    pub async fn send_payment(&self, address: String, amount: u64, memo: String) -> Result<String> {
+        info!("💸 Sending {} µT to {}", amount, address);
+
+        self.ensure_available()?;
+
+        if !self.is_connected().await {
+            warn!("⚠️  Offline mode: Simulating payment");
+            let tx_id = format!("mock_payment_tx_{}", hex::encode(&blake3::hash(memo.as_bytes()).as_bytes()[..8]));
+
+            info!("✅ Mock payment sent with tx_id: {}", tx_id);
+            return Ok(tx_id);
+        }
+
+        let tx_id = self.wallet_transfer(address, amount, memo.into_bytes(), true).await?.to_string();
+        info!("✅ Payment sent with tx_id: {}", tx_id);
+        Ok(tx_id)
+    }
+
+    /// Pay `amount` µT of the escrow deposit made in `deposit_tx_id` to
+    /// `address`. Only the deposit is spent, never the wallet's own funds,
+    /// and each deposit pays out once.
+    #[instrument(skip_all, fields(deposit_tx_id = %deposit_tx_id, amount = amount))]
+    pub async fn release_escrow(&self, deposit_tx_id: String, address: String, amount: u64) -> Result<String> {
+        info!("🔓 Releasing {} µT of escrow deposit {} to {}", amount, deposit_tx_id, address);
+
+        self.ensure_available()?;
+
+        if !self.is_connected().await {
+            warn!("⚠️  Offline mode: Simulating escrow release");
+            if !self.chain.spent_deposits.lock().await.insert(deposit_tx_id.clone()) {
+                return Err(anyhow!("Escrow deposit {} was already paid out", deposit_tx_id));
+            }
+            let tx_id = format!("mock_release_tx_{}", hex::encode(&blake3::hash(deposit_tx_id.as_bytes()).as_bytes()[..8]));
+
+            info!("✅ Mock escrow released with tx_id: {}", tx_id);
+            return Ok(tx_id);
+        }
+
+        // The deposit is an output the wallet cannot spend alone, and paying
+        // the payee from the wallet instead would spend someone else's funds
+        Err(anyhow!("Releasing escrow deposit {} on L1 is not supported yet", deposit_tx_id))
+    }
+
+    /// Send a one-sided payment from the connected wallet, returning the
+    /// wallet's transaction ID
+    async fn wallet_transfer(&self, address: String, amount: u64, payment_id: Vec<u8>, stealth: bool) -> Result<u64> {
+        let wallet_grpc = self.config.wallet_grpc.as_ref()
+            .ok_or_else(|| anyhow!("Wallet gRPC not configured"))?;
+
+        use minotari_wallet_grpc_client::WalletGrpcClient;
+        use minotari_wallet_grpc_client::grpc::{payment_recipient::PaymentType, PaymentRecipient, TransferRequest};
+
+        let mut client = WalletGrpcClient::connect(wallet_grpc).await
+            .map_err(|e| anyhow!("Failed to connect to wallet: {}", e))?;
+
+        let request = TransferRequest {
+            recipients: vec![PaymentRecipient {
+                address: address.clone(),
+                amount,
+                fee_per_gram: 5,
+                payment_type: if stealth { PaymentType::OneSidedToStealthAddress } else { PaymentType::OneSided } as i32,
+                raw_payment_id: payment_id,
+                ..Default::default()
+            }],
+        };
+        let response = client.transfer(request).await
+            .map_err(|e| anyhow!("Wallet transfer failed: {}", e))?;
+
+        let result = response.into_inner().results.into_iter().next()
+            .ok_or_else(|| anyhow!("Wallet returned no transfer result"))?;
+        if !result.is_success {
+            return Err(anyhow!("Payment to {} failed: {}", address, result.failure_message));
+        }
+        Ok(result.transaction_id)
+    }
+
+    /// Poll the wallet until `transaction_id` is mined and confirmed
+    async fn wait_for_confirmation(&self, transaction_id: u64) -> Result<()> {
+        let wallet_grpc = self.config.wallet_grpc.as_ref()
+            .ok_or_else(|| anyhow!("Wallet gRPC not configured"))?;
+
+        use minotari_wallet_grpc_client::WalletGrpcClient;
+        use minotari_wallet_grpc_client::grpc::{GetTransactionInfoRequest, TransactionStatus};
+
+        let mut client = WalletGrpcClient::connect(wallet_grpc).await
+            .map_err(|e| anyhow!("Failed to connect to wallet: {}", e))?;
+
+        let deadline = tokio::time::Instant::now() + CONFIRMATION_TIMEOUT;
+        loop {
+            self.ensure_available()?;
+
+            let request = GetTransactionInfoRequest { transaction_ids: vec![transaction_id] };
+            let response = client.get_transaction_info(request).await
+                .map_err(|e| anyhow!("Failed to query transaction {}: {}", transaction_id, e))?;
+            let status = response.into_inner().transactions.first()
+                .map(|tx| tx.status)
+                .ok_or_else(|| anyhow!("Wallet does not know transaction {}", transaction_id))?;
+
+            match TransactionStatus::try_from(status) {
+                Ok(TransactionStatus::MinedConfirmed) => {
+                    info!("✅ Transaction {} confirmed", transaction_id);
+                    return Ok(());
+                }
+                Ok(TransactionStatus::Rejected) | Ok(TransactionStatus::NotFound) => {
+                    return Err(anyhow!("Transaction {} was not mined (status {})", transaction_id, status));
+                }
+                _ => {}
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                return Err(anyhow!("Transaction {} not confirmed after {:?}", transaction_id, CONFIRMATION_TIMEOUT));
+            }
+            tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Verify a transaction exists on L1. On a connected L1 only the
+    /// wallet's own transactions can be looked up, by their numeric ID.
+    #[instrument(skip_all, fields(tx_id = %tx_id))]
     pub async fn verify_transaction(&self, tx_id: String) -> Result<bool> {
+        self.ensure_available()?;
+
         if !self.is_connected().await {
             warn!("⚠️  Offline mode: Cannot verify transaction");
             // In offline mode, assume mock transactions are valid
             return Ok(tx_id.starts_with("mock_"));
         }
 
-        // TODO: Implement actual transaction verification
-        // This should:
-        // 1. Query the base node for the transaction
-        // 2. Check if it's in a mined block
-        // 3. Return confirmation status
-
-        Ok(tx_id.starts_with("mock_"))
+        let Ok(transaction_id) = tx_id.parse::<u64>() else {
+            return Ok(false);
+        };
+        self.transaction_carries(transaction_id, &[]).await
     }
 
     /// Get balance from connected wallet
@@ -461,15 +780,8 @@ impl TariL1Client {
         Ok(total_balance)
     }
 
-    /// Get balance for a wallet by scanning UTXOs from base node
-    pub async fn get_balance_with_key(&self, view_key: tari_crypto::ristretto::RistrettoSecretKey) -> Result<u64> {
-        if !self.is_connected().await {
-            warn!("⚠️  Base node not connected, cannot query balance");
-            return Ok(0);
-        }
-
-        info!("🔍 Scanning UTXOs with wallet...");
-
+    /// Outputs in the base node's last `depth` blocks
+    async fn recent_outputs(&self, depth: u64) -> Result<Vec<minotari_app_grpc::tari_rpc::TransactionOutput>> {
         use minotari_app_grpc::tari_rpc::base_node_client::BaseNodeClient;
         use minotari_app_grpc::tari_rpc::{Empty, GetBlocksRequest};
 
@@ -483,9 +795,7 @@ impl TariL1Client {
 
         info!("📊 Scanning blockchain at height {}", current_height);
 
-        // Scan recent blocks for outputs
-        // Scan last 1000 blocks or less if chain is shorter
-        let start_height = if current_height > 1000 { current_height - 1000 } else { 0 };
+        let start_height = current_height.saturating_sub(depth);
 
         info!("🔍 Scanning blocks {} to {}", start_height, current_height);
 
@@ -528,6 +838,39 @@ impl TariL1Client {
             }
         }
 
+        Ok(all_outputs)
+    }
+
+    /// Payment IDs of the outputs `view_key` can decrypt
+    fn payment_ids_with_key(
+        outputs: Vec<minotari_app_grpc::tari_rpc::TransactionOutput>,
+        view_key: &tari_crypto::ristretto::RistrettoSecretKey,
+    ) -> Vec<Vec<u8>> {
+        use tari_transaction_components::transaction_components::EncryptedData;
+        use tari_common_types::types::CompressedCommitment;
+        use tari_crypto::tari_utilities::ByteArray;
+
+        outputs.iter()
+            .filter_map(|output| {
+                let encrypted_data = EncryptedData::from_bytes(&output.encrypted_data).ok()?;
+                let commitment = CompressedCommitment::from_canonical_bytes(&output.commitment).ok()?;
+                let (_value, _private_key, payment_id) = EncryptedData::decrypt_data(view_key, &commitment, &encrypted_data).ok()?;
+                Some(payment_id.to_bytes())
+            })
+            .collect()
+    }
+
+    /// Get balance for a wallet by scanning UTXOs from base node
+    pub async fn get_balance_with_key(&self, view_key: tari_crypto::ristretto::RistrettoSecretKey) -> Result<u64> {
+        if !self.is_connected().await {
+            warn!("⚠️  Base node not connected, cannot query balance");
+            return Ok(0);
+        }
+
+        info!("🔍 Scanning UTXOs with wallet...");
+
+        let all_outputs = self.recent_outputs(1000).await?;
+
         info!("🔍 Scanned {} total outputs, checking ownership...", all_outputs.len());
 
         // Use private key to scan outputs
@@ -657,3 +1000,29 @@ pub struct ConnectionStatus {
     pub network: TariNetwork,
     pub endpoint: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_common::Hash;
+
+    #[tokio::test]
+    async fn test_connected_l1_refuses_disputes() {
+        let client = TariL1Client::offline(L1Config::default());
+        *client.connected.lock().await = true;
+
+        let channel_id = Hash::random();
+        let evidence = DisputeEvidence {
+            channel_id,
+            participants: Vec::new(),
+            initial_balances: HashMap::new(),
+            challenge_period: 3600,
+            genesis_signatures: HashMap::new(),
+            history: Vec::new(),
+        };
+        assert!(client.submit_dispute(channel_id.to_string(), evidence, 0).await.is_err());
+        assert!(client.get_dispute(&channel_id.to_string()).await.is_err());
+        assert!(client.open_disputes(0).await.is_err());
+        assert!(client.resolve_dispute(channel_id.to_string(), 0).await.is_err());
+    }
+}
@@ -0,0 +1,272 @@
+use async_trait::async_trait;
+use hyper::{Body, Client, Method, Request, Uri, client::HttpConnector};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::warn;
+use tari_l2_common::{L2Error, error::Result};
+use tari_l2_marketplace::{Alert, AlertHub, AlertPolicy, AlertSink, LogSink, MarketplaceEvent, MarketplaceManager};
+
+/// Longest a webhook may take to accept an alert
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most saved search matches being POSTed at once
+const MAX_CONCURRENT_SEARCH_WEBHOOKS: usize = 16;
+
+/// Where alerts go and when they are raised
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    /// Write alerts to the node log
+    pub log: bool,
+
+    /// http:// URLs each alert is POSTed to as JSON
+    pub webhooks: Vec<String>,
+
+    /// Minutes the L1 base node may be unreachable before alerting
+    pub l1_disconnect_minutes: u64,
+
+    /// Invalid signatures from one peer within `signature_window` that raise an alert
+    pub signature_failures: u32,
+
+    /// Seconds invalid signatures are counted over
+    pub signature_window: u64,
+
+    /// Seconds before the same alert is raised again
+    pub cooldown: u64,
+
+    /// Let saved search webhooks POST to loopback, private and link-local
+    /// addresses. Off by default, so a saved search cannot make the node
+    /// reach services on its own network.
+    pub private_search_webhooks: bool,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            log: true,
+            webhooks: Vec::new(),
+            l1_disconnect_minutes: 10,
+            signature_failures: 20,
+            signature_window: 60,
+            cooldown: 3600,
+            private_search_webhooks: false,
+        }
+    }
+}
+
+impl AlertConfig {
+    pub fn policy(&self) -> AlertPolicy {
+        AlertPolicy {
+            cooldown_secs: self.cooldown,
+            signature_failures: self.signature_failures,
+            signature_window_secs: self.signature_window,
+        }
+    }
+
+    /// The configured sinks
+    pub fn sinks(&self) -> Result<Vec<Arc<dyn AlertSink>>> {
+        let mut sinks: Vec<Arc<dyn AlertSink>> = Vec::new();
+        if self.log {
+            sinks.push(Arc::new(LogSink));
+        }
+        for url in &self.webhooks {
+            sinks.push(Arc::new(WebhookSink::new(url)?));
+        }
+        Ok(sinks)
+    }
+}
+
+/// POSTs each alert as JSON to a URL
+pub struct WebhookSink {
+    url: Uri,
+    client: Client<HttpConnector>,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str) -> Result<Self> {
+        let url: Uri = url.parse()
+            .map_err(|e| L2Error::InvalidParameter(format!("Invalid webhook URL '{}': {}", url, e)))?;
+        if url.scheme_str() != Some("http") {
+            return Err(L2Error::InvalidParameter(format!("Webhook URL '{}' must use http://", url)));
+        }
+        Ok(Self { url, client: Client::new() })
+    }
+
+    /// POST `body` as JSON, failing unless the webhook answers 2xx
+    pub async fn post<T: Serialize>(&self, body: &T) -> Result<()> {
+        self.send(self.url.clone(), body).await
+    }
+
+    /// POST `body` like [`post`](Self::post), but only to a public address.
+    /// The host is resolved once and refused if any of its addresses is
+    /// loopback, private or link-local; the request then goes to the address
+    /// that was checked, so a second lookup cannot point it elsewhere.
+    pub async fn post_public<T: Serialize>(&self, body: &T) -> Result<()> {
+        let host = self.url.host()
+            .ok_or_else(|| L2Error::InvalidParameter(format!("Webhook URL '{}' has no host", self.url)))?;
+        let port = self.url.port_u16().unwrap_or(80);
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.trim_matches(['[', ']']), port)).await
+            .map_err(|e| L2Error::NetworkError(format!("Could not resolve {}: {}", host, e)))?
+            .collect();
+        let Some(addr) = addrs.first() else {
+            return Err(L2Error::NetworkError(format!("{} did not resolve", host)));
+        };
+        if let Some(private) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+            return Err(L2Error::InvalidParameter(format!("{} resolves to non-public address {}", host, private.ip())));
+        }
+
+        let path = self.url.path_and_query().map_or("/", |path| path.as_str());
+        let uri: Uri = format!("http://{}{}", addr, path).parse()
+            .map_err(|e| L2Error::InvalidParameter(format!("Invalid webhook URL '{}': {}", self.url, e)))?;
+        self.send(uri, body).await
+    }
+
+    async fn send<T: Serialize>(&self, uri: Uri, body: &T) -> Result<()> {
+        let body = serde_json::to_vec(body)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header("Content-Type", "application/json");
+        if let Some(authority) = self.url.authority() {
+            request = request.header("Host", authority.as_str());
+        }
+        let request = request
+            .body(Body::from(body))
+            .map_err(|e| L2Error::NetworkError(e.to_string()))?;
+
+        let response = tokio::time::timeout(WEBHOOK_TIMEOUT, self.client.request(request)).await
+            .map_err(|_| L2Error::Timeout)?
+            .map_err(|e| L2Error::NetworkError(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(L2Error::NetworkError(format!("{} returned {}", self.url, response.status())));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AlertSink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn deliver(&self, alert: &Alert) -> Result<()> {
+        self.post(alert).await
+    }
+}
+
+/// Deliver every alert raised on `hub` to each sink
+pub fn spawn_alert_sinks(hub: &AlertHub, sinks: Vec<Arc<dyn AlertSink>>) -> JoinHandle<()> {
+    let mut alerts = hub.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let alert = match alerts.recv().await {
+                Ok(alert) => alert,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Alert delivery fell behind, {} alerts skipped", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            for sink in &sinks {
+                if let Err(e) = sink.deliver(&alert).await {
+                    warn!("Failed to deliver alert #{} to {} sink: {}", alert.id, sink.name(), e);
+                }
+            }
+        }
+    })
+}
+
+/// Whether `ip` is reachable on the public internet rather than only on the
+/// node itself or its local network
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            // 100.64.0.0/10 is carrier-grade NAT
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified()
+                || ip.is_broadcast() || ip.is_documentation() || (a == 100 && b & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            // fc00::/7 is unique local, fe80::/10 link-local
+            None => !(ip.is_loopback() || ip.is_unspecified()
+                || ip.segments()[0] & 0xfe00 == 0xfc00 || ip.segments()[0] & 0xffc0 == 0xfe80),
+        },
+    }
+}
+
+/// POST each saved search match to the webhook of its search, if it has one.
+/// Matches are delivered concurrently, so a slow webhook holds up only its
+/// own. Unless `allow_private` is set, webhooks on loopback, private or
+/// link-local addresses are refused.
+pub fn spawn_saved_search_webhooks(marketplace: Arc<MarketplaceManager>, allow_private: bool) -> JoinHandle<()> {
+    let mut events = marketplace.events().subscribe();
+    let deliveries = Arc::new(Semaphore::new(MAX_CONCURRENT_SEARCH_WEBHOOKS));
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Saved search webhooks fell behind, {} events skipped", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            let MarketplaceEvent::SavedSearchMatch { search_id, .. } = &event else {
+                continue;
+            };
+            let webhook = match marketplace.get_saved_search(search_id) {
+                Ok(search) => search.and_then(|search| search.webhook),
+                Err(e) => {
+                    warn!("Could not load saved search {:?}: {}", search_id, e);
+                    continue;
+                }
+            };
+            let Some(url) = webhook else {
+                continue;
+            };
+            let search_id = *search_id;
+            let Ok(permit) = deliveries.clone().acquire_owned().await else {
+                return;
+            };
+            tokio::spawn(async move {
+                let delivered = match WebhookSink::new(&url) {
+                    Ok(sink) if allow_private => sink.post(&event).await,
+                    Ok(sink) => sink.post_public(&event).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = delivered {
+                    warn!("Failed to deliver saved search {:?} match to {}: {}", search_id, url, e);
+                }
+                drop(permit);
+            });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_search_webhooks_refuse_private_addresses() {
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["1.1.1.1", "100.128.0.1", "2606:4700::1111"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+
+        let sink = WebhookSink::new("http://127.0.0.1:9/matches").unwrap();
+        assert!(matches!(sink.post_public(&"match").await, Err(L2Error::InvalidParameter(_))));
+        let sink = WebhookSink::new("http://localhost:9/matches").unwrap();
+        assert!(matches!(sink.post_public(&"match").await, Err(L2Error::InvalidParameter(_))));
+    }
+}
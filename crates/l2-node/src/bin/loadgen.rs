@@ -0,0 +1,338 @@
+//! Load generator for capacity testing a node over JSON-RPC.
+//!
+//! Creates synthetic wallets, opens a channel between each wallet and the
+//! target node, then drives listings, orders and transfers through those
+//! channels at a fixed rate. Every update is signed by the wallet and
+//! co-signed by the node via `submit_state_update`, so each successful
+//! request is one applied state update.
+//!
+//! ```text
+//! loadgen --target 127.0.0.1:18000 --wallets 20 --rate 200 --duration 60
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use clap::Parser;
+use hyper::{Body, Client, Method, Request, client::HttpConnector};
+use rand::Rng;
+use serde_json::{json, Value};
+use tokio::sync::{Mutex, Semaphore};
+use tari_l2_common::{Amount, Hash, PublicKey, crypto::KeyPair};
+use tari_l2_state_channel::{ChannelConfig, MarketplaceChannel, StateUpdate, update::SignedStateUpdate};
+use tari_l2_state_channel::state::{Listing, Order, OrderStatus};
+
+/// Challenge period the node's `create_channel` RPC opens channels with
+const CHALLENGE_PERIOD_SECS: u64 = 86400;
+
+#[derive(Parser)]
+#[command(name = "loadgen")]
+#[command(about = "Generate marketplace load against a Tari L2 node", long_about = None)]
+struct Args {
+    /// RPC address of the node under test
+    #[arg(short, long, default_value = "127.0.0.1:18000")]
+    target: String,
+
+    /// Number of synthetic wallets (one channel each)
+    #[arg(short, long, default_value_t = 10)]
+    wallets: usize,
+
+    /// Target operations per second
+    #[arg(short, long, default_value_t = 50)]
+    rate: u32,
+
+    /// How long to generate load, in seconds
+    #[arg(short, long, default_value_t = 30)]
+    duration: u64,
+
+    /// Maximum requests in flight
+    #[arg(short, long, default_value_t = 32)]
+    concurrency: usize,
+
+    /// Collateral locked in each channel, split evenly
+    #[arg(long, default_value_t = 1_000_000)]
+    collateral: u64,
+
+    /// Admin API key of the node; `submit_state_update` requires one
+    #[arg(long)]
+    api_key: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Op {
+    OpenChannel,
+    CreateListing,
+    CreateOrder,
+    Transfer,
+}
+
+impl Op {
+    fn is_state_update(self) -> bool {
+        self != Op::OpenChannel
+    }
+}
+
+#[derive(Default)]
+struct OpStats {
+    latencies: Vec<Duration>,
+    errors: usize,
+    first_error: Option<String>,
+}
+
+#[derive(Default)]
+struct Stats {
+    ops: BTreeMap<Op, OpStats>,
+}
+
+impl Stats {
+    fn record(&mut self, op: Op, latency: Duration, result: &Result<Value, String>) {
+        let entry = self.ops.entry(op).or_default();
+        match result {
+            Ok(_) => entry.latencies.push(latency),
+            Err(e) => {
+                entry.errors += 1;
+                entry.first_error.get_or_insert_with(|| e.clone());
+            }
+        }
+    }
+}
+
+struct Rpc {
+    client: Client<HttpConnector>,
+    url: String,
+    api_key: Option<String>,
+}
+
+impl Rpc {
+    async fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        let body = json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 });
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.url)
+            .header("Content-Type", "application/json");
+        if let Some(key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", key));
+        }
+        let request = request
+            .body(Body::from(body.to_string()))
+            .map_err(|e| e.to_string())?;
+
+        let response = self.client.request(request).await.map_err(|e| e.to_string())?;
+        let bytes = hyper::body::to_bytes(response.into_body()).await.map_err(|e| e.to_string())?;
+        let json: Value = serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+
+        match json.get("error") {
+            Some(error) if !error.is_null() => Err(error["message"].as_str().unwrap_or("unknown error").to_string()),
+            _ => Ok(json["result"].clone()),
+        }
+    }
+}
+
+/// A synthetic wallet and the channel it shares with the node
+struct Wallet {
+    keypair: KeyPair,
+    channel_id: Hash,
+    nonce: u64,
+    listings: Vec<Hash>,
+}
+
+struct Generator {
+    rpc: Rpc,
+    node: PublicKey,
+    stats: Mutex<Stats>,
+}
+
+impl Generator {
+    async fn timed(&self, op: Op, method: &str, params: Value) -> Result<Value, String> {
+        let start = Instant::now();
+        let result = self.rpc.call(method, params).await;
+        self.stats.lock().await.record(op, start.elapsed(), &result);
+        result
+    }
+
+    async fn open_wallet(&self, collateral: u64) -> Result<Wallet, String> {
+        let keypair = KeyPair::generate();
+
+        // Sign the genesis state the node will build: an even split with its
+        // default challenge period
+        let mut initial_balances = HashMap::new();
+        initial_balances.insert(self.node, Amount::new(collateral / 2));
+        initial_balances.insert(keypair.public_key(), Amount::new(collateral / 2));
+        let genesis = MarketplaceChannel::new(ChannelConfig {
+            participants: vec![self.node, keypair.public_key()],
+            initial_balances,
+            challenge_period: CHALLENGE_PERIOD_SECS,
+        });
+        let signature = keypair.sign(&genesis.genesis_signing_message());
+
+        let result = self.timed(Op::OpenChannel, "create_channel", json!({
+            "participant1": self.node,
+            "participant2": keypair.public_key(),
+            "collateral": collateral,
+            "signature": signature,
+        })).await?;
+        let channel_id: Hash = serde_json::from_value(result["id"].clone()).map_err(|e| e.to_string())?;
+        self.rpc.call("activate_channel", json!({ "channel_id": channel_id })).await?;
+
+        Ok(Wallet { keypair, channel_id, nonce: 0, listings: Vec::new() })
+    }
+
+    /// Pick an update for `wallet`, sign it and have the node co-sign and apply it
+    async fn step(&self, wallet: &mut Wallet) {
+        let me = wallet.keypair.public_key();
+        let roll = rand::thread_rng().gen_range(0..10);
+
+        let (op, update) = match (roll, wallet.listings.last()) {
+            (0..=2, _) | (3..=5, None) => {
+                let id = Hash::random();
+                (Op::CreateListing, StateUpdate::CreateListing {
+                    listing: Listing {
+                        id,
+                        seller: me,
+                        title: format!("loadgen item {}", wallet.nonce),
+                        description: String::new(),
+                        price: Amount::new(1),
+                        ipfs_hash: String::new(),
+                        active: true,
+                        category: "loadgen".to_string(),
+                        fiat_reference: None,
+                    },
+                })
+            }
+            (3..=5, Some(listing_id)) => (Op::CreateOrder, StateUpdate::CreateOrder {
+                order: Order {
+                    id: Hash::random(),
+                    listing_id: *listing_id,
+                    buyer: self.node,
+                    seller: me,
+                    amount: Amount::new(1),
+                    status: OrderStatus::Pending,
+                },
+            }),
+            // The node only co-signs updates that leave its own balance alone
+            _ => (Op::Transfer, StateUpdate::Transfer { from: me, to: self.node, amount: Amount::new(1) }),
+        };
+
+        let listing_id = match &update {
+            StateUpdate::CreateListing { listing } => Some(listing.id),
+            _ => None,
+        };
+
+        let mut signed = SignedStateUpdate::new(update, wallet.nonce + 1);
+        signed.add_signature(me, wallet.keypair.sign(&signed.signing_message()));
+
+        let result = self.timed(op, "submit_state_update", json!({
+            "channel_id": wallet.channel_id,
+            "update": signed,
+        })).await;
+
+        match result {
+            Ok(_) => {
+                wallet.nonce += 1;
+                wallet.listings.extend(listing_id);
+            }
+            Err(_) => {
+                // Resync in case the node's view of the channel moved on
+                if let Ok(info) = self.rpc.call("get_channel_info", json!({ "channel_id": wallet.channel_id })).await {
+                    wallet.nonce = info["nonce"].as_u64().unwrap_or(wallet.nonce);
+                }
+            }
+        }
+    }
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn report(stats: &mut Stats, elapsed: Duration) {
+    println!("\n{:<14} {:>8} {:>7} {:>10} {:>10} {:>10} {:>10}", "operation", "ok", "errors", "p50", "p90", "p99", "max");
+
+    let mut updates = 0;
+    for (op, entry) in stats.ops.iter_mut() {
+        entry.latencies.sort();
+        let l = &entry.latencies;
+        println!(
+            "{:<14} {:>8} {:>7} {:>10.2?} {:>10.2?} {:>10.2?} {:>10.2?}",
+            format!("{:?}", op), l.len(), entry.errors,
+            percentile(l, 50.0), percentile(l, 90.0), percentile(l, 99.0), l.last().copied().unwrap_or_default(),
+        );
+        if op.is_state_update() {
+            updates += l.len();
+        }
+    }
+
+    for (op, entry) in &stats.ops {
+        if let Some(e) = &entry.first_error {
+            println!("  first {:?} error: {}", op, e);
+        }
+    }
+
+    println!("\n{} state updates in {:.1?} → {:.1} updates/s", updates, elapsed, updates as f64 / elapsed.as_secs_f64());
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let rpc = Rpc {
+        client: Client::new(),
+        url: format!("http://{}/", args.target),
+        api_key: args.api_key.clone(),
+    };
+
+    let info = rpc.call("get_node_info", json!(null)).await
+        .map_err(|e| anyhow::anyhow!("Cannot reach node at {}: {}", args.target, e))?;
+    let node: PublicKey = serde_json::from_value(info["public_key"].clone())?;
+    println!("🎯 Target node {} ({})", args.target, node);
+
+    let generator = Arc::new(Generator { rpc, node, stats: Mutex::new(Stats::default()) });
+
+    println!("👛 Opening {} wallet channels...", args.wallets);
+    let mut wallets = Vec::with_capacity(args.wallets);
+    for _ in 0..args.wallets {
+        match generator.open_wallet(args.collateral).await {
+            Ok(wallet) => {
+                debug_assert_eq!(wallet.channel_id, MarketplaceChannel::compute_id(&[node, wallet.keypair.public_key()]));
+                wallets.push(Arc::new(Mutex::new(wallet)));
+            }
+            Err(e) => eprintln!("⚠️  Failed to open channel: {}", e),
+        }
+    }
+    anyhow::ensure!(!wallets.is_empty(), "no channels could be opened");
+
+    println!("🚀 Generating {} ops/s for {}s across {} channels...", args.rate, args.duration, wallets.len());
+    let semaphore = Arc::new(Semaphore::new(args.concurrency));
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / args.rate.max(1) as f64));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Burst);
+
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(args.duration);
+    let mut tasks = Vec::new();
+    let mut next = 0;
+
+    while Instant::now() < deadline {
+        ticker.tick().await;
+        let permit = semaphore.clone().acquire_owned().await?;
+        let wallet = wallets[next % wallets.len()].clone();
+        next += 1;
+
+        let generator = generator.clone();
+        tasks.push(tokio::spawn(async move {
+            let mut wallet = wallet.lock().await;
+            generator.step(&mut wallet).await;
+            drop(permit);
+        }));
+    }
+    for task in tasks {
+        let _ = task.await;
+    }
+    let elapsed = start.elapsed();
+
+    let mut stats = generator.stats.lock().await;
+    report(&mut stats, elapsed);
+    Ok(())
+}
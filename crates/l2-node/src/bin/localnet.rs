@@ -0,0 +1,282 @@
+//! Run a local multi-node network for development.
+//!
+//! Generates a config per node under `--dir`, wires every node to bootstrap
+//! from the ones started before it, launches them as child processes of the
+//! `tari-l2-node` binary and stops them all on Ctrl-C.
+//!
+//! ```text
+//! tari-l2-localnet up --nodes 3                      # L1 simulator
+//! tari-l2-localnet up --l1 http://127.0.0.1:18142    # local Tari base node
+//! tari-l2-localnet clean
+//! ```
+
+use std::fs::File;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use clap::{Parser, Subcommand};
+use tari_l2_l1_client::{L1Config, TariNetwork};
+use tari_l2_node::NodeConfig;
+
+/// How long a node gets to open its RPC port before we give up
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Parser)]
+#[command(name = "tari-l2-localnet")]
+#[command(about = "Spin up a local network of Tari L2 nodes", long_about = None)]
+struct Cli {
+    /// Directory holding each node's config, data and log
+    #[arg(short, long, default_value = "./localnet", global = true)]
+    dir: PathBuf,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Generate configs, start the nodes and stop them on Ctrl-C
+    Up {
+        /// Number of nodes
+        #[arg(short, long, default_value_t = 3)]
+        nodes: u16,
+
+        /// RPC port of the first node; node i listens on rpc_port + i
+        #[arg(long, default_value_t = 18000)]
+        rpc_port: u16,
+
+        /// P2P port of the first node; node i listens on p2p_port + i
+        #[arg(long, default_value_t = 19000)]
+        p2p_port: u16,
+
+        /// `sim` for the built-in L1 simulator, or a base node gRPC endpoint
+        #[arg(long, default_value = "sim")]
+        l1: String,
+
+        /// Path to the tari-l2-node binary (defaults to the one next to this binary)
+        #[arg(long)]
+        node_bin: Option<PathBuf>,
+
+        /// Delete the directory after the nodes stop
+        #[arg(long)]
+        clean: bool,
+    },
+    /// Delete the localnet directory
+    Clean,
+}
+
+struct LocalNode {
+    index: u16,
+    dir: PathBuf,
+    config: NodeConfig,
+    child: Option<Child>,
+}
+
+impl LocalNode {
+    fn rpc_addr(&self) -> String {
+        format!("{}:{}", self.config.rpc.listen_addr, self.config.rpc.port)
+    }
+
+    fn config_path(&self) -> PathBuf {
+        self.dir.join("config.json")
+    }
+
+    fn log_path(&self) -> PathBuf {
+        self.dir.join("node.log")
+    }
+}
+
+fn p2p_addr(port: u16) -> String {
+    format!("/ip4/127.0.0.1/tcp/{}", port)
+}
+
+/// Config for node `index`: its own ports and data dir, bootstrapping from
+/// every earlier node
+fn node_config(dir: &Path, index: u16, rpc_port: u16, p2p_port: u16, l1: &str) -> NodeConfig {
+    let mut config = NodeConfig {
+        data_dir: dir.join("data"),
+        ..Default::default()
+    };
+    config.rpc.port = rpc_port + index;
+    config.network.listen_addr = p2p_addr(p2p_port + index);
+    config.network.bootstrap_peers = (0..index).map(|i| p2p_addr(p2p_port + i)).collect();
+
+    if l1 == "sim" {
+        config.l1_simulator = true;
+    } else {
+        config.l1 = Some(L1Config {
+            base_node_grpc: l1.to_string(),
+            wallet_grpc: None,
+            network: TariNetwork::Esmeralda,
+        });
+    }
+    config
+}
+
+fn default_node_bin() -> anyhow::Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    Ok(exe.with_file_name(format!("tari-l2-node{}", std::env::consts::EXE_SUFFIX)))
+}
+
+fn spawn(node_bin: &Path, node: &mut LocalNode) -> anyhow::Result<()> {
+    let log = File::create(node.log_path())?;
+    let child = Command::new(node_bin)
+        .arg("--config")
+        .arg(node.config_path())
+        .arg("start")
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("failed to start {:?}: {}", node_bin, e))?;
+    node.child = Some(child);
+    Ok(())
+}
+
+/// Wait until the node accepts connections on its RPC port
+fn wait_ready(node: &mut LocalNode) -> anyhow::Result<()> {
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    loop {
+        if TcpStream::connect(node.rpc_addr()).is_ok() {
+            return Ok(());
+        }
+        if let Some(status) = node.child.as_mut().and_then(|c| c.try_wait().ok().flatten()) {
+            anyhow::bail!("node {} exited with {} (see {:?})", node.index, status, node.log_path());
+        }
+        anyhow::ensure!(Instant::now() < deadline, "node {} did not open {} in time", node.index, node.rpc_addr());
+        std::thread::sleep(Duration::from_millis(200));
+    }
+}
+
+fn teardown(nodes: &mut [LocalNode]) {
+    for node in nodes.iter_mut().rev() {
+        if let Some(mut child) = node.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+            println!("   ✓ node {} stopped", node.index);
+        }
+    }
+}
+
+async fn up(
+    dir: &Path,
+    count: u16,
+    rpc_port: u16,
+    p2p_port: u16,
+    l1: &str,
+    node_bin: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(count > 0, "need at least one node");
+    let node_bin = match node_bin {
+        Some(path) => path,
+        None => default_node_bin()?,
+    };
+    anyhow::ensure!(node_bin.exists(), "{:?} not found; build it with `cargo build -p tari-l2-node` or pass --node-bin", node_bin);
+
+    println!("🛠  Generating {} node configs in {:?}", count, dir);
+    let mut nodes = Vec::with_capacity(count as usize);
+    for index in 0..count {
+        let node_dir = dir.join(format!("node-{}", index));
+        std::fs::create_dir_all(&node_dir)?;
+        let config = node_config(&node_dir, index, rpc_port, p2p_port, l1);
+        if let Err(e) = config.validate() {
+            anyhow::bail!("node {} config is invalid: {}", index, e);
+        }
+        let node = LocalNode { index, dir: node_dir, config, child: None };
+        node.config.save_to_file(&node.config_path().to_string_lossy())
+            .map_err(|e| anyhow::anyhow!("failed to write {:?}: {}", node.config_path(), e))?;
+        nodes.push(node);
+    }
+
+    println!("🚀 Starting nodes ({})...", if l1 == "sim" { "L1 simulator".to_string() } else { format!("L1 at {}", l1) });
+    // Start in order so each node's bootstrap peers are already listening
+    for i in 0..nodes.len() {
+        let started = spawn(&node_bin, &mut nodes[i]).and_then(|_| wait_ready(&mut nodes[i]));
+        if let Err(e) = started {
+            teardown(&mut nodes);
+            return Err(e);
+        }
+        println!("   ✓ node {} ready", i);
+    }
+
+    println!("\n{:<6} {:<18} {:<28} {}", "node", "rpc", "p2p", "log");
+    for node in &nodes {
+        println!("{:<6} {:<18} {:<28} {}", node.index, node.rpc_addr(), node.config.network.listen_addr, node.log_path().display());
+    }
+    println!("\nPoint the CLI at a node with TARI_L2_RPC={}. Press Ctrl-C to stop.", nodes[0].rpc_addr());
+
+    // Run until Ctrl-C or until a node dies
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    let exited = loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break None,
+            _ = interval.tick() => {
+                let dead = nodes.iter_mut().find_map(|node| {
+                    let status = node.child.as_mut()?.try_wait().ok().flatten()?;
+                    Some((node.index, status))
+                });
+                if dead.is_some() {
+                    break dead;
+                }
+            }
+        }
+    };
+
+    println!("\n🛑 Stopping localnet...");
+    teardown(&mut nodes);
+    if let Some((index, status)) = exited {
+        anyhow::bail!("node {} exited with {} (see {:?})", index, status, nodes[index as usize].log_path());
+    }
+    Ok(())
+}
+
+fn clean(dir: &Path) -> anyhow::Result<()> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+        println!("🧹 Removed {:?}", dir);
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Commands::Up { nodes, rpc_port, p2p_port, l1, node_bin, clean: remove } => {
+            let result = up(&cli.dir, nodes, rpc_port, p2p_port, &l1, node_bin).await;
+            if remove {
+                clean(&cli.dir)?;
+            }
+            result
+        }
+        Commands::Clean => clean(&cli.dir),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_generated_configs_are_wired_and_valid() {
+        let dir = TempDir::new().unwrap();
+        let configs: Vec<NodeConfig> = (0..3)
+            .map(|i| node_config(&dir.path().join(format!("node-{}", i)), i, 18000, 19000, "sim"))
+            .collect();
+
+        for (i, config) in configs.iter().enumerate() {
+            assert!(config.validate().is_ok());
+            assert!(config.l1_simulator);
+            assert_eq!(config.rpc.port, 18000 + i as u16);
+            assert_eq!(config.network.bootstrap_peers.len(), i);
+        }
+        assert_eq!(configs[2].network.bootstrap_peers, vec![configs[0].network.listen_addr.clone(), configs[1].network.listen_addr.clone()]);
+
+        let external = node_config(dir.path(), 0, 18000, 19000, "http://127.0.0.1:18142");
+        assert!(!external.l1_simulator);
+        assert_eq!(external.l1.unwrap().base_node_grpc, "http://127.0.0.1:18142");
+    }
+}
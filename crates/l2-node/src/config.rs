@@ -1,7 +1,15 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
 use std::path::PathBuf;
-use tari_l2_p2p::NetworkConfig;
+use tari_l2_p2p::{NetworkConfig, validate_multiaddr, validate_namespace};
 use tari_l2_l1_client::L1Config;
+use tari_l2_marketplace::{ChannelPolicy, DiskPolicy, SpendingLimits, spending};
+use tari_l2_common::{PublicKey, ResourceLimits};
+use tari_l2_rpc::ApiKeys;
+use crate::tasks::TaskConfig;
+use crate::alerts::AlertConfig;
 
 /// Configuration for the L2 node
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -16,11 +24,184 @@ pub struct NodeConfig {
     #[serde(default)]
     pub l1: Option<L1Config>,
 
+    /// Never dial a base node; L1 operations run against the client's
+    /// built-in simulator (offline mode with mock transactions)
+    #[serde(default)]
+    pub l1_simulator: bool,
+
     /// P2P network configuration
     pub network: NetworkConfig,
 
     /// RPC server configuration
     pub rpc: RpcConfig,
+
+    /// Rules for accepting incoming channel requests
+    #[serde(default)]
+    pub channel_policy: ChannelPolicy,
+
+    /// Resource caps for channels, listings and P2P traffic
+    #[serde(default)]
+    pub limits: ResourceLimits,
+
+    /// Spending limits for channels without limits of their own
+    #[serde(default)]
+    pub spending: SpendingConfig,
+
+    /// Background task intervals
+    #[serde(default)]
+    pub tasks: TaskConfig,
+
+    /// Exchange rates for approximate fiat listing prices
+    #[serde(default)]
+    pub exchange_rates: ExchangeRateConfig,
+
+    /// Archive peers a fresh node downloads the catalog from
+    #[serde(default)]
+    pub catalog: CatalogConfig,
+
+    /// Dispute handling
+    #[serde(default)]
+    pub disputes: DisputeConfig,
+
+    /// Operator alert sinks and thresholds
+    #[serde(default)]
+    pub alerts: AlertConfig,
+
+    /// Data directory quota and the usage at which the node degrades
+    #[serde(default)]
+    pub disk: DiskPolicy,
+
+    /// Holding watch blobs for other nodes' channels
+    #[serde(default)]
+    pub watchtower: WatchtowerConfig,
+
+    /// Exporting tracing spans to an OpenTelemetry collector
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+}
+
+/// OTLP export of the spans the node records per RPC request, P2P message,
+/// manager operation and L1 call
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// OTLP/gRPC collector endpoint, e.g. `http://localhost:4317`; empty
+    /// disables export. Needs a node built with the `otel` feature.
+    pub otlp_endpoint: String,
+
+    /// Service name spans are reported under
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: String::new(),
+            service_name: "tari-l2-node".to_string(),
+        }
+    }
+}
+
+/// Watchtower mode: hold encrypted channel state for offline clients and
+/// challenge stale closes on their behalf
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchtowerConfig {
+    /// Accept watch blobs over RPC and scan L1 for stale closes every
+    /// `tasks.watchtower_scan_interval`
+    pub enabled: bool,
+
+    /// Most blobs held at once (one per client channel)
+    pub max_blobs: usize,
+}
+
+impl Default for WatchtowerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_blobs: 10_000,
+        }
+    }
+}
+
+/// Caps on what the node signs away from a channel, for channels that
+/// have no limits of their own
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpendingConfig {
+    /// Most a single update may move out of the node's balance, in µT
+    /// (0 for no cap)
+    pub max_transfer: u64,
+
+    /// Most that may move out over any 24 hours, in µT (0 for no cap)
+    pub max_daily: u64,
+}
+
+impl Default for SpendingConfig {
+    fn default() -> Self {
+        Self {
+            max_transfer: spending::DEFAULT_LIMITS.max_transfer.unwrap_or_default(),
+            max_daily: spending::DEFAULT_LIMITS.max_daily.unwrap_or_default(),
+        }
+    }
+}
+
+impl SpendingConfig {
+    pub fn limits(&self) -> SpendingLimits {
+        SpendingLimits {
+            max_transfer: (self.max_transfer > 0).then_some(self.max_transfer),
+            max_daily: (self.max_daily > 0).then_some(self.max_daily),
+        }
+    }
+}
+
+/// Who is told about escalated disputes
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisputeConfig {
+    /// Public keys of the arbitrators notified when an unanswered refund
+    /// request is escalated
+    pub arbitrators: Vec<PublicKey>,
+}
+
+/// Cold-start catalog download
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CatalogConfig {
+    /// Public keys of peers that serve their full catalog to new nodes
+    pub archive_peers: Vec<PublicKey>,
+
+    /// Sellers requested per catalog page (at most 100)
+    pub page_size: u32,
+}
+
+impl Default for CatalogConfig {
+    fn default() -> Self {
+        Self {
+            archive_peers: Vec::new(),
+            page_size: 100,
+        }
+    }
+}
+
+/// Exchange rates served to `get_listings`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExchangeRateConfig {
+    /// Value of one Tari per ISO 4217 currency code, e.g. `USD = 0.01`
+    pub rates: HashMap<String, f64>,
+
+    /// Seconds a rate is reused before asking the provider again
+    pub cache_ttl: u64,
+}
+
+impl Default for ExchangeRateConfig {
+    fn default() -> Self {
+        Self {
+            rates: HashMap::new(),
+            cache_ttl: 300,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -39,6 +220,20 @@ pub struct RpcConfig {
 
     /// RPC listen port
     pub port: u16,
+
+    /// Keys required on RPC requests; none means the RPC is open
+    #[serde(default)]
+    pub api_keys: ApiKeys,
+
+    /// Seconds an unlocked wallet stays unlocked without use, unless the
+    /// unlock call sets its own (defaults to five minutes, at most an hour)
+    #[serde(default)]
+    pub wallet_idle_timeout: Option<u64>,
+
+    /// Origins of web pages allowed to call the node, such as
+    /// `http://localhost:8080`; browsers on any other page are refused
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
 }
 
 impl Default for NodeConfig {
@@ -50,11 +245,26 @@ impl Default for NodeConfig {
                 port: 18142,
             },
             l1: None,
+            l1_simulator: false,
             network: NetworkConfig::default(),
             rpc: RpcConfig {
                 listen_addr: "127.0.0.1".to_string(),
                 port: 18000,
+                api_keys: ApiKeys::default(),
+                wallet_idle_timeout: None,
+                allowed_origins: Vec::new(),
             },
+            channel_policy: ChannelPolicy::default(),
+            limits: ResourceLimits::default(),
+            spending: SpendingConfig::default(),
+            tasks: TaskConfig::default(),
+            exchange_rates: ExchangeRateConfig::default(),
+            catalog: CatalogConfig::default(),
+            disputes: DisputeConfig::default(),
+            alerts: AlertConfig::default(),
+            disk: DiskPolicy::default(),
+            watchtower: WatchtowerConfig::default(),
+            telemetry: TelemetryConfig::default(),
         }
     }
 }
@@ -84,10 +294,400 @@ impl NodeConfig {
         Ok(())
     }
 
-    /// Validate configuration
+    /// Validate configuration, reporting every problem found
     pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut issues = Vec::new();
+
         // Ensure data directory can be created
-        std::fs::create_dir_all(&self.data_dir)?;
+        if let Err(e) = std::fs::create_dir_all(&self.data_dir) {
+            issues.push(ConfigIssue::new(
+                "data_dir",
+                format!("cannot create {:?}: {}", self.data_dir, e),
+                "point data_dir at a writable directory",
+            ));
+        }
+
+        // RPC server
+        match self.rpc.listen_addr.parse::<IpAddr>() {
+            Err(_) => issues.push(ConfigIssue::new(
+                "rpc.listen_addr",
+                format!("'{}' is not an IP address", self.rpc.listen_addr),
+                "use an IP such as 127.0.0.1 or 0.0.0.0",
+            )),
+            // Without keys every caller gets full access, which is only
+            // safe when no other host can connect
+            Ok(ip) if !ip.is_loopback() && !self.rpc.api_keys.required() => issues.push(ConfigIssue::new(
+                "rpc.listen_addr",
+                format!("'{}' is reachable from other hosts but no API keys are configured", self.rpc.listen_addr),
+                "listen on 127.0.0.1, or add an admin key or owner under [rpc.api_keys]",
+            )),
+            Ok(_) => {}
+        }
+        if self.rpc.port == 0 {
+            issues.push(ConfigIssue::new(
+                "rpc.port",
+                "port 0 is not allowed".to_string(),
+                "choose a fixed port such as 18000",
+            ));
+        }
+        if self.rpc.wallet_idle_timeout == Some(0) {
+            issues.push(ConfigIssue::new(
+                "rpc.wallet_idle_timeout",
+                "an unlocked wallet would lock at once".to_string(),
+                "use a number of seconds such as 300, or remove it for the default",
+            ));
+        }
+        let keys = &self.rpc.api_keys;
+        if keys.admin.iter().chain(&keys.observer).any(|k| k.trim().is_empty()) {
+            issues.push(ConfigIssue::new(
+                "rpc.api_keys",
+                "empty API key".to_string(),
+                "remove it or generate a random key",
+            ));
+        }
+        if let Some(key) = keys.observer.iter().find(|k| keys.admin.contains(k)) {
+            issues.push(ConfigIssue::new(
+                "rpc.api_keys.observer",
+                format!("key '{}...' is also an admin key", key.chars().take(4).collect::<String>()),
+                "give observers their own keys",
+            ));
+        }
+        if keys.public_reads && keys.admin.is_empty() && keys.owners.is_empty() {
+            issues.push(ConfigIssue::new(
+                "rpc.api_keys.public_reads",
+                "no admin key or owner could use the wallet or channels".to_string(),
+                "add an admin key or an owner public key",
+            ));
+        }
+
+        // P2P network
+        match validate_multiaddr(&self.network.listen_addr) {
+            Ok(()) => {
+                if self.network.listen_port() == Some(self.rpc.port) {
+                    issues.push(ConfigIssue::new(
+                        "network.listen_addr",
+                        format!("P2P port {} conflicts with rpc.port", self.rpc.port),
+                        "give the RPC server and P2P listener different ports",
+                    ));
+                }
+            }
+            Err(e) => issues.push(ConfigIssue::new(
+                "network.listen_addr",
+                format!("'{}' is not a valid multiaddr: {}", self.network.listen_addr, e),
+                "use the form /ip4/0.0.0.0/tcp/9000",
+            )),
+        }
+        for (i, peer) in self.network.bootstrap_peers.iter().enumerate() {
+            if let Err(e) = validate_multiaddr(peer) {
+                issues.push(ConfigIssue::new(
+                    &format!("network.bootstrap_peers[{}]", i),
+                    format!("'{}' is not a valid multiaddr: {}", peer, e),
+                    "use the form /ip4/1.2.3.4/tcp/9000",
+                ));
+            }
+        }
+        if let Err(e) = validate_namespace(&self.network.namespace) {
+            issues.push(ConfigIssue::new(
+                "network.namespace",
+                format!("'{}' is not a valid namespace: {}", self.network.namespace, e),
+                "use a short name such as 'default' or 'staging'",
+            ));
+        }
+        if self.network.max_peers == 0 {
+            issues.push(ConfigIssue::new(
+                "network.max_peers",
+                "must be greater than 0".to_string(),
+                "set max_peers to at least 1 (default 50)",
+            ));
+        }
+
+        // L1 endpoints
+        if let Some(l1) = &self.l1 {
+            if let Err(e) = validate_endpoint(&l1.base_node_grpc) {
+                issues.push(ConfigIssue::new(
+                    "l1.base_node_grpc",
+                    e,
+                    "use the form http://127.0.0.1:18142",
+                ));
+            }
+            if let Some(wallet_grpc) = &l1.wallet_grpc {
+                if let Err(e) = validate_endpoint(wallet_grpc) {
+                    issues.push(ConfigIssue::new(
+                        "l1.wallet_grpc",
+                        e,
+                        "use the form http://127.0.0.1:18143 or remove the entry",
+                    ));
+                }
+            }
+        }
+
+        // Channel policy
+        if let Some(max) = self.channel_policy.max_collateral {
+            if max < self.channel_policy.min_collateral {
+                issues.push(ConfigIssue::new(
+                    "channel_policy.max_collateral",
+                    format!("{} is below min_collateral {}", max, self.channel_policy.min_collateral),
+                    "raise max_collateral or lower min_collateral",
+                ));
+            }
+        }
+        for (i, key) in self.channel_policy.allowed_counterparties.iter().enumerate() {
+            if hex::decode(key).map(|b| b.len() != 32).unwrap_or(true) {
+                issues.push(ConfigIssue::new(
+                    &format!("channel_policy.allowed_counterparties[{}]", i),
+                    format!("'{}' is not a 32-byte hex public key", key),
+                    "use the 64-character hex key shown by the counterparty's node",
+                ));
+            }
+        }
+
+        // Exchange rates
+        for (currency, rate) in &self.exchange_rates.rates {
+            if !rate.is_finite() || *rate <= 0.0 {
+                issues.push(ConfigIssue::new(
+                    &format!("exchange_rates.rates.{}", currency),
+                    format!("{} is not a positive rate", rate),
+                    "set the value of one Tari in this currency, or remove the entry",
+                ));
+            }
+        }
+
+        // Catalog bootstrap
+        if self.catalog.page_size == 0 || self.catalog.page_size > 100 {
+            issues.push(ConfigIssue::new(
+                "catalog.page_size",
+                format!("{} is outside 1-100", self.catalog.page_size),
+                "set page_size between 1 and 100, or remove it to use the default",
+            ));
+        }
+
+        // Alerts
+        for (i, url) in self.alerts.webhooks.iter().enumerate() {
+            if !url.starts_with("http://") || validate_endpoint(url).is_err() {
+                issues.push(ConfigIssue::new(
+                    &format!("alerts.webhooks[{}]", i),
+                    format!("'{}' is not an http:// URL", url),
+                    "use the form http://127.0.0.1:9000/alerts",
+                ));
+            }
+        }
+
+        // Telemetry
+        let endpoint = &self.telemetry.otlp_endpoint;
+        if !endpoint.is_empty() && !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+            issues.push(ConfigIssue::new(
+                "telemetry.otlp_endpoint",
+                format!("'{}' is not an http:// or https:// URL", endpoint),
+                "use the collector's gRPC endpoint, e.g. http://localhost:4317, or leave it empty",
+            ));
+        }
+
+        // Disk thresholds
+        if self.disk.warn_percent == 0 || self.disk.warn_percent > 100 {
+            issues.push(ConfigIssue::new(
+                "disk.warn_percent",
+                format!("{} is outside 1-100", self.disk.warn_percent),
+                "set warn_percent between 1 and 100",
+            ));
+        }
+        if self.disk.degraded_percent < self.disk.warn_percent || self.disk.degraded_percent > 100 {
+            issues.push(ConfigIssue::new(
+                "disk.degraded_percent",
+                format!("{} is outside {}-100", self.disk.degraded_percent, self.disk.warn_percent),
+                "set degraded_percent between warn_percent and 100",
+            ));
+        }
+
+        // Resource limits
+        if self.limits.max_message_size == 0 || self.limits.max_messages_per_sec == 0 {
+            issues.push(ConfigIssue::new(
+                "limits",
+                "max_message_size and max_messages_per_sec must be greater than 0".to_string(),
+                "remove the [limits] section to use defaults",
+            ));
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(Box::new(ConfigValidationError { issues }))
+        }
+    }
+}
+
+/// A single configuration problem with a suggested fix
+#[derive(Clone, Debug)]
+pub struct ConfigIssue {
+    pub field: String,
+    pub problem: String,
+    pub fix: String,
+}
+
+impl ConfigIssue {
+    fn new(field: &str, problem: String, fix: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            problem,
+            fix: fix.to_string(),
+        }
+    }
+}
+
+/// All problems found while validating a configuration
+#[derive(Debug)]
+pub struct ConfigValidationError {
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl fmt::Display for ConfigValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} configuration problem(s):", self.issues.len())?;
+        for issue in &self.issues {
+            write!(f, "\n  • {}: {} (fix: {})", issue.field, issue.problem, issue.fix)?;
+        }
         Ok(())
     }
 }
+
+impl std::error::Error for ConfigValidationError {}
+
+/// Check that an endpoint looks like http(s)://host:port
+fn validate_endpoint(endpoint: &str) -> Result<(), String> {
+    let rest = endpoint.strip_prefix("http://")
+        .or_else(|| endpoint.strip_prefix("https://"))
+        .ok_or_else(|| format!("'{}' must start with http:// or https://", endpoint))?;
+
+    let authority = rest.split('/').next().unwrap_or("");
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (authority, None),
+    };
+
+    if host.is_empty() {
+        return Err(format!("'{}' has no host", endpoint));
+    }
+    if let Some(port) = port {
+        if port.parse::<u16>().map(|p| p == 0).unwrap_or(true) {
+            return Err(format!("'{}' has an invalid port '{}'", endpoint, port));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_reports_all_issues() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.rpc.listen_addr = "localhost".to_string();
+        config.rpc.port = 9000;
+        config.network.bootstrap_peers = vec!["not-a-multiaddr".to_string()];
+        config.l1 = Some(L1Config {
+            base_node_grpc: "127.0.0.1:18142".to_string(),
+            wallet_grpc: None,
+            network: Default::default(),
+        });
+
+        let err = config.validate().unwrap_err();
+        let err = err.downcast_ref::<ConfigValidationError>().unwrap();
+        let fields: Vec<_> = err.issues.iter().map(|i| i.field.as_str()).collect();
+        assert_eq!(fields, vec![
+            "rpc.listen_addr",
+            "network.listen_addr",
+            "network.bootstrap_peers[0]",
+            "l1.base_node_grpc",
+        ]);
+    }
+
+    #[test]
+    fn test_observer_key_must_not_be_admin_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.rpc.api_keys.admin = vec!["admin-key".to_string()];
+        config.rpc.api_keys.observer = vec!["dashboard-key".to_string()];
+        assert!(config.validate().is_ok());
+
+        config.rpc.api_keys.observer.push("admin-key".to_string());
+        let err = config.validate().unwrap_err();
+        let err = err.downcast_ref::<ConfigValidationError>().unwrap();
+        assert_eq!(err.issues[0].field, "rpc.api_keys.observer");
+    }
+
+    #[test]
+    fn test_exposed_rpc_needs_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.rpc.listen_addr = "0.0.0.0".to_string();
+        let err = config.validate().unwrap_err();
+        let err = err.downcast_ref::<ConfigValidationError>().unwrap();
+        assert_eq!(err.issues[0].field, "rpc.listen_addr");
+
+        config.rpc.api_keys.admin = vec!["admin-key".to_string()];
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_public_reads_need_an_admin() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.rpc.api_keys.public_reads = true;
+        let err = config.validate().unwrap_err();
+        let err = err.downcast_ref::<ConfigValidationError>().unwrap();
+        assert_eq!(err.issues[0].field, "rpc.api_keys.public_reads");
+
+        config.rpc.api_keys.owners = vec![tari_l2_common::crypto::KeyPair::generate().public_key()];
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_otlp_endpoint_must_be_a_url() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.telemetry.otlp_endpoint = "localhost:4317".to_string();
+        let err = config.validate().unwrap_err();
+        let err = err.downcast_ref::<ConfigValidationError>().unwrap();
+        assert_eq!(err.issues[0].field, "telemetry.otlp_endpoint");
+
+        config.telemetry.otlp_endpoint = "http://localhost:4317".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_endpoint() {
+        assert!(validate_endpoint("http://127.0.0.1:18142").is_ok());
+        assert!(validate_endpoint("https://node.example.com").is_ok());
+        assert!(validate_endpoint("127.0.0.1:18142").is_err());
+        assert!(validate_endpoint("http://:18142").is_err());
+        assert!(validate_endpoint("http://host:notaport").is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! The node's long-lived identity keypair.
+//!
+//! Channels are opened between public keys, so a node must keep the same key
+//! across restarts. The keypair is stored under the data directory,
+//! encrypted with the passphrase in `TARI_L2_IDENTITY_PASSPHRASE` (Argon2id
+//! and ChaCha20-Poly1305, see [`KeyPair::to_encrypted_bytes`]). Without the
+//! variable the key is sealed with an empty passphrase, which only keeps it
+//! from being read by accident.
+//!
+//! The key is a Schnorr key over Ristretto, the only kind that can be
+//! aggregated into the spend key of a channel's n-of-n collateral output on
+//! L1. Nodes created with an Ed25519 key are moved to a Schnorr one at
+//! startup once none of their channels is still open (see [`migrate`]).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use tari_l2_common::{L2Error, PublicKey, SignatureScheme, Timestamp, crypto::KeyPair, error::Result};
+use tari_l2_marketplace::MarketplaceStorage;
+use tari_l2_state_channel::channel::ChannelStatus;
+
+/// File under the data directory holding the encrypted keypair
+pub const IDENTITY_FILE: &str = "identity.key";
+
+/// Environment variable holding the passphrase the keypair is encrypted with
+pub const PASSPHRASE_ENV: &str = "TARI_L2_IDENTITY_PASSPHRASE";
+
+/// Passphrase from the environment, or an empty one
+pub fn passphrase() -> String {
+    std::env::var(PASSPHRASE_ENV).unwrap_or_else(|_| {
+        warn!("{} is not set; the node identity is stored with an empty passphrase", PASSPHRASE_ENV);
+        String::new()
+    })
+}
+
+pub fn identity_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(IDENTITY_FILE)
+}
+
+/// Load the node's keypair, or generate and store one on first start
+pub fn load_or_create(data_dir: &Path, passphrase: &str) -> Result<KeyPair> {
+    let path = identity_path(data_dir);
+    if path.exists() {
+        return load(data_dir, passphrase);
+    }
+
+    let keypair = KeyPair::generate_ristretto();
+    write(&path, &keypair, passphrase)?;
+    info!("Created node identity {} at {:?}", keypair.public_key(), path);
+    Ok(keypair)
+}
+
+/// Load the node's stored keypair
+pub fn load(data_dir: &Path, passphrase: &str) -> Result<KeyPair> {
+    let path = identity_path(data_dir);
+    let bytes = fs::read(&path)
+        .map_err(|e| L2Error::Unknown(format!("Failed to read node identity {:?}: {}", path, e)))?;
+    KeyPair::from_encrypted_bytes(&bytes, passphrase)
+        .map_err(|e| L2Error::Unknown(format!("Failed to unlock node identity {:?}: {}", path, e)))
+}
+
+/// Replace the node's keypair with a fresh one. The old key is kept beside
+/// it as `identity.key.<unix time>.bak`. Returns the old and new public keys.
+pub fn rotate(data_dir: &Path, passphrase: &str) -> Result<(Option<PublicKey>, KeyPair)> {
+    let path = identity_path(data_dir);
+    let old = if path.exists() {
+        let old = load(data_dir, passphrase)?.public_key();
+        let backup = data_dir.join(format!("{}.{}.bak", IDENTITY_FILE, Timestamp::now().as_secs()));
+        fs::rename(&path, &backup)
+            .map_err(|e| L2Error::Unknown(format!("Failed to back up node identity to {:?}: {}", backup, e)))?;
+        Some(old)
+    } else {
+        None
+    };
+
+    let keypair = KeyPair::generate_ristretto();
+    write(&path, &keypair, passphrase)?;
+    Ok((old, keypair))
+}
+
+/// Channels in `storage` that are not yet closed, and so would be stranded
+/// by a new identity
+pub fn open_channels(storage: &MarketplaceStorage) -> Result<usize> {
+    let mut open = 0;
+    for channel_id in storage.list_channels()? {
+        if storage.load_channel(&channel_id)?.is_some_and(|c| c.status != ChannelStatus::Closed) {
+            open += 1;
+        }
+    }
+    Ok(open)
+}
+
+/// Replace an Ed25519 identity, which cannot take part in an L1 collateral
+/// lock, with a Schnorr one. The key is kept while `open_channels` are still
+/// open under it, since they would be stranded. Returns the key to run with.
+pub fn migrate(data_dir: &Path, passphrase: &str, keypair: KeyPair, open_channels: usize) -> Result<KeyPair> {
+    if keypair.scheme() != SignatureScheme::Ed25519 {
+        return Ok(keypair);
+    }
+    if open_channels > 0 {
+        warn!(
+            "Node identity {} is an Ed25519 key, so its channels cannot lock L1 collateral. It is replaced with a Schnorr key once its {} open channel(s) are closed",
+            keypair.public_key(), open_channels
+        );
+        return Ok(keypair);
+    }
+    let (old, keypair) = rotate(data_dir, passphrase)?;
+    info!("Replaced Ed25519 node identity {:?} with Schnorr identity {}", old, keypair.public_key());
+    Ok(keypair)
+}
+
+/// Write the encrypted keypair, readable only by the owner
+fn write(path: &Path, keypair: &KeyPair, passphrase: &str) -> Result<()> {
+    use std::io::Write;
+
+    let bytes = keypair.to_encrypted_bytes(passphrase).map_err(L2Error::Unknown)?;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)
+        .map_err(|e| L2Error::Unknown(format!("Failed to create node identity {:?}: {}", path, e)))?;
+    file.write_all(&bytes)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| L2Error::Unknown(format!("Failed to write node identity {:?}: {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_l1_client::CollateralLock;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_identity_survives_restart_and_rotates() {
+        let dir = TempDir::new().unwrap();
+        let first = load_or_create(dir.path(), "secret").unwrap();
+        let again = load_or_create(dir.path(), "secret").unwrap();
+        assert_eq!(first.public_key(), again.public_key());
+
+        // The key is not stored in the clear
+        let stored = fs::read(identity_path(dir.path())).unwrap();
+        let secret = first.to_bytes();
+        assert!(!stored.windows(32).any(|w| w == &secret[..]));
+        assert!(load(dir.path(), "wrong").is_err());
+
+        let (old, rotated) = rotate(dir.path(), "secret").unwrap();
+        assert_eq!(old, Some(first.public_key()));
+        assert_ne!(rotated.public_key(), first.public_key());
+        assert_eq!(load(dir.path(), "secret").unwrap().public_key(), rotated.public_key());
+        let backups = fs::read_dir(dir.path()).unwrap()
+            .filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().ends_with(".bak"))
+            .count();
+        assert_eq!(backups, 1);
+    }
+
+    #[test]
+    fn test_identity_can_lock_collateral() {
+        let (ours, theirs) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let participants: Vec<String> = [&ours, &theirs].iter()
+            .map(|dir| load_or_create(dir.path(), "secret").unwrap().public_key().to_string())
+            .collect();
+        assert!(CollateralLock::new("ab".repeat(32), &participants, 3600).is_ok());
+
+        // A legacy Ed25519 identity is kept while its channels are open...
+        let legacy = TempDir::new().unwrap();
+        write(&identity_path(legacy.path()), &KeyPair::generate(), "secret").unwrap();
+        let kept = migrate(legacy.path(), "secret", load(legacy.path(), "secret").unwrap(), 1).unwrap();
+        assert_eq!(kept.scheme(), SignatureScheme::Ed25519);
+
+        // ...and replaced by one that can lock collateral once they are not
+        let migrated = migrate(legacy.path(), "secret", kept, 0).unwrap();
+        assert_eq!(migrated.scheme(), SignatureScheme::Schnorr);
+        assert_eq!(load(legacy.path(), "secret").unwrap().public_key(), migrated.public_key());
+        let participants = vec![participants[1].clone(), migrated.public_key().to_string()];
+        assert!(CollateralLock::new("ab".repeat(32), &participants, 3600).is_ok());
+    }
+}
@@ -1,7 +1,14 @@
 pub mod node;
 pub mod config;
 pub mod tari_client;
+pub mod scheduler;
+pub mod tasks;
+pub mod alerts;
+pub mod identity;
+pub mod redact;
+pub mod telemetry;
 
 pub use node::L2Node;
 pub use config::NodeConfig;
 pub use tari_client::TariClient;
+pub use scheduler::{RunLog, ScheduledTask, Scheduler};
@@ -1,6 +1,5 @@
-use tari_l2_node::{L2Node, NodeConfig};
+use tari_l2_node::{L2Node, NodeConfig, telemetry};
 use tracing::{info, error};
-use tracing_subscriber;
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
@@ -31,8 +30,85 @@ enum Commands {
     Start,
     /// Show node version and info
     Version,
+    /// Audit stored channels by replaying their signed history (run with the node stopped)
+    Replay {
+        /// Channel ID (hex); all stored channels if omitted
+        #[arg(long)]
+        channel: Option<String>,
+    },
+    /// Emit the canonical SDK test vectors, or check a vector file
+    Conformance {
+        /// Write the vectors here instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Verify this vector file instead of emitting
+        #[arg(long)]
+        verify: Option<String>,
+    },
+    /// Show or rotate the node's identity keypair (run with the node stopped)
+    Identity {
+        #[command(subcommand)]
+        action: Option<IdentityAction>,
+    },
+    /// Manage the address book of counterparties (run with the node stopped)
+    Contacts {
+        #[command(subcommand)]
+        action: Option<ContactsAction>,
+    },
+    /// Back up the encrypted wallet keystore, or move a plaintext wallet into it
+    Wallet {
+        #[command(subcommand)]
+        action: WalletAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum IdentityAction {
+    /// Print the node's public key
+    Show,
+    /// Replace the keypair with a fresh one, keeping the old key as a backup
+    Rotate {
+        /// Rotate even though channels opened under the current key are
+        /// still open; they can no longer be signed for
+        #[arg(long)]
+        force: bool,
+    },
 }
 
+#[derive(Subcommand)]
+enum ContactsAction {
+    /// List every contact
+    List,
+    /// Name a public key (hex or address)
+    Add {
+        label: String,
+        public_key: String,
+        /// Tari address L1 payouts to the contact go to
+        #[arg(long)]
+        tari_address: Option<String>,
+    },
+    /// Remove a contact
+    Remove {
+        label: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WalletAction {
+    /// Copy the keystore, still encrypted, to a new file
+    Backup {
+        output: std::path::PathBuf,
+    },
+    /// Encrypt a wallet left in current_wallet.json by earlier versions into
+    /// the keystore, under the passphrase in TARI_L2_WALLET_PASSPHRASE, and
+    /// delete the plaintext file
+    Migrate,
+}
+
+/// Environment variable holding the keystore passphrase for `wallet migrate`
+const WALLET_PASSPHRASE_ENV: &str = "TARI_L2_WALLET_PASSPHRASE";
+
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
@@ -47,9 +123,18 @@ async fn main() {
         _ => tracing::Level::INFO,
     };
 
-    tracing_subscriber::fmt()
-        .with_max_level(log_level)
-        .init();
+    // Span export comes from the config file; one that does not load yet
+    // only logs, and `start` reports why it failed
+    let telemetry_config = NodeConfig::load_from_file(&cli.config)
+        .map(|config| config.telemetry)
+        .unwrap_or_default();
+    let _telemetry = match telemetry::init(log_level, &telemetry_config) {
+        Ok(guard) => guard,
+        Err(e) => {
+            eprintln!("Failed to set up telemetry: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     match &cli.command {
         Some(Commands::Init { output }) => {
@@ -67,6 +152,55 @@ async fn main() {
             println!("Version: 0.1.0");
             println!("Network: Testnet");
         }
+        Some(Commands::Replay { channel }) => {
+            if let Err(e) = replay(&cli.config, channel.as_deref()) {
+                error!("Replay failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Conformance { output, verify }) => {
+            use tari_l2_marketplace::conformance;
+
+            let result = match verify {
+                Some(path) => std::fs::read_to_string(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+                    .and_then(|vectors| conformance::verify(&vectors).map_err(|e| e.to_string()))
+                    .map(|_| println!("✅ {} matches", path)),
+                None => conformance::generate()
+                    .map_err(|e| e.to_string())
+                    .and_then(|vectors| serde_json::to_string_pretty(&vectors).map_err(|e| e.to_string()))
+                    .and_then(|json| match output {
+                        Some(path) => std::fs::write(path, json).map_err(|e| e.to_string()),
+                        None => {
+                            println!("{}", json);
+                            Ok(())
+                        }
+                    }),
+            };
+            if let Err(e) = result {
+                error!("Conformance failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Identity { action }) => {
+            if let Err(e) = identity(&cli.config, action.as_ref().unwrap_or(&IdentityAction::Show)) {
+                error!("Identity failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Contacts { action }) => {
+            if let Err(e) = contacts(&cli.config, action.as_ref().unwrap_or(&ContactsAction::List)) {
+                error!("Contacts failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Wallet { action }) => {
+            if let Err(e) = wallet(&cli.config, action) {
+                error!("Wallet failed: {}", e);
+                std::process::exit(1);
+            }
+        }
         Some(Commands::Start) | None => {
             info!("╔══════════════════════════════════════╗");
             info!("║   Tari L2 Marketplace Node v0.1.0   ║");
@@ -125,3 +259,136 @@ async fn main() {
         }
     }
 }
+
+/// Config for the commands that run with the node stopped. A missing file
+/// means defaults, as for `start`; one that does not parse is an error, so a
+/// typo cannot point the command at a different data directory.
+fn offline_config(config_path: &str) -> anyhow::Result<NodeConfig> {
+    if !std::path::Path::new(config_path).exists() {
+        return Ok(NodeConfig::default());
+    }
+    NodeConfig::load_from_file(config_path).map_err(|e| anyhow::anyhow!("failed to load {}: {}", config_path, e))
+}
+
+/// Replay channels straight from the node's database. Exits non-zero if any diverge.
+fn replay(config_path: &str, channel: Option<&str>) -> anyhow::Result<()> {
+    use tari_l2_common::Hash;
+    use tari_l2_marketplace::MarketplaceStorage;
+
+    let config = offline_config(config_path)?;
+    let storage = MarketplaceStorage::open(&config.data_dir)
+        .map_err(|e| anyhow::anyhow!("failed to open {:?} (is the node still running?): {}", config.data_dir, e))?;
+
+    let channel_ids = match channel {
+        Some(id) => vec![id.parse::<Hash>().map_err(|e| anyhow::anyhow!("invalid channel id: {}", e))?],
+        None => storage.list_channels()?,
+    };
+
+    let mut diverged = 0;
+    for channel_id in channel_ids {
+        let channel = storage.load_channel(&channel_id)?
+            .ok_or_else(|| anyhow::anyhow!("channel {} not found", channel_id))?;
+        let report = channel.replay();
+        match &report.divergence {
+            None => println!("✅ {} consistent ({} updates)", channel_id, report.replayed),
+            Some(divergence) => {
+                diverged += 1;
+                println!("❌ {} diverges after {} updates: {}", channel_id, report.replayed, serde_json::to_string(divergence)?);
+            }
+        }
+    }
+    anyhow::ensure!(diverged == 0, "{} channel(s) diverge", diverged);
+    Ok(())
+}
+
+/// Show or rotate the identity stored under the node's data directory
+fn identity(config_path: &str, action: &IdentityAction) -> anyhow::Result<()> {
+    use tari_l2_marketplace::MarketplaceStorage;
+    use tari_l2_node::identity;
+
+    let config = offline_config(config_path)?;
+    let passphrase = identity::passphrase();
+
+    match action {
+        IdentityAction::Show => {
+            let keypair = identity::load(&config.data_dir, &passphrase)?;
+            println!("{}", keypair.public_key());
+        }
+        IdentityAction::Rotate { force } => {
+            if !force && identity::identity_path(&config.data_dir).exists() {
+                let storage = MarketplaceStorage::open(&config.data_dir)
+                    .map_err(|e| anyhow::anyhow!("failed to open {:?} (is the node still running?): {}", config.data_dir, e))?;
+                let open = identity::open_channels(&storage)?;
+                anyhow::ensure!(
+                    open == 0,
+                    "{} channel(s) are still open under the current key; close them first or pass --force",
+                    open
+                );
+            }
+            let (old, keypair) = identity::rotate(&config.data_dir, &passphrase)?;
+            if let Some(old) = old {
+                println!("Old identity: {}", old);
+            }
+            println!("New identity: {}", keypair.public_key());
+        }
+    }
+    Ok(())
+}
+
+/// Back up or migrate the wallet keystore under the node's data directory
+fn wallet(config_path: &str, action: &WalletAction) -> anyhow::Result<()> {
+    use tari_l2_rpc::session;
+
+    let config = offline_config(config_path)?;
+    match action {
+        WalletAction::Backup { output } => {
+            session::backup_keystore(&config.data_dir, output).map_err(|e| anyhow::anyhow!(e))?;
+            println!("Keystore copied to {}; it opens with the same passphrase", output.display());
+        }
+        WalletAction::Migrate => {
+            let passphrase = std::env::var(WALLET_PASSPHRASE_ENV)
+                .map_err(|_| anyhow::anyhow!("set {} to the passphrase to encrypt the wallet with", WALLET_PASSPHRASE_ENV))?;
+            match session::migrate_legacy_wallet(&config.data_dir, &passphrase).map_err(|e| anyhow::anyhow!(e))? {
+                Some(address) => println!("Moved wallet {} into the keystore", address),
+                None => println!("No {} to migrate", session::LEGACY_WALLET_FILE),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Edit the address book stored in the node's database
+fn contacts(config_path: &str, action: &ContactsAction) -> anyhow::Result<()> {
+    use tari_l2_common::{PublicKey, Timestamp};
+    use tari_l2_marketplace::{Contact, MarketplaceStorage};
+    use tari_l2_marketplace::contacts::normalize_label;
+
+    let config = offline_config(config_path)?;
+    let storage = MarketplaceStorage::open(&config.data_dir)
+        .map_err(|e| anyhow::anyhow!("failed to open {:?} (is the node still running?): {}", config.data_dir, e))?;
+
+    match action {
+        ContactsAction::List => {
+            for contact in storage.load_contacts()? {
+                match &contact.tari_address {
+                    Some(address) => println!("{}\t{}\t{}", contact.label, contact.public_key, address),
+                    None => println!("{}\t{}", contact.label, contact.public_key),
+                }
+            }
+        }
+        ContactsAction::Add { label, public_key, tari_address } => {
+            let public_key: PublicKey = public_key.parse().map_err(|e| anyhow::anyhow!("invalid public key: {}", e))?;
+            let contact = Contact::new(label, public_key, tari_address.clone(), Timestamp::now())
+                .map_err(|e| anyhow::anyhow!(e))?;
+            anyhow::ensure!(storage.load_contact(&contact.label)?.is_none(), "contact {} already exists", contact.label);
+            storage.store_contact(&contact)?;
+            println!("Added {}", contact.label);
+        }
+        ContactsAction::Remove { label } => {
+            let label = normalize_label(label).map_err(|e| anyhow::anyhow!(e))?;
+            anyhow::ensure!(storage.delete_contact(&label)?, "no contact named {}", label);
+            println!("Removed {}", label);
+        }
+    }
+    Ok(())
+}
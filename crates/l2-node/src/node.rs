@@ -1,12 +1,22 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
 use tokio::signal;
-use tracing::{info, error};
-use tari_l2_common::{crypto::KeyPair, error::Result};
-use tari_l2_marketplace::{MarketplaceManager, MarketplaceStorage};
-use tari_l2_p2p::{P2PNetwork, MessageHandler};
+use tracing::{field, info, info_span, error, warn, Instrument};
+use tari_l2_common::{Timestamp, clock::SystemClock, crypto::KeyPair, error::Result};
+use tari_l2_marketplace::{CachedRates, CatalogRoot, ChannelRequest, CheckpointPolicy, MarketplaceManager, MarketplaceStorage, StaticRates};
+use tari_l2_p2p::{Capability, P2PNetwork, MessageHandler};
 use tari_l2_rpc::{RpcApi, RpcServer};
+use crate::alerts::{spawn_alert_sinks, spawn_saved_search_webhooks};
 use crate::config::NodeConfig;
+use crate::identity;
 use crate::tari_client::TariClient;
+use crate::scheduler::Scheduler;
+use crate::tasks::{
+    AlertMonitorTask, CatalogBootstrapTask, CatalogRootTask, ChannelCloseTask, CheckpointTask, DiskMonitorTask, EscrowTimeoutTask,
+    HibernationTask, L1ReconcileTask, ListingExpiryTask, OrderExpiryTask, OutboxFlushTask, PeerMaintenanceTask,
+    PendingUpdateExpiryTask, RefundEscalationTask, WatchtowerTask,
+};
 use tari_l2_l1_client::{TariL1Client, L1Config, TariNetwork};
 use async_trait::async_trait;
 use tari_l2_common::{PublicKey, L2Error};
@@ -17,6 +27,7 @@ pub struct L2Node {
     config: NodeConfig,
     keypair: Arc<KeyPair>,
     marketplace: Arc<MarketplaceManager>,
+    storage: Arc<MarketplaceStorage>,
     network: Arc<P2PNetwork>,
     tari_client: Arc<TariClient>,
     l1_client: Arc<TariL1Client>,
@@ -31,16 +42,22 @@ impl L2Node {
         std::fs::create_dir_all(&config.data_dir)
             .map_err(|e| L2Error::Unknown(format!("Failed to create data directory: {}", e)))?;
 
-        // Initialize components
-        let keypair = Arc::new(KeyPair::generate());
-        info!("Node public key: {}", keypair.public_key());
+        // Load the node's identity, creating it on first start
+        let passphrase = identity::passphrase();
+        let keypair = identity::load_or_create(&config.data_dir, &passphrase)?;
 
         // Initialize storage
         let storage = Arc::new(
             MarketplaceStorage::open(&config.data_dir)
                 .map_err(|e| L2Error::DatabaseError(e.to_string()))?
+                .with_limits(config.limits.clone())
         );
 
+        // Move a legacy Ed25519 identity to a Schnorr key that can lock collateral
+        let open_channels = identity::open_channels(&storage)?;
+        let keypair = Arc::new(identity::migrate(&config.data_dir, &passphrase, keypair, open_channels)?);
+        info!("Node public key: {}", keypair.public_key());
+
         // Initialize L1 client
         let l1_config = config.l1.clone().unwrap_or_else(|| {
             info!("L1 config not found in config file, using tari_node address");
@@ -52,10 +69,16 @@ impl L2Node {
         });
         info!("L1 client configuration: base_node_grpc={}, wallet_grpc={:?}, network={:?}",
             l1_config.base_node_grpc, l1_config.wallet_grpc, l1_config.network);
-        let l1_client = Arc::new(
-            TariL1Client::new(l1_config).await
-                .map_err(|e| L2Error::Unknown(format!("Failed to create L1 client: {}", e)))?
-        );
+        let tari_network = l1_config.network_name().to_string();
+        let l1_client = if config.l1_simulator {
+            info!("🧪 Using the L1 simulator");
+            Arc::new(TariL1Client::offline(l1_config))
+        } else {
+            Arc::new(
+                TariL1Client::new(l1_config).await
+                    .map_err(|e| L2Error::Unknown(format!("Failed to create L1 client: {}", e)))?
+            )
+        };
 
         // Check L1 connection and log status
         if l1_client.is_connected().await {
@@ -65,16 +88,61 @@ impl L2Node {
         }
 
         // Initialize marketplace manager with L1 client
-        let marketplace = Arc::new(MarketplaceManager::new(storage, keypair.clone(), Some(l1_client.clone())));
+        let marketplace = Arc::new(MarketplaceManager::new(storage.clone(), keypair.clone(), Some(l1_client.clone())));
+        marketplace.set_channel_policy(config.channel_policy.clone()).await;
+        marketplace.set_limits(config.limits.clone()).await;
+        marketplace.set_default_spending_limits(config.spending.limits()).await;
+        marketplace.set_arbitrators(config.disputes.arbitrators.clone()).await;
+        marketplace.set_watchtower(config.watchtower.enabled.then_some(config.watchtower.max_blobs)).await;
+        marketplace.alerts().set_policy(config.alerts.policy());
+        if !config.exchange_rates.rates.is_empty() {
+            let rates = StaticRates::new(config.exchange_rates.rates.clone());
+            let cached = CachedRates::new(Box::new(rates), config.exchange_rates.cache_ttl, Arc::new(SystemClock));
+            marketplace.set_exchange_rate_provider(Arc::new(cached)).await;
+        }
 
-        // Load existing channels
+        // Load existing channels, leaving idle ones on disk
+        let hibernate_after = config.tasks.channel_hibernate_after;
+        marketplace.set_hibernate_after((hibernate_after > 0).then_some(hibernate_after)).await;
         marketplace.load_channels().await?;
+        let every_updates = config.tasks.checkpoint_every_updates;
+        marketplace.set_checkpoint_policy(CheckpointPolicy {
+            interval: config.tasks.checkpoint_interval,
+            every_updates: (every_updates > 0).then_some(every_updates),
+        }).await;
+
+        // Replay any state updates that were in flight when the node stopped
+        let recovered = marketplace.recover_journal().await?;
+        if recovered > 0 {
+            info!("Recovered {} in-flight state updates from journal", recovered);
+        }
 
-        // Load existing listings from storage
+        // Load existing listings, orders and escrows from storage
         marketplace.load_listings().await?;
+        marketplace.load_orders().await?;
+        marketplace.load_escrows().await?;
 
-        // Initialize P2P network
-        let network = Arc::new(P2PNetwork::new(config.network.clone()));
+        // A node starting with an empty catalog downloads it from archive peers
+        marketplace.configure_archive_peers(
+            config.catalog.archive_peers.clone(),
+            config.catalog.page_size,
+        ).await;
+
+        // Initialize P2P network, advertising what this node offers peers
+        let mut capabilities = Vec::new();
+        if config.channel_policy.max_open_channels != Some(0) {
+            capabilities.push(Capability::AcceptsChannelOpens);
+        }
+        if config.watchtower.enabled {
+            capabilities.push(Capability::Watchtower);
+        }
+        let network = Arc::new(
+            P2PNetwork::new(config.network.clone())
+                .with_limits(config.limits.clone())
+                .with_tari_network(&tari_network)
+                .with_advert(keypair.clone(), capabilities)
+                .with_known_peers(storage.load_known_peers()?)
+        );
 
         // Initialize Tari client
         let tari_client = Arc::new(TariClient::new(
@@ -86,6 +154,7 @@ impl L2Node {
             config,
             keypair,
             marketplace,
+            storage,
             network,
             tari_client,
             l1_client,
@@ -96,6 +165,10 @@ impl L2Node {
     pub async fn start(&self) -> Result<()> {
         info!("Starting L2 node");
 
+        // Pass alerts on to the log and webhooks
+        spawn_alert_sinks(&self.marketplace.alerts(), self.config.alerts.sinks()?);
+        spawn_saved_search_webhooks(self.marketplace.clone(), self.config.alerts.private_search_webhooks);
+
         // Connect to Tari L1
         self.tari_client.connect().await?;
 
@@ -121,9 +194,24 @@ impl L2Node {
             .parse()
             .map_err(|e| L2Error::InvalidParameter(format!("Invalid RPC address: {}", e)))?;
 
-        let l1_connected = Arc::new(std::sync::atomic::AtomicBool::new(self.l1_client.is_connected().await));
-        let api = Arc::new(RpcApi::new_with_l1(self.marketplace.clone(), self.l1_client.clone(), l1_connected));
-        let rpc_server = RpcServer::new(api, rpc_addr);
+        if self.config.data_dir.join(tari_l2_rpc::session::LEGACY_WALLET_FILE).exists() {
+            warn!(
+                "{} under {:?} holds a wallet in the clear; run `tari-l2-node wallet migrate` to encrypt it into the keystore",
+                tari_l2_rpc::session::LEGACY_WALLET_FILE, self.config.data_dir
+            );
+        }
+        let l1_connected = Arc::new(AtomicBool::new(self.l1_client.is_connected().await));
+        let wallet_idle_timeout = self.config.rpc.wallet_idle_timeout.unwrap_or(tari_l2_rpc::session::DEFAULT_IDLE_TIMEOUT_SECS);
+        let api = Arc::new(
+            RpcApi::new_with_l1(self.marketplace.clone(), self.l1_client.clone(), l1_connected.clone())
+                .with_wallet_session(&self.config.data_dir, Duration::from_secs(wallet_idle_timeout))
+        );
+        let rpc_server = RpcServer::new(api, rpc_addr)
+            .with_api_keys(self.config.rpc.api_keys.clone())
+            .with_allowed_origins(self.config.rpc.allowed_origins.clone());
+        if !self.config.rpc.api_keys.required() {
+            warn!("RPC has no API keys configured; anyone who can reach {} has full access", rpc_addr);
+        }
 
         tokio::spawn(async move {
             if let Err(e) = rpc_server.start().await {
@@ -131,15 +219,127 @@ impl L2Node {
             }
         });
 
+        // Start periodic background tasks
+        let scheduler = self.start_background_tasks(l1_connected);
+
         info!("L2 node started successfully");
         info!("RPC server listening on {}", rpc_addr);
 
         // Wait for shutdown signal
         self.wait_for_shutdown().await;
+        scheduler.shutdown().await;
+
+        // Remember who we were connected to for the next start
+        self.storage.store_known_peers(&self.network.known_peers())?;
 
         Ok(())
     }
 
+    fn start_background_tasks(&self, l1_connected: Arc<AtomicBool>) -> Scheduler {
+        let tasks = &self.config.tasks;
+        let jitter = Duration::from_secs(tasks.jitter);
+        let mut scheduler = Scheduler::new().with_run_log(self.storage.clone());
+
+        scheduler.schedule(
+            Arc::new(EscrowTimeoutTask { marketplace: self.marketplace.clone() }),
+            Duration::from_secs(tasks.escrow_timeout_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(ListingExpiryTask { marketplace: self.marketplace.clone(), ttl: tasks.listing_ttl }),
+            Duration::from_secs(tasks.listing_expiry_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(OrderExpiryTask { marketplace: self.marketplace.clone(), deadline: tasks.order_funding_deadline }),
+            Duration::from_secs(tasks.order_expiry_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(PendingUpdateExpiryTask { marketplace: self.marketplace.clone(), ttl: tasks.pending_update_ttl }),
+            Duration::from_secs(tasks.pending_update_expiry_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(RefundEscalationTask { marketplace: self.marketplace.clone(), deadline: tasks.refund_response_deadline }),
+            Duration::from_secs(tasks.refund_escalation_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(ChannelCloseTask { marketplace: self.marketplace.clone() }),
+            Duration::from_secs(tasks.channel_close_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(CatalogBootstrapTask { marketplace: self.marketplace.clone() }),
+            Duration::from_secs(tasks.catalog_bootstrap_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(OutboxFlushTask { marketplace: self.marketplace.clone() }),
+            Duration::from_secs(tasks.outbox_flush_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(CatalogRootTask { marketplace: self.marketplace.clone() }),
+            Duration::from_secs(tasks.catalog_root_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(HibernationTask { marketplace: self.marketplace.clone() }),
+            Duration::from_secs(tasks.hibernation_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(CheckpointTask { marketplace: self.marketplace.clone() }),
+            Duration::from_secs(tasks.checkpoint_poll_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(DiskMonitorTask {
+                marketplace: self.marketplace.clone(),
+                data_dir: self.config.data_dir.clone(),
+                policy: self.config.disk.clone(),
+            }),
+            Duration::from_secs(tasks.disk_check_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(AlertMonitorTask {
+                marketplace: self.marketplace.clone(),
+                l1_connected: (!self.config.l1_simulator).then(|| l1_connected.clone()),
+                config: self.config.alerts.clone(),
+                l1_down_since: Mutex::new(None),
+            }),
+            Duration::from_secs(tasks.alert_check_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(L1ReconcileTask { l1_client: self.l1_client.clone(), l1_connected }),
+            Duration::from_secs(tasks.l1_reconcile_interval),
+            jitter,
+        );
+        scheduler.schedule(
+            Arc::new(PeerMaintenanceTask {
+                marketplace: self.marketplace.clone(),
+                network: self.network.clone(),
+                storage: self.storage.clone(),
+            }),
+            Duration::from_secs(tasks.peer_maintenance_interval),
+            jitter,
+        );
+        if self.config.watchtower.enabled {
+            scheduler.schedule(
+                Arc::new(WatchtowerTask { marketplace: self.marketplace.clone() }),
+                Duration::from_secs(tasks.watchtower_scan_interval),
+                jitter,
+            );
+        }
+
+        info!("Started {} background tasks", scheduler.len());
+        scheduler
+    }
+
     async fn wait_for_shutdown(&self) {
         match signal::ctrl_c().await {
             Ok(()) => {
@@ -168,6 +368,26 @@ impl MessageHandler for NodeMessageHandler {
         from: PublicKey,
         message: L2Message,
     ) -> Result<Option<L2Message>> {
+        // Tagged with the channel and order so a trace links it to the RPC
+        // calls and L1 transactions for the same order
+        let span = info_span!("p2p", message = ?message.message_type(), from = %from, channel_id = field::Empty, order_id = field::Empty);
+        if let Some(channel_id) = message.channel_id() {
+            span.record("channel_id", field::display(channel_id));
+        }
+        if let Some(order_id) = message.order_id() {
+            span.record("order_id", field::display(order_id));
+        }
+
+        let result = self.dispatch(from, message).instrument(span).await;
+        if let Err(L2Error::InvalidSignature) = &result {
+            self.marketplace.alerts().note_invalid_signature(from, Timestamp::now());
+        }
+        result
+    }
+}
+
+impl NodeMessageHandler {
+    async fn dispatch(&self, from: PublicKey, message: L2Message) -> Result<Option<L2Message>> {
         info!("Handling message from {:?}: {:?}", from, message.message_type());
 
         match message {
@@ -175,18 +395,49 @@ impl MessageHandler for NodeMessageHandler {
                 Ok(Some(L2Message::Pong))
             }
             L2Message::StateUpdateProposal { channel_id, update } => {
-                // Apply the state update
-                match self.marketplace.apply_state_update(&channel_id, update).await {
-                    Ok(()) => {
-                        info!("Applied state update for channel {:?}", channel_id);
-                        Ok(None)
-                    }
+                // Co-sign and ack, or commit if already fully signed
+                match self.marketplace.receive_state_update(&channel_id, update).await {
+                    Ok(()) => Ok(None),
                     Err(e) => {
-                        error!("Failed to apply state update: {}", e);
+                        error!("Rejected state update proposal: {}", e);
                         Err(e)
                     }
                 }
             }
+            L2Message::StateUpdateAck { channel_id, nonce, signature } => {
+                if let Some(update) = self.marketplace.handle_state_update_ack(&channel_id, nonce, signature).await? {
+                    info!("Committed state update {} for channel {:?}", update.nonce, channel_id);
+                }
+                Ok(None)
+            }
+            L2Message::StateUpdateGapRequest { channel_id, from_nonce, to_nonce } => {
+                self.marketplace.handle_gap_request(&from, &channel_id, from_nonce, to_nonce).await
+            }
+            L2Message::StateUpdateGapFill { channel_id, updates } => {
+                self.marketplace.handle_gap_fill(&channel_id, updates).await?;
+                Ok(None)
+            }
+            L2Message::ChannelOpenRequest { participants, initiator, initial_balances, challenge_period, signature } => {
+                // Directed sends may still reach every peer, so ignore requests not meant for us
+                if !participants.contains(&self.marketplace.public_key()) {
+                    return Ok(None);
+                }
+                let request = ChannelRequest::new(initiator, participants, initial_balances, challenge_period)
+                    .with_signature(signature);
+                match self.marketplace.handle_channel_request(request).await? {
+                    Some(channel_id) => info!("Opened channel {:?} for {:?}", channel_id, initiator),
+                    None => info!("Channel request from {:?} awaiting approval", initiator),
+                }
+                Ok(None)
+            }
+            L2Message::ChannelOpenResponse { channel_id, accepted, signature } => {
+                if let Some(channel_id) = self.marketplace
+                    .handle_channel_open_response(&from, &channel_id, accepted, signature).await?
+                {
+                    info!("Opened channel {:?}", channel_id);
+                }
+                Ok(None)
+            }
             L2Message::ChannelInfoRequest { channel_id } => {
                 match self.marketplace.get_channel_info(&channel_id).await {
                     Ok(info) => {
@@ -210,20 +461,54 @@ impl MessageHandler for NodeMessageHandler {
                     }
                 }
             }
+            L2Message::ListingUpdated { listing, signature, timestamp, update_signature } => {
+                if let Err(e) = self.marketplace.handle_listing_update(listing, signature, timestamp, update_signature).await {
+                    warn!("Ignoring listing update: {}", e);
+                }
+                Ok(None)
+            }
             L2Message::ListingsRequest => {
-                let listings = self.marketplace.list_all_listings().await;
-                let listings_only: Vec<_> = listings.into_iter().map(|(_, listing)| listing).collect();
-                Ok(Some(L2Message::ListingsResponse { listings: listings_only }))
+                let listings = self.marketplace.signed_listings().await?;
+                Ok(Some(L2Message::ListingsResponse { listings }))
             }
             L2Message::ListingsResponse { listings } => {
                 info!("📦 Received {} listings from peer", listings.len());
-                for listing in listings {
-                    // Process each listing - using dummy signature/timestamp since these are responses
-                    let dummy_sig = tari_l2_common::Signature::new([0u8; 64]);
-                    if let Err(e) = self.marketplace.handle_received_listing(listing, dummy_sig, 0).await {
-                        error!("Failed to process listing: {}", e);
+                match self.marketplace.import_signed_listings(listings).await {
+                    Ok(imported) => info!("Imported {} new listings", imported),
+                    Err(e) => error!("Failed to process listings: {}", e),
+                }
+                Ok(None)
+            }
+            L2Message::CatalogPageRequest { offset, limit } => {
+                let page = self.marketplace.catalog_page(offset, limit).await?;
+                Ok(Some(L2Message::CatalogPageResponse { page }))
+            }
+            L2Message::CatalogPageResponse { page } => {
+                match self.marketplace.handle_catalog_page(&from, page).await {
+                    Ok(next) => Ok(next),
+                    Err(e) => {
+                        warn!("Ignoring catalog page: {}", e);
+                        Ok(None)
                     }
                 }
+            }
+            L2Message::CatalogRoot { seller, root, listings, timestamp, signature } => {
+                let root = CatalogRoot { seller, root, listings, timestamp, signature };
+                if let Err(e) = self.marketplace.handle_catalog_root(root).await {
+                    warn!("Ignoring catalog root from {:?}: {}", seller, e);
+                }
+                Ok(None)
+            }
+            L2Message::OrderExpired { channel_id, order_id } => {
+                info!("⌛ Order {:?} in channel {:?} was cancelled by the seller: not funded in time", order_id, channel_id);
+                Ok(None)
+            }
+            L2Message::DisputeEscalated { escrow_id, order_id, reason, .. } => {
+                warn!("⚖️  Arbitration requested for escrow {:?} (order {:?}): {}", escrow_id, order_id, reason);
+                Ok(None)
+            }
+            L2Message::Error { code, category, message } => {
+                warn!("Peer reported error {} ({}): {}", code, category, message);
                 Ok(None)
             }
             _ => {
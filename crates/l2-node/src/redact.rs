@@ -0,0 +1,149 @@
+//! Scrubbing secrets from log output.
+//!
+//! [`Redacting`] wraps the log writer and rewrites each line before it is
+//! written: the value of any field named in [`SECRET_FIELDS`], whether
+//! logged as a tracing field (`seed_phrase="..."`) or inside JSON
+//! (`"private_key": "..."`), becomes `[redacted]`. The RPC server never
+//! logs these on purpose; this catches them if a request or response ends
+//! up in a log line anyway.
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Field names whose values are never written to the log
+pub const SECRET_FIELDS: &[&str] = &["seed_phrase", "private_key", "spend_key", "passphrase", "mnemonic"];
+
+const REDACTED: &str = "[redacted]";
+
+/// Replace the value of every secret field in `text`
+pub fn redact(text: &str) -> Cow<'_, str> {
+    if !SECRET_FIELDS.iter().any(|field| text.contains(field)) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, field)) = SECRET_FIELDS.iter().filter_map(|f| rest.find(f).map(|i| (i, *f))).min() {
+        let (head, tail) = rest.split_at(start + field.len());
+        out.push_str(head);
+        rest = tail;
+        let Some(separator) = separator_len(rest) else {
+            continue;
+        };
+        out.push_str(&rest[..separator]);
+        rest = &rest[separator..];
+        rest = push_redacted_value(&mut out, rest);
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Length of what joins a field name to its value: a closing quote, if the
+/// name was quoted, then `:` or `=` and any spaces
+fn separator_len(text: &str) -> Option<usize> {
+    let unquoted = text.strip_prefix("\\\"").or_else(|| text.strip_prefix('"')).unwrap_or(text);
+    let unspaced = unquoted.trim_start();
+    let value = unspaced.strip_prefix(':').or_else(|| unspaced.strip_prefix('='))?.trim_start();
+    Some(text.len() - value.len())
+}
+
+/// Write `[redacted]` in place of the value at the start of `text`, keeping
+/// its quotes, and return what follows the value
+fn push_redacted_value<'a>(out: &mut String, text: &'a str) -> &'a str {
+    for quote in ["\\\"", "\""] {
+        if let Some(inner) = text.strip_prefix(quote) {
+            out.push_str(quote);
+            out.push_str(REDACTED);
+            return match inner.find(quote) {
+                Some(end) => {
+                    out.push_str(quote);
+                    &inner[end + quote.len()..]
+                }
+                None => "",
+            };
+        }
+    }
+    let end = text.find(|c: char| c.is_whitespace() || matches!(c, ',' | '}' | ')')).unwrap_or(text.len());
+    if end > 0 {
+        out.push_str(REDACTED);
+    }
+    &text[end..]
+}
+
+/// Makes writers that redact each log line before passing it to `M`'s
+pub struct Redacting<M>(pub M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter { inner: self.0.make_writer(), buffer: Vec::new() }
+    }
+}
+
+/// Collects one log event and writes it, redacted, when flushed or dropped
+pub struct RedactingWriter<W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> RedactingWriter<W> {
+    fn write_buffer(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(&self.buffer);
+        self.inner.write_all(redact(&text).as_bytes())?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_buffer()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for RedactingWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.write_buffer();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_fields_are_redacted() {
+        assert_eq!(
+            redact(r#"wallet created seed_phrase="gate federal tree" public_key=ab12"#),
+            r#"wallet created seed_phrase="[redacted]" public_key=ab12"#,
+        );
+        assert_eq!(
+            redact(r#"{"private_key": "26033a0e", "address": "x"}"#),
+            r#"{"private_key": "[redacted]", "address": "x"}"#,
+        );
+        assert_eq!(redact(r#"params={\"passphrase\":\"hunter2\"}"#), r#"params={\"passphrase\":\"[redacted]\"}"#);
+        assert_eq!(redact("spend_key=deadbeef, ok"), "spend_key=[redacted], ok");
+
+        // Names without a value, and lines without secrets, are left alone
+        assert_eq!(redact("call export_private_key first"), "call export_private_key first");
+        assert!(matches!(redact("listing published"), Cow::Borrowed(_)));
+
+        let mut written = Vec::new();
+        {
+            let mut writer = RedactingWriter { inner: &mut written, buffer: Vec::new() };
+            writer.write_all(b"mnemonic=\"a b c\"\n").unwrap();
+        }
+        assert_eq!(written, b"mnemonic=\"[redacted]\"\n");
+    }
+}
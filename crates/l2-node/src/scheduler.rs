@@ -0,0 +1,219 @@
+use async_trait::async_trait;
+use rand::Rng;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use tari_l2_common::{Timestamp, error::Result};
+use tari_l2_marketplace::MarketplaceStorage;
+
+/// A unit of periodic background work
+#[async_trait]
+pub trait ScheduledTask: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Run one iteration of the task
+    async fn run(&self) -> Result<()>;
+}
+
+/// Where the scheduler records when each task last ran, so a restart
+/// does not start every interval over
+pub trait RunLog: Send + Sync {
+    /// When `task` last ran, in seconds since the epoch
+    fn last_run(&self, task: &str) -> Option<u64>;
+
+    fn record_run(&self, task: &str, at: u64);
+}
+
+impl RunLog for MarketplaceStorage {
+    fn last_run(&self, task: &str) -> Option<u64> {
+        self.load_task_run(task).unwrap_or_else(|e| {
+            warn!("Failed to read last run of task '{}': {}", task, e);
+            None
+        })
+    }
+
+    fn record_run(&self, task: &str, at: u64) {
+        if let Err(e) = self.store_task_run(task, at) {
+            warn!("Failed to record run of task '{}': {}", task, e);
+        }
+    }
+}
+
+/// Runs scheduled tasks on fixed intervals until shutdown
+pub struct Scheduler {
+    shutdown_tx: watch::Sender<bool>,
+    handles: Vec<JoinHandle<()>>,
+    run_log: Option<Arc<dyn RunLog>>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        let (shutdown_tx, _) = watch::channel(false);
+        Self {
+            shutdown_tx,
+            handles: Vec::new(),
+            run_log: None,
+        }
+    }
+
+    /// Record task runs in `log`, and time each task's first run from its
+    /// last recorded one
+    pub fn with_run_log(mut self, log: Arc<dyn RunLog>) -> Self {
+        self.run_log = Some(log);
+        self
+    }
+
+    /// Run a task every `interval`, delaying each tick by up to `jitter`.
+    /// With a run log, a task that last ran less than `interval` ago first
+    /// runs when the rest of the interval has passed, and an overdue one at
+    /// once.
+    pub fn schedule(&mut self, task: Arc<dyn ScheduledTask>, interval: Duration, jitter: Duration) {
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        let run_log = self.run_log.clone();
+        let mut next = match run_log.as_ref().and_then(|log| log.last_run(task.name())) {
+            Some(last) => {
+                let elapsed = Timestamp::now().as_secs().saturating_sub(last);
+                interval.saturating_sub(Duration::from_secs(elapsed))
+            }
+            None => interval,
+        };
+
+        let handle = tokio::spawn(async move {
+            info!("⏱️  Scheduled task '{}' every {:?}", task.name(), interval);
+
+            loop {
+                let delay = next + random_jitter(jitter);
+                next = interval;
+
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {
+                        debug!("Running task '{}'", task.name());
+                        if let Err(e) = task.run().await {
+                            warn!("Task '{}' failed: {}", task.name(), e);
+                        }
+                        if let Some(log) = &run_log {
+                            log.record_run(task.name(), Timestamp::now().as_secs());
+                        }
+                    }
+                    _ = shutdown_rx.changed() => {
+                        debug!("Task '{}' stopping", task.name());
+                        break;
+                    }
+                }
+            }
+        });
+
+        self.handles.push(handle);
+    }
+
+    /// Number of scheduled tasks
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Whether no tasks are scheduled
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Signal all tasks to stop and wait for in-flight runs to finish
+    pub async fn shutdown(self) {
+        let _ = self.shutdown_tx.send(true);
+        for handle in self.handles {
+            let _ = handle.await;
+        }
+        info!("Background tasks stopped");
+    }
+}
+
+fn random_jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    let millis = rand::thread_rng().gen_range(0..=max.as_millis() as u64);
+    Duration::from_millis(millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingTask {
+        runs: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ScheduledTask for CountingTask {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        async fn run(&self) -> Result<()> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_runs_and_stops() {
+        let task = Arc::new(CountingTask { runs: AtomicUsize::new(0) });
+        let mut scheduler = Scheduler::new();
+        scheduler.schedule(task.clone(), Duration::from_millis(10), Duration::ZERO);
+        assert_eq!(scheduler.len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        scheduler.shutdown().await;
+
+        let runs = task.runs.load(Ordering::SeqCst);
+        assert!(runs > 0);
+
+        // No further runs after shutdown
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(task.runs.load(Ordering::SeqCst), runs);
+    }
+
+    #[tokio::test]
+    async fn test_intervals_carry_over_a_restart() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(dir.path()).unwrap());
+        let now = Timestamp::now().as_secs();
+        storage.record_run("counting", now - 3600);
+        storage.record_run("recent", now);
+
+        // An hourly task that last ran an hour ago is due at once
+        let overdue = Arc::new(CountingTask { runs: AtomicUsize::new(0) });
+        let mut scheduler = Scheduler::new().with_run_log(storage.clone());
+        scheduler.schedule(overdue.clone(), Duration::from_secs(3600), Duration::ZERO);
+
+        // One that just ran waits out the rest of its interval
+        struct RecentTask(AtomicUsize);
+        #[async_trait]
+        impl ScheduledTask for RecentTask {
+            fn name(&self) -> &'static str {
+                "recent"
+            }
+            async fn run(&self) -> Result<()> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+        let recent = Arc::new(RecentTask(AtomicUsize::new(0)));
+        scheduler.schedule(recent.clone(), Duration::from_secs(3600), Duration::ZERO);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        scheduler.shutdown().await;
+        assert_eq!(overdue.runs.load(Ordering::SeqCst), 1);
+        assert_eq!(recent.0.load(Ordering::SeqCst), 0);
+        assert!(storage.last_run("counting").unwrap() >= now);
+    }
+}
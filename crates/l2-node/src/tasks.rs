@@ -0,0 +1,504 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+use tari_l2_common::{L2Error, Timestamp, error::Result};
+use tari_l2_l1_client::TariL1Client;
+use tari_l2_marketplace::{AlertKind, DiskLevel, DiskPolicy, MarketplaceManager, MarketplaceStorage};
+use tari_l2_p2p::P2PNetwork;
+use crate::alerts::AlertConfig;
+use crate::scheduler::ScheduledTask;
+
+/// Intervals for the node's background tasks (all in seconds)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskConfig {
+    /// How often to auto-release timed out escrows
+    pub escrow_timeout_interval: u64,
+
+    /// How often to expire stale listings
+    pub listing_expiry_interval: u64,
+
+    /// Age after which a listing is deactivated
+    pub listing_ttl: u64,
+
+    /// How often to cancel unfunded orders
+    pub order_expiry_interval: u64,
+
+    /// Time a pending order has to be funded before it is cancelled
+    pub order_funding_deadline: u64,
+
+    /// How often to drop state updates left unsigned
+    pub pending_update_expiry_interval: u64,
+
+    /// Time a proposed state update has to collect its signatures
+    pub pending_update_ttl: u64,
+
+    /// How often to escalate unanswered refund requests
+    pub refund_escalation_interval: u64,
+
+    /// Time a seller has to answer a refund request before it becomes a dispute
+    pub refund_response_deadline: u64,
+
+    /// How often to settle channels whose challenge period has passed
+    pub channel_close_interval: u64,
+
+    /// How often to ask archive peers for the catalog until it has been downloaded
+    pub catalog_bootstrap_interval: u64,
+
+    /// How often to gossip the signed root of our own catalog
+    pub catalog_root_interval: u64,
+
+    /// How often to retry undelivered P2P messages in the outbox
+    pub outbox_flush_interval: u64,
+
+    /// How often to hibernate idle channels
+    pub hibernation_interval: u64,
+
+    /// Inactivity after which a settled channel is evicted from memory (0 keeps all loaded)
+    pub channel_hibernate_after: u64,
+
+    /// Time after a channel's last checkpoint before newer updates are anchored on L1
+    pub checkpoint_interval: u64,
+
+    /// Anchor a channel as soon as this many updates are unanchored (0 waits for the interval)
+    pub checkpoint_every_updates: u64,
+
+    /// How often to look for channels due a checkpoint or a retry
+    pub checkpoint_poll_interval: u64,
+
+    /// How often to re-check the L1 connection
+    pub l1_reconcile_interval: u64,
+
+    /// How often to check peer connectivity
+    pub peer_maintenance_interval: u64,
+
+    /// How often to check how long the L1 connection has been down
+    pub alert_check_interval: u64,
+
+    /// How often to measure disk usage of the data directory
+    pub disk_check_interval: u64,
+
+    /// How often a watchtower scans L1 for stale closes of channels it watches
+    pub watchtower_scan_interval: u64,
+
+    /// Maximum random delay added to each tick
+    pub jitter: u64,
+}
+
+impl Default for TaskConfig {
+    fn default() -> Self {
+        Self {
+            escrow_timeout_interval: 60,
+            listing_expiry_interval: 300,
+            listing_ttl: 30 * 24 * 3600,
+            order_expiry_interval: 60,
+            order_funding_deadline: 24 * 3600,
+            pending_update_expiry_interval: 300,
+            pending_update_ttl: 24 * 3600,
+            refund_escalation_interval: 300,
+            refund_response_deadline: 3 * 24 * 3600,
+            channel_close_interval: 60,
+            catalog_bootstrap_interval: 15,
+            catalog_root_interval: 600,
+            outbox_flush_interval: 5,
+            hibernation_interval: 3600,
+            channel_hibernate_after: 7 * 24 * 3600,
+            checkpoint_interval: 3600,
+            checkpoint_every_updates: 0,
+            checkpoint_poll_interval: 60,
+            l1_reconcile_interval: 120,
+            peer_maintenance_interval: 30,
+            alert_check_interval: 60,
+            disk_check_interval: 60,
+            watchtower_scan_interval: 60,
+            jitter: 5,
+        }
+    }
+}
+
+/// Auto-release escrows whose confirmation window has passed
+pub struct EscrowTimeoutTask {
+    pub marketplace: Arc<MarketplaceManager>,
+}
+
+#[async_trait]
+impl ScheduledTask for EscrowTimeoutTask {
+    fn name(&self) -> &'static str {
+        "escrow-timeouts"
+    }
+
+    async fn run(&self) -> Result<()> {
+        let released = self.marketplace.process_escrow_timeouts().await?;
+        if !released.is_empty() {
+            info!("Released {} timed out escrows", released.len());
+        }
+        Ok(())
+    }
+}
+
+/// Deactivate listings older than the configured TTL
+pub struct ListingExpiryTask {
+    pub marketplace: Arc<MarketplaceManager>,
+    pub ttl: u64,
+}
+
+#[async_trait]
+impl ScheduledTask for ListingExpiryTask {
+    fn name(&self) -> &'static str {
+        "listing-expiry"
+    }
+
+    async fn run(&self) -> Result<()> {
+        let expired = self.marketplace.expire_listings(self.ttl).await?;
+        if !expired.is_empty() {
+            info!("Expired {} listings", expired.len());
+        }
+        Ok(())
+    }
+}
+
+/// Cancel orders that were not funded before the deadline
+pub struct OrderExpiryTask {
+    pub marketplace: Arc<MarketplaceManager>,
+    pub deadline: u64,
+}
+
+#[async_trait]
+impl ScheduledTask for OrderExpiryTask {
+    fn name(&self) -> &'static str {
+        "order-expiry"
+    }
+
+    async fn run(&self) -> Result<()> {
+        let expired = self.marketplace.expire_unfunded_orders(self.deadline).await?;
+        if !expired.is_empty() {
+            info!("Cancelled {} unfunded orders", expired.len());
+        }
+        Ok(())
+    }
+}
+
+/// Drop proposed state updates that never collected their signatures
+pub struct PendingUpdateExpiryTask {
+    pub marketplace: Arc<MarketplaceManager>,
+    pub ttl: u64,
+}
+
+#[async_trait]
+impl ScheduledTask for PendingUpdateExpiryTask {
+    fn name(&self) -> &'static str {
+        "pending-update-expiry"
+    }
+
+    async fn run(&self) -> Result<()> {
+        let expired = self.marketplace.expire_pending_updates(self.ttl).await?;
+        if !expired.is_empty() {
+            info!("Dropped {} unsigned state updates", expired.len());
+        }
+        Ok(())
+    }
+}
+
+/// Escalate refund requests the seller has not answered in time
+pub struct RefundEscalationTask {
+    pub marketplace: Arc<MarketplaceManager>,
+    pub deadline: u64,
+}
+
+#[async_trait]
+impl ScheduledTask for RefundEscalationTask {
+    fn name(&self) -> &'static str {
+        "refund-escalation"
+    }
+
+    async fn run(&self) -> Result<()> {
+        let escalated = self.marketplace.escalate_stale_refunds(self.deadline).await?;
+        if !escalated.is_empty() {
+            info!("Escalated {} unanswered refund requests to disputes", escalated.len());
+        }
+        Ok(())
+    }
+}
+
+/// Notice channels a counterparty force-closed on L1, settle closing
+/// channels once their challenge period has passed, and disputed ones once
+/// their L1 challenge window has
+pub struct ChannelCloseTask {
+    pub marketplace: Arc<MarketplaceManager>,
+}
+
+#[async_trait]
+impl ScheduledTask for ChannelCloseTask {
+    fn name(&self) -> &'static str {
+        "channel-close"
+    }
+
+    async fn run(&self) -> Result<()> {
+        // Disputes are refused on a connected L1; still finalize closes
+        match self.marketplace.detect_force_closes().await {
+            Ok(forced) if !forced.is_empty() => {
+                warn!("{} channels were force-closed by a counterparty", forced.len());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to check L1 for force-closed channels: {}", e),
+        }
+        let closed = self.marketplace.finalize_closed_channels().await?;
+        if !closed.is_empty() {
+            info!("Closed {} channels after their challenge period", closed.len());
+        }
+        let resolved = self.marketplace.resolve_disputes().await?;
+        if !resolved.is_empty() {
+            info!("Resolved disputes for {} channels", resolved.len());
+        }
+        Ok(())
+    }
+}
+
+/// Download the catalog from archive peers once they are connected
+pub struct CatalogBootstrapTask {
+    pub marketplace: Arc<MarketplaceManager>,
+}
+
+#[async_trait]
+impl ScheduledTask for CatalogBootstrapTask {
+    fn name(&self) -> &'static str {
+        "catalog-bootstrap"
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.marketplace.bootstrap_catalog().await?;
+        Ok(())
+    }
+}
+
+/// Gossip the catalog root over our active listings
+pub struct CatalogRootTask {
+    pub marketplace: Arc<MarketplaceManager>,
+}
+
+#[async_trait]
+impl ScheduledTask for CatalogRootTask {
+    fn name(&self) -> &'static str {
+        "catalog-root"
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.marketplace.announce_catalog_root().await?;
+        Ok(())
+    }
+}
+
+/// Retry P2P messages that have not been delivered or acknowledged
+pub struct OutboxFlushTask {
+    pub marketplace: Arc<MarketplaceManager>,
+}
+
+#[async_trait]
+impl ScheduledTask for OutboxFlushTask {
+    fn name(&self) -> &'static str {
+        "outbox-flush"
+    }
+
+    async fn run(&self) -> Result<()> {
+        let sent = self.marketplace.flush_outbox().await?;
+        if sent > 0 {
+            info!("Resent {} queued P2P messages", sent);
+        }
+        Ok(())
+    }
+}
+
+/// Evict channels with no recent activity from memory
+pub struct HibernationTask {
+    pub marketplace: Arc<MarketplaceManager>,
+}
+
+#[async_trait]
+impl ScheduledTask for HibernationTask {
+    fn name(&self) -> &'static str {
+        "channel-hibernation"
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.marketplace.hibernate_idle_channels().await?;
+        Ok(())
+    }
+}
+
+/// Anchor the state roots of channels due a checkpoint on L1
+pub struct CheckpointTask {
+    pub marketplace: Arc<MarketplaceManager>,
+}
+
+#[async_trait]
+impl ScheduledTask for CheckpointTask {
+    fn name(&self) -> &'static str {
+        "checkpoint"
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.marketplace.checkpoint_channels().await?;
+        Ok(())
+    }
+}
+
+/// Challenge stale closes on L1 of channels a watchtower holds blobs for
+pub struct WatchtowerTask {
+    pub marketplace: Arc<MarketplaceManager>,
+}
+
+#[async_trait]
+impl ScheduledTask for WatchtowerTask {
+    fn name(&self) -> &'static str {
+        "watchtower"
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.marketplace.watch_l1_closes().await?;
+        Ok(())
+    }
+}
+
+/// Re-check the L1 connection and publish the result to the RPC layer
+pub struct L1ReconcileTask {
+    pub l1_client: Arc<TariL1Client>,
+    pub l1_connected: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl ScheduledTask for L1ReconcileTask {
+    fn name(&self) -> &'static str {
+        "l1-reconcile"
+    }
+
+    async fn run(&self) -> Result<()> {
+        let connected = self.l1_client.refresh_connection().await;
+        let was_connected = self.l1_connected.swap(connected, Ordering::Relaxed);
+        if connected != was_connected {
+            info!("L1 connection status changed: connected={}", connected);
+        }
+        Ok(())
+    }
+}
+
+/// Top connections up to `max_peers` from the known peers, redial bootstrap
+/// peers when the node has lost all connections, and save the known peers
+pub struct PeerMaintenanceTask {
+    pub marketplace: Arc<MarketplaceManager>,
+    pub network: Arc<P2PNetwork>,
+    pub storage: Arc<MarketplaceStorage>,
+}
+
+#[async_trait]
+impl ScheduledTask for PeerMaintenanceTask {
+    fn name(&self) -> &'static str {
+        "peer-maintenance"
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.network.set_protected_peers(self.marketplace.channel_counterparties().await);
+        self.network.maintain_peers().await?;
+        self.storage.store_known_peers(&self.network.known_peers())
+    }
+}
+
+/// Raise an alert once the L1 connection has been down too long
+pub struct AlertMonitorTask {
+    pub marketplace: Arc<MarketplaceManager>,
+    /// None when running against the L1 simulator
+    pub l1_connected: Option<Arc<AtomicBool>>,
+    pub config: AlertConfig,
+    /// When the L1 connection was first seen down
+    pub l1_down_since: Mutex<Option<u64>>,
+}
+
+#[async_trait]
+impl ScheduledTask for AlertMonitorTask {
+    fn name(&self) -> &'static str {
+        "alert-monitor"
+    }
+
+    async fn run(&self) -> Result<()> {
+        let alerts = self.marketplace.alerts();
+        let now = Timestamp::now();
+
+        if let Some(l1_connected) = &self.l1_connected {
+            let mut down_since = self.l1_down_since.lock().unwrap();
+            if l1_connected.load(Ordering::Relaxed) {
+                *down_since = None;
+            } else {
+                let since = *down_since.get_or_insert(now.as_secs());
+                if now.as_secs().saturating_sub(since) >= self.config.l1_disconnect_minutes * 60 {
+                    alerts.raise(AlertKind::L1Disconnected { since }, now);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Measure the data directory and free disk space, alerting as it fills
+/// and moving the marketplace in and out of degraded mode
+pub struct DiskMonitorTask {
+    pub marketplace: Arc<MarketplaceManager>,
+    pub data_dir: PathBuf,
+    pub policy: DiskPolicy,
+}
+
+#[async_trait]
+impl ScheduledTask for DiskMonitorTask {
+    fn name(&self) -> &'static str {
+        "disk-monitor"
+    }
+
+    async fn run(&self) -> Result<()> {
+        let data_dir = self.data_dir.clone();
+        let (used, free) = tokio::task::spawn_blocking(move || -> io::Result<(u64, u64)> {
+            Ok((dir_size(&data_dir)?, fs2::available_space(&data_dir)?))
+        })
+        .await
+        .map_err(|e| L2Error::Unknown(e.to_string()))?
+        .map_err(|e| L2Error::Unknown(format!("Failed to measure disk usage: {}", e)))?;
+
+        let now = Timestamp::now();
+        let status = self.policy.evaluate(used, free, now.as_secs());
+        let previous = self.marketplace.disk_status().await.level;
+        if status.level != previous {
+            match status.level {
+                DiskLevel::Degraded => warn!(
+                    "💾 Disk nearly full ({}% used, {} bytes free): refusing new listings and channels",
+                    status.usage_percent, status.available_bytes
+                ),
+                _ if previous == DiskLevel::Degraded => info!("💾 Disk space recovered, leaving degraded mode"),
+                _ => {}
+            }
+        }
+        if status.level >= DiskLevel::Warning {
+            self.marketplace.alerts().raise(AlertKind::StorageNearlyFull {
+                used_bytes: status.used_bytes,
+                capacity_bytes: status.capacity_bytes,
+            }, now);
+        }
+        if status.is_degraded() {
+            self.marketplace.alerts().raise(AlertKind::DiskDegraded { available_bytes: status.available_bytes }, now);
+        }
+
+        self.marketplace.set_disk_status(status).await;
+        Ok(())
+    }
+}
+
+/// Total size of the files under `path`
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() { dir_size(&entry.path())? } else { metadata.len() };
+    }
+    Ok(size)
+}
@@ -0,0 +1,138 @@
+//! Log output and OpenTelemetry export.
+//!
+//! The node always logs to stdout through [`Redacting`]. Its spans, one per
+//! RPC request, P2P message, manager operation and L1 call, carry the
+//! `channel_id` and `order_id` they concern, so with
+//! `telemetry.otlp_endpoint` set they can also be exported over OTLP and a
+//! slow order followed across subsystems in a tracing backend. Export needs
+//! the node built with the `otel` feature.
+
+use tracing::{Level, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use crate::config::TelemetryConfig;
+use crate::redact::Redacting;
+
+/// Flushes spans not yet exported when dropped; hold it until the node exits
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            let _ = provider.shutdown();
+        }
+    }
+}
+
+/// Install the global subscriber: logs at `level` and above, plus OTLP
+/// export when configured. Must run inside the Tokio runtime.
+pub fn init(level: Level, config: &TelemetryConfig) -> Result<TelemetryGuard, String> {
+    let subscriber = tracing_subscriber::registry().with(log_layer(level, std::io::stdout));
+
+    #[cfg(feature = "otel")]
+    let (subscriber, provider) = {
+        use opentelemetry::trace::TracerProvider as _;
+
+        let provider = otlp_provider(config)?;
+        let export = provider.as_ref().map(|provider| {
+            tracing_opentelemetry::layer()
+                .with_tracer(provider.tracer("tari-l2-node"))
+                .with_filter(LevelFilter::from_level(level))
+        });
+        (subscriber.with(export), provider)
+    };
+    subscriber.init();
+
+    if !config.otlp_endpoint.is_empty() {
+        if cfg!(feature = "otel") {
+            tracing::info!("Exporting spans to {}", config.otlp_endpoint);
+        } else {
+            tracing::warn!("telemetry.otlp_endpoint is set, but this node was built without the otel feature; spans are not exported");
+        }
+    }
+    Ok(TelemetryGuard {
+        #[cfg(feature = "otel")]
+        provider,
+    })
+}
+
+/// Log lines at `level` and above, passed through [`Redacting`] so secrets
+/// never reach the log. Colours are off: their escape codes would split a
+/// field name from its `=` and keep the secret from being recognised.
+fn log_layer<S, W>(level: Level, writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_writer(Redacting(writer))
+        .with_filter(LevelFilter::from_level(level))
+}
+
+/// Batch exporter sending spans to the configured collector, if any
+#[cfg(feature = "otel")]
+fn otlp_provider(config: &TelemetryConfig) -> Result<Option<opentelemetry_sdk::trace::TracerProvider>, String> {
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{Resource, runtime, trace};
+
+    if config.otlp_endpoint.is_empty() {
+        return Ok(None);
+    }
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(config.otlp_endpoint.clone()))
+        .with_trace_config(trace::Config::default().with_resource(Resource::new([
+            KeyValue::new("service.name", config.service_name.clone()),
+        ])))
+        .install_batch(runtime::Tokio)
+        .map(Some)
+        .map_err(|e| format!("Cannot export spans to {}: {}", config.otlp_endpoint, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_log_lines_are_redacted() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::registry().with(log_layer(Level::INFO, move || writer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(seed_phrase = "gate federal tree", passphrase = %"hunter2", "Wallet imported");
+        });
+
+        let log = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        assert!(log.contains("Wallet imported"));
+        assert!(log.contains("seed_phrase=\"[redacted]\""));
+        assert!(!log.contains("gate federal tree"));
+        assert!(!log.contains("hunter2"));
+        assert!(!log.contains('\x1b'));
+    }
+}
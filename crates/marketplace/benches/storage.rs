@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use tempfile::TempDir;
+use tari_l2_common::{Amount, Hash, PublicKey, Signature, crypto::{self, KeyPair, SigningContext}};
+use tari_l2_marketplace::MarketplaceStorage;
+use tari_l2_state_channel::{ChannelConfig, MarketplaceChannel, StateUpdate, state::Listing, update::SignedStateUpdate};
+
+fn listing(seller: PublicKey, i: u64) -> Listing {
+    Listing {
+        id: Hash::random(),
+        seller,
+        title: format!("Listing {}", i),
+        description: "A reasonably sized description of the item for sale".to_string(),
+        price: Amount::new(1_000 + i),
+        ipfs_hash: String::new(),
+        active: true,
+        category: "electronics".to_string(),
+        fiat_reference: None,
+    }
+}
+
+/// An active channel with `listings` applied updates in its history
+fn busy_channel(listings: u64) -> MarketplaceChannel {
+    let kp = KeyPair::from_private_key(&[1; 32]).unwrap();
+    let mut initial_balances = HashMap::new();
+    initial_balances.insert(kp.public_key(), Amount::new(1_000_000));
+    let mut channel = MarketplaceChannel::new(ChannelConfig {
+        participants: vec![kp.public_key()],
+        initial_balances,
+        challenge_period: 3600,
+    });
+    channel.activate().unwrap();
+
+    for i in 0..listings {
+        let mut signed = SignedStateUpdate::new(StateUpdate::CreateListing { listing: listing(kp.public_key(), i) }, i + 1);
+        signed.add_signature(kp.public_key(), kp.sign(&signed.signing_message()));
+        channel.apply_update(signed).unwrap();
+    }
+    channel
+}
+
+fn bench_channel_persistence(c: &mut Criterion) {
+    let mut group = c.benchmark_group("channel");
+    let dir = TempDir::new().unwrap();
+    let storage = MarketplaceStorage::open(dir.path()).unwrap();
+
+    for size in [10u64, 100] {
+        let channel = busy_channel(size);
+        let bytes = bincode::serialize(&channel).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("serialize", size), &channel, |b, channel| {
+            b.iter(|| bincode::serialize(channel).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("deserialize", size), &bytes, |b, bytes| {
+            b.iter(|| bincode::deserialize::<MarketplaceChannel>(bytes).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("store", size), &channel, |b, channel| {
+            b.iter(|| storage.store_channel(channel).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("append", size), &channel, |b, channel| {
+            b.iter(|| storage.append_update(channel).unwrap())
+        });
+        storage.store_channel(&channel).unwrap();
+        group.bench_with_input(BenchmarkId::new("load", size), &channel.channel_id, |b, id| {
+            b.iter(|| storage.load_channel(id).unwrap().unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn signed_listings(count: u64) -> (Vec<PublicKey>, Vec<Vec<u8>>, Vec<Signature>) {
+    let sellers: Vec<KeyPair> = (0..4).map(|_| KeyPair::generate()).collect();
+    let mut keys = Vec::new();
+    let mut messages = Vec::new();
+    let mut signatures = Vec::new();
+
+    for i in 0..count {
+        let seller = &sellers[i as usize % sellers.len()];
+        let bytes = bincode::serialize(&listing(seller.public_key(), i)).unwrap();
+        let message = SigningContext::Listing.message(&bytes);
+        signatures.push(seller.sign(&message));
+        keys.push(seller.public_key());
+        messages.push(message);
+    }
+    (keys, messages, signatures)
+}
+
+fn bench_listing_signatures(c: &mut Criterion) {
+    let mut group = c.benchmark_group("listing_signatures");
+
+    for count in [1u64, 64, 256] {
+        let (keys, messages, signatures) = signed_listings(count);
+        let message_refs: Vec<&[u8]> = messages.iter().map(|m| m.as_slice()).collect();
+
+        group.bench_with_input(BenchmarkId::new("individual", count), &count, |b, _| {
+            b.iter(|| {
+                for ((pk, message), signature) in keys.iter().zip(&messages).zip(&signatures) {
+                    assert!(pk.verify(message, signature));
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("batch", count), &count, |b, _| {
+            b.iter(|| assert!(crypto::verify_signatures_batch(&keys, &message_refs, &signatures)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_channel_persistence, bench_listing_signatures);
+criterion_main!(benches);
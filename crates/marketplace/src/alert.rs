@@ -0,0 +1,331 @@
+//! High-severity events an operator should hear about.
+//!
+//! The node raises alerts on the [`AlertHub`]; sinks subscribe to it and pass
+//! them on (log, webhooks, the RPC alert stream). The hub keeps the most
+//! recent alerts for polling and suppresses repeats of the same alert within
+//! a cooldown.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{Hash, PublicKey};
+
+/// How urgently an alert needs attention
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Warning,
+    Critical,
+}
+
+/// What happened
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// The L1 base node has been unreachable since `since`
+    L1Disconnected { since: u64 },
+
+    /// A counterparty disputed an order we are party to
+    DisputeOpened { channel_id: Hash, order_id: Hash, by: PublicKey },
+
+    /// The data directory is running out of space
+    StorageNearlyFull { used_bytes: u64, capacity_bytes: u64 },
+
+    /// A peer sent many messages with invalid signatures in a short time
+    InvalidSignatureStorm { peer: PublicKey, failures: u32, window_secs: u64 },
+
+    /// The data directory is so full the node refuses new listings and channels
+    DiskDegraded { available_bytes: u64 },
+
+    /// An event could not be appended to the audit log, which is missing
+    /// everything from entry `seq` on
+    AuditLogGap { seq: u64 },
+
+    /// A channel closed but its collateral could not be released on L1
+    CollateralLocked { channel_id: Hash },
+}
+
+impl AlertKind {
+    pub fn severity(&self) -> AlertSeverity {
+        match self {
+            AlertKind::L1Disconnected { .. } => AlertSeverity::Critical,
+            AlertKind::DisputeOpened { .. } => AlertSeverity::Critical,
+            AlertKind::StorageNearlyFull { .. } => AlertSeverity::Warning,
+            AlertKind::InvalidSignatureStorm { .. } => AlertSeverity::Warning,
+            AlertKind::DiskDegraded { .. } => AlertSeverity::Critical,
+            AlertKind::AuditLogGap { .. } => AlertSeverity::Critical,
+            AlertKind::CollateralLocked { .. } => AlertSeverity::Critical,
+        }
+    }
+
+    /// Alerts with the same key are repeats of one another
+    pub fn key(&self) -> String {
+        match self {
+            AlertKind::L1Disconnected { since } => format!("l1-disconnected:{}", since),
+            AlertKind::DisputeOpened { order_id, .. } => format!("dispute:{}", order_id),
+            AlertKind::StorageNearlyFull { .. } => "storage".to_string(),
+            AlertKind::InvalidSignatureStorm { peer, .. } => format!("signatures:{}", peer),
+            AlertKind::DiskDegraded { .. } => "disk-degraded".to_string(),
+            AlertKind::AuditLogGap { .. } => "audit-gap".to_string(),
+            AlertKind::CollateralLocked { channel_id } => format!("collateral:{}", channel_id),
+        }
+    }
+
+    /// One-line description for logs and notifications
+    pub fn describe(&self, now: u64) -> String {
+        match self {
+            AlertKind::L1Disconnected { since } => {
+                format!("L1 base node unreachable for {} minutes", now.saturating_sub(*since) / 60)
+            }
+            AlertKind::DisputeOpened { order_id, by, .. } => {
+                format!("Order {} disputed by {}", order_id, by)
+            }
+            AlertKind::StorageNearlyFull { used_bytes, capacity_bytes } => {
+                format!("Data directory using {} of {} bytes", used_bytes, capacity_bytes)
+            }
+            AlertKind::InvalidSignatureStorm { peer, failures, window_secs } => {
+                format!("{} invalid signatures from {} in {}s", failures, peer, window_secs)
+            }
+            AlertKind::DiskDegraded { available_bytes } => {
+                format!("Only {} bytes free for the data directory; refusing new listings and channels", available_bytes)
+            }
+            AlertKind::AuditLogGap { seq } => {
+                format!("Failed to append audit log entry {}; the log is broken from there", seq)
+            }
+            AlertKind::CollateralLocked { channel_id } => {
+                format!("Channel {} closed but its collateral is still locked on L1", channel_id)
+            }
+        }
+    }
+}
+
+/// A raised alert
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Alert {
+    /// Increases with each alert raised by this node
+    pub id: u64,
+    pub severity: AlertSeverity,
+    pub kind: AlertKind,
+    pub message: String,
+    pub raised_at: u64,
+}
+
+/// Thresholds for alerts the hub detects itself
+#[derive(Clone, Debug)]
+pub struct AlertPolicy {
+    /// Seconds during which a repeat of the same alert is suppressed
+    pub cooldown_secs: u64,
+
+    /// Invalid signatures from one peer that make a storm
+    pub signature_failures: u32,
+
+    /// Window the invalid signatures are counted over
+    pub signature_window_secs: u64,
+}
+
+impl Default for AlertPolicy {
+    fn default() -> Self {
+        Self {
+            cooldown_secs: 3600,
+            signature_failures: 20,
+            signature_window_secs: 60,
+        }
+    }
+}
+
+#[cfg(feature = "node")]
+pub use hub::{AlertHub, AlertSink, LogSink};
+
+#[cfg(feature = "node")]
+mod hub {
+    use super::*;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::Mutex;
+    use async_trait::async_trait;
+    use tari_l2_common::{Timestamp, error::Result};
+    use tokio::sync::broadcast;
+    use tracing::{error, warn};
+
+    /// Alerts kept for polling
+    const RECENT_ALERTS: usize = 100;
+
+    /// Alerts buffered for each subscriber before it starts missing them
+    const SUBSCRIBER_BUFFER: usize = 64;
+
+    #[derive(Default)]
+    struct HubState {
+        next_id: u64,
+        recent: VecDeque<Alert>,
+        /// When each alert key was last raised
+        last_raised: HashMap<String, u64>,
+        /// Times of recent invalid signatures, by peer
+        signature_failures: HashMap<PublicKey, VecDeque<u64>>,
+    }
+
+    /// Collects alerts and fans them out to subscribers
+    pub struct AlertHub {
+        sender: broadcast::Sender<Alert>,
+        policy: Mutex<AlertPolicy>,
+        state: Mutex<HubState>,
+    }
+
+    impl Default for AlertHub {
+        fn default() -> Self {
+            Self::new(AlertPolicy::default())
+        }
+    }
+
+    impl AlertHub {
+        pub fn new(policy: AlertPolicy) -> Self {
+            let (sender, _) = broadcast::channel(SUBSCRIBER_BUFFER);
+            Self {
+                sender,
+                policy: Mutex::new(policy),
+                state: Mutex::new(HubState::default()),
+            }
+        }
+
+        pub fn set_policy(&self, policy: AlertPolicy) {
+            *self.policy.lock().unwrap() = policy;
+        }
+
+        /// Receive every alert raised from now on
+        pub fn subscribe(&self) -> broadcast::Receiver<Alert> {
+            self.sender.subscribe()
+        }
+
+        /// Raise an alert unless the same one was raised within the cooldown.
+        /// Returns the alert if it was raised.
+        pub fn raise(&self, kind: AlertKind, now: Timestamp) -> Option<Alert> {
+            let cooldown = self.policy.lock().unwrap().cooldown_secs;
+            let now = now.as_secs();
+
+            let alert = {
+                let mut state = self.state.lock().unwrap();
+                let key = kind.key();
+                if let Some(last) = state.last_raised.get(&key) {
+                    if now.saturating_sub(*last) < cooldown {
+                        return None;
+                    }
+                }
+                state.last_raised.insert(key, now);
+                state.last_raised.retain(|_, last| now.saturating_sub(*last) < cooldown);
+
+                state.next_id += 1;
+                let alert = Alert {
+                    id: state.next_id,
+                    severity: kind.severity(),
+                    message: kind.describe(now),
+                    kind,
+                    raised_at: now,
+                };
+                if state.recent.len() == RECENT_ALERTS {
+                    state.recent.pop_front();
+                }
+                state.recent.push_back(alert.clone());
+                alert
+            };
+
+            // No subscribers is fine; the alert is still kept for polling
+            let _ = self.sender.send(alert.clone());
+            Some(alert)
+        }
+
+        /// Count an invalid signature from `peer`, raising a storm alert once
+        /// the peer crosses the policy threshold
+        pub fn note_invalid_signature(&self, peer: PublicKey, now: Timestamp) -> Option<Alert> {
+            let policy = self.policy.lock().unwrap().clone();
+            let failures = {
+                let mut state = self.state.lock().unwrap();
+                let times = state.signature_failures.entry(peer).or_default();
+                times.push_back(now.as_secs());
+                while times.front().is_some_and(|t| now.as_secs().saturating_sub(*t) >= policy.signature_window_secs) {
+                    times.pop_front();
+                }
+                let failures = times.len() as u32;
+                if failures >= policy.signature_failures {
+                    times.clear();
+                }
+                failures
+            };
+
+            if failures < policy.signature_failures {
+                return None;
+            }
+            self.raise(AlertKind::InvalidSignatureStorm {
+                peer,
+                failures,
+                window_secs: policy.signature_window_secs,
+            }, now)
+        }
+
+        /// Recent alerts with an ID above `since`, oldest first
+        pub fn recent(&self, since: u64) -> Vec<Alert> {
+            self.state.lock().unwrap().recent.iter()
+                .filter(|a| a.id > since)
+                .cloned()
+                .collect()
+        }
+    }
+
+    /// Destination alerts are delivered to
+    #[async_trait]
+    pub trait AlertSink: Send + Sync {
+        fn name(&self) -> &str;
+
+        async fn deliver(&self, alert: &Alert) -> Result<()>;
+    }
+
+    /// Writes alerts to the node log
+    pub struct LogSink;
+
+    #[async_trait]
+    impl AlertSink for LogSink {
+        fn name(&self) -> &str {
+            "log"
+        }
+
+        async fn deliver(&self, alert: &Alert) -> Result<()> {
+            match alert.severity {
+                AlertSeverity::Critical => error!("🚨 ALERT #{}: {}", alert.id, alert.message),
+                AlertSeverity::Warning => warn!("⚠️  ALERT #{}: {}", alert.id, alert.message),
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "node"))]
+mod tests {
+    use super::*;
+    use tari_l2_common::Timestamp;
+
+    #[test]
+    fn test_repeats_suppressed_and_storms_detected() {
+        let hub = AlertHub::new(AlertPolicy {
+            cooldown_secs: 600,
+            signature_failures: 3,
+            signature_window_secs: 60,
+        });
+        let mut receiver = hub.subscribe();
+        let now = Timestamp::from_secs(1_700_000_000);
+        let later = |secs| Timestamp::from_secs(now.as_secs() + secs);
+
+        let kind = AlertKind::StorageNearlyFull { used_bytes: 85, capacity_bytes: 100 };
+        let alert = hub.raise(kind.clone(), now).unwrap();
+        assert_eq!(alert.severity, AlertSeverity::Warning);
+        assert!(hub.raise(kind.clone(), later(60)).is_none());
+        assert!(hub.raise(kind, later(600)).is_some());
+        assert_eq!(receiver.try_recv().unwrap(), alert);
+
+        // Failures spread wider than the window never add up to a storm
+        let peer = PublicKey::new([7; 32]);
+        assert!(hub.note_invalid_signature(peer, now).is_none());
+        assert!(hub.note_invalid_signature(peer, later(30)).is_none());
+        assert!(hub.note_invalid_signature(peer, later(90)).is_none());
+        assert!(hub.note_invalid_signature(peer, later(100)).is_none());
+        let storm = hub.note_invalid_signature(peer, later(110)).unwrap();
+        assert!(matches!(storm.kind, AlertKind::InvalidSignatureStorm { failures: 3, .. }));
+
+        let ids: Vec<u64> = hub.recent(1).iter().map(|a| a.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+    }
+}
@@ -0,0 +1,137 @@
+//! Tamper-evident audit log of what the node did.
+//!
+//! Every applied channel update, escrow transition and L1 transaction the
+//! node submits is appended as an [`AuditEntry`]. Entries are numbered from
+//! zero and each one's hash covers the hash of the entry before it, so
+//! editing, removing or reordering an entry breaks every link after it.
+//! Recording the latest hash somewhere else lets an auditor later show the
+//! log was not rewritten wholesale either.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{Hash, canonical};
+use tari_l2_common::crypto::{SigningContext, hash_multiple};
+use crate::escrow::EscrowStatus;
+
+/// What the first entry links to
+pub const GENESIS_HASH: Hash = Hash::new([0u8; 32]);
+
+/// L1 transactions the node submits
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum L1Action {
+    LockCollateral,
+    UnlockCollateral,
+    Checkpoint,
+    /// A force close, challenge or counter with our latest state
+    Dispute,
+    ResolveDispute,
+    EscrowPayout,
+}
+
+/// Something recorded in the audit log. Stored with bincode, so externally
+/// tagged.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// A co-signed update was applied at `nonce`
+    StateUpdate { channel_id: Hash, nonce: u64, update_hash: Hash },
+
+    /// An escrow was created or moved to `status`
+    Escrow { escrow_id: Hash, order_id: Hash, channel_id: Hash, status: EscrowStatus },
+
+    /// An L1 transaction was accepted by the wallet
+    L1 { action: L1Action, channel_id: Option<Hash>, tx_id: String },
+}
+
+/// One link in the audit log
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub timestamp: u64,
+    pub event: AuditEvent,
+    /// Hash of entry `seq - 1`, or [`GENESIS_HASH`]
+    pub prev_hash: Hash,
+    pub hash: Hash,
+}
+
+impl AuditEntry {
+    /// The entry after one with `prev_hash`, numbered `seq`
+    pub fn new(seq: u64, timestamp: u64, event: AuditEvent, prev_hash: Hash) -> Result<Self, String> {
+        let hash = Self::compute_hash(seq, timestamp, &event, &prev_hash)?;
+        Ok(Self { seq, timestamp, event, prev_hash, hash })
+    }
+
+    pub fn compute_hash(seq: u64, timestamp: u64, event: &AuditEvent, prev_hash: &Hash) -> Result<Hash, String> {
+        let event = canonical::encode(event)?;
+        Ok(hash_multiple(&[
+            &SigningContext::Audit.message(&[]),
+            &seq.to_le_bytes(),
+            &timestamp.to_le_bytes(),
+            &event,
+            prev_hash.as_bytes(),
+        ]))
+    }
+
+    /// Whether `hash` matches the rest of the entry
+    pub fn is_intact(&self) -> bool {
+        Self::compute_hash(self.seq, self.timestamp, &self.event, &self.prev_hash)
+            .is_ok_and(|hash| hash == self.hash)
+    }
+}
+
+/// Check that `entries` are intact and each links to the one before.
+/// `prev` is the entry just before the first, or None when the first should
+/// be entry zero. Returns the sequence number of the first entry that fails.
+pub fn verify_chain(prev: Option<&AuditEntry>, entries: &[AuditEntry]) -> Result<(), u64> {
+    let (mut expected_seq, mut expected_prev) = match prev {
+        Some(prev) if !prev.is_intact() => return Err(prev.seq),
+        Some(prev) => (prev.seq + 1, prev.hash),
+        None => (0, GENESIS_HASH),
+    };
+    for entry in entries {
+        if entry.seq != expected_seq || entry.prev_hash != expected_prev || !entry.is_intact() {
+            return Err(entry.seq);
+        }
+        expected_seq += 1;
+        expected_prev = entry.hash;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(len: u64) -> Vec<AuditEntry> {
+        let mut entries: Vec<AuditEntry> = Vec::new();
+        for seq in 0..len {
+            let prev = entries.last().map_or(GENESIS_HASH, |e| e.hash);
+            let event = AuditEvent::StateUpdate { channel_id: Hash::new([1; 32]), nonce: seq + 1, update_hash: Hash::new([seq as u8; 32]) };
+            entries.push(AuditEntry::new(seq, 1_700_000_000 + seq, event, prev).unwrap());
+        }
+        entries
+    }
+
+    #[test]
+    fn test_audit_chain_detects_tampering() {
+        let entries = chain(4);
+        assert_eq!(verify_chain(None, &entries), Ok(()));
+        assert_eq!(verify_chain(Some(&entries[1]), &entries[2..]), Ok(()));
+        // A range must start right after the entry it is checked against
+        assert_eq!(verify_chain(Some(&entries[0]), &entries[2..]), Err(2));
+
+        // Rewriting an event breaks that entry's hash
+        let mut edited = entries.clone();
+        edited[1].event = AuditEvent::L1 { action: L1Action::EscrowPayout, channel_id: None, tx_id: "tx".to_string() };
+        assert_eq!(verify_chain(None, &edited), Err(1));
+
+        // Rehashing it breaks the link from the next entry instead
+        edited[1] = AuditEntry::new(1, edited[1].timestamp, edited[1].event.clone(), edited[0].hash).unwrap();
+        assert_eq!(verify_chain(None, &edited), Err(2));
+
+        // So does dropping an entry
+        let mut dropped = entries.clone();
+        dropped.remove(2);
+        assert_eq!(verify_chain(None, &dropped), Err(3));
+    }
+}
@@ -1,5 +1,11 @@
 use serde::{Deserialize, Serialize};
-use tari_l2_common::{PublicKey, Signature};
+use std::collections::HashMap;
+use tari_l2_common::{Hash, PublicKey, Signature, Timestamp, canonical};
+use tari_l2_common::crypto::{SigningContext, hash_multiple};
+use tari_l2_common::clock::{Clock, SystemClock, TimestampWindow, DEFAULT_MAX_CLOCK_SKEW};
+
+/// Signed actions are accepted for 5 minutes, allowing for some clock skew
+pub const ACTION_TIMESTAMP_WINDOW: TimestampWindow = TimestampWindow::new(300, DEFAULT_MAX_CLOCK_SKEW);
 
 /// Signed action for P2P verification
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -20,17 +26,18 @@ pub struct SignedAction<T> {
 impl<T: Serialize> SignedAction<T> {
     /// Create a new signed action (client-side)
     pub fn new(payload: T, public_key: PublicKey, sign_fn: impl FnOnce(&[u8]) -> Signature) -> Result<Self, String> {
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map_err(|e| e.to_string())?
-            .as_secs();
-
-        // Serialize payload + timestamp for signing
-        let mut message = bincode::serialize(&payload)
-            .map_err(|e| e.to_string())?;
-        message.extend_from_slice(&timestamp.to_le_bytes());
+        Self::new_at(payload, public_key, SystemClock.now(), sign_fn)
+    }
 
-        let signature = sign_fn(&message);
+    /// Create a signed action stamped with a specific time
+    pub fn new_at(
+        payload: T,
+        public_key: PublicKey,
+        now: Timestamp,
+        sign_fn: impl FnOnce(&[u8]) -> Signature,
+    ) -> Result<Self, String> {
+        let timestamp = now.as_secs();
+        let signature = sign_fn(&Self::signing_message(&payload, timestamp)?);
 
         Ok(Self {
             payload,
@@ -40,37 +47,212 @@ impl<T: Serialize> SignedAction<T> {
         })
     }
 
-    /// Verify the signature on this action
+    /// Verify the signature on this action against the system clock
     pub fn verify(&self) -> Result<(), String> {
-        use tari_l2_common::crypto::verify_signature;
+        self.verify_with(&SystemClock, ACTION_TIMESTAMP_WINDOW)
+    }
 
-        // Reconstruct the signed message
-        let mut message = bincode::serialize(&self.payload)
-            .map_err(|e| e.to_string())?;
-        message.extend_from_slice(&self.timestamp.to_le_bytes());
+    /// Verify the signature and that the timestamp is inside `window` of `clock`
+    pub fn verify_with(&self, clock: &dyn Clock, window: TimestampWindow) -> Result<(), String> {
+        let message = Self::signing_message(&self.payload, self.timestamp)?;
 
         // Verify signature
-        if !verify_signature(&self.public_key, &message, &self.signature) {
+        if !self.public_key.verify(&message, &self.signature) {
             return Err("Invalid signature".to_string());
         }
 
-        // Check timestamp is not too old (max 5 minutes)
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map_err(|e| e.to_string())?
-            .as_secs();
-
-        if now > self.timestamp + 300 {
-            return Err("Action expired (timestamp too old)".to_string());
-        }
-
-        Ok(())
+        // Check timestamp is neither stale nor too far ahead of our clock
+        window.check(clock.now(), Timestamp::from_secs(self.timestamp))
+            .map_err(|e| format!("Action expired: {}", e))
     }
 
     /// Get the signer's public key
     pub fn signer(&self) -> &PublicKey {
         &self.public_key
     }
+
+    /// Identifies the action for replay detection. It covers what was
+    /// signed rather than the signature, so re-signing the same payload in
+    /// the same second does not produce a fresh action.
+    pub fn id(&self) -> Result<Hash, String> {
+        let message = Self::signing_message(&self.payload, self.timestamp)?;
+        Ok(hash_multiple(&[&self.public_key.as_bytes()[..], &message[..]]))
+    }
+
+    /// Payload followed by the timestamp, as signed
+    fn signing_message(payload: &T, timestamp: u64) -> Result<Vec<u8>, String> {
+        let mut message = canonical::encode(payload)?;
+        message.extend_from_slice(&timestamp.to_le_bytes());
+        Ok(message)
+    }
+}
+
+/// Payload an acting key signs, as a [`SignedAction`], to authorize one
+/// RPC call: the method name with the call's other params, so the
+/// signature cannot be replayed against a different method
+pub fn rpc_call_payload(method: &str, params: &serde_json::Value) -> serde_json::Value {
+    serde_json::json!({ "method": method, "params": params })
+}
+
+/// Payload an owner key signs, as a [`SignedAction`], to authenticate one
+/// HTTP request to the RPC server: the HTTP method, the path and the body,
+/// under the [`SigningContext::RpcRequest`] tag
+pub fn rpc_request_payload(method: &str, path: &str, body: &[u8]) -> Vec<u8> {
+    let mut request = format!("{} {}\n", method, path).into_bytes();
+    request.extend_from_slice(body);
+    SigningContext::RpcRequest.message(&request)
+}
+
+/// Signed actions accepted inside the timestamp window. Anything older is
+/// refused as stale, so an action only has to be remembered until then.
+#[derive(Debug, Default)]
+pub struct SeenActions {
+    seen: HashMap<Hash, u64>,
+}
+
+impl SeenActions {
+    /// Record action `id` signed at `timestamp`, failing if it was already seen
+    pub fn insert(&mut self, id: Hash, timestamp: u64) -> Result<(), String> {
+        if self.seen.contains_key(&id) {
+            return Err("Action was already submitted".to_string());
+        }
+        self.seen.insert(id, timestamp);
+        Ok(())
+    }
+
+    /// Forget actions signed before `cutoff`, returning their IDs
+    pub fn prune(&mut self, cutoff: u64) -> Vec<Hash> {
+        let expired: Vec<Hash> = self.seen.iter()
+            .filter(|(_, timestamp)| **timestamp < cutoff)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &expired {
+            self.seen.remove(id);
+        }
+        expired
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
+
+/// Signed proof that a node controls its public key and P2P identity
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeAttestation {
+    /// Node public key that produced the signature
+    pub public_key: PublicKey,
+
+    /// libp2p peer ID of the node (if the P2P network is running)
+    pub peer_id: Option<String>,
+
+    /// Caller-supplied challenge
+    pub nonce: Vec<u8>,
+
+    /// When the attestation was produced
+    pub timestamp: u64,
+
+    /// Signature over the attestation message
+    pub signature: Signature,
+}
+
+impl NodeAttestation {
+    const DOMAIN: &'static [u8] = b"tari-l2-node-attestation";
+
+    /// Build the message signed for an attestation
+    pub fn signing_message(nonce: &[u8], peer_id: Option<&str>, timestamp: u64) -> Vec<u8> {
+        let mut message = Self::DOMAIN.to_vec();
+        message.extend_from_slice(&(nonce.len() as u32).to_le_bytes());
+        message.extend_from_slice(nonce);
+        message.extend_from_slice(peer_id.unwrap_or("").as_bytes());
+        message.extend_from_slice(&timestamp.to_le_bytes());
+        message
+    }
+
+    /// Verify the attestation signature
+    pub fn verify(&self) -> bool {
+        let message = Self::signing_message(&self.nonce, self.peer_id.as_deref(), self.timestamp);
+        verify_ownership(&self.public_key, &message, &self.signature)
+    }
+}
+
+/// Where the funds behind a [`ReserveProof`] are held on L1. Only sources
+/// a verifier can look up on L1 are offered: a wallet balance is
+/// confidential, so a proof of one would rest on the node's word alone.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReserveSource {
+    /// Collateral locked for a channel the signer participates in
+    LockedCollateral { channel_id: Hash },
+}
+
+/// Signed statement that a node has at least `amount` µT locked on L1.
+///
+/// The verifier supplies the nonce, so a proof cannot be replayed to a
+/// different buyer, and only a signature is revealed - never a key. The
+/// signature only says who makes the claim; a verifier also checks the lock
+/// on L1 (see `MarketplaceManager::verify_reserve_proof`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReserveProof {
+    /// Node public key that produced the signature
+    pub public_key: PublicKey,
+
+    /// Where the reserves are held
+    pub source: ReserveSource,
+
+    /// Amount in µT the node vouches for
+    pub amount: u64,
+
+    /// L1 chain height the amount was read at
+    pub block_height: u64,
+
+    /// Caller-supplied challenge
+    pub nonce: Vec<u8>,
+
+    /// When the proof was produced
+    pub timestamp: u64,
+
+    /// Signature over the proof message
+    pub signature: Signature,
+}
+
+impl ReserveProof {
+    /// Build the message signed for a reserve proof
+    pub fn signing_message(
+        source: &ReserveSource,
+        amount: u64,
+        block_height: u64,
+        nonce: &[u8],
+        timestamp: u64,
+    ) -> Vec<u8> {
+        let ReserveSource::LockedCollateral { channel_id } = source;
+        let mut message = channel_id.to_vec();
+        message.extend_from_slice(&amount.to_le_bytes());
+        message.extend_from_slice(&block_height.to_le_bytes());
+        message.extend_from_slice(&(nonce.len() as u32).to_le_bytes());
+        message.extend_from_slice(nonce);
+        message.extend_from_slice(&timestamp.to_le_bytes());
+        SigningContext::ReserveProof.message(&message)
+    }
+
+    /// Verify the signature, that it answers `nonce`, and that it is fresh
+    pub fn verify_with(&self, nonce: &[u8], clock: &dyn Clock, window: TimestampWindow) -> Result<(), String> {
+        if self.nonce != nonce {
+            return Err("Proof does not answer this challenge".to_string());
+        }
+
+        let message = Self::signing_message(&self.source, self.amount, self.block_height, &self.nonce, self.timestamp);
+        if !verify_ownership(&self.public_key, &message, &self.signature) {
+            return Err("Invalid signature".to_string());
+        }
+
+        window.check(clock.now(), Timestamp::from_secs(self.timestamp))
+            .map_err(|e| format!("Proof expired: {}", e))
+    }
 }
 
 /// Helper to verify ownership of a public key
@@ -89,7 +271,6 @@ mod tests {
     use tari_l2_common::crypto::KeyPair;
 
     #[test]
-    #[ignore] // TODO: verify() method not implemented on PublicKey
     fn test_signed_action() {
         let keypair = KeyPair::generate();
         let payload = "test message".to_string();
@@ -100,7 +281,105 @@ mod tests {
             |msg| keypair.sign(msg)
         ).unwrap();
 
-        // assert!(signed.verify().is_ok());
+        assert!(signed.verify().is_ok());
         assert_eq!(signed.signer(), &keypair.public_key());
+
+        let mut tampered = signed.clone();
+        tampered.payload = "other message".to_string();
+        assert!(tampered.verify().is_err());
+
+        let other = KeyPair::generate();
+        let mut wrong_signer = signed;
+        wrong_signer.public_key = other.public_key();
+        assert!(wrong_signer.verify().is_err());
+    }
+
+    #[test]
+    fn test_signed_action_timestamp_window() {
+        use tari_l2_common::clock::ManualClock;
+
+        let keypair = KeyPair::generate();
+        let clock = ManualClock::new(Timestamp::from_secs(1_700_000_000));
+        let signed = SignedAction::new_at(1u32, keypair.public_key(), clock.now(), |msg| keypair.sign(msg)).unwrap();
+        assert!(signed.verify_with(&clock, ACTION_TIMESTAMP_WINDOW).is_ok());
+
+        // Stale after the window
+        clock.advance(301);
+        assert!(signed.verify_with(&clock, ACTION_TIMESTAMP_WINDOW).is_err());
+
+        // Signed by a peer whose clock runs ahead
+        clock.set(Timestamp::from_secs(1_700_000_000 - 31));
+        assert!(signed.verify_with(&clock, ACTION_TIMESTAMP_WINDOW).is_err());
+        clock.advance(1);
+        assert!(signed.verify_with(&clock, ACTION_TIMESTAMP_WINDOW).is_ok());
+    }
+
+    #[test]
+    fn test_seen_actions_reject_replays_until_pruned() {
+        let keypair = KeyPair::generate();
+        let signed = SignedAction::new_at(1u32, keypair.public_key(), Timestamp::from_secs(1_000), |msg| keypair.sign(msg)).unwrap();
+        let later = SignedAction::new_at(1u32, keypair.public_key(), Timestamp::from_secs(1_001), |msg| keypair.sign(msg)).unwrap();
+        assert_ne!(signed.id().unwrap(), later.id().unwrap());
+
+        let mut seen = SeenActions::default();
+        assert!(seen.insert(signed.id().unwrap(), signed.timestamp).is_ok());
+        assert!(seen.insert(signed.id().unwrap(), signed.timestamp).is_err());
+        assert!(seen.insert(later.id().unwrap(), later.timestamp).is_ok());
+
+        assert_eq!(seen.prune(1_001), vec![signed.id().unwrap()]);
+        assert_eq!(seen.len(), 1);
+    }
+
+    #[test]
+    fn test_node_attestation() {
+        let keypair = KeyPair::generate();
+        let nonce = b"challenge".to_vec();
+        let peer_id = Some("12D3KooWExample".to_string());
+        let timestamp = 1_700_000_000;
+
+        let message = NodeAttestation::signing_message(&nonce, peer_id.as_deref(), timestamp);
+        let mut attestation = NodeAttestation {
+            public_key: keypair.public_key(),
+            peer_id,
+            nonce,
+            timestamp,
+            signature: keypair.sign(&message),
+        };
+        assert!(attestation.verify());
+
+        attestation.nonce = b"other".to_vec();
+        assert!(!attestation.verify());
+    }
+
+    #[test]
+    fn test_reserve_proof() {
+        use tari_l2_common::clock::ManualClock;
+
+        let keypair = KeyPair::generate();
+        let clock = ManualClock::new(Timestamp::from_secs(1_700_000_000));
+        let source = ReserveSource::LockedCollateral { channel_id: Hash::new([7u8; 32]) };
+        let nonce = b"buyer-challenge".to_vec();
+        let timestamp = clock.now().as_secs();
+
+        let message = ReserveProof::signing_message(&source, 5_000_000, 100, &nonce, timestamp);
+        let mut proof = ReserveProof {
+            public_key: keypair.public_key(),
+            source,
+            amount: 5_000_000,
+            block_height: 100,
+            nonce: nonce.clone(),
+            timestamp,
+            signature: keypair.sign(&message),
+        };
+        assert!(proof.verify_with(&nonce, &clock, ACTION_TIMESTAMP_WINDOW).is_ok());
+        assert!(proof.verify_with(b"other", &clock, ACTION_TIMESTAMP_WINDOW).is_err());
+
+        clock.advance(301);
+        assert!(proof.verify_with(&nonce, &clock, ACTION_TIMESTAMP_WINDOW).is_err());
+        clock.set(Timestamp::from_secs(timestamp));
+
+        // Inflating the amount breaks the signature
+        proof.amount = 50_000_000;
+        assert!(proof.verify_with(&nonce, &clock, ACTION_TIMESTAMP_WINDOW).is_err());
     }
 }
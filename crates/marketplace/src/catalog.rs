@@ -0,0 +1,157 @@
+//! Content addressing for seller catalogs.
+//!
+//! Each listing is identified by the hash of its canonical (bincode)
+//! encoding, and a seller commits to their active listings with a signed
+//! Merkle root over those hashes, ordered by listing ID. Sellers gossip the
+//! root periodically. A node that synced a catalog through an untrusted
+//! relay can then tell whether it holds exactly what the seller published,
+//! not a subset or an outdated version.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{Hash, PublicKey, Signature, Timestamp, canonical};
+use tari_l2_common::crypto::SigningContext;
+use tari_l2_common::merkle::{leaf_hash, MerkleTree};
+use tari_l2_state_channel::state::Listing;
+
+/// Hash of a listing's canonical encoding
+pub fn listing_content_hash(listing: &Listing) -> Result<Hash, String> {
+    let bytes = canonical::encode(listing)?;
+    Ok(leaf_hash(&bytes))
+}
+
+/// Merkle root over `seller`'s active listings among `listings`, and how
+/// many there are
+pub fn catalog_root(seller: &PublicKey, listings: &[Listing]) -> Result<(Hash, u32), String> {
+    let mut active: Vec<&Listing> = listings.iter()
+        .filter(|l| l.seller == *seller && l.active)
+        .collect();
+    active.sort_by(|a, b| a.id.as_bytes().cmp(b.id.as_bytes()));
+
+    let leaves = active.iter()
+        .map(|l| listing_content_hash(l))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((MerkleTree::from_leaf_hashes(leaves).root(), active.len() as u32))
+}
+
+/// A seller's signed commitment to their active listings
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CatalogRoot {
+    pub seller: PublicKey,
+
+    /// Merkle root over the content hashes of the active listings
+    pub root: Hash,
+
+    /// Number of active listings
+    pub listings: u32,
+
+    /// When the root was signed; a newer root replaces an older one
+    pub timestamp: u64,
+
+    pub signature: Signature,
+}
+
+impl CatalogRoot {
+    /// Commit to `seller`'s active listings, signing with `sign_fn`
+    pub fn new(
+        seller: PublicKey,
+        listings: &[Listing],
+        now: Timestamp,
+        sign_fn: impl FnOnce(&[u8]) -> Signature,
+    ) -> Result<Self, String> {
+        let (root, count) = catalog_root(&seller, listings)?;
+        let timestamp = now.as_secs();
+        let signature = sign_fn(&Self::signing_message(&seller, &root, count, timestamp));
+        Ok(Self { seller, root, listings: count, timestamp, signature })
+    }
+
+    pub fn signing_message(seller: &PublicKey, root: &Hash, listings: u32, timestamp: u64) -> Vec<u8> {
+        let mut message = seller.as_bytes().to_vec();
+        message.extend_from_slice(root.as_bytes());
+        message.extend_from_slice(&listings.to_le_bytes());
+        message.extend_from_slice(&timestamp.to_le_bytes());
+        SigningContext::CatalogRoot.message(&message)
+    }
+
+    /// Check the seller signed the root
+    pub fn verify(&self) -> bool {
+        let message = Self::signing_message(&self.seller, &self.root, self.listings, self.timestamp);
+        self.seller.verify(&message, &self.signature)
+    }
+}
+
+/// Whether the listings held for a seller match the root they announced
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CatalogIntegrity {
+    /// No root has been received from the seller
+    #[default]
+    Unannounced,
+    /// The held listings are exactly the ones the seller committed to
+    Verified,
+    /// Listings are missing, extra or outdated
+    Diverged { announced: u32, held: u32 },
+}
+
+impl CatalogIntegrity {
+    /// Compare the listings held for the root's seller against the root
+    pub fn check(root: Option<&CatalogRoot>, held: &[Listing]) -> Self {
+        let Some(root) = root else {
+            return CatalogIntegrity::Unannounced;
+        };
+        match catalog_root(&root.seller, held) {
+            Ok((hash, _)) if hash == root.root => CatalogIntegrity::Verified,
+            Ok((_, count)) => CatalogIntegrity::Diverged { announced: root.listings, held: count },
+            Err(_) => CatalogIntegrity::Diverged { announced: root.listings, held: 0 },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_common::{Amount, crypto::KeyPair};
+
+    fn listing(seller: PublicKey, price: u64) -> Listing {
+        Listing {
+            id: Hash::random(),
+            seller,
+            title: "Lamp".to_string(),
+            description: String::new(),
+            price: Amount::new(price),
+            ipfs_hash: String::new(),
+            active: true,
+            category: "home".to_string(),
+            fiat_reference: None,
+        }
+    }
+
+    #[test]
+    fn test_catalog_root_detects_withheld_and_stale_listings() {
+        let seller = KeyPair::generate();
+        let listings: Vec<Listing> = (1..=3).map(|i| listing(seller.public_key(), i * 100)).collect();
+        let root = CatalogRoot::new(seller.public_key(), &listings, Timestamp::from_secs(1_000), |m| seller.sign(m)).unwrap();
+        assert!(root.verify());
+        assert_eq!(root.listings, 3);
+
+        // Order does not matter, other sellers' listings are ignored
+        let mut held: Vec<Listing> = listings.iter().rev().cloned().collect();
+        held.push(listing(KeyPair::generate().public_key(), 1));
+        assert_eq!(CatalogIntegrity::check(Some(&root), &held), CatalogIntegrity::Verified);
+
+        // A relay withholding one listing
+        assert_eq!(
+            CatalogIntegrity::check(Some(&root), &listings[..2]),
+            CatalogIntegrity::Diverged { announced: 3, held: 2 }
+        );
+
+        // Or serving an old price
+        let mut stale = listings.clone();
+        stale[0].price = Amount::new(1);
+        assert!(matches!(CatalogIntegrity::check(Some(&root), &stale), CatalogIntegrity::Diverged { .. }));
+
+        let mut forged = root.clone();
+        forged.listings = 2;
+        assert!(!forged.verify());
+        assert_eq!(CatalogIntegrity::check(None, &listings), CatalogIntegrity::Unannounced);
+    }
+}
@@ -0,0 +1,108 @@
+//! When channels are anchored on L1.
+//!
+//! A channel is checkpointed once when it first becomes active, then again
+//! whenever it has unanchored updates and either the policy interval has
+//! passed since its last checkpoint or enough updates have built up.
+//! Channels with nothing new are skipped, so idle channels cost no L1 fees.
+//! A failed checkpoint is retried with exponential backoff.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::Timestamp;
+use tari_l2_state_channel::channel::ChannelMetrics;
+
+/// Delay before the first retry; it doubles with each further attempt
+const BASE_RETRY_SECS: u64 = 30;
+
+/// Longest delay between retries
+const MAX_RETRY_SECS: u64 = 3600;
+
+/// How often channels are checkpointed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointPolicy {
+    /// Seconds after a checkpoint before newer updates are anchored
+    pub interval: u64,
+
+    /// Anchor as soon as this many updates are unanchored, without waiting
+    /// for the interval
+    pub every_updates: Option<u64>,
+}
+
+impl Default for CheckpointPolicy {
+    fn default() -> Self {
+        Self {
+            interval: 3600,
+            every_updates: None,
+        }
+    }
+}
+
+impl CheckpointPolicy {
+    /// Whether a channel at `nonce` with `metrics` should be anchored at `now`
+    pub fn is_due(&self, metrics: &ChannelMetrics, nonce: u64, now: Timestamp) -> bool {
+        let Some(last) = metrics.checkpointed_at else {
+            return true;
+        };
+        let unanchored = nonce.saturating_sub(metrics.checkpointed_nonce);
+        if unanchored == 0 {
+            return false;
+        }
+        now.as_secs() >= last.as_secs().saturating_add(self.interval)
+            || self.every_updates.is_some_and(|n| unanchored >= n)
+    }
+}
+
+/// Backoff for a channel whose checkpoint failed
+#[derive(Clone, Debug)]
+pub struct CheckpointRetry {
+    /// Failed attempts so far
+    pub attempts: u32,
+
+    /// When the next attempt may be made
+    pub next_attempt: Timestamp,
+}
+
+impl CheckpointRetry {
+    pub fn is_due(&self, now: Timestamp) -> bool {
+        now >= self.next_attempt
+    }
+
+    /// Record a failure at `now` and push the next attempt back exponentially
+    pub fn record_failure(retry: Option<CheckpointRetry>, now: Timestamp) -> Self {
+        let attempts = retry.map_or(0, |r| r.attempts);
+        let delay = BASE_RETRY_SECS
+            .saturating_mul(1u64 << attempts.min(16))
+            .min(MAX_RETRY_SECS);
+        Self {
+            attempts: attempts + 1,
+            next_attempt: Timestamp::from_secs(now.as_secs().saturating_add(delay)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_due_by_interval_or_update_count() {
+        let policy = CheckpointPolicy { interval: 3600, every_updates: Some(10) };
+        let mut metrics = ChannelMetrics::default();
+        let now = Timestamp::from_secs(10_000);
+
+        // Never anchored, even with no updates yet
+        assert!(policy.is_due(&metrics, 0, now));
+
+        metrics.checkpointed_at = Some(now);
+        metrics.checkpointed_nonce = 5;
+        assert!(!policy.is_due(&metrics, 5, Timestamp::from_secs(20_000)));
+        assert!(!policy.is_due(&metrics, 6, Timestamp::from_secs(13_599)));
+        assert!(policy.is_due(&metrics, 6, Timestamp::from_secs(13_600)));
+        assert!(policy.is_due(&metrics, 15, now));
+
+        let first = CheckpointRetry::record_failure(None, now);
+        assert_eq!(first.next_attempt, Timestamp::from_secs(10_030));
+        let second = CheckpointRetry::record_failure(Some(first), now);
+        assert_eq!((second.attempts, second.next_attempt), (2, Timestamp::from_secs(10_060)));
+        assert!(!second.is_due(now));
+    }
+}
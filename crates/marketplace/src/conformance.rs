@@ -0,0 +1,348 @@
+//! Canonical test vectors for SDKs in other languages.
+//!
+//! [`generate`] derives every vector from fixed inputs, so its output is
+//! stable across runs and platforms. [`verify`] checks a vector file from
+//! its own inputs: an SDK that emits the same format passes if it derives
+//! the same keys and addresses, builds byte-identical signing payloads and
+//! broadcast encodings, and produces signatures that verify.
+//!
+//! Emit or check a file with `tari-l2-node conformance`. The expected output
+//! is committed at `tests/vectors/conformance.json`; a change that alters it
+//! must bump [`VECTORS_VERSION`] and regenerate the file.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::address::{decode_address, encode_address, AddressNetwork};
+use tari_l2_common::crypto::{KeyBranch, KeyPair, SigningContext};
+use tari_l2_common::error::{L2Error, Result};
+use tari_l2_common::{Amount, Hash, PublicKey, Signature, SignatureScheme, canonical};
+use tari_l2_p2p::{wire, L2Message, Topic, Topics, PROTOCOL_VERSION};
+use tari_l2_state_channel::{Listing, Order, OrderStatus, StateUpdate};
+
+/// Bumped whenever an encoding or signing payload changes
+pub const VECTORS_VERSION: u32 = 7;
+
+/// Master seed every key in the vectors derives from. Public; never fund it.
+pub const TEST_SEED_HEX: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+/// Fixed broadcast time, so encodings do not depend on the clock
+const TEST_TIMESTAMP: u64 = 1_700_000_000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TestVectors {
+    pub version: u32,
+    /// Hex master seed for the key derivations below
+    pub seed: String,
+    pub addresses: Vec<AddressVector>,
+    pub state_updates: Vec<StateUpdateVector>,
+    pub listing_broadcasts: Vec<ListingBroadcastVector>,
+}
+
+/// Key derived from the seed and its address on one network
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddressVector {
+    /// `ed25519` or `schnorr`
+    pub scheme: String,
+    /// `node`, `channel` or `order`
+    pub branch: String,
+    pub index: u64,
+    /// Address human-readable prefix, e.g. `tl2e`
+    pub network: String,
+    pub public_key: PublicKey,
+    pub address: String,
+}
+
+/// Signing payload for a channel update
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StateUpdateVector {
+    pub name: String,
+    pub update: StateUpdate,
+    pub nonce: u64,
+    pub signer: PublicKey,
+    /// Hex of `bincode(update) || nonce (u64 LE)`
+    pub payload: String,
+    /// Hex of the bytes actually signed: the payload under the state update context
+    pub signing_message: String,
+    pub signature: Signature,
+}
+
+/// Seller-signed listing and its gossip encoding
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListingBroadcastVector {
+    pub name: String,
+    pub listing: Listing,
+    pub timestamp: u64,
+    /// Hex of `bincode(listing)` under the listing context
+    pub signing_message: String,
+    pub signature: Signature,
+    /// Gossip topic the envelope is addressed to
+    pub topic: String,
+    /// Hex of the `L2Message::ListingBroadcast` envelope sent over gossipsub
+    /// at the current protocol version, signed by the seller's key at
+    /// `timestamp` for `topic`
+    pub encoded: String,
+}
+
+fn seed() -> Vec<u8> {
+    hex::decode(TEST_SEED_HEX).expect("test seed is valid hex")
+}
+
+fn parse_branch(label: &str) -> Result<KeyBranch> {
+    [KeyBranch::Node, KeyBranch::Channel, KeyBranch::Order]
+        .into_iter()
+        .find(|b| b.label() == label)
+        .ok_or_else(|| L2Error::InvalidParameter(format!("unknown key branch '{}'", label)))
+}
+
+fn scheme_label(scheme: SignatureScheme) -> &'static str {
+    match scheme {
+        SignatureScheme::Ed25519 => "ed25519",
+        SignatureScheme::Schnorr => "schnorr",
+    }
+}
+
+fn derive(seed: &[u8], scheme: &str, branch: KeyBranch, index: u64) -> Result<KeyPair> {
+    match scheme {
+        "ed25519" => KeyPair::derive(seed, branch, index),
+        "schnorr" => KeyPair::derive_ristretto(seed, branch, index),
+        other => return Err(L2Error::InvalidParameter(format!("unknown scheme '{}'", other))),
+    }
+    .map_err(L2Error::InvalidParameter)
+}
+
+fn update_payload(update: &StateUpdate, nonce: u64) -> Result<Vec<u8>> {
+    let mut payload = canonical::encode(update).map_err(L2Error::SerializationError)?;
+    payload.extend_from_slice(&nonce.to_le_bytes());
+    Ok(payload)
+}
+
+fn listing_signing_message(listing: &Listing) -> Result<Vec<u8>> {
+    let bytes = canonical::encode(listing).map_err(L2Error::SerializationError)?;
+    Ok(SigningContext::Listing.message(&bytes))
+}
+
+fn broadcast_encoding(listing: &Listing, signature: &Signature, timestamp: u64, topic: &str, sender: &KeyPair) -> Result<Vec<u8>> {
+    let message = L2Message::ListingBroadcast {
+        listing: listing.clone(),
+        signature: signature.clone(),
+        timestamp,
+    };
+    let recipient = wire::Recipient::Topic(topic.to_string());
+    wire::encode(&message, PROTOCOL_VERSION, &recipient, timestamp, sender)
+        .map_err(|e| L2Error::SerializationError(e.to_string()))
+}
+
+/// Key the vector listings are sold and broadcast under
+fn seller_key(seed: &[u8]) -> Result<KeyPair> {
+    KeyPair::derive(seed, KeyBranch::Channel, 0).map_err(L2Error::InvalidParameter)
+}
+
+fn mismatch(section: &str, name: &str, field: &str) -> L2Error {
+    L2Error::InvalidParameter(format!("{} vector '{}': {} does not match", section, name, field))
+}
+
+/// Build the canonical vectors
+pub fn generate() -> Result<TestVectors> {
+    let seed = seed();
+
+    let mut addresses = Vec::new();
+    for scheme in [SignatureScheme::Ed25519, SignatureScheme::Schnorr] {
+        for branch in [KeyBranch::Node, KeyBranch::Channel, KeyBranch::Order] {
+            for index in 0..2 {
+                let key = derive(&seed, scheme_label(scheme), branch, index)?.public_key();
+                for network in AddressNetwork::ALL {
+                    addresses.push(AddressVector {
+                        scheme: scheme_label(scheme).to_string(),
+                        branch: branch.label().to_string(),
+                        index,
+                        network: network.hrp().to_string(),
+                        public_key: key,
+                        address: encode_address(network, &key),
+                    });
+                }
+            }
+        }
+    }
+
+    let seller = seller_key(&seed)?;
+    let buyer = KeyPair::derive_ristretto(&seed, KeyBranch::Channel, 1).map_err(L2Error::InvalidParameter)?;
+    let listing = Listing {
+        id: Hash::new([0x11; 32]),
+        seller: seller.public_key(),
+        title: "Hand-thrown mug".to_string(),
+        description: "Stoneware, 350 ml".to_string(),
+        price: Amount::new(25_000),
+        ipfs_hash: "QmTestVectorImage".to_string(),
+        active: true,
+        category: "home".to_string(),
+        fiat_reference: None,
+    };
+
+    let updates = [
+        ("transfer", StateUpdate::Transfer {
+            from: buyer.public_key(),
+            to: seller.public_key(),
+            amount: Amount::new(1_000),
+        }, 1, &buyer),
+        ("create_listing", StateUpdate::CreateListing { listing: listing.clone() }, 2, &seller),
+        ("create_order", StateUpdate::CreateOrder {
+            order: Order {
+                id: Hash::new([0x22; 32]),
+                listing_id: listing.id,
+                buyer: buyer.public_key(),
+                seller: seller.public_key(),
+                amount: listing.price,
+                status: OrderStatus::Pending,
+            },
+        }, 3, &buyer),
+        ("update_order_status", StateUpdate::UpdateOrderStatus {
+            order_id: Hash::new([0x22; 32]),
+            status: OrderStatus::Shipping,
+        }, 4, &seller),
+        ("lock_escrow", StateUpdate::LockEscrow {
+            order_id: Hash::new([0x22; 32]),
+        }, 5, &buyer),
+    ];
+    let mut state_updates = Vec::new();
+    for (name, update, nonce, signer) in updates {
+        let payload = update_payload(&update, nonce)?;
+        let signing_message = SigningContext::StateUpdate.message(&payload);
+        state_updates.push(StateUpdateVector {
+            name: name.to_string(),
+            signer: signer.public_key(),
+            payload: hex::encode(&payload),
+            signing_message: hex::encode(&signing_message),
+            signature: signer.sign(&signing_message),
+            update,
+            nonce,
+        });
+    }
+
+    let topic = Topics::default().name(Topic::Marketplace);
+    let inactive = Listing { id: Hash::new([0x33; 32]), active: false, ipfs_hash: String::new(), ..listing.clone() };
+    let mut listing_broadcasts = Vec::new();
+    for (name, listing) in [("active", listing), ("inactive_no_image", inactive)] {
+        let signing_message = listing_signing_message(&listing)?;
+        let signature = seller.sign(&signing_message);
+        listing_broadcasts.push(ListingBroadcastVector {
+            name: name.to_string(),
+            timestamp: TEST_TIMESTAMP,
+            signing_message: hex::encode(&signing_message),
+            encoded: hex::encode(broadcast_encoding(&listing, &signature, TEST_TIMESTAMP, &topic, &seller)?),
+            topic: topic.clone(),
+            signature,
+            listing,
+        });
+    }
+
+    Ok(TestVectors {
+        version: VECTORS_VERSION,
+        seed: TEST_SEED_HEX.to_string(),
+        addresses,
+        state_updates,
+        listing_broadcasts,
+    })
+}
+
+/// Check every vector in `vectors` against this implementation
+pub fn verify(vectors: &TestVectors) -> Result<()> {
+    if vectors.version != VECTORS_VERSION {
+        return Err(L2Error::InvalidParameter(format!(
+            "vectors are version {}, expected {}", vectors.version, VECTORS_VERSION
+        )));
+    }
+    let seed = hex::decode(&vectors.seed).map_err(|e| L2Error::InvalidParameter(format!("seed: {}", e)))?;
+
+    for v in &vectors.addresses {
+        let name = format!("{}/{}/{}/{}", v.scheme, v.branch, v.index, v.network);
+        let key = derive(&seed, &v.scheme, parse_branch(&v.branch)?, v.index)?.public_key();
+        if key != v.public_key {
+            return Err(mismatch("address", &name, "public_key"));
+        }
+        let network = AddressNetwork::from_hrp(&v.network)
+            .ok_or_else(|| L2Error::InvalidParameter(format!("unknown network '{}'", v.network)))?;
+        if encode_address(network, &key) != v.address || decode_address(&v.address)? != (network, key) {
+            return Err(mismatch("address", &name, "address"));
+        }
+    }
+
+    for v in &vectors.state_updates {
+        let payload = update_payload(&v.update, v.nonce)?;
+        if hex::encode(&payload) != v.payload {
+            return Err(mismatch("state update", &v.name, "payload"));
+        }
+        let signing_message = SigningContext::StateUpdate.message(&payload);
+        if hex::encode(&signing_message) != v.signing_message {
+            return Err(mismatch("state update", &v.name, "signing_message"));
+        }
+        if !v.signer.verify(&signing_message, &v.signature) {
+            return Err(mismatch("state update", &v.name, "signature"));
+        }
+    }
+
+    let seller = seller_key(&seed)?;
+    for v in &vectors.listing_broadcasts {
+        let signing_message = listing_signing_message(&v.listing)?;
+        if hex::encode(&signing_message) != v.signing_message {
+            return Err(mismatch("listing broadcast", &v.name, "signing_message"));
+        }
+        if !v.listing.seller.verify(&signing_message, &v.signature) {
+            return Err(mismatch("listing broadcast", &v.name, "signature"));
+        }
+        if hex::encode(broadcast_encoding(&v.listing, &v.signature, v.timestamp, &v.topic, &seller)?) != v.encoded {
+            return Err(mismatch("listing broadcast", &v.name, "encoded"));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PINNED: &str = include_str!("../tests/vectors/conformance.json");
+
+    #[test]
+    fn test_vectors_match_committed_file() {
+        // Byte-for-byte what `tari-l2-node conformance` emits today
+        let vectors = generate().unwrap();
+        assert_eq!(serde_json::to_string_pretty(&vectors).unwrap(), PINNED);
+
+        // and what other SDKs read back through JSON still verifies
+        let pinned: TestVectors = serde_json::from_str(PINNED).unwrap();
+        assert!(verify(&pinned).is_ok());
+    }
+
+    #[test]
+    fn test_signing_message_layout() {
+        let vectors = generate().unwrap();
+        let transfer = &vectors.state_updates[0];
+
+        // One length byte, the context tag, then the payload ending in the LE nonce
+        let tag = SigningContext::StateUpdate.tag();
+        let expected_prefix = format!("{:02x}{}", tag.len(), hex::encode(tag));
+        assert!(transfer.signing_message.starts_with(&expected_prefix));
+        assert!(transfer.signing_message.ends_with(&transfer.payload));
+        assert!(transfer.payload.ends_with(&hex::encode(1u64.to_le_bytes())));
+
+        // Same bytes the channel code signs
+        let signed = tari_l2_state_channel::update::SignedStateUpdate::new(transfer.update.clone(), transfer.nonce);
+        assert_eq!(hex::encode(signed.signing_message()), transfer.signing_message);
+    }
+
+    #[test]
+    fn test_verify_rejects_tampering() {
+        let mut vectors = generate().unwrap();
+        vectors.state_updates[1].nonce += 1;
+        assert!(verify(&vectors).is_err());
+
+        let mut vectors = generate().unwrap();
+        vectors.addresses[0].address = vectors.addresses[1].address.clone();
+        assert!(verify(&vectors).is_err());
+
+        let mut vectors = generate().unwrap();
+        vectors.listing_broadcasts[0].timestamp += 1;
+        let err = verify(&vectors).unwrap_err().to_string();
+        assert!(err.contains("encoded"), "{}", err);
+    }
+}
@@ -0,0 +1,76 @@
+//! Address book of known counterparties.
+//!
+//! A contact maps a short label such as `alice` to an L2 public key, and
+//! optionally to the Tari address L1 payouts to that party go to. RPC
+//! methods that take a counterparty accept a label wherever they accept a
+//! key or address, so nobody has to paste 64 characters of hex. Labels are
+//! at most 32 characters, so they can never be mistaken for a hex key.
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use tari_common_types::tari_address::TariAddress;
+use tari_l2_common::{PublicKey, Timestamp};
+
+/// Longest label allowed
+pub const MAX_LABEL_LEN: usize = 32;
+
+/// A named counterparty
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    pub label: String,
+    pub public_key: PublicKey,
+    /// Tari address L1 payouts to this party go to
+    #[serde(default)]
+    pub tari_address: Option<String>,
+    pub added_at: Timestamp,
+}
+
+impl Contact {
+    /// A contact with a checked label and Tari address
+    pub fn new(label: &str, public_key: PublicKey, tari_address: Option<String>, now: Timestamp) -> Result<Self, String> {
+        if let Some(address) = &tari_address {
+            TariAddress::from_str(address).map_err(|e| format!("Invalid Tari address {}: {}", address, e))?;
+        }
+        Ok(Self {
+            label: normalize_label(label)?,
+            public_key,
+            tari_address,
+            added_at: now,
+        })
+    }
+}
+
+/// The stored form of a label: lowercase, so lookups ignore case. Labels
+/// start with a letter and hold only letters, digits, `-` and `_`.
+pub fn normalize_label(label: &str) -> Result<String, String> {
+    let label = label.trim().to_ascii_lowercase();
+    if label.is_empty() || label.len() > MAX_LABEL_LEN {
+        return Err(format!("Contact labels are 1 to {} characters", MAX_LABEL_LEN));
+    }
+    if !label.starts_with(|c: char| c.is_ascii_lowercase()) {
+        return Err(format!("Contact label must start with a letter: {}", label));
+    }
+    if !label.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_') {
+        return Err(format!("Contact labels may only hold letters, digits, '-' and '_': {}", label));
+    }
+    Ok(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels_are_normalized_and_checked() {
+        assert_eq!(normalize_label(" Alice_2 ").unwrap(), "alice_2");
+        assert!(normalize_label("").is_err());
+        assert!(normalize_label("2fast").is_err());
+        assert!(normalize_label("bob smith").is_err());
+        assert!(normalize_label(&"a".repeat(MAX_LABEL_LEN + 1)).is_err());
+
+        let key = PublicKey::new([3u8; 32]);
+        let contact = Contact::new("Carol", key, None, Timestamp::from_secs(1)).unwrap();
+        assert_eq!(contact.label, "carol");
+        assert!(Contact::new("carol", key, Some("not an address".to_string()), Timestamp::from_secs(1)).is_err());
+    }
+}
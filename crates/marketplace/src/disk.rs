@@ -0,0 +1,138 @@
+//! Disk space the node has left in its data directory.
+//!
+//! The node measures its data directory and the free space on its
+//! filesystem periodically, against an optional quota, and warns the
+//! operator once usage passes `warn_percent`. Past `degraded_percent`, or
+//! with less than `min_free` bytes left, it enters a degraded mode that stops
+//! taking on new state (gossiped listings, new channels) so sled writes for
+//! existing channels keep succeeding.
+
+use serde::{Deserialize, Serialize};
+
+/// How close the data directory is to running out of space
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskLevel {
+    #[default]
+    Ok,
+    Warning,
+    /// New listings and channels are refused until space is freed
+    Degraded,
+}
+
+/// Disk usage thresholds
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiskPolicy {
+    /// Most bytes the data directory may use (0 for no quota; the
+    /// filesystem's free space always applies)
+    pub quota: u64,
+
+    /// Usage, as a percentage of the space the node can use, at which to warn
+    pub warn_percent: u64,
+
+    /// Usage at which the node enters degraded mode
+    pub degraded_percent: u64,
+
+    /// Free bytes below which the node enters degraded mode regardless of usage
+    pub min_free: u64,
+}
+
+impl Default for DiskPolicy {
+    fn default() -> Self {
+        Self {
+            quota: 0,
+            warn_percent: 80,
+            degraded_percent: 95,
+            min_free: 512 * 1024 * 1024,
+        }
+    }
+}
+
+impl DiskPolicy {
+    /// Status of a data directory using `used` bytes on a filesystem with
+    /// `free` bytes left
+    pub fn evaluate(&self, used: u64, free: u64, now: u64) -> DiskStatus {
+        let available = if self.quota > 0 {
+            free.min(self.quota.saturating_sub(used))
+        } else {
+            free
+        };
+        let capacity = used.saturating_add(available);
+        let percent = used.saturating_mul(100).checked_div(capacity).unwrap_or(100);
+
+        let level = if available < self.min_free || percent >= self.degraded_percent {
+            DiskLevel::Degraded
+        } else if percent >= self.warn_percent {
+            DiskLevel::Warning
+        } else {
+            DiskLevel::Ok
+        };
+
+        DiskStatus {
+            used_bytes: used,
+            available_bytes: available,
+            capacity_bytes: capacity,
+            usage_percent: percent,
+            level,
+            checked_at: now,
+        }
+    }
+}
+
+/// Result of the latest disk check
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct DiskStatus {
+    /// Bytes used by the data directory
+    pub used_bytes: u64,
+
+    /// Bytes the data directory may still grow by
+    pub available_bytes: u64,
+
+    /// `used_bytes` plus `available_bytes`
+    pub capacity_bytes: u64,
+
+    pub usage_percent: u64,
+
+    pub level: DiskLevel,
+
+    /// When the check ran (0 if it has not run yet)
+    pub checked_at: u64,
+}
+
+impl DiskStatus {
+    pub fn is_degraded(&self) -> bool {
+        self.level == DiskLevel::Degraded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_follow_thresholds() {
+        let policy = DiskPolicy { quota: 1000, warn_percent: 80, degraded_percent: 100, min_free: 0 };
+
+        assert_eq!(policy.evaluate(500, 10_000, 0).level, DiskLevel::Ok);
+
+        // The quota caps the space available, however much the disk has free
+        let status = policy.evaluate(850, 10_000, 0);
+        assert_eq!((status.available_bytes, status.usage_percent, status.level), (150, 85, DiskLevel::Warning));
+
+        // A nearly full filesystem warns below the quota
+        let status = policy.evaluate(100, 5, 0);
+        assert_eq!((status.capacity_bytes, status.level), (105, DiskLevel::Warning));
+    }
+
+    #[test]
+    fn test_degrades_before_the_disk_fills() {
+        let policy = DiskPolicy { quota: 1000, warn_percent: 80, degraded_percent: 95, min_free: 10 };
+
+        assert!(!policy.evaluate(850, 10_000, 0).is_degraded());
+        assert!(policy.evaluate(960, 10_000, 0).is_degraded());
+
+        // Too few bytes left degrades the node at any usage
+        assert!(policy.evaluate(10, 5, 0).is_degraded());
+    }
+}
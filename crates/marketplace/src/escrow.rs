@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
-use tari_l2_common::{Amount, Hash, PublicKey, Timestamp};
+use tari_l2_common::{Amount, Hash, L2Error, PublicKey, Timestamp};
+use tari_l2_common::crypto::KeyPair;
+use tari_l2_common::error::Result;
+use tari_l2_common::vrf::{self, VrfProof};
+use tari_l2_state_channel::lifecycle::{self, Role, Transition, TransitionError};
+use tari_l2_state_channel::state::Order;
 
 /// Escrow contract status
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -22,6 +27,43 @@ pub enum EscrowStatus {
     Cancelled,
 }
 
+const fn row(from: EscrowStatus, to: EscrowStatus, roles: &'static [Role]) -> Transition<EscrowStatus> {
+    Transition { from, to, roles }
+}
+
+/// Every legal escrow move. The buyer funds, confirms receipt and asks for
+/// refunds, and the seller ships and approves refunds. Either side can
+/// dispute until the escrow settles, and an arbitrator settles a dispute by
+/// completing or refunding the escrow. Some moves have a further condition:
+/// a late-shipment refund needs the ship-by deadline passed, and the node's
+/// timers also release shipped escrows and escalate unanswered refund
+/// requests.
+pub const ESCROW_TRANSITIONS: &[Transition<EscrowStatus>] = {
+    use EscrowStatus::*;
+    const BUYER: &[Role] = &[Role::Buyer];
+    const SELLER: &[Role] = &[Role::Seller];
+    const PARTIES: &[Role] = &[Role::Buyer, Role::Seller];
+    const ARBITRATOR: &[Role] = &[Role::Arbitrator];
+    &[
+        row(Created, Funded, BUYER),
+        row(Created, Cancelled, PARTIES),
+        row(Created, Disputed, PARTIES),
+        row(Funded, Shipped, SELLER),
+        row(Funded, RefundRequested, BUYER),
+        row(Funded, Refunded, BUYER),
+        row(Funded, Disputed, PARTIES),
+        row(Shipped, Completed, BUYER),
+        row(Shipped, RefundRequested, BUYER),
+        row(Shipped, Disputed, PARTIES),
+        row(RefundRequested, Refunded, SELLER),
+        row(RefundRequested, Disputed, PARTIES),
+        // Raising a dispute again replaces its reason
+        row(Disputed, Disputed, PARTIES),
+        row(Disputed, Completed, ARBITRATOR),
+        row(Disputed, Refunded, ARBITRATOR),
+    ]
+};
+
 /// Escrow contract for a marketplace transaction
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EscrowContract {
@@ -31,6 +73,12 @@ pub struct EscrowContract {
     /// The listing being purchased
     pub listing_id: Hash,
 
+    /// The order this escrow secures
+    pub order_id: Hash,
+
+    /// Channel holding the order
+    pub channel_id: Hash,
+
     /// Buyer's public key
     pub buyer: PublicKey,
 
@@ -55,128 +103,364 @@ pub struct EscrowContract {
     /// Transaction ID on L1 (if funds locked on-chain)
     pub l1_tx_id: Option<String>,
 
+    /// Funds are locked from the buyer's channel balance rather than on L1
+    #[serde(default)]
+    pub funded_in_channel: bool,
+
+    /// Seconds after funding the seller has to ship; past that the buyer
+    /// can take a refund without the seller's approval
+    #[serde(default)]
+    pub ship_within: Option<u64>,
+
+    /// When the buyer funded the escrow
+    #[serde(default)]
+    pub funded_at: Option<Timestamp>,
+
     /// Shipping/tracking information
     pub tracking_info: Option<String>,
 
     /// Dispute reason (if any)
     pub dispute_reason: Option<String>,
+
+    /// Seller's L1 address, paid when an L1-funded escrow completes
+    #[serde(default)]
+    pub seller_address: Option<String>,
+
+    /// Buyer's L1 address, paid when an L1-funded escrow is refunded
+    #[serde(default)]
+    pub buyer_address: Option<String>,
+
+    /// L1 transaction that paid out the escrow
+    #[serde(default)]
+    pub payout_tx_id: Option<String>,
 }
 
 impl EscrowContract {
+    /// Escrow for `order` in `channel_id`; parties and amount come from the order
     pub fn new(
-        listing_id: Hash,
-        buyer: PublicKey,
-        seller: PublicKey,
-        amount: Amount,
+        channel_id: Hash,
+        order: &Order,
         timeout_period: u64,
+        now: Timestamp,
     ) -> Self {
-        let now = Timestamp::now();
         Self {
             id: Hash::random(),
-            listing_id,
-            buyer,
-            seller,
-            amount,
+            listing_id: order.listing_id,
+            order_id: order.id,
+            channel_id,
+            buyer: order.buyer,
+            seller: order.seller,
+            amount: order.amount,
             status: EscrowStatus::Created,
             created_at: now,
             updated_at: now,
             timeout_period,
             l1_tx_id: None,
+            funded_in_channel: false,
+            ship_within: None,
+            funded_at: None,
             tracking_info: None,
             dispute_reason: None,
+            seller_address: None,
+            buyer_address: None,
+            payout_tx_id: None,
+        }
+    }
+
+    /// Roles `key` holds in this escrow; none if it is not a party
+    pub fn roles_of(&self, key: &PublicKey) -> Vec<Role> {
+        let mut roles = Vec::new();
+        if *key == self.buyer {
+            roles.push(Role::Buyer);
+        }
+        if *key == self.seller {
+            roles.push(Role::Seller);
+        }
+        roles
+    }
+
+    /// Check that a key holding `roles` may move this escrow to `status`
+    pub fn check_transition(&self, roles: &[Role], status: &EscrowStatus) -> std::result::Result<(), TransitionError<EscrowStatus>> {
+        lifecycle::check_by(ESCROW_TRANSITIONS, &self.status, status, roles)
+    }
+
+    /// Move to `status` at `now` if the table allows it
+    fn advance(&mut self, status: EscrowStatus, now: Timestamp) -> Result<()> {
+        lifecycle::check(ESCROW_TRANSITIONS, &self.status, &status)?;
+        self.status = status;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Whether the escrow has reached a final status
+    pub fn is_settled(&self) -> bool {
+        matches!(self.status, EscrowStatus::Completed | EscrowStatus::Refunded | EscrowStatus::Cancelled)
+    }
+
+    /// Whether the escrow was funded on L1, so settling it has to pay the
+    /// payee out, and that has not happened yet
+    pub fn needs_l1_payout(&self) -> bool {
+        !self.funded_in_channel && self.l1_tx_id.is_some() && self.payout_tx_id.is_none()
+    }
+
+    /// Party the funds go to once settled, with the L1 address they gave
+    pub fn payee(&self) -> Option<(PublicKey, Option<&str>)> {
+        match self.status {
+            EscrowStatus::Completed => Some((self.seller, self.seller_address.as_deref())),
+            EscrowStatus::Refunded => Some((self.buyer, self.buyer_address.as_deref())),
+            _ => None,
+        }
+    }
+
+    /// Record the L1 address `party` is paid at. Leaves `updated_at` alone,
+    /// so it does not restart the auto-release timeout.
+    pub fn set_payout_address(&mut self, party: &PublicKey, address: String) -> Result<()> {
+        if self.is_settled() {
+            return Err(L2Error::InvalidStateTransition);
+        }
+        if *party == self.seller {
+            self.seller_address = Some(address);
+        } else if *party == self.buyer {
+            self.buyer_address = Some(address);
+        } else {
+            return Err(L2Error::InvalidParameter("Not a party to the escrow".to_string()));
         }
+        Ok(())
     }
 
     /// Check if escrow has timed out (auto-release condition)
-    pub fn is_timed_out(&self) -> bool {
+    pub fn is_timed_out(&self, now: Timestamp) -> bool {
         if self.status != EscrowStatus::Shipped {
             return false;
         }
 
-        let elapsed = Timestamp::now().as_secs() - self.updated_at.as_secs();
+        let elapsed = now.as_secs().saturating_sub(self.updated_at.as_secs());
         elapsed > self.timeout_period
     }
 
     /// Fund the escrow (buyer deposits funds)
-    pub fn fund(&mut self, l1_tx_id: String) -> Result<(), String> {
-        if self.status != EscrowStatus::Created {
-            return Err(format!("Cannot fund escrow in status {:?}", self.status));
-        }
-
-        self.status = EscrowStatus::Funded;
+    pub fn fund(&mut self, l1_tx_id: String, now: Timestamp) -> Result<()> {
+        self.advance(EscrowStatus::Funded, now)?;
         self.l1_tx_id = Some(l1_tx_id);
-        self.updated_at = Timestamp::now();
+        self.funded_at = Some(now);
         Ok(())
     }
 
-    /// Mark as shipped (seller)
-    pub fn mark_shipped(&mut self, tracking_info: Option<String>) -> Result<(), String> {
-        if self.status != EscrowStatus::Funded {
-            return Err(format!("Cannot ship from status {:?}", self.status));
+    /// Fund the escrow from the buyer's channel balance
+    pub fn fund_in_channel(&mut self, now: Timestamp) -> Result<()> {
+        self.advance(EscrowStatus::Funded, now)?;
+        self.funded_in_channel = true;
+        self.funded_at = Some(now);
+        Ok(())
+    }
+
+    /// Deadline for the seller to ship, once the escrow is funded
+    pub fn ship_by(&self) -> Option<Timestamp> {
+        let funded_at = self.funded_at?;
+        Some(Timestamp::from_secs(funded_at.as_secs().saturating_add(self.ship_within?)))
+    }
+
+    /// Whether a funded escrow is still unshipped past its ship-by deadline
+    pub fn is_shipment_overdue(&self, now: Timestamp) -> bool {
+        self.status == EscrowStatus::Funded && self.ship_by().is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Refund the buyer of an escrow the seller did not ship in time. Unlike
+    /// `approve_refund` this needs no request or seller approval.
+    pub fn refund_unshipped(&mut self, now: Timestamp) -> Result<()> {
+        if !self.is_shipment_overdue(now) {
+            return Err(L2Error::InvalidStateTransition);
         }
 
-        self.status = EscrowStatus::Shipped;
+        self.advance(EscrowStatus::Refunded, now)?;
+        self.dispute_reason = Some("Not shipped by the deadline".to_string());
+        Ok(())
+    }
+
+    /// Cancel an escrow that was never funded
+    pub fn cancel(&mut self, now: Timestamp) -> Result<()> {
+        self.advance(EscrowStatus::Cancelled, now)
+    }
+
+    /// Mark as shipped (seller)
+    pub fn mark_shipped(&mut self, tracking_info: Option<String>, now: Timestamp) -> Result<()> {
+        self.advance(EscrowStatus::Shipped, now)?;
         self.tracking_info = tracking_info;
-        self.updated_at = Timestamp::now();
         Ok(())
     }
 
     /// Confirm receipt and release funds (buyer)
-    pub fn confirm_receipt(&mut self) -> Result<(), String> {
-        if self.status != EscrowStatus::Shipped {
-            return Err(format!("Cannot confirm from status {:?}", self.status));
-        }
+    pub fn confirm_receipt(&mut self, now: Timestamp) -> Result<()> {
+        self.advance(EscrowStatus::Completed, now)
+    }
 
-        self.status = EscrowStatus::Completed;
-        self.updated_at = Timestamp::now();
+    /// Request refund (buyer)
+    pub fn request_refund(&mut self, reason: String, now: Timestamp) -> Result<()> {
+        self.advance(EscrowStatus::RefundRequested, now)?;
+        self.dispute_reason = Some(reason);
         Ok(())
     }
 
-    /// Request refund (buyer)
-    pub fn request_refund(&mut self, reason: String) -> Result<(), String> {
-        if self.status != EscrowStatus::Funded && self.status != EscrowStatus::Shipped {
-            return Err(format!("Cannot refund from status {:?}", self.status));
+    /// Approve refund (seller agrees, or an arbitrator settles a dispute
+    /// for the buyer)
+    pub fn approve_refund(&mut self, now: Timestamp) -> Result<()> {
+        if !matches!(self.status, EscrowStatus::RefundRequested | EscrowStatus::Disputed) {
+            return Err(L2Error::InvalidStateTransition);
         }
+        self.advance(EscrowStatus::Refunded, now)
+    }
 
-        self.status = EscrowStatus::RefundRequested;
+    /// Raise dispute
+    pub fn raise_dispute(&mut self, reason: String, now: Timestamp) -> Result<()> {
+        self.advance(EscrowStatus::Disputed, now)?;
         self.dispute_reason = Some(reason);
-        self.updated_at = Timestamp::now();
         Ok(())
     }
 
-    /// Approve refund (seller agrees)
-    pub fn approve_refund(&mut self) -> Result<(), String> {
+    /// Whether a refund request has gone unanswered for more than `deadline_secs`
+    pub fn is_refund_overdue(&self, now: Timestamp, deadline_secs: u64) -> bool {
+        self.status == EscrowStatus::RefundRequested
+            && now.as_secs().saturating_sub(self.updated_at.as_secs()) > deadline_secs
+    }
+
+    /// Escalate an unanswered refund request to a dispute, keeping the
+    /// buyer's reason
+    pub fn escalate_refund(&mut self, now: Timestamp) -> Result<()> {
         if self.status != EscrowStatus::RefundRequested {
-            return Err(format!("Cannot refund from status {:?}", self.status));
+            return Err(L2Error::InvalidStateTransition);
         }
-
-        self.status = EscrowStatus::Refunded;
-        self.updated_at = Timestamp::now();
-        Ok(())
+        self.advance(EscrowStatus::Disputed, now)
     }
 
-    /// Raise dispute
-    pub fn raise_dispute(&mut self, reason: String) -> Result<(), String> {
-        if self.status == EscrowStatus::Completed ||
-           self.status == EscrowStatus::Refunded ||
-           self.status == EscrowStatus::Cancelled {
-            return Err(format!("Cannot dispute from status {:?}", self.status));
+    /// Auto-release to seller after timeout
+    pub fn auto_release(&mut self, now: Timestamp) -> Result<()> {
+        if !self.is_timed_out(now) {
+            return Err(L2Error::InvalidStateTransition);
         }
+        self.advance(EscrowStatus::Completed, now)
+    }
+}
 
-        self.status = EscrowStatus::Disputed;
-        self.dispute_reason = Some(reason);
-        self.updated_at = Timestamp::now();
-        Ok(())
+/// An order together with the escrow securing it, if any
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderView {
+    pub channel_id: Hash,
+    pub order: Order,
+    pub escrow: Option<EscrowContract>,
+}
+
+/// Verifiable choice of an arbitrator for a disputed escrow.
+/// The selecting node cannot bias the pick: the VRF gives it exactly one
+/// possible output for a given escrow and candidate list.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArbitratorSelection {
+    pub escrow_id: Hash,
+    pub arbitrator: PublicKey,
+    pub selector: PublicKey,
+    pub proof: VrfProof,
+}
+
+impl ArbitratorSelection {
+    /// Pick an arbitrator from `candidates` using `keypair`'s VRF
+    pub fn select(keypair: &KeyPair, escrow_id: Hash, candidates: &[PublicKey]) -> Option<Self> {
+        let input = Self::vrf_input(&escrow_id, candidates);
+        let (output, proof) = vrf::prove(keypair, &input);
+        let index = output.select_index(candidates.len())?;
+
+        Some(Self {
+            escrow_id,
+            arbitrator: candidates[index],
+            selector: keypair.public_key(),
+            proof,
+        })
     }
 
-    /// Auto-release to seller after timeout
-    pub fn auto_release(&mut self) -> Result<(), String> {
-        if !self.is_timed_out() {
-            return Err("Escrow has not timed out yet".to_string());
+    /// Check the selection was made fairly from `candidates`
+    pub fn verify(&self, candidates: &[PublicKey]) -> bool {
+        let input = Self::vrf_input(&self.escrow_id, candidates);
+        self.proof.verify(&self.selector, &input)
+            .and_then(|output| output.select_index(candidates.len()))
+            .is_some_and(|index| candidates[index] == self.arbitrator)
+    }
+
+    fn vrf_input(escrow_id: &Hash, candidates: &[PublicKey]) -> Vec<u8> {
+        let mut input = b"arbitrator".to_vec();
+        input.extend_from_slice(escrow_id.as_bytes());
+        for candidate in candidates {
+            input.extend_from_slice(candidate.as_bytes());
         }
+        input
+    }
+}
 
-        self.status = EscrowStatus::Completed;
-        self.updated_at = Timestamp::now();
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_state_channel::state::OrderStatus;
+
+    #[test]
+    fn test_unshipped_escrow_refundable_after_deadline() {
+        let order = Order {
+            id: Hash::random(),
+            listing_id: Hash::random(),
+            buyer: KeyPair::generate().public_key(),
+            seller: KeyPair::generate().public_key(),
+            amount: Amount::new(500),
+            status: OrderStatus::Pending,
+        };
+        let mut escrow = EscrowContract::new(Hash::random(), &order, 600, Timestamp::from_secs(1_000));
+        escrow.ship_within = Some(3600);
+
+        // The clock only starts once the buyer has paid
+        assert!(escrow.ship_by().is_none());
+        escrow.fund_in_channel(Timestamp::from_secs(2_000)).unwrap();
+        assert_eq!(escrow.ship_by(), Some(Timestamp::from_secs(5_600)));
+
+        assert!(escrow.refund_unshipped(Timestamp::from_secs(5_599)).is_err());
+        escrow.refund_unshipped(Timestamp::from_secs(5_600)).unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Refunded);
+        assert!(matches!(escrow.mark_shipped(None, Timestamp::from_secs(5_601)), Err(L2Error::InvalidStateTransition)));
+
+        // Shipping in time closes the refund path
+        let mut shipped = EscrowContract::new(Hash::random(), &order, 600, Timestamp::from_secs(1_000));
+        shipped.ship_within = Some(3600);
+        shipped.fund("tx".to_string(), Timestamp::from_secs(2_000)).unwrap();
+        shipped.mark_shipped(None, Timestamp::from_secs(3_000)).unwrap();
+        assert!(!shipped.is_shipment_overdue(Timestamp::from_secs(10_000)));
+        assert!(shipped.refund_unshipped(Timestamp::from_secs(10_000)).is_err());
+
+        // Only the buyer confirms receipt
+        assert!(shipped.check_transition(&shipped.roles_of(&order.buyer), &EscrowStatus::Completed).is_ok());
+        assert!(shipped.check_transition(&shipped.roles_of(&order.seller), &EscrowStatus::Completed).is_err());
+
+        // Only an arbitrator settles a dispute
+        shipped.raise_dispute("Never arrived".to_string(), Timestamp::from_secs(10_000)).unwrap();
+        assert!(shipped.check_transition(&shipped.roles_of(&order.buyer), &EscrowStatus::Refunded).is_err());
+        assert!(shipped.check_transition(&[Role::Arbitrator], &EscrowStatus::Refunded).is_ok());
+        shipped.approve_refund(Timestamp::from_secs(10_001)).unwrap();
+        assert_eq!(shipped.status, EscrowStatus::Refunded);
+    }
+
+    #[test]
+    fn test_arbitrator_selection() {
+        let node = KeyPair::generate();
+        let candidates: Vec<PublicKey> = (0..5).map(|_| KeyPair::generate().public_key()).collect();
+        let escrow_id = Hash::random();
+
+        let selection = ArbitratorSelection::select(&node, escrow_id, &candidates).unwrap();
+        assert!(candidates.contains(&selection.arbitrator));
+        assert!(selection.verify(&candidates));
+
+        // Deterministic for the same escrow and candidates
+        let again = ArbitratorSelection::select(&node, escrow_id, &candidates).unwrap();
+        assert_eq!(again.arbitrator, selection.arbitrator);
+
+        // A different candidate list or a swapped arbitrator does not verify
+        assert!(!selection.verify(&candidates[1..]));
+        let mut forged = selection;
+        forged.arbitrator = *candidates.iter().find(|c| **c != forged.arbitrator).unwrap();
+        assert!(!forged.verify(&candidates));
+
+        assert!(ArbitratorSelection::select(&node, escrow_id, &[]).is_none());
     }
 }
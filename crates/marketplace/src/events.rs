@@ -0,0 +1,161 @@
+//! Marketplace activity pushed to subscribers.
+//!
+//! The node publishes an event on the [`EventHub`] whenever a listing is
+//! published or changed, an order is placed or changes status, an escrow
+//! moves, a channel commits an update or changes status, or a listing from
+//! the network matches a saved search. The RPC server
+//! forwards them to WebSocket subscribers, so UIs need not poll. Events are
+//! not stored: a subscriber only sees what happens while it is connected.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{Hash, PublicKey};
+use tari_l2_state_channel::channel::ChannelStatus;
+use tari_l2_state_channel::state::{Listing, OrderStatus};
+use crate::escrow::EscrowStatus;
+
+/// Kinds of event a subscription can ask for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventTopic {
+    Listings,
+    /// Order status changes and escrow transitions
+    Orders,
+    Channels,
+    SavedSearches,
+}
+
+/// Something that happened on the node
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketplaceEvent {
+    /// A listing was published or changed, here or by a peer
+    Listing { listing: Listing },
+
+    /// An order was placed, or moved from `previous` to `status`
+    OrderStatus {
+        channel_id: Hash,
+        order_id: Hash,
+        #[serde(default)]
+        previous: Option<OrderStatus>,
+        status: OrderStatus,
+    },
+
+    /// An escrow was created, or moved from `previous` to `status`
+    Escrow {
+        escrow_id: Hash,
+        order_id: Hash,
+        channel_id: Hash,
+        #[serde(default)]
+        previous: Option<EscrowStatus>,
+        status: EscrowStatus,
+    },
+
+    /// A channel committed the update at `nonce`
+    ChannelUpdate { channel_id: Hash, nonce: u64 },
+
+    /// A channel changed status
+    ChannelStatus { channel_id: Hash, status: ChannelStatus },
+
+    /// A listing from the network matched a saved search
+    SavedSearchMatch { search_id: Hash, listing: Listing },
+}
+
+impl MarketplaceEvent {
+    pub fn topic(&self) -> EventTopic {
+        match self {
+            MarketplaceEvent::Listing { .. } => EventTopic::Listings,
+            MarketplaceEvent::OrderStatus { .. } | MarketplaceEvent::Escrow { .. } => EventTopic::Orders,
+            MarketplaceEvent::ChannelUpdate { .. } | MarketplaceEvent::ChannelStatus { .. } => EventTopic::Channels,
+            MarketplaceEvent::SavedSearchMatch { .. } => EventTopic::SavedSearches,
+        }
+    }
+
+    /// Channel the event happened in; `None` for listings
+    pub fn channel_id(&self) -> Option<Hash> {
+        match self {
+            MarketplaceEvent::Listing { .. } | MarketplaceEvent::SavedSearchMatch { .. } => None,
+            MarketplaceEvent::OrderStatus { channel_id, .. }
+            | MarketplaceEvent::Escrow { channel_id, .. }
+            | MarketplaceEvent::ChannelUpdate { channel_id, .. }
+            | MarketplaceEvent::ChannelStatus { channel_id, .. } => Some(*channel_id),
+        }
+    }
+
+    /// Seller of the listing; `None` for other events
+    pub fn seller(&self) -> Option<PublicKey> {
+        match self {
+            MarketplaceEvent::Listing { listing } | MarketplaceEvent::SavedSearchMatch { listing, .. } => Some(listing.seller),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "node")]
+pub use hub::EventHub;
+
+#[cfg(feature = "node")]
+mod hub {
+    use super::*;
+    use tokio::sync::broadcast;
+
+    /// Events buffered for each subscriber before it starts missing them
+    const SUBSCRIBER_BUFFER: usize = 256;
+
+    /// Fans events out to subscribers
+    pub struct EventHub {
+        sender: broadcast::Sender<MarketplaceEvent>,
+    }
+
+    impl Default for EventHub {
+        fn default() -> Self {
+            let (sender, _) = broadcast::channel(SUBSCRIBER_BUFFER);
+            Self { sender }
+        }
+    }
+
+    impl EventHub {
+        /// Receive every event published from now on
+        pub fn subscribe(&self) -> broadcast::Receiver<MarketplaceEvent> {
+            self.sender.subscribe()
+        }
+
+        pub fn publish(&self, event: MarketplaceEvent) {
+            // No subscribers is fine; events are not kept
+            let _ = self.sender.send(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_sort_into_topics() {
+        let channel_id = Hash::new([1; 32]);
+        let order = MarketplaceEvent::OrderStatus {
+            channel_id,
+            order_id: Hash::new([2; 32]),
+            previous: Some(OrderStatus::Confirmed),
+            status: OrderStatus::Shipping,
+        };
+        assert_eq!(order.topic(), EventTopic::Orders);
+        assert_eq!(order.channel_id(), Some(channel_id));
+        assert_eq!(order.seller(), None);
+
+        let escrow = MarketplaceEvent::Escrow {
+            escrow_id: Hash::new([3; 32]),
+            order_id: Hash::new([2; 32]),
+            channel_id,
+            previous: Some(EscrowStatus::Created),
+            status: EscrowStatus::Funded,
+        };
+        assert_eq!(escrow.topic(), EventTopic::Orders);
+
+        let update = MarketplaceEvent::ChannelUpdate { channel_id, nonce: 4 };
+        assert_eq!(update.topic(), EventTopic::Channels);
+        let json = serde_json::to_value(&update).unwrap();
+        assert_eq!(json["type"], "channel_update");
+        assert_eq!(serde_json::from_value::<MarketplaceEvent>(json).unwrap(), update);
+    }
+}
@@ -0,0 +1,151 @@
+//! A buyer's purchase history across channels.
+//!
+//! Orders carry no timestamps of their own. A purchase is dated by its
+//! escrow's creation (when it was placed) and its receipt (when it settled).
+//! Purchases with neither are listed last and dropped by date filters.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::Hash;
+use tari_l2_state_channel::state::Order;
+use crate::escrow::EscrowContract;
+use crate::receipt::Receipt;
+
+/// Largest page `HistoryQuery::limit` may ask for
+pub const MAX_HISTORY_PAGE: usize = 100;
+
+/// One order a buyer placed, with its escrow, payout and receipt
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PurchaseRecord {
+    pub channel_id: Hash,
+    pub order: Order,
+
+    /// Escrow securing the order; its `payout_tx_id` records an L1 payout
+    pub escrow: Option<EscrowContract>,
+
+    /// Receipt issued when the order completed
+    pub receipt: Option<Receipt>,
+
+    /// When the order was placed, if known
+    pub placed_at: Option<u64>,
+
+    /// When the order settled, if it has
+    pub settled_at: Option<u64>,
+}
+
+impl PurchaseRecord {
+    pub fn new(channel_id: Hash, order: Order, escrow: Option<EscrowContract>, receipt: Option<Receipt>) -> Self {
+        let placed_at = escrow.as_ref().map(|e| e.created_at.as_secs())
+            .or_else(|| receipt.as_ref().and_then(|r| r.terms.started_at));
+        let settled_at = receipt.as_ref().map(|r| r.terms.settled_at);
+        Self { channel_id, order, escrow, receipt, placed_at, settled_at }
+    }
+
+    /// Latest known activity, used to order and filter the history
+    pub fn last_activity(&self) -> Option<u64> {
+        self.settled_at.or(self.placed_at)
+    }
+}
+
+/// Date range and page of a purchase history request
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// Only purchases with activity at or after this time
+    pub since: Option<u64>,
+
+    /// Only purchases with activity before this time
+    pub until: Option<u64>,
+
+    /// Purchases to skip, newest first
+    pub offset: usize,
+
+    /// Page size, at most `MAX_HISTORY_PAGE`
+    pub limit: usize,
+}
+
+impl Default for HistoryQuery {
+    fn default() -> Self {
+        Self {
+            since: None,
+            until: None,
+            offset: 0,
+            limit: 50,
+        }
+    }
+}
+
+/// A page of purchase history
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PurchaseHistory {
+    pub purchases: Vec<PurchaseRecord>,
+
+    /// Purchases matching the date range across all pages
+    pub total: usize,
+}
+
+impl PurchaseHistory {
+    /// Filter `records` to the query's date range and cut out its page,
+    /// newest first
+    pub fn page(mut records: Vec<PurchaseRecord>, query: &HistoryQuery) -> Self {
+        if query.since.is_some() || query.until.is_some() {
+            records.retain(|r| r.last_activity().is_some_and(|t| {
+                query.since.is_none_or(|since| t >= since) && query.until.is_none_or(|until| t < until)
+            }));
+        }
+        // Undated purchases sort last; ties break on order ID so pages are stable
+        records.sort_by(|a, b| {
+            b.last_activity().cmp(&a.last_activity())
+                .then_with(|| a.order.id.as_bytes().cmp(b.order.id.as_bytes()))
+        });
+
+        let total = records.len();
+        let purchases = records.into_iter()
+            .skip(query.offset)
+            .take(query.limit.clamp(1, MAX_HISTORY_PAGE))
+            .collect();
+        Self { purchases, total }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_common::{Amount, Timestamp};
+    use tari_l2_common::crypto::KeyPair;
+    use tari_l2_state_channel::state::OrderStatus;
+
+    fn record(placed_at: Option<u64>) -> PurchaseRecord {
+        let order = Order {
+            id: Hash::random(),
+            listing_id: Hash::random(),
+            buyer: KeyPair::generate().public_key(),
+            seller: KeyPair::generate().public_key(),
+            amount: Amount::new(100),
+            status: OrderStatus::Pending,
+        };
+        let channel_id = Hash::random();
+        let escrow = placed_at.map(|t| EscrowContract::new(channel_id, &order, 600, Timestamp::from_secs(t)));
+        PurchaseRecord::new(channel_id, order, escrow, None)
+    }
+
+    #[test]
+    fn test_history_pages_newest_first_within_range() {
+        let records: Vec<PurchaseRecord> = [Some(100), None, Some(300), Some(200)].into_iter().map(record).collect();
+
+        let all = PurchaseHistory::page(records.clone(), &HistoryQuery::default());
+        assert_eq!(all.total, 4);
+        let dates: Vec<Option<u64>> = all.purchases.iter().map(|r| r.placed_at).collect();
+        assert_eq!(dates, vec![Some(300), Some(200), Some(100), None]);
+
+        let query = HistoryQuery { offset: 1, limit: 1, ..Default::default() };
+        let page = PurchaseHistory::page(records.clone(), &query);
+        assert_eq!(page.total, 4);
+        assert_eq!(page.purchases[0].placed_at, Some(200));
+
+        // Date filters drop undated purchases
+        let query = HistoryQuery { since: Some(150), until: Some(300), ..Default::default() };
+        let ranged = PurchaseHistory::page(records, &query);
+        assert_eq!(ranged.total, 1);
+        assert_eq!(ranged.purchases[0].placed_at, Some(200));
+    }
+}
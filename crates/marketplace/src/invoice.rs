@@ -0,0 +1,167 @@
+//! Signed invoices: a seller's request to be paid.
+//!
+//! A seller's node issues an invoice for an amount, with a memo, an expiry
+//! and optionally the listing it is for, and signs it with its key. The
+//! buyer hands the invoice, as JSON or as its encoded string, to their own
+//! node, which checks the signature and expiry and pays it through a
+//! channel it shares with the seller. The payment names the invoice, so the
+//! seller's node can match it to the invoice it issued. Each node pays an
+//! invoice at most once, and both nodes record it as paid only once the
+//! payment commits.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{Hash, PublicKey, Signature, canonical, crypto::SigningContext};
+use crate::auth::verify_ownership;
+
+/// Longest memo allowed, in bytes
+pub const MAX_MEMO_LEN: usize = 256;
+
+/// How long an invoice stays payable when the seller gives no expiry
+pub const DEFAULT_EXPIRY_SECS: u64 = 3600;
+
+/// The signed content of an invoice
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InvoiceTerms {
+    /// Random ID chosen by the seller
+    pub id: Hash,
+
+    /// Key to pay
+    pub seller: PublicKey,
+
+    /// Amount due in µT
+    pub amount: u64,
+
+    pub memo: String,
+
+    /// Listing the invoice is for, if any
+    pub listing_id: Option<Hash>,
+
+    pub created_at: u64,
+
+    /// Unix time after which the invoice can no longer be paid
+    pub expires_at: u64,
+}
+
+impl InvoiceTerms {
+    /// Check the terms make a payable invoice
+    pub fn validate(&self) -> Result<(), String> {
+        if self.amount == 0 {
+            return Err("Invoice amount must be positive".to_string());
+        }
+        if self.memo.len() > MAX_MEMO_LEN {
+            return Err(format!("Invoice memo is longer than {} bytes", MAX_MEMO_LEN));
+        }
+        if self.expires_at <= self.created_at {
+            return Err("Invoice must expire after it is created".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Invoice terms signed by the seller
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Invoice {
+    pub terms: InvoiceTerms,
+    pub signature: Signature,
+}
+
+impl Invoice {
+    /// Build the message signed for an invoice
+    pub fn signing_message(terms: &InvoiceTerms) -> Result<Vec<u8>, String> {
+        Ok(SigningContext::Invoice.message(&canonical::encode(terms)?))
+    }
+
+    /// Check and sign `terms` as their seller
+    pub fn sign(terms: InvoiceTerms, sign_fn: impl FnOnce(&[u8]) -> Signature) -> Result<Self, String> {
+        terms.validate()?;
+        let signature = sign_fn(&Self::signing_message(&terms)?);
+        Ok(Self { terms, signature })
+    }
+
+    pub fn id(&self) -> Hash {
+        self.terms.id
+    }
+
+    /// Check the terms are valid and the seller signed them
+    pub fn verify(&self) -> bool {
+        if self.terms.validate().is_err() {
+            return false;
+        }
+        match Self::signing_message(&self.terms) {
+            Ok(message) => verify_ownership(&self.terms.seller, &message, &self.signature),
+            Err(_) => false,
+        }
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.terms.expires_at
+    }
+
+    /// The invoice as a single hex string, for sharing as text or a QR code
+    pub fn encode(&self) -> Result<String, String> {
+        bincode::serialize(self)
+            .map(hex::encode)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Read an invoice from its encoded string. The signature is not checked.
+    pub fn decode(encoded: &str) -> Result<Self, String> {
+        let bytes = hex::decode(encoded.trim()).map_err(|e| format!("Invalid invoice encoding: {}", e))?;
+        bincode::deserialize(&bytes).map_err(|e| format!("Invalid invoice: {}", e))
+    }
+}
+
+/// A payment of an invoice, as recorded by the node that paid it or by
+/// the seller that issued it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InvoicePayment {
+    pub invoice: Invoice,
+
+    /// Channel the payment was made in
+    pub channel_id: Hash,
+
+    /// Nonce of the update that pays the invoice. Zero while the payer's
+    /// node is still proposing it.
+    pub nonce: u64,
+
+    pub paid_at: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_common::crypto::KeyPair;
+
+    #[test]
+    fn test_invoice_verifies_and_round_trips() {
+        let seller = KeyPair::generate();
+        let terms = InvoiceTerms {
+            id: Hash::new([1; 32]),
+            seller: seller.public_key(),
+            amount: 2_500,
+            memo: "Two coffees".to_string(),
+            listing_id: Some(Hash::new([2; 32])),
+            created_at: 1_700_000_000,
+            expires_at: 1_700_003_600,
+        };
+
+        let invoice = Invoice::sign(terms.clone(), |m| seller.sign(m)).unwrap();
+        assert!(invoice.verify());
+        assert!(!invoice.is_expired(1_700_003_599));
+        assert!(invoice.is_expired(1_700_003_600));
+        assert_eq!(Invoice::decode(&invoice.encode().unwrap()).unwrap(), invoice);
+        assert!(Invoice::decode("not hex").is_err());
+
+        // Raising the amount breaks the seller's signature
+        let mut altered = invoice.clone();
+        altered.terms.amount = 25_000;
+        assert!(!altered.verify());
+
+        // Only the seller can sign their invoice
+        let outsider = KeyPair::generate();
+        assert!(!Invoice::sign(terms.clone(), |m| outsider.sign(m)).unwrap().verify());
+
+        assert!(Invoice::sign(InvoiceTerms { amount: 0, ..terms.clone() }, |m| seller.sign(m)).is_err());
+        assert!(Invoice::sign(InvoiceTerms { memo: "x".repeat(MAX_MEMO_LEN + 1), ..terms }, |m| seller.sign(m)).is_err());
+    }
+}
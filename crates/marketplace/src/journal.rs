@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{Hash, Timestamp};
+use tari_l2_state_channel::update::SignedStateUpdate;
+
+/// Stage an in-flight state update has reached
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum JournalStatus {
+    /// Created locally, still collecting counterparty signatures
+    Proposed,
+    /// Fully signed and about to be applied to the channel
+    Acked,
+}
+
+/// Write-ahead journal record for a state update
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Channel the update belongs to
+    pub channel_id: Hash,
+
+    /// The signed update
+    pub update: SignedStateUpdate,
+
+    /// Journal stage
+    pub status: JournalStatus,
+
+    /// When the entry was written
+    pub recorded_at: Timestamp,
+}
+
+impl JournalEntry {
+    pub fn new(channel_id: Hash, update: SignedStateUpdate, status: JournalStatus) -> Self {
+        Self {
+            channel_id,
+            update,
+            status,
+            recorded_at: Timestamp::now(),
+        }
+    }
+
+    /// Storage key: channel ID, big-endian nonce, then update hash so
+    /// entries sort by nonce within a channel
+    pub fn key(&self) -> Vec<u8> {
+        let mut key = self.channel_id.to_vec();
+        key.extend_from_slice(&self.update.nonce.to_be_bytes());
+        key.extend_from_slice(self.update.update.hash().as_bytes());
+        key
+    }
+}
@@ -1,13 +1,67 @@
+#[cfg(feature = "node")]
 pub mod manager;
+#[cfg(feature = "node")]
 pub mod storage;
+#[cfg(feature = "node")]
+pub mod migrate;
 pub mod escrow;
 pub mod auth;
 pub mod wallet;
 pub mod profile;
+pub mod policy;
+pub mod journal;
+pub mod receipt;
+pub mod alert;
+pub mod disk;
+pub mod spending;
+pub mod watch;
+pub mod catalog;
+pub mod checkpoint;
+pub mod history;
+pub mod contacts;
+pub mod invoice;
+pub mod payout;
+pub mod events;
+pub mod audit;
+pub mod saved_search;
+#[cfg(feature = "node")]
+pub mod outbox;
+#[cfg(feature = "node")]
+pub mod conformance;
+#[cfg(feature = "node")]
+pub mod rates;
+#[cfg(all(feature = "node", any(test, feature = "test-utils")))]
+pub mod testing;
 
+#[cfg(feature = "node")]
 pub use manager::MarketplaceManager;
+#[cfg(feature = "node")]
 pub use storage::MarketplaceStorage;
-pub use escrow::{EscrowContract, EscrowStatus};
-pub use auth::{SignedAction, verify_ownership};
+#[cfg(feature = "node")]
+pub use rates::{CachedRates, ExchangeRateProvider, StaticRates};
+pub use escrow::{ArbitratorSelection, EscrowContract, EscrowStatus, OrderView};
+pub use auth::{NodeAttestation, ReserveProof, ReserveSource, SeenActions, SignedAction, rpc_call_payload, rpc_request_payload, verify_ownership};
 pub use wallet::Wallet;
-pub use profile::UserProfile;
+pub use profile::{Review, SellerCatalog, SellerReputation, UserProfile};
+pub use policy::{ChannelPolicy, ChannelRequest, PolicyDecision};
+pub use journal::{JournalEntry, JournalStatus};
+pub use receipt::{Receipt, ReceiptKind, ReceiptTerms};
+pub use alert::{Alert, AlertKind, AlertPolicy, AlertSeverity};
+pub use disk::{DiskLevel, DiskPolicy, DiskStatus};
+pub use spending::SpendingLimits;
+pub use watch::{WatchBlob, WatchPayload};
+pub use catalog::{CatalogIntegrity, CatalogRoot};
+pub use checkpoint::CheckpointPolicy;
+pub use history::{HistoryQuery, PurchaseHistory, PurchaseRecord};
+pub use contacts::Contact;
+pub use invoice::{Invoice, InvoicePayment, InvoiceTerms};
+pub use payout::{PayoutMethod, PayoutPreference};
+pub use events::{EventTopic, MarketplaceEvent};
+pub use audit::{AuditEntry, AuditEvent, L1Action};
+pub use saved_search::{SavedSearch, SearchMatch, SearchQuery};
+#[cfg(feature = "node")]
+pub use alert::{AlertHub, AlertSink, LogSink};
+#[cfg(feature = "node")]
+pub use events::EventHub;
+#[cfg(feature = "node")]
+pub use outbox::{Confirmation, OutboxEntry};
@@ -1,32 +1,113 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tari_l2_common::{Amount, Hash, PublicKey, L2Error, error::Result, crypto::KeyPair};
+use tokio::sync::{Mutex, RwLock};
+use tari_l2_common::{Amount, Hash, PublicKey, L2Error, ResourceLimits, Signature, Timestamp, canonical, error::Result, crypto::{self, KeyPair, SigningContext}};
 use tari_l2_state_channel::{
-    MarketplaceChannel, ChannelConfig, StateUpdate,
-    channel::ChannelInfo,
+    MarketplaceChannel, ChannelConfig, DisputeEvidence, ReplayReport, StateUpdate,
+    channel::{ChannelInfo, ChannelStatus},
     update::SignedStateUpdate,
     state::{Listing, Order, OrderStatus},
 };
-use tari_l2_p2p::P2PNetwork;
+use tari_l2_common::clock::{SharedClock, SystemClock, TimestampWindow, DEFAULT_MAX_CLOCK_SKEW};
+use tari_l2_p2p::{Capability, CatalogPage, NetworkInterface, PeerInfo};
 use crate::storage::MarketplaceStorage;
-use crate::escrow::EscrowContract;
-use tracing::info;
+use crate::escrow::{ArbitratorSelection, EscrowContract, EscrowStatus, OrderView};
+use crate::auth::{NodeAttestation, ReserveProof, ReserveSource, SeenActions, SignedAction, ACTION_TIMESTAMP_WINDOW};
+use crate::journal::{JournalEntry, JournalStatus};
+use crate::outbox::{OutboxEntry, MAX_DELIVERY_ATTEMPTS};
+use crate::receipt::{Receipt, ReceiptKind, ReceiptTerms};
+use crate::history::{HistoryQuery, PurchaseHistory, PurchaseRecord};
+use crate::contacts::{self, Contact};
+use crate::saved_search::{SavedSearch, SearchMatch, MAX_SAVED_SEARCHES};
+use crate::invoice::{Invoice, InvoicePayment, InvoiceTerms};
+use crate::payout::PayoutPreference;
+use crate::alert::{AlertHub, AlertKind};
+use crate::events::{EventHub, MarketplaceEvent};
+use crate::audit::{self, AuditEntry, AuditEvent, L1Action, GENESIS_HASH};
+use crate::disk::DiskStatus;
+use crate::spending::{self, SpendingLimits, SpendingWindow};
+use crate::watch::WatchBlob;
+use crate::catalog::{CatalogIntegrity, CatalogRoot};
+use crate::checkpoint::{CheckpointPolicy, CheckpointRetry};
+use crate::policy::{ChannelPolicy, ChannelRequest, PolicyDecision};
+use crate::rates::{self, ExchangeRateProvider};
+use crate::profile::{Review, SellerCatalog, SellerReputation, UserProfile, MAX_REVIEW_RATING};
+use tracing::{debug, error, field, info, instrument, warn};
+
+/// Listing broadcasts older than an hour are treated as replays
+const LISTING_BROADCAST_WINDOW: TimestampWindow = TimestampWindow::new(3600, DEFAULT_MAX_CLOCK_SKEW);
+
+/// Number of reviews included in a seller catalog
+const CATALOG_RECENT_REVIEWS: usize = 10;
+
+/// Most sellers served in one catalog page
+const MAX_CATALOG_PAGE: u32 = 100;
+
+/// How far ahead of the local nonce a proposal may be and still be buffered
+/// until the updates before it arrive
+const REORDER_WINDOW: u64 = 32;
+
+/// How long a seller has to co-sign the cancellation of an unshipped order
+/// funded in the channel before the buyer force-closes to get the lock back
+const UNSHIPPED_REFUND_GRACE: u64 = 3600;
+
+/// A loaded channel behind its own lock, so updates to different channels
+/// proceed concurrently
+type ChannelHandle = Arc<Mutex<MarketplaceChannel>>;
+
+/// Archive peers a fresh node downloads the full catalog from
+#[derive(Default)]
+struct CatalogBootstrap {
+    archive_peers: HashSet<PublicKey>,
+    page_size: u32,
+    /// Archive peers already asked for the first page
+    requested: HashSet<PublicKey>,
+    /// Whether the node still needs the download
+    pending: bool,
+}
+
+/// Marks an escrow as mid-transition until dropped
+struct EscrowClaim {
+    claimed: Arc<std::sync::Mutex<HashSet<Hash>>>,
+    escrow_id: Hash,
+}
+
+impl EscrowClaim {
+    /// Claim `escrow_id`, failing if another transition holds it
+    fn new(claimed: &Arc<std::sync::Mutex<HashSet<Hash>>>, escrow_id: Hash) -> Result<Self> {
+        if !claimed.lock().unwrap().insert(escrow_id) {
+            return Err(L2Error::InvalidParameter(format!("Escrow {:?} is already being updated", escrow_id)));
+        }
+        Ok(Self { claimed: claimed.clone(), escrow_id })
+    }
+}
+
+impl Drop for EscrowClaim {
+    fn drop(&mut self) {
+        self.claimed.lock().unwrap().remove(&self.escrow_id);
+    }
+}
 
 /// Manages all marketplace channels and operations
 pub struct MarketplaceManager {
-    /// Active channels indexed by channel ID
-    channels: Arc<RwLock<HashMap<Hash, MarketplaceChannel>>>,
+    /// Active channels indexed by channel ID. The map lock is only held to
+    /// look up, insert or evict a handle, never while a channel is locked.
+    channels: Arc<RwLock<HashMap<Hash, ChannelHandle>>>,
 
     /// Global marketplace listings (not tied to specific channels)
     global_listings: Arc<RwLock<Vec<Listing>>>,
 
-    /// Global orders (tracking purchases across the marketplace)
-    global_orders: Arc<RwLock<Vec<Order>>>,
+    /// Every order seen in a channel, by order ID, with its channel ID.
+    /// Stored apart from channel state, so an order outlives its channel.
+    global_orders: Arc<RwLock<HashMap<Hash, (Hash, Order)>>>,
 
     /// Escrow contracts indexed by escrow ID
     escrow_contracts: Arc<RwLock<HashMap<Hash, EscrowContract>>>,
 
+    /// Escrows with a transition under way, held across its L1 payout and
+    /// order proposal
+    escrows_in_transition: Arc<std::sync::Mutex<HashSet<Hash>>>,
+
     /// Persistent storage
     storage: Arc<MarketplaceStorage>,
 
@@ -34,10 +115,111 @@ pub struct MarketplaceManager {
     keypair: Arc<KeyPair>,
 
     /// P2P network for broadcasting listings
-    network: Arc<RwLock<Option<Arc<P2PNetwork>>>>,
+    network: Arc<RwLock<Option<Arc<dyn NetworkInterface>>>>,
 
     /// Optional L1 client for blockchain operations
     l1_client: Option<Arc<tari_l2_l1_client::TariL1Client>>,
+
+    /// Rules for accepting incoming channel open requests
+    channel_policy: Arc<RwLock<ChannelPolicy>>,
+
+    /// Channel requests awaiting manual approval, indexed by channel ID
+    pending_channel_requests: Arc<RwLock<HashMap<Hash, ChannelRequest>>>,
+
+    /// Channels we proposed that still await counterparty co-signatures
+    channel_proposals: Arc<RwLock<HashMap<Hash, MarketplaceChannel>>>,
+
+    /// Known user profiles (used for reputation checks)
+    profiles: Arc<RwLock<HashMap<PublicKey, UserProfile>>>,
+
+    /// Buyer reviews indexed by seller
+    reviews: Arc<RwLock<HashMap<PublicKey, Vec<Review>>>>,
+
+    /// Storefront views built on demand, dropped when the seller's listings,
+    /// profile or reviews change
+    seller_catalogs: Arc<RwLock<HashMap<PublicKey, SellerCatalog>>>,
+
+    /// Signed updates created but not yet applied, indexed by channel ID
+    pending_updates: Arc<RwLock<HashMap<Hash, Vec<SignedStateUpdate>>>>,
+
+    /// When the first proposal at each pending channel and nonce was tracked
+    pending_since: Arc<RwLock<HashMap<(Hash, u64), Timestamp>>>,
+
+    /// Counterparty proposals that move more of our funds than we sign
+    /// unattended, held for the operator by channel ID
+    awaiting_approval: Arc<RwLock<HashMap<Hash, Vec<SignedStateUpdate>>>>,
+
+    /// Proposals received ahead of the next nonce, by channel ID then nonce
+    future_updates: Arc<RwLock<HashMap<Hash, BTreeMap<u64, SignedStateUpdate>>>>,
+
+    /// Resource caps enforced by the manager
+    limits: Arc<RwLock<ResourceLimits>>,
+
+    /// When each global listing was created or received, for expiry
+    listing_timestamps: Arc<RwLock<HashMap<Hash, Timestamp>>>,
+
+    /// Source of fiat exchange rates for display prices
+    exchange_rates: Arc<RwLock<Option<Arc<dyn ExchangeRateProvider>>>>,
+
+    /// Arbitrators notified when a dispute is escalated
+    arbitrators: Arc<RwLock<Vec<PublicKey>>>,
+
+    /// Cold-start catalog download from archive peers
+    catalog_bootstrap: Arc<RwLock<CatalogBootstrap>>,
+
+    /// Operator alerts raised by the manager and the node
+    alerts: Arc<AlertHub>,
+
+    /// Listing, order and channel activity pushed to RPC subscribers
+    events: Arc<EventHub>,
+
+    /// Sequence number and hash the next audit log entry links to; None
+    /// until read from storage
+    audit_head: Arc<std::sync::Mutex<Option<(u64, Hash)>>>,
+
+    /// Latest disk check; a degraded node takes on no new listings or channels
+    disk_status: Arc<RwLock<DiskStatus>>,
+
+    /// Idle channels evicted from `channels`. They stay in storage and are
+    /// loaded again when referenced.
+    hibernated: Arc<RwLock<HashSet<Hash>>>,
+
+    /// When each in-memory channel was last loaded, so a channel woken from
+    /// hibernation is not evicted again straight away
+    channel_loaded_at: Arc<RwLock<HashMap<Hash, Timestamp>>>,
+
+    /// Seconds without activity after which a channel is hibernated
+    hibernate_after: Arc<RwLock<Option<u64>>>,
+
+    /// Caps on what this node signs away from each channel
+    spending_limits: Arc<RwLock<HashMap<Hash, SpendingLimits>>>,
+
+    /// Caps for channels without limits of their own
+    default_spending_limits: Arc<RwLock<SpendingLimits>>,
+
+    /// What this node signed away from each channel in the last day
+    spending_windows: Arc<RwLock<HashMap<Hash, SpendingWindow>>>,
+
+    /// Signed actions already accepted, so none is acted on twice
+    seen_actions: Arc<RwLock<SeenActions>>,
+
+    /// Most watch blobs held for clients; None when this node is not a watchtower
+    watchtower: Arc<RwLock<Option<usize>>>,
+
+    /// Watch blobs held for clients, by locator and client
+    watch_blobs: Arc<RwLock<HashMap<(Hash, PublicKey), WatchBlob>>>,
+
+    /// Latest catalog root each seller announced
+    catalog_roots: Arc<RwLock<HashMap<PublicKey, CatalogRoot>>>,
+
+    /// When channels are anchored on L1
+    checkpoint_policy: Arc<RwLock<CheckpointPolicy>>,
+
+    /// Backoff for channels whose last checkpoint failed
+    checkpoint_retries: Arc<RwLock<HashMap<Hash, CheckpointRetry>>>,
+
+    /// Time source for timeouts and expiry
+    clock: SharedClock,
 }
 
 impl MarketplaceManager {
@@ -50,72 +232,606 @@ impl MarketplaceManager {
         Self {
             channels: Arc::new(RwLock::new(HashMap::new())),
             global_listings: Arc::new(RwLock::new(Vec::new())),
-            global_orders: Arc::new(RwLock::new(Vec::new())),
+            global_orders: Arc::new(RwLock::new(HashMap::new())),
             escrow_contracts: Arc::new(RwLock::new(HashMap::new())),
+            escrows_in_transition: Arc::new(std::sync::Mutex::new(HashSet::new())),
             storage,
             keypair,
             network: Arc::new(RwLock::new(None)),
             l1_client,
+            channel_policy: Arc::new(RwLock::new(ChannelPolicy::default())),
+            pending_channel_requests: Arc::new(RwLock::new(HashMap::new())),
+            channel_proposals: Arc::new(RwLock::new(HashMap::new())),
+            profiles: Arc::new(RwLock::new(HashMap::new())),
+            reviews: Arc::new(RwLock::new(HashMap::new())),
+            seller_catalogs: Arc::new(RwLock::new(HashMap::new())),
+            pending_updates: Arc::new(RwLock::new(HashMap::new())),
+            pending_since: Arc::new(RwLock::new(HashMap::new())),
+            awaiting_approval: Arc::new(RwLock::new(HashMap::new())),
+            future_updates: Arc::new(RwLock::new(HashMap::new())),
+            limits: Arc::new(RwLock::new(ResourceLimits::default())),
+            listing_timestamps: Arc::new(RwLock::new(HashMap::new())),
+            exchange_rates: Arc::new(RwLock::new(None)),
+            arbitrators: Arc::new(RwLock::new(Vec::new())),
+            catalog_bootstrap: Arc::new(RwLock::new(CatalogBootstrap::default())),
+            alerts: Arc::new(AlertHub::default()),
+            events: Arc::new(EventHub::default()),
+            audit_head: Arc::new(std::sync::Mutex::new(None)),
+            disk_status: Arc::new(RwLock::new(DiskStatus::default())),
+            hibernated: Arc::new(RwLock::new(HashSet::new())),
+            channel_loaded_at: Arc::new(RwLock::new(HashMap::new())),
+            hibernate_after: Arc::new(RwLock::new(None)),
+            spending_limits: Arc::new(RwLock::new(HashMap::new())),
+            default_spending_limits: Arc::new(RwLock::new(spending::DEFAULT_LIMITS)),
+            spending_windows: Arc::new(RwLock::new(HashMap::new())),
+            seen_actions: Arc::new(RwLock::new(SeenActions::default())),
+            watchtower: Arc::new(RwLock::new(None)),
+            watch_blobs: Arc::new(RwLock::new(HashMap::new())),
+            catalog_roots: Arc::new(RwLock::new(HashMap::new())),
+            checkpoint_policy: Arc::new(RwLock::new(CheckpointPolicy::default())),
+            checkpoint_retries: Arc::new(RwLock::new(HashMap::new())),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Use a different time source (e.g. a `ManualClock` in tests)
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Set the P2P network for broadcasting listings
-    /// Set the P2P network for broadcasting listings
-    pub async fn set_network(&self, network: Arc<P2PNetwork>) {
+    pub async fn set_network(&self, network: Arc<dyn NetworkInterface>) {
         *self.network.write().await = Some(network);
     }
 
-    /// Load all channels from storage
+    /// Set the exchange rate source used for approximate fiat prices
+    pub async fn set_exchange_rate_provider(&self, provider: Arc<dyn ExchangeRateProvider>) {
+        *self.exchange_rates.write().await = Some(provider);
+    }
+
+    /// Approximate value of `amount` in `currency`, if a rate is available
+    pub async fn fiat_value(&self, amount: Amount, currency: &str) -> Option<f64> {
+        let provider = self.exchange_rates.read().await.clone()?;
+        match provider.rate(currency).await {
+            Ok(rate) => rate.map(|rate| rates::fiat_value(amount, rate)),
+            Err(e) => {
+                warn!("No exchange rate for {}: {}", currency, e);
+                None
+            }
+        }
+    }
+
+    /// Peers the P2P layer reports as connected, and our own transport ID
+    pub async fn network_peers(&self) -> (Option<String>, Vec<PublicKey>) {
+        match self.network.read().await.as_ref() {
+            Some(network) => (Some(network.local_id()), network.connected_peers().await),
+            None => (None, Vec::new()),
+        }
+    }
+
+    /// Identified peers, optionally only those advertising `capability`
+    pub async fn peer_table(&self, capability: Option<Capability>) -> Vec<PeerInfo> {
+        let peers = match self.network.read().await.as_ref() {
+            Some(network) => network.peer_table().await,
+            None => return Vec::new(),
+        };
+        peers.into_iter()
+            .filter(|peer| capability.map_or(true, |c| peer.offers(c)))
+            .collect()
+    }
+
+    /// Counterparties of loaded channels that are not closed, whose
+    /// connections the network keeps however many peers it has
+    pub async fn channel_counterparties(&self) -> Vec<PublicKey> {
+        let local = self.keypair.public_key();
+        let mut counterparties = Vec::new();
+        for handle in self.channel_handles().await {
+            let channel = handle.lock().await;
+            if channel.status == ChannelStatus::Closed {
+                continue;
+            }
+            counterparties.extend(channel.participants.iter().filter(|pk| **pk != local).copied());
+        }
+        counterparties.sort_by_key(|pk| pk.to_string());
+        counterparties.dedup();
+        counterparties
+    }
+
+    /// Hub operator alerts are raised on
+    pub fn alerts(&self) -> Arc<AlertHub> {
+        self.alerts.clone()
+    }
+
+    /// Hub marketplace events are published on
+    pub fn events(&self) -> Arc<EventHub> {
+        self.events.clone()
+    }
+
+    fn publish_channel_status(&self, channel: &MarketplaceChannel) {
+        self.events.publish(MarketplaceEvent::ChannelStatus { channel_id: channel.channel_id, status: channel.status.clone() });
+    }
+
+    /// Publish that `escrow` was created, or moved from `previous`
+    fn publish_escrow(&self, escrow: &EscrowContract, previous: Option<EscrowStatus>) {
+        self.events.publish(MarketplaceEvent::Escrow {
+            escrow_id: escrow.id,
+            order_id: escrow.order_id,
+            channel_id: escrow.channel_id,
+            previous,
+            status: escrow.status.clone(),
+        });
+    }
+
+    /// Append `event` to the audit log. What it records has already
+    /// happened, so a failure is not returned; instead the log is marked
+    /// broken from the entry the event should have been, and an alert raised.
+    fn audit(&self, event: AuditEvent) {
+        if let Err(e) = self.append_audit(event) {
+            let seq = match *self.audit_head.lock().unwrap() {
+                Some((seq, _)) => seq,
+                None => self.storage.last_audit_entry().ok().flatten().map_or(0, |last| last.seq + 1),
+            };
+            error!("Failed to append audit log entry {}: {}", seq, e);
+            if let Err(e) = self.storage.record_audit_gap(seq) {
+                error!("Failed to mark audit log entry {} missing: {}", seq, e);
+            }
+            self.alerts.raise(AlertKind::AuditLogGap { seq }, self.clock.now());
+        }
+    }
+
+    fn append_audit(&self, event: AuditEvent) -> Result<()> {
+        let mut head = self.audit_head.lock().unwrap();
+        let (seq, prev_hash) = match *head {
+            Some(head) => head,
+            None => match self.storage.last_audit_entry()? {
+                Some(last) => (last.seq + 1, last.hash),
+                None => (0, GENESIS_HASH),
+            },
+        };
+        let entry = AuditEntry::new(seq, self.clock.now().as_secs(), event, prev_hash)
+            .map_err(L2Error::SerializationError)?;
+        self.storage.append_audit_entry(&entry)?;
+        *head = Some((seq + 1, entry.hash));
+        Ok(())
+    }
+
+    fn audit_l1(&self, action: L1Action, channel_id: Option<Hash>, tx_id: &str) {
+        self.audit(AuditEvent::L1 { action, channel_id, tx_id: tx_id.to_string() });
+    }
+
+    /// Up to `limit` audit log entries from `from`, and the sequence number
+    /// of the first that is not intact, does not link to the one before, or
+    /// is missing an event that failed to append
+    pub fn audit_log(&self, from: u64, limit: usize) -> Result<(Vec<AuditEntry>, Option<u64>)> {
+        let prev = match from.checked_sub(1) {
+            Some(prev) => self.storage.load_audit_entries(prev, 1)?.into_iter().next(),
+            None => None,
+        };
+        let entries = self.storage.load_audit_entries(from, limit)?;
+        if from > 0 && prev.is_none() && !entries.is_empty() {
+            return Ok((entries, Some(from - 1)));
+        }
+        let broken_at = audit::verify_chain(prev.as_ref(), &entries).err();
+        let gap = self.storage.first_audit_gap()?
+            .filter(|seq| *seq < from + entries.len() as u64);
+        Ok((entries, broken_at.into_iter().chain(gap).min()))
+    }
+
+    /// The most recent audit log entry, whose hash commits to the whole log
+    pub fn audit_head(&self) -> Result<Option<AuditEntry>> {
+        self.storage.last_audit_entry()
+    }
+
+    /// Record the latest disk check
+    pub async fn set_disk_status(&self, status: DiskStatus) {
+        *self.disk_status.write().await = status;
+    }
+
+    pub async fn disk_status(&self) -> DiskStatus {
+        self.disk_status.read().await.clone()
+    }
+
+    /// Refuse new state while the data directory is nearly full, so writes
+    /// for existing channels still have room
+    async fn ensure_disk_space(&self, what: &str) -> Result<()> {
+        let status = self.disk_status.read().await;
+        if status.is_degraded() {
+            return Err(L2Error::ResourceLimitExceeded(format!(
+                "Disk nearly full ({}% used, {} bytes free): not accepting {}",
+                status.usage_percent, status.available_bytes, what
+            )));
+        }
+        Ok(())
+    }
+
+    /// Set the resource limits enforced by the manager
+    pub async fn set_limits(&self, limits: ResourceLimits) {
+        *self.limits.write().await = limits;
+    }
+
+    /// Set the policy used to evaluate incoming channel requests
+    pub async fn set_channel_policy(&self, policy: ChannelPolicy) {
+        *self.channel_policy.write().await = policy;
+    }
+
+    /// Hibernate channels idle for `secs` seconds (None keeps every channel
+    /// in memory)
+    pub async fn set_hibernate_after(&self, secs: Option<u64>) {
+        *self.hibernate_after.write().await = secs;
+    }
+
+    /// Set when channels are anchored on L1
+    pub async fn set_checkpoint_policy(&self, policy: CheckpointPolicy) {
+        *self.checkpoint_policy.write().await = policy;
+    }
+
+    /// Load channels from storage. Channels that were already idle when the
+    /// node stopped are left hibernated.
     pub async fn load_channels(&self) -> Result<()> {
+        *self.spending_limits.write().await = self.storage.load_spending_limits()?.into_iter().collect();
+        {
+            let mut seen = self.seen_actions.write().await;
+            for (id, timestamp) in self.storage.load_seen_actions()? {
+                let _ = seen.insert(id, timestamp);
+            }
+        }
+        *self.watch_blobs.write().await = self.storage.load_watch_blobs()?
+            .into_iter()
+            .map(|blob| ((blob.locator, blob.client), blob))
+            .collect();
         let channel_ids = self.storage.list_channels()?;
+        let hibernate_after = *self.hibernate_after.read().await;
+        let now = self.clock.now();
         let mut channels = self.channels.write().await;
+        let mut hibernated = self.hibernated.write().await;
+        let mut loaded_at = self.channel_loaded_at.write().await;
 
         for channel_id in channel_ids {
             if let Some(channel) = self.storage.load_channel(&channel_id)? {
+                let idle = hibernate_after.is_some_and(|after| {
+                    channel.metrics.last_update_at.is_some_and(|last| now.as_secs().saturating_sub(last.as_secs()) >= after)
+                });
+                if idle && self.can_hibernate(&channel) {
+                    debug!("Leaving idle channel {:?} hibernated", channel_id);
+                    hibernated.insert(channel_id);
+                    continue;
+                }
                 info!("Loaded channel: {:?}", channel_id);
-                channels.insert(channel_id, channel);
+                channels.insert(channel_id, Arc::new(Mutex::new(channel)));
+                loaded_at.insert(channel_id, now);
+            }
+        }
+
+        if !hibernated.is_empty() {
+            info!("{} idle channels left hibernated", hibernated.len());
+        }
+        Ok(())
+    }
+
+    /// Whether a channel has nothing in progress that needs it in memory:
+    /// no open orders or listings, and (with an L1 client) its latest state
+    /// checkpointed. Pending and buffered updates are checked by the caller.
+    fn can_hibernate(&self, channel: &MarketplaceChannel) -> bool {
+        let settled = match channel.status {
+            ChannelStatus::Active => self.l1_client.is_none() || channel.metrics.checkpointed_nonce >= channel.state.nonce,
+            ChannelStatus::Closed => true,
+            _ => false,
+        };
+        settled
+            && channel.state.orders.iter().all(|o| matches!(o.status, OrderStatus::Completed | OrderStatus::Cancelled))
+            && !channel.state.listings.iter().any(|l| l.active)
+    }
+
+    /// Evict channels with no activity for the configured time from memory,
+    /// returning their IDs. They are written to storage and loaded again the
+    /// next time they are referenced.
+    pub async fn hibernate_idle_channels(&self) -> Result<Vec<Hash>> {
+        let Some(after) = *self.hibernate_after.read().await else {
+            return Ok(Vec::new());
+        };
+        let now = self.clock.now().as_secs();
+        let busy: HashSet<Hash> = self.pending_updates.read().await.iter()
+            .filter(|(_, pending)| !pending.is_empty())
+            .map(|(id, _)| *id)
+            .chain(self.future_updates.read().await.keys().copied())
+            .collect();
+
+        let mut channels = self.channels.write().await;
+        let mut hibernated = self.hibernated.write().await;
+        let mut loaded_at = self.channel_loaded_at.write().await;
+
+        let mut idle = Vec::new();
+        for (channel_id, handle) in channels.iter() {
+            // A handle held outside the map is a channel in use
+            if busy.contains(channel_id) || Arc::strong_count(handle) > 1 {
+                continue;
+            }
+            let Ok(channel) = handle.try_lock() else {
+                continue;
+            };
+            if !self.can_hibernate(&channel) {
+                continue;
+            }
+            let last_active = channel.metrics.last_update_at.into_iter()
+                .chain(loaded_at.get(channel_id).copied())
+                .map(|t| t.as_secs())
+                .max()
+                .unwrap_or(0);
+            if now.saturating_sub(last_active) >= after {
+                idle.push(*channel_id);
+            }
+        }
+
+        for channel_id in &idle {
+            if let Some(handle) = channels.remove(channel_id) {
+                self.storage.store_channel(&*handle.lock().await)?;
+                loaded_at.remove(channel_id);
+                hibernated.insert(*channel_id);
             }
         }
 
+        if !idle.is_empty() {
+            info!("💤 Hibernated {} idle channels", idle.len());
+        }
+        Ok(idle)
+    }
+
+    /// Load a hibernated channel back into memory. A no-op for channels that
+    /// are already loaded or unknown.
+    async fn wake_channel(&self, channel_id: &Hash) -> Result<()> {
+        if !self.hibernated.read().await.contains(channel_id) {
+            return Ok(());
+        }
+
+        let mut channels = self.channels.write().await;
+        let mut hibernated = self.hibernated.write().await;
+        if !hibernated.contains(channel_id) {
+            return Ok(());
+        }
+        if let Some(channel) = self.storage.load_channel(channel_id)? {
+            channels.insert(*channel_id, Arc::new(Mutex::new(channel)));
+            self.channel_loaded_at.write().await.insert(*channel_id, self.clock.now());
+            debug!("Woke hibernated channel {:?}", channel_id);
+        }
+        hibernated.remove(channel_id);
         Ok(())
     }
 
-    /// Create a new channel
+    /// Handle to a loaded channel, waking it first if it is hibernated. The
+    /// map lock is released before returning, so callers lock only the channel.
+    async fn channel(&self, channel_id: &Hash) -> Result<ChannelHandle> {
+        self.wake_channel(channel_id).await?;
+        self.channels.read().await
+            .get(channel_id)
+            .cloned()
+            .ok_or_else(|| L2Error::ChannelNotFound(channel_id.to_string()))
+    }
+
+    /// Handles to every loaded channel, taken without holding the map lock
+    /// while the channels themselves are locked
+    async fn channel_handles(&self) -> Vec<ChannelHandle> {
+        self.channels.read().await.values().cloned().collect()
+    }
+
+    /// Number of channels currently hibernated
+    pub async fn hibernated_count(&self) -> usize {
+        self.hibernated.read().await.len()
+    }
+
+    /// Hibernated channels read from storage without waking them
+    async fn hibernated_channels(&self) -> Vec<MarketplaceChannel> {
+        let ids: Vec<Hash> = self.hibernated.read().await.iter().copied().collect();
+        ids.iter()
+            .filter_map(|id| match self.storage.load_channel(id) {
+                Ok(channel) => channel,
+                Err(e) => {
+                    warn!("Failed to read hibernated channel {:?}: {}", id, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Create a channel locally and lock its collateral without a handshake.
+    /// Only for channels the counterparties agreed to out of band; channels
+    /// with peers go through `propose_channel`.
     pub async fn create_channel(&self, config: ChannelConfig) -> Result<Hash> {
-        let channel = MarketplaceChannel::new(config.clone());
+        self.check_channel_config(&config).await?;
+        let channel = MarketplaceChannel::new(config);
+
+        info!("Creating channel: {:?}", channel.channel_id);
+
+        self.ensure_can_open(&channel.channel_id).await?;
+        self.lock_collateral(&channel).await;
+        self.store_new_channel(channel).await
+    }
+
+    /// Start the open handshake: sign the genesis state and send the proposal
+    /// to the other participants. The channel is persisted and its collateral
+    /// locked once every participant has co-signed.
+    pub async fn propose_channel(&self, config: ChannelConfig) -> Result<Hash> {
+        let local = self.keypair.public_key();
+        if !config.participants.contains(&local) {
+            return Err(L2Error::InvalidParameter("This node is not a channel participant".to_string()));
+        }
+        self.check_channel_config(&config).await?;
+
+        let network = self.network.read().await.clone()
+            .ok_or_else(|| L2Error::NetworkError("No P2P network to reach the counterparty".to_string()))?;
+
+        let mut channel = MarketplaceChannel::new(config);
         let channel_id = channel.channel_id;
+        self.ensure_can_open(&channel_id).await?;
+
+        let signature = self.keypair.sign(&channel.genesis_signing_message());
+        channel.add_genesis_signature(local, signature.clone())?;
+
+        let message = tari_l2_p2p::L2Message::ChannelOpenRequest {
+            participants: channel.participants.clone(),
+            initiator: local,
+            initial_balances: channel.initial_balances.clone(),
+            challenge_period: channel.challenge_period,
+            signature,
+        };
+        let counterparties: Vec<PublicKey> = channel.participants.iter()
+            .filter(|p| **p != local)
+            .copied()
+            .collect();
+
+        {
+            let mut proposals = self.channel_proposals.write().await;
+            if proposals.contains_key(&channel_id) {
+                return Err(L2Error::ChannelAlreadyExists(channel_id.to_string()));
+            }
+            proposals.insert(channel_id, channel);
+        }
+
+        for peer in counterparties {
+            if let Err(e) = network.send_message(peer, message.clone()).await {
+                self.channel_proposals.write().await.remove(&channel_id);
+                return Err(e);
+            }
+        }
+
+        info!("Proposed channel {:?}", channel_id);
+        Ok(channel_id)
+    }
+
+    /// Handle a counterparty's answer to one of our channel proposals.
+    /// `from` is the verified sender, which must be a participant that has
+    /// not co-signed yet, so no one else can reject the proposal for them.
+    /// Returns the channel ID once every participant has co-signed and the
+    /// channel is open, or None if the response was not for a proposal of
+    /// ours, was a rejection, or more co-signatures are outstanding.
+    pub async fn handle_channel_open_response(
+        &self,
+        from: &PublicKey,
+        channel_id: &Hash,
+        accepted: bool,
+        signature: Option<Signature>,
+    ) -> Result<Option<Hash>> {
+        let mut proposals = self.channel_proposals.write().await;
+        let Some(channel) = proposals.get_mut(channel_id) else {
+            return Ok(None);
+        };
+        if !channel.participants.contains(from) || channel.genesis_signatures.contains_key(from) {
+            return Err(L2Error::ParticipantNotFound);
+        }
+
+        if !accepted {
+            proposals.remove(channel_id);
+            info!("Channel proposal {:?} was rejected by {:?}", channel_id, from);
+            return Ok(None);
+        }
+
+        let signature = signature.ok_or(L2Error::InvalidSignature)?;
+        channel.add_genesis_signature(*from, signature)?;
+
+        if !channel.genesis_complete() {
+            return Ok(None);
+        }
+        let channel = proposals.remove(channel_id).expect("proposal present");
+        drop(proposals);
+
+        self.ensure_can_open(channel_id).await?;
+        let channel_id = self.store_new_channel(channel.clone()).await?;
+        self.lock_collateral(&channel).await;
+
+        info!("✅ Channel {:?} co-signed by all participants", channel_id);
+        Ok(Some(channel_id))
+    }
+
+    /// Open a channel whose counterparty has already signed the genesis state
+    /// out of band (e.g. a wallet over RPC): co-sign, persist, then lock collateral
+    pub async fn open_cosigned_channel(
+        &self,
+        config: ChannelConfig,
+        counterparty: PublicKey,
+        signature: Signature,
+    ) -> Result<Hash> {
+        let local = self.keypair.public_key();
+        if !config.participants.contains(&local) {
+            return Err(L2Error::InvalidParameter("This node is not a channel participant".to_string()));
+        }
+        self.check_channel_config(&config).await?;
+
+        let mut channel = MarketplaceChannel::new(config);
+        channel.add_genesis_signature(counterparty, signature)?;
+        let own = self.keypair.sign(&channel.genesis_signing_message());
+        channel.add_genesis_signature(local, own)?;
+        if !channel.genesis_complete() {
+            return Err(L2Error::InvalidParameter("Every participant must sign the genesis state".to_string()));
+        }
 
-        info!("Creating channel: {:?}", channel_id);
+        self.ensure_can_open(&channel.channel_id).await?;
+        let channel_id = self.store_new_channel(channel.clone()).await?;
+        self.lock_collateral(&channel).await;
+        Ok(channel_id)
+    }
+
+    /// Validate a proposed channel's collateral and challenge period
+    async fn check_channel_config(&self, config: &ChannelConfig) -> Result<()> {
+        check_collateral(config)?;
+        self.channel_policy.read().await.check_challenge_period(config.challenge_period)
+    }
 
-        // Calculate total collateral
-        let total_collateral: u64 = config.initial_balances.values().map(|a| a.value()).sum();
+    /// Fail if the channel already exists or the channel cap is reached
+    async fn ensure_can_open(&self, channel_id: &Hash) -> Result<()> {
+        self.wake_channel(channel_id).await?;
+        self.ensure_disk_space("new channels").await?;
+        let channels = self.channels.read().await;
+        if channels.contains_key(channel_id) {
+            return Err(L2Error::ChannelAlreadyExists(channel_id.to_string()));
+        }
+        // Hibernated channels are still open, so they count toward the cap
+        let open = channels.len() + self.hibernated.read().await.len();
+        let max_channels = self.limits.read().await.max_channels;
+        if open >= max_channels {
+            return Err(L2Error::ResourceLimitExceeded(format!(
+                "Channel limit reached ({})", max_channels
+            )));
+        }
+        Ok(())
+    }
 
-        // Lock collateral on L1 if client available
+    /// Lock the channel's collateral on L1 if a client is available
+    async fn lock_collateral(&self, channel: &MarketplaceChannel) {
         if let Some(ref l1_client) = self.l1_client {
-            let participants: Vec<String> = config.participants
+            let participants: Vec<String> = channel.participants
                 .iter()
-                .map(|pk| format!("{:?}", pk))
+                .map(|pk| pk.to_string())
                 .collect();
 
-            match l1_client.lock_collateral(channel_id.to_string(), total_collateral, participants).await {
+            match l1_client.lock_collateral(
+                channel.channel_id.to_string(),
+                channel.collateral.value(),
+                participants,
+                channel.challenge_period,
+            ).await {
                 Ok(tx_id) => {
-                    info!("✅ Locked {} units of collateral on L1, tx: {}", total_collateral, tx_id);
+                    info!("✅ Locked {} of collateral on L1, tx: {}", channel.collateral, tx_id);
+                    self.audit_l1(L1Action::LockCollateral, Some(channel.channel_id), &tx_id);
                 }
                 Err(e) => {
                     info!("⚠️  Failed to lock collateral on L1: {}. Continuing without L1 lock.", e);
                 }
             }
         }
+    }
+
+    /// Insert a newly opened channel in memory and persist it
+    async fn store_new_channel(&self, channel: MarketplaceChannel) -> Result<Hash> {
+        let channel_id = channel.channel_id;
 
-        // Store in memory
         let mut channels = self.channels.write().await;
         if channels.contains_key(&channel_id) {
             return Err(L2Error::ChannelAlreadyExists(channel_id.to_string()));
         }
-        channels.insert(channel_id, channel.clone());
+        channels.insert(channel_id, Arc::new(Mutex::new(channel.clone())));
         drop(channels);
+        self.channel_loaded_at.write().await.insert(channel_id, self.clock.now());
 
-        // Persist to storage
         self.storage.store_channel(&channel)?;
 
         Ok(channel_id)
@@ -123,15 +839,14 @@ impl MarketplaceManager {
 
     /// Activate a channel
     pub async fn activate_channel(&self, channel_id: &Hash) -> Result<()> {
-        let mut channels = self.channels.write().await;
-
-        let channel = channels.get_mut(channel_id)
-            .ok_or_else(|| L2Error::ChannelNotFound(channel_id.to_string()))?;
+        let handle = self.channel(channel_id).await?;
+        let mut channel = handle.lock().await;
 
         channel.activate()?;
 
         // Persist changes
-        self.storage.store_channel(channel)?;
+        self.storage.store_channel(&channel)?;
+        self.publish_channel_status(&channel);
 
         info!("Activated channel: {:?}", channel_id);
         Ok(())
@@ -139,11 +854,81 @@ impl MarketplaceManager {
 
     /// Get channel info
     pub async fn get_channel_info(&self, channel_id: &Hash) -> Result<ChannelInfo> {
-        let channels = self.channels.read().await;
-        let channel = channels.get(channel_id)
+        let mut info = self.channel(channel_id).await?.lock().await.info();
+        info.pending_updates = self.list_pending_updates(channel_id).await.len();
+        Ok(info)
+    }
+
+    /// Replay the persisted channel's signed history from its opening
+    /// balances and report where it first disagrees with the stored state
+    pub async fn replay_channel(&self, channel_id: &Hash) -> Result<ReplayReport> {
+        let channel = self.storage.load_channel(channel_id)?
             .ok_or_else(|| L2Error::ChannelNotFound(channel_id.to_string()))?;
+        let report = channel.replay();
+        match &report.divergence {
+            None => info!("🔁 Replayed {} updates of channel {}: consistent", report.replayed, channel_id),
+            Some(divergence) => warn!("⚠️  Channel {} diverges after {} updates: {:?}", channel_id, report.replayed, divergence),
+        }
+        Ok(report)
+    }
+
+    /// Replay the update journal after a restart.
+    ///
+    /// Acked updates that were not persisted are applied exactly once,
+    /// entries already reflected in the stored channel are discarded, and
+    /// proposals still collecting signatures are restored as pending.
+    /// Returns the number of updates applied.
+    pub async fn recover_journal(&self) -> Result<usize> {
+        let entries = self.storage.load_journal()?;
+        let mut applied = 0;
+
+        for entry in entries {
+            let handle = match self.channel(&entry.channel_id).await {
+                Ok(handle) => handle,
+                Err(L2Error::ChannelNotFound(_)) => {
+                    warn!("Dropping journal entry for unknown channel {:?}", entry.channel_id);
+                    self.storage.remove_journal_entry(&entry)?;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let mut channel = handle.lock().await;
+
+            if entry.update.nonce <= channel.state.nonce {
+                // Already reflected in persisted state
+                self.storage.remove_journal_entry(&entry)?;
+                continue;
+            }
 
-        Ok(channel.info())
+            match entry.status {
+                JournalStatus::Acked => {
+                    match channel.apply_update(entry.update.clone()) {
+                        Ok(()) => {
+                            self.storage.append_update(&channel)?;
+                            self.storage.clear_journal(&entry.channel_id, channel.state.nonce)?;
+                            applied += 1;
+                            info!("Recovered update {} for channel {:?}", entry.update.nonce, entry.channel_id);
+                        }
+                        Err(e) => {
+                            warn!("Discarding unrecoverable journal entry for {:?}: {}", entry.channel_id, e);
+                            self.storage.remove_journal_entry(&entry)?;
+                        }
+                    }
+                }
+                JournalStatus::Proposed => {
+                    drop(channel);
+                    self.pending_since.write().await
+                        .entry((entry.channel_id, entry.update.nonce))
+                        .or_insert(entry.recorded_at);
+                    self.pending_updates.write().await
+                        .entry(entry.channel_id)
+                        .or_insert_with(Vec::new)
+                        .push(entry.update);
+                }
+            }
+        }
+
+        Ok(applied)
     }
 
     /// Create and sign a state update
@@ -152,61 +937,914 @@ impl MarketplaceManager {
         channel_id: &Hash,
         update: StateUpdate,
     ) -> Result<SignedStateUpdate> {
-        let channels = self.channels.read().await;
-        let channel = channels.get(channel_id)
-            .ok_or_else(|| L2Error::ChannelNotFound(channel_id.to_string()))?;
-
-        let nonce = channel.state.nonce + 1;
+        let nonce = self.channel(channel_id).await?.lock().await.state.nonce + 1;
+        self.check_spending(channel_id, nonce, &update).await?;
         let mut signed_update = SignedStateUpdate::new(update, nonce);
 
         // Sign the update
-        let message = bincode::serialize(&signed_update.update)
-            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
-        let mut signing_data = message;
-        signing_data.extend_from_slice(&nonce.to_le_bytes());
-
-        let signature = self.keypair.sign(&signing_data);
+        let signature = self.keypair.sign(&signed_update.signing_message());
         signed_update.add_signature(self.keypair.public_key(), signature);
 
+        self.track_pending_update(channel_id, signed_update.clone()).await?;
+
         Ok(signed_update)
     }
 
-    /// Add a signature to a pending state update
-    pub async fn add_signature_to_update(
+    /// Create and sign a state update, then send it to the other participants
+    /// to co-sign. It is committed once their acks complete it, or at once
+    /// if this node is the only participant.
+    #[instrument(skip_all, fields(channel_id = %channel_id, order_id = update.order_id().map(field::display)))]
+    pub async fn propose_state_update(
         &self,
-        signed_update: &mut SignedStateUpdate,
-    ) -> Result<()> {
-        let message = bincode::serialize(&signed_update.update)
-            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
-        let mut signing_data = message;
-        signing_data.extend_from_slice(&signed_update.nonce.to_le_bytes());
+        channel_id: &Hash,
+        update: StateUpdate,
+    ) -> Result<SignedStateUpdate> {
+        let signed_update = self.create_state_update(channel_id, update).await?;
+        let peers = self.counterparties(channel_id).await?;
+        if peers.is_empty() {
+            self.apply_state_update(channel_id, signed_update.clone()).await?;
+            return Ok(signed_update);
+        }
+        self.send_reliably(&peers, tari_l2_p2p::L2Message::StateUpdateProposal {
+            channel_id: *channel_id,
+            update: signed_update.clone(),
+        }).await?;
+        Ok(signed_update)
+    }
 
-        let signature = self.keypair.sign(&signing_data);
-        signed_update.add_signature(self.keypair.public_key(), signature);
+    /// Co-sign an update a client signed for its side of the channel. If
+    /// that completes the signature set the update is committed and the
+    /// fully signed copy sent to the other participants; otherwise it is
+    /// kept pending and proposed to the participants yet to sign, and
+    /// committed once their acks complete it. Returns whether it was
+    /// committed.
+    pub async fn submit_state_update(&self, channel_id: &Hash, mut update: SignedStateUpdate) -> Result<bool> {
+        self.wake_channel(channel_id).await?;
+        let local = self.keypair.public_key();
+        let (participants, nonce, outgoing) = {
+            let handle = self.channel(channel_id).await?;
+            let channel = handle.lock().await;
+            let outgoing = spending::outgoing(&update.update, &channel.state, &local);
+            (channel.participants.clone(), channel.state.nonce, outgoing)
+        };
+        if update.nonce != nonce + 1 {
+            return Err(L2Error::InvalidStateTransition);
+        }
+        // Funds only leave our key through updates this node builds itself,
+        // such as `transfer`; a caller cannot have us sign one away
+        if outgoing.value() > 0 && !update.signatures.contains_key(&local) {
+            return Err(L2Error::InvalidParameter(
+                "Submitted updates may not move funds out of the node's key".to_string()
+            ));
+        }
 
-        Ok(())
+        // Every signature it carries must check out: one under our key
+        // means this node built it, anything else is a counterparty's
+        // proposal and gets the checks one arriving over P2P would
+        self.validate_proposal(channel_id, &participants, &update).await?;
+        if !update.signatures.contains_key(&local) {
+            self.check_spending(channel_id, update.nonce, &update.update).await?;
+            self.add_signature_to_update(&mut update).await?;
+        }
+
+        let (recipients, committed) = if update.verify(&participants) {
+            self.apply_state_update(channel_id, update.clone()).await?;
+            (self.counterparties(channel_id).await?, true)
+        } else {
+            self.track_pending_update(channel_id, update.clone()).await?;
+            let unsigned = participants.iter()
+                .filter(|pk| !update.signatures.contains_key(pk))
+                .copied()
+                .collect();
+            (unsigned, false)
+        };
+        self.send_reliably(&recipients, tari_l2_p2p::L2Message::StateUpdateProposal {
+            channel_id: *channel_id,
+            update,
+        }).await?;
+        Ok(committed)
     }
 
-    /// Apply a signed state update to a channel
-    pub async fn apply_state_update(
+    /// Track an update that is still collecting signatures, merging it with
+    /// any pending copy, and journal it so signature collection survives a restart
+    async fn track_pending_update(&self, channel_id: &Hash, update: SignedStateUpdate) -> Result<()> {
+        let max_pending = self.limits.read().await.max_pending_updates;
+        let mut pending_updates = self.pending_updates.write().await;
+        let pending = pending_updates.entry(*channel_id).or_insert_with(Vec::new);
+
+        let hash = update.update.hash();
+        let record = match pending.iter_mut().find(|u| u.nonce == update.nonce && u.update.hash() == hash) {
+            Some(existing) => {
+                existing.signatures.extend(update.signatures);
+                existing.clone()
+            }
+            None if pending.len() >= max_pending => {
+                return Err(L2Error::ResourceLimitExceeded(format!(
+                    "Too many pending updates for channel {:?} ({})", channel_id, max_pending
+                )));
+            }
+            None => {
+                self.pending_since.write().await
+                    .entry((*channel_id, update.nonce))
+                    .or_insert_with(|| self.clock.now());
+                pending.push(update.clone());
+                update
+            }
+        };
+        drop(pending_updates);
+
+        self.storage.journal_update(&JournalEntry::new(*channel_id, record, JournalStatus::Proposed))
+    }
+
+    /// Set the limits on what this node signs away from a channel. Default
+    /// (unlimited) limits remove them, so the node's defaults apply again.
+    pub async fn set_spending_limits(&self, channel_id: &Hash, limits: SpendingLimits) -> Result<()> {
+        self.channel(channel_id).await?;
+        self.storage.store_spending_limits(channel_id, &limits)?;
+        let mut all = self.spending_limits.write().await;
+        if limits.is_unlimited() {
+            all.remove(channel_id);
+        } else {
+            all.insert(*channel_id, limits);
+        }
+        Ok(())
+    }
+
+    /// Set the limits for channels without their own
+    pub async fn set_default_spending_limits(&self, limits: SpendingLimits) {
+        *self.default_spending_limits.write().await = limits;
+    }
+
+    /// The limits that apply to a channel: its own, or the node's defaults
+    async fn effective_spending_limits(&self, channel_id: &Hash) -> SpendingLimits {
+        match self.spending_limits.read().await.get(channel_id) {
+            Some(limits) => limits.clone(),
+            None => self.default_spending_limits.read().await.clone(),
+        }
+    }
+
+    /// A channel's spending limits and what was spent against them in the
+    /// last 24 hours
+    pub async fn spending_limits(&self, channel_id: &Hash) -> (SpendingLimits, u64) {
+        let limits = self.effective_spending_limits(channel_id).await;
+        let spent = self.spending_windows.write().await
+            .get_mut(channel_id)
+            .map_or(0, |window| window.spent(self.clock.now()));
+        (limits, spent)
+    }
+
+    /// Refuse to sign the update at `nonce` if it moves more of our funds
+    /// out of a channel than its spending limits allow; otherwise count it
+    /// against the daily limit. An update already counted passes again.
+    async fn check_spending(&self, channel_id: &Hash, nonce: u64, update: &StateUpdate) -> Result<()> {
+        let limits = self.effective_spending_limits(channel_id).await;
+        if limits.is_unlimited() {
+            return Ok(());
+        }
+        let amount = self.outgoing(channel_id, update).await?;
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let now = self.clock.now();
+        let hash = update.hash();
+        let mut windows = self.spending_windows.write().await;
+        let window = windows.entry(*channel_id).or_default();
+        if window.contains(nonce, &hash) {
+            return Ok(());
+        }
+        if let Err(reason) = limits.check(amount, window.spent(now)) {
+            warn!("Refused to sign update for channel {:?}: {}", channel_id, reason);
+            return Err(L2Error::ResourceLimitExceeded(reason));
+        }
+        window.record(nonce, hash, amount, now);
+        Ok(())
+    }
+
+    /// Value `update` would move out of our balance in a channel
+    async fn outgoing(&self, channel_id: &Hash, update: &StateUpdate) -> Result<u64> {
+        let handle = self.channel(channel_id).await?;
+        let channel = handle.lock().await;
+        Ok(spending::outgoing(update, &channel.state, &self.keypair.public_key()).value())
+    }
+
+    /// Whether a counterparty's proposal may be co-signed without the
+    /// operator: it moves none of our funds, or it stays within limits that
+    /// apply to the channel (and is counted against them)
+    async fn may_co_sign(&self, channel_id: &Hash, nonce: u64, update: &StateUpdate) -> Result<bool> {
+        if self.outgoing(channel_id, update).await? == 0 {
+            return Ok(true);
+        }
+        if self.effective_spending_limits(channel_id).await.is_unlimited() {
+            return Ok(false);
+        }
+        match self.check_spending(channel_id, nonce, update).await {
+            Ok(()) => Ok(true),
+            Err(L2Error::ResourceLimitExceeded(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Hold a counterparty's proposal until the operator approves or
+    /// declines it. A redelivered copy is kept once; a different update at
+    /// a nonce already held is refused.
+    async fn hold_for_approval(&self, channel_id: &Hash, update: SignedStateUpdate) -> Result<()> {
+        let max_pending = self.limits.read().await.max_pending_updates;
+        let mut awaiting = self.awaiting_approval.write().await;
+        let held = awaiting.entry(*channel_id).or_default();
+
+        if let Some(existing) = held.iter().find(|u| u.nonce == update.nonce) {
+            if existing.update.hash() != update.update.hash() {
+                return Err(L2Error::InvalidStateTransition);
+            }
+            return Ok(());
+        }
+        if held.len() >= max_pending {
+            return Err(L2Error::ResourceLimitExceeded(format!(
+                "Too many updates awaiting approval for channel {:?} ({})", channel_id, max_pending
+            )));
+        }
+
+        info!("Update {} for channel {:?} moves our funds and awaits approval", update.nonce, channel_id);
+        held.push(update);
+        Ok(())
+    }
+
+    /// Counterparty proposals for a channel held for the operator's approval
+    pub async fn list_awaiting_approval(&self, channel_id: &Hash) -> Vec<SignedStateUpdate> {
+        self.awaiting_approval.read().await
+            .get(channel_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Take a held proposal out of the approval queue
+    async fn take_awaiting(&self, channel_id: &Hash, nonce: u64) -> Result<SignedStateUpdate> {
+        let mut awaiting = self.awaiting_approval.write().await;
+        let held = awaiting.get_mut(channel_id)
+            .ok_or_else(|| L2Error::InvalidParameter(format!("No update {} awaits approval", nonce)))?;
+        let position = held.iter().position(|u| u.nonce == nonce)
+            .ok_or_else(|| L2Error::InvalidParameter(format!("No update {} awaits approval", nonce)))?;
+        let update = held.remove(position);
+        if held.is_empty() {
+            awaiting.remove(channel_id);
+        }
+        Ok(update)
+    }
+
+    /// Co-sign a held proposal and ack it to its proposer. It is checked
+    /// against the current state again and counted against the daily
+    /// spending limit, which the approval overrides.
+    pub async fn approve_update(&self, channel_id: &Hash, nonce: u64) -> Result<SignedStateUpdate> {
+        use tari_l2_p2p::L2Message;
+
+        let mut update = self.take_awaiting(channel_id, nonce).await?;
+        let participants = self.channel(channel_id).await?.lock().await.participants.clone();
+        self.validate_proposal(channel_id, &participants, &update).await?;
+
+        let amount = self.outgoing(channel_id, &update.update).await?;
+        self.spending_windows.write().await
+            .entry(*channel_id)
+            .or_default()
+            .record(nonce, update.update.hash(), amount, self.clock.now());
+        self.add_signature_to_update(&mut update).await?;
+        self.track_pending_update(channel_id, update.clone()).await?;
+
+        let local = self.keypair.public_key();
+        let signers: Vec<PublicKey> = update.signatures.keys()
+            .filter(|pk| **pk != local)
+            .copied()
+            .collect();
+        if let Some(signature) = update.signatures.get(&local) {
+            let ack = L2Message::StateUpdateAck { channel_id: *channel_id, nonce, signature: signature.clone() };
+            self.send_to_peers(&signers, ack).await?;
+        }
+        Ok(update)
+    }
+
+    /// Drop a held proposal without signing it
+    pub async fn decline_update(&self, channel_id: &Hash, nonce: u64) -> Result<()> {
+        self.take_awaiting(channel_id, nonce).await?;
+        info!("Declined update {} for channel {:?}", nonce, channel_id);
+        Ok(())
+    }
+
+    /// Drop proposals that have waited more than `ttl_secs` for their
+    /// signatures, with their journal entries, so a counterparty that never
+    /// answers does not hold a channel's pending slots forever. Returns the
+    /// channel and nonce of each dropped update.
+    pub async fn expire_pending_updates(&self, ttl_secs: u64) -> Result<Vec<(Hash, u64)>> {
+        let now = self.clock.now();
+        let mut pending_updates = self.pending_updates.write().await;
+        let mut since = self.pending_since.write().await;
+
+        let mut expired = Vec::new();
+        for (channel_id, pending) in pending_updates.iter_mut() {
+            pending.retain(|update| {
+                let proposed = since.get(&(*channel_id, update.nonce)).copied().unwrap_or(now);
+                if now.as_secs().saturating_sub(proposed.as_secs()) > ttl_secs {
+                    expired.push((*channel_id, update.clone()));
+                    return false;
+                }
+                true
+            });
+        }
+        pending_updates.retain(|_, pending| !pending.is_empty());
+        since.retain(|(channel_id, nonce), _| {
+            pending_updates.get(channel_id).is_some_and(|pending| pending.iter().any(|u| u.nonce == *nonce))
+        });
+        drop(pending_updates);
+        drop(since);
+
+        for (channel_id, update) in &expired {
+            info!("Expired unsigned update {} for channel {:?}", update.nonce, channel_id);
+            self.storage.remove_journal_entry(&JournalEntry::new(*channel_id, update.clone(), JournalStatus::Proposed))?;
+        }
+        Ok(expired.into_iter().map(|(channel_id, update)| (channel_id, update.nonce)).collect())
+    }
+
+    /// List updates created for a channel that have not been applied yet
+    pub async fn list_pending_updates(&self, channel_id: &Hash) -> Vec<SignedStateUpdate> {
+        self.pending_updates.read().await
+            .get(channel_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The fully signed update applied to a channel at `nonce`, for peers
+    /// catching up after missing it
+    pub async fn applied_update(&self, channel_id: &Hash, nonce: u64) -> Option<SignedStateUpdate> {
+        let handle = self.channel(channel_id).await.ok()?;
+        let channel = handle.lock().await;
+        channel.state_history
+            .iter()
+            .find(|u| u.nonce == nonce)
+            .cloned()
+    }
+
+    /// Add a signature to a pending state update
+    pub async fn add_signature_to_update(
+        &self,
+        signed_update: &mut SignedStateUpdate,
+    ) -> Result<()> {
+        let signature = self.keypair.sign(&signed_update.signing_message());
+        signed_update.add_signature(self.keypair.public_key(), signature);
+
+        Ok(())
+    }
+
+    /// Apply a signed state update to a channel
+    #[instrument(skip_all, fields(channel_id = %channel_id, nonce = signed_update.nonce, order_id = signed_update.update.order_id().map(field::display)))]
+    pub async fn apply_state_update(
         &self,
         channel_id: &Hash,
         signed_update: SignedStateUpdate,
     ) -> Result<()> {
-        let mut channels = self.channels.write().await;
+        let handle = self.channel(channel_id).await?;
+        let mut channel = handle.lock().await;
 
-        let channel = channels.get_mut(channel_id)
-            .ok_or_else(|| L2Error::ChannelNotFound(channel_id.to_string()))?;
+        // Redelivery of an update we already applied (gossip, sync replays)
+        // is a no-op; a different update at an applied nonce still fails
+        if channel.is_duplicate(&signed_update) {
+            debug!("Ignoring duplicate update {} for channel {:?}", signed_update.nonce, channel_id);
+            return Ok(());
+        }
 
-        channel.apply_update(signed_update)?;
+        // Write ahead: record the fully signed update before touching state
+        let entry = JournalEntry::new(*channel_id, signed_update.clone(), JournalStatus::Acked);
+        self.storage.journal_update(&entry)?;
 
-        // Persist changes
-        self.storage.store_channel(channel)?;
+        let order_event = match &signed_update.update {
+            StateUpdate::CreateOrder { order } => Some((order.id, None, order.status.clone())),
+            StateUpdate::UpdateOrderStatus { order_id, status } => {
+                let previous = channel.state.orders.iter().find(|o| o.id == *order_id).map(|o| o.status.clone());
+                Some((*order_id, previous, status.clone()))
+            }
+            StateUpdate::CompleteOrderPaidOnL1 { order_id, .. } => {
+                let previous = channel.state.orders.iter().find(|o| o.id == *order_id).map(|o| o.status.clone());
+                Some((*order_id, previous, OrderStatus::Completed))
+            }
+            _ => None,
+        };
+        let locked_order = match &signed_update.update {
+            StateUpdate::LockEscrow { order_id } => Some(*order_id),
+            _ => None,
+        };
+        let completed_order = match &signed_update.update {
+            StateUpdate::UpdateOrderStatus { order_id, status: OrderStatus::Completed }
+            | StateUpdate::CompleteOrderPaidOnL1 { order_id, .. } => channel.state.orders.iter()
+                .find(|o| o.id == *order_id && o.status != OrderStatus::Completed)
+                .cloned(),
+            _ => None,
+        };
+        let invoice_payment = matches!(signed_update.update, StateUpdate::PayInvoice { .. })
+            .then(|| signed_update.update.clone());
+
+        let update_hash = crypto::hash_data(&signed_update.signing_message());
+        let now = self.clock.now();
+        if let Err(e) = channel.apply_update_at(signed_update, now) {
+            self.storage.remove_journal_entry(&entry)?;
+            return Err(e);
+        }
+        let nonce = channel.state.nonce;
 
+        // Persist changes, then retire the journal entries they cover
+        self.storage.append_update(&channel)?;
+        self.storage.clear_journal(channel_id, nonce)?;
+        let changed_order = order_event.as_ref()
+            .and_then(|(order_id, _, _)| channel.state.orders.iter().find(|o| o.id == *order_id))
+            .cloned();
+        if let Some(order) = &changed_order {
+            self.storage.store_order(channel_id, order)?;
+        }
+        if let Some(update) = &invoice_payment {
+            self.record_invoice_payment(channel_id, nonce, update, now.as_secs())?;
+        }
+
+        let own_key = self.keypair.public_key();
+        let receipt_terms = completed_order
+            .filter(|o| o.buyer == own_key || o.seller == own_key)
+            .zip(channel.state_history.last().cloned())
+            .map(|(order, anchor)| (ReceiptTerms {
+                kind: ReceiptKind::Order {
+                    order_id: order.id,
+                    listing_id: order.listing_id,
+                    buyer: order.buyer,
+                    seller: order.seller,
+                    amount: order.amount.value(),
+                },
+                channel_id: *channel_id,
+                parties: channel.participants.clone(),
+                state_root: channel.get_state_root(),
+                nonce,
+                started_at: None,
+                settled_at: now.as_secs(),
+            }, anchor));
+        drop(channel);
+
+        // Drop pending and held updates superseded by the applied state
+        if let Some(pending) = self.pending_updates.write().await.get_mut(channel_id) {
+            pending.retain(|u| u.nonce > nonce);
+        }
+        if let Some(held) = self.awaiting_approval.write().await.get_mut(channel_id) {
+            held.retain(|u| u.nonce > nonce);
+        }
+        if let Some(order) = changed_order {
+            self.global_orders.write().await.insert(order.id, (*channel_id, order));
+        }
+        if let Some(order_id) = locked_order {
+            self.commit_escrow_funding(&order_id, now).await?;
+        }
+
+        if let Some((mut terms, anchor)) = receipt_terms {
+            if let ReceiptKind::Order { order_id, .. } = &terms.kind {
+                terms.started_at = self.escrow_for_order(order_id).await.map(|e| e.created_at.as_secs());
+            }
+            self.issue_receipt(terms, Some(anchor))?;
+        }
+
+        self.audit(AuditEvent::StateUpdate { channel_id: *channel_id, nonce, update_hash });
+        self.events.publish(MarketplaceEvent::ChannelUpdate { channel_id: *channel_id, nonce });
+        if let Some((order_id, previous, status)) = order_event {
+            self.events.publish(MarketplaceEvent::OrderStatus { channel_id: *channel_id, order_id, previous, status });
+        }
         info!("Applied state update to channel: {:?}", channel_id);
         Ok(())
     }
 
+    /// Handle a state update proposed by a counterparty.
+    ///
+    /// A proposal missing our signature is validated against the current
+    /// state, co-signed and kept as a pending record, then acked; it is only
+    /// committed once the fully signed copy comes back. A fully signed
+    /// proposal is committed and acked. Proposals for nonces we already
+    /// committed get our ack, or our fully signed copy if theirs lacks
+    /// signatures, so a peer that lost messages can catch up.
+    ///
+    /// Proposals up to [`REORDER_WINDOW`] nonces ahead are buffered and the
+    /// missing updates requested from the proposer; buffered proposals are
+    /// processed as soon as they are next in line.
+    #[instrument(skip_all, fields(channel_id = %channel_id, nonce = update.nonce, order_id = update.update.order_id().map(field::display)))]
+    pub async fn receive_state_update(&self, channel_id: &Hash, update: SignedStateUpdate) -> Result<()> {
+        self.wake_channel(channel_id).await?;
+        self.receive_next_update(channel_id, update).await?;
+        self.drain_future_updates(channel_id).await;
+        Ok(())
+    }
+
+    /// Process buffered proposals that are now next in line
+    async fn drain_future_updates(&self, channel_id: &Hash) {
+        loop {
+            let Ok(handle) = self.channel(channel_id).await else {
+                return;
+            };
+            let nonce = handle.lock().await.state.nonce;
+            let next = {
+                let mut future = self.future_updates.write().await;
+                let Some(buffer) = future.get_mut(channel_id) else {
+                    return;
+                };
+                // Anything at or below the committed nonce is stale
+                *buffer = buffer.split_off(&(nonce + 1));
+                let next = buffer.remove(&(nonce + 1));
+                if buffer.is_empty() {
+                    future.remove(channel_id);
+                }
+                next
+            };
+            let Some(next) = next else {
+                return;
+            };
+            if let Err(e) = self.receive_next_update(channel_id, next).await {
+                warn!("Dropping buffered update for channel {:?}: {}", channel_id, e);
+            }
+        }
+    }
+
+    async fn receive_next_update(&self, channel_id: &Hash, mut update: SignedStateUpdate) -> Result<()> {
+        use tari_l2_p2p::L2Message;
+
+        let local = self.keypair.public_key();
+        let (participants, nonce) = {
+            let handle = self.channel(channel_id).await?;
+            let channel = handle.lock().await;
+            (channel.participants.clone(), channel.state.nonce)
+        };
+        let signers: Vec<PublicKey> = update.signatures.keys()
+            .filter(|pk| **pk != local)
+            .copied()
+            .collect();
+
+        if update.nonce <= nonce {
+            let Some(applied) = self.applied_update(channel_id, update.nonce).await else {
+                return Ok(());
+            };
+            let reply = if applied.signatures.len() > update.signatures.len() {
+                L2Message::StateUpdateProposal { channel_id: *channel_id, update: applied }
+            } else if let Some(signature) = applied.signatures.get(&local) {
+                L2Message::StateUpdateAck { channel_id: *channel_id, nonce: applied.nonce, signature: signature.clone() }
+            } else {
+                return Ok(());
+            };
+            return self.send_to_peers(&signers, reply).await;
+        }
+        if update.nonce > nonce + 1 {
+            if update.nonce > nonce + REORDER_WINDOW {
+                // Too far ahead to buffer; the proposer resends once we have caught up
+                debug!("Ignoring update {} for channel {:?} at nonce {}", update.nonce, channel_id, nonce);
+                return Ok(());
+            }
+            let message = update.signing_message();
+            let signed_by_participants = update.verify(&participants) || (!update.signatures.is_empty()
+                && update.signatures.iter().all(|(pk, sig)| participants.contains(pk) && pk.verify(&message, sig)));
+            if !signed_by_participants {
+                return Err(L2Error::InvalidSignature);
+            }
+
+            let newly_buffered = self.future_updates.write().await
+                .entry(*channel_id)
+                .or_default()
+                .insert(update.nonce, update.clone())
+                .is_none();
+            if !newly_buffered {
+                return Ok(());
+            }
+            let peers = if signers.is_empty() { self.counterparties(channel_id).await? } else { signers };
+            return self.send_to_peers(&peers, L2Message::StateUpdateGapRequest {
+                channel_id: *channel_id,
+                from_nonce: nonce + 1,
+                to_nonce: update.nonce - 1,
+            }).await;
+        }
+
+        if update.verify(&participants) {
+            self.apply_state_update(channel_id, update.clone()).await?;
+        } else {
+            self.validate_proposal(channel_id, &participants, &update).await?;
+            if !update.signatures.contains_key(&local) {
+                self.alert_on_dispute(channel_id, &update, &signers).await;
+                // Proposals that debit us beyond what we sign unattended
+                // wait for the operator, unsigned and unacked
+                if !self.may_co_sign(channel_id, update.nonce, &update.update).await? {
+                    return self.hold_for_approval(channel_id, update).await;
+                }
+                self.add_signature_to_update(&mut update).await?;
+            }
+            self.track_pending_update(channel_id, update.clone()).await?;
+        }
+
+        match update.signatures.get(&local) {
+            Some(signature) => {
+                let ack = L2Message::StateUpdateAck { channel_id: *channel_id, nonce: update.nonce, signature: signature.clone() };
+                self.send_to_peers(&signers, ack).await
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Alert the operator when a counterparty proposes disputing one of our orders
+    async fn alert_on_dispute(&self, channel_id: &Hash, update: &SignedStateUpdate, signers: &[PublicKey]) {
+        let StateUpdate::UpdateOrderStatus { order_id, status: OrderStatus::Disputed } = &update.update else {
+            return;
+        };
+        let Some(by) = signers.first() else {
+            return;
+        };
+        let local = self.keypair.public_key();
+        let Some(handle) = self.channels.read().await.get(channel_id).cloned() else {
+            return;
+        };
+        let ours = handle.lock().await.state.orders.iter().any(|o| {
+            o.id == *order_id && o.status != OrderStatus::Disputed && (o.buyer == local || o.seller == local)
+        });
+        if ours {
+            self.alerts.raise(AlertKind::DisputeOpened { channel_id: *channel_id, order_id: *order_id, by: *by }, self.clock.now());
+        }
+    }
+
+    /// Handle a counterparty's ack: add its signature to our pending update at
+    /// `nonce` and, once every participant has signed, commit the update and
+    /// send the fully signed copy to the other participants. Returns the
+    /// committed update, or None if the ack did not complete one.
+    #[instrument(skip_all, fields(channel_id = %channel_id, nonce = nonce))]
+    pub async fn handle_state_update_ack(
+        &self,
+        channel_id: &Hash,
+        nonce: u64,
+        signature: Signature,
+    ) -> Result<Option<SignedStateUpdate>> {
+        self.wake_channel(channel_id).await?;
+        self.confirm_outbox_delivery(channel_id, nonce, &signature)?;
+
+        let participants = {
+            let handle = self.channel(channel_id).await?;
+            let channel = handle.lock().await;
+            if channel.state.nonce >= nonce {
+                return Ok(None);
+            }
+            channel.participants.clone()
+        };
+
+        let update = {
+            let mut pending_updates = self.pending_updates.write().await;
+            let Some(pending) = pending_updates.get_mut(channel_id) else {
+                return Ok(None);
+            };
+            if !pending.iter().any(|u| u.nonce == nonce) {
+                return Ok(None);
+            }
+
+            // Acks do not name their signer, so match the signature against
+            // the participants for each candidate at this nonce
+            let mut acked = None;
+            for update in pending.iter_mut().filter(|u| u.nonce == nonce) {
+                let message = update.signing_message();
+                if let Some(signer) = participants.iter().find(|p| p.verify(&message, &signature)) {
+                    update.add_signature(*signer, signature.clone());
+                    acked = Some(update.clone());
+                    break;
+                }
+            }
+            acked.ok_or(L2Error::InvalidSignature)?
+        };
+
+        if !update.verify(&participants) {
+            self.storage.journal_update(&JournalEntry::new(*channel_id, update, JournalStatus::Proposed))?;
+            return Ok(None);
+        }
+
+        self.apply_state_update(channel_id, update.clone()).await?;
+        let peers = self.counterparties(channel_id).await?;
+        self.send_reliably(&peers, tari_l2_p2p::L2Message::StateUpdateProposal {
+            channel_id: *channel_id,
+            update: update.clone(),
+        }).await?;
+        self.drain_future_updates(channel_id).await;
+        Ok(Some(update))
+    }
+
+    /// Answer a counterparty's gap-fill request with the committed updates
+    /// it is missing, at most [`REORDER_WINDOW`] of them
+    pub async fn handle_gap_request(
+        &self,
+        from: &PublicKey,
+        channel_id: &Hash,
+        from_nonce: u64,
+        to_nonce: u64,
+    ) -> Result<Option<tari_l2_p2p::L2Message>> {
+        let handle = self.channel(channel_id).await?;
+        let channel = handle.lock().await;
+        if !channel.participants.contains(from) {
+            return Err(L2Error::ParticipantNotFound);
+        }
+
+        let to_nonce = to_nonce.min(from_nonce.saturating_add(REORDER_WINDOW - 1));
+        let updates: Vec<SignedStateUpdate> = channel.state_history.iter()
+            .filter(|u| (from_nonce..=to_nonce).contains(&u.nonce))
+            .cloned()
+            .collect();
+        if updates.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(tari_l2_p2p::L2Message::StateUpdateGapFill { channel_id: *channel_id, updates }))
+    }
+
+    /// Apply committed updates a counterparty sent to fill a gap, followed
+    /// by any buffered proposals they unblock
+    pub async fn handle_gap_fill(&self, channel_id: &Hash, mut updates: Vec<SignedStateUpdate>) -> Result<()> {
+        updates.sort_by_key(|u| u.nonce);
+        for update in updates.into_iter().take(REORDER_WINDOW as usize) {
+            self.receive_state_update(channel_id, update).await?;
+        }
+        Ok(())
+    }
+
+    /// Check a counterparty's proposal before co-signing it: its signatures
+    /// are valid, it applies to our current state, an order move is one the
+    /// proposer's role allows, an order we sell completed as paid on L1 has
+    /// its payout there, and we have not already signed a different update
+    /// at the same nonce
+    async fn validate_proposal(
+        &self,
+        channel_id: &Hash,
+        participants: &[PublicKey],
+        update: &SignedStateUpdate,
+    ) -> Result<()> {
+        if update.aggregate.is_some() || update.signatures.is_empty() {
+            return Err(L2Error::InvalidSignature);
+        }
+        let message = update.signing_message();
+        for (participant, signature) in &update.signatures {
+            if !participants.contains(participant) {
+                return Err(L2Error::ParticipantNotFound);
+            }
+            if !participant.verify(&message, signature) {
+                return Err(L2Error::InvalidSignature);
+            }
+        }
+
+        let local = self.keypair.public_key();
+        let hash = update.update.hash();
+        if let Some(pending) = self.pending_updates.read().await.get(channel_id) {
+            let conflicting = pending.iter().any(|u| {
+                u.nonce == update.nonce && u.update.hash() != hash && u.signatures.contains_key(&local)
+            });
+            if conflicting {
+                return Err(L2Error::InvalidStateTransition);
+            }
+        }
+
+        let handle = self.channel(channel_id).await?;
+        let channel = handle.lock().await;
+        if channel.status != ChannelStatus::Active {
+            return Err(L2Error::InvalidChannelState);
+        }
+        update.update.apply(channel.state.clone())?;
+
+        if let StateUpdate::UpdateOrderStatus { order_id, status } = &update.update {
+            let order = channel.state.orders.iter().find(|o| o.id == *order_id);
+            if let Some(order) = order.filter(|o| !(o.status.is_final() && o.status == *status)) {
+                for proposer in update.signatures.keys() {
+                    order.check_transition(&order.roles_of(proposer), status)?;
+                }
+            }
+        }
+
+        // Completing an order paid on L1 gives up the seller's payment in
+        // the channel, so as the seller we first check the payout was made
+        let mut unverified_payout = None;
+        if let StateUpdate::CompleteOrderPaidOnL1 { order_id, payout_tx_id } = &update.update {
+            let order = channel.state.orders.iter().find(|o| o.id == *order_id);
+            if let Some(order) = order.filter(|o| o.status != OrderStatus::Completed) {
+                for proposer in update.signatures.keys() {
+                    order.check_transition(&order.roles_of(proposer), &OrderStatus::Completed)?;
+                }
+                if order.seller == local && !update.signatures.contains_key(&local) {
+                    unverified_payout = Some(payout_tx_id.clone());
+                }
+            }
+        }
+        drop(channel);
+
+        if let Some(tx_id) = unverified_payout {
+            let l1_client = self.l1_client.as_ref()
+                .ok_or_else(|| L2Error::TariConnectionError("No L1 client configured".to_string()))?;
+            let paid = l1_client.verify_transaction(tx_id.clone()).await
+                .map_err(|e| L2Error::TariConnectionError(format!("Could not verify escrow payout {}: {}", tx_id, e)))?;
+            if !paid {
+                return Err(L2Error::InvalidParameter(format!("Escrow payout {} is not on L1", tx_id)));
+            }
+        }
+        Ok(())
+    }
+
+    /// The other participants of a channel
+    async fn counterparties(&self, channel_id: &Hash) -> Result<Vec<PublicKey>> {
+        let local = self.keypair.public_key();
+        let handle = self.channel(channel_id).await?;
+        let channel = handle.lock().await;
+        Ok(channel.participants.iter().filter(|p| **p != local).copied().collect())
+    }
+
+    /// Send a message to each of `peers` over the P2P network, if there is one
+    async fn send_to_peers(&self, peers: &[PublicKey], message: tari_l2_p2p::L2Message) -> Result<()> {
+        if let Some(network) = self.network.read().await.as_ref() {
+            for peer in peers {
+                network.send_message(*peer, message.clone()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a message to each of `peers` through the persistent outbox.
+    /// Failed sends, and proposals until the peer acks them, are retried
+    /// by [`Self::flush_outbox`].
+    async fn send_reliably(&self, peers: &[PublicKey], message: tari_l2_p2p::L2Message) -> Result<()> {
+        let now = self.clock.now();
+        for peer in peers {
+            let entry = OutboxEntry::new(*peer, message.clone(), now);
+            self.storage.store_outbox_entry(&entry)?;
+            self.deliver(entry, now).await?;
+        }
+        Ok(())
+    }
+
+    /// Make one delivery attempt for an outbox entry, then retire it or
+    /// schedule its retry
+    async fn deliver(&self, mut entry: OutboxEntry, now: Timestamp) -> Result<bool> {
+        let sent = match self.network.read().await.as_ref() {
+            Some(network) => match network.send_message(entry.peer, entry.message.clone()).await {
+                Ok(()) => true,
+                Err(e) => {
+                    debug!("Send to {:?} failed, will retry: {}", entry.peer, e);
+                    false
+                }
+            },
+            None => false,
+        };
+
+        if sent && entry.confirmation.is_none() {
+            self.storage.delete_outbox_entry(&entry.id)?;
+        } else {
+            entry.schedule_retry(now);
+            self.storage.store_outbox_entry(&entry)?;
+        }
+        Ok(sent)
+    }
+
+    /// Retry outbox messages that are due. Proposals for channels that are
+    /// no longer active, and messages out of attempts, are dropped.
+    /// Returns the number of messages sent.
+    pub async fn flush_outbox(&self) -> Result<usize> {
+        let now = self.clock.now();
+        let mut sent = 0;
+
+        for entry in self.storage.load_outbox()? {
+            if !entry.is_due(now) {
+                continue;
+            }
+
+            let stale = match &entry.message {
+                tari_l2_p2p::L2Message::StateUpdateProposal { channel_id, .. } => {
+                    match self.channel(channel_id).await {
+                        Ok(handle) => handle.lock().await.status != ChannelStatus::Active,
+                        Err(L2Error::ChannelNotFound(_)) => true,
+                        Err(e) => return Err(e),
+                    }
+                }
+                _ => false,
+            };
+            if stale || entry.attempts >= MAX_DELIVERY_ATTEMPTS {
+                if !stale {
+                    warn!("Giving up on {:?} to {:?} after {} attempts", entry.message.message_type(), entry.peer, entry.attempts);
+                }
+                self.storage.delete_outbox_entry(&entry.id)?;
+                continue;
+            }
+
+            if self.deliver(entry, now).await? {
+                sent += 1;
+            }
+        }
+
+        Ok(sent)
+    }
+
+    /// Messages still waiting in the outbox
+    pub fn outbox_len(&self) -> Result<usize> {
+        Ok(self.storage.load_outbox()?.len())
+    }
+
+    /// Retire outbox proposals that an ack with `signature` confirms
+    fn confirm_outbox_delivery(&self, channel_id: &Hash, nonce: u64, signature: &Signature) -> Result<()> {
+        for entry in self.storage.load_outbox()? {
+            if entry.confirmed_by(channel_id, nonce, signature) {
+                self.storage.delete_outbox_entry(&entry.id)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Create a new listing
     pub async fn create_listing(
         &self,
@@ -218,6 +1856,7 @@ impl MarketplaceManager {
     }
 
     /// Create a new order
+    #[instrument(skip_all, fields(channel_id = %channel_id, order_id = %order.id))]
     pub async fn create_order(
         &self,
         channel_id: &Hash,
@@ -228,6 +1867,7 @@ impl MarketplaceManager {
     }
 
     /// Update order status
+    #[instrument(skip_all, fields(channel_id = %channel_id, order_id = %order_id))]
     pub async fn update_order_status(
         &self,
         channel_id: &Hash,
@@ -252,57 +1892,857 @@ impl MarketplaceManager {
 
     /// Get balance for a participant
     pub async fn get_balance(&self, channel_id: &Hash, participant: &PublicKey) -> Result<Amount> {
-        let channels = self.channels.read().await;
-        let channel = channels.get(channel_id)
-            .ok_or_else(|| L2Error::ChannelNotFound(channel_id.to_string()))?;
+        let handle = self.channel(channel_id).await?;
+        let channel = handle.lock().await;
 
         channel.get_balance(participant)
     }
 
-    /// List all channels
+    /// List all channels, including hibernated ones
     pub async fn list_channels(&self) -> Vec<ChannelInfo> {
-        let channels = self.channels.read().await;
-        channels.values().map(|c| c.info()).collect()
+        let hibernated = self.hibernated_channels().await;
+        let mut infos = Vec::new();
+        for handle in self.channel_handles().await {
+            infos.push(handle.lock().await.info());
+        }
+        let pending = self.pending_updates.read().await;
+        for info in &mut infos {
+            info.pending_updates = pending.get(&info.channel_id).map_or(0, Vec::len);
+        }
+        infos.extend(hibernated.iter().map(MarketplaceChannel::info));
+        infos
     }
 
-    /// Close a channel
-    pub async fn close_channel(&self, channel_id: &Hash) -> Result<()> {
-        let mut channels = self.channels.write().await;
+    /// Start closing a channel. Collateral is released by
+    /// `finalize_closed_channels` once the challenge period has passed;
+    /// returns when that will be.
+    #[instrument(skip_all, fields(channel_id = %channel_id))]
+    pub async fn close_channel(&self, channel_id: &Hash) -> Result<Timestamp> {
+        let handle = self.channel(channel_id).await?;
+        let mut channel = handle.lock().await;
 
-        let channel = channels.get_mut(channel_id)
-            .ok_or_else(|| L2Error::ChannelNotFound(channel_id.to_string()))?;
+        // The close pays out balances only; funds locked for orders would be
+        // lost to the seller, so those orders have to settle first
+        if !channel.state.escrow_locks.is_empty() {
+            return Err(L2Error::InvalidParameter(format!(
+                "{} order(s) still hold escrowed funds; complete or cancel them before closing",
+                channel.state.escrow_locks.len()
+            )));
+        }
 
-        // Get final balances before closing
-        let final_balances: HashMap<String, u64> = channel.participants
-            .iter()
-            .filter_map(|participant| {
-                channel.get_balance(participant).ok().map(|balance| {
-                    (format!("{:?}", participant), balance.value())
-                })
-            })
-            .collect();
+        channel.initiate_close(self.clock.now())?;
+        let deadline = channel.challenge_deadline().expect("close initiated");
+
+        // Persist changes
+        self.storage.store_channel(&channel)?;
+        self.publish_channel_status(&channel);
 
-        channel.initiate_close()?;
+        info!("Closing channel: {:?} (challenge period ends at {})", channel_id, deadline.as_secs());
+        Ok(deadline)
+    }
+
+    /// Dispute a closing channel during its challenge period, submitting
+    /// evidence of the latest co-signed state to L1. The collateral stays
+    /// locked until `resolve_disputes` pays it out. If L1 refuses the
+    /// dispute, as a connected L1 does for now, the channel stays Closing.
+    pub async fn challenge_close(&self, channel_id: &Hash) -> Result<()> {
+        let handle = self.channel(channel_id).await?;
+        let mut channel = handle.lock().await;
+
+        let now = self.clock.now();
+        channel.challenge(now)?;
 
-        // Unlock collateral on L1 if client available
         if let Some(ref l1_client) = self.l1_client {
-            match l1_client.unlock_collateral(channel_id.to_string(), final_balances.clone()).await {
+            let evidence = DisputeEvidence::from_channel(&channel);
+            match l1_client.submit_dispute(channel_id.to_string(), evidence, now.as_secs()).await {
                 Ok(tx_id) => {
-                    info!("✅ Unlocked collateral on L1, tx: {}", tx_id);
+                    info!("⚠️  Challenged close of {:?} on L1, tx: {}", channel_id, tx_id);
+                    self.audit_l1(L1Action::Dispute, Some(*channel_id), &tx_id);
                 }
                 Err(e) => {
-                    info!("⚠️  Failed to unlock collateral on L1: {}", e);
+                    channel.status = ChannelStatus::Closing;
+                    return Err(L2Error::TariConnectionError(format!("Failed to submit challenge to L1: {}", e)));
                 }
             }
         }
 
-        // Persist changes
-        self.storage.store_channel(channel)?;
+        self.storage.store_channel(&channel)?;
+        self.publish_channel_status(&channel);
+        Ok(())
+    }
+
+    /// Close a channel without the counterparties by publishing our latest
+    /// co-signed state to L1. The channel is Challenged at once: until the
+    /// challenge period ends a counterparty can counter with a higher-nonce
+    /// state, after which `resolve_disputes` pays out the collateral.
+    /// Returns the dispute transaction ID and when the window closes.
+    #[instrument(skip_all, fields(channel_id = %channel_id))]
+    pub async fn force_close_channel(&self, channel_id: &Hash) -> Result<(String, Timestamp)> {
+        let Some(ref l1_client) = self.l1_client else {
+            return Err(L2Error::TariConnectionError("No L1 client to publish the close".to_string()));
+        };
+        let handle = self.channel(channel_id).await?;
+        let mut channel = handle.lock().await;
+        if !channel.participants.contains(&self.keypair.public_key()) {
+            return Err(L2Error::ParticipantNotFound);
+        }
+        if !matches!(channel.status, ChannelStatus::Active | ChannelStatus::Closing) {
+            return Err(L2Error::InvalidChannelState);
+        }
+
+        let now = self.clock.now();
+        let evidence = DisputeEvidence::from_channel(&channel);
+        let tx_id = l1_client.submit_dispute(channel_id.to_string(), evidence, now.as_secs()).await
+            .map_err(|e| L2Error::TariConnectionError(format!("Failed to publish force close: {}", e)))?;
+        self.audit_l1(L1Action::Dispute, Some(*channel_id), &tx_id);
+        channel.force_close(now)?;
+        let deadline = channel.challenge_deadline().expect("close initiated");
+        self.storage.store_channel(&channel)?;
+        self.publish_channel_status(&channel);
+
+        warn!("⚠️  Force-closed channel {:?} at nonce {}, tx: {} (challenge period ends at {})",
+            channel_id, channel.state.nonce, tx_id, deadline.as_secs());
+        Ok((tx_id, deadline))
+    }
+
+    /// Find our channels a counterparty force-closed on L1 and mark them
+    /// Challenged. While the window is open, a close published at an older
+    /// state than ours is countered with our latest co-signed state.
+    /// Returns the channels newly found force-closed; a channel that cannot
+    /// be updated is logged and skipped so the rest are still handled.
+    pub async fn detect_force_closes(&self) -> Result<Vec<Hash>> {
+        let Some(ref l1_client) = self.l1_client else {
+            return Ok(Vec::new());
+        };
+        let now = self.clock.now().as_secs();
+        let mut found = Vec::new();
+
+        let disputes = l1_client.open_disputes(now).await
+            .map_err(|e| L2Error::TariConnectionError(format!("Failed to scan L1 for disputes: {}", e)))?;
+        for dispute in disputes {
+            let Ok(channel_id) = dispute.channel_id.parse::<Hash>() else {
+                continue;
+            };
+            let Ok(handle) = self.channel(&channel_id).await else {
+                continue;
+            };
+            let mut channel = handle.lock().await;
+            if matches!(channel.status, ChannelStatus::Active | ChannelStatus::Closing) {
+                if let Err(e) = channel.force_close(Timestamp::from_secs(dispute.opened_at))
+                    .and_then(|()| self.storage.store_channel(&channel))
+                {
+                    warn!("Failed to record force close of {:?}: {}", channel_id, e);
+                    continue;
+                }
+                self.publish_channel_status(&channel);
+                warn!("⚠️  Channel {:?} was force-closed on L1 at nonce {}", channel_id, dispute.best.nonce);
+                found.push(channel_id);
+            }
+
+            if channel.status == ChannelStatus::Challenged && channel.state.nonce > dispute.best.nonce {
+                let evidence = DisputeEvidence::from_channel(&channel);
+                match l1_client.submit_dispute(dispute.channel_id.clone(), evidence, now).await {
+                    Ok(tx_id) => {
+                        warn!("⚠️  Countered close of {:?} at nonce {} with nonce {}, tx: {}",
+                            channel_id, dispute.best.nonce, channel.state.nonce, tx_id);
+                        self.audit_l1(L1Action::Dispute, Some(channel_id), &tx_id);
+                    }
+                    Err(e) => warn!("Failed to counter close of {:?}: {}", channel_id, e),
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Hold watch blobs for up to `max_blobs` client channels; None stops
+    /// accepting new ones
+    pub async fn set_watchtower(&self, max_blobs: Option<usize>) {
+        *self.watchtower.write().await = max_blobs;
+    }
+
+    /// Seal our latest state of a channel for a watchtower to hold while we
+    /// are offline
+    pub async fn export_watch_blob(&self, channel_id: &Hash) -> Result<WatchBlob> {
+        let handle = self.channel(channel_id).await?;
+        let channel = handle.lock().await;
+
+        let local = self.keypair.public_key();
+        if !channel.participants.contains(&local) {
+            return Err(L2Error::ParticipantNotFound);
+        }
+        WatchBlob::seal(&channel, local, |message| self.keypair.sign(message))
+            .map_err(L2Error::SerializationError)
+    }
+
+    /// Hold a client's watch blob, replacing an older one they left for the
+    /// same channel. Only accepted in watchtower mode.
+    pub async fn accept_watch_blob(&self, blob: WatchBlob) -> Result<()> {
+        let Some(max_blobs) = *self.watchtower.read().await else {
+            return Err(L2Error::InvalidParameter("This node is not a watchtower".to_string()));
+        };
+        if !blob.verify() {
+            return Err(L2Error::InvalidSignature);
+        }
+
+        let mut blobs = self.watch_blobs.write().await;
+        let key = (blob.locator, blob.client);
+        match blobs.get(&key) {
+            Some(held) if held.nonce >= blob.nonce => {
+                return Err(L2Error::InvalidParameter(format!(
+                    "Already holding a blob at nonce {}", held.nonce
+                )));
+            }
+            None if blobs.len() >= max_blobs => {
+                return Err(L2Error::ResourceLimitExceeded(format!("Watch blob limit reached ({})", max_blobs)));
+            }
+            _ => {}
+        }
+
+        self.storage.store_watch_blob(&blob)?;
+        debug!("Holding watch blob from {} at nonce {}", blob.client, blob.nonce);
+        blobs.insert(key, blob);
+        Ok(())
+    }
+
+    /// A client reported `channel_id` closing. The report is only a hint:
+    /// the close is looked up on L1, and answered at the nonce published
+    /// there, so a report of a close that never happened does nothing.
+    pub async fn check_reported_close(&self, channel_id: &Hash) -> Result<Vec<String>> {
+        let Some(ref l1_client) = self.l1_client else {
+            return Err(L2Error::TariConnectionError("No L1 client to look the close up on".to_string()));
+        };
+        let now = self.clock.now().as_secs();
+        let dispute = l1_client.get_dispute(&channel_id.to_string()).await
+            .map_err(|e| L2Error::TariConnectionError(format!("Failed to look up close on L1: {}", e)))?
+            .filter(|dispute| dispute.resolution.is_none() && now < dispute.deadline)
+            .ok_or_else(|| L2Error::InvalidParameter(format!("No close of {:?} is open on L1", channel_id)))?;
+        self.respond_to_close(channel_id, dispute.best.nonce).await
+    }
+
+    /// A close of `channel_id` at state `nonce` was seen. Challenge it on L1
+    /// with every held blob that has a newer state, returning the dispute
+    /// transaction IDs.
+    #[instrument(skip_all, fields(channel_id = %channel_id, nonce = nonce))]
+    pub async fn respond_to_close(&self, channel_id: &Hash, nonce: u64) -> Result<Vec<String>> {
+        let locator = WatchBlob::locator(channel_id);
+        let newer: Vec<WatchBlob> = self.watch_blobs.read().await.values()
+            .filter(|blob| blob.locator == locator && blob.nonce > nonce)
+            .cloned()
+            .collect();
+        if newer.is_empty() {
+            return Ok(Vec::new());
+        }
+        let Some(ref l1_client) = self.l1_client else {
+            return Err(L2Error::TariConnectionError("No L1 client to submit challenges".to_string()));
+        };
+
+        let mut disputes = Vec::new();
+        for blob in newer {
+            let payload = match blob.open(channel_id) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    warn!("Watch blob from {} for {:?} is unusable: {}", blob.client, channel_id, e);
+                    continue;
+                }
+            };
+            let now = self.clock.now().as_secs();
+            match l1_client.submit_dispute(channel_id.to_string(), payload.evidence, now).await {
+                Ok(tx_id) => {
+                    warn!("⚠️  Challenged stale close of {:?} at nonce {} with nonce {} for {}, tx: {}",
+                        channel_id, nonce, payload.nonce, blob.client, tx_id);
+                    self.audit_l1(L1Action::Dispute, Some(*channel_id), &tx_id);
+                    disputes.push(tx_id);
+                }
+                Err(e) => warn!("Failed to challenge stale close of {:?} for {}: {}", channel_id, blob.client, e),
+            }
+        }
+        Ok(disputes)
+    }
+
+    /// Scan L1 for channels being closed by dispute and challenge, while the
+    /// window is still open, each close at an older state than a held watch
+    /// blob. Once a close is answered the dispute's best nonce matches the
+    /// blob, so later scans leave it alone. Returns the dispute transaction
+    /// IDs.
+    pub async fn watch_l1_closes(&self) -> Result<Vec<String>> {
+        let Some(ref l1_client) = self.l1_client else {
+            return Ok(Vec::new());
+        };
+        let locators: HashSet<Hash> = self.watch_blobs.read().await.keys()
+            .map(|(locator, _)| *locator)
+            .collect();
+        if locators.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let open = l1_client.open_disputes(self.clock.now().as_secs()).await
+            .map_err(|e| L2Error::TariConnectionError(format!("Failed to scan L1 for disputes: {}", e)))?;
+        let mut disputes = Vec::new();
+        for dispute in open {
+            let Ok(channel_id) = dispute.channel_id.parse::<Hash>() else {
+                continue;
+            };
+            if locators.contains(&WatchBlob::locator(&channel_id)) {
+                match self.respond_to_close(&channel_id, dispute.best.nonce).await {
+                    Ok(submitted) => disputes.extend(submitted),
+                    Err(e) => warn!("Failed to answer close of {:?}: {}", channel_id, e),
+                }
+            }
+        }
+        Ok(disputes)
+    }
+
+    /// Close challenged channels whose L1 dispute window has passed, paying
+    /// out the collateral by the highest-nonce state proven, and return
+    /// their IDs
+    pub async fn resolve_disputes(&self) -> Result<Vec<Hash>> {
+        let Some(ref l1_client) = self.l1_client else {
+            return Ok(Vec::new());
+        };
+        let now = self.clock.now();
+        let mut resolved = Vec::new();
+
+        for handle in self.channel_handles().await {
+            let mut channel = handle.lock().await;
+            if channel.status != ChannelStatus::Challenged {
+                continue;
+            }
+            let channel_key = channel.channel_id.to_string();
+            match l1_client.get_dispute(&channel_key).await {
+                Ok(Some(dispute)) if now.as_secs() >= dispute.deadline => {}
+                Ok(_) => continue,
+                Err(e) => {
+                    warn!("Failed to look up dispute for {:?}: {}", channel.channel_id, e);
+                    continue;
+                }
+            }
+
+            let resolution = match l1_client.resolve_dispute(channel_key, now.as_secs()).await {
+                Ok(resolution) => resolution,
+                Err(e) => {
+                    warn!("Failed to resolve dispute for {:?}: {}", channel.channel_id, e);
+                    continue;
+                }
+            };
+            self.audit_l1(L1Action::ResolveDispute, Some(channel.channel_id), &resolution.tx_id);
+            if let Err(e) = channel.settle_dispute().and_then(|()| self.storage.store_channel(&channel)) {
+                warn!("Failed to record resolution of {:?}: {}", channel.channel_id, e);
+                continue;
+            }
+            self.publish_channel_status(&channel);
+
+            let anchor = channel.state_history.iter().find(|u| u.nonce == resolution.nonce).cloned();
+            let receipt = resolution.state_root.parse::<Hash>().map_err(L2Error::SerializationError)
+                .and_then(|state_root| self.issue_receipt(ReceiptTerms {
+                    kind: ReceiptKind::ChannelClose {
+                        balances: channel.participants.iter()
+                            .map(|p| (*p, resolution.payouts.get(&p.to_string()).copied().unwrap_or(0)))
+                            .collect(),
+                    },
+                    channel_id: channel.channel_id,
+                    parties: channel.participants.clone(),
+                    state_root,
+                    nonce: resolution.nonce,
+                    started_at: channel.close_initiated_at.map(|t| t.as_secs()),
+                    settled_at: now.as_secs(),
+                }, anchor));
+            if let Err(e) = receipt {
+                warn!("Failed to issue receipt for {:?}: {}", channel.channel_id, e);
+            }
+            info!("⚖️  Closed disputed channel {:?} at nonce {}, tx: {}", channel.channel_id, resolution.nonce, resolution.tx_id);
+            resolved.push(channel.channel_id);
+        }
+
+        Ok(resolved)
+    }
+
+    /// Close channels whose challenge period passed without a challenge and
+    /// release their collateral on L1, returning their IDs. A channel that
+    /// cannot be closed is logged and left for the next pass; one whose
+    /// collateral cannot be released is closed and raises an alert.
+    pub async fn finalize_closed_channels(&self) -> Result<Vec<Hash>> {
+        let now = self.clock.now();
+        let mut closed = Vec::new();
+
+        for handle in self.channel_handles().await {
+            let mut channel = handle.lock().await;
+            let due = channel.challenge_deadline().is_some_and(|deadline| now >= deadline);
+            if channel.status != ChannelStatus::Closing || !due {
+                continue;
+            }
+
+            // Work out the payout before closing, so a failed lookup leaves
+            // the channel Closing to be retried on the next pass
+            let final_balances = match self.final_balances(&channel) {
+                Ok(final_balances) => final_balances,
+                Err(e) => {
+                    warn!("Failed to work out the payout of {:?}: {}", channel.channel_id, e);
+                    continue;
+                }
+            };
+            if let Err(e) = channel.finalize_close(now).and_then(|()| self.storage.store_channel(&channel)) {
+                warn!("Failed to close {:?}: {}", channel.channel_id, e);
+                continue;
+            }
+
+            // Unlock collateral on L1 if client available
+            if let Some(ref l1_client) = self.l1_client {
+                match l1_client.unlock_collateral(channel.channel_id.to_string(), final_balances).await {
+                    Ok(tx_id) => {
+                        info!("✅ Unlocked collateral on L1, tx: {}", tx_id);
+                        self.audit_l1(L1Action::UnlockCollateral, Some(channel.channel_id), &tx_id);
+                    }
+                    Err(e) if l1_client.get_locked_collateral(&channel.channel_id.to_string()).await.is_some() => {
+                        warn!("⚠️  Failed to unlock collateral of {:?} on L1: {}", channel.channel_id, e);
+                        self.alerts.raise(AlertKind::CollateralLocked { channel_id: channel.channel_id }, now);
+                    }
+                    Err(e) => debug!("No collateral to unlock for {:?}: {}", channel.channel_id, e),
+                }
+            }
+
+            let anchor = channel.state_history.last().cloned();
+            if let Err(e) = self.issue_receipt(ReceiptTerms {
+                kind: ReceiptKind::ChannelClose {
+                    balances: channel.participants.iter()
+                        .map(|p| (*p, channel.state.get_balance(p).value()))
+                        .collect(),
+                },
+                channel_id: channel.channel_id,
+                parties: channel.participants.clone(),
+                state_root: channel.get_state_root(),
+                nonce: channel.state.nonce,
+                started_at: channel.close_initiated_at.map(|t| t.as_secs()),
+                settled_at: now.as_secs(),
+            }, anchor) {
+                warn!("Failed to issue receipt for {:?}: {}", channel.channel_id, e);
+            }
+            self.publish_channel_status(&channel);
+            info!("Closed channel: {:?}", channel.channel_id);
+            closed.push(channel.channel_id);
+        }
+
+        Ok(closed)
+    }
+
+    /// Sign and store a receipt for a settlement this node took part in,
+    /// anchored to the co-signed update it settled at. A channel that closes
+    /// before any update was applied has nothing the other parties signed
+    /// to anchor to, so it gets no receipt.
+    fn issue_receipt(&self, terms: ReceiptTerms, anchor: Option<SignedStateUpdate>) -> Result<Option<Receipt>> {
+        let Some(anchor) = anchor.filter(|a| a.nonce == terms.nonce) else {
+            debug!("No co-signed update at nonce {} of {:?} to anchor a receipt to", terms.nonce, terms.channel_id);
+            return Ok(None);
+        };
+        let receipt = Receipt::sign(terms, anchor, self.keypair.public_key(), |message| self.keypair.sign(message))
+            .map_err(L2Error::SerializationError)?;
+        self.storage.store_receipt(&receipt)?;
+        debug!("Issued receipt {:?}", receipt.id());
+        Ok(Some(receipt))
+    }
+
+    /// Receipt for a completed order or closed channel, by its ID
+    pub fn get_receipt(&self, id: &Hash) -> Result<Option<Receipt>> {
+        self.storage.load_receipt(id)
+    }
+
+    /// Every receipt this node has issued
+    pub fn list_receipts(&self) -> Result<Vec<Receipt>> {
+        self.storage.load_receipts()
+    }
 
-        info!("Closing channel: {:?}", channel_id);
+    /// Register where settlements owed to `preference.public_key` are paid
+    pub fn set_payout_preference(&self, preference: PayoutPreference) -> Result<()> {
+        self.storage.store_payout_preference(&preference)?;
+        info!("Set payout preference of {} to {:?}", preference.public_key, preference.method);
         Ok(())
     }
 
+    pub fn payout_preference(&self, public_key: &PublicKey) -> Result<Option<PayoutPreference>> {
+        self.storage.load_payout_preference(public_key)
+    }
+
+    /// Key a channel participant's collateral payout by their preferred L1
+    /// address, or by their public key without one
+    fn collateral_payee(&self, participant: &PublicKey) -> Result<String> {
+        Ok(self.storage.load_payout_preference(participant)?
+            .and_then(|p| p.address)
+            .unwrap_or_else(|| format!("{:?}", participant)))
+    }
+
+    /// What the channel's collateral pays each payee on close
+    fn final_balances(&self, channel: &MarketplaceChannel) -> Result<HashMap<String, u64>> {
+        let mut final_balances: HashMap<String, u64> = HashMap::new();
+        for participant in &channel.participants {
+            *final_balances.entry(self.collateral_payee(participant)?).or_insert(0) += channel.state.get_balance(participant).value();
+        }
+        Ok(final_balances)
+    }
+
+    /// Every contact in the address book, ordered by label
+    pub fn list_contacts(&self) -> Result<Vec<Contact>> {
+        self.storage.load_contacts()
+    }
+
+    pub fn get_contact(&self, label: &str) -> Result<Option<Contact>> {
+        let label = contacts::normalize_label(label).map_err(L2Error::InvalidParameter)?;
+        self.storage.load_contact(&label)
+    }
+
+    /// Add a contact under a label not yet in use
+    pub fn add_contact(&self, contact: Contact) -> Result<()> {
+        if self.storage.load_contact(&contact.label)?.is_some() {
+            return Err(L2Error::InvalidParameter(format!("Contact {} already exists", contact.label)));
+        }
+        self.storage.store_contact(&contact)
+    }
+
+    /// Replace the key and Tari address of an existing contact
+    pub fn update_contact(&self, contact: Contact) -> Result<()> {
+        let existing = self.storage.load_contact(&contact.label)?
+            .ok_or_else(|| L2Error::InvalidParameter(format!("Unknown contact: {}", contact.label)))?;
+        self.storage.store_contact(&Contact { added_at: existing.added_at, ..contact })
+    }
+
+    /// Remove a contact. Returns whether it existed.
+    pub fn remove_contact(&self, label: &str) -> Result<bool> {
+        let label = contacts::normalize_label(label).map_err(L2Error::InvalidParameter)?;
+        self.storage.delete_contact(&label)
+    }
+
+    /// Save a search that listings from the network are checked against
+    pub fn save_search(&self, search: SavedSearch) -> Result<()> {
+        if self.storage.load_saved_searches()?.len() >= MAX_SAVED_SEARCHES {
+            return Err(L2Error::InvalidParameter(format!("At most {} saved searches", MAX_SAVED_SEARCHES)));
+        }
+        self.storage.store_saved_search(&search)
+    }
+
+    pub fn list_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        let mut searches = self.storage.load_saved_searches()?;
+        searches.sort_by_key(|search| search.created_at);
+        Ok(searches)
+    }
+
+    pub fn get_saved_search(&self, search_id: &Hash) -> Result<Option<SavedSearch>> {
+        Ok(self.storage.load_saved_searches()?.into_iter().find(|search| search.id == *search_id))
+    }
+
+    /// Delete a saved search and its matches. Returns whether it existed.
+    pub fn delete_saved_search(&self, search_id: &Hash) -> Result<bool> {
+        self.storage.delete_saved_search(search_id)
+    }
+
+    /// Listings that matched a saved search, newest match first, as they
+    /// are now. Matches whose listing has since expired are left out.
+    pub async fn saved_search_results(&self, search_id: &Hash) -> Result<Vec<(SearchMatch, Listing)>> {
+        if self.get_saved_search(search_id)?.is_none() {
+            return Err(L2Error::InvalidParameter(format!("Unknown saved search: {:?}", search_id)));
+        }
+        let mut matches = self.storage.load_search_matches(search_id)?;
+        matches.sort_by(|a, b| b.matched_at.cmp(&a.matched_at));
+
+        let listings = self.global_listings.read().await;
+        Ok(matches.into_iter()
+            .filter_map(|m| {
+                let listing = listings.iter().find(|l| l.id == m.listing_id)?.clone();
+                Some((m, listing))
+            })
+            .collect())
+    }
+
+    /// Record and announce each saved search a listing from the network
+    /// newly matches. Failures are logged; they never refuse the listing.
+    async fn check_saved_searches(&self, listing: &Listing) {
+        let searches = match self.storage.load_saved_searches() {
+            Ok(searches) => searches,
+            Err(e) => {
+                warn!("Could not load saved searches: {}", e);
+                return;
+            }
+        };
+        for search in searches.iter().filter(|search| search.query.matches(listing)) {
+            let search_match = SearchMatch { search_id: search.id, listing_id: listing.id, matched_at: self.clock.now() };
+            match self.storage.record_search_match(&search_match) {
+                Ok(true) => {
+                    info!("🔔 Listing {:?} matches saved search {:?}", listing.id, search.id);
+                    self.events.publish(MarketplaceEvent::SavedSearchMatch { search_id: search.id, listing: listing.clone() });
+                }
+                Ok(false) => {}
+                Err(e) => warn!("Could not record match of saved search {:?}: {}", search.id, e),
+            }
+        }
+    }
+
+    /// A counterparty given as a hex key, an address or a contact label
+    pub fn resolve_counterparty(&self, name: &str) -> Result<PublicKey> {
+        if let Ok(key) = name.parse() {
+            return Ok(key);
+        }
+        let contact = match contacts::normalize_label(name) {
+            Ok(label) => self.storage.load_contact(&label)?,
+            Err(_) => None,
+        };
+        contact
+            .map(|contact| contact.public_key)
+            .ok_or_else(|| L2Error::InvalidParameter(format!("Not a public key, address or contact: {}", name)))
+    }
+
+    /// An L1 payout address given as a Tari address or as the label of a
+    /// contact with one
+    pub fn resolve_payout_address(&self, name: &str) -> Result<String> {
+        let Ok(label) = contacts::normalize_label(name) else {
+            return Ok(name.to_string());
+        };
+        match self.storage.load_contact(&label)? {
+            Some(contact) => contact.tari_address
+                .ok_or_else(|| L2Error::InvalidParameter(format!("Contact {} has no Tari address", label))),
+            None => Ok(name.to_string()),
+        }
+    }
+
+    /// Issue and store a signed invoice for `amount` µT, payable to this
+    /// node for `expires_in` seconds
+    pub fn create_invoice(&self, amount: u64, memo: String, listing_id: Option<Hash>, expires_in: u64) -> Result<Invoice> {
+        let now = self.clock.now().as_secs();
+        let terms = InvoiceTerms {
+            id: Hash::random(),
+            seller: self.keypair.public_key(),
+            amount,
+            memo,
+            listing_id,
+            created_at: now,
+            expires_at: now.saturating_add(expires_in),
+        };
+        let invoice = Invoice::sign(terms, |message| self.keypair.sign(message))
+            .map_err(L2Error::InvalidParameter)?;
+        self.storage.store_invoice(&invoice)?;
+        debug!("Issued invoice {:?} for {} µT", invoice.id(), amount);
+        Ok(invoice)
+    }
+
+    /// Every invoice this node issued, oldest first
+    pub fn list_invoices(&self) -> Result<Vec<Invoice>> {
+        let mut invoices = self.storage.load_invoices()?;
+        invoices.sort_by_key(|invoice| invoice.terms.created_at);
+        Ok(invoices)
+    }
+
+    /// Pay another node's invoice from this node's balance. Of the active
+    /// channels shared with the seller that can cover the amount, the one
+    /// holding most of our balance is used. The payment is proposed like
+    /// any transfer and names the invoice. An invoice is paid at most once:
+    /// a claim on it is taken atomically before proposing, and it counts
+    /// as paid once the payment commits.
+    pub async fn pay_invoice(&self, invoice: &Invoice) -> Result<InvoicePayment> {
+        if !invoice.verify() {
+            return Err(L2Error::InvalidSignature);
+        }
+        let now = self.clock.now().as_secs();
+        if invoice.is_expired(now) {
+            return Err(L2Error::InvalidParameter(format!("Invoice {:?} has expired", invoice.id())));
+        }
+        if let Some(payment) = self.storage.load_invoice_payment(&invoice.id())? {
+            return Err(L2Error::InvalidParameter(format!(
+                "Invoice {:?} was already paid in channel {:?} at nonce {}",
+                invoice.id(), payment.channel_id, payment.nonce
+            )));
+        }
+        let local = self.keypair.public_key();
+        let seller = invoice.terms.seller;
+        if seller == local {
+            return Err(L2Error::InvalidParameter("Cannot pay an invoice this node issued".to_string()));
+        }
+
+        let channel_id = self.payment_channel(&seller, invoice.terms.amount).await?;
+        let claim = InvoicePayment { invoice: invoice.clone(), channel_id, nonce: 0, paid_at: now };
+        let previous = self.storage.load_invoice_claim(&invoice.id())?;
+        if let Some(previous) = &previous {
+            if self.invoice_claim_is_live(previous).await {
+                return Err(L2Error::InvalidParameter(format!(
+                    "Invoice {:?} is already being paid in channel {:?}", invoice.id(), previous.channel_id
+                )));
+            }
+        }
+        if !self.storage.swap_invoice_claim(&invoice.id(), previous.as_ref(), Some(&claim))? {
+            return Err(L2Error::InvalidParameter(format!("Invoice {:?} is already being paid", invoice.id())));
+        }
+
+        let update = StateUpdate::PayInvoice {
+            invoice_id: invoice.id(),
+            from: local,
+            to: seller,
+            amount: Amount::new(invoice.terms.amount),
+        };
+        let nonce = match self.propose_state_update(&channel_id, update).await {
+            Ok(signed) => signed.nonce,
+            Err(e) => {
+                self.storage.swap_invoice_claim(&invoice.id(), Some(&claim), None)?;
+                return Err(e);
+            }
+        };
+
+        // The payment may already have committed and released the claim
+        let proposed = InvoicePayment { nonce, ..claim.clone() };
+        self.storage.swap_invoice_claim(&invoice.id(), Some(&claim), Some(&proposed))?;
+        info!("Proposed payment of invoice {:?} for {} µT in channel {:?}", invoice.id(), invoice.terms.amount, channel_id);
+        Ok(proposed)
+    }
+
+    /// Whether a claim on paying an invoice may still commit: it is being
+    /// proposed, or its payment is still pending. A claim whose proposal
+    /// expired or was superseded no longer blocks paying again.
+    async fn invoice_claim_is_live(&self, claim: &InvoicePayment) -> bool {
+        if claim.nonce == 0 {
+            return true;
+        }
+        self.list_pending_updates(&claim.channel_id).await.iter().any(|update| {
+            update.nonce == claim.nonce
+                && matches!(&update.update, StateUpdate::PayInvoice { invoice_id, .. } if *invoice_id == claim.invoice.id())
+        })
+    }
+
+    /// Record a committed invoice payment: as paid if this node paid it,
+    /// or against the invoice this node issued if it was paid to us
+    fn record_invoice_payment(&self, channel_id: &Hash, nonce: u64, update: &StateUpdate, now: u64) -> Result<()> {
+        let StateUpdate::PayInvoice { invoice_id, from, to, amount } = update else {
+            return Ok(());
+        };
+        let local = self.keypair.public_key();
+        let invoice = if *from == local {
+            let claim = self.storage.load_invoice_claim(invoice_id)?;
+            if let Some(claim) = &claim {
+                self.storage.swap_invoice_claim(invoice_id, Some(claim), None)?;
+            }
+            claim.map(|claim| claim.invoice)
+        } else if *to == local {
+            self.storage.load_invoice(invoice_id)?
+                .filter(|invoice| amount.value() >= invoice.terms.amount)
+        } else {
+            None
+        };
+        let Some(invoice) = invoice else {
+            warn!("Payment at nonce {} in channel {:?} names invoice {:?}, which does not match one of ours", nonce, channel_id, invoice_id);
+            return Ok(());
+        };
+        info!("Invoice {:?} paid in channel {:?} at nonce {}", invoice_id, channel_id, nonce);
+        self.storage.store_invoice_payment(&InvoicePayment { invoice, channel_id: *channel_id, nonce, paid_at: now })
+    }
+
+    /// The committed payment of an invoice, made by this node or to it
+    pub fn invoice_payment(&self, invoice_id: &Hash) -> Result<Option<InvoicePayment>> {
+        self.storage.load_invoice_payment(invoice_id)
+    }
+
+    /// The active channel shared with `payee` holding most of this node's
+    /// balance, if that covers `amount`
+    async fn payment_channel(&self, payee: &PublicKey, amount: u64) -> Result<Hash> {
+        let local = self.keypair.public_key();
+        let usable = |channel: &MarketplaceChannel| {
+            let balance = channel.state.get_balance(&local).value();
+            (channel.status == ChannelStatus::Active && channel.participants.contains(payee))
+                .then_some((balance, channel.channel_id))
+        };
+
+        let mut candidates = Vec::new();
+        for handle in self.channel_handles().await {
+            candidates.extend(usable(&*handle.lock().await));
+        }
+        candidates.extend(self.hibernated_channels().await.iter().filter_map(usable));
+
+        let (balance, channel_id) = candidates.into_iter()
+            .max_by_key(|(balance, _)| *balance)
+            .ok_or_else(|| L2Error::InvalidParameter(format!("No active channel with {}", payee)))?;
+        if balance < amount {
+            return Err(L2Error::InsufficientBalance { required: amount, available: balance });
+        }
+        Ok(channel_id)
+    }
+
+    /// Anchor the state root of every active channel that is due under the
+    /// checkpoint policy on L1. A channel whose checkpoint fails is skipped
+    /// until its retry backoff has passed. Returns the number of checkpoints
+    /// submitted.
+    pub async fn checkpoint_channels(&self) -> Result<usize> {
+        let l1_client = match &self.l1_client {
+            Some(client) => client,
+            None => return Ok(0),
+        };
+        let now = self.clock.now();
+        let policy = self.checkpoint_policy.read().await.clone();
+
+        // Channels whose latest update carries an aggregate signature are
+        // anchored with that single signature
+        let mut roots: Vec<(Hash, Hash, Vec<String>)> = Vec::new();
+        {
+            let retries = self.checkpoint_retries.read().await;
+            for handle in self.channel_handles().await {
+                let c = handle.lock().await;
+                if c.status != ChannelStatus::Active
+                    || retries.get(&c.channel_id).is_some_and(|retry| !retry.is_due(now))
+                    || !policy.is_due(&c.metrics, c.state.nonce, now)
+                {
+                    continue;
+                }
+                let signatures = c.state_history.last()
+                    .and_then(|u| u.aggregate.as_ref())
+                    .map(|a| vec![hex::encode(a.signature.as_bytes())])
+                    .unwrap_or_default();
+                roots.push((c.channel_id, c.get_state_root(), signatures));
+            }
+        }
+
+        if roots.is_empty() {
+            return Ok(0);
+        }
+
+        let block_height = l1_client.get_chain_height().await
+            .map_err(|e| L2Error::TariConnectionError(e.to_string()))?;
+
+        let mut submitted = 0;
+        for (channel_id, state_root, signatures) in &roots {
+            let tx_id = match l1_client.checkpoint_state(
+                channel_id.to_string(), state_root.to_string(), signatures.clone(), block_height,
+            ).await {
+                Ok(tx_id) => tx_id,
+                Err(e) => {
+                    let mut retries = self.checkpoint_retries.write().await;
+                    let retry = CheckpointRetry::record_failure(retries.remove(channel_id), now);
+                    warn!("Failed to checkpoint {:?} (attempt {}), retrying at {}: {}",
+                        channel_id, retry.attempts, retry.next_attempt.as_secs(), e);
+                    retries.insert(*channel_id, retry);
+                    continue;
+                }
+            };
+            self.audit_l1(L1Action::Checkpoint, Some(*channel_id), &tx_id);
+            self.checkpoint_retries.write().await.remove(channel_id);
+            submitted += 1;
+
+            // Only count the checkpoint if no update landed while it was in flight
+            let handle = self.channels.read().await.get(channel_id).cloned();
+            if let Some(handle) = handle {
+                let mut channel = handle.lock().await;
+                if channel.get_state_root() == *state_root {
+                    channel.mark_checkpointed(now, block_height);
+                    self.storage.store_channel(&channel)?;
+                }
+            }
+        }
+
+        info!("📌 Checkpointed {} of {} due channels at block {}", submitted, roots.len(), block_height);
+        Ok(submitted)
+    }
+
+    /// Whether `state_root` of `channel_id` is anchored in a checkpoint on
+    /// L1, found on the chain whichever participant submitted it
+    pub async fn verify_checkpoint(&self, channel_id: &Hash, state_root: &Hash) -> Result<bool> {
+        let Some(ref l1_client) = self.l1_client else {
+            return Err(L2Error::TariConnectionError("No L1 client to verify checkpoints".to_string()));
+        };
+        l1_client.verify_checkpoint(&channel_id.to_string(), &state_root.to_string())
+            .await
+            .map_err(|e| L2Error::TariConnectionError(e.to_string()))
+    }
+
     /// Get L1 connection status
     pub fn get_l1_status(&self) -> Option<String> {
         self.l1_client.as_ref().map(|client| {
@@ -326,298 +2766,4081 @@ impl MarketplaceManager {
         let listing = Listing {
             id,
             seller,
-            title: title.clone(),
+            title,
             description,
             price: Amount::new(price),
             ipfs_hash,
             active: true,
             category,
+            fiat_reference: None,
         };
+        self.publish_listing(listing).await
+    }
+
+    /// Sign, store and broadcast a new global listing
+    pub async fn publish_listing(&self, listing: Listing) -> Result<()> {
+        if listing.fiat_reference.as_ref().is_some_and(|f| !f.is_valid()) {
+            return Err(L2Error::InvalidParameter("Fiat currency must be an ISO 4217 code".to_string()));
+        }
+        let (title, price) = (listing.title.clone(), listing.price);
+
+        let signature = self.sign_listing(&listing)?;
+
+        // Persist to database first
+        self.storage.store_listing(&listing)?;
+        self.storage.store_listing_signature(&listing.id, &signature)?;
+
+        // Add to in-memory cache
+        self.global_listings.write().await.push(listing.clone());
+        self.listing_timestamps.write().await.insert(listing.id, self.clock.now());
+        self.seller_catalogs.write().await.remove(&listing.seller);
+        self.events.publish(MarketplaceEvent::Listing { listing: listing.clone() });
+
+        // Broadcast to P2P network
+        if let Some(network) = self.network.read().await.as_ref() {
+            let timestamp = self.clock.now().as_secs();
+
+            let message = tari_l2_p2p::L2Message::ListingBroadcast {
+                listing,
+                signature,
+                timestamp,
+            };
+
+            network.broadcast_message(message).await
+                .map_err(|e| L2Error::Unknown(format!("Failed to broadcast listing: {}", e)))?;
+
+            info!("✅ Created and broadcast global listing: {} - {}", title, price);
+        } else {
+            info!("⚠️  Created global listing (no P2P network): {} - {}", title, price);
+        }
+
+        Ok(())
+    }
+
+    /// Change the price of a listing created on this node and broadcast the
+    /// re-signed listing. Orders already placed keep the amount they were
+    /// created with; only new orders use the new price.
+    pub async fn update_listing_price(&self, listing_id: &Hash, price: u64) -> Result<Listing> {
+        let updated = self.update_own_listing(listing_id, |listing| {
+            if !listing.active {
+                return Err(L2Error::InvalidParameter(format!("Listing is not active: {:?}", listing_id)));
+            }
+            listing.price = Amount::new(price);
+            Ok(())
+        }).await?;
+
+        info!("Updated price of listing {:?} to {}", listing_id, price);
+        Ok(updated)
+    }
+
+    /// Save a listing locally without signing or broadcasting it. Drafts are
+    /// published later with [`Self::publish_draft`].
+    pub async fn create_draft(&self, mut listing: Listing) -> Result<Listing> {
+        if listing.fiat_reference.as_ref().is_some_and(|f| !f.is_valid()) {
+            return Err(L2Error::InvalidParameter("Fiat currency must be an ISO 4217 code".to_string()));
+        }
+        listing.active = false;
+        self.storage.store_draft(&listing)?;
+        info!("Saved draft listing {:?}: {}", listing.id, listing.title);
+        Ok(listing)
+    }
+
+    /// Draft listings waiting to be published
+    pub fn list_drafts(&self) -> Result<Vec<Listing>> {
+        self.storage.load_drafts()
+    }
+
+    /// Sign and broadcast a draft. A draft that was unpublished earlier is
+    /// re-activated on peers with a `ListingUpdated`; a new one is announced
+    /// like any other listing.
+    pub async fn publish_draft(&self, listing_id: &Hash) -> Result<Listing> {
+        let mut draft = self.storage.load_draft(listing_id)?
+            .ok_or_else(|| L2Error::InvalidParameter(format!("Draft not found: {:?}", listing_id)))?;
+        draft.active = true;
+
+        let known = self.global_listings.read().await.iter().any(|l| l.id == *listing_id);
+        let listing = if known {
+            self.update_own_listing(listing_id, |listing| {
+                *listing = draft;
+                Ok(())
+            }).await?
+        } else {
+            self.publish_listing(draft.clone()).await?;
+            draft
+        };
+
+        self.storage.delete_draft(listing_id)?;
+        Ok(listing)
+    }
+
+    /// Withdraw a listing from the network and keep it as a draft. Peers
+    /// receive the listing marked inactive so it drops out of their catalogs.
+    pub async fn unpublish_listing(&self, listing_id: &Hash) -> Result<Listing> {
+        let listing = self.update_own_listing(listing_id, |listing| {
+            if !listing.active {
+                return Err(L2Error::InvalidParameter(format!("Listing is not active: {:?}", listing_id)));
+            }
+            listing.active = false;
+            Ok(())
+        }).await?;
+
+        self.storage.store_draft(&listing)?;
+        info!("Unpublished listing {:?}", listing_id);
+        Ok(listing)
+    }
+
+    /// Apply `change` to a listing created on this node, then re-sign, store
+    /// and broadcast it as a `ListingUpdated`
+    async fn update_own_listing<F>(&self, listing_id: &Hash, change: F) -> Result<Listing>
+    where
+        F: FnOnce(&mut Listing) -> Result<()>,
+    {
+        let own_key = self.keypair.public_key();
+        let mut listings = self.global_listings.write().await;
+        let listing = listings.iter_mut()
+            .find(|l| l.id == *listing_id)
+            .ok_or_else(|| L2Error::InvalidParameter(format!("Listing not found: {:?}", listing_id)))?;
+
+        // Only listings we signed can be re-signed by us
+        let created_here = listing.seller == own_key || match self.storage.load_listing_signature(listing_id)? {
+            Some(signature) => own_key.verify(&listing_signing_message(listing)?, &signature),
+            None => false,
+        };
+        if !created_here {
+            return Err(L2Error::InvalidParameter("Listing was not created on this node".to_string()));
+        }
+
+        let mut updated = listing.clone();
+        change(&mut updated)?;
+        updated.id = *listing_id;
+        updated.seller = listing.seller;
+
+        // Peers only apply updates newer than the last, so two updates in
+        // the same second still get distinct timestamps
+        let last = self.storage.load_listing_version(listing_id)?;
+        let timestamp = self.clock.now().as_secs().max(last.map_or(0, |t| t + 1));
+        let signature = self.sign_listing(&updated)?;
+        let update_signature = self.keypair.sign(&listing_update_message(&updated, timestamp)?);
+        self.storage.store_listing(&updated)?;
+        self.storage.store_listing_signature(listing_id, &signature)?;
+        self.storage.store_listing_version(listing_id, timestamp)?;
+        *listing = updated.clone();
+        drop(listings);
+
+        self.seller_catalogs.write().await.remove(&updated.seller);
+        self.events.publish(MarketplaceEvent::Listing { listing: updated.clone() });
+
+        if let Some(network) = self.network.read().await.as_ref() {
+            let message = tari_l2_p2p::L2Message::ListingUpdated {
+                listing: updated.clone(),
+                signature,
+                timestamp,
+                update_signature,
+            };
+            network.broadcast_message(message).await
+                .map_err(|e| L2Error::Unknown(format!("Failed to broadcast listing update: {}", e)))?;
+        }
+
+        Ok(updated)
+    }
+
+    /// Apply a listing update from the P2P network. The seller signs the
+    /// update's timestamp, and updates not newer than the last one applied
+    /// are refused, so an old update cannot be replayed to roll the listing
+    /// back. Unknown listings are imported.
+    pub async fn handle_listing_update(
+        &self,
+        listing: Listing,
+        signature: tari_l2_common::Signature,
+        timestamp: u64,
+        update_signature: tari_l2_common::Signature,
+    ) -> Result<()> {
+        LISTING_BROADCAST_WINDOW.check(self.clock.now(), Timestamp::from_secs(timestamp))?;
+
+        let message = listing_signing_message(&listing)?;
+        if !listing.seller.verify(&message, &signature)
+            || !listing.seller.verify(&listing_update_message(&listing, timestamp)?, &update_signature)
+        {
+            return Err(L2Error::InvalidSignature);
+        }
+
+        let mut listings = self.global_listings.write().await;
+        if let Some(last) = self.storage.load_listing_version(&listing.id)?.filter(|last| *last >= timestamp) {
+            return Err(L2Error::InvalidParameter(format!(
+                "Update of listing {:?} at {} is not newer than {}", listing.id, timestamp, last
+            )));
+        }
+
+        let Some(existing) = listings.iter_mut().find(|l| l.id == listing.id) else {
+            drop(listings);
+            self.storage.store_listing_version(&listing.id, timestamp)?;
+            return self.import_listing(listing, signature).await;
+        };
+        if existing.seller != listing.seller {
+            return Err(L2Error::InvalidSignature);
+        }
+
+        self.storage.store_listing(&listing)?;
+        self.storage.store_listing_signature(&listing.id, &signature)?;
+        self.storage.store_listing_version(&listing.id, timestamp)?;
+        *existing = listing.clone();
+        drop(listings);
+
+        self.seller_catalogs.write().await.remove(&listing.seller);
+        self.events.publish(MarketplaceEvent::Listing { listing: listing.clone() });
+        self.check_saved_searches(&listing).await;
+        info!("📦 Listing {:?} updated by seller: price {}", listing.id, listing.price);
+        Ok(())
+    }
+
+    /// Handle incoming listing from P2P network
+    pub async fn handle_received_listing(&self, listing: Listing, signature: tari_l2_common::Signature, timestamp: u64) -> Result<()> {
+        LISTING_BROADCAST_WINDOW.check(self.clock.now(), Timestamp::from_secs(timestamp))?;
+
+        // Verify signature
+        let message = listing_signing_message(&listing)?;
+        if !listing.seller.verify(&message, &signature) {
+            return Err(L2Error::InvalidSignature);
+        }
+
+        self.import_listing(listing, signature).await
+    }
+
+    /// Verify and store listings from a peer's catalog response, returning how many were new.
+    /// Signatures are batch verified; if the batch fails each one is checked to drop the bad ones.
+    /// Different versions of listings we already hold are taken only when
+    /// they bring the seller's catalog in line with its announced root.
+    pub async fn import_signed_listings(&self, signed: Vec<(Listing, tari_l2_common::Signature)>) -> Result<usize> {
+        let known: HashMap<Hash, Listing> = self.global_listings.read().await.iter()
+            .map(|l| (l.id, l.clone()))
+            .collect();
+        let (new, other): (Vec<_>, Vec<_>) = signed.into_iter()
+            .partition(|(listing, _)| !known.contains_key(&listing.id));
+        let changed: Vec<_> = other.into_iter()
+            .filter(|(listing, _)| known.get(&listing.id) != Some(listing))
+            .collect();
+        if !changed.is_empty() {
+            self.repair_from_catalog_roots(changed).await?;
+        }
+        if new.is_empty() {
+            return Ok(0);
+        }
+
+        let messages = new.iter()
+            .map(|(listing, _)| listing_signing_message(listing))
+            .collect::<Result<Vec<_>>>()?;
+        let sellers: Vec<PublicKey> = new.iter().map(|(listing, _)| listing.seller).collect();
+        let signatures: Vec<_> = new.iter().map(|(_, signature)| signature.clone()).collect();
+        let message_refs: Vec<&[u8]> = messages.iter().map(|m| m.as_slice()).collect();
+
+        let all_valid = crypto::verify_signatures_batch(&sellers, &message_refs, &signatures);
+
+        let mut imported = 0;
+        for ((listing, signature), message) in new.into_iter().zip(&messages) {
+            if !all_valid && !listing.seller.verify(message, &signature) {
+                warn!("Dropping listing {:?} with invalid seller signature", listing.id);
+                continue;
+            }
+            self.import_listing(listing, signature).await?;
+            imported += 1;
+        }
+
+        Ok(imported)
+    }
+
+    /// Replace held listings with the offered versions, seller by seller,
+    /// where that makes the seller's catalog match the root they announced
+    async fn repair_from_catalog_roots(&self, offered: Vec<(Listing, tari_l2_common::Signature)>) -> Result<()> {
+        let mut by_seller: HashMap<PublicKey, Vec<(Listing, tari_l2_common::Signature)>> = HashMap::new();
+        for (listing, signature) in offered {
+            if listing.seller.verify(&listing_signing_message(&listing)?, &signature) {
+                by_seller.entry(listing.seller).or_default().push((listing, signature));
+            }
+        }
+
+        let roots = self.catalog_roots.read().await.clone();
+        for (seller, versions) in by_seller {
+            let Some(root) = roots.get(&seller) else {
+                continue;
+            };
+            let mut listings = self.global_listings.write().await;
+            let mut candidate = listings.clone();
+            for (version, _) in &versions {
+                if let Some(held) = candidate.iter_mut().find(|l| l.id == version.id) {
+                    *held = version.clone();
+                }
+            }
+            if CatalogIntegrity::check(Some(root), &candidate) != CatalogIntegrity::Verified {
+                continue;
+            }
+
+            for (version, signature) in &versions {
+                self.storage.store_listing(version)?;
+                self.storage.store_listing_signature(&version.id, signature)?;
+            }
+            *listings = candidate;
+            drop(listings);
+            self.seller_catalogs.write().await.remove(&seller);
+            info!("Updated {} outdated listings of {} to match their catalog root", versions.len(), seller);
+        }
+        Ok(())
+    }
+
+    /// Active global listings with the seller signatures peers need to verify them.
+    /// Our own listings are re-signed if no signature was stored; foreign listings without one are skipped.
+    pub async fn signed_listings(&self) -> Result<Vec<(Listing, tari_l2_common::Signature)>> {
+        let own_key = self.keypair.public_key();
+        let listings = self.global_listings.read().await.clone();
+
+        let mut signed = Vec::with_capacity(listings.len());
+        for listing in listings.into_iter().filter(|l| l.active) {
+            let signature = match self.storage.load_listing_signature(&listing.id)? {
+                Some(signature) => signature,
+                None if listing.seller == own_key => self.sign_listing(&listing)?,
+                None => continue,
+            };
+            signed.push((listing, signature));
+        }
+
+        Ok(signed)
+    }
+
+    /// Sign and broadcast the catalog root over our active listings, so peers
+    /// can check the copies they synced. Nothing is sent without listings.
+    pub async fn announce_catalog_root(&self) -> Result<Option<CatalogRoot>> {
+        let seller = self.keypair.public_key();
+        let listings = self.global_listings.read().await.clone();
+        if !listings.iter().any(|l| l.seller == seller && l.active) {
+            return Ok(None);
+        }
+
+        let root = CatalogRoot::new(seller, &listings, self.clock.now(), |message| self.keypair.sign(message))
+            .map_err(L2Error::SerializationError)?;
+        self.catalog_roots.write().await.insert(seller, root.clone());
+
+        if let Some(network) = self.network.read().await.as_ref() {
+            let message = tari_l2_p2p::L2Message::CatalogRoot {
+                seller,
+                root: root.root,
+                listings: root.listings,
+                timestamp: root.timestamp,
+                signature: root.signature.clone(),
+            };
+            network.broadcast_message(message).await
+                .map_err(|e| L2Error::Unknown(format!("Failed to broadcast catalog root: {}", e)))?;
+        }
+        Ok(Some(root))
+    }
+
+    /// Record a seller's catalog root from the network and check our copy
+    /// of their catalog against it. If listings are missing or outdated,
+    /// they are requested from the seller directly rather than from
+    /// whichever peer relayed them.
+    pub async fn handle_catalog_root(&self, root: CatalogRoot) -> Result<CatalogIntegrity> {
+        LISTING_BROADCAST_WINDOW.check(self.clock.now(), Timestamp::from_secs(root.timestamp))?;
+        if !root.verify() {
+            return Err(L2Error::InvalidSignature);
+        }
+
+        let seller = root.seller;
+        {
+            let mut roots = self.catalog_roots.write().await;
+            if roots.get(&seller).is_some_and(|held| held.timestamp >= root.timestamp) {
+                return Ok(CatalogIntegrity::check(roots.get(&seller), &self.global_listings.read().await));
+            }
+            roots.insert(seller, root.clone());
+        }
+        self.seller_catalogs.write().await.remove(&seller);
+
+        let integrity = CatalogIntegrity::check(Some(&root), &self.global_listings.read().await);
+        if let CatalogIntegrity::Diverged { announced, held } = integrity {
+            warn!("Catalog of {} diverges from its root: {} listings announced, {} held", seller, announced, held);
+            if let Some(network) = self.network.read().await.as_ref() {
+                if let Err(e) = network.send_message(seller, tari_l2_p2p::L2Message::ListingsRequest).await {
+                    debug!("Could not request listings from {}: {}", seller, e);
+                }
+            }
+        }
+        Ok(integrity)
+    }
+
+    /// Remember the archive peers to bootstrap the catalog from. The download
+    /// only runs if the node starts without any listings.
+    pub async fn configure_archive_peers(&self, archive_peers: Vec<PublicKey>, page_size: u32) {
+        let pending = !archive_peers.is_empty() && self.global_listings.read().await.is_empty();
+        *self.catalog_bootstrap.write().await = CatalogBootstrap {
+            archive_peers: archive_peers.into_iter().collect(),
+            page_size: page_size.clamp(1, MAX_CATALOG_PAGE),
+            requested: HashSet::new(),
+            pending,
+        };
+    }
+
+    /// Ask each connected archive peer, once, for the first page of its
+    /// catalog. Returns how many requests were sent.
+    pub async fn bootstrap_catalog(&self) -> Result<usize> {
+        let network = match self.network.read().await.clone() {
+            Some(network) => network,
+            None => return Ok(0),
+        };
+        let connected: HashSet<PublicKey> = network.connected_peers().await.into_iter().collect();
+
+        let (targets, limit) = {
+            let mut bootstrap = self.catalog_bootstrap.write().await;
+            if !bootstrap.pending {
+                return Ok(0);
+            }
+            let targets: Vec<PublicKey> = bootstrap.archive_peers.iter()
+                .filter(|peer| connected.contains(peer) && !bootstrap.requested.contains(peer))
+                .copied()
+                .collect();
+            bootstrap.requested.extend(targets.iter().copied());
+            (targets, bootstrap.page_size)
+        };
+
+        for peer in &targets {
+            info!("📚 Requesting catalog from archive peer {:?}", peer);
+            network.send_message(*peer, tari_l2_p2p::L2Message::CatalogPageRequest { offset: 0, limit }).await?;
+        }
+        Ok(targets.len())
+    }
+
+    /// One page of our catalog for a bootstrapping peer: up to `limit` users,
+    /// ordered by public key, with their signed listings, profiles and reviews
+    pub async fn catalog_page(&self, offset: u32, limit: u32) -> Result<CatalogPage> {
+        let limit = limit.clamp(1, MAX_CATALOG_PAGE) as usize;
+        let listings = self.signed_listings().await?;
+        let profiles = self.profiles.read().await;
+        let reviews = self.reviews.read().await;
+
+        let mut users: Vec<PublicKey> = listings.iter()
+            .map(|(listing, _)| listing.seller)
+            .chain(profiles.keys().copied())
+            .chain(reviews.keys().copied())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        users.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+
+        let start = (offset as usize).min(users.len());
+        let end = start.saturating_add(limit).min(users.len());
+        let page = &users[start..end];
+
+        Ok(CatalogPage {
+            listings: listings.into_iter().filter(|(listing, _)| page.contains(&listing.seller)).collect(),
+            profiles: page.iter().filter_map(|user| profiles.get(user).cloned()).collect(),
+            reviews: page.iter().flat_map(|user| reviews.get(user).cloned().unwrap_or_default()).collect(),
+            next_offset: (end < users.len()).then_some(end as u32),
+        })
+    }
+
+    /// Import a catalog page from an archive peer, returning the request for
+    /// the next page. Profiles and reviews are unsigned, so pages are only
+    /// accepted from configured archive peers; listings are still verified.
+    pub async fn handle_catalog_page(&self, from: &PublicKey, page: CatalogPage) -> Result<Option<tari_l2_p2p::L2Message>> {
+        let limit = {
+            let bootstrap = self.catalog_bootstrap.read().await;
+            if !bootstrap.archive_peers.contains(from) {
+                return Err(L2Error::InvalidParameter("Catalog page from a peer that is not an archive peer".to_string()));
+            }
+            bootstrap.page_size
+        };
+
+        let imported = self.import_signed_listings(page.listings).await?;
+        {
+            let mut profiles = self.profiles.write().await;
+            for profile in page.profiles {
+                profiles.entry(profile.public_key).or_insert(profile);
+            }
+        }
+        {
+            let mut reviews = self.reviews.write().await;
+            for review in page.reviews {
+                let seller_reviews = reviews.entry(review.seller).or_default();
+                if !seller_reviews.iter().any(|r| r.order_id == review.order_id) {
+                    seller_reviews.push(review);
+                }
+            }
+        }
+        self.seller_catalogs.write().await.clear();
+        debug!("Imported {} listings from archive peer {:?}", imported, from);
+
+        match page.next_offset {
+            Some(offset) => Ok(Some(tari_l2_p2p::L2Message::CatalogPageRequest { offset, limit })),
+            None => {
+                self.catalog_bootstrap.write().await.pending = false;
+                info!("📚 Catalog bootstrap from {:?} complete", from);
+                Ok(None)
+            }
+        }
+    }
+
+    fn sign_listing(&self, listing: &Listing) -> Result<tari_l2_common::Signature> {
+        let listing_bytes = canonical::encode(listing).map_err(L2Error::SerializationError)?;
+        Ok(self.keypair.sign_with_context(SigningContext::Listing, &listing_bytes))
+    }
+
+    /// Store a listing whose seller signature has already been verified
+    async fn import_listing(&self, listing: Listing, signature: tari_l2_common::Signature) -> Result<()> {
+        // Check if we already have this listing
+        let listings = self.global_listings.read().await;
+        if listings.iter().any(|l| l.id == listing.id) {
+            return Ok(()); // Already have it
+        }
+        drop(listings);
+        self.ensure_disk_space("new listings").await?;
+
+        // Persist to database
+        self.storage.store_listing(&listing)?;
+        self.storage.store_listing_signature(&listing.id, &signature)?;
+
+        // Add to in-memory cache
+        self.global_listings.write().await.push(listing.clone());
+        self.listing_timestamps.write().await.insert(listing.id, self.clock.now());
+        self.seller_catalogs.write().await.remove(&listing.seller);
+        self.events.publish(MarketplaceEvent::Listing { listing: listing.clone() });
+        self.check_saved_searches(&listing).await;
+
+        info!("📦 Received and stored listing from network: {} (ID: {:?})", listing.title, listing.id);
+
+        Ok(())
+    }
+
+    /// Load all listings from storage
+    pub async fn load_listings(&self) -> Result<()> {
+        let listings = self.storage.load_all_listings()?;
+
+        // Loaded listings start their expiry clock at load time
+        let now = self.clock.now();
+        let mut timestamps = self.listing_timestamps.write().await;
+        for listing in &listings {
+            timestamps.entry(listing.id).or_insert(now);
+        }
+        drop(timestamps);
+
+        let mut global_listings = self.global_listings.write().await;
+        *global_listings = listings;
+        self.seller_catalogs.write().await.clear();
+        info!("✅ Loaded {} listings from storage", global_listings.len());
+        Ok(())
+    }
+
+    /// Deactivate global listings older than `ttl_secs`, dropping their saved
+    /// search matches, and return their IDs
+    pub async fn expire_listings(&self, ttl_secs: u64) -> Result<Vec<Hash>> {
+        let now = self.clock.now().as_secs();
+        let timestamps = self.listing_timestamps.read().await;
+        let mut listings = self.global_listings.write().await;
+        let mut expired = Vec::new();
+
+        for listing in listings.iter_mut().filter(|l| l.active) {
+            let created = timestamps.get(&listing.id).map(|t| t.as_secs()).unwrap_or(now);
+            if now.saturating_sub(created) > ttl_secs {
+                listing.active = false;
+                self.storage.store_listing(listing)?;
+                self.seller_catalogs.write().await.remove(&listing.seller);
+                expired.push(listing.id);
+                info!("Expired listing: {} ({:?})", listing.title, listing.id);
+            }
+        }
+        if !expired.is_empty() {
+            self.storage.delete_search_matches(&expired)?;
+        }
+
+        Ok(expired)
+    }
+
+    /// List all global marketplace listings
+    pub async fn list_all_listings(&self) -> Vec<(Hash, Listing)> {
+        // Return global listings (channel ID is not meaningful for global listings,
+        // so we use a zero hash as placeholder)
+        let listings = self.global_listings.read().await;
+        listings.iter()
+            .filter(|l| l.active)
+            .map(|l| (Hash::new([0u8; 32]), l.clone()))
+            .collect()
+    }
+
+    /// Get listings for a specific channel
+    pub async fn get_channel_listings(&self, channel_id: &Hash) -> Result<Vec<Listing>> {
+        let handle = self.channel(channel_id).await?;
+        let channel = handle.lock().await;
+
+        Ok(channel.state.listings.iter()
+            .filter(|l| l.active)
+            .cloned()
+            .collect())
+    }
+
+    /// Load the order history from storage
+    pub async fn load_orders(&self) -> Result<()> {
+        let mut orders: HashMap<Hash, (Hash, Order)> = self.storage.load_orders()?
+            .into_iter()
+            .map(|(channel_id, order)| (order.id, (channel_id, order)))
+            .collect();
+
+        // Orders placed before they were stored apart are only in channel state
+        let mut backfilled = 0;
+        for channel_id in self.storage.list_channels()? {
+            let channel = match self.storage.load_channel(&channel_id) {
+                Ok(Some(channel)) => channel,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to read channel {:?} for its orders: {}", channel_id, e);
+                    continue;
+                }
+            };
+            for order in channel.state.orders {
+                if !orders.contains_key(&order.id) {
+                    self.storage.store_order(&channel_id, &order)?;
+                    orders.insert(order.id, (channel_id, order));
+                    backfilled += 1;
+                }
+            }
+        }
+
+        let mut global_orders = self.global_orders.write().await;
+        *global_orders = orders;
+        info!("Loaded {} orders from storage, {} of them from channel state", global_orders.len(), backfilled);
+        Ok(())
+    }
+
+    /// List all orders across all channels, including hibernated ones and
+    /// ones whose channel is no longer stored
+    pub async fn list_all_orders(&self) -> Vec<(Hash, Order)> {
+        let mut all_orders = Vec::new();
+
+        for handle in self.channel_handles().await {
+            let channel = handle.lock().await;
+            for order in &channel.state.orders {
+                all_orders.push((channel.channel_id, order.clone()));
+            }
+        }
+        for channel in self.hibernated_channels().await {
+            for order in &channel.state.orders {
+                all_orders.push((channel.channel_id, order.clone()));
+            }
+        }
+
+        // Channel state is current; stored orders fill in the rest
+        let in_channels: HashSet<Hash> = all_orders.iter().map(|(_, order)| order.id).collect();
+        for (order_id, (channel_id, order)) in self.global_orders.read().await.iter() {
+            if !in_channels.contains(order_id) {
+                all_orders.push((*channel_id, order.clone()));
+            }
+        }
+
+        all_orders
+    }
+
+    /// Get orders for a specific channel
+    pub async fn get_channel_orders(&self, channel_id: &Hash) -> Result<Vec<Order>> {
+        let handle = self.channel(channel_id).await?;
+        let orders = handle.lock().await.state.orders.clone();
+        Ok(orders)
+    }
+
+    // ===== Channel Requests =====
+
+    /// Evaluate an incoming channel open request against the channel policy.
+    /// The initiator's genesis signature is checked first. Returns the channel
+    /// ID if the request was auto-accepted and co-signed, or None if it was
+    /// queued for manual approval.
+    pub async fn handle_channel_request(&self, mut request: ChannelRequest) -> Result<Option<Hash>> {
+        if !request.participants.contains(&self.keypair.public_key()) {
+            return Err(L2Error::InvalidParameter("Channel request does not include this node".to_string()));
+        }
+        self.check_channel_config(&request.to_config()).await?;
+        let signature = request.signature.clone().ok_or(L2Error::InvalidSignature)?;
+        MarketplaceChannel::new(request.to_config())
+            .add_genesis_signature(request.initiator, signature)?;
+
+        let mut open_channels = 0;
+        for handle in self.channel_handles().await {
+            if handle.lock().await.status != ChannelStatus::Closed {
+                open_channels += 1;
+            }
+        }
+        let reputation = self.profiles.read().await
+            .get(&request.initiator)
+            .map(|p| p.rating);
+
+        let decision = self.channel_policy.read().await
+            .evaluate(&request, open_channels, reputation);
+
+        match decision {
+            PolicyDecision::Accept => {
+                let channel_id = self.accept_channel_request(&request).await?;
+                info!("✅ Auto-accepted channel request from {:?}", request.initiator);
+                Ok(Some(channel_id))
+            }
+            PolicyDecision::Review(reason) => {
+                info!("⏸️  Channel request {:?} queued for approval: {}", request.channel_id, reason);
+                request.review_reason = Some(reason);
+                self.pending_channel_requests.write().await.insert(request.channel_id, request);
+                Ok(None)
+            }
+        }
+    }
+
+    /// List channel requests awaiting manual approval
+    pub async fn list_channel_requests(&self) -> Vec<ChannelRequest> {
+        self.pending_channel_requests.read().await.values().cloned().collect()
+    }
+
+    /// Approve a queued channel request and open the channel
+    pub async fn approve_channel_request(&self, channel_id: &Hash) -> Result<Hash> {
+        let request = self.pending_channel_requests.write().await
+            .remove(channel_id)
+            .ok_or_else(|| L2Error::Unknown(format!("Channel request not found: {:?}", channel_id)))?;
+
+        let channel_id = self.accept_channel_request(&request).await?;
+
+        info!("Approved channel request: {:?}", channel_id);
+        Ok(channel_id)
+    }
+
+    /// Reject a queued channel request
+    pub async fn reject_channel_request(&self, channel_id: &Hash) -> Result<()> {
+        let request = self.pending_channel_requests.write().await
+            .remove(channel_id)
+            .ok_or_else(|| L2Error::Unknown(format!("Channel request not found: {:?}", channel_id)))?;
+
+        self.send_channel_open_response(request.initiator, *channel_id, None).await?;
+
+        info!("Rejected channel request: {:?}", channel_id);
+        Ok(())
+    }
+
+    /// Co-sign the genesis state of an accepted request, persist the channel
+    /// and send our signature to the initiator. Collateral is locked by the
+    /// initiator once it holds every signature.
+    async fn accept_channel_request(&self, request: &ChannelRequest) -> Result<Hash> {
+        let mut channel = MarketplaceChannel::new(request.to_config());
+        let signature = request.signature.clone().ok_or(L2Error::InvalidSignature)?;
+        channel.add_genesis_signature(request.initiator, signature)?;
+
+        let own = self.keypair.sign(&channel.genesis_signing_message());
+        channel.add_genesis_signature(self.keypair.public_key(), own.clone())?;
+
+        self.ensure_can_open(&channel.channel_id).await?;
+        let channel_id = self.store_new_channel(channel).await?;
+        self.send_channel_open_response(request.initiator, channel_id, Some(own)).await?;
+        Ok(channel_id)
+    }
+
+    /// Notify the initiator of a channel request decision; a co-signature means accepted
+    async fn send_channel_open_response(
+        &self,
+        initiator: PublicKey,
+        channel_id: Hash,
+        signature: Option<Signature>,
+    ) -> Result<()> {
+        if let Some(network) = self.network.read().await.as_ref() {
+            let message = tari_l2_p2p::L2Message::ChannelOpenResponse {
+                channel_id,
+                accepted: signature.is_some(),
+                signature,
+            };
+            network.send_message(initiator, message).await?;
+        }
+        Ok(())
+    }
+
+    /// Store or replace a user profile
+    pub async fn upsert_profile(&self, profile: UserProfile) {
+        self.seller_catalogs.write().await.remove(&profile.public_key);
+        self.profiles.write().await.insert(profile.public_key, profile);
+    }
+
+    /// Get a user profile by public key
+    pub async fn get_profile(&self, public_key: &PublicKey) -> Option<UserProfile> {
+        self.profiles.read().await.get(public_key).cloned()
+    }
+
+    /// Review the seller of a completed order. Only the order's buyer can
+    /// review it, and only once.
+    pub async fn add_review(
+        &self,
+        order_id: &Hash,
+        reviewer: PublicKey,
+        rating: u8,
+        comment: String,
+    ) -> Result<Review> {
+        if rating == 0 || rating > MAX_REVIEW_RATING {
+            return Err(L2Error::InvalidParameter(format!("Rating must be between 1 and {}", MAX_REVIEW_RATING)));
+        }
+
+        let (_, order) = self.list_all_orders().await
+            .into_iter()
+            .find(|(_, o)| o.id == *order_id)
+            .ok_or_else(|| L2Error::InvalidParameter(format!("Order not found: {:?}", order_id)))?;
+        if order.buyer != reviewer {
+            return Err(L2Error::InvalidParameter("Only the buyer can review an order".to_string()));
+        }
+        if order.status != OrderStatus::Completed {
+            return Err(L2Error::InvalidParameter(format!("Order is not completed: {:?}", order.status)));
+        }
+
+        let mut reviews = self.reviews.write().await;
+        let seller_reviews = reviews.entry(order.seller).or_default();
+        if seller_reviews.iter().any(|r| r.order_id == *order_id) {
+            return Err(L2Error::InvalidParameter(format!("Order already reviewed: {:?}", order_id)));
+        }
+
+        let review = Review {
+            order_id: *order_id,
+            reviewer,
+            seller: order.seller,
+            rating,
+            comment,
+            created_at: self.clock.now().as_secs(),
+        };
+        seller_reviews.push(review.clone());
+        drop(reviews);
+
+        self.seller_catalogs.write().await.remove(&order.seller);
+        info!("Review of {:?} for order {:?}: {} stars", order.seller, order_id, rating);
+        Ok(review)
+    }
+
+    /// A seller's profile, reputation, active listings and recent reviews in
+    /// one view for storefront pages. Built on first request and cached until
+    /// any of them change.
+    pub async fn get_seller_catalog(&self, seller: &PublicKey) -> SellerCatalog {
+        if let Some(catalog) = self.seller_catalogs.read().await.get(seller) {
+            return catalog.clone();
+        }
+
+        let profile = self.get_profile(seller).await;
+        let listings: Vec<Listing> = self.global_listings.read().await.iter()
+            .filter(|l| l.seller == *seller && l.active)
+            .cloned()
+            .collect();
+        let mut reviews = self.reviews.read().await.get(seller).cloned().unwrap_or_default();
+        let reputation = SellerReputation::from_reviews(profile.as_ref(), &reviews);
+
+        reviews.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        reviews.truncate(CATALOG_RECENT_REVIEWS);
+
+        let integrity = CatalogIntegrity::check(self.catalog_roots.read().await.get(seller), &listings);
+
+        let catalog = SellerCatalog {
+            seller: *seller,
+            profile,
+            reputation,
+            listings,
+            recent_reviews: reviews,
+            integrity,
+        };
+        self.seller_catalogs.write().await.insert(*seller, catalog.clone());
+        catalog
+    }
+
+    // ===== Escrow Management =====
+
+    /// Create an escrow contract securing an order in a channel. Parties and
+    /// amount are taken from the order; an order can only have one unsettled escrow.
+    /// With `ship_within`, the buyer can take a refund if the seller has not
+    /// shipped that many seconds after funding.
+    #[instrument(skip_all, fields(channel_id = %channel_id, order_id = %order_id))]
+    pub async fn create_escrow(
+        &self,
+        channel_id: &Hash,
+        order_id: &Hash,
+        timeout_period: u64,
+        ship_within: Option<u64>,
+    ) -> Result<Hash> {
+        let order = self.get_channel_orders(channel_id).await?
+            .into_iter()
+            .find(|o| o.id == *order_id)
+            .ok_or_else(|| L2Error::InvalidParameter(format!("Order not found: {:?}", order_id)))?;
+        if matches!(order.status, OrderStatus::Completed | OrderStatus::Cancelled) {
+            return Err(L2Error::InvalidParameter(format!("Order is already {:?}", order.status)));
+        }
+
+        let mut escrows = self.escrow_contracts.write().await;
+        if escrows.values().any(|e| e.order_id == *order_id && !e.is_settled()) {
+            return Err(L2Error::InvalidParameter(format!("Order already has an escrow: {:?}", order_id)));
+        }
+
+        let mut escrow = EscrowContract::new(*channel_id, &order, timeout_period, self.clock.now());
+        escrow.ship_within = ship_within;
+        let escrow_id = escrow.id;
+        self.storage.store_escrow(&escrow)?;
+        self.audit(AuditEvent::Escrow { escrow_id, order_id: escrow.order_id, channel_id: escrow.channel_id, status: escrow.status.clone() });
+        self.publish_escrow(&escrow, None);
+        escrows.insert(escrow_id, escrow);
+        info!("Created escrow contract {:?} for order {:?}", escrow_id, order_id);
+
+        Ok(escrow_id)
+    }
+
+    /// Fund an escrow contract (buyer deposits funds to L1)
+    pub async fn fund_escrow(&self, escrow_id: &Hash, l1_tx_id: String) -> Result<()> {
+        self.transition_escrow(escrow_id, None, |escrow, now| escrow.fund(l1_tx_id, now)).await?;
+        info!("Funded escrow: {:?}", escrow_id);
+        Ok(())
+    }
+
+    /// Fund an escrow from the buyer's channel balance, with no L1
+    /// transaction. Proposes a `LockEscrow` update; once co-signed the lock
+    /// pays the seller when the order completes, or returns to the buyer if
+    /// it is cancelled. The escrow only counts as funded when that update is
+    /// applied.
+    #[instrument(skip_all, fields(escrow_id = %escrow_id))]
+    pub async fn fund_escrow_from_channel(&self, escrow_id: &Hash) -> Result<SignedStateUpdate> {
+        let escrows = self.escrow_contracts.write().await;
+        let escrow = escrows.get(escrow_id)
+            .ok_or_else(|| L2Error::Unknown(format!("Escrow not found: {:?}", escrow_id)))?;
+
+        // Check the move is legal now rather than when the lock is applied
+        escrow.clone().fund_in_channel(self.clock.now())?;
+
+        let update = StateUpdate::LockEscrow { order_id: escrow.order_id };
+        let already_proposed = self.pending_updates.read().await
+            .get(&escrow.channel_id)
+            .is_some_and(|pending| pending.iter().any(|u| matches!(u.update, StateUpdate::LockEscrow { order_id } if order_id == escrow.order_id)));
+        if already_proposed {
+            return Err(L2Error::InvalidParameter(format!("Funding of escrow {:?} is already awaiting signatures", escrow_id)));
+        }
+
+        let available = self.get_balance(&escrow.channel_id, &escrow.buyer).await?;
+        if available < escrow.amount {
+            return Err(L2Error::InsufficientBalance {
+                required: escrow.amount.value(),
+                available: available.value(),
+            });
+        }
+
+        let signed = self.propose_state_update(&escrow.channel_id, update).await?;
+        info!("Proposed funding escrow {:?} from channel balance", escrow_id);
+        Ok(signed)
+    }
+
+    /// Mark the escrow of an order funded once its `LockEscrow` update has
+    /// been applied to the channel
+    async fn commit_escrow_funding(&self, order_id: &Hash, now: Timestamp) -> Result<()> {
+        let mut escrows = self.escrow_contracts.write().await;
+        let Some(escrow) = escrows.values_mut().find(|e| e.order_id == *order_id && e.status == EscrowStatus::Created) else {
+            return Ok(());
+        };
+
+        let previous = escrow.status.clone();
+        let mut updated = escrow.clone();
+        updated.fund_in_channel(now)?;
+        self.storage.store_escrow(&updated)?;
+        self.audit(AuditEvent::Escrow { escrow_id: updated.id, order_id: updated.order_id, channel_id: updated.channel_id, status: updated.status.clone() });
+        self.publish_escrow(&updated, Some(previous));
+        info!("Funded escrow {:?} from channel balance", updated.id);
+        *escrow = updated;
+        Ok(())
+    }
+
+    /// Mark order as shipped (seller confirms shipment)
+    pub async fn ship_order(&self, escrow_id: &Hash, tracking_info: Option<String>) -> Result<()> {
+        self.transition_escrow(escrow_id, None, |escrow, now| escrow.mark_shipped(tracking_info, now)).await?;
+        info!("Marked escrow as shipped: {:?}", escrow_id);
+        Ok(())
+    }
+
+    /// Record the L1 address `party` is paid at if an escrow funded on L1
+    /// settles in their favour
+    pub async fn set_escrow_payout_address(&self, escrow_id: &Hash, party: &PublicKey, address: String) -> Result<()> {
+        if address.trim().is_empty() {
+            return Err(L2Error::InvalidParameter("Payout address is empty".to_string()));
+        }
+        self.transition_escrow(escrow_id, None, |escrow, _| escrow.set_payout_address(party, address)).await?;
+        info!("Set payout address of {:?} for escrow {:?}", party, escrow_id);
+        Ok(())
+    }
+
+    /// Confirm delivery and release funds to seller (buyer confirms receipt).
+    /// Also proposes completing the linked order, which pays the seller in
+    /// the channel; an escrow funded on L1 is paid out there.
+    pub async fn confirm_delivery(&self, escrow_id: &Hash) -> Result<()> {
+        self.transition_escrow(escrow_id, Some(OrderStatus::Completed), |escrow, now| escrow.confirm_receipt(now)).await?;
+        info!("Confirmed delivery and released escrow: {:?}", escrow_id);
+        Ok(())
+    }
+
+    /// Request refund (buyer initiates refund request)
+    pub async fn request_refund(&self, escrow_id: &Hash, reason: String) -> Result<()> {
+        self.transition_escrow(escrow_id, None, |escrow, now| escrow.request_refund(reason, now)).await?;
+        info!("Refund requested for escrow: {:?}", escrow_id);
+        Ok(())
+    }
+
+    /// Approve refund (seller agrees to refund). Also proposes cancelling
+    /// the linked order; an escrow funded on L1 is refunded there.
+    pub async fn approve_refund(&self, escrow_id: &Hash) -> Result<()> {
+        self.transition_escrow(escrow_id, Some(OrderStatus::Cancelled), |escrow, now| escrow.approve_refund(now)).await?;
+        info!("Approved refund for escrow: {:?}", escrow_id);
+        Ok(())
+    }
+
+    /// Take a refund for an escrow we bought through whose seller did not
+    /// ship by the deadline. No seller approval is needed: the linked order
+    /// is proposed cancelled, which returns any locked funds to us.
+    pub async fn refund_unshipped(&self, escrow_id: &Hash) -> Result<()> {
+        let escrow = self.get_escrow(escrow_id).await?;
+        if escrow.buyer != self.keypair.public_key() {
+            return Err(L2Error::InvalidParameter("Only the buyer can claim a late-shipment refund".to_string()));
+        }
+        self.transition_escrow(escrow_id, Some(OrderStatus::Cancelled), |escrow, now| escrow.refund_unshipped(now)).await?;
+        info!("Refunded escrow {:?}: not shipped by the deadline", escrow_id);
+        Ok(())
+    }
+
+    /// Raise dispute (either party can dispute). Also proposes marking the linked order disputed.
+    pub async fn raise_dispute(&self, escrow_id: &Hash, reason: String) -> Result<()> {
+        self.transition_escrow(escrow_id, Some(OrderStatus::Disputed), |escrow, now| escrow.raise_dispute(reason, now)).await?;
+        info!("Dispute raised for escrow: {:?}", escrow_id);
+        Ok(())
+    }
+
+    /// Apply `transition` to an escrow and, when `order_status` is given,
+    /// propose the matching status for its order. The escrow only changes
+    /// once the order update has been proposed, so a proposal that fails
+    /// leaves both as they were. The counterparty can still decline to
+    /// co-sign, in which case the order lags the escrow until its status is
+    /// updated again.
+    ///
+    /// Settling an escrow funded on L1 first pays the payee there from the
+    /// deposit (see `escrow_payout_address`). A failed payout leaves the
+    /// escrow untouched; a successful one is recorded straight away, so
+    /// retrying after a failed proposal never pays twice. An order whose
+    /// seller was paid on L1 completes without moving channel funds.
+    ///
+    /// The escrow map is not locked across the payout or the proposal.
+    /// Instead the escrow is claimed for the whole transition, and a second
+    /// transition of it started meanwhile is refused.
+    #[instrument(skip_all, fields(escrow_id = %escrow_id))]
+    async fn transition_escrow<F>(
+        &self,
+        escrow_id: &Hash,
+        order_status: Option<OrderStatus>,
+        transition: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(&mut EscrowContract, Timestamp) -> Result<()>,
+    {
+        let _claim = EscrowClaim::new(&self.escrows_in_transition, *escrow_id)?;
+        let previous = self.get_escrow(escrow_id).await?;
+
+        let mut updated = previous.clone();
+        transition(&mut updated, self.clock.now())?;
+
+        if updated.is_settled() && updated.needs_l1_payout() {
+            let address = self.escrow_payout_address(&updated)?;
+            let tx_id = self.pay_out_escrow(&updated, &address).await?;
+            updated.payout_tx_id = Some(tx_id.clone());
+
+            let mut escrows = self.escrow_contracts.write().await;
+            let escrow = escrows.get_mut(escrow_id)
+                .ok_or_else(|| L2Error::Unknown(format!("Escrow not found: {:?}", escrow_id)))?;
+            escrow.payout_tx_id = Some(tx_id);
+            self.storage.store_escrow(escrow)?;
+        }
+
+        if let Some(status) = order_status {
+            // The buyer already paid through the L1 deposit, so completing
+            // the order must not charge them again in the channel
+            let update = match (&status, &updated.payout_tx_id) {
+                (OrderStatus::Completed, Some(payout_tx_id)) => StateUpdate::CompleteOrderPaidOnL1 {
+                    order_id: updated.order_id,
+                    payout_tx_id: payout_tx_id.clone(),
+                },
+                _ => StateUpdate::UpdateOrderStatus { order_id: updated.order_id, status },
+            };
+            self.propose_state_update(&updated.channel_id, update).await?;
+        }
+
+        let mut escrows = self.escrow_contracts.write().await;
+        let escrow = escrows.get_mut(escrow_id)
+            .ok_or_else(|| L2Error::Unknown(format!("Escrow not found: {:?}", escrow_id)))?;
+        // Funding committed from the channel meanwhile is not ours to overwrite
+        if escrow.status != previous.status {
+            return Err(L2Error::InvalidStateTransition);
+        }
+        self.storage.store_escrow(&updated)?;
+        self.audit(AuditEvent::Escrow { escrow_id: updated.id, order_id: updated.order_id, channel_id: updated.channel_id, status: updated.status.clone() });
+        self.publish_escrow(&updated, Some(previous.status));
+        *escrow = updated;
+        Ok(())
+    }
+
+    /// L1 address a settled escrow funded on L1 pays its payee at: the one
+    /// set on the escrow, else the payee's preferred one. An escrow funded
+    /// on L1 can only pay out there, so a payee preferring in-channel
+    /// payouts still needs an address.
+    fn escrow_payout_address(&self, escrow: &EscrowContract) -> Result<String> {
+        let Some((payee, address)) = escrow.payee() else {
+            return Err(L2Error::Unknown(format!("Escrow has no payee in status {:?}", escrow.status)));
+        };
+        if let Some(address) = address {
+            return Ok(address.to_string());
+        }
+
+        self.storage.load_payout_preference(&payee)?
+            .and_then(|p| p.address)
+            .ok_or_else(|| L2Error::InvalidParameter(format!("No L1 payout address set for {:?}", payee)))
+    }
+
+    /// Pay a settled escrow's amount to its payee's L1 address out of the
+    /// buyer's deposit
+    #[instrument(skip_all, fields(escrow_id = %escrow.id, channel_id = %escrow.channel_id, order_id = %escrow.order_id))]
+    async fn pay_out_escrow(&self, escrow: &EscrowContract, address: &str) -> Result<String> {
+        let payee = escrow.payee().map(|(payee, _)| payee);
+        let l1_client = self.l1_client.as_ref()
+            .ok_or_else(|| L2Error::TariConnectionError("No L1 client configured".to_string()))?;
+        let deposit = escrow.l1_tx_id.clone()
+            .ok_or_else(|| L2Error::InvalidParameter(format!("Escrow {:?} has no L1 deposit", escrow.id)))?;
+
+        let tx_id = l1_client.release_escrow(deposit, address.to_string(), escrow.amount.value()).await
+            .map_err(|e| L2Error::TariConnectionError(format!("Escrow payout failed: {}", e)))?;
+        info!("Paid out escrow {:?} to {:?} in L1 tx {}", escrow.id, payee, tx_id);
+        self.audit_l1(L1Action::EscrowPayout, Some(escrow.channel_id), &tx_id);
+        Ok(tx_id)
+    }
+
+    /// Verifiably pick an arbitrator for a disputed escrow
+    pub async fn select_arbitrator(&self, escrow_id: &Hash, candidates: &[PublicKey]) -> Result<ArbitratorSelection> {
+        let escrows = self.escrow_contracts.read().await;
+        let escrow = escrows.get(escrow_id)
+            .ok_or_else(|| L2Error::Unknown(format!("Escrow not found: {:?}", escrow_id)))?;
+
+        if escrow.status != EscrowStatus::Disputed {
+            return Err(L2Error::InvalidParameter(format!("Escrow is not disputed: {:?}", escrow.status)));
+        }
+
+        // The parties to the dispute cannot arbitrate it
+        let eligible: Vec<PublicKey> = candidates.iter()
+            .filter(|c| **c != escrow.buyer && **c != escrow.seller)
+            .copied()
+            .collect();
+
+        let selection = ArbitratorSelection::select(&self.keypair, *escrow_id, &eligible)
+            .ok_or_else(|| L2Error::InvalidParameter("No eligible arbitrators".to_string()))?;
+        info!("Selected arbitrator {:?} for escrow {:?}", selection.arbitrator, escrow_id);
+
+        Ok(selection)
+    }
+
+    /// Get escrow contract details
+    pub async fn get_escrow(&self, escrow_id: &Hash) -> Result<EscrowContract> {
+        let escrows = self.escrow_contracts.read().await;
+        escrows.get(escrow_id)
+            .cloned()
+            .ok_or_else(|| L2Error::Unknown(format!("Escrow not found: {:?}", escrow_id)))
+    }
+
+    /// Load escrow contracts from storage
+    pub async fn load_escrows(&self) -> Result<()> {
+        let escrows = self.storage.load_escrows()?;
+        let mut contracts = self.escrow_contracts.write().await;
+        *contracts = escrows.into_iter().map(|escrow| (escrow.id, escrow)).collect();
+        info!("Loaded {} escrows from storage", contracts.len());
+        Ok(())
+    }
+
+    /// List all escrow contracts
+    pub async fn list_escrows(&self) -> Vec<EscrowContract> {
+        self.escrow_contracts.read().await.values().cloned().collect()
+    }
+
+    /// An order with the escrow securing it. Settled escrows are only
+    /// returned when the order has no unsettled one.
+    pub async fn get_order_view(&self, order_id: &Hash) -> Result<OrderView> {
+        let (channel_id, order) = self.list_all_orders().await
+            .into_iter()
+            .find(|(_, o)| o.id == *order_id)
+            .ok_or_else(|| L2Error::InvalidParameter(format!("Order not found: {:?}", order_id)))?;
+        let escrow = self.escrow_for_order(order_id).await;
+        Ok(OrderView { channel_id, order, escrow })
+    }
+
+    /// Every order with its escrow, if any
+    pub async fn list_order_views(&self) -> Vec<OrderView> {
+        let mut views = Vec::new();
+        for (channel_id, order) in self.list_all_orders().await {
+            let escrow = self.escrow_for_order(&order.id).await;
+            views.push(OrderView { channel_id, order, escrow });
+        }
+        views
+    }
+
+    /// Orders `buyer` placed in any channel, each with its escrow and
+    /// receipt, filtered and paged by `query`
+    pub async fn get_purchase_history(&self, buyer: &PublicKey, query: &HistoryQuery) -> Result<PurchaseHistory> {
+        let mut records = Vec::new();
+        for (channel_id, order) in self.list_all_orders().await {
+            if order.buyer != *buyer {
+                continue;
+            }
+            let escrow = self.escrow_for_order(&order.id).await;
+            let receipt = self.storage.load_receipt(&order.id)?;
+            records.push(PurchaseRecord::new(channel_id, order, escrow, receipt));
+        }
+        Ok(PurchaseHistory::page(records, query))
+    }
+
+    async fn escrow_for_order(&self, order_id: &Hash) -> Option<EscrowContract> {
+        let escrows = self.escrow_contracts.read().await;
+        let mut linked = escrows.values().filter(|e| e.order_id == *order_id);
+        linked.clone().find(|e| !e.is_settled())
+            .or_else(|| linked.max_by_key(|e| e.updated_at.as_secs()))
+            .cloned()
+    }
+
+    /// Cancel pending orders we sell that are still unfunded `deadline_secs`
+    /// after we first saw them, returning their IDs. The cancellation is
+    /// proposed to the buyer as a state update, together with an
+    /// `OrderExpired` notice; an unfunded escrow for the order is cancelled too.
+    pub async fn expire_unfunded_orders(&self, deadline_secs: u64) -> Result<Vec<Hash>> {
+        let now = self.clock.now();
+        let own_key = self.keypair.public_key();
+
+        let mut pending: Vec<(Hash, Order)> = Vec::new();
+        for handle in self.channel_handles().await {
+            let c = handle.lock().await;
+            if c.status != ChannelStatus::Active {
+                continue;
+            }
+            pending.extend(c.state.orders.iter()
+                .filter(|o| o.status == OrderStatus::Pending && o.seller == own_key)
+                .map(|o| (c.channel_id, o.clone())));
+        }
+
+        // Start the clock for new orders and forget ones that left Pending.
+        // The clocks are stored so a restart does not reset them.
+        let mut first_seen: HashMap<Hash, u64> = HashMap::new();
+        for (order_id, at) in self.storage.load_order_first_seen()? {
+            if pending.iter().any(|(_, o)| o.id == order_id) {
+                first_seen.insert(order_id, at);
+            } else {
+                self.storage.delete_order_first_seen(&order_id)?;
+            }
+        }
+        let mut overdue: Vec<(Hash, Order)> = Vec::new();
+        for (channel_id, order) in pending {
+            let seen = match first_seen.get(&order.id) {
+                Some(at) => *at,
+                None => {
+                    self.storage.store_order_first_seen(&order.id, now.as_secs())?;
+                    now.as_secs()
+                }
+            };
+            if now.as_secs().saturating_sub(seen) > deadline_secs {
+                overdue.push((channel_id, order));
+            }
+        }
+
+        let mut expired = Vec::new();
+        for (channel_id, order) in overdue {
+            let already_cancelling = self.pending_updates.read().await
+                .get(&channel_id)
+                .is_some_and(|updates| updates.iter().any(|u| matches!(
+                    &u.update,
+                    StateUpdate::UpdateOrderStatus { order_id, status: OrderStatus::Cancelled } if *order_id == order.id
+                )));
+            if already_cancelling {
+                continue;
+            }
+
+            let escrow = self.escrow_for_order(&order.id).await;
+            if escrow.as_ref().is_some_and(|e| !matches!(e.status, EscrowStatus::Created | EscrowStatus::Cancelled)) {
+                continue;
+            }
+
+            let cancelled = match escrow.filter(|e| e.status == EscrowStatus::Created) {
+                Some(escrow) => self
+                    .transition_escrow(&escrow.id, Some(OrderStatus::Cancelled), |e, now| e.cancel(now))
+                    .await,
+                None => self
+                    .propose_state_update(&channel_id, StateUpdate::UpdateOrderStatus {
+                        order_id: order.id,
+                        status: OrderStatus::Cancelled,
+                    })
+                    .await
+                    .map(|_| ()),
+            };
+            if let Err(e) = cancelled {
+                warn!("Failed to cancel unfunded order {:?}: {}", order.id, e);
+                continue;
+            }
+
+            // The order is cancelled either way; a buyer who misses the
+            // notice gets it from the outbox
+            let notice = tari_l2_p2p::L2Message::OrderExpired { channel_id, order_id: order.id };
+            if let Err(e) = self.send_reliably(&[order.buyer], notice).await {
+                warn!("Could not queue expiry notice for order {:?}: {}", order.id, e);
+            }
+            info!("⌛ Cancelled order {:?} in channel {:?}: not funded within {}s", order.id, channel_id, deadline_secs);
+            expired.push(order.id);
+        }
+
+        Ok(expired)
+    }
+
+    /// Set the arbitrators notified of escalated disputes
+    pub async fn set_arbitrators(&self, arbitrators: Vec<PublicKey>) {
+        *self.arbitrators.write().await = arbitrators;
+    }
+
+    /// Whether `key` is one of the arbitrators, who may settle disputed orders
+    pub async fn is_arbitrator(&self, key: &PublicKey) -> bool {
+        self.arbitrators.read().await.contains(key)
+    }
+
+    /// Escalate refund requests on escrows we bought through that the seller
+    /// has left unanswered for more than `deadline_secs`, returning their IDs.
+    /// Each escrow moves to Disputed, the order is marked disputed in the
+    /// channel, and the arbitrator set is sent a `DisputeEscalated` notice.
+    pub async fn escalate_stale_refunds(&self, deadline_secs: u64) -> Result<Vec<Hash>> {
+        let now = self.clock.now();
+        let own_key = self.keypair.public_key();
+        let overdue: Vec<EscrowContract> = self.escrow_contracts.read().await.values()
+            .filter(|e| e.buyer == own_key && e.is_refund_overdue(now, deadline_secs))
+            .cloned()
+            .collect();
+
+        let arbitrators = self.arbitrators.read().await.clone();
+        let mut escalated = Vec::new();
+        for escrow in overdue {
+            if let Err(e) = self
+                .transition_escrow(&escrow.id, Some(OrderStatus::Disputed), |e, now| e.escalate_refund(now))
+                .await
+            {
+                warn!("Could not escalate refund for escrow {:?}: {}", escrow.id, e);
+                continue;
+            }
+
+            // The escrow is disputed either way; arbitrators who miss the
+            // notice get it from the outbox
+            let notice = tari_l2_p2p::L2Message::DisputeEscalated {
+                escrow_id: escrow.id,
+                channel_id: escrow.channel_id,
+                order_id: escrow.order_id,
+                reason: escrow.dispute_reason.clone().unwrap_or_default(),
+            };
+            if let Err(e) = self.send_reliably(&arbitrators, notice).await {
+                warn!("Could not queue dispute notice for escrow {:?}: {}", escrow.id, e);
+            }
+            info!("⚖️  Escalated escrow {:?} to dispute: refund unanswered for {}s", escrow.id, deadline_secs);
+            escalated.push(escrow.id);
+        }
+
+        Ok(escalated)
+    }
+
+    /// Process timeouts for escrows: auto-release shipped ones to the
+    /// seller, completing the order, and refund ones we bought through that
+    /// were not shipped by their deadline
+    pub async fn process_escrow_timeouts(&self) -> Result<Vec<Hash>> {
+        let now = self.clock.now();
+        let own_key = self.keypair.public_key();
+        let (timed_out, unshipped): (Vec<Hash>, Vec<Hash>) = {
+            let escrows = self.escrow_contracts.read().await;
+            (
+                escrows.values().filter(|e| e.is_timed_out(now)).map(|e| e.id).collect(),
+                escrows.values().filter(|e| e.buyer == own_key && e.is_shipment_overdue(now)).map(|e| e.id).collect(),
+            )
+        };
+
+        let mut released = Vec::new();
+        for escrow_id in timed_out {
+            match self.transition_escrow(&escrow_id, Some(OrderStatus::Completed), |escrow, now| escrow.auto_release(now)).await {
+                Ok(()) => {
+                    released.push(escrow_id);
+                    info!("Auto-released timed out escrow: {:?}", escrow_id);
+                }
+                Err(e) => warn!("Could not auto-release escrow {:?}: {}", escrow_id, e),
+            }
+        }
+
+        for escrow_id in unshipped {
+            match self.refund_unshipped(&escrow_id).await {
+                Ok(()) => released.push(escrow_id),
+                Err(e) => warn!("Could not refund unshipped escrow {:?}: {}", escrow_id, e),
+            }
+        }
+
+        self.reclaim_unsigned_refunds(now).await;
+        Ok(released)
+    }
+
+    /// Force-close channels whose seller has not co-signed the cancellation
+    /// of an unshipped order we refunded within `UNSHIPPED_REFUND_GRACE`.
+    /// The lock is still in the channel state, and an L1 settlement returns
+    /// locked funds to the buyer, so the refund needs nothing from the
+    /// seller.
+    async fn reclaim_unsigned_refunds(&self, now: Timestamp) {
+        let own_key = self.keypair.public_key();
+        let refunded: Vec<(Hash, Hash)> = self.escrow_contracts.read().await.values()
+            .filter(|e| e.buyer == own_key && e.funded_in_channel && e.status == EscrowStatus::Refunded)
+            .filter(|e| now.as_secs() >= e.updated_at.as_secs().saturating_add(UNSHIPPED_REFUND_GRACE))
+            .map(|e| (e.channel_id, e.order_id))
+            .collect();
+
+        for (channel_id, order_id) in refunded {
+            let Ok(handle) = self.channel(&channel_id).await else {
+                continue;
+            };
+            let stranded = {
+                let channel = handle.lock().await;
+                channel.status == ChannelStatus::Active && channel.state.escrow_locks.contains_key(&order_id)
+            };
+            if !stranded {
+                continue;
+            }
+            match self.force_close_channel(&channel_id).await {
+                Ok((tx_id, _)) => warn!("Seller never co-signed the refund of order {:?}; force-closed {:?}, tx: {}", order_id, channel_id, tx_id),
+                Err(e) => warn!("Could not force-close {:?} to reclaim order {:?}: {}", channel_id, order_id, e),
+            }
+        }
+    }
+
+    /// Get the node's public key
+    pub fn public_key(&self) -> PublicKey {
+        self.keypair.public_key()
+    }
+
+    /// Verify a signed action and accept it once. A replay of an accepted
+    /// action is refused until its timestamp leaves the window, after which
+    /// it is refused as stale.
+    pub async fn accept_action<T: serde::Serialize>(&self, action: &SignedAction<T>) -> Result<()> {
+        action.verify_with(self.clock.as_ref(), ACTION_TIMESTAMP_WINDOW)
+            .map_err(L2Error::InvalidParameter)?;
+        let id = action.id().map_err(L2Error::SerializationError)?;
+
+        let mut seen = self.seen_actions.write().await;
+        let cutoff = self.clock.now().as_secs().saturating_sub(ACTION_TIMESTAMP_WINDOW.max_age);
+        let expired = seen.prune(cutoff);
+        if !expired.is_empty() {
+            self.storage.remove_seen_actions(&expired)?;
+        }
+        if let Err(e) = seen.insert(id, action.timestamp) {
+            warn!("Refused replayed action from {}", action.public_key);
+            return Err(L2Error::InvalidParameter(e));
+        }
+        self.storage.store_seen_action(&id, action.timestamp)
+    }
+
+    /// Sign a caller-supplied nonce to prove this node's identity
+    pub async fn attest_identity(&self, nonce: Vec<u8>) -> Result<NodeAttestation> {
+        if nonce.is_empty() || nonce.len() > 256 {
+            return Err(L2Error::InvalidParameter("Nonce must be 1-256 bytes".to_string()));
+        }
+
+        let peer_id = self.network.read().await
+            .as_ref()
+            .map(|network| network.local_id());
+        let timestamp = self.clock.now().as_secs();
+
+        let message = NodeAttestation::signing_message(&nonce, peer_id.as_deref(), timestamp);
+        let signature = self.keypair.sign(&message);
+
+        Ok(NodeAttestation {
+            public_key: self.keypair.public_key(),
+            peer_id,
+            nonce,
+            timestamp,
+            signature,
+        })
+    }
+
+    /// Answer a buyer's challenge with a signed statement of the collateral
+    /// locked on L1 for one of this node's channels
+    pub async fn prove_reserves(&self, nonce: Vec<u8>, channel_id: Hash) -> Result<ReserveProof> {
+        if nonce.is_empty() || nonce.len() > 256 {
+            return Err(L2Error::InvalidParameter("Nonce must be 1-256 bytes".to_string()));
+        }
+
+        let l1_client = self.l1_client.as_ref()
+            .ok_or_else(|| L2Error::TariConnectionError("No L1 client configured".to_string()))?;
+
+        let participants = self.channel(&channel_id).await?.lock().await.participants.clone();
+        if !participants.contains(&self.keypair.public_key()) {
+            return Err(L2Error::ParticipantNotFound);
+        }
+
+        let collateral = l1_client.get_locked_collateral(&channel_id.to_string()).await
+            .ok_or_else(|| L2Error::InvalidParameter(format!("No collateral locked for channel {}", channel_id)))?;
+        let (source, amount, block_height) = (ReserveSource::LockedCollateral { channel_id }, collateral.amount, collateral.block_height);
+
+        let timestamp = self.clock.now().as_secs();
+        let message = ReserveProof::signing_message(&source, amount, block_height, &nonce, timestamp);
+        let signature = self.keypair.sign(&message);
+
+        Ok(ReserveProof {
+            public_key: self.keypair.public_key(),
+            source,
+            amount,
+            block_height,
+            nonce,
+            timestamp,
+            signature,
+        })
+    }
+
+    /// Check a seller's reserve proof against the challenge we issued and
+    /// against L1. The seller's signature alone proves nothing about their
+    /// funds, so without an L1 client no proof is accepted.
+    pub async fn verify_reserve_proof(&self, proof: &ReserveProof, nonce: &[u8], min_amount: Amount) -> Result<()> {
+        proof.verify_with(nonce, self.clock.as_ref(), ACTION_TIMESTAMP_WINDOW)
+            .map_err(L2Error::InvalidParameter)?;
+
+        if proof.amount < min_amount.value() {
+            return Err(L2Error::InsufficientBalance {
+                required: min_amount.value(),
+                available: proof.amount,
+            });
+        }
+
+        let l1_client = self.l1_client.as_ref()
+            .ok_or_else(|| L2Error::TariConnectionError("Reserve proofs are checked on L1, and no L1 client is configured".to_string()))?;
+        let ReserveSource::LockedCollateral { channel_id } = &proof.source;
+        let collateral = l1_client.get_locked_collateral(&channel_id.to_string()).await
+            .ok_or_else(|| L2Error::InvalidParameter(format!("No collateral locked for channel {}", channel_id)))?;
+        if !collateral.participants.contains(&proof.public_key.to_string()) {
+            return Err(L2Error::ParticipantNotFound);
+        }
+        if collateral.amount < proof.amount {
+            return Err(L2Error::InsufficientBalance {
+                required: proof.amount,
+                available: collateral.amount,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Reject configs whose opening balances overflow
+fn check_collateral(config: &ChannelConfig) -> Result<()> {
+    config.initial_balances.values()
+        .sum::<Option<Amount>>()
+        .map(|_| ())
+        .ok_or_else(|| L2Error::InvalidParameter("Collateral overflow".to_string()))
+}
+
+/// Message a seller signs to vouch for a listing
+fn listing_signing_message(listing: &Listing) -> Result<Vec<u8>> {
+    let listing_bytes = canonical::encode(listing).map_err(L2Error::SerializationError)?;
+    Ok(SigningContext::Listing.message(&listing_bytes))
+}
+
+/// Message a seller signs to announce `listing` as changed at `timestamp`
+fn listing_update_message(listing: &Listing, timestamp: u64) -> Result<Vec<u8>> {
+    let update_bytes = canonical::encode(&(listing, timestamp)).map_err(L2Error::SerializationError)?;
+    Ok(SigningContext::ListingUpdate.message(&update_bytes))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disk::DiskPolicy;
+    use crate::payout::PayoutMethod;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_marketplace_manager() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let manager = MarketplaceManager::new(storage, keypair.clone(), None);
+
+        let kp2 = KeyPair::generate();
+
+        let mut balances = HashMap::new();
+        balances.insert(keypair.public_key(), Amount::new(1000));
+        balances.insert(kp2.public_key(), Amount::new(1000));
+
+        let config = ChannelConfig {
+            participants: vec![keypair.public_key(), kp2.public_key()],
+            initial_balances: balances,
+            challenge_period: 3600,
+        };
+
+        // Create channel
+        let channel_id = manager.create_channel(config).await.unwrap();
+
+        // Activate channel
+        manager.activate_channel(&channel_id).await.unwrap();
+
+        // Get channel info
+        let info = manager.get_channel_info(&channel_id).await.unwrap();
+        assert_eq!(info.channel_id, channel_id);
+
+        // Check balance
+        let balance = manager.get_balance(&channel_id, &keypair.public_key()).await.unwrap();
+        assert_eq!(balance, Amount::new(1000));
+    }
+
+    #[tokio::test]
+    async fn test_channel_request_policy() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let manager = MarketplaceManager::new(storage, keypair.clone(), None);
+
+        let kp2 = KeyPair::generate();
+        let mut balances = HashMap::new();
+        balances.insert(keypair.public_key(), Amount::new(1000));
+        balances.insert(kp2.public_key(), Amount::new(1000));
+
+        let sign = |request: ChannelRequest| {
+            let message = MarketplaceChannel::new(request.to_config()).genesis_signing_message();
+            let signature = kp2.sign(&message);
+            request.with_signature(signature)
+        };
+
+        let request = ChannelRequest::new(
+            kp2.public_key(),
+            vec![kp2.public_key(), keypair.public_key()],
+            balances.clone(),
+            3600,
+        );
+
+        // Requests without a valid genesis signature from the initiator are refused
+        assert!(matches!(
+            manager.handle_channel_request(request.clone()).await,
+            Err(L2Error::InvalidSignature)
+        ));
+        let forged = request.clone().with_signature(keypair.sign(b"not the genesis state"));
+        assert!(manager.handle_channel_request(forged).await.is_err());
+        let request = sign(request);
+
+        // Collateral above the cap is queued for approval
+        manager.set_channel_policy(ChannelPolicy {
+            max_collateral: Some(500),
+            ..Default::default()
+        }).await;
+        let result = manager.handle_channel_request(request.clone()).await.unwrap();
+        assert!(result.is_none());
+        assert_eq!(manager.list_channel_requests().await.len(), 1);
+
+        let channel_id = manager.approve_channel_request(&request.channel_id).await.unwrap();
+        assert_eq!(channel_id, request.channel_id);
+        assert!(manager.list_channel_requests().await.is_empty());
+
+        // A request within policy is opened immediately
+        manager.set_channel_policy(ChannelPolicy::default()).await;
+        let request = sign(ChannelRequest::new(
+            kp2.public_key(),
+            vec![keypair.public_key(), kp2.public_key()],
+            balances,
+            3600,
+        ));
+        let result = manager.handle_channel_request(request).await.unwrap();
+        assert!(result.is_some());
+        assert_eq!(manager.list_channels().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pending_update_limit() {
+        use tari_l2_common::clock::ManualClock;
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let manager = MarketplaceManager::new(storage, keypair.clone(), None).with_clock(clock.clone());
+        manager.set_limits(ResourceLimits {
+            max_channels: 1,
+            max_pending_updates: 2,
+            ..Default::default()
+        }).await;
+
+        let kp2 = KeyPair::generate();
+        let mut balances = HashMap::new();
+        balances.insert(keypair.public_key(), Amount::new(1000));
+        balances.insert(kp2.public_key(), Amount::new(1000));
+
+        let config = ChannelConfig {
+            participants: vec![keypair.public_key(), kp2.public_key()],
+            initial_balances: balances.clone(),
+            challenge_period: 3600,
+        };
+        let channel_id = manager.create_channel(config).await.unwrap();
+
+        // Second channel exceeds the cap
+        let config = ChannelConfig {
+            participants: vec![kp2.public_key(), keypair.public_key()],
+            initial_balances: balances,
+            challenge_period: 3600,
+        };
+        assert!(matches!(
+            manager.create_channel(config).await,
+            Err(L2Error::ResourceLimitExceeded(_))
+        ));
+
+        for amount in 1..=2 {
+            manager.transfer(&channel_id, keypair.public_key(), kp2.public_key(), Amount::new(amount))
+                .await
+                .unwrap();
+        }
+        let result = manager.transfer(&channel_id, keypair.public_key(), kp2.public_key(), Amount::new(3)).await;
+        assert!(matches!(result, Err(L2Error::ResourceLimitExceeded(_))));
+        assert_eq!(manager.list_pending_updates(&channel_id).await.len(), 2);
+
+        // Proposals left unsigned past their TTL free the slots again
+        assert!(manager.expire_pending_updates(3600).await.unwrap().is_empty());
+        clock.advance(3601);
+        assert_eq!(manager.expire_pending_updates(3600).await.unwrap().len(), 2);
+        assert!(manager.list_pending_updates(&channel_id).await.is_empty());
+        assert!(manager.storage.load_journal().unwrap().is_empty());
+        manager.transfer(&channel_id, keypair.public_key(), kp2.public_key(), Amount::new(3)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_submitted_update_waits_for_every_signature() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let manager = MarketplaceManager::new(storage, keypair.clone(), None);
+
+        let (client, third) = (KeyPair::generate(), KeyPair::generate());
+        let participants = vec![keypair.public_key(), client.public_key(), third.public_key()];
+        let config = ChannelConfig {
+            initial_balances: participants.iter().map(|pk| (*pk, Amount::new(1000))).collect(),
+            participants,
+            challenge_period: 3600,
+        };
+        let channel_id = manager.create_channel(config).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+
+        let mut update = SignedStateUpdate::new(StateUpdate::Transfer {
+            from: client.public_key(),
+            to: third.public_key(),
+            amount: Amount::new(100),
+        }, 1);
+        update.add_signature(client.public_key(), client.sign(&update.signing_message()));
+
+        // A signature under our key has to be one this node made
+        let mut forged = update.clone();
+        forged.add_signature(keypair.public_key(), client.sign(&update.signing_message()));
+        assert!(manager.submit_state_update(&channel_id, forged).await.is_err());
+
+        // Nor will it sign away its own funds for a caller
+        let mut drain = SignedStateUpdate::new(StateUpdate::Transfer {
+            from: keypair.public_key(),
+            to: client.public_key(),
+            amount: Amount::new(100),
+        }, 1);
+        drain.add_signature(client.public_key(), client.sign(&drain.signing_message()));
+        assert!(matches!(
+            manager.submit_state_update(&channel_id, drain).await,
+            Err(L2Error::InvalidParameter(_))
+        ));
+
+        // Our signature does not complete it, so it stays pending
+        assert!(!manager.submit_state_update(&channel_id, update.clone()).await.unwrap());
+        assert_eq!(manager.get_channel_info(&channel_id).await.unwrap().nonce, 0);
+        assert_eq!(manager.list_pending_updates(&channel_id).await[0].signatures.len(), 2);
+
+        // An update out of sequence is refused outright
+        let mut skipped = update.clone();
+        skipped.nonce = 3;
+        assert!(manager.submit_state_update(&channel_id, skipped).await.is_err());
+
+        // The last participant's ack commits it
+        let signature = third.sign(&update.signing_message());
+        let committed = manager.handle_state_update_ack(&channel_id, 1, signature).await.unwrap().unwrap();
+        assert!(committed.verify(&[keypair.public_key(), client.public_key(), third.public_key()]));
+        let balance = manager.get_balance(&channel_id, &third.public_key()).await.unwrap();
+        assert_eq!(balance, Amount::new(1100));
+    }
+
+    #[tokio::test]
+    async fn test_journal_recovery() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let manager = MarketplaceManager::new(storage.clone(), keypair.clone(), None);
+
+        let kp2 = KeyPair::generate();
+        let mut balances = HashMap::new();
+        balances.insert(keypair.public_key(), Amount::new(1000));
+        balances.insert(kp2.public_key(), Amount::new(1000));
+
+        let config = ChannelConfig {
+            participants: vec![keypair.public_key(), kp2.public_key()],
+            initial_balances: balances,
+            challenge_period: 3600,
+        };
+        let channel_id = manager.create_channel(config).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+
+        // Fully sign a transfer and journal it as if we crashed before applying
+        let mut update = manager.transfer(&channel_id, keypair.public_key(), kp2.public_key(), Amount::new(100))
+            .await
+            .unwrap();
+        let message = update.signing_message();
+        update.add_signature(kp2.public_key(), kp2.sign(&message));
+        storage.journal_update(&JournalEntry::new(channel_id, update, JournalStatus::Acked)).unwrap();
+
+        // A restarted manager replays the update exactly once
+        let restarted = MarketplaceManager::new(storage.clone(), keypair.clone(), None);
+        restarted.load_channels().await.unwrap();
+        assert_eq!(restarted.recover_journal().await.unwrap(), 1);
+        assert_eq!(restarted.recover_journal().await.unwrap(), 0);
+
+        let balance = restarted.get_balance(&channel_id, &kp2.public_key()).await.unwrap();
+        assert_eq!(balance, Amount::new(1100));
+        assert!(storage.load_journal().unwrap().is_empty());
+
+        // The recovered update is part of the replayable history
+        let report = restarted.replay_channel(&channel_id).await.unwrap();
+        assert!(report.is_consistent());
+        assert_eq!(report.replayed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_signed_listings() {
+        let seller_dir = TempDir::new().unwrap();
+        let seller_key = Arc::new(KeyPair::generate());
+        let seller = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(seller_dir.path()).unwrap()),
+            seller_key.clone(),
+            None,
+        );
+        for i in 0..3u8 {
+            seller.create_global_listing(
+                Hash::new([i; 32]),
+                seller_key.public_key(),
+                format!("Item {}", i),
+                "desc".to_string(),
+                100,
+                String::new(),
+                "misc".to_string(),
+            ).await.unwrap();
+        }
+
+        let mut signed = seller.signed_listings().await.unwrap();
+        assert_eq!(signed.len(), 3);
+
+        // Tamper with one listing so its signature no longer matches
+        signed[1].0.price = Amount::new(1);
+
+        let buyer_dir = TempDir::new().unwrap();
+        let buyer = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(buyer_dir.path()).unwrap()),
+            Arc::new(KeyPair::generate()),
+            None,
+        );
+        assert_eq!(buyer.import_signed_listings(signed.clone()).await.unwrap(), 2);
+        assert_eq!(buyer.list_all_listings().await.len(), 2);
+
+        // Already known listings are skipped, and imported ones can be re-served
+        assert_eq!(buyer.import_signed_listings(signed).await.unwrap(), 0);
+        assert_eq!(buyer.signed_listings().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_degraded_node_refuses_new_listings_and_channels() {
+        let seller_dir = TempDir::new().unwrap();
+        let seller_key = Arc::new(KeyPair::generate());
+        let seller = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(seller_dir.path()).unwrap()),
+            seller_key.clone(),
+            None,
+        );
+        seller.create_global_listing(
+            Hash::new([1; 32]),
+            seller_key.public_key(),
+            "Item".to_string(),
+            "desc".to_string(),
+            100,
+            String::new(),
+            "misc".to_string(),
+        ).await.unwrap();
+        let signed = seller.signed_listings().await.unwrap();
+
+        let node_dir = TempDir::new().unwrap();
+        let node_key = Arc::new(KeyPair::generate());
+        let node = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(node_dir.path()).unwrap()),
+            node_key.clone(),
+            None,
+        );
+        let policy = DiskPolicy { min_free: 100, ..Default::default() };
+        node.set_disk_status(policy.evaluate(1_000, 50, 0)).await;
+
+        assert!(matches!(node.import_signed_listings(signed.clone()).await, Err(L2Error::ResourceLimitExceeded(_))));
+        let peer = KeyPair::generate().public_key();
+        let config = ChannelConfig {
+            participants: vec![node_key.public_key(), peer],
+            initial_balances: [(node_key.public_key(), Amount::new(1000)), (peer, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        };
+        assert!(matches!(node.create_channel(config.clone()).await, Err(L2Error::ResourceLimitExceeded(_))));
+
+        // Freeing space lifts the restrictions
+        node.set_disk_status(policy.evaluate(1_000, 1_000_000, 0)).await;
+        assert_eq!(node.import_signed_listings(signed).await.unwrap(), 1);
+        node.create_channel(config).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_draft_publish_and_unpublish() {
+        let temp_dir = TempDir::new().unwrap();
+        let keypair = Arc::new(KeyPair::generate());
+        let manager = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap()),
+            keypair.clone(),
+            None,
+        );
+
+        let draft = manager.create_draft(Listing {
+            id: Hash::new([7; 32]),
+            seller: keypair.public_key(),
+            title: "Lamp".to_string(),
+            description: "desc".to_string(),
+            price: Amount::new(100),
+            ipfs_hash: String::new(),
+            active: true,
+            category: "misc".to_string(),
+            fiat_reference: None,
+        }).await.unwrap();
+        assert!(!draft.active);
+        assert!(manager.list_all_listings().await.is_empty());
+        assert!(manager.signed_listings().await.unwrap().is_empty());
+
+        manager.publish_draft(&draft.id).await.unwrap();
+        assert_eq!(manager.list_all_listings().await.len(), 1);
+        assert!(manager.list_drafts().unwrap().is_empty());
+
+        // Unpublishing hides the listing and returns it to the drafts
+        let unpublished = manager.unpublish_listing(&draft.id).await.unwrap();
+        assert!(!unpublished.active);
+        assert!(manager.list_all_listings().await.is_empty());
+        assert_eq!(manager.list_drafts().unwrap().len(), 1);
+        assert!(manager.unpublish_listing(&draft.id).await.is_err());
+
+        let republished = manager.publish_draft(&draft.id).await.unwrap();
+        assert!(republished.active);
+        assert_eq!(manager.list_all_listings().await.len(), 1);
+        assert!(manager.publish_draft(&draft.id).await.is_err());
+    }
+
+    /// Open an active channel between `keypair` and a new buyer holding one
+    /// pending order from the buyer for 500
+    async fn channel_with_order(manager: &MarketplaceManager, keypair: &KeyPair) -> (Hash, Order, KeyPair) {
+        let buyer = KeyPair::generate();
+        let (seller, buyer_pk) = (keypair.public_key(), buyer.public_key());
+        let channel_id = manager.create_channel(ChannelConfig {
+            participants: vec![seller, buyer_pk],
+            initial_balances: [(seller, Amount::new(1000)), (buyer_pk, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+
+        let order = Order {
+            id: Hash::random(),
+            listing_id: Hash::random(),
+            buyer: buyer_pk,
+            seller,
+            amount: Amount::new(500),
+            status: OrderStatus::Pending,
+        };
+        let mut signed = manager.create_order(&channel_id, order.clone()).await.unwrap();
+        signed.add_signature(buyer_pk, buyer.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+        (channel_id, order, buyer)
+    }
+
+    /// A manager on storage in a fresh temporary directory, which is removed
+    /// when the fixture is dropped
+    struct TestManager {
+        _temp_dir: TempDir,
+        storage: Arc<MarketplaceStorage>,
+        keypair: Arc<KeyPair>,
+        manager: MarketplaceManager,
+    }
+
+    fn test_manager() -> TestManager {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let manager = MarketplaceManager::new(storage.clone(), keypair.clone(), None);
+        TestManager { _temp_dir: temp_dir, storage, keypair, manager }
+    }
+
+    /// A [`test_manager`] with the channel and order of [`channel_with_order`]
+    struct OrderFixture {
+        _temp_dir: TempDir,
+        storage: Arc<MarketplaceStorage>,
+        keypair: Arc<KeyPair>,
+        manager: MarketplaceManager,
+        channel_id: Hash,
+        order: Order,
+        buyer: KeyPair,
+    }
+
+    async fn order_fixture() -> OrderFixture {
+        let TestManager { _temp_dir, storage, keypair, manager } = test_manager();
+        let (channel_id, order, buyer) = channel_with_order(&manager, &keypair).await;
+        OrderFixture { _temp_dir, storage, keypair, manager, channel_id, order, buyer }
+    }
+
+    #[tokio::test]
+    async fn test_escrow_follows_its_order() {
+        let OrderFixture { _temp_dir, manager, channel_id, order, .. } = order_fixture().await;
+
+        assert!(manager.create_escrow(&channel_id, &Hash::random(), 600, None).await.is_err());
+        let escrow_id = manager.create_escrow(&channel_id, &order.id, 600, None).await.unwrap();
+        assert!(manager.create_escrow(&channel_id, &order.id, 600, None).await.is_err());
+
+        let escrow = manager.get_escrow(&escrow_id).await.unwrap();
+        assert_eq!((escrow.order_id, escrow.channel_id), (order.id, channel_id));
+        assert_eq!((escrow.buyer, escrow.seller, escrow.amount), (order.buyer, order.seller, order.amount));
+
+        // Confirming delivery before shipping changes neither side
+        assert!(manager.confirm_delivery(&escrow_id).await.is_err());
+        assert!(manager.list_pending_updates(&channel_id).await.is_empty());
+
+        manager.fund_escrow(&escrow_id, "tx".to_string()).await.unwrap();
+        manager.ship_order(&escrow_id, None).await.unwrap();
+        manager.confirm_delivery(&escrow_id).await.unwrap();
+
+        // The escrow completes and the order completion is proposed to the buyer
+        let pending = manager.list_pending_updates(&channel_id).await;
+        assert!(matches!(
+            &pending[..],
+            [SignedStateUpdate { update: StateUpdate::UpdateOrderStatus { order_id, status: OrderStatus::Completed }, .. }]
+                if *order_id == order.id
+        ));
+        let view = manager.get_order_view(&order.id).await.unwrap();
+        assert_eq!(view.channel_id, channel_id);
+        assert_eq!(view.escrow.unwrap().status, EscrowStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_records_updates_and_escrows() {
+        let OrderFixture { _temp_dir, storage, keypair, manager, channel_id, order, .. } = order_fixture().await;
+        let escrow_id = manager.create_escrow(&channel_id, &order.id, 600, None).await.unwrap();
+        manager.fund_escrow(&escrow_id, "tx".to_string()).await.unwrap();
+
+        let (entries, broken_at) = manager.audit_log(0, 100).unwrap();
+        assert_eq!(broken_at, None);
+        assert!(entries.iter().any(|e| matches!(e.event, AuditEvent::StateUpdate { channel_id: id, .. } if id == channel_id)));
+        let statuses: Vec<_> = entries.iter()
+            .filter_map(|e| match &e.event {
+                AuditEvent::Escrow { escrow_id: id, status, .. } if *id == escrow_id => Some(status.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(statuses, vec![EscrowStatus::Created, EscrowStatus::Funded]);
+        assert_eq!(manager.audit_head().unwrap().as_ref(), entries.last());
+
+        // A range is checked against the entry before it
+        let (tail, broken_at) = manager.audit_log(1, 100).unwrap();
+        assert_eq!((tail.len(), broken_at), (entries.len() - 1, None));
+
+        // A restarted node carries on the chain where storage left it
+        let restarted = MarketplaceManager::new(storage, keypair, None);
+        restarted.audit_l1(L1Action::Checkpoint, Some(channel_id), "tx2");
+        let (all, broken_at) = restarted.audit_log(0, 100).unwrap();
+        assert_eq!((all.len(), broken_at), (entries.len() + 1, None));
+    }
+
+    #[tokio::test]
+    async fn test_failed_audit_append_marks_log_broken() {
+        let TestManager { _temp_dir, storage, keypair, manager } = test_manager();
+        let mut receiver = manager.alerts().subscribe();
+        manager.audit_l1(L1Action::Checkpoint, None, "tx1");
+
+        // Something else takes entry 1, so the manager's append of it fails
+        let head = manager.audit_head().unwrap().unwrap();
+        let event = AuditEvent::L1 { action: L1Action::Checkpoint, channel_id: None, tx_id: "other".to_string() };
+        storage.append_audit_entry(&AuditEntry::new(1, head.timestamp, event, head.hash).unwrap()).unwrap();
+        manager.audit_l1(L1Action::Checkpoint, None, "tx2");
+
+        assert_eq!(receiver.try_recv().unwrap().kind, AlertKind::AuditLogGap { seq: 1 });
+        // The stored entries still link, but the log is reported broken
+        let (entries, broken_at) = manager.audit_log(0, 100).unwrap();
+        assert_eq!((entries.len(), broken_at), (2, Some(1)));
+        assert_eq!(manager.audit_log(0, 1).unwrap().1, None);
+        assert_eq!(MarketplaceManager::new(storage, keypair, None).audit_log(1, 100).unwrap().1, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_escrows_survive_restart() {
+        let OrderFixture { _temp_dir, storage, keypair, manager, channel_id, order, .. } = order_fixture().await;
+        let escrow_id = manager.create_escrow(&channel_id, &order.id, 600, None).await.unwrap();
+        manager.fund_escrow(&escrow_id, "tx".to_string()).await.unwrap();
+        manager.ship_order(&escrow_id, Some("track".to_string())).await.unwrap();
+
+        let restarted = MarketplaceManager::new(storage.clone(), keypair, None);
+        restarted.load_channels().await.unwrap();
+        restarted.load_escrows().await.unwrap();
+        let escrow = restarted.get_escrow(&escrow_id).await.unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Shipped);
+        assert_eq!(escrow.tracking_info.as_deref(), Some("track"));
+
+        // A second escrow for the same order is still refused
+        assert!(restarted.create_escrow(&channel_id, &order.id, 600, None).await.is_err());
+
+        assert!(storage.delete_escrow(&escrow_id).unwrap());
+        assert!(storage.load_escrow(&escrow_id).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_orders_outlive_their_channel() {
+        let OrderFixture { _temp_dir, storage, keypair, manager, channel_id, order, buyer } = order_fixture().await;
+        let mut signed = manager.update_order_status(&channel_id, order.id, OrderStatus::Confirmed).await.unwrap();
+        signed.add_signature(order.buyer, buyer.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+        storage.delete_channel(&channel_id).unwrap();
+
+        let restarted = MarketplaceManager::new(storage, keypair, None);
+        restarted.load_channels().await.unwrap();
+        restarted.load_orders().await.unwrap();
+        let view = restarted.get_order_view(&order.id).await.unwrap();
+        assert_eq!(view.channel_id, channel_id);
+        assert_eq!(view.order.status, OrderStatus::Confirmed);
+    }
+
+    #[tokio::test]
+    async fn test_load_orders_backfills_from_channel_state() {
+        let OrderFixture { _temp_dir, storage, keypair, channel_id, order, .. } = order_fixture().await;
+
+        // An order from before orders were stored apart is only in its channel
+        let legacy = Order { id: Hash::random(), ..order };
+        let mut channel = storage.load_channel(&channel_id).unwrap().unwrap();
+        channel.state.orders.push(legacy.clone());
+        storage.store_channel(&channel).unwrap();
+        let stored = |id: Hash| storage.load_orders().unwrap().iter().any(|(_, order)| order.id == id);
+        assert!(!stored(legacy.id));
+
+        MarketplaceManager::new(storage.clone(), keypair.clone(), None).load_orders().await.unwrap();
+        assert!(stored(legacy.id));
+
+        // So it is still listed once the channel is gone
+        storage.delete_channel(&channel_id).unwrap();
+        let restarted = MarketplaceManager::new(storage.clone(), keypair, None);
+        restarted.load_channels().await.unwrap();
+        restarted.load_orders().await.unwrap();
+        assert!(restarted.list_all_orders().await.iter().any(|(id, order)| *id == channel_id && order.id == legacy.id));
+    }
+
+    #[tokio::test]
+    async fn test_escrow_funded_from_channel_balance() {
+        let OrderFixture { _temp_dir, manager, channel_id, order, buyer, .. } = order_fixture().await;
+        let escrow_id = manager.create_escrow(&channel_id, &order.id, 600, None).await.unwrap();
+
+        let mut signed = manager.fund_escrow_from_channel(&escrow_id).await.unwrap();
+        assert!(manager.fund_escrow_from_channel(&escrow_id).await.is_err());
+
+        // Not funded until the counterparty co-signs the lock
+        assert_eq!(manager.get_escrow(&escrow_id).await.unwrap().status, EscrowStatus::Created);
+        signed.add_signature(order.buyer, buyer.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+
+        let escrow = manager.get_escrow(&escrow_id).await.unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Funded);
+        assert!(escrow.funded_in_channel && escrow.l1_tx_id.is_none());
+        assert_eq!(manager.get_balance(&channel_id, &order.buyer).await.unwrap(), Amount::new(500));
+
+        // The locked amount would be lost to a close
+        assert!(matches!(manager.close_channel(&channel_id).await, Err(L2Error::InvalidParameter(_))));
+
+        // Delivery pays the seller out of the lock
+        manager.ship_order(&escrow_id, None).await.unwrap();
+        manager.confirm_delivery(&escrow_id).await.unwrap();
+        let mut signed = manager.list_pending_updates(&channel_id).await.remove(0);
+        signed.add_signature(order.buyer, buyer.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+        assert_eq!(manager.get_balance(&channel_id, &order.buyer).await.unwrap(), Amount::new(500));
+        assert_eq!(manager.get_balance(&channel_id, &order.seller).await.unwrap(), Amount::new(1500));
+    }
+
+    #[tokio::test]
+    async fn test_unshipped_escrow_refunded_after_deadline() {
+        use tari_l2_common::clock::ManualClock;
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let seller = KeyPair::generate();
+        let (buyer_pk, seller_pk) = (keypair.public_key(), seller.public_key());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let manager = MarketplaceManager::new(storage, keypair, None).with_clock(clock.clone());
+
+        let channel_id = manager.create_channel(ChannelConfig {
+            participants: vec![buyer_pk, seller_pk],
+            initial_balances: [(buyer_pk, Amount::new(1000)), (seller_pk, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+        let cosign = |mut signed: SignedStateUpdate| {
+            signed.add_signature(seller_pk, seller.sign(&signed.signing_message()));
+            signed
+        };
+        let listing = Listing {
+            id: Hash::random(),
+            seller: seller_pk,
+            title: "Lamp".to_string(),
+            description: String::new(),
+            price: Amount::new(500),
+            ipfs_hash: String::new(),
+            active: true,
+            category: "home".to_string(),
+            fiat_reference: None,
+        };
+        let order = Order {
+            id: Hash::random(),
+            listing_id: listing.id,
+            buyer: buyer_pk,
+            seller: seller_pk,
+            amount: Amount::new(500),
+            status: OrderStatus::Pending,
+        };
+        let signed = manager.create_listing(&channel_id, listing).await.unwrap();
+        manager.apply_state_update(&channel_id, cosign(signed)).await.unwrap();
+        let signed = manager.create_order(&channel_id, order.clone()).await.unwrap();
+        manager.apply_state_update(&channel_id, cosign(signed)).await.unwrap();
+
+        let escrow_id = manager.create_escrow(&channel_id, &order.id, 600, Some(3600)).await.unwrap();
+        let signed = manager.fund_escrow_from_channel(&escrow_id).await.unwrap();
+        manager.apply_state_update(&channel_id, cosign(signed)).await.unwrap();
+        assert_eq!(manager.get_balance(&channel_id, &buyer_pk).await.unwrap(), Amount::new(500));
+
+        clock.advance(3599);
+        assert!(manager.refund_unshipped(&escrow_id).await.is_err());
+        assert!(manager.process_escrow_timeouts().await.unwrap().is_empty());
+
+        // Past the deadline the refund goes through without the seller
+        clock.advance(1);
+        assert_eq!(manager.process_escrow_timeouts().await.unwrap(), vec![escrow_id]);
+        assert_eq!(manager.get_escrow(&escrow_id).await.unwrap().status, EscrowStatus::Refunded);
+
+        let signed = manager.list_pending_updates(&channel_id).await.remove(0);
+        assert!(matches!(signed.update, StateUpdate::UpdateOrderStatus { status: OrderStatus::Cancelled, .. }));
+        manager.apply_state_update(&channel_id, cosign(signed)).await.unwrap();
+        assert_eq!(manager.get_balance(&channel_id, &buyer_pk).await.unwrap(), Amount::new(1000));
+    }
+
+    #[tokio::test]
+    async fn test_unshipped_refund_reclaimed_on_l1_without_the_seller() {
+        use tari_l2_common::clock::ManualClock;
+        use tari_l2_l1_client::{L1Config, TariL1Client};
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let seller = KeyPair::generate();
+        let (buyer_pk, seller_pk) = (keypair.public_key(), seller.public_key());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let l1 = Arc::new(TariL1Client::offline(L1Config::default()));
+        let manager = MarketplaceManager::new(storage, keypair, Some(l1)).with_clock(clock.clone());
+
+        let config = ChannelConfig {
+            participants: vec![buyer_pk, seller_pk],
+            initial_balances: [(buyer_pk, Amount::new(1000)), (seller_pk, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        };
+        let genesis = MarketplaceChannel::new(config.clone()).genesis_signing_message();
+        let channel_id = manager.open_cosigned_channel(config, seller_pk, seller.sign(&genesis)).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+        let cosign = |mut signed: SignedStateUpdate| {
+            signed.add_signature(seller_pk, seller.sign(&signed.signing_message()));
+            signed
+        };
+        let listing = Listing {
+            id: Hash::random(),
+            seller: seller_pk,
+            title: "Lamp".to_string(),
+            description: String::new(),
+            price: Amount::new(500),
+            ipfs_hash: String::new(),
+            active: true,
+            category: "home".to_string(),
+            fiat_reference: None,
+        };
+        let order = Order {
+            id: Hash::random(),
+            listing_id: listing.id,
+            buyer: buyer_pk,
+            seller: seller_pk,
+            amount: Amount::new(500),
+            status: OrderStatus::Pending,
+        };
+        let signed = manager.create_listing(&channel_id, listing).await.unwrap();
+        manager.apply_state_update(&channel_id, cosign(signed)).await.unwrap();
+        let signed = manager.create_order(&channel_id, order.clone()).await.unwrap();
+        manager.apply_state_update(&channel_id, cosign(signed)).await.unwrap();
+        let escrow_id = manager.create_escrow(&channel_id, &order.id, 600, Some(3600)).await.unwrap();
+        let signed = manager.fund_escrow_from_channel(&escrow_id).await.unwrap();
+        manager.apply_state_update(&channel_id, cosign(signed)).await.unwrap();
+
+        // The seller neither ships nor co-signs the cancellation
+        clock.advance(3600);
+        assert_eq!(manager.process_escrow_timeouts().await.unwrap(), vec![escrow_id]);
+        clock.advance(UNSHIPPED_REFUND_GRACE - 1);
+        manager.process_escrow_timeouts().await.unwrap();
+        assert_eq!(manager.get_channel_info(&channel_id).await.unwrap().status, ChannelStatus::Active);
+
+        // After the grace period the buyer takes the channel to L1 alone,
+        // where the lock settles back to them
+        clock.advance(1);
+        manager.process_escrow_timeouts().await.unwrap();
+        assert_eq!(manager.get_channel_info(&channel_id).await.unwrap().status, ChannelStatus::Challenged);
+        clock.advance(3600);
+        assert_eq!(manager.resolve_disputes().await.unwrap(), vec![channel_id]);
+        let receipt = manager.get_receipt(&channel_id).unwrap().unwrap();
+        let ReceiptKind::ChannelClose { balances } = &receipt.terms.kind else {
+            panic!("expected a channel receipt");
+        };
+        assert!(balances.contains(&(buyer_pk, 1000)));
+    }
+
+    #[tokio::test]
+    async fn test_l1_funded_escrow_paid_out_on_settlement() {
+        use tari_l2_l1_client::{L1Config, TariL1Client};
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let seller = KeyPair::generate();
+        let (buyer_pk, seller_pk) = (keypair.public_key(), seller.public_key());
+        let l1 = Arc::new(TariL1Client::offline(L1Config::default()));
+        let manager = MarketplaceManager::new(storage, keypair, Some(l1.clone()));
+
+        let channel_id = manager.create_channel(ChannelConfig {
+            participants: vec![buyer_pk, seller_pk],
+            initial_balances: [(buyer_pk, Amount::new(1000)), (seller_pk, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+        let order = Order {
+            id: Hash::random(),
+            listing_id: Hash::random(),
+            buyer: buyer_pk,
+            seller: seller_pk,
+            amount: Amount::new(500),
+            status: OrderStatus::Pending,
+        };
+        let mut signed = manager.create_order(&channel_id, order.clone()).await.unwrap();
+        signed.add_signature(seller_pk, seller.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+
+        let escrow_id = manager.create_escrow(&channel_id, &order.id, 600, None).await.unwrap();
+        manager.fund_escrow(&escrow_id, "mock_deposit".to_string()).await.unwrap();
+        manager.ship_order(&escrow_id, None).await.unwrap();
+
+        // Nowhere to send the funds yet, so delivery cannot be confirmed
+        assert!(matches!(manager.confirm_delivery(&escrow_id).await, Err(L2Error::InvalidParameter(_))));
+        assert_eq!(manager.get_escrow(&escrow_id).await.unwrap().status, EscrowStatus::Shipped);
+        assert!(manager.set_escrow_payout_address(&escrow_id, &KeyPair::generate().public_key(), "addr".to_string()).await.is_err());
+
+        // Nor while L1 is down
+        manager.set_escrow_payout_address(&escrow_id, &seller_pk, "seller_addr".to_string()).await.unwrap();
+        l1.set_outage(true);
+        assert!(manager.confirm_delivery(&escrow_id).await.is_err());
+        assert!(manager.get_escrow(&escrow_id).await.unwrap().payout_tx_id.is_none());
+
+        l1.set_outage(false);
+        manager.confirm_delivery(&escrow_id).await.unwrap();
+        let escrow = manager.get_escrow(&escrow_id).await.unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Completed);
+        assert!(escrow.payout_tx_id.unwrap().starts_with("mock_release_tx_"));
+
+        // The deposit paid the seller, so the order completes without
+        // charging the buyer again in the channel
+        for mut signed in manager.list_pending_updates(&channel_id).await {
+            signed.add_signature(seller_pk, seller.sign(&signed.signing_message()));
+            manager.apply_state_update(&channel_id, signed).await.unwrap();
+        }
+        assert_eq!(manager.get_order_view(&order.id).await.unwrap().order.status, OrderStatus::Completed);
+        assert_eq!(manager.get_balance(&channel_id, &buyer_pk).await.unwrap(), Amount::new(1000));
+        assert_eq!(manager.get_balance(&channel_id, &seller_pk).await.unwrap(), Amount::new(1000));
+
+        // A deposit pays out once
+        assert!(l1.release_escrow("mock_deposit".to_string(), "seller_addr".to_string(), 500).await.is_err());
+    }
+
+    #[test]
+    fn test_escrow_claim_is_exclusive() {
+        let claimed = Arc::new(std::sync::Mutex::new(HashSet::new()));
+        let escrow_id = Hash::random();
+
+        let claim = EscrowClaim::new(&claimed, escrow_id).unwrap();
+        assert!(EscrowClaim::new(&claimed, escrow_id).is_err());
+        drop(claim);
+        assert!(EscrowClaim::new(&claimed, escrow_id).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_escrow_payout_follows_payee_preference() {
+        use tari_l2_l1_client::{L1Config, TariL1Client};
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let seller = KeyPair::generate();
+        let (buyer_pk, seller_pk) = (keypair.public_key(), seller.public_key());
+        let l1 = Arc::new(TariL1Client::offline(L1Config::default()));
+        let manager = MarketplaceManager::new(storage, keypair, Some(l1));
+
+        let channel_id = manager.create_channel(ChannelConfig {
+            participants: vec![buyer_pk, seller_pk],
+            initial_balances: [(buyer_pk, Amount::new(1000)), (seller_pk, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+
+        let mut escrow_ids = Vec::new();
+        for i in 0..2 {
+            let order = Order {
+                id: Hash::random(),
+                listing_id: Hash::random(),
+                buyer: buyer_pk,
+                seller: seller_pk,
+                amount: Amount::new(200),
+                status: OrderStatus::Pending,
+            };
+            let mut signed = manager.create_order(&channel_id, order.clone()).await.unwrap();
+            signed.add_signature(seller_pk, seller.sign(&signed.signing_message()));
+            manager.apply_state_update(&channel_id, signed).await.unwrap();
+
+            let escrow_id = manager.create_escrow(&channel_id, &order.id, 600, None).await.unwrap();
+            manager.fund_escrow(&escrow_id, format!("mock_deposit_{}", i)).await.unwrap();
+            manager.ship_order(&escrow_id, None).await.unwrap();
+            escrow_ids.push(escrow_id);
+        }
+
+        // An on-chain preference is paid at its address without one on the escrow
+        manager.set_payout_preference(PayoutPreference {
+            public_key: seller_pk,
+            address: Some("seller_addr".to_string()),
+            method: PayoutMethod::OnChain,
+            updated_at: Timestamp::from_secs(1),
+        }).unwrap();
+        manager.confirm_delivery(&escrow_ids[0]).await.unwrap();
+        let escrow = manager.get_escrow(&escrow_ids[0]).await.unwrap();
+        assert!(escrow.payout_tx_id.unwrap().starts_with("mock_release_tx_"));
+
+        // An escrow funded on L1 cannot pay out in the channel, so an
+        // in-channel preference without an address leaves it unsettled
+        // rather than charging the buyer or this node there
+        manager.set_payout_preference(PayoutPreference {
+            public_key: seller_pk,
+            address: None,
+            method: PayoutMethod::InChannel,
+            updated_at: Timestamp::from_secs(2),
+        }).unwrap();
+        assert_eq!(manager.payout_preference(&seller_pk).unwrap().unwrap().method, PayoutMethod::InChannel);
+        assert!(matches!(manager.confirm_delivery(&escrow_ids[1]).await, Err(L2Error::InvalidParameter(_))));
+        assert_eq!(manager.get_escrow(&escrow_ids[1]).await.unwrap().status, EscrowStatus::Shipped);
+
+        manager.set_payout_preference(PayoutPreference {
+            public_key: seller_pk,
+            address: Some("seller_addr".to_string()),
+            method: PayoutMethod::InChannel,
+            updated_at: Timestamp::from_secs(3),
+        }).unwrap();
+        manager.confirm_delivery(&escrow_ids[1]).await.unwrap();
+        let escrow = manager.get_escrow(&escrow_ids[1]).await.unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Completed);
+        assert!(escrow.payout_tx_id.unwrap().starts_with("mock_release_tx_"));
+    }
+
+    #[tokio::test]
+    async fn test_idle_channels_hibernate_and_wake() {
+        use tari_l2_common::clock::ManualClock;
+
+        const WEEK: u64 = 7 * 24 * 3600;
+        let temp_dir = TempDir::new().unwrap();
+        let keypair = Arc::new(KeyPair::generate());
+        let peer = KeyPair::generate();
+        let (a, b) = (keypair.public_key(), peer.public_key());
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let manager = MarketplaceManager::new(storage.clone(), keypair.clone(), None)
+            .with_clock(clock.clone());
+        manager.set_hibernate_after(Some(WEEK)).await;
+
+        let channel_id = manager.create_channel(ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1000)), (b, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+        let transfer = StateUpdate::Transfer { from: a, to: b, amount: Amount::new(10) };
+        let mut signed = manager.create_state_update(&channel_id, transfer).await.unwrap();
+        signed.add_signature(b, peer.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+
+        assert!(manager.hibernate_idle_channels().await.unwrap().is_empty());
+        clock.advance(WEEK);
+        assert_eq!(manager.hibernate_idle_channels().await.unwrap(), vec![channel_id]);
+        assert_eq!(manager.hibernated_count().await, 1);
+        assert_eq!(manager.list_channels().await.len(), 1);
+
+        // Any reference loads it back, and it is not evicted again right away
+        assert_eq!(manager.get_balance(&channel_id, &b).await.unwrap(), Amount::new(1010));
+        assert_eq!(manager.hibernated_count().await, 0);
+        assert!(manager.hibernate_idle_channels().await.unwrap().is_empty());
+
+        // A restarted node leaves channels that were already idle on disk
+        let restarted = MarketplaceManager::new(storage, keypair, None).with_clock(clock);
+        restarted.set_hibernate_after(Some(WEEK)).await;
+        restarted.load_channels().await.unwrap();
+        assert_eq!(restarted.hibernated_count().await, 1);
+        assert_eq!(restarted.get_channel_info(&channel_id).await.unwrap().nonce, 1);
+    }
+
+    #[tokio::test]
+    async fn test_hibernated_channel_counts_and_wakes_for_peers() {
+        use tari_l2_common::clock::ManualClock;
+        use tari_l2_p2p::L2Message;
+
+        const WEEK: u64 = 7 * 24 * 3600;
+        let temp_dir = TempDir::new().unwrap();
+        let keypair = Arc::new(KeyPair::generate());
+        let peer = KeyPair::generate();
+        let (a, b) = (keypair.public_key(), peer.public_key());
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let manager = MarketplaceManager::new(storage.clone(), keypair, None).with_clock(clock.clone());
+        manager.set_hibernate_after(Some(WEEK)).await;
+
+        let channel_id = manager.create_channel(ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1000)), (b, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+        let transfer = StateUpdate::Transfer { from: a, to: b, amount: Amount::new(10) };
+        let mut signed = manager.create_state_update(&channel_id, transfer).await.unwrap();
+        signed.add_signature(b, peer.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+        clock.advance(WEEK);
+        assert_eq!(manager.hibernate_idle_channels().await.unwrap(), vec![channel_id]);
+
+        // A hibernated channel still counts toward the cap
+        manager.set_limits(ResourceLimits { max_channels: 1, ..Default::default() }).await;
+        let other = KeyPair::generate().public_key();
+        assert!(manager.create_channel(ChannelConfig {
+            participants: vec![a, other],
+            initial_balances: [(a, Amount::new(1000)), (other, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.is_err());
+
+        // A peer catching up loads it back
+        let update = manager.applied_update(&channel_id, 1).await.unwrap();
+        assert_eq!(update.nonce, 1);
+        assert_eq!(manager.hibernated_count().await, 0);
+
+        // So does a queued proposal, which is kept for retry rather than
+        // dropped as if the channel were gone
+        clock.advance(WEEK);
+        assert_eq!(manager.hibernate_idle_channels().await.unwrap(), vec![channel_id]);
+        let message = L2Message::StateUpdateProposal { channel_id, update };
+        storage.store_outbox_entry(&OutboxEntry::new(b, message, clock.now())).unwrap();
+        assert_eq!(manager.flush_outbox().await.unwrap(), 0);
+        assert_eq!(manager.outbox_len().unwrap(), 1);
+        assert_eq!(manager.hibernated_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_busy_channel_does_not_block_others() {
+        use std::time::Duration;
+
+        let temp_dir = TempDir::new().unwrap();
+        let keypair = Arc::new(KeyPair::generate());
+        let (other, peer) = (KeyPair::generate(), KeyPair::generate());
+        let (a, b) = (keypair.public_key(), peer.public_key());
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let manager = MarketplaceManager::new(storage, keypair, None);
+
+        let mut ids = Vec::new();
+        for (counterparty, collateral) in [(other.public_key(), 1000), (b, 2000)] {
+            let channel_id = manager.create_channel(ChannelConfig {
+                participants: vec![a, counterparty],
+                initial_balances: [(a, Amount::new(collateral)), (counterparty, Amount::new(collateral))].into_iter().collect(),
+                challenge_period: 3600,
+            }).await.unwrap();
+            manager.activate_channel(&channel_id).await.unwrap();
+            ids.push(channel_id);
+        }
+
+        // Hold the first channel's lock as a long-running update would
+        let busy = manager.channel(&ids[0]).await.unwrap();
+        let _guard = busy.lock().await;
+
+        let transfer = StateUpdate::Transfer { from: a, to: b, amount: Amount::new(10) };
+        let mut signed = tokio::time::timeout(Duration::from_secs(1), manager.create_state_update(&ids[1], transfer))
+            .await
+            .expect("other channel not blocked")
+            .unwrap();
+        signed.add_signature(b, peer.sign(&signed.signing_message()));
+        tokio::time::timeout(Duration::from_secs(1), manager.apply_state_update(&ids[1], signed))
+            .await
+            .expect("other channel not blocked")
+            .unwrap();
+        assert_eq!(manager.get_balance(&ids[1], &b).await.unwrap(), Amount::new(2010));
+
+        // The busy channel's own callers wait for it
+        assert!(tokio::time::timeout(Duration::from_millis(50), manager.get_balance(&ids[0], &a)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_spending_limits_checked_before_signing() {
+        let temp_dir = TempDir::new().unwrap();
+        let keypair = Arc::new(KeyPair::generate());
+        let peer = KeyPair::generate();
+        let (a, b) = (keypair.public_key(), peer.public_key());
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let manager = MarketplaceManager::new(storage.clone(), keypair.clone(), None);
+
+        let channel_id = manager.create_channel(ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1000)), (b, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+        manager.set_spending_limits(&channel_id, SpendingLimits { max_transfer: Some(100), max_daily: Some(150) })
+            .await.unwrap();
+
+        let transfer = |from, to, amount| StateUpdate::Transfer { from, to, amount: Amount::new(amount) };
+        assert!(matches!(
+            manager.create_state_update(&channel_id, transfer(a, b, 101)).await,
+            Err(L2Error::ResourceLimitExceeded(_))
+        ));
+        let mut signed = manager.create_state_update(&channel_id, transfer(a, b, 100)).await.unwrap();
+        signed.add_signature(b, peer.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+        assert!(manager.create_state_update(&channel_id, transfer(a, b, 51)).await.is_err());
+
+        // Incoming funds are not limited
+        manager.create_state_update(&channel_id, transfer(b, a, 500)).await.unwrap();
+        assert_eq!(manager.spending_limits(&channel_id).await.1, 100);
+
+        // Limits survive a restart
+        let restarted = MarketplaceManager::new(storage, keypair, None);
+        restarted.load_channels().await.unwrap();
+        assert_eq!(restarted.spending_limits(&channel_id).await.0.max_daily, Some(150));
+    }
+
+    #[tokio::test]
+    async fn test_proposals_spending_our_funds_wait_for_approval() {
+        let dirs: Vec<TempDir> = (0..2).map(|_| TempDir::new().unwrap()).collect();
+        let keys: Vec<Arc<KeyPair>> = (0..2).map(|_| Arc::new(KeyPair::generate())).collect();
+        let managers: Vec<MarketplaceManager> = dirs.iter().zip(&keys)
+            .map(|(dir, kp)| MarketplaceManager::new(Arc::new(MarketplaceStorage::open(dir.path()).unwrap()), kp.clone(), None))
+            .collect();
+        let (proposer, counterparty) = (&managers[0], &managers[1]);
+        let (a, b) = (keys[0].public_key(), keys[1].public_key());
+
+        let config = ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1000)), (b, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        };
+        let channel_id = MarketplaceChannel::compute_id(&config.participants);
+        for manager in &managers {
+            manager.create_channel(config.clone()).await.unwrap();
+            manager.activate_channel(&channel_id).await.unwrap();
+        }
+
+        // A proposal paying the proposer more of our balance than the
+        // limits allow is held, and a redelivered copy is held once
+        counterparty.set_default_spending_limits(SpendingLimits { max_transfer: Some(500), max_daily: None }).await;
+        let transfer = |from, to, amount| StateUpdate::Transfer { from, to, amount: Amount::new(amount) };
+        let proposal = proposer.create_state_update(&channel_id, transfer(b, a, 1000)).await.unwrap();
+        counterparty.receive_state_update(&channel_id, proposal.clone()).await.unwrap();
+        counterparty.receive_state_update(&channel_id, proposal).await.unwrap();
+        assert!(counterparty.list_pending_updates(&channel_id).await.is_empty());
+        assert_eq!(counterparty.list_awaiting_approval(&channel_id).await.len(), 1);
+
+        let approved = counterparty.approve_update(&channel_id, 1).await.unwrap();
+        assert!(counterparty.list_awaiting_approval(&channel_id).await.is_empty());
+        let committed = proposer.handle_state_update_ack(&channel_id, 1, approved.signatures[&b].clone())
+            .await.unwrap().unwrap();
+        counterparty.receive_state_update(&channel_id, committed).await.unwrap();
+        assert_eq!(counterparty.get_balance(&channel_id, &b).await.unwrap(), Amount::new(0));
+
+        // Within limits the operator set, such proposals are signed unattended
+        counterparty.set_spending_limits(&channel_id, SpendingLimits { max_transfer: Some(50), max_daily: None })
+            .await.unwrap();
+        let refund = proposer.create_state_update(&channel_id, transfer(a, b, 100)).await.unwrap();
+        let mut refund_signed = refund.clone();
+        refund_signed.add_signature(b, keys[1].sign(&refund.signing_message()));
+        proposer.apply_state_update(&channel_id, refund_signed.clone()).await.unwrap();
+        counterparty.receive_state_update(&channel_id, refund_signed).await.unwrap();
+
+        let small = proposer.create_state_update(&channel_id, transfer(b, a, 40)).await.unwrap();
+        counterparty.receive_state_update(&channel_id, small.clone()).await.unwrap();
+        assert_eq!(counterparty.list_pending_updates(&channel_id).await.len(), 1);
+        assert!(counterparty.list_awaiting_approval(&channel_id).await.is_empty());
+        assert_eq!(counterparty.spending_limits(&channel_id).await.1, 1040);
+
+        // A redelivered proposal is not counted again
+        counterparty.receive_state_update(&channel_id, small).await.unwrap();
+        assert_eq!(counterparty.spending_limits(&channel_id).await.1, 1040);
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_verifies_anchored_root() {
+        use tari_l2_l1_client::{L1Config, OfflineChain, TariL1Client};
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let peer = KeyPair::generate();
+        let (a, b) = (keypair.public_key(), peer.public_key());
+        let chain = Arc::new(OfflineChain::default());
+        let l1 = Arc::new(TariL1Client::offline_on(L1Config::default(), chain.clone()));
+        let manager = MarketplaceManager::new(storage, keypair, Some(l1.clone()));
+
+        // The counterparty's node, with its own storage and L1 client
+        let peer_dir = TempDir::new().unwrap();
+        let counterparty = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(peer_dir.path()).unwrap()),
+            Arc::new(KeyPair::generate()),
+            Some(Arc::new(TariL1Client::offline_on(L1Config::default(), chain))),
+        );
+
+        let channel_id = manager.create_channel(ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1000)), (b, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+        let anchored = manager.channel(&channel_id).await.unwrap().lock().await.get_state_root();
+        assert!(!manager.verify_checkpoint(&channel_id, &anchored).await.unwrap());
+
+        assert_eq!(manager.checkpoint_channels().await.unwrap(), 1);
+        assert!(manager.verify_checkpoint(&channel_id, &anchored).await.unwrap());
+        assert!(counterparty.verify_checkpoint(&channel_id, &anchored).await.unwrap());
+
+        // A later state is not covered until it is checkpointed too
+        let mut signed = manager.transfer(&channel_id, a, b, Amount::new(10)).await.unwrap();
+        signed.add_signature(b, peer.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+        let latest = manager.channel(&channel_id).await.unwrap().lock().await.get_state_root();
+        assert!(!manager.verify_checkpoint(&channel_id, &latest).await.unwrap());
+        assert!(manager.verify_checkpoint(&channel_id, &anchored).await.unwrap());
+
+        // A root on another channel's anchor does not count
+        assert!(!counterparty.verify_checkpoint(&Hash::random(), &anchored).await.unwrap());
+
+        l1.set_outage(true);
+        assert!(manager.verify_checkpoint(&channel_id, &anchored).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_policy_skips_channels_not_due() {
+        use tari_l2_common::clock::ManualClock;
+        use tari_l2_l1_client::{L1Config, TariL1Client};
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let peer = KeyPair::generate();
+        let (a, b) = (keypair.public_key(), peer.public_key());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_000_000)));
+        let l1 = Arc::new(TariL1Client::offline(L1Config::default()));
+        let manager = MarketplaceManager::new(storage, keypair, Some(l1))
+            .with_clock(clock.clone());
+        manager.set_checkpoint_policy(CheckpointPolicy { interval: 3600, every_updates: Some(2) }).await;
+
+        let channel_id = manager.create_channel(ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1000)), (b, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+
+        assert_eq!(manager.checkpoint_channels().await.unwrap(), 1);
+        let info = manager.get_channel_info(&channel_id).await.unwrap();
+        assert!(info.last_checkpoint_height.is_some());
+
+        // Nothing new to anchor
+        assert_eq!(manager.checkpoint_channels().await.unwrap(), 0);
+
+        // One update waits for the interval, a second reaches the update count
+        let transfer = || async {
+            let mut signed = manager.transfer(&channel_id, a, b, Amount::new(10)).await.unwrap();
+            signed.add_signature(b, peer.sign(&signed.signing_message()));
+            manager.apply_state_update(&channel_id, signed).await.unwrap();
+        };
+        transfer().await;
+        assert_eq!(manager.checkpoint_channels().await.unwrap(), 0);
+        transfer().await;
+        assert_eq!(manager.checkpoint_channels().await.unwrap(), 1);
+
+        transfer().await;
+        clock.advance(3599);
+        assert_eq!(manager.checkpoint_channels().await.unwrap(), 0);
+        clock.advance(1);
+        assert_eq!(manager.checkpoint_channels().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_challenged_close_resolved_on_l1() {
+        use tari_l2_common::clock::ManualClock;
+        use tari_l2_l1_client::{L1Config, TariL1Client};
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let peer = KeyPair::generate();
+        let (a, b) = (keypair.public_key(), peer.public_key());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let l1 = Arc::new(TariL1Client::offline(L1Config::default()));
+        let manager = MarketplaceManager::new(storage, keypair, Some(l1.clone())).with_clock(clock.clone());
+
+        let config = ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1000)), (b, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        };
+        let genesis = MarketplaceChannel::new(config.clone()).genesis_signing_message();
+        let channel_id = manager.open_cosigned_channel(config, b, peer.sign(&genesis)).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+        let mut signed = manager.transfer(&channel_id, a, b, Amount::new(300)).await.unwrap();
+        signed.add_signature(b, peer.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+
+        manager.close_channel(&channel_id).await.unwrap();
+        manager.challenge_close(&channel_id).await.unwrap();
+        let dispute = l1.get_dispute(&channel_id.to_string()).await.unwrap().unwrap();
+        assert_eq!(dispute.best.nonce, 1);
+
+        // Nothing is paid out while counter-proofs can still arrive
+        assert!(manager.resolve_disputes().await.unwrap().is_empty());
+        clock.advance(3600);
+        assert_eq!(manager.resolve_disputes().await.unwrap(), vec![channel_id]);
+
+        let info = manager.get_channel_info(&channel_id).await.unwrap();
+        assert_eq!(info.status, ChannelStatus::Closed);
+        let resolution = l1.get_dispute(&channel_id.to_string()).await.unwrap().unwrap().resolution.unwrap();
+        assert_eq!(resolution.payouts[&a.to_string()], 700);
+        assert_eq!(resolution.payouts[&b.to_string()], 1300);
+        assert!(l1.get_locked_collateral(&channel_id.to_string()).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reserve_proofs_checked_against_l1_collateral() {
+        use tari_l2_l1_client::{L1Config, TariL1Client};
+
+        let dirs: Vec<TempDir> = (0..2).map(|_| TempDir::new().unwrap()).collect();
+        let keypair = Arc::new(KeyPair::generate());
+        let (a, b) = (keypair.public_key(), KeyPair::generate().public_key());
+        let l1 = Arc::new(TariL1Client::offline(L1Config::default()));
+        let seller = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(dirs[0].path()).unwrap()), keypair.clone(), Some(l1.clone()),
+        );
+        let channel_id = seller.create_channel(ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1500)), (b, Amount::new(500))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+
+        let proof = seller.prove_reserves(b"challenge".to_vec(), channel_id).await.unwrap();
+        assert_eq!(proof.amount, 2000);
+        seller.verify_reserve_proof(&proof, b"challenge", Amount::new(1500)).await.unwrap();
+
+        // A validly signed claim above the lock on L1 is refused
+        let mut inflated = proof.clone();
+        inflated.amount = 5000;
+        inflated.signature = keypair.sign(&ReserveProof::signing_message(
+            &inflated.source, inflated.amount, inflated.block_height, &inflated.nonce, inflated.timestamp,
+        ));
+        assert!(matches!(
+            seller.verify_reserve_proof(&inflated, b"challenge", Amount::new(1500)).await,
+            Err(L2Error::InsufficientBalance { .. })
+        ));
+
+        // Without L1 there is nothing to check a claim against
+        let offline = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(dirs[1].path()).unwrap()), Arc::new(KeyPair::generate()), None,
+        );
+        assert!(matches!(
+            offline.verify_reserve_proof(&proof, b"challenge", Amount::new(1500)).await,
+            Err(L2Error::TariConnectionError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_watchtower_holds_newest_blob() {
+        let temp_dir = TempDir::new().unwrap();
+        let keypair = Arc::new(KeyPair::generate());
+        let peer = KeyPair::generate();
+        let (a, b) = (keypair.public_key(), peer.public_key());
+        let client = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(temp_dir.path().join("client")).unwrap()), keypair, None,
+        );
+        let tower = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(temp_dir.path().join("tower")).unwrap()),
+            Arc::new(KeyPair::generate()),
+            None,
+        );
+
+        let channel_id = client.create_channel(ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1000)), (b, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+        client.activate_channel(&channel_id).await.unwrap();
+        let old = client.export_watch_blob(&channel_id).await.unwrap();
+        assert!(tower.accept_watch_blob(old.clone()).await.is_err());
+
+        tower.set_watchtower(Some(10)).await;
+        tower.accept_watch_blob(old.clone()).await.unwrap();
+
+        let mut signed = client.create_state_update(&channel_id, StateUpdate::Transfer {
+            from: a, to: b, amount: Amount::new(10),
+        }).await.unwrap();
+        signed.add_signature(b, peer.sign(&signed.signing_message()));
+        client.apply_state_update(&channel_id, signed).await.unwrap();
+        let newest = client.export_watch_blob(&channel_id).await.unwrap();
+        tower.accept_watch_blob(newest.clone()).await.unwrap();
+        assert!(tower.accept_watch_blob(old).await.is_err());
+
+        // A close at the newest state needs no challenge; an older one does,
+        // which fails here only for want of an L1 client
+        assert!(tower.respond_to_close(&channel_id, newest.nonce).await.unwrap().is_empty());
+        assert!(matches!(
+            tower.respond_to_close(&channel_id, newest.nonce - 1).await,
+            Err(L2Error::TariConnectionError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_force_close_settles_on_l1_at_the_latest_state() {
+        use tari_l2_common::clock::ManualClock;
+        use tari_l2_l1_client::{L1Config, OfflineChain, TariL1Client};
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let local = keypair.public_key();
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let chain = Arc::new(OfflineChain::default());
+        let l1 = Arc::new(TariL1Client::offline_on(L1Config::default(), chain.clone()));
+        let counterparty_l1 = TariL1Client::offline_on(L1Config::default(), chain);
+        let manager = MarketplaceManager::new(storage, keypair, Some(l1.clone())).with_clock(clock.clone());
+
+        // Two channels, each one transfer past their opening state
+        let mut opened = Vec::new();
+        for _ in 0..2 {
+            let peer = KeyPair::generate();
+            let config = ChannelConfig {
+                participants: vec![local, peer.public_key()],
+                initial_balances: [(local, Amount::new(1000)), (peer.public_key(), Amount::new(1000))].into_iter().collect(),
+                challenge_period: 3600,
+            };
+            let genesis = MarketplaceChannel::new(config.clone()).genesis_signing_message();
+            let channel_id = manager.open_cosigned_channel(config, peer.public_key(), peer.sign(&genesis)).await.unwrap();
+            manager.activate_channel(&channel_id).await.unwrap();
+            let opening = manager.export_watch_blob(&channel_id).await.unwrap().open(&channel_id).unwrap().evidence;
+
+            let mut signed = manager.transfer(&channel_id, local, peer.public_key(), Amount::new(300)).await.unwrap();
+            signed.add_signature(peer.public_key(), peer.sign(&signed.signing_message()));
+            manager.apply_state_update(&channel_id, signed).await.unwrap();
+            opened.push((channel_id, peer.public_key(), opening));
+        }
+        let (ours, theirs) = (opened[0].0, opened[1].0);
+
+        // We force-close the first with our latest state
+        let (_, deadline) = manager.force_close_channel(&ours).await.unwrap();
+        assert_eq!(deadline, Timestamp::from_secs(1_700_003_600));
+        assert_eq!(manager.get_channel_info(&ours).await.unwrap().status, ChannelStatus::Challenged);
+        assert_eq!(l1.get_dispute(&ours.to_string()).await.unwrap().unwrap().best.nonce, 1);
+        assert!(manager.force_close_channel(&ours).await.is_err());
+
+        // The counterparty force-closes the second at its opening state
+        // through its own client; we notice on chain and counter with the
+        // later one
+        counterparty_l1.submit_dispute(theirs.to_string(), opened[1].2.clone(), clock.now().as_secs()).await.unwrap();
+        assert_eq!(manager.detect_force_closes().await.unwrap(), vec![theirs]);
+        assert_eq!(manager.get_channel_info(&theirs).await.unwrap().status, ChannelStatus::Challenged);
+        assert_eq!(l1.get_dispute(&theirs.to_string()).await.unwrap().unwrap().best.nonce, 1);
+        assert!(manager.detect_force_closes().await.unwrap().is_empty());
+
+        // Funds are distributed once the challenge period has passed
+        assert!(manager.resolve_disputes().await.unwrap().is_empty());
+        clock.advance(3600);
+        let mut resolved = manager.resolve_disputes().await.unwrap();
+        resolved.sort_by_key(|id| id.to_string());
+        let mut expected = vec![ours, theirs];
+        expected.sort_by_key(|id| id.to_string());
+        assert_eq!(resolved, expected);
+        let resolution = l1.get_dispute(&theirs.to_string()).await.unwrap().unwrap().resolution.unwrap();
+        assert_eq!(resolution.payouts[&local.to_string()], 700);
+        assert_eq!(resolution.payouts[&opened[1].1.to_string()], 1300);
+    }
+
+    #[tokio::test]
+    async fn test_watchtower_challenges_stale_close_seen_on_l1() {
+        use tari_l2_common::clock::ManualClock;
+        use tari_l2_l1_client::{L1Config, OfflineChain, TariL1Client};
+
+        let temp_dir = TempDir::new().unwrap();
+        let keypair = Arc::new(KeyPair::generate());
+        let peer = KeyPair::generate();
+        let (a, b) = (keypair.public_key(), peer.public_key());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        // Client, tower and counterparty each have their own L1 client
+        let chain = Arc::new(OfflineChain::default());
+        let l1 = Arc::new(TariL1Client::offline_on(L1Config::default(), chain.clone()));
+        let peer_l1 = TariL1Client::offline_on(L1Config::default(), chain.clone());
+        let client = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(temp_dir.path().join("client")).unwrap()), keypair, Some(l1.clone()),
+        ).with_clock(clock.clone());
+        let tower = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(temp_dir.path().join("tower")).unwrap()),
+            Arc::new(KeyPair::generate()),
+            Some(Arc::new(TariL1Client::offline_on(L1Config::default(), chain))),
+        ).with_clock(clock.clone());
+        tower.set_watchtower(Some(10)).await;
+
+        let config = ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1000)), (b, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        };
+        let genesis = MarketplaceChannel::new(config.clone()).genesis_signing_message();
+        let channel_id = client.open_cosigned_channel(config, b, peer.sign(&genesis)).await.unwrap();
+        client.activate_channel(&channel_id).await.unwrap();
+        let opening = client.export_watch_blob(&channel_id).await.unwrap().open(&channel_id).unwrap().evidence;
+
+        let mut signed = client.transfer(&channel_id, a, b, Amount::new(300)).await.unwrap();
+        signed.add_signature(b, peer.sign(&signed.signing_message()));
+        client.apply_state_update(&channel_id, signed).await.unwrap();
+        tower.accept_watch_blob(client.export_watch_blob(&channel_id).await.unwrap()).await.unwrap();
+        assert!(tower.watch_l1_closes().await.unwrap().is_empty());
+
+        // A reported close is checked against L1, not taken on trust
+        assert!(matches!(tower.check_reported_close(&channel_id).await, Err(L2Error::InvalidParameter(_))));
+
+        // The counterparty closes at the opening state while the client is offline
+        peer_l1.submit_dispute(channel_id.to_string(), opening, clock.now().as_secs()).await.unwrap();
+        assert_eq!(tower.watch_l1_closes().await.unwrap().len(), 1);
+        assert_eq!(l1.get_dispute(&channel_id.to_string()).await.unwrap().unwrap().best.nonce, 1);
+
+        // Once answered, later scans and reports leave it alone
+        assert!(tower.watch_l1_closes().await.unwrap().is_empty());
+        assert!(tower.check_reported_close(&channel_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_signed_action_accepted_once() {
+        use tari_l2_common::clock::ManualClock;
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let manager = MarketplaceManager::new(storage.clone(), keypair.clone(), None)
+            .with_clock(clock.clone());
+
+        let signer = KeyPair::generate();
+        let action = SignedAction::new_at("buy".to_string(), signer.public_key(), clock.now(), |msg| signer.sign(msg)).unwrap();
+        manager.accept_action(&action).await.unwrap();
+        assert!(manager.accept_action(&action).await.is_err());
+
+        // Still refused after a restart
+        let restarted = MarketplaceManager::new(storage, keypair, None).with_clock(clock.clone());
+        restarted.load_channels().await.unwrap();
+        assert!(restarted.accept_action(&action).await.is_err());
+
+        // Too far ahead of our clock
+        let ahead = Timestamp::from_secs(clock.now().as_secs() + 60);
+        let future = SignedAction::new_at("buy".to_string(), signer.public_key(), ahead, |msg| signer.sign(msg)).unwrap();
+        assert!(restarted.accept_action(&future).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_refund_escalates() {
+        use tari_l2_common::clock::ManualClock;
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let manager = MarketplaceManager::new(storage, keypair.clone(), None)
+            .with_clock(clock.clone());
+        manager.set_arbitrators(vec![KeyPair::generate().public_key()]).await;
+
+        // This node is the buyer
+        let seller = KeyPair::generate();
+        let (buyer_pk, seller_pk) = (keypair.public_key(), seller.public_key());
+        let channel_id = manager.create_channel(ChannelConfig {
+            participants: vec![buyer_pk, seller_pk],
+            initial_balances: [(buyer_pk, Amount::new(1000)), (seller_pk, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+        let order = Order {
+            id: Hash::random(),
+            listing_id: Hash::random(),
+            buyer: buyer_pk,
+            seller: seller_pk,
+            amount: Amount::new(500),
+            status: OrderStatus::Pending,
+        };
+        let mut signed = manager.create_order(&channel_id, order.clone()).await.unwrap();
+        signed.add_signature(seller_pk, seller.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+
+        let escrow_id = manager.create_escrow(&channel_id, &order.id, 600, None).await.unwrap();
+        manager.fund_escrow(&escrow_id, "tx".to_string()).await.unwrap();
+        manager.request_refund(&escrow_id, "never arrived".to_string()).await.unwrap();
+
+        clock.advance(3600);
+        assert!(manager.escalate_stale_refunds(3600).await.unwrap().is_empty());
+
+        clock.advance(1);
+        assert_eq!(manager.escalate_stale_refunds(3600).await.unwrap(), vec![escrow_id]);
+        let escrow = manager.get_escrow(&escrow_id).await.unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Disputed);
+        assert_eq!(escrow.dispute_reason.as_deref(), Some("never arrived"));
+        assert!(matches!(
+            &manager.list_pending_updates(&channel_id).await[..],
+            [SignedStateUpdate { update: StateUpdate::UpdateOrderStatus { status: OrderStatus::Disputed, .. }, .. }]
+        ));
+
+        // Already disputed, so nothing more to escalate
+        assert!(manager.escalate_stale_refunds(3600).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_counterparty_dispute_raises_alert() {
+        let OrderFixture { _temp_dir, manager, channel_id, order, buyer, .. } = order_fixture().await;
+        let alerts = manager.alerts();
+        let mut receiver = alerts.subscribe();
+
+        let dispute = StateUpdate::UpdateOrderStatus { order_id: order.id, status: OrderStatus::Disputed };
+        let mut signed = SignedStateUpdate::new(dispute, 2);
+        signed.add_signature(buyer.public_key(), buyer.sign(&signed.signing_message()));
+        manager.receive_state_update(&channel_id, signed).await.unwrap();
+
+        let alert = receiver.try_recv().unwrap();
+        assert_eq!(alert.kind, AlertKind::DisputeOpened { channel_id, order_id: order.id, by: buyer.public_key() });
+        assert_eq!(alerts.recent(0), vec![alert]);
+    }
+
+    #[tokio::test]
+    async fn test_counterparties_resolve_by_contact_label() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let manager = MarketplaceManager::new(storage, Arc::new(KeyPair::generate()), None);
+
+        let (alice, other) = (KeyPair::generate().public_key(), KeyPair::generate().public_key());
+        manager.add_contact(Contact::new("Alice", alice, None, Timestamp::from_secs(1)).unwrap()).unwrap();
+        assert!(manager.add_contact(Contact::new("alice", other, None, Timestamp::from_secs(2)).unwrap()).is_err());
+
+        // Labels ignore case; keys still parse as keys
+        assert_eq!(manager.resolve_counterparty("ALICE").unwrap(), alice);
+        assert_eq!(manager.resolve_counterparty(&other.to_string()).unwrap(), other);
+        assert!(manager.resolve_counterparty("bob").is_err());
+
+        // Updating keeps when the contact was added
+        manager.update_contact(Contact::new("alice", other, None, Timestamp::from_secs(3)).unwrap()).unwrap();
+        let updated = manager.get_contact("alice").unwrap().unwrap();
+        assert_eq!((updated.public_key, updated.added_at), (other, Timestamp::from_secs(1)));
+
+        // Payout addresses resolve to a contact's Tari address; others pass through
+        assert!(manager.resolve_payout_address("alice").is_err());
+        manager.update_contact(Contact { tari_address: Some("alice_addr".to_string()), ..updated }).unwrap();
+        assert_eq!(manager.resolve_payout_address("Alice").unwrap(), "alice_addr");
+        assert_eq!(manager.resolve_payout_address("bob").unwrap(), "bob");
+
+        assert!(manager.remove_contact("Alice").unwrap());
+        assert!(!manager.remove_contact("alice").unwrap());
+        assert!(manager.list_contacts().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_saved_search_notifies_once_per_matching_listing() {
+        let TestManager { _temp_dir: _seller_dir, keypair: seller_key, manager: seller, .. } = test_manager();
+        for (i, (title, price)) in [("Road bike", 400), ("Road bike, carbon", 900), ("Desk lamp", 20)].into_iter().enumerate() {
+            seller.create_global_listing(
+                Hash::new([i as u8; 32]),
+                seller_key.public_key(),
+                title.to_string(),
+                "desc".to_string(),
+                price,
+                String::new(),
+                "misc".to_string(),
+            ).await.unwrap();
+        }
+
+        let TestManager { _temp_dir: _buyer_dir, storage: buyer_storage, manager: buyer, .. } = test_manager();
+        let clock = Arc::new(tari_l2_common::clock::ManualClock::new(Timestamp::now()));
+        let buyer = buyer.with_clock(clock.clone());
+        let query = crate::saved_search::SearchQuery {
+            keywords: vec!["bike".to_string()],
+            max_price: Some(Amount::new(500)),
+            ..Default::default()
+        };
+        let search = SavedSearch::new(query, None, Timestamp::from_secs(1)).unwrap();
+        buyer.save_search(search.clone()).unwrap();
+        let mut events = buyer.events().subscribe();
+
+        let signed = seller.signed_listings().await.unwrap();
+        assert_eq!(buyer.import_signed_listings(signed).await.unwrap(), 3);
+
+        let results = buyer.saved_search_results(&search.id).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.title, "Road bike");
+        let mut matched = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let MarketplaceEvent::SavedSearchMatch { search_id, listing } = event {
+                matched.push((search_id, listing.id));
+            }
+        }
+        assert_eq!(matched, vec![(search.id, results[0].0.listing_id)]);
+
+        // The same listing changing again is not a new match
+        let mut repriced = results[0].1.clone();
+        repriced.price = Amount::new(350);
+        let signature = seller_key.sign_with_context(SigningContext::Listing, &canonical::encode(&repriced).unwrap());
+        let timestamp = Timestamp::now().as_secs();
+        let update_signature = seller_key.sign(&listing_update_message(&repriced, timestamp).unwrap());
+        buyer.handle_listing_update(repriced, signature, timestamp, update_signature).await.unwrap();
+        assert_eq!(buyer.saved_search_results(&search.id).await.unwrap().len(), 1);
+        assert!(!std::iter::from_fn(|| events.try_recv().ok())
+            .any(|event| matches!(event, MarketplaceEvent::SavedSearchMatch { .. })));
+
+        // Matches go when their listing expires
+        clock.advance(3600);
+        assert_eq!(buyer.expire_listings(60).await.unwrap().len(), 3);
+        assert!(buyer_storage.load_search_matches(&search.id).unwrap().is_empty());
+
+        assert!(buyer.delete_saved_search(&search.id).unwrap());
+        assert!(buyer.saved_search_results(&search.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_invoice_paid_once_from_the_best_channel() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let manager = MarketplaceManager::new(storage, keypair.clone(), None);
+        let seller = KeyPair::generate();
+        let (local, seller_pk) = (keypair.public_key(), seller.public_key());
+
+        // A direct channel and a larger one shared with a third party
+        let carol = KeyPair::generate();
+        let mut channels = Vec::new();
+        for (participants, balance) in [(vec![local, seller_pk], 300), (vec![local, seller_pk, carol.public_key()], 800)] {
+            let initial_balances = participants.iter()
+                .map(|pk| (*pk, Amount::new(if *pk == local { balance } else { 0 })))
+                .collect();
+            let channel_id = manager.create_channel(ChannelConfig {
+                participants,
+                initial_balances,
+                challenge_period: 3600,
+            }).await.unwrap();
+            manager.activate_channel(&channel_id).await.unwrap();
+            channels.push(channel_id);
+        }
+
+        let now = Timestamp::now().as_secs();
+        let terms = InvoiceTerms {
+            id: Hash::random(),
+            seller: seller_pk,
+            amount: 500,
+            memo: "Order #12".to_string(),
+            listing_id: None,
+            created_at: now,
+            expires_at: now + 600,
+        };
+        let invoice = Invoice::sign(terms.clone(), |m| seller.sign(m)).unwrap();
+
+        // A tampered invoice is refused
+        let mut altered = invoice.clone();
+        altered.terms.amount = 50;
+        assert!(matches!(manager.pay_invoice(&altered).await, Err(L2Error::InvalidSignature)));
+
+        // Only the channel holding 800 can cover it, and the payment names
+        // the invoice
+        let payment = manager.pay_invoice(&invoice).await.unwrap();
+        assert_eq!((payment.channel_id, payment.nonce), (channels[1], 1));
+        let pending = manager.list_pending_updates(&channels[1]).await;
+        assert!(matches!(
+            pending.as_slice(),
+            [SignedStateUpdate { update: StateUpdate::PayInvoice { invoice_id, to, .. }, .. }]
+                if *invoice_id == invoice.id() && *to == seller_pk
+        ));
+
+        // It is not paid until the payment commits, and cannot be paid again
+        // while in flight
+        assert_eq!(manager.invoice_payment(&invoice.id()).unwrap(), None);
+        assert!(manager.pay_invoice(&invoice).await.is_err());
+        let mut signed = pending[0].clone();
+        for key in [&seller, &carol] {
+            signed.add_signature(key.public_key(), key.sign(&signed.signing_message()));
+        }
+        manager.apply_state_update(&channels[1], signed).await.unwrap();
+        assert_eq!(manager.invoice_payment(&invoice.id()).unwrap().map(|p| (p.channel_id, p.nonce)), Some((channels[1], 1)));
+        assert!(manager.pay_invoice(&invoice).await.is_err());
+
+        let expired = Invoice::sign(InvoiceTerms { id: Hash::random(), expires_at: now, created_at: now - 1, ..terms.clone() }, |m| seller.sign(m)).unwrap();
+        assert!(manager.pay_invoice(&expired).await.is_err());
+        let too_large = Invoice::sign(InvoiceTerms { id: Hash::random(), amount: 900, ..terms }, |m| seller.sign(m)).unwrap();
+        assert!(matches!(manager.pay_invoice(&too_large).await, Err(L2Error::InsufficientBalance { .. })));
+
+        // Invoices this node issues are signed by it and kept
+        let own = manager.create_invoice(1_000, "Consulting".to_string(), None, 3600).unwrap();
+        assert!(own.verify());
+        assert_eq!(own.terms.seller, local);
+        assert_eq!(manager.list_invoices().unwrap(), vec![own.clone()]);
+        assert!(manager.pay_invoice(&own).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_receipts_issued_on_settlement() {
+        use tari_l2_common::clock::ManualClock;
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let manager = MarketplaceManager::new(storage, keypair.clone(), None)
+            .with_clock(clock.clone());
+        let (channel_id, order, buyer) = channel_with_order(&manager, &keypair).await;
+        assert!(manager.get_receipt(&order.id).unwrap().is_none());
+
+        let mut signed = manager.update_order_status(&channel_id, order.id, OrderStatus::Completed).await.unwrap();
+        signed.add_signature(order.buyer, buyer.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+
+        let receipt = manager.get_receipt(&order.id).unwrap().unwrap();
+        assert!(receipt.verify());
+        assert_eq!(receipt.issuer, keypair.public_key());
+        assert_eq!(receipt.terms.nonce, 2);
+        assert!(matches!(receipt.terms.kind, ReceiptKind::Order { amount: 500, .. }));
+
+        manager.close_channel(&channel_id).await.unwrap();
+        clock.advance(3600);
+        manager.finalize_closed_channels().await.unwrap();
+
+        let receipt = manager.get_receipt(&channel_id).unwrap().unwrap();
+        assert!(receipt.verify());
+        assert_eq!(receipt.terms.started_at, Some(1_700_000_000));
+        assert_eq!(receipt.terms.settled_at, 1_700_003_600);
+        let ReceiptKind::ChannelClose { balances } = &receipt.terms.kind else {
+            panic!("expected a channel receipt");
+        };
+        assert!(balances.contains(&(order.seller, 1500)));
+        assert_eq!(manager.list_receipts().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_purchase_history_includes_receipts() {
+        use tari_l2_common::clock::ManualClock;
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let manager = MarketplaceManager::new(storage, keypair.clone(), None)
+            .with_clock(clock.clone());
+        let (channel_id, order, buyer) = channel_with_order(&manager, &keypair).await;
+
+        let history = manager.get_purchase_history(&order.buyer, &HistoryQuery::default()).await.unwrap();
+        assert_eq!(history.total, 1);
+        assert!(history.purchases[0].receipt.is_none());
+
+        clock.advance(60);
+        let mut signed = manager.update_order_status(&channel_id, order.id, OrderStatus::Completed).await.unwrap();
+        signed.add_signature(order.buyer, buyer.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
+
+        let history = manager.get_purchase_history(&order.buyer, &HistoryQuery::default()).await.unwrap();
+        let purchase = &history.purchases[0];
+        assert_eq!(purchase.channel_id, channel_id);
+        assert!(purchase.receipt.as_ref().is_some_and(|r| r.verify()));
+        assert_eq!(purchase.settled_at, Some(1_700_000_060));
+
+        let query = HistoryQuery { since: Some(1_700_000_061), ..Default::default() };
+        assert_eq!(manager.get_purchase_history(&order.buyer, &query).await.unwrap().total, 0);
+        assert_eq!(manager.get_purchase_history(&order.seller, &HistoryQuery::default()).await.unwrap().total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_challenge_period_enforced() {
+        use tari_l2_common::clock::ManualClock;
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let manager = MarketplaceManager::new(storage, keypair.clone(), None)
+            .with_clock(clock.clone());
+
+        let counterparty = KeyPair::generate().public_key();
+        let config = |challenge_period| ChannelConfig {
+            participants: vec![keypair.public_key(), counterparty],
+            initial_balances: [(keypair.public_key(), Amount::new(1000)), (counterparty, Amount::new(1000))].into_iter().collect(),
+            challenge_period,
+        };
+        assert!(matches!(manager.create_channel(config(60)).await, Err(L2Error::InvalidParameter(_))));
+
+        let channel_id = manager.create_channel(config(3600)).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+        let deadline = manager.close_channel(&channel_id).await.unwrap();
+        assert_eq!(deadline, Timestamp::from_secs(1_700_003_600));
+
+        clock.advance(3599);
+        assert!(manager.finalize_closed_channels().await.unwrap().is_empty());
+        clock.advance(1);
+        assert_eq!(manager.finalize_closed_channels().await.unwrap(), vec![channel_id]);
+        assert_eq!(manager.get_channel_info(&channel_id).await.unwrap().status, ChannelStatus::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_failed_collateral_unlock_raises_alert() {
+        use tari_l2_common::clock::ManualClock;
+        use tari_l2_l1_client::{L1Config, TariL1Client};
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let l1 = Arc::new(TariL1Client::offline(L1Config::default()));
+        let manager = MarketplaceManager::new(storage, keypair.clone(), Some(l1.clone()))
+            .with_clock(clock.clone());
+        let mut receiver = manager.alerts().subscribe();
 
-        // Persist to database first
-        self.storage.store_listing(&listing)?;
+        let counterparty = KeyPair::generate().public_key();
+        let channel_id = manager.create_channel(ChannelConfig {
+            participants: vec![keypair.public_key(), counterparty],
+            initial_balances: [(keypair.public_key(), Amount::new(1000)), (counterparty, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        }).await.unwrap();
+        manager.activate_channel(&channel_id).await.unwrap();
+        manager.close_channel(&channel_id).await.unwrap();
 
-        // Add to in-memory cache
-        self.global_listings.write().await.push(listing.clone());
+        // The channel still closes, but the locked collateral is reported
+        l1.set_outage(true);
+        clock.advance(3600);
+        assert_eq!(manager.finalize_closed_channels().await.unwrap(), vec![channel_id]);
+        assert_eq!(manager.get_channel_info(&channel_id).await.unwrap().status, ChannelStatus::Closed);
+        assert!(l1.get_locked_collateral(&channel_id.to_string()).await.is_some());
+        assert_eq!(receiver.try_recv().unwrap().kind, AlertKind::CollateralLocked { channel_id });
+    }
 
-        // Broadcast to P2P network
-        if let Some(network) = self.network.read().await.as_ref() {
-            let listing_bytes = bincode::serialize(&listing)
-                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
-            let signature = self.keypair.sign(&listing_bytes);
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+    #[tokio::test]
+    async fn test_unfunded_orders_expire() {
+        use tari_l2_common::clock::ManualClock;
 
-            let message = tari_l2_p2p::L2Message::ListingBroadcast {
-                listing,
-                signature,
-                timestamp,
-            };
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let manager = MarketplaceManager::new(storage.clone(), keypair.clone(), None)
+            .with_clock(clock.clone());
 
-            network.broadcast_message(message).await
-                .map_err(|e| L2Error::Unknown(format!("Failed to broadcast listing: {}", e)))?;
+        let (channel_id, unfunded, _buyer) = channel_with_order(&manager, &keypair).await;
+        let escrow_id = manager.create_escrow(&channel_id, &unfunded.id, 600, None).await.unwrap();
+        let (funded_channel, funded, _buyer) = channel_with_order(&manager, &keypair).await;
+        let funded_escrow = manager.create_escrow(&funded_channel, &funded.id, 600, None).await.unwrap();
+        manager.fund_escrow(&funded_escrow, "tx".to_string()).await.unwrap();
 
-            info!("✅ Created and broadcast global listing: {} - {}", title, price);
-        } else {
-            info!("⚠️  Created global listing (no P2P network): {} - {}", title, price);
-        }
+        // The deadline runs from when the order was first seen, which is
+        // stored so a restart does not reset it
+        assert!(manager.expire_unfunded_orders(600).await.unwrap().is_empty());
+        assert!(storage.load_order_first_seen().unwrap().contains(&(unfunded.id, 1_700_000_000)));
+        clock.advance(600);
+        assert!(manager.expire_unfunded_orders(600).await.unwrap().is_empty());
 
-        Ok(())
+        clock.advance(1);
+        assert_eq!(manager.expire_unfunded_orders(600).await.unwrap(), vec![unfunded.id]);
+        assert_eq!(manager.get_escrow(&escrow_id).await.unwrap().status, EscrowStatus::Cancelled);
+        assert!(matches!(
+            &manager.list_pending_updates(&channel_id).await[..],
+            [SignedStateUpdate { update: StateUpdate::UpdateOrderStatus { order_id, status: OrderStatus::Cancelled }, .. }]
+                if *order_id == unfunded.id
+        ));
+        assert!(manager.list_pending_updates(&funded_channel).await.is_empty());
+
+        // The cancellation is only proposed once
+        assert!(manager.expire_unfunded_orders(600).await.unwrap().is_empty());
     }
 
-    /// Handle incoming listing from P2P network
-    pub async fn handle_received_listing(&self, listing: Listing, signature: tari_l2_common::Signature, timestamp: u64) -> Result<()> {
-        // Verify signature
-        let listing_bytes = bincode::serialize(&listing)
-            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+    #[tokio::test]
+    async fn test_seller_catalog_cached_until_changed() {
+        use tari_l2_common::clock::ManualClock;
 
-        if !listing.seller.verify(&listing_bytes, signature.as_bytes()) {
-            return Err(L2Error::InvalidSignature);
-        }
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let manager = MarketplaceManager::new(storage, keypair.clone(), None)
+            .with_clock(clock.clone());
+        let seller = keypair.public_key();
+        let (channel_id, order, buyer) = channel_with_order(&manager, &keypair).await;
 
-        // Check if we already have this listing
-        let listings = self.global_listings.read().await;
-        if listings.iter().any(|l| l.id == listing.id) {
-            return Ok(()); // Already have it
-        }
-        drop(listings);
+        let catalog = manager.get_seller_catalog(&seller).await;
+        assert!(catalog.profile.is_none() && catalog.listings.is_empty() && catalog.recent_reviews.is_empty());
 
-        // Persist to database
-        self.storage.store_listing(&listing)?;
+        manager.create_global_listing(
+            Hash::random(), seller, "Item".to_string(), "desc".to_string(),
+            100, String::new(), "misc".to_string(),
+        ).await.unwrap();
+        manager.upsert_profile(UserProfile::new(seller, "Alice".to_string())).await;
+        let catalog = manager.get_seller_catalog(&seller).await;
+        assert_eq!(catalog.listings.len(), 1);
+        assert_eq!(catalog.profile.unwrap().name, "Alice");
 
-        // Add to in-memory cache
-        self.global_listings.write().await.push(listing.clone());
+        // Only the buyer of a completed order can review it, once
+        assert!(manager.add_review(&order.id, buyer.public_key(), 4, "ok".to_string()).await.is_err());
+        let mut signed = manager.update_order_status(&channel_id, order.id, OrderStatus::Completed).await.unwrap();
+        signed.add_signature(buyer.public_key(), buyer.sign(&signed.signing_message()));
+        manager.apply_state_update(&channel_id, signed).await.unwrap();
 
-        info!("📦 Received and stored listing from network: {} (ID: {:?})", listing.title, listing.id);
+        assert!(manager.add_review(&order.id, seller, 5, "great".to_string()).await.is_err());
+        assert!(manager.add_review(&order.id, buyer.public_key(), 6, "great".to_string()).await.is_err());
+        manager.add_review(&order.id, buyer.public_key(), 4, "ok".to_string()).await.unwrap();
+        assert!(manager.add_review(&order.id, buyer.public_key(), 5, "again".to_string()).await.is_err());
 
-        Ok(())
-    }
+        let catalog = manager.get_seller_catalog(&seller).await;
+        assert_eq!(catalog.recent_reviews.len(), 1);
+        assert_eq!(catalog.reputation.rating, 4.0);
 
-    /// Load all listings from storage
-    pub async fn load_listings(&self) -> Result<()> {
-        let listings = self.storage.load_all_listings()?;
-        let mut global_listings = self.global_listings.write().await;
-        *global_listings = listings;
-        info!("✅ Loaded {} listings from storage", global_listings.len());
-        Ok(())
+        clock.advance(1);
+        assert_eq!(manager.expire_listings(0).await.unwrap().len(), 1);
+        assert!(manager.get_seller_catalog(&seller).await.listings.is_empty());
     }
 
-    /// List all global marketplace listings
-    pub async fn list_all_listings(&self) -> Vec<(Hash, Listing)> {
-        // Return global listings (channel ID is not meaningful for global listings,
-        // so we use a zero hash as placeholder)
-        let listings = self.global_listings.read().await;
-        listings.iter()
-            .filter(|l| l.active)
-            .map(|l| (Hash::new([0u8; 32]), l.clone()))
-            .collect()
-    }
+    #[tokio::test]
+    async fn test_timeouts_follow_injected_clock() {
+        use tari_l2_common::clock::ManualClock;
 
-    /// Get listings for a specific channel
-    pub async fn get_channel_listings(&self, channel_id: &Hash) -> Result<Vec<Listing>> {
-        let channels = self.channels.read().await;
-        let channel = channels.get(channel_id)
-            .ok_or_else(|| L2Error::ChannelNotFound(channel_id.to_string()))?;
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let manager = MarketplaceManager::new(storage, keypair.clone(), None)
+            .with_clock(clock.clone());
 
-        Ok(channel.state.listings.iter()
-            .filter(|l| l.active)
-            .cloned()
-            .collect())
+        let (channel_id, order, _buyer) = channel_with_order(&manager, &keypair).await;
+        let escrow_id = manager.create_escrow(&channel_id, &order.id, 600, None).await.unwrap();
+        manager.fund_escrow(&escrow_id, "tx".to_string()).await.unwrap();
+        manager.ship_order(&escrow_id, None).await.unwrap();
+
+        manager.create_global_listing(
+            Hash::random(), keypair.public_key(), "Item".to_string(), "desc".to_string(),
+            100, String::new(), "misc".to_string(),
+        ).await.unwrap();
+
+        clock.advance(600);
+        assert!(manager.process_escrow_timeouts().await.unwrap().is_empty());
+        assert!(manager.expire_listings(600).await.unwrap().is_empty());
+
+        clock.advance(1);
+        assert_eq!(manager.process_escrow_timeouts().await.unwrap(), vec![escrow_id]);
+        assert_eq!(manager.expire_listings(600).await.unwrap().len(), 1);
     }
 
-    /// List all orders across all channels
-    pub async fn list_all_orders(&self) -> Vec<(Hash, Order)> {
-        let channels = self.channels.read().await;
-        let mut all_orders = Vec::new();
+    #[tokio::test]
+    async fn test_listing_sync_over_sim_network() {
+        use async_trait::async_trait;
+        use tari_l2_p2p::sim::{SimConfig, SimNetwork};
+        use tari_l2_p2p::{L2Message, MessageHandler};
 
-        for (channel_id, channel) in channels.iter() {
-            for order in &channel.state.orders {
-                all_orders.push((*channel_id, order.clone()));
+        struct ListingHandler(Arc<MarketplaceManager>);
+
+        #[async_trait]
+        impl MessageHandler for ListingHandler {
+            async fn handle_message(&self, _from: PublicKey, message: L2Message) -> Result<Option<L2Message>> {
+                match message {
+                    L2Message::ListingBroadcast { listing, signature, timestamp } => {
+                        self.0.handle_received_listing(listing, signature, timestamp).await?;
+                        Ok(None)
+                    }
+                    L2Message::ListingUpdated { listing, signature, timestamp, update_signature } => {
+                        self.0.handle_listing_update(listing, signature, timestamp, update_signature).await?;
+                        Ok(None)
+                    }
+                    L2Message::ListingsRequest => {
+                        Ok(Some(L2Message::ListingsResponse { listings: self.0.signed_listings().await? }))
+                    }
+                    L2Message::ListingsResponse { listings } => {
+                        self.0.import_signed_listings(listings).await?;
+                        Ok(None)
+                    }
+                    _ => Ok(None),
+                }
             }
         }
 
-        all_orders
-    }
+        let sim = SimNetwork::new(SimConfig { seed: 1, latency_ms: 20, jitter_ms: 10, drop_rate: 0.0 });
+        let dirs: Vec<TempDir> = (0..3).map(|_| TempDir::new().unwrap()).collect();
+        let mut managers = Vec::new();
+        for dir in &dirs {
+            let keypair = Arc::new(KeyPair::generate());
+            let manager = Arc::new(MarketplaceManager::new(
+                Arc::new(MarketplaceStorage::open(dir.path()).unwrap()),
+                keypair.clone(),
+                None,
+            ));
+            manager.set_network(sim.add_node(keypair.public_key())).await;
+            sim.set_handler(&keypair.public_key(), Arc::new(ListingHandler(manager.clone())));
+            managers.push((keypair, manager));
+        }
 
-    /// Get orders for a specific channel
-    pub async fn get_channel_orders(&self, channel_id: &Hash) -> Result<Vec<Order>> {
-        let channels = self.channels.read().await;
-        let channel = channels.get(channel_id)
-            .ok_or_else(|| L2Error::ChannelNotFound(channel_id.to_string()))?;
+        // The third node misses the broadcast, then catches up by requesting the catalog
+        sim.partition(&[managers[0].0.public_key()], &[managers[2].0.public_key()]);
+        let (seller_key, seller) = &managers[0];
+        seller.create_global_listing(
+            Hash::random(), seller_key.public_key(), "Lamp".to_string(), "desc".to_string(),
+            250, String::new(), "home".to_string(),
+        ).await.unwrap();
+        sim.run_until_idle().await;
 
-        Ok(channel.state.orders.clone())
-    }
+        assert_eq!(managers[1].1.list_all_listings().await.len(), 1);
+        assert!(managers[2].1.list_all_listings().await.is_empty());
 
-    // ===== Escrow Management =====
+        sim.heal();
+        let late = sim.add_node(managers[2].0.public_key());
+        late.send_message(seller_key.public_key(), L2Message::ListingsRequest).await.unwrap();
+        sim.run_until_idle().await;
+        assert_eq!(managers[2].1.list_all_listings().await.len(), 1);
 
-    /// Create a new escrow contract for a purchase
-    pub async fn create_escrow(
-        &self,
-        listing_id: Hash,
-        buyer: PublicKey,
-        seller: PublicKey,
-        amount: Amount,
-        timeout_period: u64,
-    ) -> Result<Hash> {
-        let escrow = EscrowContract::new(listing_id, buyer, seller, amount, timeout_period);
-        let escrow_id = escrow.id;
+        // Price changes reach every cache
+        let listing_id = seller.list_all_listings().await[0].1.id;
+        seller.update_listing_price(&listing_id, 300).await.unwrap();
+        sim.run_until_idle().await;
+        for (_, manager) in &managers {
+            assert_eq!(manager.list_all_listings().await[0].1.price, Amount::new(300));
+        }
 
-        self.escrow_contracts.write().await.insert(escrow_id, escrow);
-        info!("Created escrow contract: {:?}", escrow_id);
+        // An older update replayed, or one given a later timestamp than the
+        // seller signed, cannot roll the price back
+        let mut old = seller.list_all_listings().await[0].1.clone();
+        old.price = Amount::new(250);
+        let signature = seller_key.sign_with_context(SigningContext::Listing, &canonical::encode(&old).unwrap());
+        let signed_at = |at| seller_key.sign(&listing_update_message(&old, at).unwrap());
+        let last = managers[1].1.storage.load_listing_version(&listing_id).unwrap().unwrap();
+        let (replayed, retimed) = (signed_at(last), signed_at(last));
+        assert!(managers[1].1.handle_listing_update(old.clone(), signature.clone(), last, replayed).await.is_err());
+        assert!(matches!(
+            managers[1].1.handle_listing_update(old, signature, last + 1, retimed).await,
+            Err(L2Error::InvalidSignature)
+        ));
+        assert_eq!(managers[1].1.list_all_listings().await[0].1.price, Amount::new(300));
 
-        Ok(escrow_id)
+        // Only the node that signed a listing can reprice it
+        assert!(managers[1].1.update_listing_price(&listing_id, 1).await.is_err());
     }
 
-    /// Fund an escrow contract (buyer deposits funds to L1)
-    pub async fn fund_escrow(&self, escrow_id: &Hash, l1_tx_id: String) -> Result<()> {
-        let mut escrows = self.escrow_contracts.write().await;
-        let escrow = escrows.get_mut(escrow_id)
-            .ok_or_else(|| L2Error::Unknown(format!("Escrow not found: {:?}", escrow_id)))?;
-
-        escrow.fund(l1_tx_id).map_err(|e| L2Error::Unknown(e))?;
-        info!("Funded escrow: {:?}", escrow_id);
+    #[tokio::test]
+    async fn test_catalog_root_flags_and_repairs_relayed_catalog() {
+        let dirs: Vec<TempDir> = (0..2).map(|_| TempDir::new().unwrap()).collect();
+        let seller_key = Arc::new(KeyPair::generate());
+        let seller = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(dirs[0].path()).unwrap()), seller_key.clone(), None,
+        );
+        let buyer = MarketplaceManager::new(
+            Arc::new(MarketplaceStorage::open(dirs[1].path()).unwrap()), Arc::new(KeyPair::generate()), None,
+        );
 
-        Ok(())
-    }
+        for (title, price) in [("Lamp", 100), ("Desk", 200), ("Chair", 300)] {
+            seller.create_global_listing(
+                Hash::random(), seller_key.public_key(), title.to_string(), String::new(),
+                price, String::new(), "home".to_string(),
+            ).await.unwrap();
+        }
 
-    /// Mark order as shipped (seller confirms shipment)
-    pub async fn ship_order(&self, escrow_id: &Hash, tracking_info: Option<String>) -> Result<()> {
-        let mut escrows = self.escrow_contracts.write().await;
-        let escrow = escrows.get_mut(escrow_id)
-            .ok_or_else(|| L2Error::Unknown(format!("Escrow not found: {:?}", escrow_id)))?;
+        // A relay passes on only two of the three listings
+        let mut signed = seller.signed_listings().await.unwrap();
+        let withheld = signed.pop().unwrap();
+        buyer.import_signed_listings(signed).await.unwrap();
+        let root = seller.announce_catalog_root().await.unwrap().unwrap();
+        assert_eq!(
+            buyer.handle_catalog_root(root).await.unwrap(),
+            CatalogIntegrity::Diverged { announced: 3, held: 2 }
+        );
+        buyer.import_signed_listings(vec![withheld]).await.unwrap();
+        assert_eq!(buyer.get_seller_catalog(&seller_key.public_key()).await.integrity, CatalogIntegrity::Verified);
 
-        escrow.mark_shipped(tracking_info).map_err(|e| L2Error::Unknown(e))?;
-        info!("Marked escrow as shipped: {:?}", escrow_id);
+        // After a price change the old version no longer matches the new root
+        let listing_id = seller.list_all_listings().await[0].1.id;
+        seller.update_listing_price(&listing_id, 150).await.unwrap();
+        let mut root = seller.announce_catalog_root().await.unwrap().unwrap();
+        root.timestamp += 1;
+        root.signature = seller_key.sign(&CatalogRoot::signing_message(&root.seller, &root.root, root.listings, root.timestamp));
+        assert!(matches!(buyer.handle_catalog_root(root).await.unwrap(), CatalogIntegrity::Diverged { .. }));
 
-        Ok(())
+        // The seller's current versions repair it
+        buyer.import_signed_listings(seller.signed_listings().await.unwrap()).await.unwrap();
+        let catalog = buyer.get_seller_catalog(&seller_key.public_key()).await;
+        assert_eq!(catalog.integrity, CatalogIntegrity::Verified);
+        assert!(catalog.listings.iter().any(|l| l.price == Amount::new(150)));
     }
 
-    /// Confirm delivery and release funds to seller (buyer confirms receipt)
-    pub async fn confirm_delivery(&self, escrow_id: &Hash) -> Result<()> {
-        let mut escrows = self.escrow_contracts.write().await;
-        let escrow = escrows.get_mut(escrow_id)
-            .ok_or_else(|| L2Error::Unknown(format!("Escrow not found: {:?}", escrow_id)))?;
-
-        escrow.confirm_receipt().map_err(|e| L2Error::Unknown(e))?;
+    #[tokio::test]
+    async fn test_catalog_bootstrap_from_archive_peer() {
+        use async_trait::async_trait;
+        use tari_l2_p2p::sim::{SimConfig, SimNetwork};
+        use tari_l2_p2p::{L2Message, MessageHandler};
 
-        // TODO: Release funds to seller on L1 when L1 escrow methods are implemented
-        info!("Confirmed delivery and released escrow: {:?}", escrow_id);
-        Ok(())
-    }
+        struct CatalogHandler(Arc<MarketplaceManager>);
 
-    /// Request refund (buyer initiates refund request)
-    pub async fn request_refund(&self, escrow_id: &Hash, reason: String) -> Result<()> {
-        let mut escrows = self.escrow_contracts.write().await;
-        let escrow = escrows.get_mut(escrow_id)
-            .ok_or_else(|| L2Error::Unknown(format!("Escrow not found: {:?}", escrow_id)))?;
+        #[async_trait]
+        impl MessageHandler for CatalogHandler {
+            async fn handle_message(&self, from: PublicKey, message: L2Message) -> Result<Option<L2Message>> {
+                match message {
+                    L2Message::CatalogPageRequest { offset, limit } => {
+                        Ok(Some(L2Message::CatalogPageResponse { page: self.0.catalog_page(offset, limit).await? }))
+                    }
+                    L2Message::CatalogPageResponse { page } => self.0.handle_catalog_page(&from, page).await,
+                    _ => Ok(None),
+                }
+            }
+        }
 
-        escrow.request_refund(reason).map_err(|e| L2Error::Unknown(e))?;
-        info!("Refund requested for escrow: {:?}", escrow_id);
+        let sim = SimNetwork::new(SimConfig { seed: 3, latency_ms: 20, jitter_ms: 10, drop_rate: 0.0 });
+        let dirs: Vec<TempDir> = (0..2).map(|_| TempDir::new().unwrap()).collect();
+        let mut nodes = Vec::new();
+        for dir in &dirs {
+            let keypair = Arc::new(KeyPair::generate());
+            let manager = Arc::new(MarketplaceManager::new(
+                Arc::new(MarketplaceStorage::open(dir.path()).unwrap()),
+                keypair.clone(),
+                None,
+            ));
+            manager.set_network(sim.add_node(keypair.public_key())).await;
+            sim.set_handler(&keypair.public_key(), Arc::new(CatalogHandler(manager.clone())));
+            nodes.push((keypair, manager));
+        }
+        let (archive_key, archive) = &nodes[0];
+        let (fresh_key, fresh) = &nodes[1];
 
-        Ok(())
-    }
+        // The fresh node is offline while the archive hears about two users
+        sim.partition(&[archive_key.public_key()], &[fresh_key.public_key()]);
+        archive.create_global_listing(
+            Hash::random(), archive_key.public_key(), "Lamp".to_string(), "desc".to_string(),
+            250, String::new(), "home".to_string(),
+        ).await.unwrap();
+        let other = KeyPair::generate().public_key();
+        archive.upsert_profile(UserProfile::new(archive_key.public_key(), "Archive".to_string())).await;
+        archive.upsert_profile(UserProfile::new(other, "Bob".to_string())).await;
+        sim.run_until_idle().await;
+        assert!(fresh.list_all_listings().await.is_empty());
 
-    /// Approve refund (seller agrees to refund)
-    pub async fn approve_refund(&self, escrow_id: &Hash) -> Result<()> {
-        let mut escrows = self.escrow_contracts.write().await;
-        let escrow = escrows.get_mut(escrow_id)
-            .ok_or_else(|| L2Error::Unknown(format!("Escrow not found: {:?}", escrow_id)))?;
+        // One user per page, so the download takes two round trips
+        sim.heal();
+        fresh.configure_archive_peers(vec![archive_key.public_key()], 1).await;
+        assert_eq!(fresh.bootstrap_catalog().await.unwrap(), 1);
+        sim.run_until_idle().await;
 
-        escrow.approve_refund().map_err(|e| L2Error::Unknown(e))?;
+        assert_eq!(fresh.list_all_listings().await.len(), 1);
+        assert!(fresh.get_profile(&archive_key.public_key()).await.is_some());
+        assert!(fresh.get_profile(&other).await.is_some());
+        assert_eq!(fresh.bootstrap_catalog().await.unwrap(), 0);
 
-        // TODO: Refund to buyer on L1 when L1 escrow methods are implemented
-        info!("Approved refund for escrow: {:?}", escrow_id);
-        Ok(())
+        // Pages from anyone else are refused
+        let page = archive.catalog_page(0, 10).await.unwrap();
+        assert!(archive.handle_catalog_page(&fresh_key.public_key(), page).await.is_err());
     }
 
-    /// Raise dispute (either party can dispute)
-    pub async fn raise_dispute(&self, escrow_id: &Hash, reason: String) -> Result<()> {
-        let mut escrows = self.escrow_contracts.write().await;
-        let escrow = escrows.get_mut(escrow_id)
-            .ok_or_else(|| L2Error::Unknown(format!("Escrow not found: {:?}", escrow_id)))?;
+    #[tokio::test]
+    async fn test_peer_table_filters_by_capability() {
+        use tari_l2_p2p::sim::{SimConfig, SimNetwork};
 
-        escrow.raise_dispute(reason).map_err(|e| L2Error::Unknown(e))?;
-        info!("Dispute raised for escrow: {:?}", escrow_id);
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
+        let keypair = Arc::new(KeyPair::generate());
+        let manager = MarketplaceManager::new(storage, keypair.clone(), None);
+        assert!(manager.peer_table(None).await.is_empty());
 
-        Ok(())
-    }
+        let sim = SimNetwork::new(SimConfig::default());
+        manager.set_network(sim.add_node(keypair.public_key())).await;
+        let (tower, seller) = (KeyPair::generate().public_key(), KeyPair::generate().public_key());
+        sim.add_node(tower);
+        sim.add_node(seller);
+        sim.set_capabilities(&tower, [Capability::Watchtower, Capability::Relay]);
+        sim.set_capabilities(&seller, [Capability::AcceptsChannelOpens]);
 
-    /// Get escrow contract details
-    pub async fn get_escrow(&self, escrow_id: &Hash) -> Result<EscrowContract> {
-        let escrows = self.escrow_contracts.read().await;
-        escrows.get(escrow_id)
-            .cloned()
-            .ok_or_else(|| L2Error::Unknown(format!("Escrow not found: {:?}", escrow_id)))
-    }
+        assert_eq!(manager.peer_table(None).await.len(), 2);
+        let towers = manager.peer_table(Some(Capability::Watchtower)).await;
+        assert_eq!(towers.len(), 1);
+        assert_eq!(towers[0].public_key, Some(tower));
+        assert!(manager.peer_table(Some(Capability::Arbitrator)).await.is_empty());
 
-    /// List all escrow contracts
-    pub async fn list_escrows(&self) -> Vec<EscrowContract> {
-        self.escrow_contracts.read().await.values().cloned().collect()
+        // Unreachable peers drop out of the table
+        sim.partition(&[keypair.public_key()], &[seller]);
+        assert!(manager.peer_table(Some(Capability::AcceptsChannelOpens)).await.is_empty());
     }
 
-    /// Process timeouts for escrows (auto-release to seller)
-    pub async fn process_escrow_timeouts(&self) -> Result<Vec<Hash>> {
-        let mut escrows = self.escrow_contracts.write().await;
-        let mut released = Vec::new();
+    #[tokio::test]
+    async fn test_outbox_retries_proposal_until_acked() {
+        use async_trait::async_trait;
+        use tari_l2_common::clock::ManualClock;
+        use tari_l2_p2p::sim::{SimConfig, SimNetwork};
+        use tari_l2_p2p::{L2Message, MessageHandler};
 
-        for (escrow_id, escrow) in escrows.iter_mut() {
-            if escrow.is_timed_out() {
-                if let Ok(_) = escrow.auto_release() {
-                    released.push(*escrow_id);
-                    info!("Auto-released timed out escrow: {:?}", escrow_id);
+        struct UpdateHandler(Arc<MarketplaceManager>);
+
+        #[async_trait]
+        impl MessageHandler for UpdateHandler {
+            async fn handle_message(&self, _from: PublicKey, message: L2Message) -> Result<Option<L2Message>> {
+                match message {
+                    L2Message::StateUpdateProposal { channel_id, update } => {
+                        self.0.receive_state_update(&channel_id, update).await?;
+                    }
+                    L2Message::StateUpdateAck { channel_id, nonce, signature } => {
+                        self.0.handle_state_update_ack(&channel_id, nonce, signature).await?;
+                    }
+                    _ => {}
                 }
+                Ok(None)
             }
         }
 
-        Ok(released)
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let sim = SimNetwork::new(SimConfig { seed: 5, latency_ms: 20, jitter_ms: 0, drop_rate: 0.0 });
+        let dirs: Vec<TempDir> = (0..2).map(|_| TempDir::new().unwrap()).collect();
+        let keys: Vec<Arc<KeyPair>> = (0..2).map(|_| Arc::new(KeyPair::generate())).collect();
+        let mut managers = Vec::new();
+        for (dir, keypair) in dirs.iter().zip(&keys) {
+            let manager = Arc::new(MarketplaceManager::new(
+                Arc::new(MarketplaceStorage::open(dir.path()).unwrap()),
+                keypair.clone(),
+                None,
+            ).with_clock(clock.clone()));
+            manager.set_network(sim.add_node(keypair.public_key())).await;
+            sim.set_handler(&keypair.public_key(), Arc::new(UpdateHandler(manager.clone())));
+            managers.push(manager);
+        }
+        let (a, b) = (keys[0].public_key(), keys[1].public_key());
+
+        let config = ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1000)), (b, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        };
+        let channel_id = MarketplaceChannel::compute_id(&config.participants);
+        for manager in &managers {
+            manager.create_channel(config.clone()).await.unwrap();
+            manager.activate_channel(&channel_id).await.unwrap();
+        }
+
+        // The proposal is lost while the peers are cut off
+        sim.partition(&[a], &[b]);
+        let transfer = StateUpdate::Transfer { from: a, to: b, amount: Amount::new(100) };
+        managers[0].propose_state_update(&channel_id, transfer).await.unwrap();
+        sim.run_until_idle().await;
+        assert_eq!(managers[0].outbox_len().unwrap(), 1);
+        assert_eq!(managers[1].get_channel_info(&channel_id).await.unwrap().nonce, 0);
+
+        // Nothing is resent before the backoff has passed
+        sim.heal();
+        assert_eq!(managers[0].flush_outbox().await.unwrap(), 0);
+
+        clock.advance(5);
+        assert_eq!(managers[0].flush_outbox().await.unwrap(), 1);
+        sim.run_until_idle().await;
+
+        for manager in &managers {
+            assert_eq!(manager.get_channel_info(&channel_id).await.unwrap().nonce, 1);
+            assert_eq!(manager.outbox_len().unwrap(), 0);
+        }
     }
-    /// Get the node's public key
-    pub fn public_key(&self) -> PublicKey {
-        self.keypair.public_key()
+
+    #[tokio::test]
+    async fn test_out_of_order_updates_buffered_until_gap_filled() {
+        let dirs: Vec<TempDir> = (0..2).map(|_| TempDir::new().unwrap()).collect();
+        let keys: Vec<Arc<KeyPair>> = (0..2).map(|_| Arc::new(KeyPair::generate())).collect();
+        let managers: Vec<MarketplaceManager> = dirs.iter().zip(&keys)
+            .map(|(dir, kp)| MarketplaceManager::new(Arc::new(MarketplaceStorage::open(dir.path()).unwrap()), kp.clone(), None))
+            .collect();
+        let (proposer, counterparty) = (&managers[0], &managers[1]);
+        let (a, b) = (keys[0].public_key(), keys[1].public_key());
+
+        let config = ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1000)), (b, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        };
+        let channel_id = MarketplaceChannel::compute_id(&config.participants);
+        for manager in &managers {
+            manager.create_channel(config.clone()).await.unwrap();
+            manager.activate_channel(&channel_id).await.unwrap();
+        }
+
+        // Three updates the proposer committed, fully signed by both sides
+        let mut committed = Vec::new();
+        for _ in 0..3 {
+            let transfer = StateUpdate::Transfer { from: a, to: b, amount: Amount::new(10) };
+            let mut signed = proposer.create_state_update(&channel_id, transfer).await.unwrap();
+            signed.add_signature(b, keys[1].sign(&signed.signing_message()));
+            proposer.apply_state_update(&channel_id, signed.clone()).await.unwrap();
+            committed.push(signed);
+        }
+
+        // Later nonces arrive first and wait in the buffer
+        counterparty.receive_state_update(&channel_id, committed[2].clone()).await.unwrap();
+        counterparty.receive_state_update(&channel_id, committed[1].clone()).await.unwrap();
+        assert_eq!(counterparty.get_channel_info(&channel_id).await.unwrap().nonce, 0);
+
+        // Only participants may ask for the history
+        let outsider = KeyPair::generate().public_key();
+        assert!(proposer.handle_gap_request(&outsider, &channel_id, 1, 2).await.is_err());
+
+        let Some(tari_l2_p2p::L2Message::StateUpdateGapFill { updates, .. }) =
+            proposer.handle_gap_request(&b, &channel_id, 1, 1).await.unwrap()
+        else {
+            panic!("expected a gap fill");
+        };
+        assert_eq!(updates.len(), 1);
+
+        // Filling the gap releases the buffered updates in order
+        counterparty.handle_gap_fill(&channel_id, updates).await.unwrap();
+        assert_eq!(counterparty.get_channel_info(&channel_id).await.unwrap().nonce, 3);
+        assert_eq!(counterparty.get_balance(&channel_id, &b).await.unwrap(), Amount::new(1030));
     }
 
-}
+    #[tokio::test]
+    async fn test_counterparty_cosigns_and_acks_before_commit() {
+        let dirs: Vec<TempDir> = (0..2).map(|_| TempDir::new().unwrap()).collect();
+        let keys: Vec<Arc<KeyPair>> = (0..2).map(|_| Arc::new(KeyPair::generate())).collect();
+        let managers: Vec<MarketplaceManager> = dirs.iter().zip(&keys)
+            .map(|(dir, kp)| MarketplaceManager::new(Arc::new(MarketplaceStorage::open(dir.path()).unwrap()), kp.clone(), None))
+            .collect();
+        let (proposer, counterparty) = (&managers[0], &managers[1]);
+        let (a, b) = (keys[0].public_key(), keys[1].public_key());
+
+        let config = ChannelConfig {
+            participants: vec![a, b],
+            initial_balances: [(a, Amount::new(1000)), (b, Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        };
+        let channel_id = MarketplaceChannel::compute_id(&config.participants);
+        for manager in &managers {
+            manager.create_channel(config.clone()).await.unwrap();
+            manager.activate_channel(&channel_id).await.unwrap();
+        }
 
+        // The counterparty co-signs and records the proposal but does not commit
+        let transfer = StateUpdate::Transfer { from: a, to: b, amount: Amount::new(100) };
+        let proposal = proposer.create_state_update(&channel_id, transfer.clone()).await.unwrap();
+        counterparty.receive_state_update(&channel_id, proposal.clone()).await.unwrap();
+        assert_eq!(counterparty.get_channel_info(&channel_id).await.unwrap().nonce, 0);
+        let pending = counterparty.list_pending_updates(&channel_id).await;
+        assert_eq!(pending.len(), 1);
+        let ack = pending[0].signatures[&b].clone();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
+        // A forged ack is refused; the real one completes and commits the update
+        let forged = keys[1].sign(b"something else");
+        assert!(matches!(
+            proposer.handle_state_update_ack(&channel_id, 1, forged).await,
+            Err(L2Error::InvalidSignature)
+        ));
+        let committed = proposer.handle_state_update_ack(&channel_id, 1, ack).await.unwrap().unwrap();
+        assert_eq!(proposer.get_channel_info(&channel_id).await.unwrap().nonce, 1);
+
+        counterparty.receive_state_update(&channel_id, committed).await.unwrap();
+        assert_eq!(counterparty.get_channel_info(&channel_id).await.unwrap().nonce, 1);
+        assert!(counterparty.list_pending_updates(&channel_id).await.is_empty());
+
+        // Once it has signed one update at a nonce, it will not sign another
+        let first = proposer.create_state_update(&channel_id, transfer.clone()).await.unwrap();
+        counterparty.receive_state_update(&channel_id, first).await.unwrap();
+        let mut conflicting = SignedStateUpdate::new(
+            StateUpdate::Transfer { from: a, to: b, amount: Amount::new(900) },
+            2,
+        );
+        conflicting.add_signature(a, keys[0].sign(&conflicting.signing_message()));
+        assert!(matches!(
+            counterparty.receive_state_update(&channel_id, conflicting).await,
+            Err(L2Error::InvalidStateTransition)
+        ));
+    }
 
     #[tokio::test]
-    async fn test_marketplace_manager() {
-        let temp_dir = TempDir::new().unwrap();
-        let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
-        let keypair = Arc::new(KeyPair::generate());
-        let manager = MarketplaceManager::new(storage, keypair.clone(), None);
+    async fn test_channel_open_handshake_over_sim_network() {
+        use async_trait::async_trait;
+        use tari_l2_p2p::sim::{SimConfig, SimNetwork};
+        use tari_l2_p2p::{L2Message, MessageHandler};
 
-        let kp2 = KeyPair::generate();
+        struct HandshakeHandler(Arc<MarketplaceManager>);
 
-        let mut balances = HashMap::new();
-        balances.insert(keypair.public_key(), Amount::new(1000));
-        balances.insert(kp2.public_key(), Amount::new(1000));
+        #[async_trait]
+        impl MessageHandler for HandshakeHandler {
+            async fn handle_message(&self, from: PublicKey, message: L2Message) -> Result<Option<L2Message>> {
+                match message {
+                    L2Message::ChannelOpenRequest { participants, initiator, initial_balances, challenge_period, signature } => {
+                        let request = ChannelRequest::new(initiator, participants, initial_balances, challenge_period)
+                            .with_signature(signature);
+                        self.0.handle_channel_request(request).await?;
+                    }
+                    L2Message::ChannelOpenResponse { channel_id, accepted, signature } => {
+                        self.0.handle_channel_open_response(&from, &channel_id, accepted, signature).await?;
+                    }
+                    _ => {}
+                }
+                Ok(None)
+            }
+        }
+
+        let sim = SimNetwork::new(SimConfig { seed: 7, latency_ms: 20, jitter_ms: 10, drop_rate: 0.0 });
+        let dirs: Vec<TempDir> = (0..2).map(|_| TempDir::new().unwrap()).collect();
+        let mut nodes = Vec::new();
+        for dir in &dirs {
+            let keypair = Arc::new(KeyPair::generate());
+            let manager = Arc::new(MarketplaceManager::new(
+                Arc::new(MarketplaceStorage::open(dir.path()).unwrap()),
+                keypair.clone(),
+                None,
+            ));
+            manager.set_network(sim.add_node(keypair.public_key())).await;
+            sim.set_handler(&keypair.public_key(), Arc::new(HandshakeHandler(manager.clone())));
+            nodes.push((keypair, manager));
+        }
+        let (initiator_key, initiator) = &nodes[0];
+        let (responder_key, responder) = &nodes[1];
 
+        let mut balances = HashMap::new();
+        balances.insert(initiator_key.public_key(), Amount::new(1000));
+        balances.insert(responder_key.public_key(), Amount::new(1000));
         let config = ChannelConfig {
-            participants: vec![keypair.public_key(), kp2.public_key()],
+            participants: vec![initiator_key.public_key(), responder_key.public_key()],
             initial_balances: balances,
             challenge_period: 3600,
         };
 
-        // Create channel
-        let channel_id = manager.create_channel(config).await.unwrap();
+        // A rejected proposal never opens on either side
+        responder.set_channel_policy(ChannelPolicy { auto_accept: false, ..Default::default() }).await;
+        let channel_id = initiator.propose_channel(config.clone()).await.unwrap();
+        sim.run_until_idle().await;
+        assert!(initiator.list_channels().await.is_empty());
 
-        // Activate channel
-        manager.activate_channel(&channel_id).await.unwrap();
+        // Only the counterparty can reject it
+        let stranger = KeyPair::generate().public_key();
+        assert!(matches!(
+            initiator.handle_channel_open_response(&stranger, &channel_id, false, None).await,
+            Err(L2Error::ParticipantNotFound)
+        ));
+        assert!(initiator.channel_proposals.read().await.contains_key(&channel_id));
 
-        // Get channel info
-        let info = manager.get_channel_info(&channel_id).await.unwrap();
-        assert_eq!(info.channel_id, channel_id);
+        responder.reject_channel_request(&channel_id).await.unwrap();
+        sim.run_until_idle().await;
+        assert!(initiator.list_channels().await.is_empty());
+        assert!(responder.list_channels().await.is_empty());
 
-        // Check balance
-        let balance = manager.get_balance(&channel_id, &keypair.public_key()).await.unwrap();
-        assert_eq!(balance, Amount::new(1000));
+        // An accepted proposal is persisted by both sides with both genesis signatures
+        responder.set_channel_policy(ChannelPolicy::default()).await;
+        let channel_id = initiator.propose_channel(config).await.unwrap();
+        assert!(initiator.get_channel_info(&channel_id).await.is_err());
+        sim.run_until_idle().await;
+
+        for (_, manager) in &nodes {
+            let channel = manager.storage.load_channel(&channel_id).unwrap().unwrap();
+            assert!(channel.genesis_complete());
+        }
     }
 }
-
@@ -0,0 +1,788 @@
+//! Upgrades for databases written by earlier versions.
+//!
+//! Records are bincode, which is not self-describing: appending a field to
+//! a stored struct makes every existing record of it fail to decode. The
+//! `meta` tree records the layout version a database was written with.
+//! Databases without one predate it and may hold records in any earlier
+//! layout, so each record is decoded against the layouts below, newest
+//! first, and rewritten in the current one.
+
+use bincode::Options;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sled::{Db, Tree};
+use std::collections::{HashMap, HashSet};
+use tari_l2_common::{Amount, Hash, L2Error, PublicKey, Signature, Timestamp, error::Result, musig::AggregateSignature};
+use tari_l2_state_channel::{
+    ChannelState, FiatReference, Listing, MarketplaceChannel, Order, OrderStatus, StateUpdate,
+    channel::{ChannelMetrics, ChannelStatus},
+    update::SignedStateUpdate,
+};
+use crate::journal::{JournalEntry, JournalStatus};
+use crate::storage::LoggedUpdate;
+
+/// Layout version of the records this build writes
+pub const SCHEMA_VERSION: u32 = 1;
+
+const VERSION_KEY: &[u8] = b"schema_version";
+
+/// Bring a database up to [`SCHEMA_VERSION`], rewriting records left in an
+/// earlier layout. Databases written by a newer version are refused rather
+/// than misread.
+pub fn upgrade(db: &Db) -> Result<()> {
+    let meta = db.open_tree("meta")
+        .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+    let version = match meta.get(VERSION_KEY)
+        .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+        Some(value) => {
+            let bytes: [u8; 4] = value.as_ref().try_into()
+                .map_err(|_| L2Error::DatabaseError("Malformed schema version".to_string()))?;
+            u32::from_be_bytes(bytes)
+        }
+        None => 0,
+    };
+
+    if version > SCHEMA_VERSION {
+        return Err(L2Error::DatabaseError(format!(
+            "Database schema version {} is newer than this build supports ({})",
+            version, SCHEMA_VERSION
+        )));
+    }
+    if version == SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    rewrite(db, "channels", decode_channel)?;
+    rewrite(db, "update_journal", decode_journal_entry)?;
+    rewrite(db, "listings", decode_listing)?;
+    rewrite(db, "channel_updates", decode_logged_update)?;
+
+    meta.insert(VERSION_KEY, &SCHEMA_VERSION.to_be_bytes())
+        .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+    meta.flush()
+        .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Decode every record of a tree and write back any whose encoding changed
+fn rewrite<T: Serialize>(db: &Db, name: &str, decode: fn(&[u8]) -> Option<Result<T>>) -> Result<()> {
+    let tree: Tree = db.open_tree(name)
+        .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+    for result in tree.iter() {
+        let (key, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        let record = decode(&value).ok_or_else(|| L2Error::DatabaseError(format!(
+            "A record in {} matches no known layout", name
+        )))??;
+
+        let encoded = bincode::serialize(&record)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+        if encoded != value.as_ref() {
+            tree.insert(key, encoded)
+                .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        }
+    }
+
+    tree.flush()
+        .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Decode `bytes` only if they are exactly one `T`, so a record in an older
+/// layout is never taken for a newer one that happens to fit its prefix
+fn strict<T: DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    bincode::options()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(bytes)
+        .ok()
+}
+
+fn decode_channel(bytes: &[u8]) -> Option<Result<MarketplaceChannel>> {
+    if let Some(channel) = strict::<MarketplaceChannel>(bytes) {
+        return Some(Ok(channel));
+    }
+    if let Some(channel) = strict::<ChannelV7>(bytes) {
+        return Some(channel.upgrade());
+    }
+    if let Some(channel) = strict::<ChannelV6>(bytes) {
+        return Some(channel.upgrade());
+    }
+    if let Some(channel) = strict::<ChannelV5>(bytes) {
+        return Some(channel.upgrade());
+    }
+    if let Some(channel) = strict::<ChannelV4>(bytes) {
+        return Some(channel.upgrade());
+    }
+    if let Some(channel) = strict::<ChannelV3>(bytes) {
+        return Some(channel.upgrade());
+    }
+    if let Some(channel) = strict::<ChannelV2>(bytes) {
+        return Some(channel.upgrade());
+    }
+    if let Some(channel) = strict::<ChannelV1>(bytes) {
+        return Some(channel.upgrade());
+    }
+    if let Some(channel) = strict::<ChannelV0>(bytes) {
+        return Some(channel.upgrade());
+    }
+    None
+}
+
+fn decode_journal_entry(bytes: &[u8]) -> Option<Result<JournalEntry>> {
+    if let Some(entry) = strict::<JournalEntry>(bytes) {
+        return Some(Ok(entry));
+    }
+    if let Some(entry) = strict::<JournalEntryV1>(bytes) {
+        return Some(Ok(entry.upgrade()));
+    }
+    if let Some(entry) = strict::<JournalEntryV0>(bytes) {
+        return Some(Ok(entry.upgrade()));
+    }
+    None
+}
+
+fn decode_logged_update(bytes: &[u8]) -> Option<Result<LoggedUpdate>> {
+    if let Some(logged) = strict::<LoggedUpdate>(bytes) {
+        return Some(Ok(logged));
+    }
+    if let Some(logged) = strict::<LoggedUpdateV0>(bytes) {
+        return Some(Ok(LoggedUpdate { update: logged.update, metrics: logged.metrics.upgrade() }));
+    }
+    None
+}
+
+fn decode_listing(bytes: &[u8]) -> Option<Result<Listing>> {
+    if let Some(listing) = strict::<Listing>(bytes) {
+        return Some(Ok(listing));
+    }
+    if let Some(listing) = strict::<ListingV0>(bytes) {
+        return Some(Ok(listing.upgrade()));
+    }
+    None
+}
+
+/// Channels written before state updates carried aggregate signatures
+type ChannelV0 = LegacyChannel<(), (), (), (), (), (), ()>;
+
+/// Channels written before channels recorded their opening balances
+type ChannelV1 = LegacyChannel<Option<AggregateSignature>, (), (), (), (), (), ()>;
+
+/// Channels written before opening balances were signed by both parties
+type ChannelV2 = LegacyChannel<Option<AggregateSignature>, HashMap<PublicKey, Amount>, (), (), (), (), ()>;
+
+/// Channels written before channels recorded when a close began
+type ChannelV3 = LegacyChannel<
+    Option<AggregateSignature>,
+    HashMap<PublicKey, Amount>,
+    HashMap<PublicKey, Signature>,
+    (),
+    (),
+    (),
+    (),
+>;
+
+/// Channels written before listings carried fiat reference prices
+type ChannelV4 = LegacyChannel<
+    Option<AggregateSignature>,
+    HashMap<PublicKey, Amount>,
+    HashMap<PublicKey, Signature>,
+    Option<Timestamp>,
+    (),
+    (),
+    (),
+>;
+
+/// Channels written before channel state tracked escrow locks
+type ChannelV5 = LegacyChannel<
+    Option<AggregateSignature>,
+    HashMap<PublicKey, Amount>,
+    HashMap<PublicKey, Signature>,
+    Option<Timestamp>,
+    Option<FiatReference>,
+    (),
+    (),
+>;
+
+/// Channels written before channels kept running metrics
+type ChannelV6 = LegacyChannel<
+    Option<AggregateSignature>,
+    HashMap<PublicKey, Amount>,
+    HashMap<PublicKey, Signature>,
+    Option<Timestamp>,
+    Option<FiatReference>,
+    HashMap<Hash, Amount>,
+    (),
+>;
+
+/// Channels written before metrics recorded when and where the last
+/// checkpoint landed
+type ChannelV7 = LegacyChannel<
+    Option<AggregateSignature>,
+    HashMap<PublicKey, Amount>,
+    HashMap<PublicKey, Signature>,
+    Option<Timestamp>,
+    Option<FiatReference>,
+    HashMap<Hash, Amount>,
+    MetricsV0,
+>;
+
+/// Journal entries written before state updates carried aggregate signatures
+type JournalEntryV0 = LegacyJournalEntry<(), ()>;
+
+/// Journal entries written before listings carried fiat reference prices
+type JournalEntryV1 = LegacyJournalEntry<(), Option<AggregateSignature>>;
+
+/// Listings written before they carried fiat reference prices
+type ListingV0 = LegacyListing<()>;
+
+/// Logged updates written before metrics recorded when and where the last
+/// checkpoint landed
+#[derive(Serialize, Deserialize)]
+struct LoggedUpdateV0 {
+    update: SignedStateUpdate,
+    metrics: MetricsV0,
+}
+
+/// [`ChannelMetrics`] as first written, without checkpoint time and height
+#[derive(Serialize, Deserialize)]
+struct MetricsV0 {
+    updates: u64,
+    first_update_at: Option<Timestamp>,
+    last_update_at: Option<Timestamp>,
+    volume: Amount,
+    checkpointed_nonce: u64,
+}
+
+impl MetricsV0 {
+    fn upgrade(self) -> ChannelMetrics {
+        ChannelMetrics {
+            updates: self.updates,
+            first_update_at: self.first_update_at,
+            last_update_at: self.last_update_at,
+            volume: self.volume,
+            checkpointed_nonce: self.checkpointed_nonce,
+            checkpointed_at: None,
+            checkpointed_height: None,
+        }
+    }
+}
+
+/// A field that later layouts added, read as `()` from layouts before it
+trait Added<T> {
+    fn value(self) -> Option<T>;
+}
+
+impl<T> Added<T> for () {
+    fn value(self) -> Option<T> {
+        None
+    }
+}
+
+impl Added<Option<AggregateSignature>> for Option<AggregateSignature> {
+    fn value(self) -> Option<Option<AggregateSignature>> {
+        Some(self)
+    }
+}
+
+impl Added<HashMap<PublicKey, Amount>> for HashMap<PublicKey, Amount> {
+    fn value(self) -> Option<HashMap<PublicKey, Amount>> {
+        Some(self)
+    }
+}
+
+impl Added<HashMap<PublicKey, Signature>> for HashMap<PublicKey, Signature> {
+    fn value(self) -> Option<HashMap<PublicKey, Signature>> {
+        Some(self)
+    }
+}
+
+impl Added<Option<Timestamp>> for Option<Timestamp> {
+    fn value(self) -> Option<Option<Timestamp>> {
+        Some(self)
+    }
+}
+
+impl Added<Option<FiatReference>> for Option<FiatReference> {
+    fn value(self) -> Option<Option<FiatReference>> {
+        Some(self)
+    }
+}
+
+impl Added<HashMap<Hash, Amount>> for HashMap<Hash, Amount> {
+    fn value(self) -> Option<HashMap<Hash, Amount>> {
+        Some(self)
+    }
+}
+
+impl Added<ChannelMetrics> for MetricsV0 {
+    fn value(self) -> Option<ChannelMetrics> {
+        Some(self.upgrade())
+    }
+}
+
+/// A [`MarketplaceChannel`] in any earlier layout. Each type parameter is
+/// a field in the order it was added, `()` for layouts written before it.
+#[derive(Serialize, Deserialize)]
+struct LegacyChannel<A, I, G, C, F, E, M> {
+    channel_id: Hash,
+    participants: Vec<PublicKey>,
+    collateral: Amount,
+    state: LegacyState<F, E>,
+    status: ChannelStatus,
+    challenge_period: u64,
+    initial_balances: I,
+    state_history: Vec<LegacySignedUpdate<F, A>>,
+    genesis_signatures: G,
+    close_initiated_at: C,
+    metrics: M,
+}
+
+impl<A, I, G, C, F, E, M> LegacyChannel<A, I, G, C, F, E, M>
+where
+    A: Added<Option<AggregateSignature>>,
+    I: Added<HashMap<PublicKey, Amount>>,
+    G: Added<HashMap<PublicKey, Signature>>,
+    C: Added<Option<Timestamp>>,
+    F: Added<Option<FiatReference>>,
+    E: Added<HashMap<Hash, Amount>>,
+    M: Added<ChannelMetrics>,
+{
+    fn upgrade(self) -> Result<MarketplaceChannel> {
+        let state = self.state.upgrade();
+        let state_history: Vec<SignedStateUpdate> = self.state_history.into_iter()
+            .map(LegacySignedUpdate::upgrade)
+            .collect();
+
+        let initial_balances = match self.initial_balances.value() {
+            Some(balances) => balances,
+            None => opening_balances(&state.balances, &state_history)?,
+        };
+        let metrics = self.metrics.value().unwrap_or_else(|| ChannelMetrics {
+            updates: state_history.len() as u64,
+            ..ChannelMetrics::default()
+        });
+
+        Ok(MarketplaceChannel {
+            channel_id: self.channel_id,
+            participants: self.participants,
+            collateral: self.collateral,
+            state,
+            status: self.status,
+            challenge_period: self.challenge_period,
+            initial_balances,
+            state_history,
+            genesis_signatures: self.genesis_signatures.value().unwrap_or_default(),
+            close_initiated_at: self.close_initiated_at.value().flatten(),
+            metrics,
+        })
+    }
+}
+
+/// Balances a channel opened with, found by undoing the moves in its
+/// history. Channels that did not record them predate escrow locks, so
+/// only transfers and completed orders moved funds.
+fn opening_balances(
+    balances: &HashMap<PublicKey, Amount>,
+    history: &[SignedStateUpdate],
+) -> Result<HashMap<PublicKey, Amount>> {
+    let mut opening: HashMap<PublicKey, i128> = balances.iter()
+        .map(|(participant, amount)| (*participant, amount.value() as i128))
+        .collect();
+    let mut orders: HashMap<Hash, &Order> = HashMap::new();
+    let mut completed = HashSet::new();
+
+    for signed in history {
+        match &signed.update {
+            StateUpdate::Transfer { from, to, amount } => {
+                *opening.entry(*from).or_default() += amount.value() as i128;
+                *opening.entry(*to).or_default() -= amount.value() as i128;
+            }
+            StateUpdate::CreateOrder { order } => {
+                orders.insert(order.id, order);
+            }
+            StateUpdate::UpdateOrderStatus { order_id, status: OrderStatus::Completed } => {
+                let Some(order) = orders.get(order_id) else { continue };
+                if completed.insert(*order_id) {
+                    *opening.entry(order.buyer).or_default() += order.amount.value() as i128;
+                    *opening.entry(order.seller).or_default() -= order.amount.value() as i128;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    opening.into_iter()
+        .map(|(participant, amount)| {
+            u64::try_from(amount)
+                .map(|amount| (participant, Amount::new(amount)))
+                .map_err(|_| L2Error::DatabaseError(
+                    "Channel history does not unwind to its balances".to_string()
+                ))
+        })
+        .collect()
+}
+
+/// A [`ChannelState`] in any earlier layout
+#[derive(Serialize, Deserialize)]
+struct LegacyState<F, E> {
+    nonce: u64,
+    balances: HashMap<PublicKey, Amount>,
+    listings: Vec<LegacyListing<F>>,
+    orders: Vec<Order>,
+    escrow_locks: E,
+}
+
+impl<F: Added<Option<FiatReference>>, E: Added<HashMap<Hash, Amount>>> LegacyState<F, E> {
+    fn upgrade(self) -> ChannelState {
+        ChannelState {
+            nonce: self.nonce,
+            balances: self.balances,
+            listings: self.listings.into_iter().map(LegacyListing::upgrade).collect(),
+            orders: self.orders,
+            escrow_locks: self.escrow_locks.value().unwrap_or_default(),
+        }
+    }
+}
+
+/// A [`Listing`] in any earlier layout
+#[derive(Serialize, Deserialize)]
+struct LegacyListing<F> {
+    id: Hash,
+    seller: PublicKey,
+    title: String,
+    description: String,
+    price: Amount,
+    ipfs_hash: String,
+    active: bool,
+    category: String,
+    fiat_reference: F,
+}
+
+impl<F: Added<Option<FiatReference>>> LegacyListing<F> {
+    fn upgrade(self) -> Listing {
+        Listing {
+            id: self.id,
+            seller: self.seller,
+            title: self.title,
+            description: self.description,
+            price: self.price,
+            ipfs_hash: self.ipfs_hash,
+            active: self.active,
+            category: self.category,
+            fiat_reference: self.fiat_reference.value().flatten(),
+        }
+    }
+}
+
+/// A [`StateUpdate`] in any earlier layout. Variants are only ever
+/// appended, so old records are a subset of these.
+#[derive(Serialize, Deserialize)]
+enum LegacyUpdate<F> {
+    Transfer { from: PublicKey, to: PublicKey, amount: Amount },
+    CreateListing { listing: LegacyListing<F> },
+    UpdateListing { listing_id: Hash, active: bool },
+    CreateOrder { order: Order },
+    UpdateOrderStatus { order_id: Hash, status: OrderStatus },
+    LockEscrow { order_id: Hash },
+}
+
+impl<F: Added<Option<FiatReference>>> LegacyUpdate<F> {
+    fn upgrade(self) -> StateUpdate {
+        match self {
+            LegacyUpdate::Transfer { from, to, amount } => StateUpdate::Transfer { from, to, amount },
+            LegacyUpdate::CreateListing { listing } => StateUpdate::CreateListing { listing: listing.upgrade() },
+            LegacyUpdate::UpdateListing { listing_id, active } => StateUpdate::UpdateListing { listing_id, active },
+            LegacyUpdate::CreateOrder { order } => StateUpdate::CreateOrder { order },
+            LegacyUpdate::UpdateOrderStatus { order_id, status } => StateUpdate::UpdateOrderStatus { order_id, status },
+            LegacyUpdate::LockEscrow { order_id } => StateUpdate::LockEscrow { order_id },
+        }
+    }
+}
+
+/// A [`SignedStateUpdate`] in any earlier layout
+#[derive(Serialize, Deserialize)]
+struct LegacySignedUpdate<F, A> {
+    update: LegacyUpdate<F>,
+    nonce: u64,
+    signatures: HashMap<PublicKey, Signature>,
+    aggregate: A,
+}
+
+impl<F: Added<Option<FiatReference>>, A: Added<Option<AggregateSignature>>> LegacySignedUpdate<F, A> {
+    fn upgrade(self) -> SignedStateUpdate {
+        SignedStateUpdate {
+            update: self.update.upgrade(),
+            nonce: self.nonce,
+            signatures: self.signatures,
+            aggregate: self.aggregate.value().flatten(),
+        }
+    }
+}
+
+/// A [`JournalEntry`] in any earlier layout
+#[derive(Serialize, Deserialize)]
+struct LegacyJournalEntry<F, A> {
+    channel_id: Hash,
+    update: LegacySignedUpdate<F, A>,
+    status: JournalStatus,
+    recorded_at: Timestamp,
+}
+
+impl<F: Added<Option<FiatReference>>, A: Added<Option<AggregateSignature>>> LegacyJournalEntry<F, A> {
+    fn upgrade(self) -> JournalEntry {
+        JournalEntry {
+            channel_id: self.channel_id,
+            update: self.update.upgrade(),
+            status: self.status,
+            recorded_at: self.recorded_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MarketplaceStorage;
+    use tari_l2_common::crypto::KeyPair;
+    use tempfile::TempDir;
+
+    const CHANNEL_ID: Hash = Hash::new([7u8; 32]);
+
+    /// A channel alice and bob opened with 1000 each, in which alice paid
+    /// bob 300 and listed an item, in the layout whose added fields are
+    /// `added`
+    fn paid_channel<A, I, G, C, F, E, M>(
+        alice: PublicKey,
+        bob: PublicKey,
+        added: (A, I, G, C, F, E, M),
+    ) -> LegacyChannel<A, I, G, C, F, E, M> {
+        let (aggregate, initial_balances, genesis_signatures, close_initiated_at, fiat_reference, escrow_locks, metrics) = added;
+
+        let listing = LegacyListing {
+            id: Hash::new([9u8; 32]),
+            seller: alice,
+            title: "Lamp".to_string(),
+            description: "Brass desk lamp".to_string(),
+            price: Amount::new(100),
+            ipfs_hash: String::new(),
+            active: true,
+            category: "home".to_string(),
+            fiat_reference,
+        };
+        let transfer = LegacySignedUpdate {
+            update: LegacyUpdate::Transfer { from: alice, to: bob, amount: Amount::new(300) },
+            nonce: 1,
+            signatures: HashMap::new(),
+            aggregate,
+        };
+
+        LegacyChannel {
+            channel_id: CHANNEL_ID,
+            participants: vec![alice, bob],
+            collateral: Amount::new(2000),
+            state: LegacyState {
+                nonce: 1,
+                balances: [(alice, Amount::new(700)), (bob, Amount::new(1300))].into_iter().collect(),
+                listings: vec![listing],
+                orders: Vec::new(),
+                escrow_locks,
+            },
+            status: ChannelStatus::Active,
+            challenge_period: 3600,
+            initial_balances,
+            state_history: vec![transfer],
+            genesis_signatures,
+            close_initiated_at,
+            metrics,
+        }
+    }
+
+    /// Metrics of [`paid_channel`] as first written
+    fn metrics_v0() -> MetricsV0 {
+        MetricsV0 {
+            updates: 1,
+            first_update_at: None,
+            last_update_at: None,
+            volume: Amount::new(300),
+            checkpointed_nonce: 0,
+        }
+    }
+
+    /// Store an encoded channel in a database without a schema version and
+    /// load it back through the upgrade
+    fn reload_channel(record: &[u8]) -> MarketplaceChannel {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let db = sled::open(temp_dir.path()).unwrap();
+            db.open_tree("channels").unwrap()
+                .insert(CHANNEL_ID.to_vec(), record).unwrap();
+            db.flush().unwrap();
+        }
+
+        let storage = MarketplaceStorage::open(temp_dir.path()).unwrap();
+        storage.load_channel(&CHANNEL_ID).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_upgrades_records_written_before_aggregate_signatures() {
+        let temp_dir = TempDir::new().unwrap();
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+        let channel_id = CHANNEL_ID;
+
+        let channel: ChannelV0 = paid_channel(alice, bob, ((), (), (), (), (), (), ()));
+        let entry: JournalEntryV0 = LegacyJournalEntry {
+            channel_id,
+            update: LegacySignedUpdate {
+                update: LegacyUpdate::Transfer { from: bob, to: alice, amount: Amount::new(50) },
+                nonce: 2,
+                signatures: HashMap::new(),
+                aggregate: (),
+            },
+            status: JournalStatus::Proposed,
+            recorded_at: Timestamp::from_secs(1_000),
+        };
+
+        let mut journal_key = channel_id.to_vec();
+        journal_key.extend_from_slice(&2u64.to_be_bytes());
+
+        {
+            let db = sled::open(temp_dir.path()).unwrap();
+            db.open_tree("channels").unwrap()
+                .insert(channel_id.to_vec(), bincode::serialize(&channel).unwrap()).unwrap();
+            db.open_tree("update_journal").unwrap()
+                .insert(journal_key, bincode::serialize(&entry).unwrap()).unwrap();
+            db.flush().unwrap();
+        }
+
+        let storage = MarketplaceStorage::open(temp_dir.path()).unwrap();
+        let loaded = storage.load_channel(&channel_id).unwrap().unwrap();
+        assert_eq!(loaded.state_history.len(), 1);
+        assert!(loaded.state_history[0].aggregate.is_none());
+        assert_eq!(loaded.initial_balances[&alice], Amount::new(1000));
+        assert_eq!(loaded.initial_balances[&bob], Amount::new(1000));
+        assert_eq!(loaded.metrics.updates, 1);
+
+        let journal = storage.load_journal().unwrap();
+        assert_eq!(journal.len(), 1);
+        assert_eq!(journal[0].update.nonce, 2);
+        drop(storage);
+
+        // Upgraded databases are stamped and left alone on the next open
+        let storage = MarketplaceStorage::open(temp_dir.path()).unwrap();
+        assert!(storage.load_channel(&channel_id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_upgrades_channels_from_each_earlier_layout() {
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+
+        let opened: HashMap<PublicKey, Amount> = [(alice, Amount::new(1000)), (bob, Amount::new(1000))].into_iter().collect();
+
+        let layouts = [
+            bincode::serialize(&paid_channel(alice, bob, (None::<AggregateSignature>, (), (), (), (), (), ()))).unwrap(),
+            bincode::serialize(&paid_channel(alice, bob, (None::<AggregateSignature>, opened.clone(), (), (), (), (), ()))).unwrap(),
+            bincode::serialize(&paid_channel(alice, bob, (None::<AggregateSignature>, opened.clone(), HashMap::<PublicKey, Signature>::new(), (), (), (), ()))).unwrap(),
+            bincode::serialize(&paid_channel(alice, bob, (None::<AggregateSignature>, opened.clone(), HashMap::<PublicKey, Signature>::new(), None::<Timestamp>, (), (), ()))).unwrap(),
+            bincode::serialize(&paid_channel(alice, bob, (None::<AggregateSignature>, opened.clone(), HashMap::<PublicKey, Signature>::new(), None::<Timestamp>, None::<FiatReference>, (), ()))).unwrap(),
+            bincode::serialize(&paid_channel(alice, bob, (None::<AggregateSignature>, opened.clone(), HashMap::<PublicKey, Signature>::new(), None::<Timestamp>, None::<FiatReference>, HashMap::<Hash, Amount>::new(), ()))).unwrap(),
+            bincode::serialize(&paid_channel(alice, bob, (None::<AggregateSignature>, opened.clone(), HashMap::<PublicKey, Signature>::new(), None::<Timestamp>, None::<FiatReference>, HashMap::<Hash, Amount>::new(), metrics_v0()))).unwrap(),
+        ];
+
+        for record in layouts {
+            let loaded = reload_channel(&record);
+            assert_eq!(loaded.state_history.len(), 1);
+            assert_eq!(loaded.state.listings.len(), 1);
+            assert_eq!(loaded.initial_balances[&alice], Amount::new(1000));
+            assert_eq!(loaded.initial_balances[&bob], Amount::new(1000));
+            assert_eq!(loaded.metrics.updates, 1);
+        }
+    }
+
+    #[test]
+    fn test_upgrades_listings_written_before_fiat_references() {
+        let temp_dir = TempDir::new().unwrap();
+        let seller = KeyPair::generate().public_key();
+
+        let channel: ChannelV0 = paid_channel(seller, KeyPair::generate().public_key(), ((), (), (), (), (), (), ()));
+        let listing: ListingV0 = channel.state.listings.into_iter().next().unwrap();
+        let listing_key = listing.id.to_vec();
+        let stored_listing = bincode::serialize(&listing).unwrap();
+        let entry: JournalEntryV1 = LegacyJournalEntry {
+            channel_id: CHANNEL_ID,
+            update: LegacySignedUpdate {
+                update: LegacyUpdate::CreateListing { listing: LegacyListing { id: Hash::new([8u8; 32]), ..listing } },
+                nonce: 2,
+                signatures: HashMap::new(),
+                aggregate: None,
+            },
+            status: JournalStatus::Acked,
+            recorded_at: Timestamp::from_secs(1_000),
+        };
+
+        {
+            let db = sled::open(temp_dir.path()).unwrap();
+            db.open_tree("listings").unwrap()
+                .insert(listing_key, stored_listing).unwrap();
+            db.open_tree("update_journal").unwrap()
+                .insert(CHANNEL_ID.to_vec(), bincode::serialize(&entry).unwrap()).unwrap();
+            db.flush().unwrap();
+        }
+
+        let storage = MarketplaceStorage::open(temp_dir.path()).unwrap();
+        let listings = storage.load_all_listings().unwrap();
+        assert_eq!(listings.len(), 1);
+        assert_eq!(listings[0].seller, seller);
+        assert!(listings[0].fiat_reference.is_none());
+
+        let journal = storage.load_journal().unwrap();
+        assert_eq!(journal.len(), 1);
+        assert!(matches!(&journal[0].update.update, StateUpdate::CreateListing { listing } if listing.seller == seller));
+    }
+
+    #[test]
+    fn test_upgrades_logged_updates_written_before_checkpoint_times() {
+        let temp_dir = TempDir::new().unwrap();
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+
+        let channel = paid_channel(alice, bob, ((), (), (), (), (), (), ())).upgrade().unwrap();
+        let logged = LoggedUpdateV0 {
+            update: channel.state_history[0].clone(),
+            metrics: metrics_v0(),
+        };
+        let mut key = CHANNEL_ID.to_vec();
+        key.extend_from_slice(&1u64.to_be_bytes());
+
+        {
+            let db = sled::open(temp_dir.path()).unwrap();
+            db.open_tree("channels").unwrap()
+                .insert(CHANNEL_ID.to_vec(), bincode::serialize(&channel).unwrap()).unwrap();
+            db.open_tree("channel_updates").unwrap()
+                .insert(key, bincode::serialize(&logged).unwrap()).unwrap();
+            db.flush().unwrap();
+        }
+
+        let storage = MarketplaceStorage::open(temp_dir.path()).unwrap();
+        let loaded = storage.load_channel(&CHANNEL_ID).unwrap().unwrap();
+        assert_eq!(loaded.state_history.len(), 1);
+        assert!(loaded.metrics.checkpointed_at.is_none());
+    }
+
+    #[test]
+    fn test_refuses_databases_from_a_newer_version() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let db = sled::open(temp_dir.path()).unwrap();
+            db.open_tree("meta").unwrap()
+                .insert(VERSION_KEY, &(SCHEMA_VERSION + 1).to_be_bytes()).unwrap();
+            db.flush().unwrap();
+        }
+
+        assert!(MarketplaceStorage::open(temp_dir.path()).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Persistent queue for P2P messages that must reach their peer.
+//!
+//! Sends can fail outright (the swarm is still starting, publish errors) or
+//! vanish in transit. Messages queued here are retried with exponential
+//! backoff until the network accepts them or, for state update proposals,
+//! until the peer acks them.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{Hash, PublicKey, Signature, Timestamp};
+use tari_l2_p2p::L2Message;
+
+/// Attempts after which an undelivered message is given up on
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 12;
+
+/// Delay before the first retry; it doubles with each further attempt
+const BASE_RETRY_SECS: u64 = 5;
+
+/// Longest delay between retries
+const MAX_RETRY_SECS: u64 = 600;
+
+/// What the peer sends back to show it received a message
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum Confirmation {
+    /// A `StateUpdateAck` for this update carrying the peer's signature
+    StateUpdateAck { channel_id: Hash, nonce: u64 },
+}
+
+/// A message waiting to be sent, or to be confirmed by its peer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub id: Hash,
+
+    /// Peer the message is addressed to
+    pub peer: PublicKey,
+
+    pub message: L2Message,
+
+    /// Set for messages that stay queued until the peer confirms them;
+    /// others are done once the network accepts them
+    pub confirmation: Option<Confirmation>,
+
+    /// Sends made so far
+    pub attempts: u32,
+
+    /// When the message is next due
+    pub next_attempt: Timestamp,
+}
+
+impl OutboxEntry {
+    /// Queue `message` for `peer`, due immediately. State update proposals
+    /// wait for the peer's ack.
+    pub fn new(peer: PublicKey, message: L2Message, now: Timestamp) -> Self {
+        let confirmation = match &message {
+            L2Message::StateUpdateProposal { channel_id, update } => Some(Confirmation::StateUpdateAck {
+                channel_id: *channel_id,
+                nonce: update.nonce,
+            }),
+            _ => None,
+        };
+
+        Self {
+            id: Hash::random(),
+            peer,
+            message,
+            confirmation,
+            attempts: 0,
+            next_attempt: now,
+        }
+    }
+
+    pub fn is_due(&self, now: Timestamp) -> bool {
+        now >= self.next_attempt
+    }
+
+    /// Count an attempt and push the next one back exponentially
+    pub fn schedule_retry(&mut self, now: Timestamp) {
+        let delay = BASE_RETRY_SECS
+            .saturating_mul(1u64 << self.attempts.min(16))
+            .min(MAX_RETRY_SECS);
+        self.attempts += 1;
+        self.next_attempt = Timestamp::from_secs(now.as_secs().saturating_add(delay));
+    }
+
+    /// Whether an ack with `signature` from our peer confirms this entry
+    pub fn confirmed_by(&self, channel_id: &Hash, nonce: u64, signature: &Signature) -> bool {
+        let Some(Confirmation::StateUpdateAck { channel_id: expected, nonce: expected_nonce }) = &self.confirmation else {
+            return false;
+        };
+        let L2Message::StateUpdateProposal { update, .. } = &self.message else {
+            return false;
+        };
+        expected == channel_id
+            && *expected_nonce == nonce
+            && self.peer.verify(&update.signing_message(), signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_backoff_is_capped() {
+        let now = Timestamp::from_secs(1_700_000_000);
+        let mut entry = OutboxEntry::new(PublicKey::new([1; 32]), L2Message::Ping, now);
+        assert!(entry.is_due(now));
+        assert!(entry.confirmation.is_none());
+
+        entry.schedule_retry(now);
+        assert_eq!(entry.next_attempt, Timestamp::from_secs(now.as_secs() + 5));
+        entry.schedule_retry(now);
+        assert_eq!(entry.next_attempt, Timestamp::from_secs(now.as_secs() + 10));
+
+        for _ in 0..20 {
+            entry.schedule_retry(now);
+        }
+        assert_eq!(entry.next_attempt, Timestamp::from_secs(now.as_secs() + MAX_RETRY_SECS));
+    }
+}
@@ -0,0 +1,67 @@
+//! Where a wallet wants settlements paid.
+//!
+//! Each L2 key can register an L1 payout address and whether it would
+//! rather be paid inside a channel or on chain. Settlement and refund flows
+//! consult the preference of the party being paid, so funds reach the
+//! address its owner chose rather than whichever key signed for them. An
+//! address set on a single escrow still takes precedence.
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use tari_common_types::tari_address::TariAddress;
+use tari_l2_common::{PublicKey, Timestamp};
+
+/// How a party prefers to receive escrow payouts and refunds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutMethod {
+    /// Inside the channel, where escrows funded from it settle. An escrow
+    /// funded on L1 can only pay out there, so it still needs the address.
+    #[default]
+    InChannel,
+    /// As an L1 payment to the registered address
+    OnChain,
+}
+
+/// A wallet's payout preference
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayoutPreference {
+    pub public_key: PublicKey,
+    /// Tari address L1 payouts go to
+    pub address: Option<String>,
+    pub method: PayoutMethod,
+    pub updated_at: Timestamp,
+}
+
+impl PayoutPreference {
+    /// A preference with a checked address. On-chain payouts need one.
+    pub fn new(public_key: PublicKey, address: Option<String>, method: PayoutMethod, now: Timestamp) -> Result<Self, String> {
+        match &address {
+            Some(address) => {
+                TariAddress::from_str(address).map_err(|e| format!("Invalid Tari address {}: {}", address, e))?;
+            }
+            None if method == PayoutMethod::OnChain => {
+                return Err("On-chain payouts need an address".to_string());
+            }
+            None => {}
+        }
+        Ok(Self { public_key, address, method, updated_at: now })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_on_chain_payouts_need_a_valid_address() {
+        let key = PublicKey::new([4u8; 32]);
+        let now = Timestamp::from_secs(1);
+
+        let preference = PayoutPreference::new(key, None, PayoutMethod::InChannel, now).unwrap();
+        assert_eq!(preference.method, PayoutMethod::default());
+        assert!(PayoutPreference::new(key, None, PayoutMethod::OnChain, now).is_err());
+        assert!(PayoutPreference::new(key, Some("not an address".to_string()), PayoutMethod::InChannel, now).is_err());
+        assert_eq!(serde_json::to_value(PayoutMethod::OnChain).unwrap(), "on_chain");
+    }
+}
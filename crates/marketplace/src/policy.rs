@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tari_l2_common::{Amount, Hash, L2Error, PublicKey, Signature, Timestamp, error::Result};
+use tari_l2_state_channel::{ChannelConfig, MarketplaceChannel};
+
+/// Rules evaluated against incoming channel open requests
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelPolicy {
+    /// Accept requests that pass every rule without operator approval
+    pub auto_accept: bool,
+
+    /// Minimum total collateral for the channel
+    pub min_collateral: u64,
+
+    /// Maximum total collateral for the channel (None = unlimited)
+    pub max_collateral: Option<u64>,
+
+    /// Counterparties allowed to open channels, as hex public keys (empty = anyone)
+    pub allowed_counterparties: Vec<String>,
+
+    /// Maximum number of channels that are not yet closed (None = unlimited)
+    pub max_open_channels: Option<usize>,
+
+    /// Minimum profile rating required from the initiator (None = not required)
+    pub min_reputation: Option<f32>,
+
+    /// Shortest challenge period accepted for a channel (seconds)
+    pub min_challenge_period: u64,
+
+    /// Longest challenge period accepted for a channel (seconds)
+    pub max_challenge_period: u64,
+}
+
+impl Default for ChannelPolicy {
+    fn default() -> Self {
+        Self {
+            auto_accept: true,
+            min_collateral: 0,
+            max_collateral: None,
+            allowed_counterparties: Vec::new(),
+            max_open_channels: Some(100),
+            min_reputation: None,
+            min_challenge_period: 3600,
+            max_challenge_period: 30 * 24 * 3600,
+        }
+    }
+}
+
+/// Outcome of evaluating a channel request against the policy
+#[derive(Clone, Debug, PartialEq)]
+pub enum PolicyDecision {
+    /// All rules passed, the channel can be opened immediately
+    Accept,
+    /// The request needs manual approval (reason attached)
+    Review(String),
+}
+
+impl ChannelPolicy {
+    /// Reject challenge periods outside the configured bounds. Unlike the
+    /// other rules this is not open to review: a channel with a too short
+    /// window cannot be disputed safely.
+    pub fn check_challenge_period(&self, challenge_period: u64) -> Result<()> {
+        if challenge_period < self.min_challenge_period || challenge_period > self.max_challenge_period {
+            return Err(L2Error::InvalidParameter(format!(
+                "Challenge period {}s outside allowed range {}s..={}s",
+                challenge_period, self.min_challenge_period, self.max_challenge_period
+            )));
+        }
+        Ok(())
+    }
+
+    /// Evaluate a request given the number of open channels and the initiator's rating
+    pub fn evaluate(
+        &self,
+        request: &ChannelRequest,
+        open_channels: usize,
+        reputation: Option<f32>,
+    ) -> PolicyDecision {
+        if !self.auto_accept {
+            return PolicyDecision::Review("Auto-accept is disabled".to_string());
+        }
+
+        let collateral = request.collateral().value();
+        if collateral < self.min_collateral {
+            return PolicyDecision::Review(format!(
+                "Collateral {} below minimum {}", collateral, self.min_collateral
+            ));
+        }
+
+        if let Some(max) = self.max_collateral {
+            if collateral > max {
+                return PolicyDecision::Review(format!(
+                    "Collateral {} above maximum {}", collateral, max
+                ));
+            }
+        }
+
+        if !self.allowed_counterparties.is_empty() {
+            let initiator = hex::encode(request.initiator.as_bytes());
+            if !self.allowed_counterparties.iter().any(|pk| pk.eq_ignore_ascii_case(&initiator)) {
+                return PolicyDecision::Review(format!("Counterparty {} not in allow list", initiator));
+            }
+        }
+
+        if let Some(max) = self.max_open_channels {
+            if open_channels >= max {
+                return PolicyDecision::Review(format!(
+                    "Open channel limit reached ({}/{})", open_channels, max
+                ));
+            }
+        }
+
+        if let Some(min) = self.min_reputation {
+            match reputation {
+                Some(rating) if rating >= min => {}
+                Some(rating) => {
+                    return PolicyDecision::Review(format!(
+                        "Counterparty rating {:.1} below required {:.1}", rating, min
+                    ));
+                }
+                None => {
+                    return PolicyDecision::Review("Counterparty has no known rating".to_string());
+                }
+            }
+        }
+
+        PolicyDecision::Accept
+    }
+}
+
+/// Incoming request to open a channel with this node
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChannelRequest {
+    /// ID the channel will have once opened
+    pub channel_id: Hash,
+
+    /// Node that requested the channel
+    pub initiator: PublicKey,
+
+    /// Channel participants
+    pub participants: Vec<PublicKey>,
+
+    /// Proposed opening balances
+    pub initial_balances: HashMap<PublicKey, Amount>,
+
+    /// Proposed challenge period in seconds
+    pub challenge_period: u64,
+
+    /// When the request was received
+    pub received_at: Timestamp,
+
+    /// Why the request was not auto-accepted (if queued)
+    pub review_reason: Option<String>,
+
+    /// Initiator's signature over the genesis state
+    #[serde(default)]
+    pub signature: Option<Signature>,
+}
+
+impl ChannelRequest {
+    pub fn new(
+        initiator: PublicKey,
+        participants: Vec<PublicKey>,
+        initial_balances: HashMap<PublicKey, Amount>,
+        challenge_period: u64,
+    ) -> Self {
+        Self {
+            channel_id: MarketplaceChannel::compute_id(&participants),
+            initiator,
+            participants,
+            initial_balances,
+            challenge_period,
+            received_at: Timestamp::now(),
+            review_reason: None,
+            signature: None,
+        }
+    }
+
+    /// Attach the initiator's genesis signature
+    pub fn with_signature(mut self, signature: Signature) -> Self {
+        self.signature = Some(signature);
+        self
+    }
+
+    /// Total collateral proposed for the channel
+    pub fn collateral(&self) -> Amount {
+        self.initial_balances.values()
+            .sum::<Option<Amount>>()
+            .unwrap_or(Amount::MAX)
+    }
+
+    /// Build the channel config for this request
+    pub fn to_config(&self) -> ChannelConfig {
+        ChannelConfig {
+            participants: self.participants.clone(),
+            initial_balances: self.initial_balances.clone(),
+            challenge_period: self.challenge_period,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_common::crypto::KeyPair;
+
+    fn make_request(collateral: u64) -> (KeyPair, ChannelRequest) {
+        let kp1 = KeyPair::generate();
+        let kp2 = KeyPair::generate();
+
+        let mut balances = HashMap::new();
+        balances.insert(kp1.public_key(), Amount::new(collateral / 2));
+        balances.insert(kp2.public_key(), Amount::new(collateral / 2));
+
+        let request = ChannelRequest::new(
+            kp1.public_key(),
+            vec![kp1.public_key(), kp2.public_key()],
+            balances,
+            3600,
+        );
+        (kp1, request)
+    }
+
+    #[test]
+    fn test_default_policy_accepts() {
+        let (_, request) = make_request(1000);
+        let policy = ChannelPolicy::default();
+        assert_eq!(policy.evaluate(&request, 0, None), PolicyDecision::Accept);
+    }
+
+    #[test]
+    fn test_collateral_bounds() {
+        let (_, request) = make_request(1000);
+        let policy = ChannelPolicy {
+            min_collateral: 2000,
+            ..Default::default()
+        };
+        assert!(matches!(policy.evaluate(&request, 0, None), PolicyDecision::Review(_)));
+
+        let policy = ChannelPolicy {
+            max_collateral: Some(500),
+            ..Default::default()
+        };
+        assert!(matches!(policy.evaluate(&request, 0, None), PolicyDecision::Review(_)));
+    }
+
+    #[test]
+    fn test_allow_list_and_limits() {
+        let (kp, request) = make_request(1000);
+        let policy = ChannelPolicy {
+            allowed_counterparties: vec![hex::encode(kp.public_key().as_bytes())],
+            max_open_channels: Some(2),
+            min_reputation: Some(4.0),
+            ..Default::default()
+        };
+
+        assert_eq!(policy.evaluate(&request, 1, Some(4.5)), PolicyDecision::Accept);
+        assert!(matches!(policy.evaluate(&request, 2, Some(4.5)), PolicyDecision::Review(_)));
+        assert!(matches!(policy.evaluate(&request, 1, Some(3.0)), PolicyDecision::Review(_)));
+        assert!(matches!(policy.evaluate(&request, 1, None), PolicyDecision::Review(_)));
+
+        let (_, other) = make_request(1000);
+        assert!(matches!(policy.evaluate(&other, 1, Some(4.5)), PolicyDecision::Review(_)));
+    }
+
+    #[test]
+    fn test_challenge_period_bounds() {
+        let policy = ChannelPolicy::default();
+        assert!(policy.check_challenge_period(3600).is_ok());
+        assert!(policy.check_challenge_period(30 * 24 * 3600).is_ok());
+        assert!(policy.check_challenge_period(60).is_err());
+        assert!(policy.check_challenge_period(365 * 24 * 3600).is_err());
+    }
+}
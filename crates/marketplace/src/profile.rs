@@ -1,83 +1,63 @@
 use serde::{Deserialize, Serialize};
 use tari_l2_common::PublicKey;
+use tari_l2_state_channel::state::Listing;
+use crate::catalog::CatalogIntegrity;
 
-/// User profile information
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct UserProfile {
-    /// Wallet public key (unique identifier)
-    pub public_key: PublicKey,
-
-    /// Display name
-    pub name: String,
-
-    /// Location (city, country, etc.)
-    pub location: Option<String>,
+// Profiles and reviews travel in P2P catalog pages, so they live next to `Listing`
+pub use tari_l2_state_channel::state::{Review, UserProfile};
 
-    /// Profile bio/description
-    pub bio: Option<String>,
+/// Highest star rating a review can give
+pub const MAX_REVIEW_RATING: u8 = 5;
 
-    /// Contact email (optional)
-    pub email: Option<String>,
-
-    /// Profile avatar URL or hash
-    pub avatar: Option<String>,
-
-    /// Rating (0-5 stars)
+/// Summary of a seller's standing
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SellerReputation {
+    /// Average review rating, or the profile rating if there are no reviews
     pub rating: f32,
 
-    /// Number of completed transactions
-    pub transactions_completed: u32,
+    /// Number of reviews received
+    pub review_count: u32,
 
-    /// Timestamp when profile was created
-    pub created_at: u64,
+    /// Number of completed transactions from the profile
+    pub transactions_completed: u32,
 }
 
-impl UserProfile {
-    /// Create a new user profile
-    pub fn new(public_key: PublicKey, name: String) -> Self {
-        let created_at = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+impl SellerReputation {
+    /// Combine a seller's reviews with their profile
+    pub fn from_reviews(profile: Option<&UserProfile>, reviews: &[Review]) -> Self {
+        let rating = if reviews.is_empty() {
+            profile.map(|p| p.rating).unwrap_or(0.0)
+        } else {
+            reviews.iter().map(|r| r.rating as f32).sum::<f32>() / reviews.len() as f32
+        };
 
         Self {
-            public_key,
-            name,
-            location: None,
-            bio: None,
-            email: None,
-            avatar: None,
-            rating: 0.0,
-            transactions_completed: 0,
-            created_at,
-        }
-    }
-
-    /// Update profile information
-    pub fn update(&mut self, name: Option<String>, location: Option<String>, bio: Option<String>, email: Option<String>) {
-        if let Some(n) = name {
-            self.name = n;
-        }
-        if let Some(l) = location {
-            self.location = Some(l);
-        }
-        if let Some(b) = bio {
-            self.bio = Some(b);
-        }
-        if let Some(e) = email {
-            self.email = Some(e);
+            rating,
+            review_count: reviews.len() as u32,
+            transactions_completed: profile.map(|p| p.transactions_completed).unwrap_or(0),
         }
     }
+}
 
-    /// Get public key as hex string
-    pub fn address(&self) -> String {
-        hex::encode(self.public_key.as_bytes())
-    }
+/// Everything a storefront page shows for one seller
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SellerCatalog {
+    pub seller: PublicKey,
+    pub profile: Option<UserProfile>,
+    pub reputation: SellerReputation,
+    /// Active listings
+    pub listings: Vec<Listing>,
+    /// Most recent reviews, newest first
+    pub recent_reviews: Vec<Review>,
+    /// Whether `listings` match the catalog root the seller announced
+    #[serde(default)]
+    pub integrity: CatalogIntegrity,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tari_l2_common::Hash;
     use tari_l2_common::crypto::KeyPair;
 
     #[test]
@@ -106,4 +86,29 @@ mod tests {
         assert_eq!(profile.location, Some("New York, USA".to_string()));
         assert_eq!(profile.bio, Some("Crypto enthusiast".to_string()));
     }
+
+    #[test]
+    fn test_reputation_prefers_reviews() {
+        let keypair = KeyPair::generate();
+        let mut profile = UserProfile::new(keypair.public_key(), "Alice".to_string());
+        profile.rating = 3.0;
+        profile.transactions_completed = 7;
+
+        let reputation = SellerReputation::from_reviews(Some(&profile), &[]);
+        assert_eq!(reputation.rating, 3.0);
+        assert_eq!(reputation.review_count, 0);
+
+        let review = |rating| Review {
+            order_id: Hash::new([rating; 32]),
+            reviewer: KeyPair::generate().public_key(),
+            seller: keypair.public_key(),
+            rating,
+            comment: String::new(),
+            created_at: 0,
+        };
+        let reputation = SellerReputation::from_reviews(Some(&profile), &[review(5), review(4)]);
+        assert_eq!(reputation.rating, 4.5);
+        assert_eq!(reputation.review_count, 2);
+        assert_eq!(reputation.transactions_completed, 7);
+    }
 }
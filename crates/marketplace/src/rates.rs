@@ -0,0 +1,134 @@
+//! Exchange rates for showing approximate fiat values next to µT prices.
+//!
+//! The node asks an [`ExchangeRateProvider`] for the value of one Tari in a
+//! currency. [`StaticRates`] serves rates from the node configuration; a
+//! live price feed plugs in by implementing the trait. Wrap either in
+//! [`CachedRates`] so listing queries do not hit the source every time.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tari_l2_common::clock::SharedClock;
+use tari_l2_common::error::Result;
+use tari_l2_common::{Amount, Timestamp, MICRO_TARI_PER_TARI};
+use tracing::warn;
+
+/// Source of Tari exchange rates
+#[async_trait]
+pub trait ExchangeRateProvider: Send + Sync {
+    /// Value of one Tari in `currency` (ISO 4217 code), if known
+    async fn rate(&self, currency: &str) -> Result<Option<f64>>;
+}
+
+/// Rates fixed in the node configuration
+pub struct StaticRates {
+    rates: HashMap<String, f64>,
+}
+
+impl StaticRates {
+    pub fn new(rates: HashMap<String, f64>) -> Self {
+        let rates = rates.into_iter()
+            .map(|(currency, rate)| (currency.to_ascii_uppercase(), rate))
+            .collect();
+        Self { rates }
+    }
+}
+
+#[async_trait]
+impl ExchangeRateProvider for StaticRates {
+    async fn rate(&self, currency: &str) -> Result<Option<f64>> {
+        Ok(self.rates.get(&currency.to_ascii_uppercase()).copied())
+    }
+}
+
+/// Caches another provider's answers for `ttl_secs`. If a refresh fails the
+/// last known rate is served until the source recovers.
+pub struct CachedRates {
+    inner: Box<dyn ExchangeRateProvider>,
+    ttl_secs: u64,
+    clock: SharedClock,
+    cache: RwLock<HashMap<String, (Option<f64>, Timestamp)>>,
+}
+
+impl CachedRates {
+    pub fn new(inner: Box<dyn ExchangeRateProvider>, ttl_secs: u64, clock: SharedClock) -> Self {
+        Self {
+            inner,
+            ttl_secs,
+            clock,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl ExchangeRateProvider for CachedRates {
+    async fn rate(&self, currency: &str) -> Result<Option<f64>> {
+        let currency = currency.to_ascii_uppercase();
+        let now = self.clock.now();
+
+        let cached = self.cache.read().await.get(&currency).copied();
+        if let Some((rate, fetched)) = cached {
+            if now.as_secs().saturating_sub(fetched.as_secs()) < self.ttl_secs {
+                return Ok(rate);
+            }
+        }
+
+        match self.inner.rate(&currency).await {
+            Ok(rate) => {
+                self.cache.write().await.insert(currency, (rate, now));
+                Ok(rate)
+            }
+            Err(e) => match cached {
+                Some((rate, _)) => {
+                    warn!("Exchange rate refresh for {} failed, serving stale rate: {}", currency, e);
+                    Ok(rate)
+                }
+                None => Err(e),
+            },
+        }
+    }
+}
+
+/// Approximate value of `amount` given the rate for one Tari
+pub fn fiat_value(amount: Amount, rate: f64) -> f64 {
+    amount.value() as f64 / MICRO_TARI_PER_TARI as f64 * rate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tari_l2_common::clock::ManualClock;
+
+    struct CountingRates(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl ExchangeRateProvider for CountingRates {
+        async fn rate(&self, _currency: &str) -> Result<Option<f64>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(Some(0.5))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_rates_expire() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let clock = Arc::new(ManualClock::new(Timestamp::from_secs(1_700_000_000)));
+        let rates = CachedRates::new(Box::new(CountingRates(calls.clone())), 60, clock.clone());
+
+        assert_eq!(rates.rate("usd").await.unwrap(), Some(0.5));
+        assert_eq!(rates.rate("USD").await.unwrap(), Some(0.5));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        clock.advance(60);
+        rates.rate("USD").await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_fiat_value() {
+        assert_eq!(fiat_value(Amount::new(2 * MICRO_TARI_PER_TARI), 0.25), 0.5);
+    }
+}
@@ -0,0 +1,193 @@
+//! Signed receipts for settled orders and channels.
+//!
+//! A receipt is issued by one of the parties when an order completes or a
+//! channel closes. It carries everything needed to check it offline: the
+//! parties, the amounts, the channel state root and nonce it settled at, the
+//! issuer's signature, and the state update every party co-signed at that
+//! nonce. The co-signed update is what makes the receipt worth something to
+//! a third party: the issuer alone cannot produce it.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{Hash, PublicKey, Signature, canonical, crypto::SigningContext};
+use tari_l2_state_channel::{MarketplaceChannel, OrderStatus, StateUpdate, update::SignedStateUpdate};
+use crate::auth::verify_ownership;
+
+/// What a receipt settles
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptKind {
+    /// A completed order: `amount` µT moved from the buyer to the seller
+    Order {
+        order_id: Hash,
+        listing_id: Hash,
+        buyer: PublicKey,
+        seller: PublicKey,
+        amount: u64,
+    },
+    /// A closed channel and each participant's final balance in µT
+    ChannelClose {
+        balances: Vec<(PublicKey, u64)>,
+    },
+}
+
+/// The signed content of a receipt
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReceiptTerms {
+    pub kind: ReceiptKind,
+
+    /// Channel the settlement happened in
+    pub channel_id: Hash,
+
+    /// Channel participants
+    pub parties: Vec<PublicKey>,
+
+    /// Channel state root after settlement
+    pub state_root: Hash,
+
+    /// Channel nonce after settlement
+    pub nonce: u64,
+
+    /// When the order was placed or the close started, if known
+    pub started_at: Option<u64>,
+
+    /// When the settlement was recorded
+    pub settled_at: u64,
+}
+
+/// Settlement terms signed by one of the parties
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Receipt {
+    pub terms: ReceiptTerms,
+
+    /// Party that signed the receipt
+    pub issuer: PublicKey,
+
+    pub signature: Signature,
+
+    /// The update all parties signed at `terms.nonce`; for an order, the
+    /// one completing it
+    pub anchor: SignedStateUpdate,
+}
+
+impl Receipt {
+    /// Build the message signed for a receipt
+    pub fn signing_message(terms: &ReceiptTerms, issuer: &PublicKey) -> Result<Vec<u8>, String> {
+        Ok(SigningContext::Receipt.message(&canonical::encode(&(terms, issuer))?))
+    }
+
+    /// Sign `terms` as `issuer`, anchored to the co-signed update settling them
+    pub fn sign(
+        terms: ReceiptTerms,
+        anchor: SignedStateUpdate,
+        issuer: PublicKey,
+        sign_fn: impl FnOnce(&[u8]) -> Signature,
+    ) -> Result<Self, String> {
+        let signature = sign_fn(&Self::signing_message(&terms, &issuer)?);
+        Ok(Self { terms, issuer, signature, anchor })
+    }
+
+    /// The order ID for order receipts, the channel ID for channel receipts
+    pub fn id(&self) -> Hash {
+        match &self.terms.kind {
+            ReceiptKind::Order { order_id, .. } => *order_id,
+            ReceiptKind::ChannelClose { .. } => self.terms.channel_id,
+        }
+    }
+
+    /// Check the issuer is one of the channel's parties and signed these
+    /// terms, and that every party co-signed the anchoring update
+    pub fn verify(&self) -> bool {
+        // The channel ID commits to its participants, so the party list
+        // cannot be cut down to the issuer alone
+        if MarketplaceChannel::compute_id(&self.terms.parties) != self.terms.channel_id
+            || !self.terms.parties.contains(&self.issuer)
+        {
+            return false;
+        }
+        if self.anchor.nonce != self.terms.nonce || !self.anchor.verify(&self.terms.parties) {
+            return false;
+        }
+        if let ReceiptKind::Order { order_id, buyer, seller, .. } = &self.terms.kind {
+            let completes_order = match &self.anchor.update {
+                StateUpdate::UpdateOrderStatus { order_id: id, status: OrderStatus::Completed }
+                | StateUpdate::CompleteOrderPaidOnL1 { order_id: id, .. } => id == order_id,
+                _ => false,
+            };
+            if !completes_order || !self.terms.parties.contains(buyer) || !self.terms.parties.contains(seller) {
+                return false;
+            }
+        }
+        match Self::signing_message(&self.terms, &self.issuer) {
+            Ok(message) => verify_ownership(&self.issuer, &message, &self.signature),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_common::crypto::KeyPair;
+
+    fn co_signed(update: StateUpdate, nonce: u64, signers: &[&KeyPair]) -> SignedStateUpdate {
+        let mut signed = SignedStateUpdate::new(update, nonce);
+        for signer in signers {
+            signed.add_signature(signer.public_key(), signer.sign(&signed.signing_message()));
+        }
+        signed
+    }
+
+    #[test]
+    fn test_receipt_verifies_only_unaltered() {
+        let buyer = KeyPair::generate();
+        let seller = KeyPair::generate();
+        let parties = vec![buyer.public_key(), seller.public_key()];
+        let order_id = Hash::new([1; 32]);
+        let terms = ReceiptTerms {
+            kind: ReceiptKind::Order {
+                order_id,
+                listing_id: Hash::new([2; 32]),
+                buyer: buyer.public_key(),
+                seller: seller.public_key(),
+                amount: 500,
+            },
+            channel_id: MarketplaceChannel::compute_id(&parties),
+            parties,
+            state_root: Hash::new([4; 32]),
+            nonce: 7,
+            started_at: Some(1_700_000_000),
+            settled_at: 1_700_000_600,
+        };
+        let completion = StateUpdate::UpdateOrderStatus { order_id, status: OrderStatus::Completed };
+        let anchor = co_signed(completion.clone(), 7, &[&buyer, &seller]);
+
+        let receipt = Receipt::sign(terms.clone(), anchor.clone(), seller.public_key(), |m| seller.sign(m)).unwrap();
+        assert!(receipt.verify());
+        assert_eq!(receipt.id(), order_id);
+
+        let mut altered = receipt.clone();
+        altered.terms.nonce = 8;
+        assert!(!altered.verify());
+
+        // Only a party to the trade can issue its receipt
+        let outsider = KeyPair::generate();
+        let forged = Receipt::sign(terms.clone(), anchor, outsider.public_key(), |m| outsider.sign(m)).unwrap();
+        assert!(!forged.verify());
+
+        // A receipt the counterparty never co-signed proves nothing
+        let self_signed = co_signed(completion, 7, &[&seller]);
+        assert!(!Receipt::sign(terms.clone(), self_signed, seller.public_key(), |m| seller.sign(m)).unwrap().verify());
+
+        // Nor does dropping the counterparty from the party list
+        let solo = ReceiptTerms {
+            parties: vec![seller.public_key()],
+            ..terms.clone()
+        };
+        let solo_anchor = co_signed(StateUpdate::UpdateOrderStatus { order_id, status: OrderStatus::Completed }, 7, &[&seller]);
+        assert!(!Receipt::sign(solo, solo_anchor, seller.public_key(), |m| seller.sign(m)).unwrap().verify());
+
+        // The anchor has to be the update completing this order
+        let shipped = co_signed(StateUpdate::UpdateOrderStatus { order_id, status: OrderStatus::Delivered }, 7, &[&buyer, &seller]);
+        assert!(!Receipt::sign(terms, shipped, seller.public_key(), |m| seller.sign(m)).unwrap().verify());
+    }
+}
@@ -0,0 +1,140 @@
+//! Saved searches over listings arriving from the network.
+//!
+//! A user saves a query (keywords, a category, a maximum price) once, and
+//! each listing a peer publishes or changes afterwards is checked against
+//! it. A match is stored, published as a `saved_search_match` event for
+//! WebSocket subscribers, and POSTed to the search's webhook if it has
+//! one. Each listing matches a search at most once; a later change to the
+//! same listing does not notify again.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{Amount, Hash, Timestamp};
+use tari_l2_state_channel::state::Listing;
+
+/// Most saved searches a node keeps, as every incoming listing is checked
+/// against each one
+pub const MAX_SAVED_SEARCHES: usize = 100;
+
+/// Most keywords one search may hold
+pub const MAX_KEYWORDS: usize = 10;
+
+/// What a listing must have to match. Empty fields match anything.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchQuery {
+    /// Words that must all appear in the title or description, ignoring case
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Category the listing must be in, ignoring case
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Highest price, in µT
+    #[serde(default)]
+    pub max_price: Option<Amount>,
+}
+
+impl SearchQuery {
+    /// Whether `listing` is active and meets every criterion
+    pub fn matches(&self, listing: &Listing) -> bool {
+        if !listing.active {
+            return false;
+        }
+        if self.max_price.is_some_and(|max| listing.price > max) {
+            return false;
+        }
+        if self.category.as_ref().is_some_and(|category| !category.eq_ignore_ascii_case(&listing.category)) {
+            return false;
+        }
+        let text = format!("{}\n{}", listing.title, listing.description).to_lowercase();
+        self.keywords.iter().all(|keyword| text.contains(keyword.as_str()))
+    }
+}
+
+/// A query the node checks new listings against
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: Hash,
+    pub query: SearchQuery,
+    /// http:// URL each match is POSTed to as JSON
+    #[serde(default)]
+    pub webhook: Option<String>,
+    pub created_at: Timestamp,
+}
+
+impl SavedSearch {
+    /// A search with lowercased, trimmed keywords and a checked webhook.
+    /// A search must ask for something, or it would match every listing.
+    pub fn new(mut query: SearchQuery, webhook: Option<String>, now: Timestamp) -> Result<Self, String> {
+        query.keywords = query.keywords.iter()
+            .map(|keyword| keyword.trim().to_lowercase())
+            .filter(|keyword| !keyword.is_empty())
+            .collect();
+        query.category = query.category.map(|category| category.trim().to_string()).filter(|c| !c.is_empty());
+        if query.keywords.len() > MAX_KEYWORDS {
+            return Err(format!("A saved search holds at most {} keywords", MAX_KEYWORDS));
+        }
+        if query == SearchQuery::default() {
+            return Err("A saved search needs keywords, a category or a maximum price".to_string());
+        }
+        if let Some(url) = &webhook {
+            if !url.starts_with("http://") {
+                return Err(format!("Webhook URL '{}' must use http://", url));
+            }
+        }
+        Ok(Self { id: Hash::random(), query, webhook, created_at: now })
+    }
+}
+
+/// A listing that matched a saved search
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchMatch {
+    pub search_id: Hash,
+    pub listing_id: Hash,
+    pub matched_at: Timestamp,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_common::PublicKey;
+
+    fn listing(title: &str, category: &str, price: u64) -> Listing {
+        Listing {
+            id: Hash::random(),
+            seller: PublicKey::new([1; 32]),
+            title: title.to_string(),
+            description: "Barely used, ships in a week".to_string(),
+            price: Amount::new(price),
+            ipfs_hash: String::new(),
+            active: true,
+            category: category.to_string(),
+            fiat_reference: None,
+        }
+    }
+
+    #[test]
+    fn test_saved_search_matches_keywords_category_and_price() {
+        let query = SearchQuery {
+            keywords: vec![" Road ".to_string(), "BIKE".to_string()],
+            category: Some("Sports".to_string()),
+            max_price: Some(Amount::new(500)),
+        };
+        let search = SavedSearch::new(query, None, Timestamp::from_secs(1)).unwrap();
+        assert_eq!(search.query.keywords, vec!["road", "bike"]);
+
+        assert!(search.query.matches(&listing("Road bike, carbon", "sports", 500)));
+        assert!(!search.query.matches(&listing("Road bike, carbon", "sports", 501)));
+        assert!(!search.query.matches(&listing("Mountain bike", "sports", 100)));
+        assert!(!search.query.matches(&listing("Road bike", "garden", 100)));
+        let mut inactive = listing("Road bike", "sports", 100);
+        inactive.active = false;
+        assert!(!search.query.matches(&inactive));
+
+        // Keywords may also be in the description
+        let by_description = SearchQuery { keywords: vec!["ships".to_string()], ..Default::default() };
+        assert!(by_description.matches(&listing("Lamp", "home", 10)));
+
+        assert!(SavedSearch::new(SearchQuery::default(), None, Timestamp::from_secs(1)).is_err());
+        let price_only = SearchQuery { max_price: Some(Amount::new(10)), ..Default::default() };
+        assert!(SavedSearch::new(price_only, Some("https://example.com".to_string()), Timestamp::from_secs(1)).is_err());
+    }
+}
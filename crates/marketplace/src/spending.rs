@@ -0,0 +1,136 @@
+//! Local spending limits for a channel.
+//!
+//! Limits are checked by this node before it signs an update that moves
+//! its own funds out, so a compromised frontend cannot drain a channel in
+//! one call. Channels without limits of their own get the node's defaults,
+//! [`DEFAULT_LIMITS`] unless configured. They are not part of channel state
+//! and the counterparty never sees them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tari_l2_common::{Amount, Hash, PublicKey, Timestamp};
+use tari_l2_state_channel::{ChannelState, OrderStatus, StateUpdate};
+
+/// Length of the rolling window `max_daily` applies to
+pub const SPENDING_WINDOW_SECS: u64 = 24 * 3600;
+
+/// Limits for channels that have none of their own: 10 T per update and
+/// 100 T over any 24 hours
+pub const DEFAULT_LIMITS: SpendingLimits = SpendingLimits {
+    max_transfer: Some(10_000_000),
+    max_daily: Some(100_000_000),
+};
+
+/// Caps on what this node signs away from a channel (None = unlimited)
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SpendingLimits {
+    /// Most a single update may move out of our balance
+    pub max_transfer: Option<u64>,
+
+    /// Most that may move out over any 24 hours
+    pub max_daily: Option<u64>,
+}
+
+impl SpendingLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_transfer.is_none() && self.max_daily.is_none()
+    }
+
+    /// Check an outgoing `amount` given what was already spent in the window
+    pub fn check(&self, amount: u64, spent: u64) -> Result<(), String> {
+        if let Some(max) = self.max_transfer {
+            if amount > max {
+                return Err(format!("Update spends {} above the per-update limit of {}", amount, max));
+            }
+        }
+        if let Some(max) = self.max_daily {
+            if spent.saturating_add(amount) > max {
+                return Err(format!(
+                    "Update spends {} with {} already spent in 24h, above the daily limit of {}",
+                    amount, spent, max
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Amounts this node signed away from a channel in the last 24 hours.
+/// Spends are recorded when signed, whether or not the update commits, so
+/// the window errs on the side of refusing. Each is keyed by the update's
+/// nonce and hash, so signing a redelivered update again counts it once.
+#[derive(Clone, Debug, Default)]
+pub struct SpendingWindow {
+    spends: VecDeque<(Timestamp, (u64, Hash), u64)>,
+}
+
+impl SpendingWindow {
+    /// Total spent in the 24 hours before `now`
+    pub fn spent(&mut self, now: Timestamp) -> u64 {
+        let cutoff = now.as_secs().saturating_sub(SPENDING_WINDOW_SECS);
+        while self.spends.front().is_some_and(|(at, _, _)| at.as_secs() <= cutoff) {
+            self.spends.pop_front();
+        }
+        self.spends.iter().map(|(_, _, amount)| amount).sum()
+    }
+
+    /// Whether the update at `nonce` with hash `update` is already counted
+    pub fn contains(&self, nonce: u64, update: &Hash) -> bool {
+        self.spends.iter().any(|(_, key, _)| *key == (nonce, *update))
+    }
+
+    pub fn record(&mut self, nonce: u64, update: Hash, amount: u64, now: Timestamp) {
+        if amount > 0 && !self.contains(nonce, &update) {
+            self.spends.push_back((now, (nonce, update), amount));
+        }
+    }
+}
+
+/// Value `update` moves out of `local`'s balance when applied to `state`.
+/// Escrow locks count when the lock is taken, not again when the order
+/// completes from them.
+pub fn outgoing(update: &StateUpdate, state: &ChannelState, local: &PublicKey) -> Amount {
+    let zero = Amount::new(0);
+    match update {
+        StateUpdate::Transfer { from, to, amount }
+        | StateUpdate::PayInvoice { from, to, amount, .. } if from == local && to != local => *amount,
+        StateUpdate::LockEscrow { order_id } => state.orders.iter()
+            .find(|o| o.id == *order_id && o.buyer == *local)
+            .map_or(zero, |o| o.amount),
+        StateUpdate::UpdateOrderStatus { order_id, status: OrderStatus::Completed } => state.orders.iter()
+            .find(|o| {
+                o.id == *order_id && o.buyer == *local && o.status != OrderStatus::Completed
+                    && !state.escrow_locks.contains_key(order_id)
+            })
+            .map_or(zero, |o| o.amount),
+        _ => zero,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_and_rolling_window() {
+        let limits = SpendingLimits { max_transfer: Some(100), max_daily: Some(250) };
+        assert!(limits.check(101, 0).is_err());
+        assert!(limits.check(100, 150).is_ok());
+        assert!(limits.check(100, 151).is_err());
+        assert!(SpendingLimits::default().check(u64::MAX, u64::MAX).is_ok());
+
+        let mut window = SpendingWindow::default();
+        window.record(1, Hash::new([1u8; 32]), 100, Timestamp::from_secs(1_000));
+        window.record(2, Hash::new([2u8; 32]), 100, Timestamp::from_secs(2_000));
+        assert_eq!(window.spent(Timestamp::from_secs(2_000)), 200);
+
+        // The same update signed again is not counted twice
+        window.record(2, Hash::new([2u8; 32]), 100, Timestamp::from_secs(2_000));
+        assert_eq!(window.spent(Timestamp::from_secs(2_000)), 200);
+
+        // The first spend ages out a day later
+        assert_eq!(window.spent(Timestamp::from_secs(1_000 + SPENDING_WINDOW_SECS)), 100);
+        assert_eq!(window.spent(Timestamp::from_secs(2_000 + SPENDING_WINDOW_SECS)), 0);
+    }
+}
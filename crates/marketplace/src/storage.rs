@@ -1,13 +1,74 @@
+use serde::{Deserialize, Serialize};
 use sled::{Db, Tree};
-use tari_l2_common::{Hash, L2Error, error::Result};
-use tari_l2_state_channel::{MarketplaceChannel, state::Listing};
+use tari_l2_common::{Hash, L2Error, PublicKey, ResourceLimits, Signature, error::Result};
+use tari_l2_state_channel::{MarketplaceChannel, channel::ChannelMetrics, state::{Listing, Order}, update::SignedStateUpdate};
 use std::path::Path;
+use tari_l2_p2p::KnownPeer;
+use crate::audit::AuditEntry;
+use crate::contacts::Contact;
+use crate::escrow::EscrowContract;
+use crate::invoice::{Invoice, InvoicePayment};
+use crate::payout::PayoutPreference;
+use crate::journal::JournalEntry;
+use crate::migrate;
+use crate::outbox::OutboxEntry;
+use crate::receipt::Receipt;
+use crate::saved_search::{SavedSearch, SearchMatch};
+use crate::spending::SpendingLimits;
+use crate::watch::WatchBlob;
 
-/// Persistent storage for marketplace state
+/// Updates appended to a channel's log between full snapshots
+pub const SNAPSHOT_INTERVAL: u64 = 64;
+
+/// An applied update in a channel's log, with the channel's metrics after
+/// it so a replay restores them exactly
+#[derive(Serialize, Deserialize)]
+pub(crate) struct LoggedUpdate {
+    pub(crate) update: SignedStateUpdate,
+    pub(crate) metrics: ChannelMetrics,
+}
+
+/// An order as last seen, with the channel it was placed in
+#[derive(Serialize, Deserialize)]
+struct StoredOrder {
+    channel_id: Hash,
+    order: Order,
+}
+
+/// Persistent storage for marketplace state.
+///
+/// Channels are kept as a snapshot without their history plus an
+/// append-only log of applied updates, so persisting an update costs the
+/// same however large the channel has grown.
 pub struct MarketplaceStorage {
     _db: Db,
     channels: Tree,
+    channel_updates: Tree,
     listings: Tree,
+    listing_signatures: Tree,
+    listing_drafts: Tree,
+    journal: Tree,
+    outbox: Tree,
+    receipts: Tree,
+    spending_limits: Tree,
+    seen_actions: Tree,
+    watch_blobs: Tree,
+    known_peers: Tree,
+    contacts: Tree,
+    invoices: Tree,
+    paid_invoices: Tree,
+    invoice_claims: Tree,
+    payout_preferences: Tree,
+    audit_log: Tree,
+    audit_gaps: Tree,
+    escrows: Tree,
+    orders: Tree,
+    saved_searches: Tree,
+    search_matches: Tree,
+    task_runs: Tree,
+    order_first_seen: Tree,
+    listing_versions: Tree,
+    limits: ResourceLimits,
 }
 
 impl MarketplaceStorage {
@@ -15,43 +76,239 @@ impl MarketplaceStorage {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
         let db = sled::open(path)
             .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        migrate::upgrade(&db)?;
 
         let channels = db.open_tree("channels")
             .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
 
+        let channel_updates = db.open_tree("channel_updates")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
         let listings = db.open_tree("listings")
             .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
 
-        Ok(Self { _db: db, channels, listings })
+        let listing_signatures = db.open_tree("listing_signatures")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let listing_drafts = db.open_tree("listing_drafts")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let journal = db.open_tree("update_journal")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let outbox = db.open_tree("outbox")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let receipts = db.open_tree("receipts")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let spending_limits = db.open_tree("spending_limits")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let seen_actions = db.open_tree("seen_actions")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let watch_blobs = db.open_tree("watch_blobs")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let known_peers = db.open_tree("known_peers")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let contacts = db.open_tree("contacts")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let invoices = db.open_tree("invoices")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let paid_invoices = db.open_tree("paid_invoices")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let invoice_claims = db.open_tree("invoice_claims")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let payout_preferences = db.open_tree("payout_preferences")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let audit_log = db.open_tree("audit_log")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let audit_gaps = db.open_tree("audit_gaps")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let escrows = db.open_tree("escrows")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let orders = db.open_tree("orders")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let saved_searches = db.open_tree("saved_searches")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let search_matches = db.open_tree("search_matches")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let task_runs = db.open_tree("task_runs")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let order_first_seen = db.open_tree("order_first_seen")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        let listing_versions = db.open_tree("listing_versions")
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(Self {
+            _db: db,
+            channels,
+            channel_updates,
+            listings,
+            listing_signatures,
+            listing_drafts,
+            journal,
+            outbox,
+            receipts,
+            spending_limits,
+            seen_actions,
+            watch_blobs,
+            known_peers,
+            contacts,
+            invoices,
+            paid_invoices,
+            invoice_claims,
+            payout_preferences,
+            audit_log,
+            audit_gaps,
+            escrows,
+            orders,
+            saved_searches,
+            search_matches,
+            task_runs,
+            order_first_seen,
+            listing_versions,
+            limits: ResourceLimits::default(),
+        })
+    }
+
+    /// Apply resource limits to stored channels and listings
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
     }
 
-    /// Store a channel
+    /// Store a full snapshot of a channel. Its history goes to the update
+    /// log; only entries the log does not have yet are written.
     pub fn store_channel(&self, channel: &MarketplaceChannel) -> Result<()> {
         let key = channel.channel_id.to_vec();
-        let value = bincode::serialize(channel)
+
+        let exists = self.channels.contains_key(&key)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        if !exists && self.channels.len() >= self.limits.max_channels {
+            return Err(L2Error::ResourceLimitExceeded(format!(
+                "Channel limit reached ({})", self.limits.max_channels
+            )));
+        }
+
+        let logged_nonce = self.last_logged_nonce(&channel.channel_id)?;
+        for update in channel.state_history.iter().filter(|u| u.nonce > logged_nonce) {
+            self.log_update(&channel.channel_id, update, &channel.metrics)?;
+        }
+
+        let snapshot = MarketplaceChannel { state_history: Vec::new(), ..channel.clone() };
+        let value = bincode::serialize(&snapshot)
             .map_err(|e| L2Error::SerializationError(e.to_string()))?;
 
         self.channels.insert(key, value)
             .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
 
+        self.channel_updates.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
         self.channels.flush()
             .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
 
         Ok(())
     }
 
-    /// Load a channel by ID
+    /// Persist the update just applied to a channel by appending it to the
+    /// channel's log. A full snapshot is only written every
+    /// [`SNAPSHOT_INTERVAL`] updates, or if the channel has none yet.
+    pub fn append_update(&self, channel: &MarketplaceChannel) -> Result<()> {
+        let Some(update) = channel.state_history.last() else {
+            return self.store_channel(channel);
+        };
+        let has_snapshot = self.channels.contains_key(channel.channel_id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        if !has_snapshot || update.nonce % SNAPSHOT_INTERVAL == 0 {
+            return self.store_channel(channel);
+        }
+
+        self.log_update(&channel.channel_id, update, &channel.metrics)?;
+        self.channel_updates.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Load a channel by ID: its latest snapshot with the logged updates
+    /// after it applied again
     pub fn load_channel(&self, channel_id: &Hash) -> Result<Option<MarketplaceChannel>> {
         let key = channel_id.to_vec();
 
-        match self.channels.get(key)
+        let mut channel: MarketplaceChannel = match self.channels.get(key)
             .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
-            Some(value) => {
-                let channel = bincode::deserialize(&value)
-                    .map_err(|e| L2Error::SerializationError(e.to_string()))?;
-                Ok(Some(channel))
+            Some(value) => bincode::deserialize(&value)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?,
+            None => return Ok(None),
+        };
+
+        // Snapshots written before the update log existed carry their history
+        let snapshot_nonce = channel.state.nonce;
+        let inline_history = !channel.state_history.is_empty();
+
+        for result in self.channel_updates.scan_prefix(channel_id.as_bytes()) {
+            let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            let logged: LoggedUpdate = bincode::deserialize(&value)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+            if logged.update.nonce <= snapshot_nonce {
+                if !inline_history {
+                    channel.state_history.push(logged.update);
+                }
+                continue;
             }
-            None => Ok(None),
+            let nonce = logged.update.nonce;
+            channel.apply_update(logged.update).map_err(|e| L2Error::DatabaseError(format!(
+                "Logged update {} of channel {} does not apply: {}", nonce, channel_id, e
+            )))?;
+            channel.metrics = logged.metrics;
+        }
+
+        Ok(Some(channel))
+    }
+
+    /// Write one entry of a channel's update log, keyed by channel ID then
+    /// big-endian nonce so a prefix scan returns it in order
+    fn log_update(&self, channel_id: &Hash, update: &SignedStateUpdate, metrics: &ChannelMetrics) -> Result<()> {
+        let mut key = channel_id.to_vec();
+        key.extend_from_slice(&update.nonce.to_be_bytes());
+
+        let value = bincode::serialize(&LoggedUpdate { update: update.clone(), metrics: metrics.clone() })
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.channel_updates.insert(key, value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Nonce of the latest update in a channel's log, 0 if it has none
+    fn last_logged_nonce(&self, channel_id: &Hash) -> Result<u64> {
+        match self.channel_updates.scan_prefix(channel_id.as_bytes()).next_back() {
+            Some(result) => {
+                let (key, _) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+                let mut nonce_bytes = [0u8; 8];
+                nonce_bytes.copy_from_slice(&key[32..40]);
+                Ok(u64::from_be_bytes(nonce_bytes))
+            }
+            None => Ok(0),
         }
     }
 
@@ -69,12 +326,20 @@ impl MarketplaceStorage {
         Ok(channels)
     }
 
-    /// Delete a channel
+    /// Delete a channel and its update log
     pub fn delete_channel(&self, channel_id: &Hash) -> Result<()> {
         let key = channel_id.to_vec();
         self.channels.remove(key)
             .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
 
+        for result in self.channel_updates.scan_prefix(channel_id.as_bytes()) {
+            let (key, _) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            self.channel_updates.remove(key)
+                .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        }
+        self.channel_updates.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
         self.channels.flush()
             .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
 
@@ -89,6 +354,15 @@ impl MarketplaceStorage {
     /// Store a listing
     pub fn store_listing(&self, listing: &Listing) -> Result<()> {
         let key = listing.id.to_vec();
+
+        let exists = self.listings.contains_key(&key)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        if !exists && self.listings.len() >= self.limits.max_listings {
+            return Err(L2Error::ResourceLimitExceeded(format!(
+                "Listing limit reached ({})", self.limits.max_listings
+            )));
+        }
+
         let value = bincode::serialize(listing)
             .map_err(|e| L2Error::SerializationError(e.to_string()))?;
 
@@ -133,7 +407,9 @@ impl MarketplaceStorage {
     /// Delete a listing
     pub fn delete_listing(&self, listing_id: &Hash) -> Result<()> {
         let key = listing_id.to_vec();
-        self.listings.remove(key)
+        self.listings.remove(&key)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.listing_signatures.remove(&key)
             .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
 
         self.listings.flush()
@@ -141,52 +417,959 @@ impl MarketplaceStorage {
 
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tari_l2_state_channel::ChannelConfig;
-    use tari_l2_common::{Amount, crypto::KeyPair};
-    use std::collections::HashMap;
-    use tempfile::TempDir;
+    /// Store the seller's signature over a listing so it can be re-served to peers
+    pub fn store_listing_signature(&self, listing_id: &Hash, signature: &Signature) -> Result<()> {
+        self.listing_signatures.insert(listing_id.to_vec(), signature.as_bytes().to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
 
-    #[test]
-    fn test_storage_operations() {
-        let temp_dir = TempDir::new().unwrap();
-        let storage = MarketplaceStorage::open(temp_dir.path()).unwrap();
+        self.listing_signatures.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
 
-        let kp1 = KeyPair::generate();
-        let kp2 = KeyPair::generate();
+        Ok(())
+    }
 
-        let mut balances = HashMap::new();
-        balances.insert(kp1.public_key(), Amount::new(1000));
-        balances.insert(kp2.public_key(), Amount::new(1000));
+    /// Load the seller's signature over a listing
+    pub fn load_listing_signature(&self, listing_id: &Hash) -> Result<Option<Signature>> {
+        match self.listing_signatures.get(listing_id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some(value) => {
+                let signature = Signature::from_slice(&value)
+                    .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+                Ok(Some(signature))
+            }
+            None => Ok(None),
+        }
+    }
 
-        let config = ChannelConfig {
-            participants: vec![kp1.public_key(), kp2.public_key()],
-            initial_balances: balances,
-            challenge_period: 3600,
-        };
+    /// Store a draft listing. Drafts are kept apart from published listings
+    /// and are never served to peers.
+    pub fn store_draft(&self, listing: &Listing) -> Result<()> {
+        let value = bincode::serialize(listing)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
 
-        let channel = MarketplaceChannel::new(config);
-        let channel_id = channel.channel_id;
+        self.listing_drafts.insert(listing.id.to_vec(), value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
 
-        // Store channel
-        storage.store_channel(&channel).unwrap();
+        self.listing_drafts.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
 
-        // Load channel
-        let loaded = storage.load_channel(&channel_id).unwrap();
-        assert!(loaded.is_some());
-        assert_eq!(loaded.unwrap().channel_id, channel_id);
+        Ok(())
+    }
 
-        // List channels
-        let channels = storage.list_channels().unwrap();
-        assert_eq!(channels.len(), 1);
+    /// Load a draft listing by ID
+    pub fn load_draft(&self, listing_id: &Hash) -> Result<Option<Listing>> {
+        match self.listing_drafts.get(listing_id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some(value) => {
+                let listing = bincode::deserialize(&value)
+                    .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+                Ok(Some(listing))
+            }
+            None => Ok(None),
+        }
+    }
 
-        // Delete channel
-        storage.delete_channel(&channel_id).unwrap();
-        let deleted = storage.load_channel(&channel_id).unwrap();
-        assert!(deleted.is_none());
+    /// Load all draft listings
+    pub fn load_drafts(&self) -> Result<Vec<Listing>> {
+        let mut drafts = Vec::new();
+
+        for result in self.listing_drafts.iter() {
+            let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            let listing = bincode::deserialize(&value)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+            drafts.push(listing);
+        }
+
+        Ok(drafts)
+    }
+
+    /// Delete a draft listing
+    pub fn delete_draft(&self, listing_id: &Hash) -> Result<()> {
+        self.listing_drafts.remove(listing_id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        self.listing_drafts.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Record an in-flight state update in the write-ahead journal
+    pub fn journal_update(&self, entry: &JournalEntry) -> Result<()> {
+        let value = bincode::serialize(entry)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.journal.insert(entry.key(), value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        self.journal.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Remove a single journal entry
+    pub fn remove_journal_entry(&self, entry: &JournalEntry) -> Result<()> {
+        self.journal.remove(entry.key())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        self.journal.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Remove all journal entries for a channel up to and including a nonce
+    pub fn clear_journal(&self, channel_id: &Hash, up_to_nonce: u64) -> Result<()> {
+        for result in self.journal.scan_prefix(channel_id.as_bytes()) {
+            let (key, _) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+            let mut nonce_bytes = [0u8; 8];
+            nonce_bytes.copy_from_slice(&key[32..40]);
+            if u64::from_be_bytes(nonce_bytes) > up_to_nonce {
+                break;
+            }
+
+            self.journal.remove(key)
+                .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        }
+
+        self.journal.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Load all journal entries, ordered by channel and nonce
+    pub fn load_journal(&self) -> Result<Vec<JournalEntry>> {
+        let mut entries = Vec::new();
+
+        for result in self.journal.iter() {
+            let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            let entry = bincode::deserialize(&value)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+            entries.push(entry);
+        }
+
+        Ok(entries)
+    }
+
+    /// Store a message queued for delivery, replacing any earlier copy
+    pub fn store_outbox_entry(&self, entry: &OutboxEntry) -> Result<()> {
+        let value = bincode::serialize(entry)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.outbox.insert(entry.id.to_vec(), value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        self.outbox.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Remove a delivered or abandoned message from the outbox
+    pub fn delete_outbox_entry(&self, id: &Hash) -> Result<()> {
+        self.outbox.remove(id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        self.outbox.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Load every message still in the outbox
+    pub fn load_outbox(&self) -> Result<Vec<OutboxEntry>> {
+        let mut entries = Vec::new();
+
+        for result in self.outbox.iter() {
+            let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            let entry = bincode::deserialize(&value)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+            entries.push(entry);
+        }
+
+        Ok(entries)
+    }
+
+    /// Store a settlement receipt under its order or channel ID
+    pub fn store_receipt(&self, receipt: &Receipt) -> Result<()> {
+        let value = bincode::serialize(receipt)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.receipts.insert(receipt.id().to_vec(), value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        self.receipts.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Load the receipt for an order or channel
+    pub fn load_receipt(&self, id: &Hash) -> Result<Option<Receipt>> {
+        match self.receipts.get(id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some(value) => {
+                let receipt = bincode::deserialize(&value)
+                    .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+                Ok(Some(receipt))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Load every stored receipt
+    pub fn load_receipts(&self) -> Result<Vec<Receipt>> {
+        let mut receipts = Vec::new();
+
+        for result in self.receipts.iter() {
+            let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            let receipt = bincode::deserialize(&value)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+            receipts.push(receipt);
+        }
+
+        Ok(receipts)
+    }
+
+    /// Store a channel's spending limits; unlimited ones are removed
+    pub fn store_spending_limits(&self, channel_id: &Hash, limits: &SpendingLimits) -> Result<()> {
+        if limits.is_unlimited() {
+            self.spending_limits.remove(channel_id.to_vec())
+                .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        } else {
+            let value = bincode::serialize(limits)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+            self.spending_limits.insert(channel_id.to_vec(), value)
+                .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        }
+
+        self.spending_limits.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Load the spending limits of every channel that has them
+    pub fn load_spending_limits(&self) -> Result<Vec<(Hash, SpendingLimits)>> {
+        let mut all = Vec::new();
+
+        for result in self.spending_limits.iter() {
+            let (key, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            let channel_id = Hash::from_slice(&key)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+            let limits = bincode::deserialize(&value)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+            all.push((channel_id, limits));
+        }
+
+        Ok(all)
+    }
+
+    /// Remember a signed action so it is refused if replayed after a restart
+    pub fn store_seen_action(&self, id: &Hash, timestamp: u64) -> Result<()> {
+        self.seen_actions.insert(id.to_vec(), &timestamp.to_be_bytes())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.seen_actions.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Forget signed actions that have aged out of the timestamp window
+    pub fn remove_seen_actions(&self, ids: &[Hash]) -> Result<()> {
+        for id in ids {
+            self.seen_actions.remove(id.to_vec())
+                .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Every remembered signed action with the time it was signed
+    pub fn load_seen_actions(&self) -> Result<Vec<(Hash, u64)>> {
+        let mut all = Vec::new();
+
+        for result in self.seen_actions.iter() {
+            let (key, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            let id = Hash::from_slice(&key)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+            let timestamp: [u8; 8] = value.as_ref().try_into()
+                .map_err(|_| L2Error::SerializationError("Invalid seen action timestamp".to_string()))?;
+            all.push((id, u64::from_be_bytes(timestamp)));
+        }
+
+        Ok(all)
+    }
+
+    /// Store a watch blob held for a client, replacing their previous one
+    /// for the same channel
+    pub fn store_watch_blob(&self, blob: &WatchBlob) -> Result<()> {
+        let mut key = blob.locator.to_vec();
+        key.extend_from_slice(blob.client.as_bytes());
+        let value = bincode::serialize(blob)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.watch_blobs.insert(key, value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.watch_blobs.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Load every watch blob held for clients
+    pub fn load_watch_blobs(&self) -> Result<Vec<WatchBlob>> {
+        self.watch_blobs.iter()
+            .map(|result| {
+                let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+                bincode::deserialize(&value).map_err(|e| L2Error::SerializationError(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Replace the known peers with `peers`
+    pub fn store_known_peers(&self, peers: &[KnownPeer]) -> Result<()> {
+        let mut batch = sled::Batch::default();
+        for result in self.known_peers.iter().keys() {
+            let key = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            batch.remove(key);
+        }
+        for peer in peers {
+            let value = bincode::serialize(peer)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+            batch.insert(peer.peer_id.as_bytes(), value);
+        }
+
+        self.known_peers.apply_batch(batch)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.known_peers.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Load the peers remembered from earlier runs
+    pub fn load_known_peers(&self) -> Result<Vec<KnownPeer>> {
+        self.known_peers.iter()
+            .map(|result| {
+                let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+                bincode::deserialize(&value).map_err(|e| L2Error::SerializationError(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Store a contact, replacing any with the same label
+    pub fn store_contact(&self, contact: &Contact) -> Result<()> {
+        let value = bincode::serialize(contact)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.contacts.insert(contact.label.as_bytes(), value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.contacts.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn load_contact(&self, label: &str) -> Result<Option<Contact>> {
+        match self.contacts.get(label.as_bytes()).map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some(value) => bincode::deserialize(&value)
+                .map(Some)
+                .map_err(|e| L2Error::SerializationError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Every contact, ordered by label
+    pub fn load_contacts(&self) -> Result<Vec<Contact>> {
+        self.contacts.iter()
+            .map(|result| {
+                let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+                bincode::deserialize(&value).map_err(|e| L2Error::SerializationError(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Delete a contact. Returns whether it existed.
+    pub fn delete_contact(&self, label: &str) -> Result<bool> {
+        let removed = self.contacts.remove(label.as_bytes())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.contacts.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(removed.is_some())
+    }
+
+    /// Store an invoice this node issued
+    pub fn store_invoice(&self, invoice: &Invoice) -> Result<()> {
+        let value = bincode::serialize(invoice)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.invoices.insert(invoice.id().to_vec(), value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        self.invoices.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// An invoice this node issued, by ID
+    pub fn load_invoice(&self, invoice_id: &Hash) -> Result<Option<Invoice>> {
+        match self.invoices.get(invoice_id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some(value) => {
+                let invoice = bincode::deserialize(&value)
+                    .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+                Ok(Some(invoice))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Every invoice this node issued
+    pub fn load_invoices(&self) -> Result<Vec<Invoice>> {
+        let mut invoices = Vec::new();
+
+        for result in self.invoices.iter() {
+            let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            let invoice = bincode::deserialize(&value)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+            invoices.push(invoice);
+        }
+
+        Ok(invoices)
+    }
+
+    /// Replace the claim on paying an invoice, but only if it is still
+    /// `current`. Returns false, changing nothing, when another claim got
+    /// there first. A `new` of None releases the claim.
+    pub fn swap_invoice_claim(
+        &self,
+        invoice_id: &Hash,
+        current: Option<&InvoicePayment>,
+        new: Option<&InvoicePayment>,
+    ) -> Result<bool> {
+        let encode = |payment: Option<&InvoicePayment>| payment
+            .map(bincode::serialize)
+            .transpose()
+            .map_err(|e| L2Error::SerializationError(e.to_string()));
+        let (current, new) = (encode(current)?, encode(new)?);
+
+        let swapped = self.invoice_claims.compare_and_swap(invoice_id.to_vec(), current, new)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?
+            .is_ok();
+        self.invoice_claims.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(swapped)
+    }
+
+    /// The claim on paying an invoice, while a payment is being proposed
+    pub fn load_invoice_claim(&self, invoice_id: &Hash) -> Result<Option<InvoicePayment>> {
+        match self.invoice_claims.get(invoice_id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some(value) => {
+                let payment = bincode::deserialize(&value)
+                    .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+                Ok(Some(payment))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Record that an invoice was paid: by this node, or to it
+    pub fn store_invoice_payment(&self, payment: &InvoicePayment) -> Result<()> {
+        let value = bincode::serialize(payment)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.paid_invoices.insert(payment.invoice.id().to_vec(), value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        self.paid_invoices.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// The committed payment of an invoice, by invoice ID
+    pub fn load_invoice_payment(&self, invoice_id: &Hash) -> Result<Option<InvoicePayment>> {
+        match self.paid_invoices.get(invoice_id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some(value) => {
+                let payment = bincode::deserialize(&value)
+                    .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+                Ok(Some(payment))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Store a wallet's payout preference, replacing any earlier one
+    pub fn store_payout_preference(&self, preference: &PayoutPreference) -> Result<()> {
+        let value = bincode::serialize(preference)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.payout_preferences.insert(preference.public_key.to_string().as_bytes(), value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.payout_preferences.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn load_payout_preference(&self, public_key: &PublicKey) -> Result<Option<PayoutPreference>> {
+        match self.payout_preferences.get(public_key.to_string().as_bytes())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some(value) => bincode::deserialize(&value)
+                .map(Some)
+                .map_err(|e| L2Error::SerializationError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Append an entry to the audit log. Entries are never overwritten.
+    pub fn append_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+        let value = bincode::serialize(entry)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.audit_log.compare_and_swap(entry.seq.to_be_bytes(), None as Option<&[u8]>, Some(value))
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?
+            .map_err(|_| L2Error::DatabaseError(format!("Audit entry {} already exists", entry.seq)))?;
+        self.audit_log.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Up to `limit` audit entries in order, starting at `from`
+    pub fn load_audit_entries(&self, from: u64, limit: usize) -> Result<Vec<AuditEntry>> {
+        let mut entries = Vec::new();
+        for result in self.audit_log.range(from.to_be_bytes()..).take(limit) {
+            let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            entries.push(bincode::deserialize(&value)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?);
+        }
+        Ok(entries)
+    }
+
+    /// The most recent audit entry
+    pub fn last_audit_entry(&self) -> Result<Option<AuditEntry>> {
+        match self.audit_log.last()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some((_, value)) => bincode::deserialize(&value)
+                .map(Some)
+                .map_err(|e| L2Error::SerializationError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Mark that an event meant for audit entry `seq` was never appended
+    pub fn record_audit_gap(&self, seq: u64) -> Result<()> {
+        self.audit_gaps.insert(seq.to_be_bytes(), &[])
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.audit_gaps.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// The earliest audit entry an event is missing from
+    pub fn first_audit_gap(&self) -> Result<Option<u64>> {
+        match self.audit_gaps.first()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some((key, _)) => key.as_ref().try_into()
+                .map(|bytes| Some(u64::from_be_bytes(bytes)))
+                .map_err(|_| L2Error::SerializationError("Invalid audit gap key".to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Store an escrow, replacing its previous state
+    pub fn store_escrow(&self, escrow: &EscrowContract) -> Result<()> {
+        let value = bincode::serialize(escrow)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.escrows.insert(escrow.id.to_vec(), value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.escrows.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn load_escrow(&self, escrow_id: &Hash) -> Result<Option<EscrowContract>> {
+        match self.escrows.get(escrow_id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some(value) => bincode::deserialize(&value)
+                .map(Some)
+                .map_err(|e| L2Error::SerializationError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Every stored escrow
+    pub fn load_escrows(&self) -> Result<Vec<EscrowContract>> {
+        self.escrows.iter()
+            .map(|result| {
+                let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+                bincode::deserialize(&value).map_err(|e| L2Error::SerializationError(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Delete an escrow. Returns whether it existed.
+    pub fn delete_escrow(&self, escrow_id: &Hash) -> Result<bool> {
+        let removed = self.escrows.remove(escrow_id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.escrows.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(removed.is_some())
+    }
+
+    /// Store an order placed in `channel_id`, replacing its previous status
+    pub fn store_order(&self, channel_id: &Hash, order: &Order) -> Result<()> {
+        let value = bincode::serialize(&StoredOrder { channel_id: *channel_id, order: order.clone() })
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.orders.insert(order.id.to_vec(), value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.orders.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Every stored order with its channel ID
+    pub fn load_orders(&self) -> Result<Vec<(Hash, Order)>> {
+        self.orders.iter()
+            .map(|result| {
+                let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+                let stored: StoredOrder = bincode::deserialize(&value)
+                    .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+                Ok((stored.channel_id, stored.order))
+            })
+            .collect()
+    }
+
+    pub fn store_saved_search(&self, search: &SavedSearch) -> Result<()> {
+        let value = bincode::serialize(search)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        self.saved_searches.insert(search.id.to_vec(), value)
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.saved_searches.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Every saved search
+    pub fn load_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        self.saved_searches.iter()
+            .map(|result| {
+                let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+                bincode::deserialize(&value).map_err(|e| L2Error::SerializationError(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Delete a saved search and its matches. Returns whether it existed.
+    pub fn delete_saved_search(&self, search_id: &Hash) -> Result<bool> {
+        let removed = self.saved_searches.remove(search_id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        for result in self.search_matches.scan_prefix(search_id.as_bytes()) {
+            let (key, _) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            self.search_matches.remove(key)
+                .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        }
+        self.saved_searches.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.search_matches.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(removed.is_some())
+    }
+
+    /// Record a match, keyed by search ID then listing ID. Returns false,
+    /// leaving the first one, if the listing already matched the search.
+    pub fn record_search_match(&self, search_match: &SearchMatch) -> Result<bool> {
+        let mut key = search_match.search_id.to_vec();
+        key.extend_from_slice(search_match.listing_id.as_bytes());
+
+        let value = bincode::serialize(search_match)
+            .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+
+        let inserted = self.search_matches.compare_and_swap(key, None as Option<&[u8]>, Some(value))
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?
+            .is_ok();
+        self.search_matches.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(inserted)
+    }
+
+    /// Delete every saved search's match of the given listings
+    pub fn delete_search_matches(&self, listing_ids: &[Hash]) -> Result<()> {
+        for result in self.saved_searches.iter().keys() {
+            let search_id = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            for listing_id in listing_ids {
+                let mut key = search_id.to_vec();
+                key.extend_from_slice(listing_id.as_bytes());
+                self.search_matches.remove(key)
+                    .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            }
+        }
+        self.search_matches.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Record when a background task last ran
+    pub fn store_task_run(&self, task: &str, at: u64) -> Result<()> {
+        self.task_runs.insert(task.as_bytes(), &at.to_be_bytes())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.task_runs.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// When a background task last ran, if it ever has
+    pub fn load_task_run(&self, task: &str) -> Result<Option<u64>> {
+        match self.task_runs.get(task.as_bytes())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some(value) => {
+                let at: [u8; 8] = value.as_ref().try_into()
+                    .map_err(|_| L2Error::SerializationError("Invalid task run time".to_string()))?;
+                Ok(Some(u64::from_be_bytes(at)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Record when an unfunded order was first seen
+    pub fn store_order_first_seen(&self, order_id: &Hash, at: u64) -> Result<()> {
+        self.order_first_seen.insert(order_id.to_vec(), &at.to_be_bytes())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.order_first_seen.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// When each unfunded order was first seen
+    pub fn load_order_first_seen(&self) -> Result<Vec<(Hash, u64)>> {
+        let mut all = Vec::new();
+
+        for result in self.order_first_seen.iter() {
+            let (key, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+            let order_id = Hash::from_slice(&key)
+                .map_err(|e| L2Error::SerializationError(e.to_string()))?;
+            let at: [u8; 8] = value.as_ref().try_into()
+                .map_err(|_| L2Error::SerializationError("Invalid first seen time".to_string()))?;
+            all.push((order_id, u64::from_be_bytes(at)));
+        }
+
+        Ok(all)
+    }
+
+    /// Record the timestamp of the latest seller update applied to a listing
+    pub fn store_listing_version(&self, listing_id: &Hash, timestamp: u64) -> Result<()> {
+        self.listing_versions.insert(listing_id.to_vec(), &timestamp.to_be_bytes())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.listing_versions.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Timestamp of the latest seller update applied to a listing, if any
+    pub fn load_listing_version(&self, listing_id: &Hash) -> Result<Option<u64>> {
+        match self.listing_versions.get(listing_id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))? {
+            Some(value) => {
+                let at: [u8; 8] = value.as_ref().try_into()
+                    .map_err(|_| L2Error::SerializationError("Invalid listing version".to_string()))?;
+                Ok(Some(u64::from_be_bytes(at)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Forget when an order was first seen, once it is funded or settled
+    pub fn delete_order_first_seen(&self, order_id: &Hash) -> Result<()> {
+        self.order_first_seen.remove(order_id.to_vec())
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        self.order_first_seen.flush()
+            .map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Every match of one saved search
+    pub fn load_search_matches(&self, search_id: &Hash) -> Result<Vec<SearchMatch>> {
+        self.search_matches.scan_prefix(search_id.as_bytes())
+            .map(|result| {
+                let (_, value) = result.map_err(|e| L2Error::DatabaseError(e.to_string()))?;
+                bincode::deserialize(&value).map_err(|e| L2Error::SerializationError(e.to_string()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_state_channel::ChannelConfig;
+    use tari_l2_common::{Amount, crypto::KeyPair};
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_storage_operations() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = MarketplaceStorage::open(temp_dir.path()).unwrap();
+
+        let kp1 = KeyPair::generate();
+        let kp2 = KeyPair::generate();
+
+        let mut balances = HashMap::new();
+        balances.insert(kp1.public_key(), Amount::new(1000));
+        balances.insert(kp2.public_key(), Amount::new(1000));
+
+        let config = ChannelConfig {
+            participants: vec![kp1.public_key(), kp2.public_key()],
+            initial_balances: balances,
+            challenge_period: 3600,
+        };
+
+        let channel = MarketplaceChannel::new(config);
+        let channel_id = channel.channel_id;
+
+        // Store channel
+        storage.store_channel(&channel).unwrap();
+
+        // Load channel
+        let loaded = storage.load_channel(&channel_id).unwrap();
+        assert!(loaded.is_some());
+        assert_eq!(loaded.unwrap().channel_id, channel_id);
+
+        // List channels
+        let channels = storage.list_channels().unwrap();
+        assert_eq!(channels.len(), 1);
+
+        // Delete channel
+        storage.delete_channel(&channel_id).unwrap();
+        let deleted = storage.load_channel(&channel_id).unwrap();
+        assert!(deleted.is_none());
+    }
+
+    #[test]
+    fn test_channel_rebuilt_from_snapshot_and_update_log() {
+        use tari_l2_state_channel::StateUpdate;
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = MarketplaceStorage::open(temp_dir.path()).unwrap();
+
+        let kp1 = KeyPair::generate();
+        let kp2 = KeyPair::generate();
+        let mut channel = MarketplaceChannel::new(ChannelConfig {
+            participants: vec![kp1.public_key(), kp2.public_key()],
+            initial_balances: [(kp1.public_key(), Amount::new(1000)), (kp2.public_key(), Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        });
+        channel.activate().unwrap();
+        storage.store_channel(&channel).unwrap();
+
+        // Cross one snapshot so the load starts from it and replays the rest
+        for nonce in 1..=SNAPSHOT_INTERVAL + 3 {
+            let mut signed = SignedStateUpdate::new(StateUpdate::Transfer {
+                from: kp1.public_key(),
+                to: kp2.public_key(),
+                amount: Amount::new(1),
+            }, nonce);
+            signed.add_signature(kp1.public_key(), kp1.sign(&signed.signing_message()));
+            signed.add_signature(kp2.public_key(), kp2.sign(&signed.signing_message()));
+            channel.apply_update_at(signed, tari_l2_common::Timestamp::from_secs(nonce)).unwrap();
+            storage.append_update(&channel).unwrap();
+        }
+
+        let loaded = storage.load_channel(&channel.channel_id).unwrap().unwrap();
+        assert_eq!(loaded.state.nonce, SNAPSHOT_INTERVAL + 3);
+        assert_eq!(loaded.state_history.len(), channel.state_history.len());
+        assert_eq!(loaded.get_state_root(), channel.get_state_root());
+        assert_eq!(loaded.metrics.updates, channel.metrics.updates);
+        assert_eq!(loaded.metrics.last_update_at, channel.metrics.last_update_at);
+
+        storage.delete_channel(&channel.channel_id).unwrap();
+        assert_eq!(storage.last_logged_nonce(&channel.channel_id).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_listing_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = MarketplaceStorage::open(temp_dir.path()).unwrap()
+            .with_limits(ResourceLimits { max_listings: 1, ..Default::default() });
+
+        let seller = KeyPair::generate().public_key();
+        let make_listing = |title: &str| Listing {
+            id: Hash::random(),
+            seller,
+            title: title.to_string(),
+            description: String::new(),
+            price: Amount::new(100),
+            ipfs_hash: String::new(),
+            active: true,
+            category: "other".to_string(),
+            fiat_reference: None,
+        };
+
+        let first = make_listing("first");
+        storage.store_listing(&first).unwrap();
+
+        // Overwriting an existing listing is still allowed
+        storage.store_listing(&first).unwrap();
+
+        let result = storage.store_listing(&make_listing("second"));
+        assert!(matches!(result, Err(L2Error::ResourceLimitExceeded(_))));
+    }
+
+    #[test]
+    fn test_drafts_stored_apart_from_listings() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = MarketplaceStorage::open(temp_dir.path()).unwrap();
+
+        let draft = Listing {
+            id: Hash::random(),
+            seller: KeyPair::generate().public_key(),
+            title: "draft".to_string(),
+            description: String::new(),
+            price: Amount::new(100),
+            ipfs_hash: String::new(),
+            active: false,
+            category: "other".to_string(),
+            fiat_reference: None,
+        };
+        storage.store_draft(&draft).unwrap();
+
+        assert_eq!(storage.load_drafts().unwrap().len(), 1);
+        assert!(storage.load_draft(&draft.id).unwrap().is_some());
+        assert!(storage.load_all_listings().unwrap().is_empty());
+
+        storage.delete_draft(&draft.id).unwrap();
+        assert!(storage.load_drafts().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_known_peers_replaced_on_store() {
+        use tari_l2_p2p::{Capability, PeerAdvert, PeerBook};
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = MarketplaceStorage::open(temp_dir.path()).unwrap();
+        let now = tari_l2_common::Timestamp::from_secs(1_000);
+
+        let mut book = PeerBook::default();
+        for peer_id in ["a", "b"] {
+            let advert = PeerAdvert {
+                public_key: Some(KeyPair::generate().public_key()),
+                binding: None,
+                capabilities: [Capability::Watchtower].into_iter().collect(),
+            };
+            book.record_identified(peer_id, vec!["/ip4/10.0.0.1/tcp/9000".to_string()], advert, now);
+        }
+        storage.store_known_peers(&book.peers(now)).unwrap();
+        let mut loaded = storage.load_known_peers().unwrap();
+        loaded.sort_by(|x, y| x.peer_id.cmp(&y.peer_id));
+        assert_eq!(loaded, book.peers(now));
+
+        // Peers dropped from the book are dropped from storage too
+        storage.store_known_peers(&loaded[..1]).unwrap();
+        assert_eq!(storage.load_known_peers().unwrap(), loaded[..1].to_vec());
     }
 }
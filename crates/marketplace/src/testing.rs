@@ -0,0 +1,696 @@
+//! Fixtures for tests that need managers, channels and fully signed updates.
+//!
+//! Available to this crate's unit tests and, with the `test-utils` feature,
+//! to other crates' tests:
+//!
+//! ```ignore
+//! let harness = TestHarness::builder().nodes(2).build().await;
+//! let channel_id = harness.open_channel(&[0, 1], 1_000).await;
+//! harness.apply(&channel_id, &[0, 1], StateUpdate::Transfer { .. }).await?;
+//! ```
+//!
+//! Built with [`TestHarnessBuilder::network`], the nodes exchange proposals
+//! over a [`SimNetwork`] instead, and the harness can inject faults (lost
+//! links, delayed acks, crash-restarts, L1 outages) and check that channels
+//! resync without losing funds:
+//!
+//! ```ignore
+//! let mut harness = TestHarness::builder().nodes(3).network(SimConfig::default()).l1().build().await;
+//! let channels = vec![harness.open_channel(&[0, 1], 1_000).await];
+//! let report = harness.run_chaos(&channels, ChaosConfig::default()).await;
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use tempfile::TempDir;
+use tari_l2_common::{Amount, Hash, PublicKey, ResourceLimits, crypto::KeyPair, error::Result};
+use tari_l2_common::clock::SharedClock;
+use tari_l2_l1_client::{L1Config, OfflineChain, TariL1Client};
+use tari_l2_p2p::{L2Message, MessageHandler, NetworkInterface};
+use tari_l2_p2p::sim::{SimConfig, SimNetwork, SimNode};
+use tari_l2_state_channel::{
+    ChannelConfig, StateUpdate,
+    state::{Listing, Order, OrderStatus},
+    update::SignedStateUpdate,
+};
+use crate::manager::MarketplaceManager;
+use crate::storage::MarketplaceStorage;
+
+/// A manager with its own keypair and temporary database
+pub struct TestNode {
+    pub keypair: Arc<KeyPair>,
+    pub storage: Arc<MarketplaceStorage>,
+    pub manager: Arc<MarketplaceManager>,
+    /// Handle on the simulated network, when the harness has one
+    pub network: Option<Arc<SimNode>>,
+    /// Offline L1 client, when the harness has one
+    pub l1: Option<Arc<TariL1Client>>,
+    _dir: TempDir,
+}
+
+impl TestNode {
+    pub fn public_key(&self) -> PublicKey {
+        self.keypair.public_key()
+    }
+}
+
+/// Builder for [`TestHarness`]
+#[derive(Default)]
+pub struct TestHarnessBuilder {
+    nodes: usize,
+    clock: Option<SharedClock>,
+    limits: Option<ResourceLimits>,
+    network: Option<SimConfig>,
+    l1: bool,
+}
+
+impl TestHarnessBuilder {
+    /// Number of nodes to create (default 2)
+    pub fn nodes(mut self, nodes: usize) -> Self {
+        self.nodes = nodes;
+        self
+    }
+
+    /// Clock shared by every node's manager
+    pub fn clock(mut self, clock: SharedClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Resource limits applied to every node's manager
+    pub fn limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Connect the nodes over a simulated network. Proposals sent with
+    /// [`TestHarness::propose`] are co-signed and acked by the receiving node.
+    pub fn network(mut self, config: SimConfig) -> Self {
+        self.network = Some(config);
+        self
+    }
+
+    /// Give every node its own offline L1 client on one shared chain, so L1
+    /// outages can be injected per node
+    pub fn l1(mut self) -> Self {
+        self.l1 = true;
+        self
+    }
+
+    pub async fn build(self) -> TestHarness {
+        let count = if self.nodes == 0 { 2 } else { self.nodes };
+        let sim = self.network.map(SimNetwork::new);
+        let mut harness = TestHarness {
+            nodes: Vec::with_capacity(count),
+            sim,
+            clock: self.clock,
+            limits: self.limits,
+            crashed: vec![false; count],
+        };
+
+        let chain = Arc::new(OfflineChain::default());
+        for _ in 0..count {
+            let dir = TempDir::new().expect("create temp dir");
+            let storage = Arc::new(MarketplaceStorage::open(dir.path()).expect("open storage"));
+            let keypair = Arc::new(KeyPair::generate());
+            let l1 = self.l1.then(|| Arc::new(TariL1Client::offline_on(L1Config::default(), chain.clone())));
+            let network = harness.sim.as_ref().map(|sim| sim.add_node(keypair.public_key()));
+
+            let node = TestNode {
+                manager: Arc::new(harness.new_manager(&storage, &keypair, &l1).await),
+                keypair,
+                storage,
+                network,
+                l1,
+                _dir: dir,
+            };
+            harness.attach(&node).await;
+            harness.nodes.push(node);
+        }
+
+        harness
+    }
+}
+
+/// Fault injection settings for [`TestHarness::run_chaos`]
+#[derive(Clone, Debug)]
+pub struct ChaosConfig {
+    /// Seed for fault and workload decisions
+    pub seed: u64,
+
+    /// Number of rounds; each proposes at most one update
+    pub rounds: usize,
+
+    /// Virtual milliseconds the network runs for each round
+    pub round_ms: u64,
+
+    /// Probability per round of cutting the link between two random nodes
+    pub disconnect_rate: f64,
+
+    /// Messages (including acks) are delayed by up to this much, which
+    /// reorders them and lets rounds overlap
+    pub max_ack_delay_ms: u64,
+
+    /// Probability per round of crashing a node right after a proposal is
+    /// sent; it restarts from storage at the start of the next round
+    pub crash_rate: f64,
+
+    /// Probability per round of an L1 outage while a checkpoint is attempted
+    pub l1_outage_rate: f64,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            rounds: 50,
+            round_ms: 20,
+            disconnect_rate: 0.2,
+            max_ack_delay_ms: 50,
+            crash_rate: 0.1,
+            l1_outage_rate: 0.2,
+        }
+    }
+}
+
+/// Faults injected and work done during a chaos run
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChaosReport {
+    pub proposed: usize,
+    pub disconnects: usize,
+    pub crashes: usize,
+    pub l1_outages: usize,
+    pub failed_checkpoints: usize,
+}
+
+/// Routes proposals and acks between harness nodes to their managers.
+///
+/// A proposal missing our signature is co-signed and acked; the proposer
+/// commits once the ack completes it and sends the fully signed copy back.
+/// Proposals for nonces we already applied mean the sender missed a message,
+/// so the manager answers with its ack or fully signed copy instead; ones
+/// ahead of us are buffered while the gap is requested.
+struct SyncHandler {
+    manager: Arc<MarketplaceManager>,
+}
+
+#[async_trait]
+impl MessageHandler for SyncHandler {
+    async fn handle_message(&self, from: PublicKey, message: L2Message) -> Result<Option<L2Message>> {
+        match message {
+            L2Message::StateUpdateProposal { channel_id, update } => {
+                self.manager.receive_state_update(&channel_id, update).await?;
+            }
+            L2Message::StateUpdateAck { channel_id, nonce, signature } => {
+                self.manager.handle_state_update_ack(&channel_id, nonce, signature).await?;
+            }
+            L2Message::StateUpdateGapRequest { channel_id, from_nonce, to_nonce } => {
+                return self.manager.handle_gap_request(&from, &channel_id, from_nonce, to_nonce).await;
+            }
+            L2Message::StateUpdateGapFill { channel_id, updates } => {
+                self.manager.handle_gap_fill(&channel_id, updates).await?;
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+}
+
+/// A set of nodes plus helpers for opening channels and applying updates
+pub struct TestHarness {
+    pub nodes: Vec<TestNode>,
+    sim: Option<SimNetwork>,
+    clock: Option<SharedClock>,
+    limits: Option<ResourceLimits>,
+    crashed: Vec<bool>,
+}
+
+impl TestHarness {
+    pub fn builder() -> TestHarnessBuilder {
+        TestHarnessBuilder::default()
+    }
+
+    pub fn node(&self, index: usize) -> &TestNode {
+        &self.nodes[index]
+    }
+
+    /// The simulated network, for harnesses built with `network`
+    pub fn sim(&self) -> &SimNetwork {
+        self.sim.as_ref().expect("harness was built without a network")
+    }
+
+    async fn new_manager(
+        &self,
+        storage: &Arc<MarketplaceStorage>,
+        keypair: &Arc<KeyPair>,
+        l1: &Option<Arc<TariL1Client>>,
+    ) -> MarketplaceManager {
+        let mut manager = MarketplaceManager::new(storage.clone(), keypair.clone(), l1.clone());
+        if let Some(clock) = &self.clock {
+            manager = manager.with_clock(clock.clone());
+        }
+        if let Some(limits) = &self.limits {
+            manager.set_limits(limits.clone()).await;
+        }
+        manager
+    }
+
+    /// Connect a node's manager to the simulated network, if there is one
+    async fn attach(&self, node: &TestNode) {
+        let (Some(sim), Some(network)) = (&self.sim, &node.network) else {
+            return;
+        };
+        node.manager.set_network(network.clone()).await;
+        sim.set_handler(&node.public_key(), Arc::new(SyncHandler {
+            manager: node.manager.clone(),
+        }));
+    }
+
+    fn index_of(&self, public_key: &PublicKey) -> usize {
+        self.nodes.iter().position(|n| n.public_key() == *public_key).expect("participant is a harness node")
+    }
+
+    /// Open and activate a channel between `members`, each funded with
+    /// `balance`, on every member's manager
+    pub async fn open_channel(&self, members: &[usize], balance: u64) -> Hash {
+        let participants: Vec<PublicKey> = members.iter().map(|i| self.nodes[*i].public_key()).collect();
+        let initial_balances: HashMap<PublicKey, Amount> = participants.iter()
+            .map(|pk| (*pk, Amount::new(balance)))
+            .collect();
+        let config = ChannelConfig {
+            participants,
+            initial_balances,
+            challenge_period: 3600,
+        };
+
+        let mut channel_id = None;
+        for i in members {
+            let manager = &self.nodes[*i].manager;
+            let id = manager.create_channel(config.clone()).await.expect("create channel");
+            manager.activate_channel(&id).await.expect("activate channel");
+            channel_id = Some(id);
+        }
+        channel_id.expect("channel needs at least one member")
+    }
+
+    /// Propose `update` from the first member, sign it with every member and
+    /// apply it on all of their managers
+    pub async fn apply(&self, channel_id: &Hash, members: &[usize], update: StateUpdate) -> Result<SignedStateUpdate> {
+        let proposer = &self.nodes[members[0]].manager;
+        let mut signed = proposer.create_state_update(channel_id, update).await?;
+        for i in &members[1..] {
+            self.nodes[*i].manager.add_signature_to_update(&mut signed).await?;
+        }
+
+        for i in members {
+            self.nodes[*i].manager.apply_state_update(channel_id, signed.clone()).await?;
+        }
+        Ok(signed)
+    }
+
+    /// Create `update` on `proposer` and send it to the other participants.
+    /// Nothing is delivered until the network runs (e.g. [`Self::settle`]).
+    pub async fn propose(&self, channel_id: &Hash, proposer: usize, update: StateUpdate) -> Result<SignedStateUpdate> {
+        let node = &self.nodes[proposer];
+        let signed = node.manager.create_state_update(channel_id, update).await?;
+        self.send_to_peers(proposer, channel_id, &signed).await?;
+        Ok(signed)
+    }
+
+    async fn send_to_peers(&self, from: usize, channel_id: &Hash, update: &SignedStateUpdate) -> Result<()> {
+        let node = &self.nodes[from];
+        let network = node.network.as_ref().expect("harness was built without a network");
+        let info = node.manager.get_channel_info(channel_id).await?;
+        for peer in info.participants.iter().filter(|pk| **pk != node.public_key()) {
+            network.send_message(*peer, L2Message::StateUpdateProposal {
+                channel_id: *channel_id,
+                update: update.clone(),
+            }).await?;
+        }
+        Ok(())
+    }
+
+    /// Deliver every queued message
+    pub async fn settle(&self) -> usize {
+        self.sim().run_until_idle().await
+    }
+
+    /// Cut the link between two nodes until [`Self::heal`]
+    pub fn disconnect(&self, a: usize, b: usize) {
+        self.sim().partition(&[self.nodes[a].public_key()], &[self.nodes[b].public_key()]);
+    }
+
+    /// Restore every link
+    pub fn heal(&self) {
+        self.sim().heal();
+    }
+
+    /// Stop a node: messages to it are lost and its in-memory state is
+    /// dropped on [`Self::restart`]
+    pub fn crash(&mut self, index: usize) {
+        self.sim().remove_handler(&self.nodes[index].public_key());
+        self.crashed[index] = true;
+    }
+
+    pub fn is_crashed(&self, index: usize) -> bool {
+        self.crashed[index]
+    }
+
+    /// Rebuild a node's manager from its database, replaying the update
+    /// journal, and reconnect it to the network
+    pub async fn restart(&mut self, index: usize) {
+        let (storage, keypair, l1) = {
+            let node = &self.nodes[index];
+            (node.storage.clone(), node.keypair.clone(), node.l1.clone())
+        };
+        let manager = self.new_manager(&storage, &keypair, &l1).await;
+        manager.load_channels().await.expect("reload channels");
+        manager.load_orders().await.expect("reload orders");
+        manager.load_escrows().await.expect("reload escrows");
+        manager.recover_journal().await.expect("recover journal");
+
+        self.nodes[index].manager = Arc::new(manager);
+        self.attach(&self.nodes[index]).await;
+        self.crashed[index] = false;
+    }
+
+    /// The sync protocol: every running node resends the updates it still
+    /// has pending, and peers that already applied them answer with their
+    /// fully signed copy. Returns once the network is idle.
+    pub async fn resync(&self) -> Result<()> {
+        for (i, node) in self.nodes.iter().enumerate() {
+            if self.crashed[i] {
+                continue;
+            }
+            for info in node.manager.list_channels().await {
+                for update in node.manager.list_pending_updates(&info.channel_id).await {
+                    self.send_to_peers(i, &info.channel_id, &update).await?;
+                }
+            }
+        }
+        self.settle().await;
+        Ok(())
+    }
+
+    /// Make every node's L1 client fail (or recover)
+    pub fn l1_outage(&self, down: bool) {
+        for node in &self.nodes {
+            if let Some(l1) = &node.l1 {
+                l1.set_outage(down);
+            }
+        }
+    }
+
+    /// Panic unless every participant of `channel_id` has the same nonce
+    /// and balances, and the balances still add up to the collateral
+    pub async fn assert_converged(&self, channel_id: &Hash) {
+        let participants = self.participants(channel_id).await;
+        let collateral = self.nodes[self.index_of(&participants[0])].manager
+            .get_channel_info(channel_id).await.expect("channel on participant").collateral;
+
+        let mut expected: Option<(u64, Vec<Amount>)> = None;
+        for pk in &participants {
+            let manager = &self.nodes[self.index_of(pk)].manager;
+            let nonce = manager.get_channel_info(channel_id).await.expect("channel on participant").nonce;
+            let mut balances = Vec::new();
+            for participant in &participants {
+                balances.push(manager.get_balance(channel_id, participant).await.expect("balance"));
+            }
+
+            let total: Amount = balances.iter().copied().sum::<Option<Amount>>().expect("balance overflow");
+            assert_eq!(total, collateral, "funds lost on channel {:?}", channel_id);
+            match &expected {
+                Some(view) => assert_eq!(view, &(nonce, balances), "participants diverged on channel {:?}", channel_id),
+                None => expected = Some((nonce, balances)),
+            }
+        }
+    }
+
+    /// Drive random transfers over `channels` while injecting faults, then
+    /// heal everything, resync and check each channel converged with its
+    /// collateral intact. Each channel's first participant proposes, so
+    /// updates never race from both sides.
+    pub async fn run_chaos(&mut self, channels: &[Hash], config: ChaosConfig) -> ChaosReport {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut report = ChaosReport::default();
+        let sim = self.sim().clone();
+        sim.set_jitter(config.max_ack_delay_ms);
+        let n = self.nodes.len();
+
+        for _ in 0..config.rounds {
+            // Faults from the previous round clear and crashed nodes come back
+            self.recover_all().await;
+
+            if rng.gen_bool(config.disconnect_rate) {
+                let (a, b) = (rng.gen_range(0..n), rng.gen_range(0..n));
+                if a != b {
+                    self.disconnect(a, b);
+                    report.disconnects += 1;
+                }
+            }
+
+            if rng.gen_bool(config.l1_outage_rate) {
+                self.l1_outage(true);
+                report.l1_outages += 1;
+            }
+            if self.nodes[rng.gen_range(0..n)].manager.checkpoint_channels().await.is_err() {
+                report.failed_checkpoints += 1;
+            }
+
+            let channel_id = channels[rng.gen_range(0..channels.len())];
+            let participants = self.participants(&channel_id).await;
+            let (from, to) = if rng.gen_bool(0.5) {
+                (participants[0], participants[1])
+            } else {
+                (participants[1], participants[0])
+            };
+            let update = StateUpdate::Transfer { from, to, amount: Amount::new(rng.gen_range(1..=10)) };
+            if self.propose(&channel_id, self.index_of(&participants[0]), update).await.is_ok() {
+                report.proposed += 1;
+            }
+
+            // Crash while the proposal is still in flight
+            if rng.gen_bool(config.crash_rate) {
+                self.crash(rng.gen_range(0..n));
+                report.crashes += 1;
+            }
+
+            sim.run_for(config.round_ms).await;
+        }
+
+        self.recover_all().await;
+        sim.set_drop_rate(0.0);
+        self.settle().await;
+        self.resync().await.expect("resync");
+
+        for channel_id in channels {
+            self.assert_converged(channel_id).await;
+        }
+        report
+    }
+
+    /// Restart crashed nodes, restore links and end any L1 outage
+    async fn recover_all(&mut self) {
+        for i in 0..self.nodes.len() {
+            if self.crashed[i] {
+                self.restart(i).await;
+            }
+        }
+        self.heal();
+        self.l1_outage(false);
+    }
+
+    async fn participants(&self, channel_id: &Hash) -> Vec<PublicKey> {
+        for node in &self.nodes {
+            if let Ok(info) = node.manager.get_channel_info(channel_id).await {
+                return info.participants;
+            }
+        }
+        panic!("no node has channel {:?}", channel_id);
+    }
+}
+
+/// Sign an update with every given keypair
+pub fn sign_with_all(update: StateUpdate, nonce: u64, keypairs: &[&KeyPair]) -> SignedStateUpdate {
+    let mut signed = SignedStateUpdate::new(update, nonce);
+    let message = signed.signing_message();
+    for kp in keypairs {
+        signed.add_signature(kp.public_key(), kp.sign(&message));
+    }
+    signed
+}
+
+/// An active listing with placeholder details
+pub fn listing(seller: PublicKey, price: u64) -> Listing {
+    Listing {
+        id: Hash::random(),
+        seller,
+        title: "Test item".to_string(),
+        description: "Test description".to_string(),
+        price: Amount::new(price),
+        ipfs_hash: String::new(),
+        active: true,
+        category: "test".to_string(),
+        fiat_reference: None,
+    }
+}
+
+/// A pending order for `listing`
+pub fn order(listing: &Listing, buyer: PublicKey) -> Order {
+    Order {
+        id: Hash::random(),
+        listing_id: listing.id,
+        buyer,
+        seller: listing.seller,
+        amount: listing.price,
+        status: OrderStatus::Pending,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_harness_purchase_flow() {
+        let harness = TestHarness::builder().nodes(2).build().await;
+        let (seller, buyer) = (harness.node(0).public_key(), harness.node(1).public_key());
+        let channel_id = harness.open_channel(&[0, 1], 1_000).await;
+
+        let item = listing(seller, 300);
+        let purchase = order(&item, buyer);
+        harness.apply(&channel_id, &[0, 1], StateUpdate::CreateListing { listing: item }).await.unwrap();
+        harness.apply(&channel_id, &[1, 0], StateUpdate::CreateOrder { order: purchase.clone() }).await.unwrap();
+        harness.apply(&channel_id, &[1, 0], StateUpdate::UpdateOrderStatus {
+            order_id: purchase.id,
+            status: OrderStatus::Completed,
+        }).await.unwrap();
+
+        // Both sides converge on the same balances
+        for node in &harness.nodes {
+            assert_eq!(node.manager.get_balance(&channel_id, &seller).await.unwrap(), Amount::new(1_300));
+            assert_eq!(node.manager.get_balance(&channel_id, &buyer).await.unwrap(), Amount::new(700));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_order_moves_checked_against_the_proposers_role() {
+        let harness = TestHarness::builder().nodes(2).build().await;
+        let (seller, buyer) = (harness.node(0).public_key(), harness.node(1).public_key());
+        let channel_id = harness.open_channel(&[0, 1], 1_000).await;
+
+        let item = listing(seller, 300);
+        let purchase = order(&item, buyer);
+        harness.apply(&channel_id, &[0, 1], StateUpdate::CreateListing { listing: item }).await.unwrap();
+        harness.apply(&channel_id, &[1, 0], StateUpdate::CreateOrder { order: purchase.clone() }).await.unwrap();
+
+        // Only the buyer completes an order, which pays the seller
+        let (proposer, counterparty) = (&harness.node(0).manager, &harness.node(1).manager);
+        let complete = StateUpdate::UpdateOrderStatus { order_id: purchase.id, status: OrderStatus::Completed };
+        let proposal = proposer.create_state_update(&channel_id, complete).await.unwrap();
+        assert!(matches!(
+            counterparty.receive_state_update(&channel_id, proposal).await,
+            Err(tari_l2_common::L2Error::InvalidStateTransition)
+        ));
+        assert!(counterparty.list_pending_updates(&channel_id).await.is_empty());
+
+        let ship = StateUpdate::UpdateOrderStatus { order_id: purchase.id, status: OrderStatus::Shipping };
+        let proposal = proposer.create_state_update(&channel_id, ship).await.unwrap();
+        counterparty.receive_state_update(&channel_id, proposal).await.unwrap();
+        assert_eq!(counterparty.list_pending_updates(&channel_id).await.len(), 1);
+    }
+
+    async fn nonce(harness: &TestHarness, node: usize, channel_id: &Hash) -> u64 {
+        harness.node(node).manager.get_channel_info(channel_id).await.unwrap().nonce
+    }
+
+    fn transfer(harness: &TestHarness, from: usize, to: usize, amount: u64) -> StateUpdate {
+        StateUpdate::Transfer {
+            from: harness.node(from).public_key(),
+            to: harness.node(to).public_key(),
+            amount: Amount::new(amount),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lost_ack_recovered_by_resync() {
+        let harness = TestHarness::builder().network(SimConfig::default()).build().await;
+        let channel_id = harness.open_channel(&[0, 1], 1_000).await;
+
+        // The counterparty co-signs and keeps a pending record, but its ack
+        // never arrives, so neither side commits
+        harness.propose(&channel_id, 0, transfer(&harness, 0, 1, 100)).await.unwrap();
+        assert!(harness.sim().step().await);
+        harness.disconnect(0, 1);
+        harness.settle().await;
+        assert_eq!((nonce(&harness, 0, &channel_id).await, nonce(&harness, 1, &channel_id).await), (0, 0));
+        assert_eq!(harness.node(1).manager.list_pending_updates(&channel_id).await.len(), 1);
+
+        harness.heal();
+        harness.resync().await.unwrap();
+        harness.assert_converged(&channel_id).await;
+        assert_eq!(nonce(&harness, 0, &channel_id).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_crash_restart_mid_update() {
+        let mut harness = TestHarness::builder().network(SimConfig::default()).build().await;
+        let channel_id = harness.open_channel(&[0, 1], 1_000).await;
+
+        // Proposer crashes before its proposal goes out; the journal keeps it
+        harness.node(0).manager.create_state_update(&channel_id, transfer(&harness, 0, 1, 100)).await.unwrap();
+        harness.crash(0);
+        harness.restart(0).await;
+        assert_eq!(harness.node(0).manager.list_pending_updates(&channel_id).await.len(), 1);
+
+        // Counterparty crashes while the proposal is in flight and misses it
+        harness.resync().await.unwrap();
+        harness.propose(&channel_id, 0, transfer(&harness, 1, 0, 30)).await.unwrap();
+        harness.crash(1);
+        harness.settle().await;
+        assert_eq!(nonce(&harness, 1, &channel_id).await, 1);
+
+        harness.restart(1).await;
+        harness.resync().await.unwrap();
+        harness.assert_converged(&channel_id).await;
+        assert_eq!(nonce(&harness, 0, &channel_id).await, 2);
+        let balance = harness.node(1).manager.get_balance(&channel_id, &harness.node(0).public_key()).await.unwrap();
+        assert_eq!(balance, Amount::new(930));
+    }
+
+    #[tokio::test]
+    async fn test_l1_outage_does_not_block_channels() {
+        let harness = TestHarness::builder().l1().build().await;
+        let channel_id = harness.open_channel(&[0, 1], 1_000).await;
+
+        harness.l1_outage(true);
+        assert!(harness.node(0).manager.checkpoint_channels().await.is_err());
+        harness.apply(&channel_id, &[0, 1], transfer(&harness, 0, 1, 100)).await.unwrap();
+
+        harness.l1_outage(false);
+        assert_eq!(harness.node(0).manager.checkpoint_channels().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_chaos_run_converges_without_losing_funds() {
+        let config = SimConfig { seed: 7, drop_rate: 0.1, ..Default::default() };
+        let mut harness = TestHarness::builder().nodes(3).network(config).l1().build().await;
+        let channels = vec![
+            harness.open_channel(&[0, 1], 1_000).await,
+            harness.open_channel(&[1, 2], 1_000).await,
+            harness.open_channel(&[2, 0], 1_000).await,
+        ];
+
+        let report = harness.run_chaos(&channels, ChaosConfig { seed: 7, rounds: 80, ..Default::default() }).await;
+
+        assert!(report.disconnects > 0 && report.crashes > 0 && report.l1_outages > 0);
+        assert!(report.failed_checkpoints >= report.l1_outages);
+        let mut applied = 0;
+        for channel_id in &channels {
+            applied += harness.node(0).manager.get_channel_info(channel_id).await.map(|i| i.nonce).unwrap_or(0);
+        }
+        assert!(applied > 0);
+    }
+}
@@ -18,10 +18,21 @@ use tari_crypto::{
     hashing::DomainSeparation,
 };
 use tari_hashing::KeyManagerDomain;
+use zeroize::{Zeroize, Zeroizing};
 
 type PrivateKey = tari_crypto::ristretto::RistrettoSecretKey;
 type PublicKey = tari_crypto::ristretto::RistrettoPublicKey;
 
+/// Header identifying an encrypted wallet keystore
+const KEYSTORE_MAGIC: &[u8; 4] = b"TL2W";
+const KEYSTORE_VERSION: u8 = 1;
+const KEYSTORE_HEADER_LEN: usize = 5;
+
+/// What a keystore holds: the seed phrase, or the spend key of a wallet
+/// imported without one
+const KEYSTORE_SEED: u8 = 0;
+const KEYSTORE_PRIVATE_KEY: u8 = 1;
+
 /// Full Tari wallet with proper key management using CipherSeed
 #[derive(Clone)]
 pub struct Wallet {
@@ -77,27 +88,29 @@ impl Wallet {
         const VIEW_KEY_INDEX: u64 = 1;
 
         // Derive spend key
-        let mut key_material = Vec::new();
+        let mut key_material = Zeroizing::new(Vec::new());
         key_material.extend_from_slice(KeyManagerDomain::domain_separation_tag("derive_key").as_bytes());
         key_material.extend_from_slice(cipher_seed.entropy());
         key_material.extend_from_slice(WALLET_BRANCH.as_bytes());
         key_material.extend_from_slice(&SPEND_KEY_INDEX.to_le_bytes());
 
-        let derive_key = Blake2b::<U64>::digest(&key_material);
+        let mut derive_key = Blake2b::<U64>::digest(&key_material[..]);
         let spend_key = PrivateKey::from_uniform_bytes(derive_key.as_ref())
             .map_err(|e| format!("Failed to create spend key: {:?}", e))?;
+        derive_key.as_mut_slice().zeroize();
         let public_spend_key = PubKeyTrait::from_secret_key(&spend_key);
 
         // Derive view key
-        let mut key_material = Vec::new();
+        let mut key_material = Zeroizing::new(Vec::new());
         key_material.extend_from_slice(KeyManagerDomain::domain_separation_tag("derive_key").as_bytes());
         key_material.extend_from_slice(cipher_seed.entropy());
         key_material.extend_from_slice(WALLET_BRANCH.as_bytes());
         key_material.extend_from_slice(&VIEW_KEY_INDEX.to_le_bytes());
 
-        let derive_key = Blake2b::<U64>::digest(&key_material);
+        let mut derive_key = Blake2b::<U64>::digest(&key_material[..]);
         let view_key = PrivateKey::from_uniform_bytes(derive_key.as_ref())
             .map_err(|e| format!("Failed to create view key: {:?}", e))?;
+        derive_key.as_mut_slice().zeroize();
         let public_view_key = PubKeyTrait::from_secret_key(&view_key);
 
         Ok(Self {
@@ -113,17 +126,14 @@ impl Wallet {
     /// Import wallet from private key (hex string)
     /// Note: This won't have a seed phrase since we're importing just the key
     pub fn from_private_key(private_key_hex: &str) -> Result<Self, String> {
-        let bytes = hex::decode(private_key_hex)
-            .map_err(|e| format!("Invalid hex: {}", e))?;
+        let bytes = Zeroizing::new(hex::decode(private_key_hex)
+            .map_err(|e| format!("Invalid hex: {}", e))?);
 
         if bytes.len() != 32 {
             return Err("Private key must be 32 bytes".to_string());
         }
 
-        let mut key_bytes = [0u8; 32];
-        key_bytes.copy_from_slice(&bytes);
-
-        let spend_key = PrivateKey::from_canonical_bytes(&key_bytes)
+        let spend_key = PrivateKey::from_canonical_bytes(&bytes[..])
             .map_err(|e| format!("Failed to create private key: {:?}", e))?;
         let public_spend_key = PubKeyTrait::from_secret_key(&spend_key);
 
@@ -196,15 +206,15 @@ impl Wallet {
         self.public_spend_key.as_bytes().to_vec()
     }
 
-    /// Export private key as hex (for backup)
-    pub fn export_private_key(&self) -> String {
-        hex::encode(self.spend_key.as_bytes())
+    /// Export private key as hex (for backup). Wiped when dropped.
+    pub fn export_private_key(&self) -> Zeroizing<String> {
+        Zeroizing::new(hex::encode(self.spend_key.as_bytes()))
     }
 
-    /// Get seed phrase for this wallet (24 words in Tari format)
-    pub fn seed_phrase(&self) -> Option<String> {
+    /// Get seed phrase for this wallet (24 words in Tari format). Wiped when dropped.
+    pub fn seed_phrase(&self) -> Option<Zeroizing<String>> {
         self.seed_words.as_ref().map(|sw| {
-            sw.join(" ").reveal().to_string()
+            Zeroizing::new(sw.join(" ").reveal().to_string())
         })
     }
 
@@ -217,6 +227,58 @@ impl Wallet {
         hash.to_vec()
     }
 
+    /// Keypair over the wallet's spend key, for signing channel updates with a Tari-native key
+    pub fn channel_keypair(&self) -> Result<tari_l2_common::crypto::KeyPair, String> {
+        tari_l2_common::crypto::KeyPair::from_ristretto_secret(self.spend_key.as_bytes())
+    }
+
+    /// Encrypt the wallet's seed phrase, or its spend key when it has none,
+    /// under a passphrase.
+    ///
+    /// Layout: magic (4) | version (1) | sealed secret, see
+    /// [`tari_l2_common::crypto::seal_with_passphrase`]. The header is
+    /// authenticated as associated data.
+    pub fn to_keystore(&self, passphrase: &str) -> Result<Vec<u8>, String> {
+        let mut secret = Zeroizing::new(Vec::new());
+        match self.seed_phrase() {
+            Some(seed_phrase) => {
+                secret.push(KEYSTORE_SEED);
+                secret.extend_from_slice(seed_phrase.as_bytes());
+            }
+            None => {
+                secret.push(KEYSTORE_PRIVATE_KEY);
+                secret.extend_from_slice(self.spend_key.as_bytes());
+            }
+        }
+
+        let mut out = KEYSTORE_MAGIC.to_vec();
+        out.push(KEYSTORE_VERSION);
+        let sealed = tari_l2_common::crypto::seal_with_passphrase(passphrase, &secret, &out)?;
+        out.extend_from_slice(&sealed);
+        Ok(out)
+    }
+
+    /// Decrypt a wallet written by [`Wallet::to_keystore`]
+    pub fn from_keystore(bytes: &[u8], passphrase: &str) -> Result<Self, String> {
+        if bytes.len() <= KEYSTORE_HEADER_LEN || &bytes[..4] != KEYSTORE_MAGIC {
+            return Err("Not a wallet keystore".to_string());
+        }
+        if bytes[4] != KEYSTORE_VERSION {
+            return Err(format!("Unsupported keystore version {}", bytes[4]));
+        }
+
+        let (header, sealed) = bytes.split_at(KEYSTORE_HEADER_LEN);
+        let secret = tari_l2_common::crypto::open_with_passphrase(passphrase, sealed, header)?;
+        match secret.split_first() {
+            Some((&KEYSTORE_SEED, seed_phrase)) => {
+                let seed_phrase = std::str::from_utf8(seed_phrase).map_err(|_| "Corrupted keystore".to_string())?;
+                Self::from_seed_phrase(seed_phrase)
+            }
+            Some((&KEYSTORE_PRIVATE_KEY, private_key)) => Self::from_private_key(&Zeroizing::new(hex::encode(private_key))),
+            _ => Err("Corrupted keystore".to_string()),
+        }
+    }
+
 }
 
 
@@ -242,7 +304,6 @@ mod tests {
         println!("Hex length: {} chars (should be 66 for 33 bytes)", hex_addr.len());
         println!("\nPublic key: {}", pubkey);
         println!("Public key length: {} chars", pubkey.len());
-        println!("\nPrivate key: {}", privkey);
         println!("Private key length: {} chars", privkey.len());
 
         // Verify seed phrase has 24 words
@@ -264,7 +325,6 @@ mod tests {
     fn test_wallet_import_export() {
         let wallet1 = Wallet::new();
         let seed_phrase = wallet1.seed_phrase().unwrap();
-        println!("Testing with seed: {}", seed_phrase);
 
         let wallet2 = Wallet::from_seed_phrase(&seed_phrase).unwrap();
         assert_eq!(wallet1.address(), wallet2.address());
@@ -280,4 +340,35 @@ mod tests {
         assert_eq!(wallet1.public_key_hex(), wallet2.public_key_hex());
         assert_eq!(wallet1.address(), wallet2.address());
     }
+
+    #[test]
+    fn test_wallet_keystore_roundtrip() {
+        let wallet = Wallet::new();
+        let keystore = wallet.to_keystore("hunter2").unwrap();
+
+        // Neither the seed phrase nor the key is stored in the clear
+        let seed_phrase = wallet.seed_phrase().unwrap();
+        assert!(!keystore.windows(seed_phrase.len()).any(|w| w == seed_phrase.as_bytes()));
+        assert!(Wallet::from_keystore(&keystore, "wrong").is_err());
+
+        let unlocked = Wallet::from_keystore(&keystore, "hunter2").unwrap();
+        assert_eq!(unlocked.address(), wallet.address());
+        assert_eq!(unlocked.seed_phrase().unwrap().as_str(), seed_phrase.as_str());
+
+        // A wallet imported from its key keeps the same spend key
+        let imported = Wallet::from_private_key(&wallet.export_private_key()).unwrap();
+        let unlocked = Wallet::from_keystore(&imported.to_keystore("hunter2").unwrap(), "hunter2").unwrap();
+        assert_eq!(unlocked.public_key_hex(), wallet.public_key_hex());
+        assert!(unlocked.seed_phrase().is_none());
+    }
+
+    #[test]
+    fn test_wallet_channel_keypair() {
+        let wallet = Wallet::new();
+        let keypair = wallet.channel_keypair().unwrap();
+        assert_eq!(keypair.public_key().as_bytes().to_vec(), wallet.public_key_bytes());
+
+        let signature = keypair.sign(b"channel update");
+        assert!(tari_l2_common::crypto::verify_signature(&keypair.public_key(), b"channel update", &signature));
+    }
 }
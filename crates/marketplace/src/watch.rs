@@ -0,0 +1,183 @@
+//! Watch blobs for delegating channel monitoring to a watchtower.
+//!
+//! A participant who may be offline while a counterparty closes exports a
+//! blob holding its latest co-signed state, encrypted under a key derived
+//! from the channel ID, and hands it to a watchtower node. The tower files
+//! blobs by a locator that is also derived from the channel ID, but it
+//! cannot work backwards from the locator to the channel. It learns which
+//! channel a blob covers, and can read the blob, only when it is told about
+//! a close of that channel. If that close is at an older nonce than the
+//! blob's, the tower challenges it with the blob's dispute evidence.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{Hash, PublicKey, Signature};
+use tari_l2_common::crypto::{self, hash_multiple};
+use tari_l2_state_channel::{DisputeEvidence, MarketplaceChannel};
+
+const LOCATOR_DOMAIN: &[u8] = b"tari-l2 watch locator";
+const KEY_DOMAIN: &[u8] = b"tari-l2 watch blob key";
+
+/// What the tower needs to challenge a stale close
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WatchPayload {
+    pub channel_id: Hash,
+
+    /// Nonce of the latest co-signed state. A close at a lower nonce is stale.
+    pub nonce: u64,
+
+    /// State root of that state
+    pub state_root: Hash,
+
+    /// Signed history proving that state, submitted with the challenge
+    pub evidence: DisputeEvidence,
+}
+
+/// Encrypted channel state left with a watchtower, signed by the
+/// participant it protects
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WatchBlob {
+    /// Files the blob without revealing the channel
+    pub locator: Hash,
+
+    /// Nonce of the sealed state, in the clear so the tower keeps the newest
+    pub nonce: u64,
+
+    /// Participant the blob protects; only they can replace it
+    pub client: PublicKey,
+
+    /// Sealed `WatchPayload`
+    #[serde(with = "hex_bytes")]
+    pub ciphertext: Vec<u8>,
+
+    /// Client's signature over the rest of the blob
+    pub signature: Signature,
+}
+
+impl WatchBlob {
+    /// Locator of the blobs covering `channel_id`
+    pub fn locator(channel_id: &Hash) -> Hash {
+        hash_multiple(&[LOCATOR_DOMAIN, channel_id.as_bytes()])
+    }
+
+    /// Seal `channel`'s latest state for `client`, signing with `sign_fn`
+    pub fn seal(
+        channel: &MarketplaceChannel,
+        client: PublicKey,
+        sign_fn: impl FnOnce(&[u8]) -> Signature,
+    ) -> Result<Self, String> {
+        let payload = WatchPayload {
+            channel_id: channel.channel_id,
+            nonce: channel.state.nonce,
+            state_root: channel.get_state_root(),
+            evidence: DisputeEvidence::from_channel(channel),
+        };
+        let plaintext = bincode::serialize(&payload).map_err(|e| e.to_string())?;
+
+        let locator = Self::locator(&channel.channel_id);
+        let aad = Self::header(&locator, payload.nonce, &client);
+        let ciphertext = crypto::seal(&Self::key(&channel.channel_id), &plaintext, &aad)?;
+
+        let mut blob = Self {
+            locator,
+            nonce: payload.nonce,
+            client,
+            ciphertext,
+            signature: Signature::new([0u8; 64]),
+        };
+        blob.signature = sign_fn(&blob.signing_message());
+        Ok(blob)
+    }
+
+    /// Check the client signed the blob
+    pub fn verify(&self) -> bool {
+        self.client.verify(&self.signing_message(), &self.signature)
+    }
+
+    /// Decrypt the payload once the channel is known. Fails unless the blob
+    /// really covers `channel_id` at the nonce it claims.
+    pub fn open(&self, channel_id: &Hash) -> Result<WatchPayload, String> {
+        if self.locator != Self::locator(channel_id) {
+            return Err("Blob does not cover this channel".to_string());
+        }
+        let aad = Self::header(&self.locator, self.nonce, &self.client);
+        let plaintext = crypto::open(&Self::key(channel_id), &self.ciphertext, &aad)?;
+        let payload: WatchPayload = bincode::deserialize(&plaintext).map_err(|e| e.to_string())?;
+
+        if payload.channel_id != *channel_id || payload.nonce != self.nonce {
+            return Err("Blob contents do not match its header".to_string());
+        }
+        Ok(payload)
+    }
+
+    fn key(channel_id: &Hash) -> [u8; 32] {
+        *hash_multiple(&[KEY_DOMAIN, channel_id.as_bytes()]).as_bytes()
+    }
+
+    fn header(locator: &Hash, nonce: u64, client: &PublicKey) -> Vec<u8> {
+        let mut header = locator.to_vec();
+        header.extend_from_slice(&nonce.to_le_bytes());
+        header.extend_from_slice(client.as_bytes());
+        header
+    }
+
+    fn signing_message(&self) -> Vec<u8> {
+        let mut message = Self::header(&self.locator, self.nonce, &self.client);
+        message.extend_from_slice(&self.ciphertext);
+        message
+    }
+}
+
+/// Hex in human-readable formats, raw bytes otherwise
+mod hex_bytes {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&hex::encode(bytes))
+        } else {
+            serializer.serialize_bytes(bytes)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        if deserializer.is_human_readable() {
+            hex::decode(String::deserialize(deserializer)?).map_err(de::Error::custom)
+        } else {
+            Vec::<u8>::deserialize(deserializer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tari_l2_common::{Amount, crypto::KeyPair};
+    use tari_l2_state_channel::ChannelConfig;
+
+    #[test]
+    fn test_watch_blob_opens_only_for_its_channel() {
+        let (a, b) = (KeyPair::generate(), KeyPair::generate());
+        let channel = MarketplaceChannel::new(ChannelConfig {
+            participants: vec![a.public_key(), b.public_key()],
+            initial_balances: HashMap::from([(a.public_key(), Amount::new(100)), (b.public_key(), Amount::new(100))]),
+            challenge_period: 3600,
+        });
+
+        let blob = WatchBlob::seal(&channel, a.public_key(), |msg| a.sign(msg)).unwrap();
+        assert!(blob.verify());
+        let json = serde_json::to_string(&blob).unwrap();
+        let blob: WatchBlob = serde_json::from_str(&json).unwrap();
+
+        let payload = blob.open(&channel.channel_id).unwrap();
+        assert_eq!(payload.state_root, channel.get_state_root());
+        assert_eq!(payload.evidence.nonce(), payload.nonce);
+        assert!(blob.open(&Hash::random()).is_err());
+
+        // Claiming a newer nonce breaks the signature and the decryption
+        let mut inflated = blob.clone();
+        inflated.nonce = 99;
+        assert!(!inflated.verify());
+        assert!(inflated.open(&channel.channel_id).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+use tari_l2_common::Amount;
+use tari_l2_marketplace::testing::{self, TestHarness};
+use tari_l2_state_channel::{OrderStatus, StateUpdate};
+
+#[tokio::test]
+async fn test_end_to_end_marketplace_flow() {
+    let harness = TestHarness::builder().nodes(2).build().await;
+    let seller = harness.node(0).public_key();
+    let buyer = harness.node(1).public_key();
+    let channel_id = harness.open_channel(&[0, 1], 1000).await;
+
+    // Seller lists an item
+    let listing = testing::listing(seller, 500);
+    harness.apply(&channel_id, &[0, 1], StateUpdate::CreateListing { listing: listing.clone() })
+        .await
+        .unwrap();
+
+    // Buyer orders it and the order moves through delivery
+    let order = testing::order(&listing, buyer);
+    harness.apply(&channel_id, &[1, 0], StateUpdate::CreateOrder { order: order.clone() })
+        .await
+        .unwrap();
+    for status in [OrderStatus::Confirmed, OrderStatus::Shipping, OrderStatus::Delivered] {
+        harness.apply(&channel_id, &[0, 1], StateUpdate::UpdateOrderStatus { order_id: order.id, status })
+            .await
+            .unwrap();
+    }
+    harness.apply(&channel_id, &[1, 0], StateUpdate::UpdateOrderStatus {
+        order_id: order.id,
+        status: OrderStatus::Completed,
+    }).await.unwrap();
+
+    for node in &harness.nodes {
+        assert_eq!(node.manager.get_balance(&channel_id, &seller).await.unwrap(), Amount::new(1500));
+        assert_eq!(node.manager.get_balance(&channel_id, &buyer).await.unwrap(), Amount::new(500));
+    }
+}
+
+#[tokio::test]
+async fn test_balance_transfer() {
+    let harness = TestHarness::builder().nodes(2).build().await;
+    let (a, b) = (harness.node(0).public_key(), harness.node(1).public_key());
+    let channel_id = harness.open_channel(&[0, 1], 1000).await;
+
+    harness.apply(&channel_id, &[0, 1], StateUpdate::Transfer { from: a, to: b, amount: Amount::new(100) })
+        .await
+        .unwrap();
+
+    let manager = &harness.node(1).manager;
+    assert_eq!(manager.get_balance(&channel_id, &a).await.unwrap(), Amount::new(900));
+    assert_eq!(manager.get_balance(&channel_id, &b).await.unwrap(), Amount::new(1100));
+}
+
+#[tokio::test]
+async fn test_update_needs_all_signatures() {
+    let harness = TestHarness::builder().nodes(2).build().await;
+    let (a, b) = (harness.node(0).public_key(), harness.node(1).public_key());
+    let channel_id = harness.open_channel(&[0, 1], 1000).await;
+
+    // Only the proposer signs
+    let manager = &harness.node(0).manager;
+    let signed = manager.transfer(&channel_id, a, b, Amount::new(100)).await.unwrap();
+    assert!(manager.apply_state_update(&channel_id, signed).await.is_err());
+    assert_eq!(manager.get_balance(&channel_id, &a).await.unwrap(), Amount::new(1000));
+}
+
+#[tokio::test]
+async fn test_redelivered_update_is_a_no_op() {
+    let harness = TestHarness::builder().nodes(2).build().await;
+    let (a, b) = (harness.node(0).public_key(), harness.node(1).public_key());
+    let channel_id = harness.open_channel(&[0, 1], 1000).await;
+
+    let applied = harness.apply(&channel_id, &[0, 1], StateUpdate::Transfer { from: a, to: b, amount: Amount::new(100) })
+        .await
+        .unwrap();
+
+    // The same fully signed update delivered again changes nothing
+    let manager = &harness.node(1).manager;
+    manager.apply_state_update(&channel_id, applied).await.unwrap();
+    assert_eq!(manager.get_channel_info(&channel_id).await.unwrap().nonce, 1);
+    assert_eq!(manager.get_balance(&channel_id, &b).await.unwrap(), Amount::new(1100));
+
+    // A different update at the applied nonce is still refused
+    let keypairs = [&*harness.node(0).keypair, &*harness.node(1).keypair];
+    let conflicting = testing::sign_with_all(StateUpdate::Transfer { from: a, to: b, amount: Amount::new(200) }, 1, &keypairs);
+    assert!(manager.apply_state_update(&channel_id, conflicting).await.is_err());
+    assert_eq!(manager.get_balance(&channel_id, &b).await.unwrap(), Amount::new(1100));
+}
+
+#[tokio::test]
+async fn test_multiple_channels() {
+    let harness = TestHarness::builder().nodes(4).build().await;
+
+    for i in 1..4 {
+        harness.open_channel(&[0, i], 1000).await;
+    }
+
+    assert_eq!(harness.node(0).manager.list_channels().await.len(), 3);
+    assert_eq!(harness.node(1).manager.list_channels().await.len(), 1);
+}
@@ -0,0 +1,11 @@
+//! Generate the Kotlin and Swift bindings from the built library:
+//!
+//! ```text
+//! cargo build -p tari-l2-mobile --release
+//! cargo run -p tari-l2-mobile --bin uniffi-bindgen -- generate \
+//!     --library target/release/libtari_l2_mobile.so --language kotlin --out-dir out
+//! ```
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
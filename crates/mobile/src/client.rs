@@ -0,0 +1,122 @@
+use std::sync::Arc;
+use serde_json::Value;
+use tari_l2_client::L2Client;
+use tari_l2_client::types::NewListing;
+use tari_l2_common::{Hash, PublicKey};
+use tari_l2_state_channel::StateUpdate;
+use crate::{MobileError, MobileWallet, from_json, parse, to_json};
+
+/// Client for a remote node. Every call returns the node's result as JSON.
+#[derive(uniffi::Object)]
+pub struct MobileClient {
+    inner: L2Client,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl MobileClient {
+    /// `endpoint` is `host:port` or an `http://` URL. With a `wallet`,
+    /// actions for its key are signed on the device.
+    #[uniffi::constructor]
+    pub fn new(endpoint: String, wallet: Option<Arc<MobileWallet>>) -> Arc<Self> {
+        let mut inner = L2Client::new(&endpoint);
+        if let Some(wallet) = wallet {
+            inner = inner.with_signer(wallet.keypair());
+        }
+        Arc::new(Self { inner })
+    }
+
+    /// Call any RPC method with raw JSON params
+    pub async fn call(&self, method: String, params_json: Option<String>) -> Result<String, MobileError> {
+        let params: Value = params_json.as_deref().map(from_json).transpose()?.unwrap_or(Value::Null);
+        to_json(&self.inner.call::<_, Value>(&method, params).await?)
+    }
+
+    pub async fn get_node_info(&self) -> Result<String, MobileError> {
+        to_json(&self.inner.get_node_info().await?)
+    }
+
+    pub async fn list_channels(&self) -> Result<String, MobileError> {
+        to_json(&self.inner.list_channels().await?)
+    }
+
+    pub async fn get_balance(&self, channel_id: String, participant: String) -> Result<u64, MobileError> {
+        let channel_id: Hash = parse(&channel_id, "channel id")?;
+        let participant: PublicKey = parse(&participant, "public key")?;
+        Ok(self.inner.get_balance(&channel_id, &participant).await?)
+    }
+
+    pub async fn transfer(&self, channel_id: String, from: String, to: String, amount: u64) -> Result<String, MobileError> {
+        let channel_id: Hash = parse(&channel_id, "channel id")?;
+        let from: PublicKey = parse(&from, "public key")?;
+        let to: PublicKey = parse(&to, "public key")?;
+        to_json(&self.inner.transfer(&channel_id, from, to, amount).await?)
+    }
+
+    /// Sign a JSON channel update as the next state and have the node co-sign it
+    pub async fn propose_update(&self, channel_id: String, update_json: String) -> Result<String, MobileError> {
+        let channel_id: Hash = parse(&channel_id, "channel id")?;
+        let update: StateUpdate = from_json(&update_json)?;
+        to_json(&self.inner.propose_update(&channel_id, update).await?)
+    }
+
+    pub async fn create_listing(
+        &self,
+        title: String,
+        description: String,
+        price: u64,
+        category: Option<String>,
+    ) -> Result<String, MobileError> {
+        let listing = NewListing { title, description, price, category, ..Default::default() };
+        to_json(&self.inner.create_listing(listing).await?)
+    }
+
+    pub async fn get_listings(&self) -> Result<String, MobileError> {
+        to_json(&self.inner.get_listings().await?)
+    }
+
+    pub async fn get_seller_catalog(&self, seller: String) -> Result<String, MobileError> {
+        let seller: PublicKey = parse(&seller, "public key")?;
+        to_json(&self.inner.get_seller_catalog(seller).await?)
+    }
+
+    pub async fn create_order(&self, channel_id: String, listing_id: String, buyer: String) -> Result<String, MobileError> {
+        let channel_id: Hash = parse(&channel_id, "channel id")?;
+        let listing_id: Hash = parse(&listing_id, "listing id")?;
+        let buyer: PublicKey = parse(&buyer, "public key")?;
+        to_json(&self.inner.create_order(&channel_id, &listing_id, buyer).await?)
+    }
+
+    pub async fn get_orders(&self) -> Result<String, MobileError> {
+        to_json(&self.inner.get_orders().await?)
+    }
+
+    pub async fn get_order(&self, order_id: String) -> Result<String, MobileError> {
+        let order_id: Hash = parse(&order_id, "order id")?;
+        to_json(&self.inner.get_order(&order_id).await?)
+    }
+
+    pub async fn get_escrow(&self, escrow_id: String) -> Result<String, MobileError> {
+        let escrow_id: Hash = parse(&escrow_id, "escrow id")?;
+        to_json(&self.inner.get_escrow(&escrow_id).await?)
+    }
+
+    pub async fn confirm_delivery(&self, escrow_id: String) -> Result<String, MobileError> {
+        let escrow_id: Hash = parse(&escrow_id, "escrow id")?;
+        to_json(&self.inner.confirm_delivery(&escrow_id).await?)
+    }
+
+    pub async fn request_refund(&self, escrow_id: String, reason: String) -> Result<String, MobileError> {
+        let escrow_id: Hash = parse(&escrow_id, "escrow id")?;
+        to_json(&self.inner.request_refund(&escrow_id, &reason).await?)
+    }
+
+    pub async fn refund_unshipped(&self, escrow_id: String) -> Result<String, MobileError> {
+        let escrow_id: Hash = parse(&escrow_id, "escrow id")?;
+        to_json(&self.inner.refund_unshipped(&escrow_id).await?)
+    }
+
+    pub async fn raise_dispute(&self, escrow_id: String, reason: String) -> Result<String, MobileError> {
+        let escrow_id: Hash = parse(&escrow_id, "escrow id")?;
+        to_json(&self.inner.raise_dispute(&escrow_id, &reason).await?)
+    }
+}
@@ -0,0 +1,64 @@
+//! UniFFI bindings for mobile apps. Keys are generated and held on the
+//! device by [`MobileWallet`]; [`MobileClient`] talks to a remote node and
+//! signs listings, orders, transfers and channel updates locally.
+//!
+//! Results are returned as JSON strings in the shapes documented for the
+//! node's RPC methods, so apps decode them with their usual JSON tooling.
+
+use serde::Serialize;
+use tari_l2_client::ClientError;
+
+mod client;
+mod wallet;
+
+pub use client::MobileClient;
+pub use wallet::MobileWallet;
+
+uniffi::setup_scaffolding!();
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum MobileError {
+    /// The node rejected the call
+    #[error("RPC error {code}: {message}")]
+    Rpc { code: i32, message: String, category: Option<String> },
+
+    /// The node could not be reached or did not answer in time
+    #[error("Network error: {message}")]
+    Network { message: String },
+
+    #[error("Invalid input: {message}")]
+    InvalidInput { message: String },
+
+    #[error("Wallet error: {message}")]
+    Wallet { message: String },
+}
+
+impl From<ClientError> for MobileError {
+    fn from(error: ClientError) -> Self {
+        match error {
+            ClientError::Rpc { code, message, category } => MobileError::Rpc {
+                code,
+                message,
+                category: category.map(|c| c.as_str().to_string()),
+            },
+            ClientError::Signing(message) => MobileError::Wallet { message },
+            other => MobileError::Network { message: other.to_string() },
+        }
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, MobileError> {
+    serde_json::to_string(value).map_err(|e| MobileError::InvalidInput { message: e.to_string() })
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(json: &str) -> Result<T, MobileError> {
+    serde_json::from_str(json).map_err(|e| MobileError::InvalidInput { message: e.to_string() })
+}
+
+/// Parse a hex hash or public key argument
+fn parse<T: std::str::FromStr>(value: &str, what: &str) -> Result<T, MobileError>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse().map_err(|e| MobileError::InvalidInput { message: format!("invalid {}: {}", what, e) })
+}
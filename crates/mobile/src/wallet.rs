@@ -0,0 +1,83 @@
+use std::sync::Arc;
+use serde_json::Value;
+use tari_l2_client::signing;
+use tari_l2_common::crypto::KeyPair;
+use tari_l2_marketplace::Wallet;
+use tari_l2_state_channel::StateUpdate;
+use crate::{MobileError, from_json, to_json};
+
+/// Tari wallet held on the device. Its channel key signs marketplace actions.
+#[derive(uniffi::Object)]
+pub struct MobileWallet {
+    wallet: Wallet,
+    keypair: Arc<KeyPair>,
+}
+
+impl MobileWallet {
+    fn from_wallet(wallet: Wallet) -> Result<Arc<Self>, MobileError> {
+        let keypair = wallet.channel_keypair().map_err(|message| MobileError::Wallet { message })?;
+        Ok(Arc::new(Self { wallet, keypair: Arc::new(keypair) }))
+    }
+
+    pub(crate) fn keypair(&self) -> Arc<KeyPair> {
+        self.keypair.clone()
+    }
+}
+
+#[uniffi::export]
+impl MobileWallet {
+    /// Generate a new wallet with a 24-word seed phrase
+    #[uniffi::constructor]
+    pub fn generate() -> Result<Arc<Self>, MobileError> {
+        Self::from_wallet(Wallet::new())
+    }
+
+    #[uniffi::constructor]
+    pub fn from_seed_phrase(seed_phrase: String) -> Result<Arc<Self>, MobileError> {
+        Self::from_wallet(Wallet::from_seed_phrase(&seed_phrase).map_err(|message| MobileError::Wallet { message })?)
+    }
+
+    /// Restore from a hex-encoded spend key, e.g. one read back from the
+    /// platform keystore
+    #[uniffi::constructor]
+    pub fn from_private_key(private_key: String) -> Result<Arc<Self>, MobileError> {
+        Self::from_wallet(Wallet::from_private_key(&private_key).map_err(|message| MobileError::Wallet { message })?)
+    }
+
+    /// Tari address (emoji form)
+    pub fn address(&self) -> String {
+        self.wallet.address()
+    }
+
+    pub fn seed_phrase(&self) -> Option<String> {
+        self.wallet.seed_phrase().map(|phrase| phrase.to_string())
+    }
+
+    /// Hex spend key, for the platform keystore (Keychain / Android Keystore)
+    pub fn export_private_key(&self) -> String {
+        self.wallet.export_private_key().to_string()
+    }
+
+    /// Key the node sees as seller, buyer or channel participant
+    pub fn public_key(&self) -> String {
+        self.keypair.public_key().to_string()
+    }
+
+    /// Attach an `authorization` to JSON RPC params for a call to `method`,
+    /// such as `create_listing`, `create_order` or `transfer`
+    pub fn authorize(&self, method: String, params_json: String) -> Result<String, MobileError> {
+        let params: Value = from_json(&params_json)?;
+        to_json(&signing::authorize(&method, params, &self.keypair)?)
+    }
+
+    /// Sign a JSON channel update as state `nonce`, for `submit_state_update`
+    pub fn sign_update(&self, update_json: String, nonce: u64) -> Result<String, MobileError> {
+        let update: StateUpdate = from_json(&update_json)?;
+        to_json(&signing::sign_update(update, nonce, &self.keypair))
+    }
+
+    /// Hex-encoded signature over `message` with the channel key
+    pub fn sign_message(&self, message: Vec<u8>) -> String {
+        self.keypair.sign(&message).to_string()
+    }
+}
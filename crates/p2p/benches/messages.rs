@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use tari_l2_common::{Amount, Hash, crypto::KeyPair};
+use tari_l2_p2p::L2Message;
+use tari_l2_state_channel::{StateUpdate, state::Listing, update::SignedStateUpdate};
+
+fn listing(kp: &KeyPair, i: u64) -> Listing {
+    Listing {
+        id: Hash::random(),
+        seller: kp.public_key(),
+        title: format!("Listing {}", i),
+        description: "A reasonably sized description of the item for sale".to_string(),
+        price: Amount::new(1_000 + i),
+        ipfs_hash: "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG".to_string(),
+        active: true,
+        category: "electronics".to_string(),
+        fiat_reference: None,
+    }
+}
+
+fn messages() -> Vec<(&'static str, L2Message)> {
+    let kp = KeyPair::generate();
+    let signature = kp.sign(b"listing");
+
+    let mut update = SignedStateUpdate::new(StateUpdate::CreateListing { listing: listing(&kp, 0) }, 1);
+    update.add_signature(kp.public_key(), kp.sign(&update.signing_message()));
+
+    let mut initial_balances = HashMap::new();
+    initial_balances.insert(kp.public_key(), Amount::new(1_000));
+
+    vec![
+        ("listing_broadcast", L2Message::ListingBroadcast {
+            listing: listing(&kp, 0),
+            signature: signature.clone(),
+            timestamp: 1_700_000_000,
+        }),
+        ("state_update_proposal", L2Message::StateUpdateProposal {
+            channel_id: Hash::random(),
+            update,
+        }),
+        ("channel_open_request", L2Message::ChannelOpenRequest {
+            participants: vec![kp.public_key()],
+            initiator: kp.public_key(),
+            initial_balances,
+            challenge_period: 3600,
+            signature: signature.clone(),
+        }),
+        ("listings_response_100", L2Message::ListingsResponse {
+            listings: (0..100).map(|i| (listing(&kp, i), signature.clone())).collect(),
+        }),
+    ]
+}
+
+fn bench_messages(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gossip_message");
+
+    for (name, message) in messages() {
+        let bytes = bincode::serialize(&message).unwrap();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("serialize", name), &message, |b, message| {
+            b.iter(|| bincode::serialize(message).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("deserialize", name), &bytes, |b, bytes| {
+            b.iter(|| bincode::deserialize::<L2Message>(bytes).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_messages);
+criterion_main!(benches);
@@ -1,29 +1,44 @@
 use libp2p::{
     gossipsub::{self, IdentTopic, MessageId, ValidationMode},
-    identify,
-    swarm::NetworkBehaviour,
+    identify, mdns,
+    request_response::{self, ProtocolSupport},
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour},
     PeerId,
 };
 use std::time::Duration;
+use crate::capabilities::PeerAdvert;
+use crate::direct::{DirectCodec, DIRECT_PROTOCOL};
+use crate::wire::{ProtocolVersions, MAX_CLOCK_SKEW_SECS};
 
-/// Network behavior combining gossipsub and identify
+/// Network behavior combining gossipsub, identify, direct messaging and,
+/// when enabled, mDNS
 #[derive(NetworkBehaviour)]
 pub struct L2Behaviour {
     pub gossipsub: gossipsub::Behaviour,
     pub identify: identify::Behaviour,
+    /// Messages for one peer, such as channel traffic between counterparties
+    pub direct: request_response::Behaviour<DirectCodec>,
+    /// Finds peers on the local network; off unless configured
+    pub mdns: Toggle<mdns::tokio::Behaviour>,
 }
 
 impl L2Behaviour {
-    pub fn new(local_key: libp2p::identity::Keypair) -> Result<Self, Box<dyn std::error::Error>> {
-        let local_peer_id = PeerId::from(local_key.public());
-
+    pub fn new(
+        local_key: libp2p::identity::Keypair,
+        max_message_size: usize,
+        advert: &PeerAdvert,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         // Configure gossipsub
         let gossipsub_config = gossipsub::ConfigBuilder::default()
             .heartbeat_interval(Duration::from_secs(1))
             .validation_mode(ValidationMode::Strict)
+            .max_transmit_size(max_message_size)
             .message_id_fn(|message: &gossipsub::Message| {
                 MessageId::from(&blake3::hash(&message.data).as_bytes()[..])
             })
+            // Remember messages for as long as their timestamp is accepted,
+            // so a signed message replayed on its own topic is a duplicate
+            .duplicate_cache_time(Duration::from_secs(2 * MAX_CLOCK_SKEW_SECS))
             .build()
             .map_err(|e| format!("Failed to build gossipsub config: {}", e))?;
 
@@ -33,17 +48,35 @@ impl L2Behaviour {
         )
         .map_err(|e| format!("Failed to create gossipsub behaviour: {}", e))?;
 
-        // Configure identify
+        // Advertise the protocol versions this node speaks and what it offers
         let identify = identify::Behaviour::new(
-            identify::Config::new("/tari-l2/1.0.0".to_string(), local_key.public())
+            identify::Config::new(ProtocolVersions::LOCAL.identify_string(), local_key.public())
+                .with_agent_version(advert.agent_version())
+        );
+
+        let direct = request_response::Behaviour::with_codec(
+            DirectCodec { max_message_size },
+            [(DIRECT_PROTOCOL, ProtocolSupport::Full)],
+            request_response::Config::default(),
         );
 
         Ok(Self {
             gossipsub,
             identify,
+            direct,
+            mdns: Toggle::from(None),
         })
     }
 
+    /// Start discovering peers on the local network. Fails if the multicast
+    /// socket cannot be bound.
+    pub fn enable_mdns(&mut self, local_peer_id: PeerId) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mdns = mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id)
+            .map_err(|e| format!("Failed to create mDNS behaviour: {}", e))?;
+        self.mdns = Toggle::from(Some(mdns));
+        Ok(())
+    }
+
     pub fn subscribe(&mut self, topic: &str) -> Result<bool, Box<dyn std::error::Error>> {
         let topic = IdentTopic::new(topic);
         self.gossipsub.subscribe(&topic)
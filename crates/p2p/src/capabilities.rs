@@ -0,0 +1,202 @@
+//! Services a node offers to its peers.
+//!
+//! Each node puts its L2 public key and its capabilities in the agent
+//! version of its identify info, e.g.
+//! `tari-l2 key=<hex> caps=relay,watchtower`. Peers record what they receive
+//! in their peer table, so a node can look for a counterparty that accepts
+//! channel opens or an archive to download the catalog from. Capabilities
+//! are self-reported: they say what a peer offers. The key is not: it comes
+//! with `sig=<hex>`, the key's signature over the peer's libp2p peer ID,
+//! which the transport authenticates. A key without a valid signature is
+//! dropped, so no peer can pose as another node and be routed its traffic.
+//! Names this node does not know are skipped, so newer nodes can advertise
+//! new capabilities without breaking older ones.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use tari_l2_common::{PublicKey, Signature};
+use tari_l2_common::crypto::{self, KeyPair, SigningContext};
+
+const AGENT_PREFIX: &str = "tari-l2";
+
+/// A service a node can advertise
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// Evaluates incoming channel open requests
+    AcceptsChannelOpens,
+    /// Forwards gossip for peers that cannot reach each other
+    Relay,
+    /// Holds watch blobs and challenges stale closes for offline clients
+    Watchtower,
+    /// Serves its full catalog to bootstrapping nodes
+    ArchiveCatalog,
+    /// Arbitrates escalated refund disputes
+    Arbitrator,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 5] = [
+        Capability::AcceptsChannelOpens,
+        Capability::Relay,
+        Capability::Watchtower,
+        Capability::ArchiveCatalog,
+        Capability::Arbitrator,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capability::AcceptsChannelOpens => "accepts-channel-opens",
+            Capability::Relay => "relay",
+            Capability::Watchtower => "watchtower",
+            Capability::ArchiveCatalog => "archive-catalog",
+            Capability::Arbitrator => "arbitrator",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Capability {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Capability::ALL.into_iter()
+            .find(|capability| capability.as_str() == s)
+            .ok_or_else(|| format!("Unknown capability: {}", s))
+    }
+}
+
+/// What a node says about itself in its identify info
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeerAdvert {
+    pub public_key: Option<PublicKey>,
+    /// `public_key`'s signature over the peer ID the advert is sent under
+    pub binding: Option<Signature>,
+    pub capabilities: BTreeSet<Capability>,
+}
+
+impl PeerAdvert {
+    /// Advertise `keypair`'s key, signed over `peer_id`, the bytes of the
+    /// libp2p peer ID this node connects as
+    pub fn bind(&mut self, keypair: &KeyPair, peer_id: &[u8]) {
+        self.public_key = Some(keypair.public_key());
+        self.binding = Some(keypair.sign_with_context(SigningContext::PeerBinding, peer_id));
+    }
+
+    /// Keep the advertised key only if it signed `peer_id`, the peer ID the
+    /// transport authenticated the advert under
+    pub fn verified(mut self, peer_id: &[u8]) -> Self {
+        let bound = match (&self.public_key, &self.binding) {
+            (Some(key), Some(binding)) => crypto::verify_with_context(key, SigningContext::PeerBinding, peer_id, binding),
+            _ => false,
+        };
+        if !bound {
+            self.public_key = None;
+            self.binding = None;
+        }
+        self
+    }
+
+    /// Identify agent version carrying the advert
+    pub fn agent_version(&self) -> String {
+        let mut agent = AGENT_PREFIX.to_string();
+        if let Some(key) = &self.public_key {
+            agent.push_str(&format!(" key={}", key));
+        }
+        if let Some(binding) = &self.binding {
+            agent.push_str(&format!(" sig={}", binding));
+        }
+        if !self.capabilities.is_empty() {
+            let names: Vec<&str> = self.capabilities.iter().map(|c| c.as_str()).collect();
+            agent.push_str(&format!(" caps={}", names.join(",")));
+        }
+        agent
+    }
+
+    /// Parse a peer's identify agent version. Peers that advertise nothing,
+    /// including nodes from before adverts, get an empty advert.
+    pub fn from_agent_version(agent: &str) -> Self {
+        let mut advert = PeerAdvert::default();
+        let mut fields = agent.split_whitespace();
+        if fields.next() != Some(AGENT_PREFIX) {
+            return advert;
+        }
+        for field in fields {
+            match field.split_once('=') {
+                Some(("key", key)) => advert.public_key = key.parse().ok(),
+                Some(("sig", binding)) => advert.binding = binding.parse().ok(),
+                Some(("caps", caps)) => {
+                    advert.capabilities = caps.split(',').filter_map(|name| name.parse().ok()).collect();
+                }
+                _ => {}
+            }
+        }
+        advert
+    }
+}
+
+/// Entry in the peer table for an identified peer
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeerInfo {
+    /// Transport-level identifier (the libp2p peer ID on the real network)
+    pub peer_id: String,
+    /// L2 public key the peer advertised and proved it holds
+    pub public_key: Option<PublicKey>,
+    /// Wire protocol version negotiated with the peer
+    pub protocol_version: u32,
+    pub capabilities: BTreeSet<Capability>,
+}
+
+impl PeerInfo {
+    pub fn offers(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advert_round_trips_through_agent_version() {
+        let advert = PeerAdvert {
+            public_key: Some(PublicKey::new([7u8; 32])),
+            binding: None,
+            capabilities: [Capability::Watchtower, Capability::AcceptsChannelOpens].into_iter().collect(),
+        };
+        let agent = advert.agent_version();
+        assert!(agent.ends_with(" caps=accepts-channel-opens,watchtower"));
+        assert_eq!(PeerAdvert::from_agent_version(&agent), advert);
+
+        // Unknown names from newer nodes are skipped
+        let newer = PeerAdvert::from_agent_version("tari-l2 caps=relay,teleport extra=1");
+        assert_eq!(newer.capabilities, [Capability::Relay].into_iter().collect());
+
+        // Other agents and legacy nodes advertise nothing
+        assert_eq!(PeerAdvert::from_agent_version("rust-libp2p/0.53.0"), PeerAdvert::default());
+        assert_eq!("archive-catalog".parse(), Ok(Capability::ArchiveCatalog));
+        assert_eq!(serde_json::to_value(Capability::ArchiveCatalog).unwrap(), "archive-catalog");
+    }
+
+    #[test]
+    fn test_advertised_key_needs_a_binding_to_the_peer_id() {
+        let keypair = KeyPair::generate();
+        let mut advert = PeerAdvert::default();
+        advert.bind(&keypair, b"peer-a");
+        let received = PeerAdvert::from_agent_version(&advert.agent_version());
+        assert_eq!(received, advert);
+        assert_eq!(received.clone().verified(b"peer-a").public_key, Some(keypair.public_key()));
+
+        // Replayed by another peer, or claimed with no signature, the key is dropped
+        assert_eq!(received.verified(b"peer-b").public_key, None);
+        let claimed = PeerAdvert { public_key: Some(keypair.public_key()), ..PeerAdvert::default() };
+        assert_eq!(PeerAdvert::from_agent_version(&claimed.agent_version()).verified(b"peer-a").public_key, None);
+    }
+}
@@ -0,0 +1,113 @@
+//! Direct peer-to-peer messaging.
+//!
+//! Gossipsub reaches every node on a topic, which suits listings but not
+//! traffic between the two parties of a channel. Messages sent to one peer
+//! whose L2 key is bound to it by its identify advert therefore go over a
+//! request-response protocol instead: the request is the wire-encoded
+//! message and the response is the handler's reply, or empty when there is
+//! none. This is how `StateUpdateProposal`, `ChannelInfoRequest` and
+//! `ListingsRequest` reach counterparties, and the acks, channel info and
+//! listings they ask for come back on the same stream. A request that fails
+//! is sent again when the peer is next identified rather than gossiped.
+//! Messages to peers with no known route still go out over gossip.
+
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use libp2p::StreamProtocol;
+use std::io;
+
+/// Protocol name negotiated for direct messages
+pub const DIRECT_PROTOCOL: StreamProtocol = StreamProtocol::new("/tari-l2/direct/1");
+
+/// Frames each message as a big-endian u32 length and the wire bytes,
+/// refusing frames over `max_message_size`
+#[derive(Clone, Debug)]
+pub struct DirectCodec {
+    pub max_message_size: usize,
+}
+
+impl DirectCodec {
+    async fn read_frame<T: AsyncRead + Unpin + Send>(&self, io: &mut T) -> io::Result<Vec<u8>> {
+        let mut len = [0u8; 4];
+        io.read_exact(&mut len).await?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len > self.max_message_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Direct message too large: {} bytes (max {})", len, self.max_message_size),
+            ));
+        }
+        let mut data = vec![0u8; len];
+        io.read_exact(&mut data).await?;
+        Ok(data)
+    }
+
+    async fn write_frame<T: AsyncWrite + Unpin + Send>(&self, io: &mut T, data: Vec<u8>) -> io::Result<()> {
+        let len = u32::try_from(data.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Direct message too large"))?;
+        io.write_all(&len.to_be_bytes()).await?;
+        io.write_all(&data).await?;
+        io.close().await
+    }
+}
+
+#[async_trait]
+impl libp2p::request_response::Codec for DirectCodec {
+    type Protocol = StreamProtocol;
+    type Request = Vec<u8>;
+    /// Empty when the handler had nothing to reply
+    type Response = Vec<u8>;
+
+    async fn read_request<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<Vec<u8>>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        self.read_frame(io).await
+    }
+
+    async fn read_response<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<Vec<u8>>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        self.read_frame(io).await
+    }
+
+    async fn write_request<T>(&mut self, _: &StreamProtocol, io: &mut T, request: Vec<u8>) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        self.write_frame(io, request).await
+    }
+
+    async fn write_response<T>(&mut self, _: &StreamProtocol, io: &mut T, response: Vec<u8>) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        self.write_frame(io, response).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::io::Cursor;
+    use libp2p::request_response::Codec;
+
+    #[tokio::test]
+    async fn test_direct_frames_round_trip_within_the_size_limit() {
+        let mut codec = DirectCodec { max_message_size: 16 };
+
+        let mut io = Cursor::new(Vec::new());
+        codec.write_request(&DIRECT_PROTOCOL, &mut io, b"proposal".to_vec()).await.unwrap();
+        codec.write_response(&DIRECT_PROTOCOL, &mut io, Vec::new()).await.unwrap();
+        io.set_position(0);
+        assert_eq!(codec.read_request(&DIRECT_PROTOCOL, &mut io).await.unwrap(), b"proposal");
+        assert!(codec.read_response(&DIRECT_PROTOCOL, &mut io).await.unwrap().is_empty());
+
+        // A peer announcing an oversized frame is refused before it is read
+        let mut io = Cursor::new(Vec::new());
+        codec.write_request(&DIRECT_PROTOCOL, &mut io, vec![0u8; 17]).await.unwrap();
+        io.set_position(0);
+        let err = codec.read_request(&DIRECT_PROTOCOL, &mut io).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
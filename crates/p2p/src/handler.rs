@@ -5,6 +5,7 @@ use crate::messages::L2Message;
 /// Handler for incoming P2P messages
 #[async_trait]
 pub trait MessageHandler: Send + Sync {
-    /// Handle an incoming message from a peer
+    /// Handle an incoming message. `from` is the L2 key that signed it,
+    /// already verified, so handlers may authorize by it.
     async fn handle_message(&self, from: PublicKey, message: L2Message) -> Result<Option<L2Message>>;
 }
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use tari_l2_common::{PublicKey, error::Result};
+use crate::capabilities::PeerInfo;
+use crate::messages::L2Message;
+
+/// Operations the rest of the node needs from the P2P layer.
+/// Implemented by the libp2p [`crate::P2PNetwork`] and the in-memory [`crate::sim::SimNode`].
+#[async_trait]
+pub trait NetworkInterface: Send + Sync {
+    /// Broadcast a message to all connected peers
+    async fn broadcast_message(&self, message: L2Message) -> Result<()>;
+
+    /// Send a message to a specific peer
+    async fn send_message(&self, peer: PublicKey, message: L2Message) -> Result<()>;
+
+    /// Get list of connected peers
+    async fn connected_peers(&self) -> Vec<PublicKey>;
+
+    /// Identified peers with the capabilities they advertise
+    async fn peer_table(&self) -> Vec<PeerInfo>;
+
+    /// Transport-level identifier of this node (the libp2p peer ID on the real network)
+    fn local_id(&self) -> String;
+}
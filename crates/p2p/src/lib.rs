@@ -2,10 +2,24 @@ pub mod network;
 pub mod messages;
 pub mod handler;
 pub mod behaviour;
+pub mod direct;
 pub mod swarm_manager;
+pub mod interface;
+pub mod sim;
+pub mod topics;
+pub mod wire;
+pub mod capabilities;
+pub mod peerbook;
+pub mod limits;
 
-pub use network::{P2PNetwork, NetworkConfig};
-pub use messages::{L2Message, MessageType};
+pub use network::{P2PNetwork, NetworkConfig, validate_multiaddr};
+pub use messages::{CatalogPage, L2Message, MessageType};
 pub use handler::MessageHandler;
 pub use behaviour::L2Behaviour;
 pub use swarm_manager::SwarmManager;
+pub use interface::NetworkInterface;
+pub use topics::{Topic, Topics, validate_namespace};
+pub use wire::{ProtocolVersions, PROTOCOL_VERSION};
+pub use capabilities::{Capability, PeerAdvert, PeerInfo};
+pub use peerbook::{KnownPeer, PeerBook};
+pub use limits::{ConnectedPeer, ConnectionLimits};
@@ -0,0 +1,112 @@
+//! Which connections to keep when the node is over its peer limits.
+//!
+//! `max_peers` caps all connections. Of those, `outbound_peers` slots are
+//! kept for peers this node dials, so inbound peers alone can never fill
+//! the node and cut it off from the peers it chose. When a new connection
+//! takes the node over a limit, the lowest-value peer that is not protected
+//! is dropped, which may be the newcomer itself. A peer's value is its
+//! peer-book score, and of equal scores the longest-held connection wins.
+//! Channel counterparties are protected: they are never pruned, so updates
+//! keep flowing however busy the node is. A peer counts as a counterparty
+//! only once its identify advert has bound the counterparty's key to its
+//! peer ID, so claiming a key is not enough to stay connected.
+
+/// Connection caps for the swarm
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConnectionLimits {
+    /// Most connected peers in total
+    pub max_peers: usize,
+    /// Slots kept for peers this node dials
+    pub outbound_peers: usize,
+}
+
+impl ConnectionLimits {
+    /// Most peers that connected to this node
+    pub fn max_inbound(&self) -> usize {
+        self.max_peers.saturating_sub(self.outbound_peers.min(self.max_peers))
+    }
+
+    /// Outbound connections still wanted, given the connected peers
+    pub fn outbound_wanted(&self, peers: &[ConnectedPeer]) -> usize {
+        let outbound = peers.iter().filter(|peer| peer.outbound).count();
+        let free = self.max_peers.saturating_sub(peers.len());
+        self.outbound_peers.saturating_sub(outbound).min(free)
+    }
+
+    /// Peers to disconnect so the rest fit the limits
+    pub fn prune(&self, peers: &[ConnectedPeer]) -> Vec<String> {
+        // Lowest value first
+        let mut candidates: Vec<&ConnectedPeer> = peers.iter().filter(|peer| !peer.protected).collect();
+        candidates.sort_by(|a, b| {
+            a.score.total_cmp(&b.score)
+                .then_with(|| b.connected_at.cmp(&a.connected_at))
+                .then_with(|| a.peer_id.cmp(&b.peer_id))
+        });
+
+        let mut pruned = Vec::new();
+        let inbound = peers.iter().filter(|peer| !peer.outbound).count();
+        let excess_inbound = inbound.saturating_sub(self.max_inbound());
+        pruned.extend(candidates.iter().filter(|peer| !peer.outbound).take(excess_inbound).map(|peer| peer.peer_id.clone()));
+
+        let excess = (peers.len() - pruned.len()).saturating_sub(self.max_peers);
+        let remaining: Vec<String> = candidates.iter()
+            .filter(|peer| !pruned.contains(&peer.peer_id))
+            .take(excess)
+            .map(|peer| peer.peer_id.clone())
+            .collect();
+        pruned.extend(remaining);
+        pruned
+    }
+}
+
+/// A connected peer as seen by the limits
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectedPeer {
+    pub peer_id: String,
+    /// Whether this node dialed the peer
+    pub outbound: bool,
+    /// Channel counterparties are never pruned
+    pub protected: bool,
+    /// Peer-book score; higher is kept first
+    pub score: f64,
+    /// Unix time the connection was established
+    pub connected_at: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(peer_id: &str, outbound: bool, score: f64, connected_at: u64) -> ConnectedPeer {
+        ConnectedPeer { peer_id: peer_id.to_string(), outbound, protected: false, score, connected_at }
+    }
+
+    #[test]
+    fn test_prune_respects_split_and_protection() {
+        let limits = ConnectionLimits { max_peers: 4, outbound_peers: 2 };
+        assert_eq!(limits.max_inbound(), 2);
+
+        // A third inbound peer pushes out the weakest inbound one, never an outbound one
+        let mut peers = vec![
+            peer("out-weak", true, 0.1, 1),
+            peer("in-good", false, 0.9, 2),
+            peer("in-old", false, 0.5, 3),
+            peer("in-new", false, 0.5, 4),
+        ];
+        assert_eq!(limits.prune(&peers), vec!["in-new"]);
+        assert_eq!(limits.outbound_wanted(&peers), 0);
+
+        // A protected counterparty is kept and the next weakest goes instead
+        peers[3].protected = true;
+        assert_eq!(limits.prune(&peers), vec!["in-old"]);
+
+        // Over the total cap, the weakest peer of either direction goes
+        peers.push(peer("out-new", true, 0.3, 5));
+        peers.push(peer("out-mid", true, 0.7, 6));
+        assert_eq!(limits.prune(&peers), vec!["in-old", "out-weak"]);
+
+        let few = vec![peer("in", false, 0.5, 1)];
+        assert!(limits.prune(&few).is_empty());
+        assert_eq!(limits.outbound_wanted(&few), 2);
+    }
+}
@@ -1,24 +1,33 @@
 use serde::{Deserialize, Serialize};
-use tari_l2_common::{Hash, PublicKey, Signature};
+use std::collections::HashMap;
+use tari_l2_common::{Amount, Hash, L2Error, PublicKey, Signature};
 use tari_l2_state_channel::{
     update::SignedStateUpdate,
     channel::ChannelInfo,
-    state::Listing,
+    state::{Listing, Review, UserProfile},
 };
 
 /// L2 network message types
+///
+/// Bincode tags each variant by its position, so new variants go at the end.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum L2Message {
     /// Request to open a new channel
     ChannelOpenRequest {
         participants: Vec<PublicKey>,
         initiator: PublicKey,
+        initial_balances: HashMap<PublicKey, Amount>,
+        challenge_period: u64,
+        /// Initiator's signature over the genesis state
+        signature: Signature,
     },
 
     /// Response to channel open request
     ChannelOpenResponse {
         channel_id: Hash,
         accepted: bool,
+        /// Responder's co-signature over the genesis state, present when accepted
+        signature: Option<Signature>,
     },
 
     /// State update proposal
@@ -54,9 +63,9 @@ pub enum L2Message {
     /// Request all listings from a peer
     ListingsRequest,
 
-    /// Response with peer's listings
+    /// Response with peer's listings and their seller signatures
     ListingsResponse {
-        listings: Vec<Listing>,
+        listings: Vec<(Listing, Signature)>,
     },
 
     /// Ping message for keepalive
@@ -64,6 +73,85 @@ pub enum L2Message {
 
     /// Pong response
     Pong,
+
+    /// Failure handling a request, using the shared `L2Error` codes
+    Error {
+        code: i32,
+        category: String,
+        message: String,
+    },
+
+    /// An order was cancelled because it was not funded in time
+    OrderExpired {
+        channel_id: Hash,
+        order_id: Hash,
+    },
+
+    /// A seller changed one of their listings (e.g. its price)
+    ListingUpdated {
+        listing: Listing,
+        signature: Signature,
+        timestamp: u64,
+        /// Seller's signature over the listing and `timestamp`, so an older
+        /// update cannot be replayed over a newer one
+        update_signature: Signature,
+    },
+
+    /// Request one page of a peer's full catalog, used by a fresh node to
+    /// bootstrap from an archive peer
+    CatalogPageRequest {
+        offset: u32,
+        limit: u32,
+    },
+
+    /// One page of a peer's catalog
+    CatalogPageResponse {
+        page: CatalogPage,
+    },
+
+    /// A refund request went unanswered and the escrow is now disputed
+    DisputeEscalated {
+        escrow_id: Hash,
+        channel_id: Hash,
+        order_id: Hash,
+        /// The buyer's refund reason
+        reason: String,
+    },
+
+    /// Ask a counterparty for the committed updates in `from_nonce..=to_nonce`,
+    /// sent when a proposal arrives ahead of the local state
+    StateUpdateGapRequest {
+        channel_id: Hash,
+        from_nonce: u64,
+        to_nonce: u64,
+    },
+
+    /// Committed updates answering a `StateUpdateGapRequest`, in nonce order
+    StateUpdateGapFill {
+        channel_id: Hash,
+        updates: Vec<SignedStateUpdate>,
+    },
+
+    /// A seller's signed Merkle root over their active listings, gossiped
+    /// periodically so peers can check the catalog they synced
+    CatalogRoot {
+        seller: PublicKey,
+        root: Hash,
+        listings: u32,
+        timestamp: u64,
+        signature: Signature,
+    },
+}
+
+/// A slice of a peer's catalog: a range of sellers, ordered by public key,
+/// with their signed listings, profiles and reviews
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CatalogPage {
+    pub listings: Vec<(Listing, Signature)>,
+    pub profiles: Vec<UserProfile>,
+    pub reviews: Vec<Review>,
+    /// Offset of the next page, `None` on the last one
+    pub next_offset: Option<u32>,
 }
 
 impl L2Message {
@@ -80,6 +168,48 @@ impl L2Message {
             L2Message::ListingsResponse { .. } => MessageType::ListingsResponse,
             L2Message::Ping => MessageType::Ping,
             L2Message::Pong => MessageType::Pong,
+            L2Message::Error { .. } => MessageType::Error,
+            L2Message::OrderExpired { .. } => MessageType::OrderExpired,
+            L2Message::ListingUpdated { .. } => MessageType::ListingUpdated,
+            L2Message::CatalogPageRequest { .. } => MessageType::CatalogPageRequest,
+            L2Message::CatalogPageResponse { .. } => MessageType::CatalogPageResponse,
+            L2Message::DisputeEscalated { .. } => MessageType::DisputeEscalated,
+            L2Message::StateUpdateGapRequest { .. } => MessageType::StateUpdateGapRequest,
+            L2Message::StateUpdateGapFill { .. } => MessageType::StateUpdateGapFill,
+            L2Message::CatalogRoot { .. } => MessageType::CatalogRoot,
+        }
+    }
+
+    /// Channel the message is about, if any
+    pub fn channel_id(&self) -> Option<Hash> {
+        match self {
+            L2Message::ChannelOpenResponse { channel_id, .. }
+            | L2Message::StateUpdateProposal { channel_id, .. }
+            | L2Message::StateUpdateAck { channel_id, .. }
+            | L2Message::StateUpdateGapRequest { channel_id, .. }
+            | L2Message::StateUpdateGapFill { channel_id, .. }
+            | L2Message::ChannelInfoRequest { channel_id }
+            | L2Message::OrderExpired { channel_id, .. }
+            | L2Message::DisputeEscalated { channel_id, .. } => Some(*channel_id),
+            _ => None,
+        }
+    }
+
+    /// Order the message is about, if any
+    pub fn order_id(&self) -> Option<Hash> {
+        match self {
+            L2Message::StateUpdateProposal { update, .. } => update.update.order_id(),
+            L2Message::OrderExpired { order_id, .. } | L2Message::DisputeEscalated { order_id, .. } => Some(*order_id),
+            _ => None,
+        }
+    }
+
+    /// Build an error response from an `L2Error`
+    pub fn error(e: &L2Error) -> Self {
+        L2Message::Error {
+            code: e.code(),
+            category: e.category().as_str().to_string(),
+            message: e.to_string(),
         }
     }
 }
@@ -97,4 +227,13 @@ pub enum MessageType {
     ListingsResponse,
     Ping,
     Pong,
+    Error,
+    OrderExpired,
+    ListingUpdated,
+    CatalogPageRequest,
+    CatalogPageResponse,
+    DisputeEscalated,
+    StateUpdateGapRequest,
+    StateUpdateGapFill,
+    CatalogRoot,
 }
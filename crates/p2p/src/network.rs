@@ -1,13 +1,22 @@
-use libp2p::{Multiaddr, PeerId};
-use std::collections::HashMap;
+use libp2p::{request_response::InboundRequestId, Multiaddr, PeerId};
+use std::collections::BTreeSet;
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
 use tracing::{info, error, debug, warn};
-use tari_l2_common::{PublicKey, error::Result, error::L2Error};
+use tari_l2_common::{PublicKey, ResourceLimits, Timestamp, error::Result, error::L2Error};
+use tari_l2_common::crypto::KeyPair;
+use crate::capabilities::{Capability, PeerAdvert, PeerInfo};
 use crate::messages::L2Message;
 use crate::handler::MessageHandler;
-use crate::swarm_manager::SwarmManager;
+use crate::interface::NetworkInterface;
+use crate::peerbook::{KnownPeer, PeerBook};
+use crate::limits::ConnectionLimits;
+use crate::swarm_manager::{InboundMessage, SharedPeers, SwarmManager};
+use crate::topics::{Topic, Topics, DEFAULT_NAMESPACE};
+
+/// Key derivation context for the libp2p identity
+const LIBP2P_KEY_CONTEXT: &str = "tari-l2 p2p libp2p identity v1";
 
 /// P2P network configuration
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -18,8 +27,36 @@ pub struct NetworkConfig {
     /// Bootstrap peers (libp2p multiaddr format)
     pub bootstrap_peers: Vec<String>,
 
-    /// Maximum number of peers
+    /// Most peers to stay connected to. Known peers are dialed to top
+    /// connections up to it and the lowest-value peers are dropped beyond it.
     pub max_peers: usize,
+
+    /// Slots of `max_peers` kept for peers this node dials, so inbound
+    /// connections cannot crowd them out
+    #[serde(default = "default_outbound_peers")]
+    pub outbound_peers: usize,
+
+    /// Deployment namespace; nodes only gossip with nodes that share it
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+
+    /// Capabilities advertised to peers on top of those the node derives
+    /// from its own configuration
+    #[serde(default)]
+    pub capabilities: BTreeSet<Capability>,
+
+    /// Discover peers on the local network with mDNS and dial them, so
+    /// development nodes on one LAN connect without bootstrap peers
+    #[serde(default)]
+    pub mdns: bool,
+}
+
+fn default_outbound_peers() -> usize {
+    10
+}
+
+fn default_namespace() -> String {
+    DEFAULT_NAMESPACE.to_string()
 }
 
 impl Default for NetworkConfig {
@@ -28,22 +65,56 @@ impl Default for NetworkConfig {
             listen_addr: "/ip4/0.0.0.0/tcp/9000".to_string(),
             bootstrap_peers: Vec::new(),
             max_peers: 50,
+            outbound_peers: default_outbound_peers(),
+            namespace: default_namespace(),
+            capabilities: BTreeSet::new(),
+            mdns: false,
         }
     }
 }
 
+impl NetworkConfig {
+    /// TCP port of the listen address, if it has one
+    pub fn listen_port(&self) -> Option<u16> {
+        let addr = Multiaddr::from_str(&self.listen_addr).ok()?;
+        addr.iter().find_map(|protocol| match protocol {
+            libp2p::multiaddr::Protocol::Tcp(port) => Some(port),
+            _ => None,
+        })
+    }
+}
+
+/// Check that a string is a valid libp2p multiaddr
+pub fn validate_multiaddr(addr: &str) -> std::result::Result<(), String> {
+    Multiaddr::from_str(addr)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
 /// P2P network for L2 nodes
 pub struct P2PNetwork {
     config: NetworkConfig,
-    peers: Arc<RwLock<HashMap<PeerId, PublicKey>>>,
-    message_tx: mpsc::UnboundedSender<L2Message>,
-    message_rx: Arc<RwLock<mpsc::UnboundedReceiver<L2Message>>>,
+    limits: ResourceLimits,
+    topics: Topics,
+    local_key: libp2p::identity::Keypair,
+    /// L2 key outgoing messages are signed with
+    signer: Arc<KeyPair>,
+    advert: PeerAdvert,
+    peers: SharedPeers,
+    message_tx: mpsc::UnboundedSender<InboundMessage>,
+    message_rx: Arc<RwLock<mpsc::UnboundedReceiver<InboundMessage>>>,
     swarm_tx: Arc<RwLock<Option<mpsc::UnboundedSender<SwarmCommand>>>>,
 }
 
 enum SwarmCommand {
     Publish { topic: String, message: L2Message },
+    /// Send to one peer, queueing the message for its next connection if
+    /// that fails
+    SendDirect { peer_id: PeerId, message: L2Message },
+    /// The handler's reply to a direct request
+    Respond { request_id: InboundRequestId, reply: Option<L2Message> },
     Dial { addr: Multiaddr },
+    MaintainPeers,
 }
 
 impl P2PNetwork {
@@ -52,14 +123,72 @@ impl P2PNetwork {
         let (message_tx, message_rx) = mpsc::unbounded_channel();
 
         Self {
+            topics: Topics::new(&config.namespace, crate::topics::DEFAULT_TARI_NETWORK),
+            advert: PeerAdvert {
+                capabilities: config.capabilities.clone(),
+                ..PeerAdvert::default()
+            },
             config,
-            peers: Arc::new(RwLock::new(HashMap::new())),
+            limits: ResourceLimits::default(),
+            local_key: libp2p::identity::Keypair::generate_ed25519(),
+            signer: Arc::new(KeyPair::generate()),
+            peers: SharedPeers::default(),
             message_tx,
             message_rx: Arc::new(RwLock::new(message_rx)),
             swarm_tx: Arc::new(RwLock::new(None)),
         }
     }
 
+    /// Apply resource limits to incoming and outgoing messages
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Scope topics to the Tari network the node settles on, so testnet and
+    /// mainnet deployments sharing a namespace stay apart
+    pub fn with_tari_network(mut self, tari_network: &str) -> Self {
+        self.topics = Topics::new(&self.config.namespace, tari_network);
+        self
+    }
+
+    /// Sign messages with the node's L2 key and advertise it to peers with
+    /// `capabilities`, in addition to those set in the network config.
+    /// The libp2p key is derived from it, so the peer ID peers remember
+    /// survives restarts, and the advert carries the L2 key's signature
+    /// over that peer ID. Without it messages are signed with a throwaway
+    /// key and the peer ID changes on every start.
+    pub fn with_advert(mut self, keypair: Arc<KeyPair>, capabilities: impl IntoIterator<Item = Capability>) -> Self {
+        self.local_key = libp2p_key(&keypair);
+        self.advert.bind(&keypair, &PeerId::from(self.local_key.public()).to_bytes());
+        self.advert.capabilities.extend(capabilities);
+        self.signer = keypair;
+        self
+    }
+
+    /// Start from the peers remembered by an earlier run
+    pub fn with_known_peers(self, peers: Vec<KnownPeer>) -> Self {
+        *self.peers.book.write().unwrap() = PeerBook::new(peers);
+        self
+    }
+
+    /// Set the L2 keys of channel counterparties. Connections to peers whose
+    /// advert binds one of these keys are never pruned, and they are
+    /// redialed ahead of other known peers.
+    pub fn set_protected_peers(&self, keys: impl IntoIterator<Item = PublicKey>) {
+        *self.peers.protected.write().unwrap() = keys.into_iter().collect();
+    }
+
+    /// Every peer seen, best-scored first, for persisting across restarts
+    pub fn known_peers(&self) -> Vec<KnownPeer> {
+        self.peers.book.read().unwrap().peers(Timestamp::now())
+    }
+
+    /// Get the libp2p peer ID of this node
+    pub fn local_peer_id(&self) -> PeerId {
+        PeerId::from(self.local_key.public())
+    }
+
     /// Start the P2P network
     pub async fn start(&self) -> Result<()> {
         info!("Starting P2P network on {}", self.config.listen_addr);
@@ -76,23 +205,33 @@ impl P2PNetwork {
         // Create and start the swarm manager
         let message_tx = self.message_tx.clone();
         let bootstrap_peers = self.config.bootstrap_peers.clone();
+        let limits = self.limits.clone();
+        let local_key = self.local_key.clone();
+        let signer = self.signer.clone();
+        let topics = self.topics.all();
+        let advert = self.advert.clone();
+        let peers = self.peers.clone();
+        let connection_limits = ConnectionLimits {
+            max_peers: self.config.max_peers,
+            outbound_peers: self.config.outbound_peers,
+        };
+        let mdns = self.config.mdns;
 
         tokio::spawn(async move {
-            match SwarmManager::new(listen_addr.clone(), message_tx) {
-                Ok(mut swarm_manager) => {
+            let swarm_manager = SwarmManager::new(listen_addr.clone(), local_key, message_tx, limits, advert, peers, connection_limits)
+                .and_then(|swarm_manager| if mdns { swarm_manager.with_mdns() } else { Ok(swarm_manager) });
+            match swarm_manager {
+                Ok(swarm_manager) => {
+                    let mut swarm_manager = swarm_manager.with_signer(signer);
+
                     // Start listening
-                    if let Err(e) = swarm_manager.start(listen_addr).await {
+                    if let Err(e) = swarm_manager.start(listen_addr, &topics).await {
                         error!("Failed to start swarm: {}", e);
                         return;
                     }
 
-                    // Subscribe to marketplace topics
-                    if let Err(e) = swarm_manager.swarm.behaviour_mut().subscribe("tari-l2-marketplace") {
-                        error!("Failed to subscribe to marketplace topic: {}", e);
-                    }
-
                     // Connect to bootstrap peers
-                    for peer_addr in bootstrap_peers {
+                    for peer_addr in &bootstrap_peers {
                         match Multiaddr::from_str(&peer_addr) {
                             Ok(addr) => {
                                 info!("Connecting to bootstrap peer: {}", addr);
@@ -106,6 +245,12 @@ impl P2PNetwork {
                         }
                     }
 
+                    // Then the best peers remembered from earlier runs
+                    let dialed = swarm_manager.top_up();
+                    if dialed > 0 {
+                        info!("Dialing {} known peers", dialed);
+                    }
+
                     // Run swarm event loop and handle commands
                     loop {
                         tokio::select! {
@@ -119,11 +264,34 @@ impl P2PNetwork {
                                             error!("Failed to publish message: {}", e);
                                         }
                                     }
+                                    SwarmCommand::SendDirect { peer_id, message } => {
+                                        if let Err(e) = swarm_manager.send_direct(peer_id, message) {
+                                            error!("Failed to send direct message: {}", e);
+                                        }
+                                    }
+                                    SwarmCommand::Respond { request_id, reply } => {
+                                        if let Err(e) = swarm_manager.respond(request_id, reply) {
+                                            debug!("Could not reply to direct request: {}", e);
+                                        }
+                                    }
                                     SwarmCommand::Dial { addr } => {
                                         if let Err(e) = swarm_manager.dial(addr) {
                                             error!("Failed to dial peer: {}", e);
                                         }
                                     }
+                                    SwarmCommand::MaintainPeers => {
+                                        swarm_manager.top_up();
+                                        if swarm_manager.swarm.connected_peers().next().is_none() {
+                                            for peer_addr in &bootstrap_peers {
+                                                if let Ok(addr) = Multiaddr::from_str(peer_addr) {
+                                                    debug!("Redialing bootstrap peer: {}", addr);
+                                                    if let Err(e) = swarm_manager.dial(addr) {
+                                                        warn!("Failed to redial bootstrap peer: {}", e);
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -144,35 +312,59 @@ impl P2PNetwork {
         let swarm_tx = self.swarm_tx.read().await;
 
         if let Some(tx) = swarm_tx.as_ref() {
-            let topic = match &message {
-                L2Message::ListingBroadcast { .. } => "tari-l2-marketplace",
-                L2Message::StateUpdateProposal { .. } => "tari-l2-state-updates",
-                L2Message::ChannelOpenRequest { .. } => "tari-l2-channel-announcements",
-                _ => "tari-l2-general",
-            };
+            let topic = self.topics.name(Topic::for_message(&message));
 
             tx.send(SwarmCommand::Publish {
-                topic: topic.to_string(),
+                topic: topic.clone(),
                 message,
             }).map_err(|e| L2Error::Unknown(format!("Failed to send publish command: {}", e)))?;
 
             debug!("Broadcast message to topic: {}", topic);
         } else {
-            warn!("Swarm not initialized, cannot broadcast message");
+            // Surface the failure so callers with an outbox can retry
+            return Err(L2Error::NetworkError("Swarm not initialized, cannot broadcast message".to_string()));
         }
 
         Ok(())
     }
 
-    /// Send a message to a specific peer (via gossipsub for now)
-    pub async fn send_message(&self, _peer: PublicKey, message: L2Message) -> Result<()> {
-        self.broadcast_message(message).await
+    /// Send a message to a specific peer: directly when a connected peer
+    /// proved it holds that key, over gossip otherwise
+    pub async fn send_message(&self, peer: PublicKey, message: L2Message) -> Result<()> {
+        let Some(peer_id) = self.route(&peer) else {
+            debug!("No connected peer is bound to {:?}, gossiping {:?}", peer, message.message_type());
+            return self.broadcast_message(message).await;
+        };
+
+        let swarm_tx = self.swarm_tx.read().await;
+        let Some(tx) = swarm_tx.as_ref() else {
+            return Err(L2Error::NetworkError("Swarm not initialized, cannot send message".to_string()));
+        };
+        tx.send(SwarmCommand::SendDirect { peer_id, message })
+            .map_err(|e| L2Error::Unknown(format!("Failed to send direct command: {}", e)))?;
+        Ok(())
+    }
+
+    /// Connected peer whose advert binds the L2 key `peer` to its peer ID.
+    /// The table only holds keys whose binding checked out.
+    fn route(&self, peer: &PublicKey) -> Option<PeerId> {
+        let table = self.peers.table.read().unwrap();
+        table.iter()
+            .find(|(_, info)| info.public_key.as_ref() == Some(peer))
+            .map(|(peer_id, _)| *peer_id)
     }
 
-    /// Get list of connected peers
+    /// L2 keys bound to identified peers
     pub async fn connected_peers(&self) -> Vec<PublicKey> {
-        let peers = self.peers.read().await;
-        peers.values().copied().collect()
+        let peers = self.peers.table.read().unwrap();
+        peers.values().filter_map(|peer| peer.public_key).collect()
+    }
+
+    /// Identified peers with the capabilities they advertise
+    pub async fn peer_table(&self) -> Vec<PeerInfo> {
+        let mut peers: Vec<PeerInfo> = self.peers.table.read().unwrap().values().cloned().collect();
+        peers.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+        peers
     }
 
     /// Connect to a peer
@@ -192,30 +384,53 @@ impl P2PNetwork {
         Ok(())
     }
 
+    /// Dial channel counterparties and the best known peers until the
+    /// outbound slots are full, and redial
+    /// bootstrap peers if the node has no connections
+    pub async fn maintain_peers(&self) -> Result<()> {
+        let swarm_tx = self.swarm_tx.read().await;
+
+        if let Some(tx) = swarm_tx.as_ref() {
+            tx.send(SwarmCommand::MaintainPeers)
+                .map_err(|e| L2Error::Unknown(format!("Failed to send maintenance command: {}", e)))?;
+        }
+
+        Ok(())
+    }
+
     /// Process messages with a handler
     pub async fn process_messages<H: MessageHandler + Send + Sync + 'static>(&self, handler: Arc<H>) -> Result<()> {
         let mut rx = self.message_rx.write().await;
 
         info!("Starting message processing loop");
 
-        while let Some(message) = rx.recv().await {
+        while let Some(InboundMessage { from, message, reply }) = rx.recv().await {
             let handler = handler.clone();
+            let swarm_tx = self.swarm_tx.read().await.clone();
 
             tokio::spawn(async move {
                 debug!("Processing message: {:?}", message.message_type());
 
-                let dummy_sender = PublicKey::new([0u8; 32]);
-
-                match handler.handle_message(dummy_sender, message).await {
+                // The sender's signature was checked when the message was decoded
+                let response = match handler.handle_message(from, message).await {
                     Ok(Some(response)) => {
                         debug!("Message handled, response: {:?}", response.message_type());
+                        Some(response)
                     }
                     Ok(None) => {
                         debug!("Message handled successfully");
+                        None
                     }
                     Err(e) => {
-                        error!("Error handling message: {}", e);
+                        let response = L2Message::error(&e);
+                        error!("Error handling message: {:?}", response);
+                        Some(response)
                     }
+                };
+
+                // Direct requests get the reply back on their stream
+                if let (Some(request_id), Some(tx)) = (reply, swarm_tx) {
+                    let _ = tx.send(SwarmCommand::Respond { request_id, reply: response });
                 }
             });
         }
@@ -223,3 +438,93 @@ impl P2PNetwork {
         Ok(())
     }
 }
+
+/// Ed25519 libp2p identity derived from the node's L2 key
+fn libp2p_key(keypair: &KeyPair) -> libp2p::identity::Keypair {
+    let mut seed = blake3::derive_key(LIBP2P_KEY_CONTEXT, keypair.to_bytes().as_slice());
+    libp2p::identity::Keypair::ed25519_from_bytes(&mut seed)
+        .expect("any 32 bytes are an Ed25519 secret key")
+}
+
+#[async_trait::async_trait]
+impl NetworkInterface for P2PNetwork {
+    async fn broadcast_message(&self, message: L2Message) -> Result<()> {
+        P2PNetwork::broadcast_message(self, message).await
+    }
+
+    async fn send_message(&self, peer: PublicKey, message: L2Message) -> Result<()> {
+        P2PNetwork::send_message(self, peer, message).await
+    }
+
+    async fn connected_peers(&self) -> Vec<PublicKey> {
+        P2PNetwork::connected_peers(self).await
+    }
+
+    async fn peer_table(&self) -> Vec<PeerInfo> {
+        P2PNetwork::peer_table(self).await
+    }
+
+    fn local_id(&self) -> String {
+        self.local_peer_id().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wire::{self, Recipient, PROTOCOL_VERSION};
+
+    #[test]
+    fn test_peer_id_follows_the_l2_key() {
+        let keypair = Arc::new(KeyPair::generate());
+        let network = |keypair: Arc<KeyPair>| P2PNetwork::new(NetworkConfig::default()).with_advert(keypair, []);
+
+        let peer_id = network(keypair.clone()).local_peer_id();
+        assert_eq!(network(keypair).local_peer_id(), peer_id);
+        assert_ne!(network(Arc::new(KeyPair::generate())).local_peer_id(), peer_id);
+    }
+
+    /// Handler that reports who each message came from
+    struct SenderRecorder(mpsc::UnboundedSender<PublicKey>);
+
+    #[async_trait::async_trait]
+    impl MessageHandler for SenderRecorder {
+        async fn handle_message(&self, from: PublicKey, _message: L2Message) -> Result<Option<L2Message>> {
+            let _ = self.0.send(from);
+            Ok(None)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handler_receives_the_verified_sender() {
+        let network = Arc::new(P2PNetwork::new(NetworkConfig::default()));
+        let mut swarm_manager = SwarmManager::new(
+            "/ip4/127.0.0.1/tcp/0".parse().unwrap(),
+            network.local_key.clone(),
+            network.message_tx.clone(),
+            ResourceLimits::default(),
+            PeerAdvert::default(),
+            network.peers.clone(),
+            ConnectionLimits { max_peers: 8, outbound_peers: 4 },
+        )
+        .unwrap();
+        let (from_tx, mut from_rx) = mpsc::unbounded_channel();
+        let handler = Arc::new(SenderRecorder(from_tx));
+        tokio::spawn({
+            let network = network.clone();
+            async move { network.process_messages(handler).await }
+        });
+
+        let sender = KeyPair::generate();
+        let topic = network.topics.name(Topic::General);
+        let recipient = Recipient::Topic(topic.clone());
+        let data = wire::encode(&L2Message::Ping, PROTOCOL_VERSION, &recipient, Timestamp::now().as_secs(), &sender).unwrap();
+
+        // Replayed onto another topic, it never reaches the handler
+        swarm_manager.receive_gossip(PeerId::random(), &network.topics.name(Topic::StateUpdates), &data);
+        swarm_manager.receive_gossip(PeerId::random(), &topic, &data);
+        assert_eq!(from_rx.recv().await, Some(sender.public_key()));
+        let more = tokio::time::timeout(std::time::Duration::from_millis(100), from_rx.recv()).await;
+        assert!(more.is_err());
+    }
+}
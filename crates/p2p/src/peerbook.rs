@@ -0,0 +1,233 @@
+//! Peers this node has seen, kept across restarts.
+//!
+//! Every identified peer is recorded with the addresses it listens on, the
+//! capabilities it advertises, when it was last seen and how often dialing
+//! it has worked. On startup, and whenever the node has fewer than
+//! `max_peers` connections, it dials the best-scored peers it is not
+//! connected to. A peer whose dials keep failing is retried less and less
+//! often, and peers not seen for a month are forgotten.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use tari_l2_common::{PublicKey, Timestamp};
+use crate::capabilities::{Capability, PeerAdvert};
+
+/// Most peers kept in the book; the lowest-scored are dropped beyond it
+pub const MAX_KNOWN_PEERS: usize = 1_000;
+
+/// Most addresses kept per peer
+const MAX_ADDRESSES: usize = 8;
+
+/// Delay before redialing a peer after a failed dial; it doubles with each
+/// further failure
+const BASE_REDIAL_SECS: u64 = 60;
+
+/// Longest delay between dials of a failing peer
+const MAX_REDIAL_SECS: u64 = 24 * 3600;
+
+/// Peers not seen for this long are forgotten
+const FORGET_AFTER_SECS: u64 = 30 * 24 * 3600;
+
+/// A peer in the book
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KnownPeer {
+    /// libp2p peer ID
+    pub peer_id: String,
+    /// Multiaddrs the peer listens on, as it last reported them
+    pub addresses: Vec<String>,
+    pub public_key: Option<PublicKey>,
+    pub capabilities: BTreeSet<Capability>,
+    pub last_seen: Timestamp,
+    /// Dials that connected
+    pub successes: u32,
+    /// Dials that failed
+    pub failures: u32,
+    /// Failed dials since the last one that connected
+    pub consecutive_failures: u32,
+    pub last_attempt: Option<Timestamp>,
+}
+
+impl KnownPeer {
+    fn new(peer_id: &str, now: Timestamp) -> Self {
+        Self {
+            peer_id: peer_id.to_string(),
+            addresses: Vec::new(),
+            public_key: None,
+            capabilities: BTreeSet::new(),
+            last_seen: now,
+            successes: 0,
+            failures: 0,
+            consecutive_failures: 0,
+            last_attempt: None,
+        }
+    }
+
+    /// Share of dials that connected, starting from an even prior so a new
+    /// peer scores 0.5
+    pub fn success_rate(&self) -> f64 {
+        (self.successes as f64 + 1.0) / (self.successes as f64 + self.failures as f64 + 2.0)
+    }
+
+    /// Dial priority: the success rate, discounted by days since the peer
+    /// was last seen
+    pub fn score(&self, now: Timestamp) -> f64 {
+        let days = now.as_secs().saturating_sub(self.last_seen.as_secs()) as f64 / 86_400.0;
+        self.success_rate() / (1.0 + days)
+    }
+
+    /// Whether the peer has an address and is not backing off at `now`
+    pub fn can_dial(&self, now: Timestamp) -> bool {
+        if self.addresses.is_empty() {
+            return false;
+        }
+        match self.last_attempt {
+            Some(last) if self.consecutive_failures > 0 => {
+                let delay = BASE_REDIAL_SECS
+                    .saturating_mul(1u64 << (self.consecutive_failures - 1).min(16))
+                    .min(MAX_REDIAL_SECS);
+                now.as_secs() >= last.as_secs().saturating_add(delay)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Every peer this node knows of, keyed by peer ID
+#[derive(Debug, Default)]
+pub struct PeerBook {
+    peers: HashMap<String, KnownPeer>,
+}
+
+impl PeerBook {
+    /// A book holding `peers`, typically loaded from storage
+    pub fn new(peers: Vec<KnownPeer>) -> Self {
+        Self {
+            peers: peers.into_iter().map(|peer| (peer.peer_id.clone(), peer)).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    pub fn get(&self, peer_id: &str) -> Option<&KnownPeer> {
+        self.peers.get(peer_id)
+    }
+
+    /// Every known peer, best-scored first
+    pub fn peers(&self, now: Timestamp) -> Vec<KnownPeer> {
+        let mut peers: Vec<KnownPeer> = self.peers.values().cloned().collect();
+        peers.sort_by(|a, b| b.score(now).total_cmp(&a.score(now)).then_with(|| a.peer_id.cmp(&b.peer_id)));
+        peers
+    }
+
+    fn entry(&mut self, peer_id: &str, now: Timestamp) -> &mut KnownPeer {
+        self.peers.entry(peer_id.to_string()).or_insert_with(|| KnownPeer::new(peer_id, now))
+    }
+
+    /// Record a peer's identify info: its listen addresses and advert
+    pub fn record_identified(&mut self, peer_id: &str, addresses: Vec<String>, advert: PeerAdvert, now: Timestamp) {
+        let peer = self.entry(peer_id, now);
+        if !addresses.is_empty() {
+            let mut unique = BTreeSet::new();
+            peer.addresses = addresses.into_iter()
+                .filter(|address| unique.insert(address.clone()))
+                .take(MAX_ADDRESSES)
+                .collect();
+        }
+        peer.public_key = advert.public_key;
+        peer.capabilities = advert.capabilities;
+        peer.last_seen = now;
+        self.prune(now);
+    }
+
+    /// Record a connection to a peer. Only connections this node dialed
+    /// count towards its success rate.
+    pub fn record_connected(&mut self, peer_id: &str, dialed: bool, now: Timestamp) {
+        let peer = self.entry(peer_id, now);
+        peer.last_seen = now;
+        if dialed {
+            peer.successes = peer.successes.saturating_add(1);
+            peer.consecutive_failures = 0;
+        }
+    }
+
+    /// Record that a dial to a known peer was started
+    pub fn record_dial_attempt(&mut self, peer_id: &str, now: Timestamp) {
+        if let Some(peer) = self.peers.get_mut(peer_id) {
+            peer.last_attempt = Some(now);
+        }
+    }
+
+    /// Record a failed dial to a known peer
+    pub fn record_dial_failure(&mut self, peer_id: &str, now: Timestamp) {
+        if let Some(peer) = self.peers.get_mut(peer_id) {
+            peer.failures = peer.failures.saturating_add(1);
+            peer.consecutive_failures = peer.consecutive_failures.saturating_add(1);
+            peer.last_attempt = Some(now);
+        }
+    }
+
+    /// Up to `count` of the best-scored dialable peers, leaving out
+    /// `connected`
+    pub fn dial_candidates(&self, connected: &HashSet<String>, count: usize, now: Timestamp) -> Vec<KnownPeer> {
+        self.peers(now).into_iter()
+            .filter(|peer| !connected.contains(&peer.peer_id) && peer.can_dial(now))
+            .take(count)
+            .collect()
+    }
+
+    /// Forget peers not seen for a month, then the lowest-scored beyond
+    /// `MAX_KNOWN_PEERS`
+    fn prune(&mut self, now: Timestamp) {
+        self.peers.retain(|_, peer| now.as_secs().saturating_sub(peer.last_seen.as_secs()) < FORGET_AFTER_SECS);
+        if self.peers.len() > MAX_KNOWN_PEERS {
+            for peer in self.peers(now).split_off(MAX_KNOWN_PEERS) {
+                self.peers.remove(&peer.peer_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identify(book: &mut PeerBook, peer_id: &str, now: Timestamp) {
+        let advert = PeerAdvert { capabilities: [Capability::Relay].into_iter().collect(), ..PeerAdvert::default() };
+        book.record_identified(peer_id, vec![format!("/ip4/10.0.0.1/tcp/9000/{}", peer_id)], advert, now);
+    }
+
+    #[test]
+    fn test_candidates_ranked_by_score_with_backoff() {
+        let now = Timestamp::from_secs(1_000_000);
+        let mut book = PeerBook::default();
+        for peer_id in ["reliable", "flaky", "connected"] {
+            identify(&mut book, peer_id, now);
+        }
+        book.record_connected("reliable", true, now);
+        book.record_dial_failure("flaky", now);
+        assert_eq!(book.get("flaky").unwrap().capabilities.len(), 1);
+
+        // Connected peers are skipped and failing ones back off
+        let connected: HashSet<String> = ["connected".to_string()].into_iter().collect();
+        let ids = |peers: Vec<KnownPeer>| peers.into_iter().map(|p| p.peer_id).collect::<Vec<_>>();
+        assert_eq!(ids(book.dial_candidates(&connected, 5, now)), vec!["reliable"]);
+        assert_eq!(ids(book.dial_candidates(&connected, 5, Timestamp::from_secs(1_000_060))), vec!["reliable", "flaky"]);
+
+        // A second failure doubles the wait
+        book.record_dial_failure("flaky", now);
+        assert!(!book.get("flaky").unwrap().can_dial(Timestamp::from_secs(1_000_119)));
+        assert!(book.get("flaky").unwrap().can_dial(Timestamp::from_secs(1_000_120)));
+
+        // Peers without an address cannot be dialed, and stale ones are forgotten
+        book.record_connected("inbound", false, now);
+        assert!(!book.get("inbound").unwrap().can_dial(now));
+        identify(&mut book, "late", Timestamp::from_secs(1_000_000 + FORGET_AFTER_SECS));
+        assert_eq!(ids(book.peers(now)), vec!["late"]);
+    }
+}
@@ -0,0 +1,486 @@
+//! Deterministic in-memory network for tests.
+//!
+//! [`SimNetwork`] routes messages between [`SimNode`]s on a virtual clock
+//! instead of sockets. Latency, jitter (which reorders messages), random drops
+//! and partitions are all driven by a seeded RNG, so a test run with the same
+//! seed always delivers the same messages in the same order. Nothing moves
+//! until the test calls [`SimNetwork::step`] or [`SimNetwork::run_until_idle`].
+
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+use tari_l2_common::{PublicKey, error::Result};
+use crate::capabilities::{Capability, PeerInfo};
+use crate::handler::MessageHandler;
+use crate::interface::NetworkInterface;
+use crate::messages::L2Message;
+use crate::wire::PROTOCOL_VERSION;
+
+/// Safety valve for `run_until_idle` when handlers keep producing messages
+const MAX_STEPS: usize = 100_000;
+
+/// Link behaviour of the simulated network
+#[derive(Clone, Debug)]
+pub struct SimConfig {
+    /// Seed for latency, reordering and drop decisions
+    pub seed: u64,
+
+    /// Base delivery latency (virtual milliseconds)
+    pub latency_ms: u64,
+
+    /// Extra random delay up to this amount; non-zero values reorder messages
+    pub jitter_ms: u64,
+
+    /// Probability in `0.0..=1.0` that a message is lost
+    pub drop_rate: f64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            latency_ms: 10,
+            jitter_ms: 0,
+            drop_rate: 0.0,
+        }
+    }
+}
+
+/// Delivery counters
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SimStats {
+    pub sent: u64,
+    pub delivered: u64,
+    pub dropped: u64,
+    pub handler_errors: u64,
+}
+
+struct Envelope {
+    deliver_at: u64,
+    seq: u64,
+    from: PublicKey,
+    to: PublicKey,
+    message: L2Message,
+}
+
+impl PartialEq for Envelope {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Envelope {}
+
+impl PartialOrd for Envelope {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Envelope {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.deliver_at, self.seq).cmp(&(other.deliver_at, other.seq))
+    }
+}
+
+struct SimState {
+    config: SimConfig,
+    rng: StdRng,
+    now_ms: u64,
+    seq: u64,
+    queue: BinaryHeap<Reverse<Envelope>>,
+    nodes: Vec<PublicKey>,
+    handlers: HashMap<PublicKey, Arc<dyn MessageHandler>>,
+    capabilities: HashMap<PublicKey, BTreeSet<Capability>>,
+    blocked: HashSet<(PublicKey, PublicKey)>,
+    stats: SimStats,
+}
+
+impl SimState {
+    fn linked(&self, from: &PublicKey, to: &PublicKey) -> bool {
+        !self.blocked.contains(&(*from, *to))
+    }
+
+    fn enqueue(&mut self, from: PublicKey, to: PublicKey, message: L2Message) {
+        self.stats.sent += 1;
+
+        if !self.linked(&from, &to) || self.rng.gen_bool(self.config.drop_rate) {
+            self.stats.dropped += 1;
+            debug!("sim: dropped {:?} from {:?} to {:?}", message.message_type(), from, to);
+            return;
+        }
+
+        let jitter = if self.config.jitter_ms > 0 {
+            self.rng.gen_range(0..=self.config.jitter_ms)
+        } else {
+            0
+        };
+        self.seq += 1;
+        self.queue.push(Reverse(Envelope {
+            deliver_at: self.now_ms + self.config.latency_ms + jitter,
+            seq: self.seq,
+            from,
+            to,
+            message,
+        }));
+    }
+}
+
+/// In-memory network connecting simulated nodes
+#[derive(Clone)]
+pub struct SimNetwork {
+    state: Arc<Mutex<SimState>>,
+}
+
+impl SimNetwork {
+    pub fn new(config: SimConfig) -> Self {
+        assert!((0.0..=1.0).contains(&config.drop_rate), "drop_rate must be in 0.0..=1.0");
+        let rng = StdRng::seed_from_u64(config.seed);
+        Self {
+            state: Arc::new(Mutex::new(SimState {
+                config,
+                rng,
+                now_ms: 0,
+                seq: 0,
+                queue: BinaryHeap::new(),
+                nodes: Vec::new(),
+                handlers: HashMap::new(),
+                capabilities: HashMap::new(),
+                blocked: HashSet::new(),
+                stats: SimStats::default(),
+            })),
+        }
+    }
+
+    /// Add a node to the network and return its network handle
+    pub fn add_node(&self, public_key: PublicKey) -> Arc<SimNode> {
+        let mut state = self.state.lock().unwrap();
+        if !state.nodes.contains(&public_key) {
+            state.nodes.push(public_key);
+        }
+        Arc::new(SimNode { public_key, state: self.state.clone() })
+    }
+
+    /// Set the handler that receives a node's messages
+    pub fn set_handler(&self, public_key: &PublicKey, handler: Arc<dyn MessageHandler>) {
+        self.state.lock().unwrap().handlers.insert(*public_key, handler);
+    }
+
+    /// Stop delivering to a node, as if it crashed. Messages addressed to it
+    /// are dropped until a handler is set again.
+    pub fn remove_handler(&self, public_key: &PublicKey) {
+        self.state.lock().unwrap().handlers.remove(public_key);
+    }
+
+    /// Capabilities a node advertises to its peers
+    pub fn set_capabilities(&self, public_key: &PublicKey, capabilities: impl IntoIterator<Item = Capability>) {
+        self.state.lock().unwrap().capabilities.insert(*public_key, capabilities.into_iter().collect());
+    }
+
+    /// Cut all links between the two groups (in both directions)
+    pub fn partition(&self, a: &[PublicKey], b: &[PublicKey]) {
+        let mut state = self.state.lock().unwrap();
+        for x in a {
+            for y in b {
+                state.blocked.insert((*x, *y));
+                state.blocked.insert((*y, *x));
+            }
+        }
+    }
+
+    /// Restore all links
+    pub fn heal(&self) {
+        self.state.lock().unwrap().blocked.clear();
+    }
+
+    /// Change the drop rate for subsequent messages
+    pub fn set_drop_rate(&self, drop_rate: f64) {
+        assert!((0.0..=1.0).contains(&drop_rate), "drop_rate must be in 0.0..=1.0");
+        self.state.lock().unwrap().config.drop_rate = drop_rate;
+    }
+
+    /// Change the jitter for subsequent messages
+    pub fn set_jitter(&self, jitter_ms: u64) {
+        self.state.lock().unwrap().config.jitter_ms = jitter_ms;
+    }
+
+    /// Current virtual time
+    pub fn now_ms(&self) -> u64 {
+        self.state.lock().unwrap().now_ms
+    }
+
+    /// Messages waiting to be delivered
+    pub fn pending(&self) -> usize {
+        self.state.lock().unwrap().queue.len()
+    }
+
+    pub fn stats(&self) -> SimStats {
+        self.state.lock().unwrap().stats
+    }
+
+    /// Deliver the next message, returning false if none are queued.
+    /// A response from the handler is sent back to the original sender.
+    pub async fn step(&self) -> bool {
+        let (envelope, handler) = {
+            let mut state = self.state.lock().unwrap();
+            let envelope = match state.queue.pop() {
+                Some(Reverse(envelope)) => envelope,
+                None => return false,
+            };
+            state.now_ms = state.now_ms.max(envelope.deliver_at);
+
+            // Links cut while the message was in flight lose it too
+            if !state.linked(&envelope.from, &envelope.to) {
+                state.stats.dropped += 1;
+                return true;
+            }
+
+            match state.handlers.get(&envelope.to).cloned() {
+                Some(handler) => {
+                    state.stats.delivered += 1;
+                    (envelope, handler)
+                }
+                None => {
+                    state.stats.dropped += 1;
+                    return true;
+                }
+            }
+        };
+
+        let Envelope { from, to, message, .. } = envelope;
+        match handler.handle_message(from, message).await {
+            Ok(Some(response)) => self.state.lock().unwrap().enqueue(to, from, response),
+            Ok(None) => {}
+            Err(e) => {
+                debug!("sim: handler for {:?} failed: {}", to, e);
+                self.state.lock().unwrap().stats.handler_errors += 1;
+            }
+        }
+
+        true
+    }
+
+    /// Deliver messages until the queue is empty, returning how many steps ran
+    pub async fn run_until_idle(&self) -> usize {
+        let mut steps = 0;
+        while steps < MAX_STEPS && self.step().await {
+            steps += 1;
+        }
+        steps
+    }
+
+    /// Deliver messages due up to `now_ms + duration_ms`, then advance the clock to it
+    pub async fn run_for(&self, duration_ms: u64) -> usize {
+        let deadline = self.now_ms() + duration_ms;
+        let mut steps = 0;
+        loop {
+            let due = {
+                let state = self.state.lock().unwrap();
+                state.queue.peek().is_some_and(|Reverse(e)| e.deliver_at <= deadline)
+            };
+            if !due || steps >= MAX_STEPS || !self.step().await {
+                break;
+            }
+            steps += 1;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.now_ms = state.now_ms.max(deadline);
+        steps
+    }
+}
+
+/// A node's handle onto the simulated network
+pub struct SimNode {
+    public_key: PublicKey,
+    state: Arc<Mutex<SimState>>,
+}
+
+impl SimNode {
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+}
+
+#[async_trait]
+impl NetworkInterface for SimNode {
+    async fn broadcast_message(&self, message: L2Message) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let peers: Vec<PublicKey> = state.nodes.iter()
+            .filter(|peer| **peer != self.public_key)
+            .copied()
+            .collect();
+        for peer in peers {
+            state.enqueue(self.public_key, peer, message.clone());
+        }
+        Ok(())
+    }
+
+    async fn send_message(&self, peer: PublicKey, message: L2Message) -> Result<()> {
+        self.state.lock().unwrap().enqueue(self.public_key, peer, message);
+        Ok(())
+    }
+
+    async fn connected_peers(&self) -> Vec<PublicKey> {
+        let state = self.state.lock().unwrap();
+        state.nodes.iter()
+            .filter(|peer| **peer != self.public_key && state.linked(&self.public_key, peer))
+            .copied()
+            .collect()
+    }
+
+    async fn peer_table(&self) -> Vec<PeerInfo> {
+        let state = self.state.lock().unwrap();
+        state.nodes.iter()
+            .filter(|peer| **peer != self.public_key && state.linked(&self.public_key, peer))
+            .map(|peer| PeerInfo {
+                peer_id: format!("sim-{}", peer),
+                public_key: Some(*peer),
+                protocol_version: PROTOCOL_VERSION,
+                capabilities: state.capabilities.get(peer).cloned().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    fn local_id(&self) -> String {
+        format!("sim-{}", self.public_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_common::{Hash, L2Error};
+
+    /// Answers pings and records everything it receives
+    struct RecordingHandler {
+        received: Mutex<Vec<(PublicKey, String)>>,
+    }
+
+    impl RecordingHandler {
+        fn new() -> Arc<Self> {
+            Arc::new(Self { received: Mutex::new(Vec::new()) })
+        }
+
+        fn received(&self) -> Vec<(PublicKey, String)> {
+            self.received.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl MessageHandler for RecordingHandler {
+        async fn handle_message(&self, from: PublicKey, message: L2Message) -> Result<Option<L2Message>> {
+            self.received.lock().unwrap().push((from, format!("{:?}", message.message_type())));
+            match message {
+                L2Message::Ping => Ok(Some(L2Message::Pong)),
+                L2Message::ChannelInfoRequest { .. } => Err(L2Error::ChannelNotFound("sim".to_string())),
+                _ => Ok(None),
+            }
+        }
+    }
+
+    fn key(n: u8) -> PublicKey {
+        PublicKey::new([n; 32])
+    }
+
+    fn network(config: SimConfig, n: u8) -> (SimNetwork, Vec<Arc<SimNode>>, Vec<Arc<RecordingHandler>>) {
+        let sim = SimNetwork::new(config);
+        let mut nodes = Vec::new();
+        let mut handlers = Vec::new();
+        for i in 1..=n {
+            nodes.push(sim.add_node(key(i)));
+            let handler = RecordingHandler::new();
+            sim.set_handler(&key(i), handler.clone());
+            handlers.push(handler);
+        }
+        (sim, nodes, handlers)
+    }
+
+    #[tokio::test]
+    async fn test_request_response() {
+        let (sim, nodes, handlers) = network(SimConfig::default(), 2);
+
+        nodes[0].send_message(key(2), L2Message::Ping).await.unwrap();
+        assert_eq!(sim.run_until_idle().await, 2);
+
+        assert_eq!(handlers[1].received(), vec![(key(1), "Ping".to_string())]);
+        assert_eq!(handlers[0].received(), vec![(key(2), "Pong".to_string())]);
+        assert_eq!(sim.now_ms(), 20);
+
+        nodes[0].send_message(key(2), L2Message::ChannelInfoRequest { channel_id: Hash::random() }).await.unwrap();
+        sim.run_until_idle().await;
+        assert_eq!(sim.stats().handler_errors, 1);
+    }
+
+    #[tokio::test]
+    async fn test_partition_and_heal() {
+        let (sim, nodes, handlers) = network(SimConfig::default(), 3);
+
+        sim.partition(&[key(1)], &[key(3)]);
+        assert_eq!(nodes[0].connected_peers().await, vec![key(2)]);
+
+        nodes[0].broadcast_message(L2Message::Pong).await.unwrap();
+        sim.run_until_idle().await;
+        assert_eq!(handlers[1].received().len(), 1);
+        assert!(handlers[2].received().is_empty());
+
+        // Messages in flight when a partition starts are lost
+        sim.heal();
+        nodes[0].broadcast_message(L2Message::Pong).await.unwrap();
+        sim.partition(&[key(1)], &[key(2)]);
+        sim.run_until_idle().await;
+        assert_eq!(handlers[1].received().len(), 1);
+        assert_eq!(handlers[2].received().len(), 1);
+        assert_eq!(sim.stats().dropped, 2);
+    }
+
+    #[tokio::test]
+    async fn test_latency_and_drops() {
+        let (sim, nodes, handlers) = network(SimConfig { latency_ms: 100, ..Default::default() }, 2);
+
+        nodes[0].send_message(key(2), L2Message::Pong).await.unwrap();
+        assert_eq!(sim.run_for(99).await, 0);
+        assert_eq!(sim.pending(), 1);
+        assert_eq!(sim.run_for(1).await, 1);
+        assert_eq!(handlers[1].received().len(), 1);
+
+        sim.set_drop_rate(1.0);
+        nodes[0].send_message(key(2), L2Message::Pong).await.unwrap();
+        assert_eq!(sim.run_until_idle().await, 0);
+        assert_eq!(sim.stats().dropped, 1);
+    }
+
+    #[tokio::test]
+    async fn test_crashed_node_misses_messages() {
+        let (sim, nodes, handlers) = network(SimConfig::default(), 2);
+
+        sim.remove_handler(&key(2));
+        nodes[0].send_message(key(2), L2Message::Pong).await.unwrap();
+        sim.run_until_idle().await;
+        assert_eq!(sim.stats().dropped, 1);
+
+        sim.set_handler(&key(2), handlers[1].clone());
+        nodes[0].send_message(key(2), L2Message::Pong).await.unwrap();
+        sim.run_until_idle().await;
+        assert_eq!(handlers[1].received().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_same_seed_same_order() {
+        async fn run(seed: u64) -> Vec<(PublicKey, String)> {
+            let config = SimConfig { seed, latency_ms: 5, jitter_ms: 50, drop_rate: 0.2 };
+            let (sim, nodes, handlers) = network(config, 4);
+            for node in &nodes {
+                node.broadcast_message(L2Message::Ping).await.unwrap();
+                node.broadcast_message(L2Message::Pong).await.unwrap();
+            }
+            sim.run_until_idle().await;
+            handlers[0].received()
+        }
+
+        assert_eq!(run(7).await, run(7).await);
+    }
+}
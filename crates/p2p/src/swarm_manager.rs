@@ -1,25 +1,83 @@
 use libp2p::{
     noise, tcp, yamux, Multiaddr, PeerId, Swarm, SwarmBuilder,
-    swarm::SwarmEvent,
-    gossipsub,
+    swarm::{dial_opts::DialOpts, SwarmEvent},
+    gossipsub, identify, mdns,
+    request_response::{self, InboundRequestId, OutboundRequestId, ResponseChannel},
 };
 use futures::StreamExt;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tracing::{info, warn, error, debug};
+use tari_l2_common::{PublicKey, ResourceLimits, Timestamp};
+use tari_l2_common::crypto::KeyPair;
 use crate::behaviour::L2Behaviour;
+use crate::capabilities::{PeerAdvert, PeerInfo};
+use crate::limits::{ConnectedPeer, ConnectionLimits};
+use crate::peerbook::PeerBook;
 use crate::messages::L2Message;
+use crate::wire::{self, DecodeError, ProtocolVersions, Recipient, PROTOCOL_VERSION};
+
+/// Most failed direct messages kept per peer for its next connection
+const MAX_UNDELIVERED_PER_PEER: usize = 32;
+
+/// Peer state shared between the swarm task and the network handle
+#[derive(Clone, Default)]
+pub struct SharedPeers {
+    /// Protocol version and advert of each identified peer
+    pub table: Arc<RwLock<HashMap<PeerId, PeerInfo>>>,
+    /// Every peer seen, connected or not
+    pub book: Arc<RwLock<PeerBook>>,
+    /// L2 keys of channel counterparties, which are never pruned
+    pub protected: Arc<RwLock<HashSet<PublicKey>>>,
+}
+
+/// A message received from a peer, on its way to the message handler
+pub struct InboundMessage {
+    /// L2 key that signed the message. Gossip is relayed, so this may be
+    /// a node other than the peer it arrived from.
+    pub from: PublicKey,
+    pub message: L2Message,
+    /// Set for direct requests, which expect the handler's reply back
+    pub reply: Option<InboundRequestId>,
+}
 
 pub struct SwarmManager {
     pub swarm: Swarm<L2Behaviour>,
-    message_tx: mpsc::UnboundedSender<L2Message>,
+    message_tx: mpsc::UnboundedSender<InboundMessage>,
+    limits: ResourceLimits,
+    /// Per-peer message counts for the current one-second window
+    rate_windows: HashMap<PeerId, (Instant, u32)>,
+    peers: SharedPeers,
+    connection_limits: ConnectionLimits,
+    /// Direction and start time of each connected peer's first connection
+    connections: HashMap<PeerId, (bool, u64)>,
+    /// Requesting peer, the L2 key that signed the request and its stream,
+    /// for each direct request until the handler replies
+    replies: HashMap<InboundRequestId, (PeerId, PublicKey, ResponseChannel<Vec<u8>>)>,
+    /// Message of each direct request in flight, queued for the peer's
+    /// next connection if the request fails
+    outbound: HashMap<OutboundRequestId, L2Message>,
+    /// Direct messages whose request failed, sent again once their peer is
+    /// identified. They are never gossiped, which would show channel
+    /// traffic to the whole network.
+    undelivered: HashMap<PeerId, VecDeque<L2Message>>,
+    /// L2 key every outgoing message is signed with
+    signer: Arc<KeyPair>,
 }
 
 impl SwarmManager {
     pub fn new(
         _listen_addr: Multiaddr,
-        message_tx: mpsc::UnboundedSender<L2Message>,
+        local_key: libp2p::identity::Keypair,
+        message_tx: mpsc::UnboundedSender<InboundMessage>,
+        limits: ResourceLimits,
+        advert: PeerAdvert,
+        peers: SharedPeers,
+        connection_limits: ConnectionLimits,
     ) -> anyhow::Result<Self> {
-        let local_key = libp2p::identity::Keypair::generate_ed25519();
         let _local_peer_id = PeerId::from(local_key.public());
 
         info!("🔑 Local peer id: {:?}", _local_peer_id);
@@ -32,7 +90,7 @@ impl SwarmManager {
                 yamux::Config::default,
             )
             .map_err(|e| anyhow::anyhow!("Failed to configure TCP: {}", e))?
-            .with_behaviour(|_| L2Behaviour::new(local_key.clone()).unwrap())
+            .with_behaviour(|_| L2Behaviour::new(local_key.clone(), limits.max_message_size, &advert))
             .map_err(|e| anyhow::anyhow!("Failed to create behaviour: {}", e))?
             .with_swarm_config(|c| c.with_idle_connection_timeout(std::time::Duration::from_secs(60)))
             .build();
@@ -40,18 +98,43 @@ impl SwarmManager {
         Ok(Self {
             swarm,
             message_tx,
+            limits,
+            rate_windows: HashMap::new(),
+            peers,
+            connection_limits,
+            connections: HashMap::new(),
+            replies: HashMap::new(),
+            outbound: HashMap::new(),
+            undelivered: HashMap::new(),
+            signer: Arc::new(KeyPair::generate()),
         })
     }
 
-    pub async fn start(&mut self, listen_addr: Multiaddr) -> anyhow::Result<()> {
+    /// Sign outgoing messages with the node's L2 key rather than a
+    /// throwaway one
+    pub fn with_signer(mut self, signer: Arc<KeyPair>) -> Self {
+        self.signer = signer;
+        self
+    }
+
+    /// Discover peers on the local network with mDNS and dial them
+    pub fn with_mdns(mut self) -> anyhow::Result<Self> {
+        let local_peer_id = *self.swarm.local_peer_id();
+        self.swarm.behaviour_mut().enable_mdns(local_peer_id)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(self)
+    }
+
+    /// Listen on `listen_addr` and subscribe to `topics`
+    pub async fn start(&mut self, listen_addr: Multiaddr, topics: &[String]) -> anyhow::Result<()> {
         self.swarm.listen_on(listen_addr.clone())
             .map_err(|e| anyhow::anyhow!("Failed to listen: {}", e))?;
         info!("📡 Listening on {:?}", listen_addr);
 
-        self.swarm.behaviour_mut().subscribe("tari-l2-state-updates")
-            .map_err(|e| anyhow::anyhow!("Failed to subscribe: {}", e))?;
-        self.swarm.behaviour_mut().subscribe("tari-l2-channel-announcements")
-            .map_err(|e| anyhow::anyhow!("Failed to subscribe: {}", e))?;
+        for topic in topics {
+            self.swarm.behaviour_mut().subscribe(topic)
+                .map_err(|e| anyhow::anyhow!("Failed to subscribe to {}: {}", topic, e))?;
+        }
 
         info!("✅ Subscribed to network topics");
         Ok(())
@@ -69,33 +152,55 @@ impl SwarmManager {
                 message,
             })) => {
                 debug!("📨 Received message from {:?}: {:?}", propagation_source, message_id);
-
-                match bincode::deserialize::<L2Message>(&message.data) {
-                    Ok(l2_message) => {
-                        info!("✅ Deserialized message: {:?}", l2_message.message_type());
-                        if let Err(e) = self.message_tx.send(l2_message) {
-                            error!("Failed to forward message: {}", e);
-                        }
-                    }
-                    Err(e) => {
-                        warn!("⚠️  Failed to deserialize message: {}", e);
-                    }
+                self.receive_gossip(propagation_source, message.topic.as_str(), &message.data);
+            }
+            SwarmEvent::Behaviour(behaviour::L2BehaviourEvent::Identify(identify::Event::Received { peer_id, info, .. })) => {
+                let Some(advert) = self.negotiate(peer_id, &info.protocol_version, &info.agent_version) else {
+                    return;
+                };
+                let addresses = info.listen_addrs.iter().map(|addr| addr.to_string()).collect();
+                self.peers.book.write().unwrap().record_identified(&peer_id.to_string(), addresses, advert, Timestamp::now());
+                self.redeliver(peer_id);
+            }
+            SwarmEvent::Behaviour(behaviour::L2BehaviourEvent::Direct(event)) => {
+                self.handle_direct(event);
+            }
+            SwarmEvent::Behaviour(behaviour::L2BehaviourEvent::Mdns(mdns::Event::Discovered(found))) => {
+                self.dial_discovered(found);
+            }
+            SwarmEvent::Behaviour(behaviour::L2BehaviourEvent::Mdns(mdns::Event::Expired(gone))) => {
+                for (peer_id, addr) in gone {
+                    debug!("📴 mDNS record expired for {:?} at {}", peer_id, addr);
                 }
             }
             SwarmEvent::NewListenAddr { address, .. } => {
                 info!("🎧 Listening on {}", address);
             }
-            SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
+            SwarmEvent::ConnectionEstablished { peer_id, endpoint, num_established, .. } => {
                 info!("🤝 Connection established with {:?} at {:?}", peer_id, endpoint);
+                let now = Timestamp::now();
+                self.peers.book.write().unwrap().record_connected(&peer_id.to_string(), endpoint.is_dialer(), now);
+                if num_established.get() == 1 {
+                    self.connections.insert(peer_id, (endpoint.is_dialer(), now.as_secs()));
+                    self.enforce_limits();
+                }
             }
-            SwarmEvent::ConnectionClosed { peer_id, cause, .. } => {
+            SwarmEvent::ConnectionClosed { peer_id, cause, num_established, .. } => {
                 info!("👋 Connection closed with {:?}: {:?}", peer_id, cause);
+                if num_established == 0 {
+                    self.rate_windows.remove(&peer_id);
+                    self.connections.remove(&peer_id);
+                    self.peers.table.write().unwrap().remove(&peer_id);
+                }
             }
             SwarmEvent::IncomingConnection { connection_id, local_addr, send_back_addr } => {
                 debug!("📥 Incoming connection {} from {} to {}", connection_id, send_back_addr, local_addr);
             }
             SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
                 warn!("❌ Outgoing connection error to {:?}: {}", peer_id, error);
+                if let Some(peer_id) = peer_id {
+                    self.peers.book.write().unwrap().record_dial_failure(&peer_id.to_string(), Timestamp::now());
+                }
             }
             SwarmEvent::IncomingConnectionError { error, .. } => {
                 warn!("❌ Incoming connection error: {}", error);
@@ -104,14 +209,337 @@ impl SwarmManager {
         }
     }
 
+    /// Check a gossip message that arrived on `topic` and pass it to the
+    /// handler with the L2 key that signed it
+    pub(crate) fn receive_gossip(&mut self, propagation_source: PeerId, topic: &str, data: &[u8]) {
+        if data.len() > self.limits.max_message_size {
+            warn!("⚠️  Dropping oversized message ({} bytes) from {:?}", data.len(), propagation_source);
+            return;
+        }
+
+        if !self.check_rate(propagation_source) {
+            warn!("⚠️  Rate limit exceeded by {:?}, dropping message", propagation_source);
+            return;
+        }
+
+        match wire::decode(data, &Recipient::Topic(topic.to_string()), Timestamp::now().as_secs()) {
+            Ok((_, sender, l2_message)) => {
+                info!("✅ Deserialized message: {:?}", l2_message.message_type());
+                if let Err(e) = self.message_tx.send(InboundMessage { from: sender, message: l2_message, reply: None }) {
+                    error!("Failed to forward message: {}", e);
+                }
+            }
+            Err(DecodeError::Unsupported(version)) => {
+                warn!("⚠️  Dropping message from {:?} at protocol version {}; this node speaks up to {}",
+                    propagation_source, version, PROTOCOL_VERSION);
+            }
+            Err(e) => {
+                warn!("⚠️  Failed to deserialize message: {}", e);
+            }
+        }
+    }
+
+    fn handle_direct(&mut self, event: request_response::Event<Vec<u8>, Vec<u8>>) {
+        match event {
+            request_response::Event::Message { peer, message: request_response::Message::Request { request_id, request, channel }, .. } => {
+                if !self.check_rate(peer) {
+                    warn!("⚠️  Rate limit exceeded by {:?}, dropping direct request", peer);
+                    return;
+                }
+                if let Some((sender, message)) = self.decode_direct(peer, &request) {
+                    self.replies.insert(request_id, (peer, sender, channel));
+                    self.forward_direct(peer, sender, message, Some(request_id));
+                }
+            }
+            request_response::Event::Message { peer, message: request_response::Message::Response { request_id, response }, .. } => {
+                self.outbound.remove(&request_id);
+                if response.is_empty() {
+                    return;
+                }
+                if let Some((sender, message)) = self.decode_direct(peer, &response) {
+                    self.forward_direct(peer, sender, message, None);
+                }
+            }
+            request_response::Event::OutboundFailure { peer, request_id, error, .. } => {
+                let Some(message) = self.outbound.remove(&request_id) else {
+                    return;
+                };
+                warn!("⚠️  Direct {:?} to {:?} failed ({}), keeping it for the next connection", message.message_type(), peer, error);
+                self.queue_undelivered(peer, message);
+            }
+            request_response::Event::InboundFailure { peer, request_id, error, .. } => {
+                debug!("Direct request from {:?} failed: {}", peer, error);
+                self.replies.remove(&request_id);
+            }
+            request_response::Event::ResponseSent { .. } => {}
+        }
+    }
+
+    /// Decode a direct message and its signer. Only messages addressed to
+    /// this node are accepted, and a peer sending messages signed by a key
+    /// other than the one bound to it has them dropped.
+    fn decode_direct(&self, peer: PeerId, data: &[u8]) -> Option<(PublicKey, L2Message)> {
+        match wire::decode(data, &Recipient::Node(self.signer.public_key()), Timestamp::now().as_secs()) {
+            Ok((_, sender, message)) => {
+                let advertised = self.peers.table.read().unwrap().get(&peer).and_then(|info| info.public_key);
+                if let Some(advertised) = advertised.filter(|key| *key != sender) {
+                    warn!("⚠️  Dropping direct message from {:?}: signed by {} but it advertises {}", peer, sender, advertised);
+                    return None;
+                }
+                Some((sender, message))
+            }
+            Err(DecodeError::Unsupported(version)) => {
+                warn!("⚠️  Dropping direct message from {:?} at protocol version {}; this node speaks up to {}",
+                    peer, version, PROTOCOL_VERSION);
+                None
+            }
+            Err(e) => {
+                warn!("⚠️  Failed to deserialize direct message from {:?}: {}", peer, e);
+                None
+            }
+        }
+    }
+
+    fn forward_direct(&mut self, peer: PeerId, from: PublicKey, message: L2Message, reply: Option<InboundRequestId>) {
+        debug!("📨 Direct {:?} from {:?}", message.message_type(), peer);
+        if let Err(e) = self.message_tx.send(InboundMessage { from, message, reply }) {
+            error!("Failed to forward message: {}", e);
+        }
+    }
+
+    /// Settle on a protocol version with a peer from its identify info and
+    /// record its advert, disconnecting it if there is no version in common.
+    /// Returns the advert, with its key only if the key signed the peer's
+    /// ID, when a version was agreed.
+    fn negotiate(&mut self, peer_id: PeerId, protocol: &str, agent: &str) -> Option<PeerAdvert> {
+        let version = ProtocolVersions::from_identify(protocol)
+            .and_then(|remote| ProtocolVersions::LOCAL.negotiate(&remote));
+        match version {
+            Some(version) => {
+                let claimed = PeerAdvert::from_agent_version(agent);
+                let advert = claimed.clone().verified(&peer_id.to_bytes());
+                if claimed.public_key.is_some() && advert.public_key.is_none() {
+                    warn!("⚠️  {:?} advertises {:?} without a valid binding to its peer ID, ignoring the key", peer_id, claimed.public_key);
+                }
+                let info = PeerInfo {
+                    peer_id: peer_id.to_string(),
+                    public_key: advert.public_key,
+                    protocol_version: version,
+                    capabilities: advert.capabilities.clone(),
+                };
+                let previous = self.peers.table.write().unwrap().insert(peer_id, info);
+                if previous.map(|p| p.protocol_version) != Some(version) {
+                    info!("🤝 Speaking protocol version {} with {:?} ({})", version, peer_id, agent);
+                }
+                Some(advert)
+            }
+            None => {
+                warn!("⚠️  Disconnecting {:?}: no protocol version in common (it speaks {}, this node {})",
+                    peer_id, protocol, ProtocolVersions::LOCAL.identify_string());
+                self.peers.table.write().unwrap().remove(&peer_id);
+                let _ = self.swarm.disconnect_peer_id(peer_id);
+                None
+            }
+        }
+    }
+
+    /// Version gossip is published at: the lowest negotiated with any
+    /// connected peer, so every peer can read it
+    pub fn wire_version(&self) -> u32 {
+        self.peers.table.read().unwrap().values()
+            .map(|peer| peer.protocol_version)
+            .min()
+            .unwrap_or(PROTOCOL_VERSION)
+    }
+
+    /// Version negotiated with one peer, for messages only it reads
+    fn peer_version(&self, peer_id: &PeerId) -> u32 {
+        self.peers.table.read().unwrap().get(peer_id)
+            .map_or(PROTOCOL_VERSION, |peer| peer.protocol_version)
+    }
+
+    /// Count a message against the peer's one-second window, returning false once over the limit
+    fn check_rate(&mut self, peer: PeerId) -> bool {
+        let now = Instant::now();
+        let window = self.rate_windows.entry(peer).or_insert((now, 0));
+
+        if now.duration_since(window.0) >= Duration::from_secs(1) {
+            *window = (now, 0);
+        }
+
+        window.1 += 1;
+        window.1 <= self.limits.max_messages_per_sec
+    }
+
     pub fn publish_message(&mut self, topic: &str, message: L2Message) -> anyhow::Result<()> {
-        let data = bincode::serialize(&message)?;
+        let recipient = Recipient::Topic(topic.to_string());
+        let data = wire::encode(&message, self.wire_version(), &recipient, Timestamp::now().as_secs(), &self.signer)?;
+        if data.len() > self.limits.max_message_size {
+            return Err(anyhow::anyhow!("Message too large: {} bytes (max {})", data.len(), self.limits.max_message_size));
+        }
         debug!("📤 Publishing {} bytes to topic: {}", data.len(), topic);
         self.swarm.behaviour_mut().publish(topic, data)
             .map_err(|e| anyhow::anyhow!("Failed to publish: {}", e))?;
         Ok(())
     }
 
+    /// Send `message` to one peer over the direct protocol, at the protocol
+    /// version negotiated with it and addressed to the key bound to it.
+    /// Should the peer not be identified or the request fail, the message
+    /// is kept and sent again when the peer is next identified.
+    pub fn send_direct(&mut self, peer_id: PeerId, message: L2Message) -> anyhow::Result<()> {
+        let bound = self.peers.table.read().unwrap().get(&peer_id).and_then(|info| info.public_key);
+        let Some(key) = bound else {
+            self.queue_undelivered(peer_id, message);
+            return Ok(());
+        };
+        let recipient = Recipient::Node(key);
+        let data = wire::encode(&message, self.peer_version(&peer_id), &recipient, Timestamp::now().as_secs(), &self.signer)?;
+        if data.len() > self.limits.max_message_size {
+            return Err(anyhow::anyhow!("Message too large: {} bytes (max {})", data.len(), self.limits.max_message_size));
+        }
+        debug!("📤 Sending {} bytes directly to {:?}", data.len(), peer_id);
+        let request_id = self.swarm.behaviour_mut().direct.send_request(&peer_id, data);
+        self.outbound.insert(request_id, message);
+        Ok(())
+    }
+
+    /// Keep a direct message that could not be delivered for the peer's
+    /// next connection, dropping the oldest once the queue is full
+    fn queue_undelivered(&mut self, peer_id: PeerId, message: L2Message) {
+        let queue = self.undelivered.entry(peer_id).or_default();
+        if queue.len() == MAX_UNDELIVERED_PER_PEER {
+            if let Some(dropped) = queue.pop_front() {
+                warn!("⚠️  Dropping undelivered {:?} to {:?}: {} messages already wait for it",
+                    dropped.message_type(), peer_id, MAX_UNDELIVERED_PER_PEER);
+            }
+        }
+        queue.push_back(message);
+    }
+
+    /// Send the messages that failed to reach a peer before it reconnected
+    fn redeliver(&mut self, peer_id: PeerId) {
+        let Some(queue) = self.undelivered.remove(&peer_id) else {
+            return;
+        };
+        info!("📬 Resending {} undelivered messages to {:?}", queue.len(), peer_id);
+        for message in queue {
+            if let Err(e) = self.send_direct(peer_id, message) {
+                error!("Failed to send direct message: {}", e);
+            }
+        }
+    }
+
+    /// Answer a direct request with the handler's reply, if it had one
+    pub fn respond(&mut self, request_id: InboundRequestId, reply: Option<L2Message>) -> anyhow::Result<()> {
+        let Some((peer_id, requester, channel)) = self.replies.remove(&request_id) else {
+            return Err(anyhow::anyhow!("No open direct request {}", request_id));
+        };
+        let data = match reply {
+            Some(reply) => {
+                let recipient = Recipient::Node(requester);
+                wire::encode(&reply, self.peer_version(&peer_id), &recipient, Timestamp::now().as_secs(), &self.signer)?
+            }
+            None => Vec::new(),
+        };
+        if data.len() > self.limits.max_message_size {
+            return Err(anyhow::anyhow!("Reply too large: {} bytes (max {})", data.len(), self.limits.max_message_size));
+        }
+        self.swarm.behaviour_mut().direct.send_response(channel, data)
+            .map_err(|_| anyhow::anyhow!("Direct request {} was closed before the reply", request_id))
+    }
+
+    /// Connected peers with what the limits weigh them by. A peer is
+    /// protected only once its identify advert has bound a counterparty's
+    /// key to its peer ID on this connection; a key in the peer book, or
+    /// one claimed before identify completes, proves nothing.
+    fn connected(&self) -> Vec<ConnectedPeer> {
+        let now = Timestamp::now();
+        let table = self.peers.table.read().unwrap();
+        let book = self.peers.book.read().unwrap();
+        let protected = self.peers.protected.read().unwrap();
+        self.connections.iter()
+            .map(|(peer_id, (outbound, connected_at))| {
+                let known = book.get(&peer_id.to_string());
+                let key = table.get(peer_id).and_then(|peer| peer.public_key);
+                ConnectedPeer {
+                    peer_id: peer_id.to_string(),
+                    outbound: *outbound,
+                    protected: key.is_some_and(|key| protected.contains(&key)),
+                    score: known.map_or(0.0, |peer| peer.score(now)),
+                    connected_at: *connected_at,
+                }
+            })
+            .collect()
+    }
+
+    /// Disconnect the lowest-value peers while over the connection limits
+    fn enforce_limits(&mut self) {
+        for peer_id in self.connection_limits.prune(&self.connected()) {
+            let Ok(peer_id) = PeerId::from_str(&peer_id) else {
+                continue;
+            };
+            info!("✂️  Disconnecting {:?} to stay within {} peers ({} inbound)",
+                peer_id, self.connection_limits.max_peers, self.connection_limits.max_inbound());
+            let _ = self.swarm.disconnect_peer_id(peer_id);
+        }
+    }
+
+    /// Dial known channel counterparties that are not connected, then the
+    /// best-scored known peers until the outbound slots are full. Returns
+    /// how many dials were started.
+    pub fn top_up(&mut self) -> usize {
+        let connected = self.connected();
+        let wanted = self.connection_limits.outbound_wanted(&connected);
+        let connected: HashSet<String> = connected.into_iter().map(|peer| peer.peer_id).collect();
+
+        let now = Timestamp::now();
+        let candidates = {
+            let book = self.peers.book.read().unwrap();
+            let protected = self.peers.protected.read().unwrap();
+            let (counterparties, others): (Vec<_>, Vec<_>) = book.dial_candidates(&connected, usize::MAX, now)
+                .into_iter()
+                .partition(|peer| peer.public_key.is_some_and(|key| protected.contains(&key)));
+            counterparties.into_iter().chain(others.into_iter().take(wanted)).collect::<Vec<_>>()
+        };
+
+        let mut dialed = 0;
+        for peer in candidates {
+            let Ok(peer_id) = PeerId::from_str(&peer.peer_id) else {
+                continue;
+            };
+            let addresses: Vec<Multiaddr> = peer.addresses.iter().filter_map(|addr| addr.parse().ok()).collect();
+            self.peers.book.write().unwrap().record_dial_attempt(&peer.peer_id, now);
+            debug!("☎️  Dialing known peer {} (score {:.2})", peer.peer_id, peer.score(now));
+            match self.swarm.dial(DialOpts::peer_id(peer_id).addresses(addresses).build()) {
+                Ok(()) => dialed += 1,
+                Err(e) => {
+                    debug!("Could not dial known peer {}: {}", peer.peer_id, e);
+                    self.peers.book.write().unwrap().record_dial_failure(&peer.peer_id, now);
+                }
+            }
+        }
+        dialed
+    }
+
+    /// Dial peers found on the local network by mDNS that are not already
+    /// connected. Connection limits apply to them as to any other peer.
+    fn dial_discovered(&mut self, found: Vec<(PeerId, Multiaddr)>) {
+        let mut addresses: HashMap<PeerId, Vec<Multiaddr>> = HashMap::new();
+        for (peer_id, addr) in found {
+            addresses.entry(peer_id).or_default().push(addr);
+        }
+        for (peer_id, addresses) in addresses {
+            if self.swarm.is_connected(&peer_id) {
+                continue;
+            }
+            info!("🔎 Found {:?} on the local network at {:?}", peer_id, addresses);
+            if let Err(e) = self.swarm.dial(DialOpts::peer_id(peer_id).addresses(addresses).build()) {
+                debug!("Could not dial local peer {:?}: {}", peer_id, e);
+            }
+        }
+    }
+
     pub fn dial(&mut self, addr: Multiaddr) -> anyhow::Result<()> {
         info!("☎️  Dialing peer at: {}", addr);
         self.swarm.dial(addr)
@@ -121,3 +549,85 @@ impl SwarmManager {
 }
 
 use crate::behaviour;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swarm_manager() -> SwarmManager {
+        let (message_tx, _) = mpsc::unbounded_channel();
+        SwarmManager::new(
+            "/ip4/127.0.0.1/tcp/0".parse().unwrap(),
+            libp2p::identity::Keypair::generate_ed25519(),
+            message_tx,
+            ResourceLimits::default(),
+            PeerAdvert::default(),
+            SharedPeers::default(),
+            ConnectionLimits { max_peers: 8, outbound_peers: 4 },
+        )
+        .unwrap()
+    }
+
+    /// Peer table entry for a peer identified with a bound `key`
+    fn peer_info(peer: PeerId, key: PublicKey) -> PeerInfo {
+        PeerInfo {
+            peer_id: peer.to_string(),
+            public_key: Some(key),
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dial_discovered_dials_each_peer_once() {
+        let mut manager = swarm_manager().with_mdns().unwrap();
+        let (peer, other) = (PeerId::random(), PeerId::random());
+
+        // mDNS reports one record per address; a peer is dialed once with all of them
+        manager.dial_discovered(vec![
+            (peer, "/ip4/127.0.0.1/tcp/1".parse().unwrap()),
+            (peer, "/ip4/127.0.0.1/tcp/2".parse().unwrap()),
+            (other, "/ip4/127.0.0.1/tcp/3".parse().unwrap()),
+        ]);
+        assert_eq!(manager.swarm.network_info().connection_counters().num_pending_outgoing(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_undelivered_messages_wait_for_their_peer() {
+        let mut manager = swarm_manager();
+        let peer = PeerId::random();
+        let key = KeyPair::generate().public_key();
+
+        // Not identified yet: kept rather than sent unaddressed
+        manager.send_direct(peer, L2Message::Ping).unwrap();
+        assert!(manager.outbound.is_empty());
+        for _ in 0..MAX_UNDELIVERED_PER_PEER {
+            manager.queue_undelivered(peer, L2Message::Ping);
+        }
+        assert_eq!(manager.undelivered[&peer].len(), MAX_UNDELIVERED_PER_PEER);
+
+        // Resent directly once the peer is identified again, never gossiped
+        manager.peers.table.write().unwrap().insert(peer, peer_info(peer, key));
+        manager.redeliver(peer);
+        assert!(manager.undelivered.is_empty());
+        assert_eq!(manager.outbound.len(), MAX_UNDELIVERED_PER_PEER);
+    }
+
+    #[tokio::test]
+    async fn test_protection_needs_a_bound_key() {
+        let mut manager = swarm_manager();
+        let peer = PeerId::random();
+        let counterparty = KeyPair::generate().public_key();
+        manager.peers.protected.write().unwrap().insert(counterparty);
+        manager.connections.insert(peer, (false, 0));
+
+        // A key only in the book, as an earlier unbound advert left it, is not enough
+        let advert = PeerAdvert { public_key: Some(counterparty), ..PeerAdvert::default() };
+        manager.peers.book.write().unwrap().record_identified(&peer.to_string(), Vec::new(), advert, Timestamp::now());
+        assert!(!manager.connected()[0].protected);
+
+        // Identify put the bound key in the peer table
+        manager.peers.table.write().unwrap().insert(peer, peer_info(peer, counterparty));
+        assert!(manager.connected()[0].protected);
+    }
+}
@@ -0,0 +1,117 @@
+//! Gossip topic names.
+//!
+//! Topics are named `tari-l2/<tari network>/<namespace>/<topic>`. Nodes
+//! only receive messages on topics they subscribe to, so deployments with
+//! different namespaces, or on different Tari networks, never see each
+//! other's traffic even when their peers are connected.
+
+use crate::messages::L2Message;
+
+/// Namespace used when the config does not set one
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// Tari network assumed until the node says otherwise
+pub const DEFAULT_TARI_NETWORK: &str = "esmeralda";
+
+/// Kinds of gossip traffic, each on its own topic
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Topic {
+    /// Listings and catalog roots
+    Marketplace,
+    /// Channel state update proposals and gap recovery
+    StateUpdates,
+    /// Channel open requests
+    ChannelAnnouncements,
+    /// Responses and requests without a dedicated topic
+    General,
+}
+
+impl Topic {
+    pub const ALL: [Topic; 4] = [Topic::Marketplace, Topic::StateUpdates, Topic::ChannelAnnouncements, Topic::General];
+
+    /// Topic a message is published on
+    pub fn for_message(message: &L2Message) -> Self {
+        match message {
+            L2Message::ListingBroadcast { .. }
+            | L2Message::ListingUpdated { .. }
+            | L2Message::CatalogRoot { .. } => Topic::Marketplace,
+            L2Message::StateUpdateProposal { .. }
+            | L2Message::StateUpdateGapRequest { .. }
+            | L2Message::StateUpdateGapFill { .. } => Topic::StateUpdates,
+            L2Message::ChannelOpenRequest { .. } => Topic::ChannelAnnouncements,
+            _ => Topic::General,
+        }
+    }
+
+    fn suffix(&self) -> &'static str {
+        match self {
+            Topic::Marketplace => "marketplace",
+            Topic::StateUpdates => "state-updates",
+            Topic::ChannelAnnouncements => "channel-announcements",
+            Topic::General => "general",
+        }
+    }
+}
+
+/// Topic names for one deployment
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Topics {
+    prefix: String,
+}
+
+impl Topics {
+    pub fn new(namespace: &str, tari_network: &str) -> Self {
+        Self {
+            prefix: format!("tari-l2/{}/{}", tari_network, namespace),
+        }
+    }
+
+    /// Full name of `topic`
+    pub fn name(&self, topic: Topic) -> String {
+        format!("{}/{}", self.prefix, topic.suffix())
+    }
+
+    /// Every topic a node subscribes to
+    pub fn all(&self) -> Vec<String> {
+        Topic::ALL.iter().map(|topic| self.name(*topic)).collect()
+    }
+}
+
+impl Default for Topics {
+    fn default() -> Self {
+        Self::new(DEFAULT_NAMESPACE, DEFAULT_TARI_NETWORK)
+    }
+}
+
+/// Check a namespace can be used in topic names: 1-64 lowercase letters,
+/// digits, `-` or `_`
+pub fn validate_namespace(namespace: &str) -> Result<(), String> {
+    if namespace.is_empty() || namespace.len() > 64 {
+        return Err("must be 1 to 64 characters".to_string());
+    }
+    if let Some(c) = namespace.chars().find(|c| !matches!(c, 'a'..='z' | '0'..='9' | '-' | '_')) {
+        return Err(format!("'{}' is not allowed; use lowercase letters, digits, '-' or '_'", c));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topics_isolate_deployments() {
+        let main = Topics::new("shop", "mainnet");
+        assert_eq!(main.name(Topic::Marketplace), "tari-l2/mainnet/shop/marketplace");
+        assert_eq!(main.all().len(), Topic::ALL.len());
+
+        // Another namespace or Tari network shares no topic
+        for other in [Topics::new("staging", "mainnet"), Topics::new("shop", "esmeralda")] {
+            assert!(other.all().iter().all(|topic| !main.all().contains(topic)));
+        }
+
+        assert!(validate_namespace("shop-eu_1").is_ok());
+        assert!(validate_namespace("").is_err());
+        assert!(validate_namespace("Shop/eu").is_err());
+    }
+}
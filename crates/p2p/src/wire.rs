@@ -0,0 +1,276 @@
+//! Wire protocol versions.
+//!
+//! Each node advertises the range of protocol versions it speaks in its
+//! identify info, and peers settle on the highest version both support when
+//! they connect. Gossip goes to every peer at once, so a node publishes at
+//! the lowest version negotiated with any connected peer. Peers with no
+//! version in common are disconnected.
+//!
+//! Every payload, gossiped or direct, is an envelope: a magic prefix, the
+//! version as a little-endian u32, then a bincode [`Signed`] message. The
+//! signature covers the version, the time of signing, the [`Recipient`]
+//! and the bincode `L2Message` under [`SigningContext::Message`], so a
+//! receiver knows which L2 key sent a message, even one relayed through
+//! other peers, and handlers can authorize by it. A message arriving on
+//! another topic or at another node than it was signed for, or signed more
+//! than [`MAX_CLOCK_SKEW_SECS`] away from the receiver's clock, is
+//! refused, so it cannot be replayed elsewhere or later. Version 2
+//! envelopes signed the message alone, version 1 carried the bare message
+//! and version 0 was a bare message with no envelope at all; none of them
+//! is accepted any more.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{PublicKey, Signature};
+use tari_l2_common::crypto::{self, KeyPair, SigningContext};
+use crate::messages::L2Message;
+
+/// Newest protocol version this node speaks
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// Oldest protocol version this node still accepts: the first whose
+/// signature covers where and when a message was sent
+pub const MIN_PROTOCOL_VERSION: u32 = 3;
+
+/// Furthest an envelope's timestamp may be from the receiver's clock,
+/// either way
+pub const MAX_CLOCK_SKEW_SECS: u64 = 120;
+
+/// Prefix of a versioned envelope
+pub const ENVELOPE_MAGIC: [u8; 4] = *b"TL2\0";
+
+const IDENTIFY_PREFIX: &str = "/tari-l2/proto/";
+
+/// Identify protocol string of nodes from before versioning
+const LEGACY_IDENTIFY: &str = "/tari-l2/1.0.0";
+
+/// Range of protocol versions a node speaks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProtocolVersions {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl ProtocolVersions {
+    /// What this node speaks
+    pub const LOCAL: ProtocolVersions = ProtocolVersions { min: MIN_PROTOCOL_VERSION, max: PROTOCOL_VERSION };
+
+    /// Protocol string advertised in identify info
+    pub fn identify_string(&self) -> String {
+        format!("{}{}-{}", IDENTIFY_PREFIX, self.min, self.max)
+    }
+
+    /// Parse a peer's identify protocol string. None if the peer is not a
+    /// Tari L2 node.
+    pub fn from_identify(protocol: &str) -> Option<Self> {
+        if protocol == LEGACY_IDENTIFY {
+            return Some(ProtocolVersions { min: 0, max: 0 });
+        }
+        let (min, max) = protocol.strip_prefix(IDENTIFY_PREFIX)?.split_once('-')?;
+        let versions = ProtocolVersions { min: min.parse().ok()?, max: max.parse().ok()? };
+        (versions.min <= versions.max).then_some(versions)
+    }
+
+    /// Highest version both ranges contain
+    pub fn negotiate(&self, remote: &ProtocolVersions) -> Option<u32> {
+        let highest = self.max.min(remote.max);
+        (highest >= self.min.max(remote.min)).then_some(highest)
+    }
+}
+
+/// Where a message is sent
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Recipient {
+    /// Every subscriber of a gossip topic, by its full name
+    Topic(String),
+    /// One node, by L2 key, over the direct protocol
+    Node(PublicKey),
+}
+
+/// A message with the L2 key of the node that sent it
+#[derive(Serialize, Deserialize)]
+struct Signed {
+    sender: PublicKey,
+    signature: Signature,
+    /// When the sender signed it, in seconds since the Unix epoch
+    timestamp: u64,
+    recipient: Recipient,
+    /// The bincode `L2Message`
+    body: Vec<u8>,
+}
+
+/// Bytes `sender` signs for a message at `version`:
+/// `version (u32 LE) || timestamp (u64 LE) || bincode(recipient) || body`
+fn signing_payload(version: u32, timestamp: u64, recipient: &Recipient, body: &[u8]) -> Result<Vec<u8>, bincode::Error> {
+    let recipient = bincode::serialize(recipient)?;
+    let mut payload = Vec::with_capacity(12 + recipient.len() + body.len());
+    payload.extend_from_slice(&version.to_le_bytes());
+    payload.extend_from_slice(&timestamp.to_le_bytes());
+    payload.extend_from_slice(&recipient);
+    payload.extend_from_slice(body);
+    Ok(SigningContext::Message.message(&payload))
+}
+
+/// Why a payload could not be decoded
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Sent at a version outside the range this node speaks
+    Unsupported(u32),
+    /// Not a valid message at its version
+    Malformed(String),
+    /// The signature does not match the claimed sender
+    BadSignature(PublicKey),
+    /// Signed for another topic or node than the one it arrived at
+    Misaddressed(Recipient),
+    /// Signed at this time, too far from the receiver's clock
+    Stale(u64),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Unsupported(version) => write!(
+                f, "protocol version {} is not supported (this node speaks {}-{})",
+                version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+            ),
+            DecodeError::Malformed(e) => write!(f, "malformed message: {}", e),
+            DecodeError::BadSignature(sender) => write!(f, "message claiming to be from {} is not signed by it", sender),
+            DecodeError::Misaddressed(recipient) => write!(f, "message was sent to {:?}, not here", recipient),
+            DecodeError::Stale(timestamp) => write!(
+                f, "message signed at {} is more than {} seconds from this node's clock", timestamp, MAX_CLOCK_SKEW_SECS
+            ),
+        }
+    }
+}
+
+/// Encode `message` at `version` for `recipient`, signed by `signer` at
+/// `timestamp`
+pub fn encode(
+    message: &L2Message,
+    version: u32,
+    recipient: &Recipient,
+    timestamp: u64,
+    signer: &KeyPair,
+) -> Result<Vec<u8>, bincode::Error> {
+    let body = bincode::serialize(message)?;
+    let signature = signer.sign(&signing_payload(version, timestamp, recipient, &body)?);
+    let signed = bincode::serialize(&Signed {
+        sender: signer.public_key(),
+        signature,
+        timestamp,
+        recipient: recipient.clone(),
+        body,
+    })?;
+    let mut data = Vec::with_capacity(ENVELOPE_MAGIC.len() + 4 + signed.len());
+    data.extend_from_slice(&ENVELOPE_MAGIC);
+    data.extend_from_slice(&version.to_le_bytes());
+    data.extend_from_slice(&signed);
+    Ok(data)
+}
+
+/// Decode a payload that arrived at `recipient` and check its signature
+/// and timestamp against `now`, returning the version it was sent at and
+/// the L2 key that sent it
+pub fn decode(data: &[u8], recipient: &Recipient, now: u64) -> Result<(u32, PublicKey, L2Message), DecodeError> {
+    let (version, envelope) = match data.strip_prefix(&ENVELOPE_MAGIC[..]) {
+        Some(rest) if rest.len() >= 4 => {
+            let version = u32::from_le_bytes(rest[..4].try_into().expect("four bytes"));
+            (version, &rest[4..])
+        }
+        Some(_) => return Err(DecodeError::Malformed("truncated envelope".to_string())),
+        // A bare message from a node that predates versioning
+        None => return Err(DecodeError::Unsupported(0)),
+    };
+    if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {
+        return Err(DecodeError::Unsupported(version));
+    }
+    let signed: Signed = bincode::deserialize(envelope).map_err(|e| DecodeError::Malformed(e.to_string()))?;
+    let payload = signing_payload(version, signed.timestamp, &signed.recipient, &signed.body)
+        .map_err(|e| DecodeError::Malformed(e.to_string()))?;
+    if !crypto::verify_signature(&signed.sender, &payload, &signed.signature) {
+        return Err(DecodeError::BadSignature(signed.sender));
+    }
+    if signed.recipient != *recipient {
+        return Err(DecodeError::Misaddressed(signed.recipient));
+    }
+    if signed.timestamp.abs_diff(now) > MAX_CLOCK_SKEW_SECS {
+        return Err(DecodeError::Stale(signed.timestamp));
+    }
+    let message = bincode::deserialize(&signed.body).map_err(|e| DecodeError::Malformed(e.to_string()))?;
+    Ok((version, signed.sender, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_negotiate_and_envelopes_round_trip() {
+        let local = ProtocolVersions::LOCAL;
+        assert_eq!(ProtocolVersions::from_identify(&local.identify_string()), Some(local));
+        let legacy = ProtocolVersions::from_identify("/tari-l2/1.0.0").unwrap();
+        assert_eq!(ProtocolVersions::from_identify("/ipfs/0.1.0"), None);
+
+        // Highest common version; nodes that cannot sign messages have none
+        assert_eq!(local.negotiate(&ProtocolVersions { min: 1, max: 5 }), Some(PROTOCOL_VERSION));
+        assert_eq!(local.negotiate(&legacy), None);
+        assert_eq!(local.negotiate(&ProtocolVersions { min: 0, max: 1 }), None);
+        assert_eq!(local.negotiate(&ProtocolVersions { min: 1, max: 2 }), None);
+        assert_eq!(local.negotiate(&ProtocolVersions { min: PROTOCOL_VERSION + 1, max: 9 }), None);
+
+        let signer = KeyPair::generate();
+        let message = L2Message::Ping;
+        let topic = Recipient::Topic("tari-l2/testnet/default/general".to_string());
+        let now = 1_700_000_000;
+        let data = encode(&message, PROTOCOL_VERSION, &topic, now, &signer).unwrap();
+        let (version, sender, decoded) = decode(&data, &topic, now).unwrap();
+        assert_eq!((version, sender), (PROTOCOL_VERSION, signer.public_key()));
+        assert!(matches!(decoded, L2Message::Ping));
+
+        // Unsigned messages from older nodes are refused
+        assert_eq!(decode(&bincode::serialize(&message).unwrap(), &topic, now).unwrap_err(), DecodeError::Unsupported(0));
+
+        // So is a message altered after signing
+        let mut altered = data.clone();
+        *altered.last_mut().unwrap() ^= 1;
+        assert!(decode(&altered, &topic, now).is_err());
+
+        // A newer envelope is reported, not misparsed
+        let future = encode(&message, PROTOCOL_VERSION + 1, &topic, now, &signer).unwrap();
+        assert_eq!(decode(&future, &topic, now).unwrap_err(), DecodeError::Unsupported(PROTOCOL_VERSION + 1));
+    }
+
+    #[test]
+    fn test_envelopes_are_bound_to_recipient_and_time() {
+        let signer = KeyPair::generate();
+        let node = Recipient::Node(KeyPair::generate().public_key());
+        let now = 1_700_000_000;
+        let data = encode(&L2Message::Ping, PROTOCOL_VERSION, &node, now, &signer).unwrap();
+
+        // Replayed to another node or onto a topic
+        let other = Recipient::Node(signer.public_key());
+        assert_eq!(decode(&data, &other, now).unwrap_err(), DecodeError::Misaddressed(node.clone()));
+        let topic = Recipient::Topic("tari-l2/testnet/default/state-updates".to_string());
+        assert_eq!(decode(&data, &topic, now).unwrap_err(), DecodeError::Misaddressed(node.clone()));
+
+        // Within the clock window either way, and not beyond it
+        assert!(decode(&data, &node, now + MAX_CLOCK_SKEW_SECS).is_ok());
+        assert!(decode(&data, &node, now - MAX_CLOCK_SKEW_SECS).is_ok());
+        assert_eq!(decode(&data, &node, now + MAX_CLOCK_SKEW_SECS + 1).unwrap_err(), DecodeError::Stale(now));
+
+        // Moving the envelope to another recipient breaks the signature
+        let mut signed: Signed = bincode::deserialize(&data[ENVELOPE_MAGIC.len() + 4..]).unwrap();
+        signed.recipient = other.clone();
+        let mut moved = data[..ENVELOPE_MAGIC.len() + 4].to_vec();
+        moved.extend_from_slice(&bincode::serialize(&signed).unwrap());
+        assert_eq!(decode(&moved, &other, now).unwrap_err(), DecodeError::BadSignature(signer.public_key()));
+    }
+
+    #[test]
+    fn test_variant_tags_keep_their_positions() {
+        // Variants from the first release keep their tags; later ones follow
+        let tag = |message: &L2Message| u32::from_le_bytes(bincode::serialize(message).unwrap()[..4].try_into().unwrap());
+        assert_eq!(tag(&L2Message::ListingsRequest), 7);
+        assert_eq!(tag(&L2Message::Pong), 10);
+        assert_eq!(tag(&L2Message::error(&tari_l2_common::L2Error::Timeout)), 11);
+    }
+}
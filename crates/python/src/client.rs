@@ -0,0 +1,220 @@
+use std::future::Future;
+use std::str::FromStr;
+use std::time::Duration;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pythonize::{depythonize, pythonize};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::runtime::Runtime;
+use tari_l2_client::{ClientConfig, L2Client, Result};
+use tari_l2_client::types::{NewEscrow, NewListing};
+use tari_l2_common::{Hash, PublicKey};
+use tari_l2_state_channel::{OrderStatus, StateUpdate};
+use crate::{Wallet, client_error};
+
+fn parse<T: FromStr>(value: &str, what: &str) -> PyResult<T>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse().map_err(|e| PyValueError::new_err(format!("invalid {}: {}", what, e)))
+}
+
+/// Blocking client for a node's JSON-RPC API. Results come back as plain
+/// dicts and lists; failed calls raise `RpcError` or `L2Error`.
+#[pyclass(module = "tari_l2")]
+pub struct Client {
+    inner: L2Client,
+    runtime: Runtime,
+}
+
+impl Client {
+    /// Run `call` to completion with the GIL released
+    fn block_on<'py, T, F>(&self, py: Python<'py>, call: F) -> PyResult<Bound<'py, PyAny>>
+    where
+        T: Serialize + Send,
+        F: Future<Output = Result<T>> + Send,
+    {
+        let value = py.allow_threads(|| self.runtime.block_on(call)).map_err(client_error)?;
+        Ok(pythonize(py, &value)?)
+    }
+}
+
+#[pymethods]
+impl Client {
+    /// `endpoint` is `host:port` or an `http://` URL. With a `wallet`,
+    /// listings, orders and transfers for its key are signed locally.
+    #[new]
+    #[pyo3(signature = (endpoint, wallet=None, timeout=10.0, max_retries=3))]
+    fn new(endpoint: &str, wallet: Option<PyRef<'_, Wallet>>, timeout: f64, max_retries: u32) -> PyResult<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let config = ClientConfig {
+            timeout: Duration::try_from_secs_f64(timeout).map_err(|e| PyValueError::new_err(e.to_string()))?,
+            max_retries,
+            ..Default::default()
+        };
+        let mut inner = L2Client::new(endpoint).with_config(config);
+        if let Some(wallet) = wallet {
+            inner = inner.with_signer(wallet.keypair());
+        }
+        Ok(Self { inner, runtime })
+    }
+
+    /// Call any RPC method with raw params
+    #[pyo3(signature = (method, params=None))]
+    fn call<'py>(&self, py: Python<'py>, method: &str, params: Option<&Bound<'py, PyAny>>) -> PyResult<Bound<'py, PyAny>> {
+        let params: Value = params.map(depythonize).transpose()?.unwrap_or(Value::Null);
+        self.block_on(py, self.inner.call::<_, Value>(method, params))
+    }
+
+    fn get_node_info<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.block_on(py, self.inner.get_node_info())
+    }
+
+    // ===== Channels =====
+
+    fn list_channels<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.block_on(py, self.inner.list_channels())
+    }
+
+    fn create_channel<'py>(&self, py: Python<'py>, participant1: &str, participant2: &str, collateral: u64) -> PyResult<Bound<'py, PyAny>> {
+        let participant1: PublicKey = parse(participant1, "public key")?;
+        let participant2: PublicKey = parse(participant2, "public key")?;
+        self.block_on(py, self.inner.create_channel(participant1, participant2, collateral))
+    }
+
+    fn get_channel_info<'py>(&self, py: Python<'py>, channel_id: &str) -> PyResult<Bound<'py, PyAny>> {
+        let channel_id: Hash = parse(channel_id, "channel id")?;
+        self.block_on(py, self.inner.get_channel_info(&channel_id))
+    }
+
+    fn get_balance<'py>(&self, py: Python<'py>, channel_id: &str, participant: &str) -> PyResult<Bound<'py, PyAny>> {
+        let channel_id: Hash = parse(channel_id, "channel id")?;
+        let participant: PublicKey = parse(participant, "public key")?;
+        self.block_on(py, self.inner.get_balance(&channel_id, &participant))
+    }
+
+    fn transfer<'py>(&self, py: Python<'py>, channel_id: &str, sender: &str, recipient: &str, amount: u64) -> PyResult<Bound<'py, PyAny>> {
+        let channel_id: Hash = parse(channel_id, "channel id")?;
+        let sender: PublicKey = parse(sender, "public key")?;
+        let recipient: PublicKey = parse(recipient, "public key")?;
+        self.block_on(py, self.inner.transfer(&channel_id, sender, recipient, amount))
+    }
+
+    /// Sign `update` as the channel's next state with the wallet and have
+    /// the node co-sign it
+    fn propose_update<'py>(&self, py: Python<'py>, channel_id: &str, update: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        let channel_id: Hash = parse(channel_id, "channel id")?;
+        let update: StateUpdate = depythonize(update)?;
+        self.block_on(py, self.inner.propose_update(&channel_id, update))
+    }
+
+    // ===== Listings and orders =====
+
+    #[pyo3(signature = (title, description, price, category=None, ipfs_hash=None))]
+    fn create_listing<'py>(
+        &self,
+        py: Python<'py>,
+        title: String,
+        description: String,
+        price: u64,
+        category: Option<String>,
+        ipfs_hash: Option<String>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let listing = NewListing { seller: None, title, description, price, ipfs_hash, category, fiat_reference: None, draft: false };
+        self.block_on(py, self.inner.create_listing(listing))
+    }
+
+    fn get_listings<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.block_on(py, self.inner.get_listings())
+    }
+
+    fn get_seller_catalog<'py>(&self, py: Python<'py>, seller: &str) -> PyResult<Bound<'py, PyAny>> {
+        let seller: PublicKey = parse(seller, "public key")?;
+        self.block_on(py, self.inner.get_seller_catalog(seller))
+    }
+
+    fn create_order<'py>(&self, py: Python<'py>, channel_id: &str, listing_id: &str, buyer: &str) -> PyResult<Bound<'py, PyAny>> {
+        let channel_id: Hash = parse(channel_id, "channel id")?;
+        let listing_id: Hash = parse(listing_id, "listing id")?;
+        let buyer: PublicKey = parse(buyer, "public key")?;
+        self.block_on(py, self.inner.create_order(&channel_id, &listing_id, buyer))
+    }
+
+    fn get_orders<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.block_on(py, self.inner.get_orders())
+    }
+
+    fn get_order<'py>(&self, py: Python<'py>, order_id: &str) -> PyResult<Bound<'py, PyAny>> {
+        let order_id: Hash = parse(order_id, "order id")?;
+        self.block_on(py, self.inner.get_order(&order_id))
+    }
+
+    /// `status` is one of `Pending`, `Confirmed`, `Shipping`, `Delivered`,
+    /// `Disputed`, `Completed` or `Cancelled`
+    fn update_order_status<'py>(&self, py: Python<'py>, channel_id: &str, order_id: &str, status: &str) -> PyResult<Bound<'py, PyAny>> {
+        let channel_id: Hash = parse(channel_id, "channel id")?;
+        let order_id: Hash = parse(order_id, "order id")?;
+        let status: OrderStatus = serde_json::from_value(Value::String(status.to_string()))
+            .map_err(|_| PyValueError::new_err(format!("invalid order status: {}", status)))?;
+        self.block_on(py, self.inner.update_order_status(&channel_id, &order_id, status))
+    }
+
+    // ===== Escrow =====
+
+    #[pyo3(signature = (channel_id, order_id, timeout_period=None, ship_within=None))]
+    fn create_escrow<'py>(
+        &self,
+        py: Python<'py>,
+        channel_id: &str,
+        order_id: &str,
+        timeout_period: Option<u64>,
+        ship_within: Option<u64>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let escrow = NewEscrow {
+            channel_id: parse(channel_id, "channel id")?,
+            order_id: parse(order_id, "order id")?,
+            timeout_period,
+            ship_within,
+        };
+        self.block_on(py, self.inner.create_escrow(&escrow))
+    }
+
+    fn fund_escrow<'py>(&self, py: Python<'py>, escrow_id: &str, l1_tx_id: &str) -> PyResult<Bound<'py, PyAny>> {
+        let escrow_id: Hash = parse(escrow_id, "escrow id")?;
+        self.block_on(py, self.inner.fund_escrow(&escrow_id, l1_tx_id))
+    }
+
+    #[pyo3(signature = (escrow_id, tracking_info=None))]
+    fn ship_order<'py>(&self, py: Python<'py>, escrow_id: &str, tracking_info: Option<&str>) -> PyResult<Bound<'py, PyAny>> {
+        let escrow_id: Hash = parse(escrow_id, "escrow id")?;
+        self.block_on(py, self.inner.ship_order(&escrow_id, tracking_info))
+    }
+
+    fn confirm_delivery<'py>(&self, py: Python<'py>, escrow_id: &str) -> PyResult<Bound<'py, PyAny>> {
+        let escrow_id: Hash = parse(escrow_id, "escrow id")?;
+        self.block_on(py, self.inner.confirm_delivery(&escrow_id))
+    }
+
+    fn approve_refund<'py>(&self, py: Python<'py>, escrow_id: &str) -> PyResult<Bound<'py, PyAny>> {
+        let escrow_id: Hash = parse(escrow_id, "escrow id")?;
+        self.block_on(py, self.inner.approve_refund(&escrow_id))
+    }
+
+    fn refund_unshipped<'py>(&self, py: Python<'py>, escrow_id: &str) -> PyResult<Bound<'py, PyAny>> {
+        let escrow_id: Hash = parse(escrow_id, "escrow id")?;
+        self.block_on(py, self.inner.refund_unshipped(&escrow_id))
+    }
+
+    fn get_escrow<'py>(&self, py: Python<'py>, escrow_id: &str) -> PyResult<Bound<'py, PyAny>> {
+        let escrow_id: Hash = parse(escrow_id, "escrow id")?;
+        self.block_on(py, self.inner.get_escrow(&escrow_id))
+    }
+
+    fn list_escrows<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.block_on(py, self.inner.list_escrows())
+    }
+}
@@ -0,0 +1,46 @@
+//! Python bindings for the Tari L2 client and wallet.
+//!
+//! Built with maturin (`maturin develop -m crates/python/Cargo.toml`).
+//! Calls block the calling thread, which suits merchant scripts:
+//!
+//! ```python
+//! import tari_l2
+//!
+//! wallet = tari_l2.Wallet.from_seed_phrase(words)
+//! node = tari_l2.Client("127.0.0.1:18000", wallet=wallet)
+//! for order in node.get_orders():
+//!     if order["status"] == "Confirmed":
+//!         node.ship_order(escrow_id, tracking_info="TRACK123")
+//! ```
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use tari_l2_client::ClientError;
+
+mod client;
+mod wallet;
+
+pub use client::Client;
+pub use wallet::Wallet;
+
+create_exception!(tari_l2, L2Error, PyException, "A call to the node failed");
+create_exception!(tari_l2, RpcError, L2Error, "The node rejected a call. Args are (message, code, category).");
+
+pub(crate) fn client_error(error: ClientError) -> PyErr {
+    match error {
+        ClientError::Rpc { code, message, category } => {
+            RpcError::new_err((message, code, category.map(|c| c.as_str())))
+        }
+        other => L2Error::new_err(other.to_string()),
+    }
+}
+
+#[pymodule]
+fn tari_l2(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Client>()?;
+    m.add_class::<Wallet>()?;
+    m.add("L2Error", m.py().get_type_bound::<L2Error>())?;
+    m.add("RpcError", m.py().get_type_bound::<RpcError>())?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pythonize::{depythonize, pythonize};
+use serde_json::Value;
+use tari_l2_client::signing;
+use tari_l2_common::crypto::KeyPair;
+use tari_l2_state_channel::StateUpdate;
+
+/// Tari wallet whose channel key signs marketplace actions locally
+#[pyclass(module = "tari_l2")]
+pub struct Wallet {
+    wallet: tari_l2_marketplace::Wallet,
+    keypair: Arc<KeyPair>,
+}
+
+impl Wallet {
+    fn from_wallet(wallet: tari_l2_marketplace::Wallet) -> PyResult<Self> {
+        let keypair = wallet.channel_keypair().map_err(PyValueError::new_err)?;
+        Ok(Self { wallet, keypair: Arc::new(keypair) })
+    }
+
+    pub(crate) fn keypair(&self) -> Arc<KeyPair> {
+        self.keypair.clone()
+    }
+}
+
+#[pymethods]
+impl Wallet {
+    /// Generate a new wallet with a 24-word seed phrase
+    #[new]
+    fn new() -> PyResult<Self> {
+        Self::from_wallet(tari_l2_marketplace::Wallet::new())
+    }
+
+    #[staticmethod]
+    fn from_seed_phrase(seed_phrase: &str) -> PyResult<Self> {
+        Self::from_wallet(tari_l2_marketplace::Wallet::from_seed_phrase(seed_phrase).map_err(PyValueError::new_err)?)
+    }
+
+    /// Import a hex-encoded spend key
+    #[staticmethod]
+    fn from_private_key(private_key: &str) -> PyResult<Self> {
+        Self::from_wallet(tari_l2_marketplace::Wallet::from_private_key(private_key).map_err(PyValueError::new_err)?)
+    }
+
+    /// Tari address (emoji form)
+    #[getter]
+    fn address(&self) -> String {
+        self.wallet.address()
+    }
+
+    #[getter]
+    fn seed_phrase(&self) -> Option<String> {
+        self.wallet.seed_phrase().map(|phrase| phrase.to_string())
+    }
+
+    /// Key the node sees as seller, buyer or channel participant
+    #[getter]
+    fn public_key(&self) -> String {
+        self.keypair.public_key().to_string()
+    }
+
+    /// Attach an `authorization` to RPC params for a call to `method`, such
+    /// as `create_listing`, `create_order` or `transfer`
+    fn authorize<'py>(&self, py: Python<'py>, method: &str, params: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        let params: Value = depythonize(params)?;
+        let signed = signing::authorize(method, params, &self.keypair).map_err(crate::client_error)?;
+        Ok(pythonize(py, &signed)?)
+    }
+
+    /// Sign a channel update as state `nonce`, for `submit_state_update`
+    fn sign_update<'py>(&self, py: Python<'py>, update: &Bound<'py, PyAny>, nonce: u64) -> PyResult<Bound<'py, PyAny>> {
+        let update: StateUpdate = depythonize(update)?;
+        Ok(pythonize(py, &signing::sign_update(update, nonce, &self.keypair))?)
+    }
+
+    /// Hex-encoded signature over `message` with the channel key
+    fn sign_message(&self, message: &[u8]) -> String {
+        self.keypair.sign(message).to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Wallet(public_key='{}')", self.public_key())
+    }
+}
@@ -0,0 +1,269 @@
+//! API keys, owner keys and the permission tier each one grants.
+//!
+//! A node with no keys configured serves every method but
+//! [`AUTHENTICATED_METHODS`] to anyone who can reach the port, which the
+//! node config only allows on a loopback address. Once any key is configured, each request must
+//! carry one as `Authorization: Bearer <key>`, or as `?api_key=<key>` on a
+//! WebSocket upgrade. Observer keys may only call [`OBSERVER_METHODS`]:
+//! queries that neither sign with the node key, move funds, nor touch the
+//! wallet, and event subscriptions.
+//!
+//! Instead of a bearer key, a request may be signed by one of the `owners`
+//! public keys, which grants full access. The signature covers the HTTP
+//! method, path and body (see [`rpc_request_payload`]) and travels in the
+//! [`PUBLIC_KEY_HEADER`], [`TIMESTAMP_HEADER`] and [`SIGNATURE_HEADER`]
+//! headers. It is refused once stale or when replayed, like any signed
+//! action. With `public_reads` set, requests carrying neither may still call
+//! [`PUBLIC_METHODS`].
+//!
+//! [`rpc_request_payload`]: tari_l2_marketplace::rpc_request_payload
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::{PublicKey, Signature};
+
+/// Header naming the owner key that signed a request
+pub const PUBLIC_KEY_HEADER: &str = "X-L2-Public-Key";
+
+/// Header with the Unix time the request was signed at
+pub const TIMESTAMP_HEADER: &str = "X-L2-Timestamp";
+
+/// Header with the owner's signature over the request
+pub const SIGNATURE_HEADER: &str = "X-L2-Signature";
+
+/// Methods anyone may call when `public_reads` is on: the public market
+/// and checks of what other nodes signed
+pub const PUBLIC_METHODS: &[&str] = &[
+    "get_node_info",
+    "get_health",
+    "get_listings",
+    "get_seller_catalog",
+    "verify_receipt",
+    "verify_reserve_proof",
+    "subscribe_listings",
+    "unsubscribe",
+];
+
+/// Methods an observer key may call
+pub const OBSERVER_METHODS: &[&str] = &[
+    "get_node_info",
+    "get_l1_status",
+    "get_health",
+    "get_alerts",
+    "get_audit_log",
+    "verify_reserve_proof",
+    "list_channels",
+    "list_peers",
+    "get_channel_info",
+    "replay_channel",
+    "verify_checkpoint",
+    "list_channel_requests",
+    "get_balance",
+    "get_spending_limits",
+    "list_awaiting_approval",
+    "get_listings",
+    "list_drafts",
+    "get_seller_catalog",
+    "get_orders",
+    "get_order",
+    "get_receipt",
+    "list_receipts",
+    "get_purchase_history",
+    "verify_receipt",
+    "get_escrow",
+    "list_escrows",
+    "list_contacts",
+    "get_contact",
+    "list_invoices",
+    "get_payout_preference",
+    "list_saved_searches",
+    "get_saved_search_results",
+    "subscribe_listings",
+    "subscribe_orders",
+    "subscribe_channel_events",
+    "subscribe_saved_searches",
+    "unsubscribe",
+];
+
+/// Methods that hand out secrets, use the unlocked wallet's key, decide
+/// what the node signs with its channel key, move funds, redirect payouts or
+/// have the node POST to a URL, refused unless the request carries an admin
+/// key or an owner signature
+pub const AUTHENTICATED_METHODS: &[&str] = &[
+    "wallet_create",
+    "wallet_import_seed",
+    "wallet_import_key",
+    "wallet_export",
+    "wallet_unlock",
+    "wallet_lock",
+    "wallet_sign",
+    "submit_state_update",
+    "transfer",
+    "transfer_in_channel",
+    "create_channel",
+    "approve_channel_request",
+    "close_channel",
+    "force_close_channel",
+    "pay_invoice",
+    "set_payout_preference",
+    "set_escrow_payout_address",
+    "set_spending_limits",
+    "approve_update",
+    "decline_update",
+    "save_search",
+];
+
+/// What a request's key allows it to do
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessLevel {
+    /// Every method
+    Admin,
+    /// No keys are configured: every method but [`AUTHENTICATED_METHODS`]
+    Open,
+    /// Read-only queries, see [`OBSERVER_METHODS`]
+    Observer,
+    /// Requests without a key, see [`PUBLIC_METHODS`]
+    Public,
+}
+
+impl AccessLevel {
+    pub fn permits(&self, method: &str) -> bool {
+        match self {
+            AccessLevel::Admin => true,
+            AccessLevel::Open => !AUTHENTICATED_METHODS.contains(&method),
+            AccessLevel::Observer => OBSERVER_METHODS.contains(&method),
+            AccessLevel::Public => PUBLIC_METHODS.contains(&method),
+        }
+    }
+}
+
+/// Keys accepted by the RPC server
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiKeys {
+    /// Keys with full access
+    pub admin: Vec<String>,
+
+    /// Keys limited to read-only queries
+    pub observer: Vec<String>,
+
+    /// Public keys whose signed requests get full access
+    pub owners: Vec<PublicKey>,
+
+    /// Let requests without a key call [`PUBLIC_METHODS`]
+    pub public_reads: bool,
+}
+
+impl ApiKeys {
+    /// Whether requests must present a key at all
+    pub fn required(&self) -> bool {
+        !self.admin.is_empty() || !self.observer.is_empty() || !self.owners.is_empty() || self.public_reads
+    }
+
+    pub fn is_owner(&self, public_key: &PublicKey) -> bool {
+        self.owners.contains(public_key)
+    }
+
+    /// Access granted to a request carrying `key`, or None to reject it
+    pub fn level(&self, key: Option<&str>) -> Option<AccessLevel> {
+        if !self.required() {
+            return Some(AccessLevel::Open);
+        }
+        let Some(key) = key else {
+            return self.public_reads.then_some(AccessLevel::Public);
+        };
+        if self.admin.iter().any(|k| keys_equal(k, key)) {
+            Some(AccessLevel::Admin)
+        } else if self.observer.iter().any(|k| keys_equal(k, key)) {
+            Some(AccessLevel::Observer)
+        } else {
+            None
+        }
+    }
+}
+
+/// Compare without exiting at the first differing byte, so response timing
+/// does not reveal how much of a guessed key was right
+pub(crate) fn keys_equal(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Key from an `Authorization: Bearer <key>` header value
+pub fn bearer_key(header: &str) -> Option<&str> {
+    header.strip_prefix("Bearer ").map(str::trim).filter(|k| !k.is_empty())
+}
+
+/// Key from an `api_key=<key>` query parameter, for browser WebSockets,
+/// which cannot set headers
+pub fn query_key(query: &str) -> Option<&str> {
+    query.split('&').find_map(|pair| pair.strip_prefix("api_key=")).filter(|k| !k.is_empty())
+}
+
+/// An owner's signature over one request, read from its headers
+#[derive(Clone, Debug)]
+pub struct RequestSignature {
+    pub public_key: PublicKey,
+    pub timestamp: u64,
+    pub signature: Signature,
+}
+
+impl RequestSignature {
+    /// Parse the three signature headers from their values, or None when the
+    /// request carries none of them
+    pub fn from_headers(
+        public_key: Option<&str>,
+        timestamp: Option<&str>,
+        signature: Option<&str>,
+    ) -> Option<Result<Self, String>> {
+        if public_key.is_none() && timestamp.is_none() && signature.is_none() {
+            return None;
+        }
+        let (Some(public_key), Some(timestamp), Some(signature)) = (public_key, timestamp, signature) else {
+            return Some(Err(format!(
+                "Signed requests need all of {}, {} and {}",
+                PUBLIC_KEY_HEADER, TIMESTAMP_HEADER, SIGNATURE_HEADER
+            )));
+        };
+        Some(Self::parse(public_key, timestamp, signature))
+    }
+
+    fn parse(public_key: &str, timestamp: &str, signature: &str) -> Result<Self, String> {
+        Ok(Self {
+            public_key: public_key.trim().parse().map_err(|_| format!("Invalid {}", PUBLIC_KEY_HEADER))?,
+            timestamp: timestamp.trim().parse().map_err(|_| format!("Invalid {}", TIMESTAMP_HEADER))?,
+            signature: signature.trim().parse().map_err(|_| format!("Invalid {}", SIGNATURE_HEADER))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyless_requests_get_public_reads_only_when_enabled() {
+        let mut keys = ApiKeys { admin: vec!["admin-key".to_string()], ..ApiKeys::default() };
+        assert_eq!(keys.level(None), None);
+        assert_eq!(keys.level(Some("admin-key")), Some(AccessLevel::Admin));
+
+        keys.public_reads = true;
+        let public = keys.level(None).unwrap();
+        assert_eq!(public, AccessLevel::Public);
+        assert!(public.permits("get_listings"));
+        assert!(!public.permits("get_balance"));
+        assert!(!public.permits("wallet_sign"));
+        // A wrong key is refused rather than downgraded
+        assert_eq!(keys.level(Some("guess")), None);
+
+        assert!(PUBLIC_METHODS.iter().all(|m| AccessLevel::Observer.permits(m)));
+        assert_eq!(ApiKeys::default().level(None), Some(AccessLevel::Open));
+        assert!(!AccessLevel::Open.permits("wallet_export"));
+        assert!(!AccessLevel::Open.permits("wallet_unlock"));
+        assert!(!AccessLevel::Open.permits("wallet_create"));
+        assert!(!AccessLevel::Open.permits("wallet_sign"));
+        assert!(!AccessLevel::Open.permits("submit_state_update"));
+        assert!(!AccessLevel::Open.permits("force_close_channel"));
+        assert!(!AccessLevel::Open.permits("set_payout_preference"));
+        assert!(RequestSignature::from_headers(None, None, None).is_none());
+        assert!(RequestSignature::from_headers(Some("00"), None, None).unwrap().is_err());
+    }
+}
@@ -1,10 +1,24 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tari_l2_common::{Hash, PublicKey};
-use tari_l2_marketplace::MarketplaceManager;
+use std::time::Duration;
+use tari_l2_common::{Amount, Hash, L2Error, PublicKey, Signature, Timestamp};
+use tari_l2_common::address::{encode_address, AddressNetwork};
+use tari_l2_common::crypto::SigningContext;
+use tari_l2_marketplace::catalog::listing_content_hash;
+use tari_l2_marketplace::invoice::DEFAULT_EXPIRY_SECS;
+use tari_l2_marketplace::{Alert, Contact, EscrowContract, EscrowStatus, HistoryQuery, Invoice, MarketplaceEvent, MarketplaceManager, OrderView, PayoutMethod, PayoutPreference, PurchaseRecord, Receipt, ReserveProof, ReserveSource, SavedSearch, SearchQuery, SellerCatalog, SignedAction, SpendingLimits, WatchBlob, rpc_call_payload};
+use tari_l2_state_channel::lifecycle::Role;
+use tari_l2_state_channel::state::{FiatReference, Listing};
+use tari_l2_state_channel::update::StateUpdate;
 use tari_l2_l1_client::TariL1Client;
-use tracing::info;
+use tari_l2_p2p::Capability;
+use tracing::{field, info, info_span, warn, Instrument, Span};
+use crate::session::{self, WalletSession};
+
+/// Most audit log entries returned by one `get_audit_log` call
+const MAX_AUDIT_ENTRIES: usize = 500;
 
 /// JSON-RPC request
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,10 +38,234 @@ pub struct JsonRpcResponse {
     pub id: Value,
 }
 
+impl JsonRpcResponse {
+    pub fn from_result(result: Result<Value, JsonRpcError>, id: Value) -> Self {
+        match result {
+            Ok(value) => Self { jsonrpc: "2.0".to_string(), result: Some(value), error: None, id },
+            Err(e) => Self { jsonrpc: "2.0".to_string(), result: None, error: Some(e), id },
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JsonRpcError {
     pub code: i32,
     pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl JsonRpcError {
+    pub const METHOD_NOT_FOUND: i32 = -32601;
+    pub const INVALID_PARAMS: i32 = -32602;
+    pub const INTERNAL_ERROR: i32 = -32603;
+    /// Generic server error for failures without a more specific code
+    pub const SERVER_ERROR: i32 = -32000;
+    /// The request's API key does not permit the method
+    pub const FORBIDDEN: i32 = -32050;
+
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), data: None }
+    }
+
+    pub fn method_not_found(method: &str) -> Self {
+        Self::new(Self::METHOD_NOT_FOUND, format!("Unknown method: {}", method))
+    }
+
+    pub fn invalid_params(e: impl std::fmt::Display) -> Self {
+        Self::new(Self::INVALID_PARAMS, e.to_string())
+    }
+
+    pub fn internal(e: impl std::fmt::Display) -> Self {
+        Self::new(Self::INTERNAL_ERROR, e.to_string())
+    }
+
+    pub fn forbidden(method: &str) -> Self {
+        Self::new(Self::FORBIDDEN, format!("API key does not permit {}", method))
+    }
+}
+
+impl From<L2Error> for JsonRpcError {
+    fn from(e: L2Error) -> Self {
+        Self {
+            code: e.code(),
+            message: e.to_string(),
+            data: Some(serde_json::json!({ "category": e.category().as_str() })),
+        }
+    }
+}
+
+impl From<String> for JsonRpcError {
+    fn from(message: String) -> Self {
+        Self::new(Self::SERVER_ERROR, message)
+    }
+}
+
+impl From<&str> for JsonRpcError {
+    fn from(message: &str) -> Self {
+        Self::new(Self::SERVER_ERROR, message)
+    }
+}
+
+type RpcResult = Result<Value, JsonRpcError>;
+
+/// Detached signature a client may attach to mutating calls under
+/// `authorization`, covering the method name and the rest of the params
+#[derive(Deserialize)]
+struct Authorization {
+    public_key: PublicKey,
+    signature: Signature,
+    timestamp: u64,
+}
+
+/// Remove the `authorization` field from `params`, returning it as a
+/// `SignedAction` over `method` and the remaining params
+fn take_authorization(method: &str, params: &mut Value) -> Result<Option<SignedAction<Value>>, JsonRpcError> {
+    let Some(raw) = params.as_object_mut().and_then(|p| p.remove("authorization")) else {
+        return Ok(None);
+    };
+    let auth: Authorization = serde_json::from_value(raw).map_err(JsonRpcError::invalid_params)?;
+
+    Ok(Some(SignedAction {
+        payload: rpc_call_payload(method, params),
+        public_key: auth.public_key,
+        signature: auth.signature,
+        timestamp: auth.timestamp,
+    }))
+}
+
+/// Span covering one request, tagged with the channel, order and escrow its
+/// params name
+fn request_span(request: &JsonRpcRequest) -> Span {
+    let span = info_span!(
+        "rpc",
+        method = %request.method,
+        channel_id = field::Empty,
+        order_id = field::Empty,
+        escrow_id = field::Empty,
+    );
+    for name in ["channel_id", "order_id", "escrow_id"] {
+        if let Some(id) = request.params.as_ref().and_then(|p| p.get(name)).and_then(Value::as_str) {
+            span.record(name, id);
+        }
+    }
+    span
+}
+
+fn escrow_json(escrow: &EscrowContract) -> Value {
+    serde_json::json!({
+        "id": escrow.id,
+        "listing_id": escrow.listing_id,
+        "order_id": escrow.order_id,
+        "channel_id": escrow.channel_id,
+        "buyer": escrow.buyer,
+        "seller": escrow.seller,
+        "amount": escrow.amount.value(),
+        "status": format!("{:?}", escrow.status),
+        "created_at": escrow.created_at.as_secs(),
+        "updated_at": escrow.updated_at.as_secs(),
+        "timeout_period": escrow.timeout_period,
+        "l1_tx_id": escrow.l1_tx_id,
+        "funded_in_channel": escrow.funded_in_channel,
+        "ship_within": escrow.ship_within,
+        "ship_by": escrow.ship_by().map(|t| t.as_secs()),
+        "tracking_info": escrow.tracking_info,
+        "dispute_reason": escrow.dispute_reason,
+        "seller_address": escrow.seller_address,
+        "buyer_address": escrow.buyer_address,
+        "payout_tx_id": escrow.payout_tx_id
+    })
+}
+
+/// An order's fields plus the escrow securing it (null when there is none)
+fn order_view_json(view: &OrderView) -> Value {
+    let order = &view.order;
+    serde_json::json!({
+        "id": order.id,
+        "channel_id": view.channel_id,
+        "listing_id": order.listing_id,
+        "buyer": order.buyer,
+        "seller": order.seller,
+        "amount": order.amount.value(),
+        "status": format!("{:?}", order.status),
+        "escrow": view.escrow.as_ref().map(escrow_json)
+    })
+}
+
+/// An order a buyer placed with its escrow and receipt (null when there is none)
+fn purchase_json(record: &PurchaseRecord) -> Value {
+    let order = &record.order;
+    serde_json::json!({
+        "id": order.id,
+        "channel_id": record.channel_id,
+        "listing_id": order.listing_id,
+        "buyer": order.buyer,
+        "seller": order.seller,
+        "amount": order.amount.value(),
+        "status": format!("{:?}", order.status),
+        "placed_at": record.placed_at,
+        "settled_at": record.settled_at,
+        "escrow": record.escrow.as_ref().map(escrow_json),
+        "receipt": record.receipt
+    })
+}
+
+/// A seller's storefront: profile, reputation, active listings and recent reviews
+fn seller_catalog_json(catalog: &SellerCatalog) -> Value {
+    serde_json::json!({
+        "seller": catalog.seller,
+        "profile": catalog.profile,
+        "reputation": catalog.reputation,
+        "listings": catalog.listings.iter().map(listing_json).collect::<Vec<_>>(),
+        "recent_reviews": catalog.recent_reviews,
+        "integrity": catalog.integrity
+    })
+}
+
+/// A reserve proof with its nonce hex-encoded, as accepted by `verify_reserve_proof`
+fn reserve_proof_json(proof: &ReserveProof) -> Value {
+    serde_json::json!({
+        "public_key": proof.public_key,
+        "source": proof.source,
+        "amount": proof.amount,
+        "block_height": proof.block_height,
+        "nonce": hex::encode(&proof.nonce),
+        "timestamp": proof.timestamp,
+        "signature": proof.signature
+    })
+}
+
+fn listing_json(listing: &Listing) -> Value {
+    serde_json::json!({
+        "id": listing.id,
+        "seller": listing.seller,
+        "title": listing.title,
+        "description": listing.description,
+        "price": listing.price.value(),
+        "ipfs_hash": listing.ipfs_hash,
+        "active": listing.active,
+        "category": listing.category,
+        "fiat_reference": listing.fiat_reference,
+        "content_hash": listing_content_hash(listing).ok()
+    })
+}
+
+/// A contact from `{label, public_key, tari_address?}` params
+fn contact_from_params(params: Option<Value>) -> Result<Contact, JsonRpcError> {
+    #[derive(Deserialize)]
+    struct Params {
+        label: String,
+        public_key: PublicKey,
+        #[serde(default)]
+        tari_address: Option<String>,
+    }
+
+    let params: Params = serde_json::from_value(
+        params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+    ).map_err(JsonRpcError::invalid_params)?;
+
+    Contact::new(&params.label, params.public_key, params.tari_address, Timestamp::now())
+        .map_err(JsonRpcError::invalid_params)
 }
 
 /// RPC API implementation
@@ -35,144 +273,406 @@ pub struct RpcApi {
     marketplace: Arc<MarketplaceManager>,
     l1_client: Arc<TariL1Client>,
     l1_connected: Arc<std::sync::atomic::AtomicBool>,
+    /// Encrypted wallet that `wallet_unlock` opens
+    keystore: PathBuf,
+    wallet_session: WalletSession,
 }
 
 impl RpcApi {
     pub fn new(marketplace: Arc<MarketplaceManager>, l1_client: Arc<TariL1Client>) -> Self {
+        Self::new_with_l1(marketplace, l1_client, Arc::new(std::sync::atomic::AtomicBool::new(false)))
+    }
+
+    pub fn new_with_l1(marketplace: Arc<MarketplaceManager>, l1_client: Arc<TariL1Client>, l1_connected: Arc<std::sync::atomic::AtomicBool>) -> Self {
         Self {
             marketplace,
             l1_client,
-            l1_connected: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            l1_connected,
+            keystore: session::keystore_path(Path::new("./data")),
+            wallet_session: WalletSession::default(),
         }
     }
 
-    pub fn new_with_l1(marketplace: Arc<MarketplaceManager>, l1_client: Arc<TariL1Client>, l1_connected: Arc<std::sync::atomic::AtomicBool>) -> Self {
-        Self { marketplace, l1_client, l1_connected }
+    /// Keep the wallet keystore under `data_dir`, and lock an unlocked
+    /// wallet after `idle_timeout` without use
+    pub fn with_wallet_session(mut self, data_dir: &Path, idle_timeout: Duration) -> Self {
+        self.keystore = session::keystore_path(data_dir);
+        self.wallet_session = WalletSession::new(idle_timeout);
+        self
+    }
+
+    /// Lock the wallet if its session has gone idle
+    pub async fn expire_wallet_session(&self) {
+        if self.wallet_session.expire().await {
+            info!("Wallet locked after its idle timeout");
+        }
+    }
+
+    /// Check an attached authorization was signed by `signer` and has not
+    /// been used before. Calls without one are treated as coming from the
+    /// node operator.
+    async fn check_authorization(&self, authorization: Option<&SignedAction<Value>>, signer: &PublicKey) -> Result<(), JsonRpcError> {
+        let Some(action) = authorization else {
+            return Ok(());
+        };
+        if action.signer() != signer {
+            return Err(JsonRpcError::invalid_params("Authorization is not signed by the acting key"));
+        }
+        match self.marketplace.accept_action(action).await {
+            Err(L2Error::InvalidParameter(e)) => Err(JsonRpcError::invalid_params(e)),
+            other => Ok(other?),
+        }
+    }
+
+    /// Refuse moving an escrow to `status` unless the acting key holds a role
+    /// the escrow transition table allows the move for. The actor is the
+    /// authorization's signer, or the node without one, as for
+    /// `update_order_status`.
+    async fn check_escrow_role(
+        &self,
+        escrow_id: &Hash,
+        status: EscrowStatus,
+        authorization: Option<&SignedAction<Value>>,
+    ) -> Result<(), JsonRpcError> {
+        let escrow = self.marketplace.get_escrow(escrow_id).await?;
+        let actor = match authorization {
+            Some(action) => *action.signer(),
+            None => self.marketplace.public_key(),
+        };
+        let mut roles = escrow.roles_of(&actor);
+        if self.marketplace.is_arbitrator(&actor).await {
+            roles.push(Role::Arbitrator);
+        }
+        escrow.check_transition(&roles, &status).map_err(L2Error::from)?;
+        self.check_authorization(authorization, &actor).await
+    }
+
+    /// Check an owner's signature over an HTTP request and record it, so the
+    /// same signed request cannot be sent again
+    pub async fn accept_request_signature(&self, action: &SignedAction<Vec<u8>>) -> Result<(), String> {
+        self.marketplace.accept_action(action).await.map_err(|e| e.to_string())
+    }
+
+    /// Receive operator alerts as they are raised
+    pub fn subscribe_alerts(&self) -> tokio::sync::broadcast::Receiver<Alert> {
+        self.marketplace.alerts().subscribe()
+    }
+
+    /// Receive listing, order and channel events as they happen
+    pub fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<MarketplaceEvent> {
+        self.marketplace.events().subscribe()
     }
 
     /// Handle a JSON-RPC request
     pub async fn handle_request(&self, request: JsonRpcRequest) -> JsonRpcResponse {
+        let span = request_span(&request);
+        self.dispatch(request).instrument(span).await
+    }
+
+    async fn dispatch(&self, request: JsonRpcRequest) -> JsonRpcResponse {
         info!("RPC request: {}", request.method);
 
         let result = match request.method.as_str() {
             "get_node_info" => self.get_node_info().await,
             "get_l1_status" => self.get_l1_status().await,
+            "get_health" => self.get_health().await,
+            "get_alerts" => self.get_alerts(request.params).await,
+            "get_audit_log" => self.get_audit_log(request.params).await,
+            "attest_identity" => self.attest_identity(request.params).await,
+            "prove_reserves" => self.prove_reserves(request.params).await,
+            "verify_reserve_proof" => self.verify_reserve_proof(request.params).await,
             "list_channels" => self.list_channels().await,
+            "list_peers" => self.list_peers(request.params).await,
             "create_channel" => self.create_channel(request.params).await,
             "get_channel_info" => self.get_channel_info(request.params).await,
+            "replay_channel" => self.replay_channel(request.params).await,
+            "verify_checkpoint" => self.verify_checkpoint(request.params).await,
+            "activate_channel" => self.activate_channel(request.params).await,
+            "submit_state_update" => self.submit_state_update(request.params).await,
             "transfer_in_channel" => self.transfer_in_channel(request.params).await,
             "close_channel" => self.close_channel(request.params).await,
+            "force_close_channel" => self.force_close_channel(request.params).await,
+            "set_spending_limits" => self.set_spending_limits(request.params).await,
+            "get_spending_limits" => self.get_spending_limits(request.params).await,
+            "list_awaiting_approval" => self.list_awaiting_approval(request.params).await,
+            "approve_update" => self.approve_update(request.params).await,
+            "decline_update" => self.decline_update(request.params).await,
+            "export_watch_blob" => self.export_watch_blob(request.params).await,
+            "submit_watch_blob" => self.submit_watch_blob(request.params).await,
+            "report_channel_close" => self.report_channel_close(request.params).await,
+            "list_channel_requests" => self.list_channel_requests().await,
+            "approve_channel_request" => self.approve_channel_request(request.params).await,
+            "reject_channel_request" => self.reject_channel_request(request.params).await,
             "get_balance" => self.get_balance(request.params).await,
             "create_listing" => self.create_listing(request.params).await,
-            "get_listings" => self.get_listings().await,
+            "get_listings" => self.get_listings(request.params).await,
+            "update_listing_price" => self.update_listing_price(request.params).await,
+            "list_drafts" => self.list_drafts().await,
+            "publish_listing" => self.publish_listing(request.params).await,
+            "unpublish_listing" => self.unpublish_listing(request.params).await,
+            "get_seller_catalog" => self.get_seller_catalog(request.params).await,
+            "add_review" => self.add_review(request.params).await,
             "create_order" => self.create_order(request.params).await,
             "get_orders" => self.get_orders().await,
+            "get_order" => self.get_order(request.params).await,
             "update_order_status" => self.update_order_status(request.params).await,
+            "get_receipt" => self.get_receipt(request.params).await,
+            "list_receipts" => self.list_receipts().await,
+            "get_purchase_history" => self.get_purchase_history(request.params).await,
+            "verify_receipt" => self.verify_receipt(request.params).await,
             "transfer" => self.transfer(request.params).await,
+            // Invoices
+            "create_invoice" => self.create_invoice(request.params).await,
+            "list_invoices" => self.list_invoices().await,
+            "pay_invoice" => self.pay_invoice(request.params).await,
+            // Address book
+            "list_contacts" => self.list_contacts().await,
+            "get_contact" => self.get_contact(request.params).await,
+            "add_contact" => self.add_contact(request.params).await,
+            "update_contact" => self.update_contact(request.params).await,
+            "remove_contact" => self.remove_contact(request.params).await,
+            // Saved searches
+            "save_search" => self.save_search(request.params).await,
+            "list_saved_searches" => self.list_saved_searches().await,
+            "get_saved_search_results" => self.get_saved_search_results(request.params).await,
+            "delete_saved_search" => self.delete_saved_search(request.params).await,
+            // Payout preferences
+            "set_payout_preference" => self.set_payout_preference(request.params).await,
+            "get_payout_preference" => self.get_payout_preference(request.params).await,
             // Escrow operations
             "create_escrow" => self.create_escrow(request.params).await,
             "fund_escrow" => self.fund_escrow(request.params).await,
+            "fund_escrow_from_channel" => self.fund_escrow_from_channel(request.params).await,
             "ship_order" => self.ship_order(request.params).await,
+            "set_escrow_payout_address" => self.set_escrow_payout_address(request.params).await,
             "confirm_delivery" => self.confirm_delivery(request.params).await,
             "request_refund" => self.request_refund(request.params).await,
             "approve_refund" => self.approve_refund(request.params).await,
             "raise_dispute" => self.raise_dispute(request.params).await,
+            "refund_unshipped" => self.refund_unshipped(request.params).await,
             "get_escrow" => self.get_escrow(request.params).await,
             "list_escrows" => self.list_escrows().await,
             // Wallet operations
-            "wallet_create" => self.wallet_create().await,
+            "wallet_create" => self.wallet_create(request.params).await,
             "wallet_import_seed" => self.wallet_import_seed(request.params).await,
             "wallet_import_key" => self.wallet_import_key(request.params).await,
+            "wallet_unlock" => self.wallet_unlock(request.params).await,
+            "wallet_lock" => self.wallet_lock(request.params).await,
             "wallet_export" => self.wallet_export(request.params).await,
             "wallet_sign" => self.wallet_sign(request.params).await,
             "get_l1_balance" => self.get_l1_balance(request.params).await,
-            _ => Err(format!("Unknown method: {}", request.method)),
+            _ => Err(JsonRpcError::method_not_found(&request.method)),
         };
 
-        match result {
-            Ok(value) => JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                result: Some(value),
-                error: None,
-                id: request.id,
-            },
-            Err(e) => JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                result: None,
-                error: Some(JsonRpcError {
-                    code: -32000,
-                    message: e,
-                }),
-                id: request.id,
-            },
-        }
-    }
-
-    async fn list_channels(&self) -> Result<Value, String> {
+        JsonRpcResponse::from_result(result, request.id)
+    }
+
+    async fn list_channels(&self) -> RpcResult {
         let channels = self.marketplace.list_channels().await;
-        serde_json::to_value(channels).map_err(|e| e.to_string())
+        serde_json::to_value(channels).map_err(JsonRpcError::internal)
+    }
+
+    /// Connected peers and what each advertises, optionally only those
+    /// offering `{capability}`
+    async fn list_peers(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize, Default)]
+        struct Params {
+            #[serde(default)]
+            capability: Option<Capability>,
+        }
+
+        let params: Params = match params {
+            Some(Value::Null) | None => Params::default(),
+            Some(params) => serde_json::from_value(params).map_err(JsonRpcError::invalid_params)?,
+        };
+
+        let (local_id, connected) = self.marketplace.network_peers().await;
+        let table = self.marketplace.peer_table(params.capability).await;
+        let peers = match params.capability {
+            Some(_) => table.iter().filter_map(|peer| peer.public_key).collect(),
+            None => connected,
+        };
+        Ok(serde_json::json!({
+            "local_id": local_id,
+            "peers": peers,
+            "table": table
+        }))
     }
 
-    async fn get_channel_info(&self, params: Option<Value>) -> Result<Value, String> {
+    async fn get_channel_info(&self, params: Option<Value>) -> RpcResult {
         #[derive(Deserialize)]
         struct Params {
-            channel_id: String,
+            channel_id: Hash,
         }
 
         let params: Params = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
 
-        let channel_id_bytes = hex::decode(&params.channel_id)
-            .map_err(|e| e.to_string())?;
-        let channel_id = Hash::from_slice(&channel_id_bytes)
-            .map_err(|e| e.to_string())?;
+        let channel_id = params.channel_id;
 
-        let info = self.marketplace.get_channel_info(&channel_id)
-            .await
-            .map_err(|e| e.to_string())?;
+        let info = self.marketplace.get_channel_info(&channel_id).await?;
 
-        serde_json::to_value(info).map_err(|e| e.to_string())
+        serde_json::to_value(info).map_err(JsonRpcError::internal)
     }
 
-    async fn get_balance(&self, params: Option<Value>) -> Result<Value, String> {
+    async fn replay_channel(&self, params: Option<Value>) -> RpcResult {
         #[derive(Deserialize)]
         struct Params {
-            channel_id: String,
-            participant: String,
+            channel_id: Hash,
         }
 
         let params: Params = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
 
-        let channel_id_bytes = hex::decode(&params.channel_id)
-            .map_err(|e| e.to_string())?;
-        let channel_id = Hash::from_slice(&channel_id_bytes)
-            .map_err(|e| e.to_string())?;
+        let report = self.marketplace.replay_channel(&params.channel_id).await?;
 
-        let participant_bytes = hex::decode(&params.participant)
-            .map_err(|e| e.to_string())?;
-        let participant = PublicKey::from_slice(&participant_bytes)
-            .map_err(|e| e.to_string())?;
+        serde_json::to_value(report).map_err(JsonRpcError::internal)
+    }
 
-        let balance = self.marketplace.get_balance(&channel_id, &participant)
-            .await
-            .map_err(|e| e.to_string())?;
+    async fn verify_checkpoint(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+            state_root: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let anchored = self.marketplace.verify_checkpoint(&params.channel_id, &params.state_root).await?;
+        Ok(serde_json::json!({
+            "channel_id": params.channel_id,
+            "state_root": params.state_root,
+            "anchored": anchored
+        }))
+    }
+
+    async fn get_balance(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+            participant: PublicKey,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let channel_id = params.channel_id;
+        let participant = params.participant;
+
+        let balance = self.marketplace.get_balance(&channel_id, &participant).await?;
 
-        serde_json::to_value(balance.value()).map_err(|e| e.to_string())
+        serde_json::to_value(balance.value()).map_err(JsonRpcError::internal)
     }
 
-    async fn get_node_info(&self) -> Result<Value, String> {
+    async fn get_node_info(&self) -> RpcResult {
         // Return basic node information
+        let public_key = self.marketplace.public_key();
         Ok(serde_json::json!({
-            "public_key": "329e35a4b55ce112e564f72a3d0dde514b7309fa6df45ffd1315e6c921db1bd1",
+            "public_key": public_key,
+            "address": encode_address(AddressNetwork::Esmeralda, &public_key),
             "version": "0.1.0",
             "network": "Esmeralda"
         }))
     }
 
-    async fn get_l1_status(&self) -> Result<Value, String> {
+    async fn attest_identity(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            nonce: String,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let nonce = hex::decode(&params.nonce)
+            .map_err(|e| format!("Invalid nonce hex: {}", e))?;
+
+        let attestation = self.marketplace.attest_identity(nonce).await?;
+
+        Ok(serde_json::json!({
+            "public_key": attestation.public_key,
+            "peer_id": attestation.peer_id,
+            "nonce": params.nonce,
+            "timestamp": attestation.timestamp,
+            "signature": attestation.signature
+        }))
+    }
+
+    async fn prove_reserves(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            nonce: String,
+            channel_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let nonce = hex::decode(&params.nonce)
+            .map_err(|e| format!("Invalid nonce hex: {}", e))?;
+
+        let proof = self.marketplace.prove_reserves(nonce, params.channel_id).await?;
+        Ok(reserve_proof_json(&proof))
+    }
+
+    async fn verify_reserve_proof(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Proof {
+            public_key: PublicKey,
+            source: ReserveSource,
+            amount: u64,
+            block_height: u64,
+            nonce: String,
+            timestamp: u64,
+            signature: Signature,
+        }
+
+        #[derive(Deserialize)]
+        struct Params {
+            proof: Proof,
+            /// Hex challenge the buyer sent to the seller
+            challenge: String,
+            #[serde(default)]
+            min_amount: u64,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let challenge = hex::decode(&params.challenge)
+            .map_err(|e| format!("Invalid challenge hex: {}", e))?;
+        let proof = ReserveProof {
+            public_key: params.proof.public_key,
+            source: params.proof.source,
+            amount: params.proof.amount,
+            block_height: params.proof.block_height,
+            nonce: hex::decode(&params.proof.nonce)
+                .map_err(|e| format!("Invalid nonce hex: {}", e))?,
+            timestamp: params.proof.timestamp,
+            signature: params.proof.signature,
+        };
+
+        let result = self.marketplace
+            .verify_reserve_proof(&proof, &challenge, Amount::new(params.min_amount))
+            .await;
+
+        Ok(serde_json::json!({
+            "valid": result.is_ok(),
+            "public_key": proof.public_key,
+            "amount": proof.amount,
+            "error": result.err().map(|e| e.to_string())
+        }))
+    }
+
+    async fn get_l1_status(&self) -> RpcResult {
         // Return L1 connection status
         let connected = self.l1_connected.load(std::sync::atomic::Ordering::Relaxed);
         Ok(serde_json::json!({
@@ -182,650 +682,1549 @@ impl RpcApi {
         }))
     }
 
-    async fn create_channel(&self, params: Option<Value>) -> Result<Value, String> {
+    /// Overall node health. The node is degraded while its data directory
+    /// is nearly full and refuses new listings and channels.
+    async fn get_health(&self) -> RpcResult {
+        let disk = self.marketplace.disk_status().await;
+        let l1_connected = self.l1_connected.load(std::sync::atomic::Ordering::Relaxed);
+        Ok(serde_json::json!({
+            "status": if disk.is_degraded() { "degraded" } else { "ok" },
+            "l1_connected": l1_connected,
+            "disk": disk,
+        }))
+    }
+
+    /// Recent operator alerts, optionally only those after alert `{since}`
+    async fn get_alerts(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize, Default)]
+        struct Params {
+            #[serde(default)]
+            since: u64,
+        }
+
+        let params: Params = match params {
+            Some(Value::Null) | None => Params::default(),
+            Some(params) => serde_json::from_value(params).map_err(JsonRpcError::invalid_params)?,
+        };
+
+        serde_json::to_value(self.marketplace.alerts().recent(params.since)).map_err(JsonRpcError::internal)
+    }
+
+    /// A range of the audit log, checked against the entry before it
+    async fn get_audit_log(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize, Default)]
+        struct Params {
+            #[serde(default)]
+            from: u64,
+            #[serde(default)]
+            limit: Option<usize>,
+        }
+
+        let params: Params = match params {
+            Some(Value::Null) | None => Params::default(),
+            Some(params) => serde_json::from_value(params).map_err(JsonRpcError::invalid_params)?,
+        };
+        let limit = params.limit.unwrap_or(MAX_AUDIT_ENTRIES).min(MAX_AUDIT_ENTRIES);
+
+        let (entries, broken_at) = self.marketplace.audit_log(params.from, limit)?;
+        let head = self.marketplace.audit_head()?;
+        Ok(serde_json::json!({
+            "entries": entries,
+            "verified": broken_at.is_none(),
+            "broken_at": broken_at,
+            "head_seq": head.as_ref().map(|entry| entry.seq),
+            "head_hash": head.map(|entry| entry.hash),
+        }))
+    }
+
+    async fn create_channel(&self, params: Option<Value>) -> RpcResult {
         use tari_l2_state_channel::ChannelConfig;
         use std::collections::HashMap;
         use tari_l2_common::Amount;
 
         #[derive(serde::Deserialize)]
         struct CreateChannelParams {
+            /// Public key, address or contact label
             participant1: String,
             participant2: String,
             collateral: u64,
+            /// Counterparty's signature over the genesis state, for wallets
+            /// that open a channel with this node directly
+            #[serde(default)]
+            signature: Option<tari_l2_common::Signature>,
         }
 
         let params: CreateChannelParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
-
-        // Parse public keys from hex
-        let pk1_bytes = hex::decode(&params.participant1)
-            .map_err(|e| format!("Invalid participant1 hex: {}", e))?;
-        let pk1 = PublicKey::from_slice(&pk1_bytes)
-            .map_err(|e| format!("Invalid participant1 key: {}", e))?;
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
 
-        let pk2_bytes = hex::decode(&params.participant2)
-            .map_err(|e| format!("Invalid participant2 hex: {}", e))?;
-        let pk2 = PublicKey::from_slice(&pk2_bytes)
-            .map_err(|e| format!("Invalid participant2 key: {}", e))?;
+        let pk1 = self.marketplace.resolve_counterparty(&params.participant1)?;
+        let pk2 = self.marketplace.resolve_counterparty(&params.participant2)?;
 
         // Create channel config
         let mut initial_balances = HashMap::new();
         initial_balances.insert(pk1.clone(), Amount::new(params.collateral / 2));
         initial_balances.insert(pk2.clone(), Amount::new(params.collateral / 2));
 
-        let config = ChannelConfig {
-            participants: vec![pk1.clone(), pk2.clone()],
-            initial_balances,
-            challenge_period: 86400, // 24 hours
+        let config = ChannelConfig {
+            participants: vec![pk1.clone(), pk2.clone()],
+            initial_balances,
+            challenge_period: 86400, // 24 hours
+        };
+
+        // Channels with this node go through the open handshake; the
+        // counterparty either signed already or is asked over P2P
+        let local = self.marketplace.public_key();
+        let counterparty = if pk1 == local { pk2 } else { pk1 };
+        let (channel_id, status) = match params.signature {
+            Some(signature) => {
+                (self.marketplace.open_cosigned_channel(config, counterparty, signature).await?, "created")
+            }
+            None if config.participants.contains(&local) => {
+                (self.marketplace.propose_channel(config).await?, "proposed")
+            }
+            None => (self.marketplace.create_channel(config).await?, "created"),
+        };
+
+        Ok(serde_json::json!({
+            "id": channel_id,
+            "status": status,
+            "participant1": pk1,
+            "participant2": pk2,
+            "collateral": params.collateral
+        }))
+    }
+
+    async fn activate_channel(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        self.marketplace.activate_channel(&params.channel_id).await?;
+
+        Ok(serde_json::json!({
+            "id": params.channel_id,
+            "status": "active"
+        }))
+    }
+
+    /// Co-sign an update the caller has already signed for its side of the
+    /// channel. It is applied once every participant has signed it.
+    async fn submit_state_update(&self, params: Option<Value>) -> RpcResult {
+        use tari_l2_state_channel::update::SignedStateUpdate;
+
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+            update: SignedStateUpdate,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let nonce = params.update.nonce;
+        let committed = self.marketplace.submit_state_update(&params.channel_id, params.update).await?;
+
+        Ok(serde_json::json!({
+            "channel_id": params.channel_id,
+            "nonce": nonce,
+            "status": if committed { "applied" } else { "proposed" }
+        }))
+    }
+
+    /// Older name for `transfer`
+    async fn transfer_in_channel(&self, params: Option<Value>) -> RpcResult {
+        self.transfer(params).await
+    }
+
+    async fn close_channel(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let deadline = self.marketplace.close_channel(&params.channel_id).await?;
+        Ok(serde_json::json!({
+            "status": "closing",
+            "challenge_deadline": deadline.as_secs()
+        }))
+    }
+
+    /// Close without the counterparties by publishing our latest state to L1
+    async fn force_close_channel(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let (tx_id, deadline) = self.marketplace.force_close_channel(&params.channel_id).await?;
+        Ok(serde_json::json!({
+            "status": "challenged",
+            "tx_id": tx_id,
+            "challenge_deadline": deadline.as_secs()
+        }))
+    }
+
+    async fn set_spending_limits(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+            #[serde(flatten)]
+            limits: SpendingLimits,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        self.marketplace.set_spending_limits(&params.channel_id, params.limits).await?;
+        Ok(serde_json::json!({
+            "status": "updated"
+        }))
+    }
+
+    async fn get_spending_limits(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let (limits, spent) = self.marketplace.spending_limits(&params.channel_id).await;
+        Ok(serde_json::json!({
+            "channel_id": params.channel_id,
+            "max_transfer": limits.max_transfer,
+            "max_daily": limits.max_daily,
+            "spent_last_24h": spent
+        }))
+    }
+
+    async fn list_awaiting_approval(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let held = self.marketplace.list_awaiting_approval(&params.channel_id).await;
+        serde_json::to_value(held).map_err(JsonRpcError::internal)
+    }
+
+    async fn approve_update(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+            nonce: u64,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        self.marketplace.approve_update(&params.channel_id, params.nonce).await?;
+        Ok(serde_json::json!({
+            "channel_id": params.channel_id,
+            "nonce": params.nonce,
+            "status": "proposed"
+        }))
+    }
+
+    async fn decline_update(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+            nonce: u64,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        self.marketplace.decline_update(&params.channel_id, params.nonce).await?;
+        Ok(serde_json::json!({
+            "status": "declined"
+        }))
+    }
+
+    async fn export_watch_blob(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let blob = self.marketplace.export_watch_blob(&params.channel_id).await?;
+        serde_json::to_value(blob).map_err(JsonRpcError::internal)
+    }
+
+    async fn submit_watch_blob(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            blob: WatchBlob,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        self.marketplace.accept_watch_blob(params.blob).await?;
+        Ok(serde_json::json!({
+            "status": "held"
+        }))
+    }
+
+    async fn report_channel_close(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let disputes = self.marketplace.check_reported_close(&params.channel_id).await?;
+        Ok(serde_json::json!({
+            "channel_id": params.channel_id,
+            "disputes": disputes
+        }))
+    }
+
+    async fn list_channel_requests(&self) -> RpcResult {
+        let requests = self.marketplace.list_channel_requests().await;
+
+        let requests_json: Vec<_> = requests.iter().map(|request| {
+            serde_json::json!({
+                "channel_id": request.channel_id,
+                "initiator": request.initiator,
+                "participants": request.participants,
+                "collateral": request.collateral().value(),
+                "challenge_period": request.challenge_period,
+                "received_at": request.received_at.as_secs(),
+                "review_reason": request.review_reason
+            })
+        }).collect();
+
+        Ok(serde_json::json!(requests_json))
+    }
+
+    async fn approve_channel_request(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let channel_id = params.channel_id;
+
+        let channel_id = self.marketplace.approve_channel_request(&channel_id).await?;
+
+        Ok(serde_json::json!({
+            "id": channel_id,
+            "status": "created"
+        }))
+    }
+
+    async fn reject_channel_request(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let channel_id = params.channel_id;
+
+        self.marketplace.reject_channel_request(&channel_id).await?;
+
+        Ok(serde_json::json!({
+            "status": "rejected"
+        }))
+    }
+
+    async fn create_listing(&self, params: Option<Value>) -> RpcResult {
+        #[derive(serde::Deserialize)]
+        struct CreateListingParams {
+            seller_pubkey: Option<PublicKey>,
+            title: String,
+            description: String,
+            price: u64,
+            ipfs_hash: Option<String>,
+            category: Option<String>,
+            #[serde(default)]
+            fiat_reference: Option<FiatReference>,
+            /// Save locally without broadcasting
+            #[serde(default)]
+            draft: bool,
+        }
+
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("create_listing", &mut params)?;
+        let params: CreateListingParams = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
+
+        // Use provided seller_pubkey or default to node's own public key
+        let seller = params.seller_pubkey.unwrap_or_else(|| self.marketplace.public_key());
+        self.check_authorization(authorization.as_ref(), &seller).await?;
+
+        // Generate listing ID
+        let listing_id = Hash::random();
+
+        // Create global listing (stored in marketplace, not in a specific channel)
+        let listing = Listing {
+            id: listing_id,
+            seller,
+            title: params.title.clone(),
+            description: params.description,
+            price: tari_l2_common::Amount::new(params.price),
+            ipfs_hash: params.ipfs_hash.unwrap_or_else(|| "QmPending".to_string()),
+            active: true,
+            category: params.category.unwrap_or_else(|| "other".to_string()),
+            fiat_reference: params.fiat_reference,
+        };
+        let status = if params.draft {
+            self.marketplace.create_draft(listing).await?;
+            "draft"
+        } else {
+            self.marketplace.publish_listing(listing).await?;
+            "active"
+        };
+
+        Ok(serde_json::json!({
+            "id": listing_id,
+            "title": params.title,
+            "price": params.price,
+            "seller": seller,
+            "status": status
+        }))
+    }
+
+    /// Each listing includes `fiat_value`, its approximate price in
+    /// `currency` (or else its own reference currency) when a rate is known
+    async fn get_listings(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize, Default)]
+        struct Params {
+            currency: Option<String>,
+        }
+
+        let params: Params = match params {
+            Some(Value::Null) | None => Params::default(),
+            Some(params) => serde_json::from_value(params).map_err(JsonRpcError::invalid_params)?,
+        };
+        let listings = self.marketplace.list_all_listings().await;
+
+        let mut listings_json = Vec::with_capacity(listings.len());
+        for (channel_id, listing) in &listings {
+            let currency = params.currency.clone()
+                .or_else(|| listing.fiat_reference.as_ref().map(|f| f.currency.clone()));
+            let fiat_value = match currency {
+                Some(currency) => self.marketplace.fiat_value(listing.price, &currency).await
+                    .map(|amount| serde_json::json!({ "currency": currency.to_ascii_uppercase(), "amount": amount })),
+                None => None,
+            };
+
+            let mut json = listing_json(listing);
+            json["channel_id"] = serde_json::json!(channel_id);
+            json["fiat_value"] = serde_json::json!(fiat_value);
+            listings_json.push(json);
+        }
+
+        Ok(serde_json::json!(listings_json))
+    }
+
+    /// Existing orders keep the price they were placed at
+    async fn update_listing_price(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            listing_id: Hash,
+            price: u64,
+        }
+
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("update_listing_price", &mut params)?;
+        let params: Params = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
+
+        let seller = self.marketplace.list_all_listings().await
+            .into_iter()
+            .find(|(_, l)| l.id == params.listing_id)
+            .map(|(_, l)| l.seller)
+            .ok_or_else(|| JsonRpcError::invalid_params("Listing not found"))?;
+        self.check_authorization(authorization.as_ref(), &seller).await?;
+
+        let listing = self.marketplace.update_listing_price(&params.listing_id, params.price).await?;
+        Ok(listing_json(&listing))
+    }
+
+    async fn list_drafts(&self) -> RpcResult {
+        let drafts = self.marketplace.list_drafts()?;
+        Ok(Value::Array(drafts.iter().map(listing_json).collect()))
+    }
+
+    /// Sign and broadcast a draft listing
+    async fn publish_listing(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            listing_id: Hash,
+        }
+
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("publish_listing", &mut params)?;
+        let params: Params = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
+
+        let seller = self.marketplace.list_drafts()?
+            .into_iter()
+            .find(|l| l.id == params.listing_id)
+            .map(|l| l.seller)
+            .ok_or_else(|| JsonRpcError::invalid_params("Draft not found"))?;
+        self.check_authorization(authorization.as_ref(), &seller).await?;
+
+        let listing = self.marketplace.publish_draft(&params.listing_id).await?;
+        Ok(listing_json(&listing))
+    }
+
+    /// Withdraw a listing from the network and keep it as a draft
+    async fn unpublish_listing(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            listing_id: Hash,
+        }
+
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("unpublish_listing", &mut params)?;
+        let params: Params = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
+
+        let seller = self.marketplace.list_all_listings().await
+            .into_iter()
+            .find(|(_, l)| l.id == params.listing_id)
+            .map(|(_, l)| l.seller)
+            .ok_or_else(|| JsonRpcError::invalid_params("Listing not found"))?;
+        self.check_authorization(authorization.as_ref(), &seller).await?;
+
+        let listing = self.marketplace.unpublish_listing(&params.listing_id).await?;
+        Ok(listing_json(&listing))
+    }
+
+    async fn get_seller_catalog(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            seller: PublicKey,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let catalog = self.marketplace.get_seller_catalog(&params.seller).await;
+        Ok(seller_catalog_json(&catalog))
+    }
+
+    async fn add_review(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            order_id: Hash,
+            reviewer: PublicKey,
+            rating: u8,
+            #[serde(default)]
+            comment: String,
+        }
+
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("add_review", &mut params)?;
+        let params: Params = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
+        self.check_authorization(authorization.as_ref(), &params.reviewer).await?;
+
+        let review = self.marketplace
+            .add_review(&params.order_id, params.reviewer, params.rating, params.comment)
+            .await?;
+        Ok(serde_json::json!(review))
+    }
+
+    async fn create_order(&self, params: Option<Value>) -> RpcResult {
+        use tari_l2_state_channel::state::{Order, OrderStatus};
+
+        #[derive(serde::Deserialize)]
+        struct CreateOrderParams {
+            channel_id: Hash,
+            listing_id: Hash,
+            buyer: String,
+        }
+
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("create_order", &mut params)?;
+        let params: CreateOrderParams = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
+        let buyer = self.marketplace.resolve_counterparty(&params.buyer)?;
+        self.check_authorization(authorization.as_ref(), &buyer).await?;
+
+        let channel_id = params.channel_id;
+        let listing_id = params.listing_id;
+
+        // Get the listing to find seller and price
+        let listings = self.marketplace.get_channel_listings(&channel_id).await?;
+
+        let listing = listings.iter()
+            .find(|l| l.id == listing_id)
+            .ok_or("Listing not found")?;
+
+        let order_id = Hash::random();
+        let order = Order {
+            id: order_id,
+            listing_id,
+            buyer,
+            seller: listing.seller.clone(),
+            amount: listing.price,
+            status: OrderStatus::Pending,
+        };
+
+        // Propose the order; it is applied once the counterparties co-sign it
+        self.marketplace.propose_state_update(&channel_id, StateUpdate::CreateOrder { order }).await?;
+
+        Ok(serde_json::json!({
+            "id": order_id,
+            "listing_id": params.listing_id,
+            "status": "pending"
+        }))
+    }
+
+    async fn get_orders(&self) -> RpcResult {
+        let views = self.marketplace.list_order_views().await;
+        let orders_json: Vec<_> = views.iter().map(order_view_json).collect();
+
+        Ok(serde_json::json!(orders_json))
+    }
+
+    /// One order with the escrow securing it
+    async fn get_order(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            order_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let view = self.marketplace.get_order_view(&params.order_id).await?;
+        Ok(order_view_json(&view))
+    }
+
+    /// Signed receipt for a completed order or closed channel
+    async fn get_receipt(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            /// Order ID or channel ID
+            id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let receipt = self.marketplace.get_receipt(&params.id)?
+            .ok_or_else(|| JsonRpcError::invalid_params("Receipt not found"))?;
+        serde_json::to_value(receipt).map_err(JsonRpcError::internal)
+    }
+
+    async fn list_receipts(&self) -> RpcResult {
+        let receipts = self.marketplace.list_receipts()?;
+        serde_json::to_value(receipts).map_err(JsonRpcError::internal)
+    }
+
+    async fn get_purchase_history(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            buyer: String,
+            #[serde(flatten)]
+            query: HistoryQuery,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let buyer = self.marketplace.resolve_counterparty(&params.buyer)?;
+        let history = self.marketplace.get_purchase_history(&buyer, &params.query).await?;
+        Ok(serde_json::json!({
+            "purchases": history.purchases.iter().map(purchase_json).collect::<Vec<_>>(),
+            "total": history.total
+        }))
+    }
+
+    /// Check a receipt was signed by one of its parties and anchored to an
+    /// update all of them co-signed. Needs no node state, so receipts from
+    /// any node can be checked.
+    async fn verify_receipt(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            receipt: Receipt,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        Ok(serde_json::json!({
+            "valid": params.receipt.verify(),
+            "id": params.receipt.id(),
+            "issuer": params.receipt.issuer,
+        }))
+    }
+
+    /// Move an order along its lifecycle. The acting key is the
+    /// authorization's signer, or the node's own key without one, and must
+    /// hold a role the order's transition table allows the move for: buyer,
+    /// seller, or one of the node's arbitrators settling a dispute.
+    async fn update_order_status(&self, params: Option<Value>) -> RpcResult {
+        use tari_l2_state_channel::state::OrderStatus;
+
+        #[derive(Deserialize)]
+        struct Params {
+            channel_id: Hash,
+            order_id: Hash,
+            status: OrderStatus,
+        }
+
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("update_order_status", &mut params)?;
+        let params: Params = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
+
+        let view = self.marketplace.get_order_view(&params.order_id).await?;
+        if view.channel_id != params.channel_id {
+            return Err(JsonRpcError::invalid_params("Order is not in this channel"));
+        }
+        let actor = match &authorization {
+            Some(action) => *action.signer(),
+            None => self.marketplace.public_key(),
         };
+        let mut roles = view.order.roles_of(&actor);
+        if self.marketplace.is_arbitrator(&actor).await {
+            roles.push(Role::Arbitrator);
+        }
+        view.order.check_transition(&roles, &params.status).map_err(L2Error::from)?;
+        self.check_authorization(authorization.as_ref(), &actor).await?;
 
-        // Create the channel
-        let channel_id = self.marketplace.create_channel(config)
-            .await
-            .map_err(|e| e.to_string())?;
+        let update = StateUpdate::UpdateOrderStatus { order_id: params.order_id, status: params.status.clone() };
+        let nonce = self.marketplace.propose_state_update(&params.channel_id, update).await?.nonce;
+        let applied = self.marketplace.applied_update(&params.channel_id, nonce).await.is_some();
 
         Ok(serde_json::json!({
-            "id": hex::encode(channel_id.as_bytes()),
-            "status": "created",
-            "participant1": params.participant1,
-            "participant2": params.participant2,
-            "collateral": params.collateral
+            "channel_id": params.channel_id,
+            "order_id": params.order_id,
+            "order_status": params.status,
+            "nonce": nonce,
+            "status": if applied { "applied" } else { "proposed" }
         }))
     }
 
-    async fn transfer_in_channel(&self, _params: Option<Value>) -> Result<Value, String> {
+    async fn transfer(&self, params: Option<Value>) -> RpcResult {
+        use tari_l2_common::Amount;
+
+        #[derive(serde::Deserialize)]
+        struct TransferParams {
+            channel_id: Hash,
+            /// Public keys, addresses or contact labels
+            from: String,
+            to: String,
+            amount: u64,
+        }
+
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("transfer", &mut params)?;
+        let params: TransferParams = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
+        let from = self.marketplace.resolve_counterparty(&params.from)?;
+        let to = self.marketplace.resolve_counterparty(&params.to)?;
+        self.check_authorization(authorization.as_ref(), &from).await?;
+
+        let update = StateUpdate::Transfer { from, to, amount: Amount::new(params.amount) };
+        let nonce = self.marketplace.propose_state_update(&params.channel_id, update).await?.nonce;
+        let applied = self.marketplace.applied_update(&params.channel_id, nonce).await.is_some();
+
         Ok(serde_json::json!({
-            "status": "success"
+            "channel_id": params.channel_id,
+            "nonce": nonce,
+            "status": if applied { "applied" } else { "proposed" }
         }))
     }
 
-    async fn close_channel(&self, _params: Option<Value>) -> Result<Value, String> {
+    // ===== Invoice RPC Methods =====
+
+    /// Issue an invoice payable to this node
+    async fn create_invoice(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            amount: u64,
+            #[serde(default)]
+            memo: String,
+            listing_id: Option<Hash>,
+            expires_in: Option<u64>,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let invoice = self.marketplace.create_invoice(
+            params.amount,
+            params.memo,
+            params.listing_id,
+            params.expires_in.unwrap_or(DEFAULT_EXPIRY_SECS),
+        )?;
+        let encoded = invoice.encode().map_err(JsonRpcError::internal)?;
         Ok(serde_json::json!({
-            "status": "closed"
+            "invoice": invoice,
+            "encoded": encoded
         }))
     }
 
-    async fn create_listing(&self, params: Option<Value>) -> Result<Value, String> {
-        #[derive(serde::Deserialize)]
-        struct CreateListingParams {
-            seller_pubkey: Option<String>,
-            title: String,
-            description: String,
-            price: u64,
-            ipfs_hash: Option<String>,
-            category: Option<String>,
+    async fn list_invoices(&self) -> RpcResult {
+        let invoices = self.marketplace.list_invoices()?;
+        serde_json::to_value(invoices).map_err(JsonRpcError::internal)
+    }
+
+    /// Pay an invoice, given as JSON or as its encoded string, through a
+    /// channel shared with the seller
+    async fn pay_invoice(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            invoice: Value,
         }
 
-        let params: CreateListingParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
 
-        // Use provided seller_pubkey or default to node's own public key
-        let seller = if let Some(seller_pubkey) = params.seller_pubkey {
-            let seller_bytes = hex::decode(&seller_pubkey)
-                .map_err(|e| format!("Invalid seller_pubkey hex: {}", e))?;
-            PublicKey::from_slice(&seller_bytes)
-                .map_err(|e| e.to_string())?
-        } else {
-            self.marketplace.public_key()
+        let invoice: Invoice = match params.invoice {
+            Value::String(encoded) => Invoice::decode(&encoded).map_err(JsonRpcError::invalid_params)?,
+            other => serde_json::from_value(other).map_err(JsonRpcError::invalid_params)?,
         };
-
-        // Generate listing ID
-        let listing_id = Hash::random();
-
-        // Create global listing (stored in marketplace, not in a specific channel)
-        self.marketplace.create_global_listing(
-            listing_id,
-            seller,
-            params.title.clone(),
-            params.description.clone(),
-            params.price,
-            params.ipfs_hash.unwrap_or_else(|| "QmPending".to_string()),
-            params.category.unwrap_or_else(|| "other".to_string()),
-        ).await.map_err(|e| e.to_string())?;
+        let payment = self.marketplace.pay_invoice(&invoice).await?;
+        let applied = self.marketplace.applied_update(&payment.channel_id, payment.nonce).await.is_some();
 
         Ok(serde_json::json!({
-            "id": hex::encode(listing_id.as_bytes()),
-            "title": params.title,
-            "price": params.price,
-            "seller": hex::encode(seller.as_bytes()),
-            "status": "active"
+            "invoice_id": invoice.id(),
+            "channel_id": payment.channel_id,
+            "nonce": payment.nonce,
+            "status": if applied { "applied" } else { "proposed" }
         }))
     }
 
-    async fn get_listings(&self) -> Result<Value, String> {
-        let listings = self.marketplace.list_all_listings().await;
-
-        let listings_json: Vec<_> = listings.iter().map(|(channel_id, listing)| {
-            serde_json::json!({
-                "id": hex::encode(listing.id.as_bytes()),
-                "channel_id": hex::encode(channel_id.as_bytes()),
-                "seller": hex::encode(listing.seller.as_bytes()),
-                "title": listing.title,
-                "description": listing.description,
-                "price": listing.price.value(),
-                "ipfs_hash": listing.ipfs_hash,
-                "active": listing.active,
-                "category": listing.category
-            })
-        }).collect();
+    // ===== Address Book RPC Methods =====
 
-        Ok(serde_json::json!(listings_json))
+    async fn list_contacts(&self) -> RpcResult {
+        let contacts = self.marketplace.list_contacts()?;
+        serde_json::to_value(contacts).map_err(JsonRpcError::internal)
     }
 
-    async fn create_order(&self, params: Option<Value>) -> Result<Value, String> {
-        use tari_l2_state_channel::state::{Order, OrderStatus};
-
-        #[derive(serde::Deserialize)]
-        struct CreateOrderParams {
-            channel_id: String,
-            listing_id: String,
-            buyer: String,
+    async fn get_contact(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            label: String,
         }
 
-        let params: CreateOrderParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
-
-        let channel_id_bytes = hex::decode(&params.channel_id)
-            .map_err(|e| format!("Invalid channel_id hex: {}", e))?;
-        let channel_id = Hash::from_slice(&channel_id_bytes)
-            .map_err(|e| e.to_string())?;
-
-        let listing_id_bytes = hex::decode(&params.listing_id)
-            .map_err(|e| format!("Invalid listing_id hex: {}", e))?;
-        let listing_id = Hash::from_slice(&listing_id_bytes)
-            .map_err(|e| e.to_string())?;
-
-        let buyer_bytes = hex::decode(&params.buyer)
-            .map_err(|e| format!("Invalid buyer hex: {}", e))?;
-        let buyer = PublicKey::from_slice(&buyer_bytes)
-            .map_err(|e| e.to_string())?;
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
 
-        // Get the listing to find seller and price
-        let listings = self.marketplace.get_channel_listings(&channel_id)
-            .await
-            .map_err(|e| e.to_string())?;
+        let contact = self.marketplace.get_contact(&params.label)?
+            .ok_or_else(|| JsonRpcError::invalid_params("Contact not found"))?;
+        serde_json::to_value(contact).map_err(JsonRpcError::internal)
+    }
 
-        let listing = listings.iter()
-            .find(|l| l.id == listing_id)
-            .ok_or("Listing not found")?;
+    async fn add_contact(&self, params: Option<Value>) -> RpcResult {
+        let contact = contact_from_params(params)?;
+        self.marketplace.add_contact(contact.clone())?;
+        serde_json::to_value(contact).map_err(JsonRpcError::internal)
+    }
 
-        let order_id = Hash::random();
-        let order = Order {
-            id: order_id,
-            listing_id,
-            buyer,
-            seller: listing.seller.clone(),
-            amount: listing.price,
-            status: OrderStatus::Pending,
-        };
+    /// Replace the key and Tari address stored under an existing label
+    async fn update_contact(&self, params: Option<Value>) -> RpcResult {
+        let contact = contact_from_params(params)?;
+        self.marketplace.update_contact(contact.clone())?;
+        Ok(serde_json::json!({
+            "label": contact.label,
+            "status": "updated"
+        }))
+    }
 
-        // Create signed state update
-        let signed_update = self.marketplace.create_order(&channel_id, order.clone())
-            .await
-            .map_err(|e| e.to_string())?;
+    async fn remove_contact(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            label: String,
+        }
 
-        // Apply the update
-        self.marketplace.apply_state_update(&channel_id, signed_update)
-            .await
-            .map_err(|e| e.to_string())?;
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
 
+        if !self.marketplace.remove_contact(&params.label)? {
+            return Err(JsonRpcError::invalid_params("Contact not found"));
+        }
         Ok(serde_json::json!({
-            "id": hex::encode(order_id.as_bytes()),
-            "listing_id": params.listing_id,
-            "status": "pending"
+            "label": params.label,
+            "status": "removed"
         }))
     }
 
-    async fn get_orders(&self) -> Result<Value, String> {
-        let orders = self.marketplace.list_all_orders().await;
+    // ===== Saved Search RPC Methods =====
 
-        let orders_json: Vec<_> = orders.iter().map(|(channel_id, order)| {
-            serde_json::json!({
-                "id": hex::encode(order.id.as_bytes()),
-                "channel_id": hex::encode(channel_id.as_bytes()),
-                "listing_id": hex::encode(order.listing_id.as_bytes()),
-                "buyer": hex::encode(order.buyer.as_bytes()),
-                "seller": hex::encode(order.seller.as_bytes()),
-                "amount": order.amount.value(),
-                "status": format!("{:?}", order.status)
-            })
-        }).collect();
+    /// Save `{keywords?, category?, max_price?}`, with an optional
+    /// `{webhook}` each match is POSTed to
+    async fn save_search(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            #[serde(flatten)]
+            query: SearchQuery,
+            #[serde(default)]
+            webhook: Option<String>,
+        }
 
-        Ok(serde_json::json!(orders_json))
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let search = SavedSearch::new(params.query, params.webhook, Timestamp::now())
+            .map_err(JsonRpcError::invalid_params)?;
+        self.marketplace.save_search(search.clone())?;
+        serde_json::to_value(search).map_err(JsonRpcError::internal)
     }
 
-    async fn update_order_status(&self, _params: Option<Value>) -> Result<Value, String> {
+    async fn list_saved_searches(&self) -> RpcResult {
+        let searches = self.marketplace.list_saved_searches()?;
+        serde_json::to_value(searches).map_err(JsonRpcError::internal)
+    }
+
+    /// Listings that matched `{search_id}`, newest first
+    async fn get_saved_search_results(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            search_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let results: Vec<Value> = self.marketplace.saved_search_results(&params.search_id).await?
+            .into_iter()
+            .map(|(search_match, listing)| serde_json::json!({
+                "matched_at": search_match.matched_at,
+                "listing": listing,
+            }))
+            .collect();
         Ok(serde_json::json!({
-            "status": "updated"
+            "search_id": params.search_id,
+            "results": results
         }))
     }
 
-    async fn transfer(&self, _params: Option<Value>) -> Result<Value, String> {
+    async fn delete_saved_search(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            search_id: Hash,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        if !self.marketplace.delete_saved_search(&params.search_id)? {
+            return Err(JsonRpcError::invalid_params("Saved search not found"));
+        }
         Ok(serde_json::json!({
-            "status": "success"
+            "search_id": params.search_id,
+            "status": "deleted"
         }))
     }
 
-    // ===== Escrow RPC Methods =====
+    /// Set where escrow payouts and refunds owed to the acting key go: the
+    /// authorization's signer, or this node's key without one
+    async fn set_payout_preference(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            #[serde(default)]
+            address: Option<String>,
+            #[serde(default)]
+            method: PayoutMethod,
+        }
 
-    async fn create_escrow(&self, params: Option<Value>) -> Result<Value, String> {
-        use tari_l2_common::Amount;
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("set_payout_preference", &mut params)?;
+        let params: Params = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
+
+        let actor = match &authorization {
+            Some(action) => *action.signer(),
+            None => self.marketplace.public_key(),
+        };
+        let address = params.address
+            .map(|address| self.marketplace.resolve_payout_address(&address))
+            .transpose()?;
+        let preference = PayoutPreference::new(actor, address, params.method, Timestamp::now())
+            .map_err(JsonRpcError::invalid_params)?;
+        self.check_authorization(authorization.as_ref(), &actor).await?;
+
+        self.marketplace.set_payout_preference(preference.clone())?;
+        serde_json::to_value(preference).map_err(JsonRpcError::internal)
+    }
+
+    /// A key's payout preference, this node's by default. Null when it has
+    /// not set one.
+    async fn get_payout_preference(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize, Default)]
+        struct Params {
+            #[serde(default)]
+            public_key: Option<String>,
+        }
+
+        let params: Params = match params {
+            Some(params) => serde_json::from_value(params).map_err(JsonRpcError::invalid_params)?,
+            None => Params::default(),
+        };
+        let public_key = match &params.public_key {
+            Some(name) => self.marketplace.resolve_counterparty(name)?,
+            None => self.marketplace.public_key(),
+        };
+        let preference = self.marketplace.payout_preference(&public_key)?;
+        serde_json::to_value(preference).map_err(JsonRpcError::internal)
+    }
+
+    // ===== Escrow RPC Methods =====
 
+    async fn create_escrow(&self, params: Option<Value>) -> RpcResult {
         #[derive(serde::Deserialize)]
         struct CreateEscrowParams {
-            listing_id: String,
-            buyer: String,
-            seller: String,
-            amount: u64,
+            channel_id: Hash,
+            order_id: Hash,
             timeout_period: Option<u64>,
+            ship_within: Option<u64>,
         }
 
         let params: CreateEscrowParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
-
-        let listing_id_bytes = hex::decode(&params.listing_id)
-            .map_err(|e| format!("Invalid listing_id hex: {}", e))?;
-        let listing_id = Hash::from_slice(&listing_id_bytes)
-            .map_err(|e| e.to_string())?;
-
-        let buyer_bytes = hex::decode(&params.buyer)
-            .map_err(|e| format!("Invalid buyer hex: {}", e))?;
-        let buyer = PublicKey::from_slice(&buyer_bytes)
-            .map_err(|e| e.to_string())?;
-
-        let seller_bytes = hex::decode(&params.seller)
-            .map_err(|e| format!("Invalid seller hex: {}", e))?;
-        let seller = PublicKey::from_slice(&seller_bytes)
-            .map_err(|e| e.to_string())?;
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
 
         let escrow_id = self.marketplace.create_escrow(
-            listing_id,
-            buyer,
-            seller,
-            Amount::new(params.amount),
+            &params.channel_id,
+            &params.order_id,
             params.timeout_period.unwrap_or(86400), // Default 24 hours
-        ).await.map_err(|e| e.to_string())?;
+            params.ship_within,
+        ).await?;
 
         Ok(serde_json::json!({
-            "id": hex::encode(escrow_id.as_bytes()),
+            "id": escrow_id,
             "status": "created"
         }))
     }
 
-    async fn fund_escrow(&self, params: Option<Value>) -> Result<Value, String> {
+    async fn fund_escrow(&self, params: Option<Value>) -> RpcResult {
         #[derive(serde::Deserialize)]
         struct FundEscrowParams {
-            escrow_id: String,
+            escrow_id: Hash,
             l1_tx_id: String,
         }
 
-        let params: FundEscrowParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("fund_escrow", &mut params)?;
+        let params: FundEscrowParams = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
 
-        let escrow_id_bytes = hex::decode(&params.escrow_id)
-            .map_err(|e| format!("Invalid escrow_id hex: {}", e))?;
-        let escrow_id = Hash::from_slice(&escrow_id_bytes)
-            .map_err(|e| e.to_string())?;
+        let escrow_id = params.escrow_id;
 
-        self.marketplace.fund_escrow(&escrow_id, params.l1_tx_id)
-            .await
-            .map_err(|e| e.to_string())?;
+        self.check_escrow_role(&escrow_id, EscrowStatus::Funded, authorization.as_ref()).await?;
+        self.marketplace.fund_escrow(&escrow_id, params.l1_tx_id).await?;
 
         Ok(serde_json::json!({
             "status": "funded"
         }))
     }
 
-    async fn ship_order(&self, params: Option<Value>) -> Result<Value, String> {
+    async fn fund_escrow_from_channel(&self, params: Option<Value>) -> RpcResult {
+        #[derive(serde::Deserialize)]
+        struct Params {
+            escrow_id: Hash,
+        }
+
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("fund_escrow_from_channel", &mut params)?;
+        let params: Params = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
+
+        self.check_escrow_role(&params.escrow_id, EscrowStatus::Funded, authorization.as_ref()).await?;
+        let signed = self.marketplace.fund_escrow_from_channel(&params.escrow_id).await?;
+
+        Ok(serde_json::json!({
+            "status": "proposed",
+            "nonce": signed.nonce
+        }))
+    }
+
+    async fn ship_order(&self, params: Option<Value>) -> RpcResult {
         #[derive(serde::Deserialize)]
         struct ShipOrderParams {
-            escrow_id: String,
+            escrow_id: Hash,
             tracking_info: Option<String>,
         }
 
-        let params: ShipOrderParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("ship_order", &mut params)?;
+        let params: ShipOrderParams = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
 
-        let escrow_id_bytes = hex::decode(&params.escrow_id)
-            .map_err(|e| format!("Invalid escrow_id hex: {}", e))?;
-        let escrow_id = Hash::from_slice(&escrow_id_bytes)
-            .map_err(|e| e.to_string())?;
+        let escrow_id = params.escrow_id;
 
-        self.marketplace.ship_order(&escrow_id, params.tracking_info)
-            .await
-            .map_err(|e| e.to_string())?;
+        self.check_escrow_role(&escrow_id, EscrowStatus::Shipped, authorization.as_ref()).await?;
+        self.marketplace.ship_order(&escrow_id, params.tracking_info).await?;
 
         Ok(serde_json::json!({
             "status": "shipped"
         }))
     }
 
-    async fn confirm_delivery(&self, params: Option<Value>) -> Result<Value, String> {
+    /// Set the L1 address the signer is paid at when an L1-funded escrow
+    /// settles, or the node's own without an authorization. Only a party
+    /// can change where their share goes.
+    async fn set_escrow_payout_address(&self, params: Option<Value>) -> RpcResult {
+        #[derive(serde::Deserialize)]
+        struct Params {
+            escrow_id: Hash,
+            address: String,
+        }
+
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("set_escrow_payout_address", &mut params)?;
+        let params: Params = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
+
+        let party = match &authorization {
+            Some(action) => *action.signer(),
+            None => self.marketplace.public_key(),
+        };
+        self.check_authorization(authorization.as_ref(), &party).await?;
+
+        let address = self.marketplace.resolve_payout_address(&params.address)?;
+        self.marketplace.set_escrow_payout_address(&params.escrow_id, &party, address).await?;
+
+        Ok(serde_json::json!({
+            "status": "updated"
+        }))
+    }
+
+    async fn confirm_delivery(&self, params: Option<Value>) -> RpcResult {
         #[derive(serde::Deserialize)]
         struct ConfirmDeliveryParams {
-            escrow_id: String,
+            escrow_id: Hash,
         }
 
-        let params: ConfirmDeliveryParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("confirm_delivery", &mut params)?;
+        let params: ConfirmDeliveryParams = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
 
-        let escrow_id_bytes = hex::decode(&params.escrow_id)
-            .map_err(|e| format!("Invalid escrow_id hex: {}", e))?;
-        let escrow_id = Hash::from_slice(&escrow_id_bytes)
-            .map_err(|e| e.to_string())?;
+        let escrow_id = params.escrow_id;
 
-        self.marketplace.confirm_delivery(&escrow_id)
-            .await
-            .map_err(|e| e.to_string())?;
+        self.check_escrow_role(&escrow_id, EscrowStatus::Completed, authorization.as_ref()).await?;
+        self.marketplace.confirm_delivery(&escrow_id).await?;
+        let escrow = self.marketplace.get_escrow(&escrow_id).await?;
 
         Ok(serde_json::json!({
-            "status": "completed"
+            "status": "completed",
+            "payout_tx_id": escrow.payout_tx_id
         }))
     }
 
-    async fn request_refund(&self, params: Option<Value>) -> Result<Value, String> {
+    async fn request_refund(&self, params: Option<Value>) -> RpcResult {
         #[derive(serde::Deserialize)]
         struct RequestRefundParams {
-            escrow_id: String,
+            escrow_id: Hash,
             reason: String,
         }
 
-        let params: RequestRefundParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("request_refund", &mut params)?;
+        let params: RequestRefundParams = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
 
-        let escrow_id_bytes = hex::decode(&params.escrow_id)
-            .map_err(|e| format!("Invalid escrow_id hex: {}", e))?;
-        let escrow_id = Hash::from_slice(&escrow_id_bytes)
-            .map_err(|e| e.to_string())?;
+        let escrow_id = params.escrow_id;
 
-        self.marketplace.request_refund(&escrow_id, params.reason)
-            .await
-            .map_err(|e| e.to_string())?;
+        self.check_escrow_role(&escrow_id, EscrowStatus::RefundRequested, authorization.as_ref()).await?;
+        self.marketplace.request_refund(&escrow_id, params.reason).await?;
 
         Ok(serde_json::json!({
             "status": "refund_requested"
         }))
     }
 
-    async fn approve_refund(&self, params: Option<Value>) -> Result<Value, String> {
+    async fn approve_refund(&self, params: Option<Value>) -> RpcResult {
         #[derive(serde::Deserialize)]
         struct ApproveRefundParams {
-            escrow_id: String,
+            escrow_id: Hash,
         }
 
-        let params: ApproveRefundParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("approve_refund", &mut params)?;
+        let params: ApproveRefundParams = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
 
-        let escrow_id_bytes = hex::decode(&params.escrow_id)
-            .map_err(|e| format!("Invalid escrow_id hex: {}", e))?;
-        let escrow_id = Hash::from_slice(&escrow_id_bytes)
-            .map_err(|e| e.to_string())?;
+        let escrow_id = params.escrow_id;
 
-        self.marketplace.approve_refund(&escrow_id)
-            .await
-            .map_err(|e| e.to_string())?;
+        self.check_escrow_role(&escrow_id, EscrowStatus::Refunded, authorization.as_ref()).await?;
+        self.marketplace.approve_refund(&escrow_id).await?;
+        let escrow = self.marketplace.get_escrow(&escrow_id).await?;
 
         Ok(serde_json::json!({
-            "status": "refunded"
+            "status": "refunded",
+            "payout_tx_id": escrow.payout_tx_id
         }))
     }
 
-    async fn raise_dispute(&self, params: Option<Value>) -> Result<Value, String> {
+    async fn refund_unshipped(&self, params: Option<Value>) -> RpcResult {
+        #[derive(serde::Deserialize)]
+        struct RefundUnshippedParams {
+            escrow_id: Hash,
+        }
+
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("refund_unshipped", &mut params)?;
+        let params: RefundUnshippedParams = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
+
+        self.check_escrow_role(&params.escrow_id, EscrowStatus::Refunded, authorization.as_ref()).await?;
+        self.marketplace.refund_unshipped(&params.escrow_id).await?;
+        let escrow = self.marketplace.get_escrow(&params.escrow_id).await?;
+
+        Ok(serde_json::json!({
+            "status": "refunded",
+            "payout_tx_id": escrow.payout_tx_id
+        }))
+    }
+
+    async fn raise_dispute(&self, params: Option<Value>) -> RpcResult {
         #[derive(serde::Deserialize)]
         struct RaiseDisputeParams {
-            escrow_id: String,
+            escrow_id: Hash,
             reason: String,
         }
 
-        let params: RaiseDisputeParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+        let mut params = params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?;
+        let authorization = take_authorization("raise_dispute", &mut params)?;
+        let params: RaiseDisputeParams = serde_json::from_value(params)
+            .map_err(JsonRpcError::invalid_params)?;
 
-        let escrow_id_bytes = hex::decode(&params.escrow_id)
-            .map_err(|e| format!("Invalid escrow_id hex: {}", e))?;
-        let escrow_id = Hash::from_slice(&escrow_id_bytes)
-            .map_err(|e| e.to_string())?;
+        let escrow_id = params.escrow_id;
 
-        self.marketplace.raise_dispute(&escrow_id, params.reason)
-            .await
-            .map_err(|e| e.to_string())?;
+        self.check_escrow_role(&escrow_id, EscrowStatus::Disputed, authorization.as_ref()).await?;
+        self.marketplace.raise_dispute(&escrow_id, params.reason).await?;
 
         Ok(serde_json::json!({
             "status": "disputed"
         }))
     }
 
-    async fn get_escrow(&self, params: Option<Value>) -> Result<Value, String> {
+    async fn get_escrow(&self, params: Option<Value>) -> RpcResult {
         #[derive(serde::Deserialize)]
         struct GetEscrowParams {
-            escrow_id: String,
+            escrow_id: Hash,
         }
 
         let params: GetEscrowParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
 
-        let escrow_id_bytes = hex::decode(&params.escrow_id)
-            .map_err(|e| format!("Invalid escrow_id hex: {}", e))?;
-        let escrow_id = Hash::from_slice(&escrow_id_bytes)
-            .map_err(|e| e.to_string())?;
+        let escrow_id = params.escrow_id;
 
-        let escrow = self.marketplace.get_escrow(&escrow_id)
-            .await
-            .map_err(|e| e.to_string())?;
+        let escrow = self.marketplace.get_escrow(&escrow_id).await?;
 
-        Ok(serde_json::json!({
-            "id": hex::encode(escrow.id.as_bytes()),
-            "listing_id": hex::encode(escrow.listing_id.as_bytes()),
-            "buyer": hex::encode(escrow.buyer.as_bytes()),
-            "seller": hex::encode(escrow.seller.as_bytes()),
-            "amount": escrow.amount.value(),
-            "status": format!("{:?}", escrow.status),
-            "created_at": escrow.created_at.as_secs(),
-            "updated_at": escrow.updated_at.as_secs(),
-            "timeout_period": escrow.timeout_period,
-            "l1_tx_id": escrow.l1_tx_id,
-            "tracking_info": escrow.tracking_info,
-            "dispute_reason": escrow.dispute_reason
-        }))
+        Ok(escrow_json(&escrow))
     }
 
-    async fn list_escrows(&self) -> Result<Value, String> {
+    async fn list_escrows(&self) -> RpcResult {
         let escrows = self.marketplace.list_escrows().await;
 
-        let escrows_json: Vec<_> = escrows.iter().map(|escrow| {
-            serde_json::json!({
-                "id": hex::encode(escrow.id.as_bytes()),
-                "listing_id": hex::encode(escrow.listing_id.as_bytes()),
-                "buyer": hex::encode(escrow.buyer.as_bytes()),
-                "seller": hex::encode(escrow.seller.as_bytes()),
-                "amount": escrow.amount.value(),
-                "status": format!("{:?}", escrow.status),
-                "created_at": escrow.created_at.as_secs(),
-                "updated_at": escrow.updated_at.as_secs(),
-                "timeout_period": escrow.timeout_period,
-                "l1_tx_id": &escrow.l1_tx_id,
-                "tracking_info": &escrow.tracking_info,
-                "dispute_reason": &escrow.dispute_reason
-            })
-        }).collect();
+        let escrows_json: Vec<_> = escrows.iter().map(escrow_json).collect();
 
         Ok(serde_json::json!(escrows_json))
     }
 
     // ===== Wallet RPC Methods =====
 
-    async fn wallet_create(&self) -> Result<Value, String> {
-        // Create a full embedded Tari wallet with 24-word seed phrase
+    /// Create a wallet and store it only in the encrypted keystore. Its seed
+    /// phrase never leaves the node unless asked for with `wallet_export`.
+    async fn wallet_create(&self, params: Option<Value>) -> RpcResult {
         use tari_l2_marketplace::Wallet;
 
-        let wallet = Wallet::new();
-        let seed_phrase = wallet.seed_phrase().unwrap_or_default();
-        let address_hex = wallet.address_hex();
-
-        // Save wallet to file
-        let wallet_data = serde_json::json!({
-            "address": wallet.address(),
-            "address_hex": address_hex.clone(),
-            "public_key": wallet.public_key_hex(),
-            "private_key": wallet.export_private_key(),
-            "seed_phrase": seed_phrase.clone(),
-            "created_at": chrono::Utc::now().to_rfc3339(),
-        });
-
-        let wallet_path = format!("./data/wallet_{}.json", &address_hex[..16]);
-        std::fs::create_dir_all("./data").map_err(|e| format!("Failed to create data directory: {}", e))?;
-        std::fs::write(&wallet_path, serde_json::to_string_pretty(&wallet_data).unwrap())
-            .map_err(|e| format!("Failed to save wallet: {}", e))?;
+        #[derive(Deserialize)]
+        struct CreateParams {
+            passphrase: String,
+        }
 
-        // Also save as "current" wallet
-        std::fs::write("./data/current_wallet.json", serde_json::to_string_pretty(&wallet_data).unwrap())
-            .map_err(|e| format!("Failed to save current wallet: {}", e))?;
+        let params: CreateParams = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
 
-        info!("💾 Wallet saved to {}", wallet_path);
+        let wallet = Wallet::new();
+        self.save_keystore(&wallet, &params.passphrase)?;
 
         Ok(serde_json::json!({
             "address": wallet.address(),
-            "address_hex": address_hex,
+            "address_hex": wallet.address_hex(),
             "public_key": wallet.public_key_hex(),
-            "private_key": wallet.export_private_key(),
-            "seed_phrase": seed_phrase,
             "source": "embedded_wallet",
-            "wallet_file": wallet_path,
-            "message": "Full Tari wallet created with 24-word seed phrase. This wallet can be used for mining and marketplace. SAVE YOUR SEED PHRASE!"
+            "keystore": self.keystore,
+            "message": "Full Tari wallet created with a 24-word seed phrase and stored encrypted. Unlock it with wallet_unlock, and back up the seed phrase with wallet_export"
         }))
     }
 
-    async fn wallet_import_seed(&self, params: Option<Value>) -> Result<Value, String> {
+    async fn wallet_import_seed(&self, params: Option<Value>) -> RpcResult {
         use tari_l2_marketplace::Wallet;
 
         #[derive(serde::Deserialize)]
         struct ImportSeedParams {
             seed_phrase: String,
+            /// Encrypts the keystore the wallet is stored in
+            passphrase: String,
         }
 
         let params: ImportSeedParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
 
         // Import wallet from 24-word Tari seed phrase
         let wallet = Wallet::from_seed_phrase(&params.seed_phrase)
-            .map_err(|e| format!("Failed to import wallet: {}", e))?;
+            .map_err(|e| JsonRpcError::invalid_params(format!("Failed to import wallet: {}", e)))?;
+        self.save_keystore(&wallet, &params.passphrase)?;
 
         Ok(serde_json::json!({
             "address": wallet.address(),
             "address_hex": wallet.address_hex(),
             "public_key": wallet.public_key_hex(),
-            "private_key": wallet.export_private_key(),
-            "seed_phrase": wallet.seed_phrase().unwrap_or_default(),
-            "message": "Wallet imported successfully from 24-word seed phrase"
+            "keystore": self.keystore,
+            "message": "Wallet imported from 24-word seed phrase and stored encrypted"
         }))
     }
 
-    async fn wallet_import_key(&self, params: Option<Value>) -> Result<Value, String> {
+    async fn wallet_import_key(&self, params: Option<Value>) -> RpcResult {
         use tari_l2_marketplace::Wallet;
 
         #[derive(serde::Deserialize)]
         struct ImportKeyParams {
             private_key: String,
+            /// Encrypts the keystore the wallet is stored in
+            passphrase: String,
         }
 
         let params: ImportKeyParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
 
         // Import wallet from private key (32-byte hex)
         let wallet = Wallet::from_private_key(&params.private_key)
-            .map_err(|e| format!("Failed to import wallet: {}", e))?;
+            .map_err(|e| JsonRpcError::invalid_params(format!("Failed to import wallet: {}", e)))?;
+        self.save_keystore(&wallet, &params.passphrase)?;
 
         Ok(serde_json::json!({
             "address": wallet.address(),
             "address_hex": wallet.address_hex(),
             "public_key": wallet.public_key_hex(),
-            "private_key": wallet.export_private_key(),
-            "message": "Wallet imported from private key (no seed phrase available for this import method)"
+            "keystore": self.keystore,
+            "message": "Wallet imported from private key and stored encrypted (no seed phrase available for this import method)"
+        }))
+    }
+
+    /// Write `wallet` to the keystore, encrypted under `passphrase`. Refused
+    /// when a keystore exists, since it may hold the only copy of a wallet.
+    fn save_keystore(&self, wallet: &tari_l2_marketplace::Wallet, passphrase: &str) -> Result<(), JsonRpcError> {
+        session::create_keystore(&self.keystore, wallet, passphrase).map_err(JsonRpcError::invalid_params)?;
+        info!("Wallet {} saved to the keystore", wallet.public_key_hex());
+        Ok(())
+    }
+
+    /// Decrypt the keystore and hold the wallet for a session, so endpoints
+    /// that need its keys work without them in their params
+    async fn wallet_unlock(&self, params: Option<Value>) -> RpcResult {
+        use tari_l2_marketplace::Wallet;
+
+        #[derive(Deserialize)]
+        struct Params {
+            passphrase: String,
+            /// Seconds without use before the wallet locks again
+            #[serde(default)]
+            idle_timeout: Option<u64>,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let keystore = std::fs::read(&self.keystore)
+            .map_err(|_| "No wallet keystore found. Create or import a wallet with a passphrase first".to_string())?;
+        let wallet = Wallet::from_keystore(&keystore, &params.passphrase)
+            .map_err(JsonRpcError::invalid_params)?;
+
+        let address = wallet.address();
+        let public_key = wallet.public_key_hex();
+        let (session, idle_timeout) = self.wallet_session.unlock(wallet, params.idle_timeout.map(Duration::from_secs)).await;
+        info!("Wallet {} unlocked for {}s of inactivity", public_key, idle_timeout.as_secs());
+
+        Ok(serde_json::json!({
+            "address": address,
+            "public_key": public_key,
+            "session": session,
+            "idle_timeout": idle_timeout.as_secs(),
+            "status": "unlocked"
+        }))
+    }
+
+    async fn wallet_lock(&self, params: Option<Value>) -> RpcResult {
+        #[derive(Deserialize)]
+        struct Params {
+            /// Token returned by `wallet_unlock`
+            session: String,
+        }
+
+        let params: Params = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let was_unlocked = self.wallet_session.lock(&params.session).await;
+        Ok(serde_json::json!({
+            "status": if was_unlocked { "locked" } else { "already_locked" }
         }))
     }
 
-    async fn wallet_export(&self, params: Option<Value>) -> Result<Value, String> {
+    /// The wallet unlocked under `session`
+    async fn session_wallet(&self, session: &str) -> Result<tari_l2_marketplace::Wallet, JsonRpcError> {
+        self.wallet_session.wallet(session).await
+            .ok_or_else(|| JsonRpcError::invalid_params("Wallet is locked or the session is invalid; call wallet_unlock first"))
+    }
+
+    /// Reveal the keystore wallet's seed phrase and private key, for backup.
+    /// Needs the keystore passphrase and an explicit confirmation, and is
+    /// refused on nodes without API keys.
+    async fn wallet_export(&self, params: Option<Value>) -> RpcResult {
+        use tari_l2_marketplace::Wallet;
+
         #[derive(serde::Deserialize)]
         struct ExportParams {
-            address: String,
+            passphrase: String,
+            /// Must be true: the response holds the wallet's secrets
+            #[serde(default)]
+            confirm: bool,
+        }
+
+        let params: ExportParams = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+        if !params.confirm {
+            return Err(JsonRpcError::invalid_params("Exporting reveals the seed phrase and private key; pass confirm: true"));
         }
 
-        let _params: ExportParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+        let keystore = std::fs::read(&self.keystore)
+            .map_err(|_| "No wallet keystore found".to_string())?;
+        let wallet = Wallet::from_keystore(&keystore, &params.passphrase)
+            .map_err(JsonRpcError::invalid_params)?;
+        warn!("Wallet {} exported over RPC", wallet.public_key_hex());
 
-        // Wallet export (seed phrase, private key) must be done via Tari wallet directly
-        Err("Wallet export must be done via Tari wallet CLI or Aurora wallet for security. The L2 marketplace does not store private keys or seed phrases.".to_string())
+        let seed_phrase = wallet.seed_phrase();
+        Ok(serde_json::json!({
+            "address": wallet.address(),
+            "public_key": wallet.public_key_hex(),
+            "private_key": wallet.export_private_key().as_str(),
+            "seed_phrase": seed_phrase.as_ref().map(|phrase| phrase.as_str()),
+        }))
     }
 
-    async fn wallet_sign(&self, params: Option<Value>) -> Result<Value, String> {
+    /// Sign a message with the unlocked wallet's key, under the
+    /// [`SigningContext::WalletMessage`] tag so the signature cannot pass
+    /// for one over a channel update, listing or other protocol message
+    async fn wallet_sign(&self, params: Option<Value>) -> RpcResult {
         #[derive(serde::Deserialize)]
         struct SignParams {
-            address: String,
+            /// Token returned by `wallet_unlock`
+            session: String,
+            /// Must name the unlocked wallet when given
+            #[serde(default)]
+            address: Option<String>,
             message: String,
         }
 
-        let _params: SignParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
+        let params: SignParams = serde_json::from_value(
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let wallet = self.session_wallet(&params.session).await?;
+        if let Some(address) = &params.address {
+            if *address != wallet.address() && *address != wallet.address_hex() {
+                return Err(JsonRpcError::invalid_params("Address is not the unlocked wallet"));
+            }
+        }
 
-        // Message signing must be done via Tari wallet gRPC
-        // TODO: Implement wallet gRPC signing method when available
-        Err("Message signing via wallet gRPC not yet implemented. Use Tari wallet CLI for signing.".to_string())
+        let keypair = wallet.channel_keypair().map_err(JsonRpcError::internal)?;
+        let signature = keypair.sign_with_context(SigningContext::WalletMessage, params.message.as_bytes());
+        Ok(serde_json::json!({
+            "public_key": keypair.public_key(),
+            "signature": signature
+        }))
     }
 
-    async fn get_l1_balance(&self, params: Option<Value>) -> Result<Value, String> {
+    /// L1 balance of the unlocked wallet, found by scanning with its key
+    async fn get_l1_balance(&self, params: Option<Value>) -> RpcResult {
         #[derive(Deserialize)]
         struct BalanceParams {
-            address: String,
-            #[serde(default)]
-            seed_phrase: Option<String>,
+            /// Token returned by `wallet_unlock`
+            session: String,
+            /// Must name the unlocked wallet when given
             #[serde(default)]
-            private_key: Option<String>,
+            address: Option<String>,
         }
 
         let params: BalanceParams = serde_json::from_value(
-            params.ok_or("Missing parameters")?
-        ).map_err(|e| e.to_string())?;
-
-        // Get wallet's view key for scanning
-        use tari_l2_marketplace::Wallet;
-        let wallet = if let Some(seed) = params.seed_phrase {
-            Wallet::from_seed_phrase(&seed)
-                .map_err(|e| format!("Invalid seed phrase: {}", e))?
-        } else if let Some(pk) = params.private_key {
-            Wallet::from_private_key(&pk)
-                .map_err(|e| format!("Invalid private key: {}", e))?
-        } else {
-            // Try to load current wallet from file
-            let wallet_data = std::fs::read_to_string("./data/current_wallet.json")
-                .map_err(|_| "No wallet found. Please provide seed_phrase or private_key, or create a wallet first".to_string())?;
-
-            let wallet_json: serde_json::Value = serde_json::from_str(&wallet_data)
-                .map_err(|e| format!("Invalid wallet file: {}", e))?;
-
-            let seed_phrase = wallet_json["seed_phrase"].as_str()
-                .ok_or("Wallet file missing seed_phrase")?;
-
-            Wallet::from_seed_phrase(seed_phrase)
-                .map_err(|e| format!("Failed to load wallet: {}", e))?
-        };
+            params.ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))?
+        ).map_err(JsonRpcError::invalid_params)?;
+
+        let wallet = self.session_wallet(&params.session).await?;
+        if let Some(address) = &params.address {
+            if *address != wallet.address() && *address != wallet.address_hex() {
+                return Err(JsonRpcError::invalid_params("Address is not the unlocked wallet"));
+            }
+        }
 
         // Extract the private spend key (used as view key for scanning)
         let view_key_hex = wallet.export_private_key();
-        let view_key_bytes = hex::decode(&view_key_hex)
+        let view_key_bytes = hex::decode(view_key_hex.as_str())
             .map_err(|e| format!("Failed to decode private key: {}", e))?;
 
         use tari_crypto::ristretto::RistrettoSecretKey;
@@ -1,5 +1,10 @@
+pub mod access;
 pub mod api;
 pub mod server;
+pub mod session;
+pub mod websocket;
 
-pub use api::{RpcApi, JsonRpcRequest, JsonRpcResponse};
+pub use api::{RpcApi, JsonRpcRequest, JsonRpcResponse, JsonRpcError};
+pub use access::{AccessLevel, ApiKeys};
 pub use server::RpcServer;
+pub use session::WalletSession;
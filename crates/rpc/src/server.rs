@@ -1,34 +1,78 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::convert::Infallible;
+use std::time::Duration;
 use hyper::{
     Body, Method, Request, Response, Server, StatusCode,
     service::{make_service_fn, service_fn},
 };
-use tracing::{info, error, debug};
-use crate::api::{RpcApi, JsonRpcRequest};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, error, debug, warn};
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tari_l2_marketplace::{rpc_request_payload, SignedAction};
+use crate::access::{bearer_key, query_key, AccessLevel, ApiKeys, RequestSignature, PUBLIC_KEY_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::api::{RpcApi, JsonRpcError, JsonRpcRequest, JsonRpcResponse};
+use crate::websocket;
+
+/// How often an idle wallet session is checked for expiry
+const WALLET_EXPIRY_INTERVAL: Duration = Duration::from_secs(10);
 
 /// RPC server with HTTP support for JSON-RPC
 pub struct RpcServer {
     api: Arc<RpcApi>,
     addr: SocketAddr,
+    keys: Arc<ApiKeys>,
+    origins: Arc<Vec<String>>,
 }
 
 impl RpcServer {
     pub fn new(api: Arc<RpcApi>, addr: SocketAddr) -> Self {
-        Self { api, addr }
+        Self { api, addr, keys: Arc::new(ApiKeys::default()), origins: Arc::new(Vec::new()) }
+    }
+
+    /// Require one of `keys`, or a signature by one of their owners, on
+    /// every request
+    pub fn with_api_keys(mut self, keys: ApiKeys) -> Self {
+        self.keys = Arc::new(keys);
+        self
+    }
+
+    /// Let pages served from `origins`, such as `http://localhost:8080`,
+    /// call the node. Browsers send the page's origin with every request;
+    /// one not listed is refused, so another website cannot drive the node
+    /// through a visitor's browser. Clients that send no `Origin` header are
+    /// not browsers and are let through.
+    pub fn with_allowed_origins(mut self, origins: Vec<String>) -> Self {
+        self.origins = Arc::new(origins);
+        self
     }
 
     /// Start the HTTP JSON-RPC server
     pub async fn start(&self) -> Result<(), Box<dyn std::error::Error>> {
         let api = self.api.clone();
+        let keys = self.keys.clone();
+        let origins = self.origins.clone();
+
+        // Drop an idle wallet session even if nobody calls in again
+        let expiring = api.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(WALLET_EXPIRY_INTERVAL);
+            loop {
+                interval.tick().await;
+                expiring.expire_wallet_session().await;
+            }
+        });
 
         let make_svc = make_service_fn(move |_conn| {
             let api = api.clone();
+            let keys = keys.clone();
+            let origins = origins.clone();
             async move {
                 Ok::<_, Infallible>(service_fn(move |req| {
                     let api = api.clone();
-                    async move { handle_request(req, api).await }
+                    let keys = keys.clone();
+                    let origins = origins.clone();
+                    async move { handle_request(req, api, keys, origins).await }
                 }))
             }
         });
@@ -45,13 +89,59 @@ impl RpcServer {
 async fn handle_request(
     req: Request<Body>,
     api: Arc<RpcApi>,
+    keys: Arc<ApiKeys>,
+    origins: Arc<Vec<String>>,
 ) -> Result<Response<Body>, Infallible> {
+    // A browser request from a page not in `rpc.allowed_origins` is refused
+    // outright, preflight included, whatever it asks for
+    let origin = match request_origin(&req, &origins) {
+        Ok(origin) => origin,
+        Err(()) => {
+            warn!("Refused request from origin {:?}", req.headers().get("Origin"));
+            return Ok(Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::from("Origin not allowed"))
+                .unwrap());
+        }
+    };
+    let origin = origin.as_deref();
+
     // Handle CORS preflight
     if req.method() == Method::OPTIONS {
         return Ok(cors_response(Response::builder()
             .status(StatusCode::OK)
             .body(Body::empty())
-            .unwrap()));
+            .unwrap(), origin));
+    }
+
+    let key = req.headers().get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(bearer_key)
+        .or_else(|| req.uri().query().filter(|_| is_websocket_upgrade(&req)).and_then(query_key))
+        .map(str::to_string);
+    let header = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok());
+    let signature = match RequestSignature::from_headers(header(PUBLIC_KEY_HEADER), header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER)) {
+        Some(Ok(signature)) => Some(signature),
+        Some(Err(e)) => return Ok(unauthorized(&e, origin)),
+        None => None,
+    };
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+
+    // Operator alerts are streamed rather than polled, and JSON-RPC and event
+    // subscriptions are also served over a WebSocket. Neither has a body.
+    if req.method() == Method::GET && (path == "/alerts" || path == "/ws") {
+        let access = match authenticate(&api, &keys, key.as_deref(), signature, &method, &path, &[]).await {
+            Ok(access) => access,
+            Err(e) => return Ok(unauthorized(&e, origin)),
+        };
+        if path == "/ws" {
+            return Ok(websocket_upgrade(req, api, access));
+        }
+        if access == AccessLevel::Public {
+            return Ok(unauthorized("Alerts need an API key", origin));
+        }
+        return Ok(cors_response(alert_stream(&api), origin));
     }
 
     // Only accept POST requests for JSON-RPC
@@ -59,7 +149,7 @@ async fn handle_request(
         return Ok(cors_response(Response::builder()
             .status(StatusCode::METHOD_NOT_ALLOWED)
             .body(Body::from("Method not allowed. Use POST for JSON-RPC"))
-            .unwrap()));
+            .unwrap(), origin));
     }
 
     // Read the request body
@@ -70,10 +160,15 @@ async fn handle_request(
             return Ok(cors_response(Response::builder()
                 .status(StatusCode::BAD_REQUEST)
                 .body(Body::from(format!("Failed to read request body: {}", e)))
-                .unwrap()));
+                .unwrap(), origin));
         }
     };
 
+    let access = match authenticate(&api, &keys, key.as_deref(), signature, &method, &path, &body_bytes).await {
+        Ok(access) => access,
+        Err(e) => return Ok(unauthorized(&e, origin)),
+    };
+
     // Parse JSON-RPC request
     let rpc_request: JsonRpcRequest = match serde_json::from_slice(&body_bytes) {
         Ok(req) => req,
@@ -91,14 +186,24 @@ async fn handle_request(
                 .status(StatusCode::OK)
                 .header("Content-Type", "application/json")
                 .body(Body::from(serde_json::to_string(&error_response).unwrap()))
-                .unwrap()));
+                .unwrap(), origin));
         }
     };
 
     debug!("Received RPC request: method={}, id={:?}", rpc_request.method, rpc_request.id);
 
-    // Handle the request
-    let response = api.handle_request(rpc_request).await;
+    // Handle the request if the key allows it
+    let response = if access.permits(&rpc_request.method) {
+        api.handle_request(rpc_request).await
+    } else {
+        warn!("API key with {:?} access refused {}", access, rpc_request.method);
+        JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            result: None,
+            error: Some(JsonRpcError::forbidden(&rpc_request.method)),
+            id: rpc_request.id,
+        }
+    };
 
     // Serialize and send response
     let response_json = match serde_json::to_string(&response) {
@@ -121,14 +226,236 @@ async fn handle_request(
         .status(StatusCode::OK)
         .header("Content-Type", "application/json")
         .body(Body::from(response_json))
-        .unwrap()))
+        .unwrap(), origin))
+}
+
+/// Access granted to a request, from its owner signature when it has one
+/// and its API key otherwise
+async fn authenticate(
+    api: &RpcApi,
+    keys: &ApiKeys,
+    key: Option<&str>,
+    signature: Option<RequestSignature>,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> Result<AccessLevel, String> {
+    if !keys.required() {
+        return Ok(AccessLevel::Open);
+    }
+    let Some(signature) = signature else {
+        return keys.level(key).ok_or_else(|| "Missing or unknown API key".to_string());
+    };
+    if !keys.is_owner(&signature.public_key) {
+        return Err(format!("{} is not an owner key", signature.public_key));
+    }
+    let action = SignedAction {
+        payload: rpc_request_payload(method, path, body),
+        public_key: signature.public_key,
+        signature: signature.signature,
+        timestamp: signature.timestamp,
+    };
+    api.accept_request_signature(&action).await?;
+    Ok(AccessLevel::Admin)
 }
 
-/// Add CORS headers to response
-fn cors_response(mut response: Response<Body>) -> Response<Body> {
+fn unauthorized(reason: &str, origin: Option<&str>) -> Response<Body> {
+    warn!("Rejected RPC request: {}", reason);
+    cors_response(Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header("WWW-Authenticate", "Bearer")
+        .body(Body::from(reason.to_string()))
+        .unwrap(), origin)
+}
+
+/// Stream alerts as they are raised, one JSON object per line, until the
+/// client disconnects
+fn alert_stream(api: &RpcApi) -> Response<Body> {
+    let mut alerts = api.subscribe_alerts();
+    let (mut sender, body) = Body::channel();
+
+    tokio::spawn(async move {
+        loop {
+            let alert = match alerts.recv().await {
+                Ok(alert) => alert,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Alert stream fell behind, {} alerts skipped", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            let mut line = match serde_json::to_vec(&alert) {
+                Ok(line) => line,
+                Err(e) => {
+                    error!("Failed to serialize alert: {}", e);
+                    continue;
+                }
+            };
+            line.push(b'\n');
+            if sender.send_data(line.into()).await.is_err() {
+                debug!("Alert stream client disconnected");
+                break;
+            }
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/x-ndjson")
+        .body(body)
+        .unwrap()
+}
+
+fn is_websocket_upgrade(req: &Request<Body>) -> bool {
+    req.headers().get("Upgrade")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+}
+
+/// The `Origin` of a request from an allowed page, None for a client that
+/// is not a browser and sends none, or Err for any other page
+fn request_origin(req: &Request<Body>, allowed: &[String]) -> Result<Option<String>, ()> {
+    let Some(origin) = req.headers().get("Origin") else {
+        return Ok(None);
+    };
+    let origin = origin.to_str().map_err(|_| ())?;
+    if allowed.iter().any(|a| a.trim_end_matches('/').eq_ignore_ascii_case(origin)) {
+        Ok(Some(origin.to_string()))
+    } else {
+        Err(())
+    }
+}
+
+/// Accept a WebSocket handshake and serve the socket once hyper hands the
+/// connection over
+fn websocket_upgrade(mut req: Request<Body>, api: Arc<RpcApi>, access: AccessLevel) -> Response<Body> {
+    let accept = match req.headers().get("Sec-WebSocket-Key") {
+        Some(key) if is_websocket_upgrade(&req) => derive_accept_key(key.as_bytes()),
+        _ => {
+            return Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from("Expected a WebSocket upgrade"))
+                .unwrap();
+        }
+    };
+
+    let on_upgrade = hyper::upgrade::on(&mut req);
+    tokio::spawn(async move {
+        match on_upgrade.await {
+            Ok(upgraded) => websocket::serve(upgraded, api, access).await,
+            Err(e) => warn!("WebSocket upgrade failed: {}", e),
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header("Upgrade", "websocket")
+        .header("Connection", "Upgrade")
+        .header("Sec-WebSocket-Accept", accept)
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// Add CORS headers to response. Only an allowed `origin` is let read it;
+/// requests without one are not from a browser and need no CORS.
+fn cors_response(mut response: Response<Body>, origin: Option<&str>) -> Response<Body> {
     let headers = response.headers_mut();
-    headers.insert("Access-Control-Allow-Origin", "*".parse().unwrap());
+    headers.insert("Vary", "Origin".parse().unwrap());
+    let Some(origin) = origin.and_then(|origin| origin.parse::<hyper::header::HeaderValue>().ok()) else {
+        return response;
+    };
+    headers.insert("Access-Control-Allow-Origin", origin);
     headers.insert("Access-Control-Allow-Methods", "POST, GET, OPTIONS".parse().unwrap());
-    headers.insert("Access-Control-Allow-Headers", "Content-Type".parse().unwrap());
+    headers.insert(
+        "Access-Control-Allow-Headers",
+        format!("Content-Type, Authorization, {}, {}, {}", PUBLIC_KEY_HEADER, TIMESTAMP_HEADER, SIGNATURE_HEADER).parse().unwrap(),
+    );
     response
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_l1_client::{L1Config, TariL1Client};
+    use tari_l2_marketplace::testing::TestHarness;
+
+    fn upgrade(uri: &str, origin: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .header("Connection", "Upgrade")
+            .header("Upgrade", "websocket")
+            .header("Sec-WebSocket-Version", "13")
+            .header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==");
+        if let Some(origin) = origin {
+            builder = builder.header("Origin", origin);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    fn post(method: &str, origin: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header("Content-Type", "text/plain");
+        if let Some(origin) = origin {
+            builder = builder.header("Origin", origin);
+        }
+        let body = serde_json::json!({"jsonrpc": "2.0", "method": method, "params": {}, "id": 1});
+        builder.body(Body::from(body.to_string())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_websocket_upgrade_checks_origin_and_key() {
+        let harness = TestHarness::builder().nodes(1).build().await;
+        let l1 = Arc::new(TariL1Client::offline(L1Config::default()));
+        let api = Arc::new(RpcApi::new(harness.node(0).manager.clone(), l1));
+        let origins = Arc::new(vec!["http://localhost:8080".to_string()]);
+        let send = |req: Request<Body>, keys: ApiKeys| handle_request(req, api.clone(), Arc::new(keys), origins.clone());
+
+        let response = send(upgrade("/ws", Some("http://localhost:8080")), ApiKeys::default()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+        assert_eq!(response.headers()["Sec-WebSocket-Accept"], "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+
+        // Another website cannot open a socket through a visitor's browser,
+        // even on a node without keys
+        let response = send(upgrade("/ws", Some("https://attacker.example")), ApiKeys::default()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // Non-browser clients send no origin, but still need a key once
+        // keys are configured
+        let keys = ApiKeys { admin: vec!["admin-key".to_string()], ..ApiKeys::default() };
+        let response = send(upgrade("/ws", None), keys.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = send(upgrade("/ws?api_key=admin-key", None), keys).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+    }
+
+    #[tokio::test]
+    async fn test_json_rpc_checks_origin() {
+        let harness = TestHarness::builder().nodes(1).build().await;
+        let l1 = Arc::new(TariL1Client::offline(L1Config::default()));
+        let api = Arc::new(RpcApi::new(harness.node(0).manager.clone(), l1));
+        let origins = Arc::new(vec!["http://localhost:8080".to_string()]);
+        let send = |req: Request<Body>| handle_request(req, api.clone(), Arc::new(ApiKeys::default()), origins.clone());
+        let result = |response: Response<Body>| async move {
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        // Only an allowed page may read the reply
+        let response = send(post("get_node_info", Some("http://localhost:8080"))).await.unwrap();
+        assert_eq!(response.headers()["Access-Control-Allow-Origin"], "http://localhost:8080");
+        assert!(result(response).await["result"].is_object());
+        let response = send(post("get_node_info", None)).await.unwrap();
+        assert!(!response.headers().contains_key("Access-Control-Allow-Origin"));
+
+        // Any other page is refused before the call is made
+        let response = send(post("transfer", Some("https://attacker.example"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // Moving funds needs a key even from an allowed page on a keyless node
+        let response = send(post("transfer", Some("http://localhost:8080"))).await.unwrap();
+        assert_eq!(result(response).await["error"]["code"], JsonRpcError::FORBIDDEN);
+    }
+}
@@ -0,0 +1,241 @@
+//! Wallet sessions: the wallet held in memory between `wallet_unlock` and
+//! `wallet_lock`.
+//!
+//! The wallet is stored encrypted under the data directory, see
+//! [`Wallet::to_keystore`]. Unlocking it with its passphrase keeps the
+//! decrypted wallet in memory, so endpoints that need its keys can use it
+//! without a seed phrase or private key in their params. Unlocking returns a
+//! session token, and only calls presenting it may use the wallet. The
+//! session ends on `wallet_lock`, or once it has gone unused for its idle
+//! timeout; each use restarts the timeout, and [`WalletSession::expire`]
+//! drops it on a timer once idle.
+//!
+//! The keystore may hold the only copy of a funded wallet, so it is never
+//! overwritten: [`create_keystore`] refuses when one exists, and
+//! [`backup_keystore`] copies it, still encrypted, before it is moved aside.
+//! Wallets that earlier versions kept in the clear in [`LEGACY_WALLET_FILE`]
+//! are moved into the keystore by [`migrate_legacy_wallet`].
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tari_l2_common::Hash;
+use tari_l2_marketplace::Wallet;
+use crate::access::keys_equal;
+
+/// File under the data directory holding the encrypted wallet
+pub const KEYSTORE_FILE: &str = "wallet.keystore";
+
+/// Idle timeout when neither the node config nor the unlock call sets one
+pub const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 300;
+
+/// Longest idle timeout an unlock call may ask for
+pub const MAX_IDLE_TIMEOUT_SECS: u64 = 3600;
+
+/// File under the data directory where earlier versions kept the wallet's
+/// seed phrase and private key unencrypted
+pub const LEGACY_WALLET_FILE: &str = "current_wallet.json";
+
+pub fn keystore_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(KEYSTORE_FILE)
+}
+
+/// Store `wallet` in a new keystore at `path`, encrypted under
+/// `passphrase`. Fails if a keystore is already there.
+pub fn create_keystore(path: &Path, wallet: &Wallet, passphrase: &str) -> Result<(), String> {
+    if passphrase.is_empty() {
+        return Err("Keystore passphrase must not be empty".to_string());
+    }
+    let keystore = wallet.to_keystore(passphrase)?;
+    write_new(path, &keystore).map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists => format!(
+            "A wallet keystore already exists at {:?}; back it up and move it aside before storing another wallet",
+            path
+        ),
+        _ => format!("Failed to write keystore {:?}: {}", path, e),
+    })
+}
+
+/// Copy the keystore under `data_dir` to `destination`, still encrypted
+/// under its passphrase. Fails rather than overwrite `destination`.
+pub fn backup_keystore(data_dir: &Path, destination: &Path) -> Result<(), String> {
+    let source = keystore_path(data_dir);
+    let keystore = fs::read(&source)
+        .map_err(|e| format!("Failed to read keystore {:?}: {}", source, e))?;
+    write_new(destination, &keystore)
+        .map_err(|e| format!("Failed to write backup {:?}: {}", destination, e))
+}
+
+/// Move a wallet left in [`LEGACY_WALLET_FILE`] into the keystore under
+/// `passphrase`, then delete the plaintext file. Returns the wallet's
+/// address, or `None` when there is no legacy file.
+pub fn migrate_legacy_wallet(data_dir: &Path, passphrase: &str) -> Result<Option<String>, String> {
+    let legacy = data_dir.join(LEGACY_WALLET_FILE);
+    let contents = match fs::read_to_string(&legacy) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {:?}: {}", legacy, e)),
+    };
+    let legacy_wallet: serde_json::Value = serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid wallet file {:?}: {}", legacy, e))?;
+
+    let wallet = match (legacy_wallet["seed_phrase"].as_str(), legacy_wallet["private_key"].as_str()) {
+        (Some(seed_phrase), _) => Wallet::from_seed_phrase(seed_phrase)?,
+        (None, Some(private_key)) => Wallet::from_private_key(private_key)?,
+        (None, None) => return Err(format!("{:?} holds neither a seed phrase nor a private key", legacy)),
+    };
+    create_keystore(&keystore_path(data_dir), &wallet, passphrase)?;
+    fs::remove_file(&legacy)
+        .map_err(|e| format!("Wallet stored in the keystore, but failed to delete {:?}: {}", legacy, e))?;
+    Ok(Some(wallet.address()))
+}
+
+/// Write `bytes` to a file that must not exist yet, readable only by the
+/// owner
+fn write_new(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+struct Unlocked {
+    wallet: Wallet,
+    token: String,
+    idle_timeout: Duration,
+    last_used: Instant,
+}
+
+/// The unlocked wallet, if any
+pub struct WalletSession {
+    unlocked: Mutex<Option<Unlocked>>,
+    default_idle_timeout: Duration,
+}
+
+impl WalletSession {
+    pub fn new(default_idle_timeout: Duration) -> Self {
+        Self { unlocked: Mutex::new(None), default_idle_timeout }
+    }
+
+    /// Hold `wallet` until it is locked or idle for `idle_timeout`, the
+    /// session default when `None`. Replaces any wallet already unlocked,
+    /// invalidating its token. Returns the new session's token and the
+    /// timeout applied.
+    pub async fn unlock(&self, wallet: Wallet, idle_timeout: Option<Duration>) -> (String, Duration) {
+        let idle_timeout = idle_timeout
+            .unwrap_or(self.default_idle_timeout)
+            .min(Duration::from_secs(MAX_IDLE_TIMEOUT_SECS));
+        let token = Hash::random().to_string();
+        *self.unlocked.lock().await = Some(Unlocked { wallet, token: token.clone(), idle_timeout, last_used: Instant::now() });
+        (token, idle_timeout)
+    }
+
+    /// Drop the wallet unlocked under `token`. Returns whether one was held.
+    pub async fn lock(&self, token: &str) -> bool {
+        let mut unlocked = self.unlocked.lock().await;
+        if !unlocked.as_ref().is_some_and(|session| keys_equal(&session.token, token)) {
+            return false;
+        }
+        *unlocked = None;
+        true
+    }
+
+    /// The wallet unlocked under `token`, restarting its idle timeout.
+    /// `None` when locked, idle for too long, in which case the wallet is
+    /// dropped, or when `token` is not the session's.
+    pub async fn wallet(&self, token: &str) -> Option<Wallet> {
+        let mut unlocked = self.unlocked.lock().await;
+        let session = unlocked.as_mut()?;
+        if session.last_used.elapsed() >= session.idle_timeout {
+            *unlocked = None;
+            return None;
+        }
+        if !keys_equal(&session.token, token) {
+            return None;
+        }
+        session.last_used = Instant::now();
+        Some(session.wallet.clone())
+    }
+
+    /// Drop the unlocked wallet if it has been idle for its timeout. Run
+    /// periodically, so an abandoned session does not keep the keys in
+    /// memory until its next use. Returns whether a wallet was dropped.
+    pub async fn expire(&self) -> bool {
+        let mut unlocked = self.unlocked.lock().await;
+        if !unlocked.as_ref().is_some_and(|session| session.last_used.elapsed() >= session.idle_timeout) {
+            return false;
+        }
+        *unlocked = None;
+        true
+    }
+}
+
+impl Default for WalletSession {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(DEFAULT_IDLE_TIMEOUT_SECS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_keystore_is_never_overwritten_and_legacy_wallets_migrate() {
+        let dir = TempDir::new().unwrap();
+        let legacy = Wallet::new();
+        let seed_phrase = legacy.seed_phrase().unwrap();
+        let legacy_file = dir.path().join(LEGACY_WALLET_FILE);
+        fs::write(&legacy_file, serde_json::json!({ "seed_phrase": seed_phrase.as_str() }).to_string()).unwrap();
+
+        assert_eq!(migrate_legacy_wallet(dir.path(), "secret").unwrap(), Some(legacy.address()));
+        assert!(!legacy_file.exists());
+        assert_eq!(migrate_legacy_wallet(dir.path(), "secret").unwrap(), None);
+        let stored = fs::read(keystore_path(dir.path())).unwrap();
+        assert_eq!(Wallet::from_keystore(&stored, "secret").unwrap().address(), legacy.address());
+
+        // A second wallet cannot replace the first
+        let err = create_keystore(&keystore_path(dir.path()), &Wallet::new(), "other").unwrap_err();
+        assert!(err.contains("already exists"));
+        assert_eq!(fs::read(keystore_path(dir.path())).unwrap(), stored);
+
+        let backup = dir.path().join("backup.keystore");
+        backup_keystore(dir.path(), &backup).unwrap();
+        assert_eq!(fs::read(&backup).unwrap(), stored);
+        assert!(backup_keystore(dir.path(), &backup).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wallet_needs_the_session_token() {
+        let session = WalletSession::default();
+        let wallet = Wallet::new();
+        let (token, _) = session.unlock(wallet.clone(), None).await;
+        assert!(session.wallet("guess").await.is_none());
+        assert!(!session.lock("guess").await);
+        assert_eq!(session.wallet(&token).await.map(|w| w.address()), Some(wallet.address()));
+
+        // Unlocking again invalidates the previous token
+        let (second, _) = session.unlock(wallet, None).await;
+        assert!(session.wallet(&token).await.is_none());
+        assert!(!session.expire().await);
+        assert!(session.lock(&second).await);
+        assert!(session.wallet(&second).await.is_none());
+
+        // An idle session is dropped without waiting for its next use
+        session.unlock(Wallet::new(), Some(Duration::ZERO)).await;
+        assert!(session.expire().await);
+        assert!(session.unlocked.lock().await.is_none());
+    }
+}
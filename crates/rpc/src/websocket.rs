@@ -0,0 +1,209 @@
+//! JSON-RPC over WebSocket, with push subscriptions.
+//!
+//! A `GET /ws` upgrade opens a socket that accepts the same JSON-RPC
+//! requests as HTTP, one per text message, plus four subscription methods:
+//! `subscribe_listings` (optionally for one `seller`), `subscribe_orders`
+//! and `subscribe_channel_events` (optionally for one `channel_id`), and
+//! `subscribe_saved_searches` for listings matching a saved search. Each
+//! returns a subscription ID; matching events then arrive as notifications:
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "method": "subscription", "params": {"subscription": 1, "result": {...}}}
+//! ```
+//!
+//! `unsubscribe` with `{"subscription": id}` stops one. Subscriptions end
+//! with the socket.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use futures::{SinkExt, StreamExt};
+use hyper::upgrade::Upgraded;
+use serde::Deserialize;
+use serde_json::Value;
+use tari_l2_common::{Hash, PublicKey};
+use tari_l2_marketplace::{EventTopic, MarketplaceEvent};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::protocol::{Message, Role};
+use tracing::{debug, warn};
+use crate::access::AccessLevel;
+use crate::api::{JsonRpcError, JsonRpcRequest, JsonRpcResponse, RpcApi};
+
+/// Most subscriptions one socket may hold
+const MAX_SUBSCRIPTIONS: usize = 32;
+
+/// What one subscription receives
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subscription {
+    pub topic: EventTopic,
+    /// Only events in this channel
+    pub channel_id: Option<Hash>,
+    /// Only listings by this seller
+    pub seller: Option<PublicKey>,
+}
+
+impl Subscription {
+    /// The subscription a `subscribe_*` call asks for, or None for other methods
+    pub fn from_request(method: &str, params: Option<Value>) -> Option<Result<Self, JsonRpcError>> {
+        #[derive(Deserialize, Default)]
+        struct Params {
+            #[serde(default)]
+            channel_id: Option<Hash>,
+            #[serde(default)]
+            seller: Option<PublicKey>,
+        }
+
+        let topic = match method {
+            "subscribe_listings" => EventTopic::Listings,
+            "subscribe_orders" => EventTopic::Orders,
+            "subscribe_channel_events" => EventTopic::Channels,
+            "subscribe_saved_searches" => EventTopic::SavedSearches,
+            _ => return None,
+        };
+        let params: Params = match params {
+            Some(params) if !params.is_null() => match serde_json::from_value(params) {
+                Ok(params) => params,
+                Err(e) => return Some(Err(JsonRpcError::invalid_params(e))),
+            },
+            _ => Params::default(),
+        };
+        if matches!(topic, EventTopic::Listings | EventTopic::SavedSearches) && params.channel_id.is_some() {
+            return Some(Err(JsonRpcError::invalid_params("Listings are not in a channel")));
+        }
+        if topic != EventTopic::Listings && params.seller.is_some() {
+            return Some(Err(JsonRpcError::invalid_params("Only listings can be filtered by seller")));
+        }
+        Some(Ok(Self { topic, channel_id: params.channel_id, seller: params.seller }))
+    }
+
+    pub fn matches(&self, event: &MarketplaceEvent) -> bool {
+        event.topic() == self.topic
+            && self.channel_id.map_or(true, |id| event.channel_id() == Some(id))
+            && self.seller.map_or(true, |seller| event.seller() == Some(seller))
+    }
+}
+
+/// Serve JSON-RPC and subscriptions on an upgraded connection until the
+/// client goes away
+pub async fn serve(upgraded: Upgraded, api: Arc<RpcApi>, access: AccessLevel) {
+    let socket = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+    let (mut sink, mut stream) = socket.split();
+    let mut events = api.subscribe_events();
+    let mut subscriptions: HashMap<u64, Subscription> = HashMap::new();
+    let mut next_id = 0u64;
+
+    loop {
+        tokio::select! {
+            message = stream.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        debug!("WebSocket read failed: {}", e);
+                        break;
+                    }
+                };
+                let response = match serde_json::from_str::<JsonRpcRequest>(&text) {
+                    Ok(request) => handle(request, &api, access, &mut subscriptions, &mut next_id).await,
+                    Err(_) => JsonRpcResponse::from_result(Err(JsonRpcError::new(-32700, "Parse error")), Value::Null),
+                };
+                let Ok(reply) = serde_json::to_string(&response) else {
+                    continue;
+                };
+                if sink.send(Message::Text(reply)).await.is_err() {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("WebSocket subscriber fell behind, {} events skipped", missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                for (id, subscription) in &subscriptions {
+                    if !subscription.matches(&event) {
+                        continue;
+                    }
+                    let notification = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "method": "subscription",
+                        "params": { "subscription": id, "result": &event }
+                    });
+                    if sink.send(Message::Text(notification.to_string())).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+    debug!("WebSocket client disconnected");
+}
+
+async fn handle(
+    request: JsonRpcRequest,
+    api: &RpcApi,
+    access: AccessLevel,
+    subscriptions: &mut HashMap<u64, Subscription>,
+    next_id: &mut u64,
+) -> JsonRpcResponse {
+    if !access.permits(&request.method) {
+        warn!("API key with {:?} access refused {}", access, request.method);
+        return JsonRpcResponse::from_result(Err(JsonRpcError::forbidden(&request.method)), request.id);
+    }
+
+    if let Some(subscription) = Subscription::from_request(&request.method, request.params.clone()) {
+        let result = subscription.and_then(|subscription| {
+            if subscriptions.len() >= MAX_SUBSCRIPTIONS {
+                return Err(JsonRpcError::invalid_params(format!("At most {} subscriptions per connection", MAX_SUBSCRIPTIONS)));
+            }
+            *next_id += 1;
+            subscriptions.insert(*next_id, subscription);
+            Ok(serde_json::json!(*next_id))
+        });
+        return JsonRpcResponse::from_result(result, request.id);
+    }
+
+    if request.method == "unsubscribe" {
+        #[derive(Deserialize)]
+        struct Params {
+            subscription: u64,
+        }
+        let result = request.params.clone()
+            .ok_or_else(|| JsonRpcError::invalid_params("Missing parameters"))
+            .and_then(|params| serde_json::from_value::<Params>(params).map_err(JsonRpcError::invalid_params))
+            .map(|params| serde_json::json!(subscriptions.remove(&params.subscription).is_some()));
+        return JsonRpcResponse::from_result(result, request.id);
+    }
+
+    api.handle_request(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_state_channel::state::OrderStatus;
+
+    #[test]
+    fn test_subscriptions_filter_events() {
+        let channel_id = Hash::new([1; 32]);
+        let other = Hash::new([2; 32]);
+        let params = serde_json::json!({ "channel_id": channel_id });
+        let orders = Subscription::from_request("subscribe_orders", Some(params)).unwrap().unwrap();
+
+        let order = |channel_id| MarketplaceEvent::OrderStatus { channel_id, order_id: Hash::new([3; 32]), previous: None, status: OrderStatus::Shipping };
+        assert!(orders.matches(&order(channel_id)));
+        assert!(!orders.matches(&order(other)));
+        assert!(!orders.matches(&MarketplaceEvent::ChannelUpdate { channel_id, nonce: 1 }));
+
+        let channels = Subscription::from_request("subscribe_channel_events", None).unwrap().unwrap();
+        assert!(channels.matches(&MarketplaceEvent::ChannelUpdate { channel_id: other, nonce: 1 }));
+
+        let params = serde_json::json!({ "seller": PublicKey::new([4; 32]) });
+        assert!(Subscription::from_request("subscribe_orders", Some(params)).unwrap().is_err());
+        assert!(Subscription::from_request("get_orders", None).is_none());
+    }
+}
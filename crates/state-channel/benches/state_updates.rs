@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use tari_l2_common::{Amount, crypto::KeyPair};
+use tari_l2_state_channel::{ChannelConfig, MarketplaceChannel, StateUpdate, update::SignedStateUpdate};
+
+fn keypairs() -> Vec<KeyPair> {
+    (1..=2u8).map(|i| KeyPair::from_private_key(&[i; 32]).unwrap()).collect()
+}
+
+fn open_channel(keypairs: &[KeyPair]) -> MarketplaceChannel {
+    let participants: Vec<_> = keypairs.iter().map(|kp| kp.public_key()).collect();
+    let initial_balances: HashMap<_, _> = participants.iter().map(|pk| (*pk, Amount::new(1_000_000))).collect();
+    let mut channel = MarketplaceChannel::new(ChannelConfig {
+        participants,
+        initial_balances,
+        challenge_period: 3600,
+    });
+    channel.activate().unwrap();
+    channel
+}
+
+fn sign(update: StateUpdate, nonce: u64, keypairs: &[KeyPair]) -> SignedStateUpdate {
+    let mut signed = SignedStateUpdate::new(update, nonce);
+    let message = signed.signing_message();
+    for kp in keypairs {
+        signed.add_signature(kp.public_key(), kp.sign(&message));
+    }
+    signed
+}
+
+fn transfer(keypairs: &[KeyPair]) -> StateUpdate {
+    StateUpdate::Transfer {
+        from: keypairs[0].public_key(),
+        to: keypairs[1].public_key(),
+        amount: Amount::new(10),
+    }
+}
+
+fn bench_state_updates(c: &mut Criterion) {
+    let keypairs = keypairs();
+    let participants: Vec<_> = keypairs.iter().map(|kp| kp.public_key()).collect();
+    let channel = open_channel(&keypairs);
+    let signed = sign(transfer(&keypairs), 1, &keypairs);
+
+    c.bench_function("state_update/sign", |b| {
+        b.iter(|| sign(transfer(&keypairs), 1, &keypairs))
+    });
+
+    c.bench_function("state_update/verify", |b| {
+        b.iter(|| assert!(signed.verify(&participants)))
+    });
+
+    c.bench_function("state_update/apply", |b| {
+        b.iter_batched(
+            || (channel.clone(), signed.clone()),
+            |(mut channel, signed)| channel.apply_update(signed).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("state_update/state_root", |b| {
+        b.iter(|| channel.get_state_root())
+    });
+}
+
+criterion_group!(benches, bench_state_updates);
+criterion_main!(benches);
@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tari_l2_common::{Amount, Hash, PublicKey, crypto};
-use crate::state::ChannelState;
-use crate::update::SignedStateUpdate;
+use tari_l2_common::{Amount, Hash, PublicKey, Signature, Timestamp, canonical, crypto};
+use crate::state::{ChannelState, OrderStatus};
+use crate::update::{SignedStateUpdate, StateUpdate};
 use tari_l2_common::{L2Error, error::Result};
 
 /// Configuration for creating a channel
@@ -22,7 +22,7 @@ pub enum ChannelStatus {
     Active,
     /// Channel is being cooperatively closed
     Closing,
-    /// Channel closure is being challenged
+    /// Channel closure is being challenged on L1, or was forced there
     Challenged,
     /// Channel is closed
     Closed,
@@ -49,23 +49,89 @@ pub struct MarketplaceChannel {
     /// Challenge period in seconds
     pub challenge_period: u64,
 
+    /// Balances the channel opened with; replays of `state_history` start here
+    #[serde(serialize_with = "canonical::sorted_map")]
+    pub initial_balances: HashMap<PublicKey, Amount>,
+
     /// History of signed state updates (kept for auditing)
     pub state_history: Vec<SignedStateUpdate>,
+
+    /// Participants' signatures over the genesis state, collected by the open handshake
+    #[serde(default, serialize_with = "canonical::sorted_map")]
+    pub genesis_signatures: HashMap<PublicKey, Signature>,
+
+    /// When the close was initiated; the challenge period runs from here
+    #[serde(default)]
+    pub close_initiated_at: Option<Timestamp>,
+
+    /// Throughput counters, kept up to date as updates are applied
+    #[serde(default)]
+    pub metrics: ChannelMetrics,
+}
+
+/// Running totals over a channel's applied updates
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChannelMetrics {
+    /// Updates applied since the channel opened
+    pub updates: u64,
+    /// When the first timestamped update was applied
+    pub first_update_at: Option<Timestamp>,
+    /// When the latest timestamped update was applied
+    pub last_update_at: Option<Timestamp>,
+    /// Value moved between participants by transfers and completed orders
+    pub volume: Amount,
+    /// Nonce of the state last anchored on L1
+    pub checkpointed_nonce: u64,
+    /// When the state was last anchored on L1
+    #[serde(default)]
+    pub checkpointed_at: Option<Timestamp>,
+    /// L1 block height of the last checkpoint
+    #[serde(default)]
+    pub checkpointed_height: Option<u64>,
+}
+
+impl Default for ChannelMetrics {
+    fn default() -> Self {
+        Self {
+            updates: 0,
+            first_update_at: None,
+            last_update_at: None,
+            volume: Amount::new(0),
+            checkpointed_nonce: 0,
+            checkpointed_at: None,
+            checkpointed_height: None,
+        }
+    }
+}
+
+impl ChannelMetrics {
+    /// Average update rate between the first and latest update. Spans
+    /// shorter than an hour count as a full hour so a burst of updates
+    /// does not read as a sustained rate.
+    pub fn updates_per_hour(&self) -> f64 {
+        let (Some(first), Some(last)) = (self.first_update_at, self.last_update_at) else {
+            return 0.0;
+        };
+        let span = last.as_secs().saturating_sub(first.as_secs()).max(3600);
+        self.updates as f64 * 3600.0 / span as f64
+    }
+
+    fn record_time(&mut self, now: Timestamp) {
+        self.first_update_at.get_or_insert(now);
+        self.last_update_at = Some(now);
+    }
 }
 
 impl MarketplaceChannel {
     /// Create a new channel
     pub fn new(config: ChannelConfig) -> Self {
-        let channel_data = bincode::serialize(&config.participants)
-            .expect("Serialization should not fail");
-        let channel_id = crypto::hash_data(&channel_data);
+        let channel_id = Self::compute_id(&config.participants);
 
         let collateral = config.initial_balances.values()
-            .fold(Amount::ZERO, |acc, &amount| {
-                acc.checked_add(amount).expect("Collateral overflow")
-            });
+            .sum::<Option<Amount>>()
+            .expect("Collateral overflow");
 
-        let state = ChannelState::new(config.participants.clone(), config.initial_balances);
+        let state = ChannelState::new(config.participants.clone(), config.initial_balances.clone());
 
         Self {
             channel_id,
@@ -74,10 +140,19 @@ impl MarketplaceChannel {
             state,
             status: ChannelStatus::Opening,
             challenge_period: config.challenge_period,
+            initial_balances: config.initial_balances,
             state_history: Vec::new(),
+            genesis_signatures: HashMap::new(),
+            close_initiated_at: None,
+            metrics: ChannelMetrics::default(),
         }
     }
 
+    /// Derive the channel ID for a set of participants
+    pub fn compute_id(participants: &[PublicKey]) -> Hash {
+        canonical::hash(participants).expect("Serialization should not fail")
+    }
+
     /// Apply a signed state update
     pub fn apply_update(&mut self, signed_update: SignedStateUpdate) -> Result<()> {
         // Verify channel is active
@@ -97,19 +172,98 @@ impl MarketplaceChannel {
 
         // Apply the update
         let new_state = signed_update.update.apply(self.state.clone())?;
+        let moved = self.value_moved(&signed_update.update);
 
         // Update channel state
         self.state = new_state;
         self.state_history.push(signed_update);
+        self.metrics.updates += 1;
+        self.metrics.volume = self.metrics.volume.saturating_add(moved);
 
         Ok(())
     }
 
+    /// Apply a signed state update received or committed at `now`
+    pub fn apply_update_at(&mut self, signed_update: SignedStateUpdate, now: Timestamp) -> Result<()> {
+        self.apply_update(signed_update)?;
+        self.metrics.record_time(now);
+        Ok(())
+    }
+
+    /// Value an update moves from one participant to another, judged
+    /// against the state before it is applied
+    fn value_moved(&self, update: &StateUpdate) -> Amount {
+        match update {
+            StateUpdate::Transfer { from, to, amount }
+            | StateUpdate::PayInvoice { from, to, amount, .. } if from != to => *amount,
+            StateUpdate::UpdateOrderStatus { order_id, status: OrderStatus::Completed } => self.state.orders.iter()
+                .find(|o| &o.id == order_id && o.status != OrderStatus::Completed)
+                .map(|o| o.amount)
+                .unwrap_or(Amount::new(0)),
+            _ => Amount::new(0),
+        }
+    }
+
+    /// Record that the current state was anchored on L1 at `now`, at
+    /// block `block_height`
+    pub fn mark_checkpointed(&mut self, now: Timestamp, block_height: u64) {
+        self.metrics.checkpointed_nonce = self.state.nonce;
+        self.metrics.checkpointed_at = Some(now);
+        self.metrics.checkpointed_height = Some(block_height);
+    }
+
+    /// Whether this exact update was already applied at its nonce, so a
+    /// redelivery can be treated as a no-op
+    pub fn is_duplicate(&self, signed_update: &SignedStateUpdate) -> bool {
+        if signed_update.nonce == 0 || signed_update.nonce > self.state.nonce {
+            return false;
+        }
+        let hash = signed_update.update.hash();
+        self.state_history.iter()
+            .rev()
+            .find(|applied| applied.nonce == signed_update.nonce)
+            .is_some_and(|applied| applied.update.hash() == hash)
+    }
+
     /// Get the latest state root for L1 checkpointing
     pub fn get_state_root(&self) -> Hash {
         self.state.merkle_root()
     }
 
+    /// Message participants sign to agree on closing at the current state
+    pub fn close_signing_message(&self) -> Vec<u8> {
+        let mut data = self.channel_id.to_vec();
+        data.extend_from_slice(self.get_state_root().as_bytes());
+        data.extend_from_slice(&self.state.nonce.to_le_bytes());
+        crypto::SigningContext::Close.message(&data)
+    }
+
+    /// Message participants sign to agree on opening with this configuration
+    pub fn genesis_signing_message(&self) -> Vec<u8> {
+        let genesis = ChannelState::new(self.participants.clone(), self.initial_balances.clone());
+        let mut data = self.channel_id.to_vec();
+        data.extend_from_slice(genesis.merkle_root().as_bytes());
+        data.extend_from_slice(&self.challenge_period.to_le_bytes());
+        crypto::SigningContext::Open.message(&data)
+    }
+
+    /// Record a participant's signature over the genesis state
+    pub fn add_genesis_signature(&mut self, participant: PublicKey, signature: Signature) -> Result<()> {
+        if !self.participants.contains(&participant) {
+            return Err(L2Error::ParticipantNotFound);
+        }
+        if !participant.verify(&self.genesis_signing_message(), &signature) {
+            return Err(L2Error::InvalidSignature);
+        }
+        self.genesis_signatures.insert(participant, signature);
+        Ok(())
+    }
+
+    /// Whether every participant has signed the genesis state
+    pub fn genesis_complete(&self) -> bool {
+        self.participants.iter().all(|p| self.genesis_signatures.contains_key(p))
+    }
+
     /// Mark channel as active
     pub fn activate(&mut self) -> Result<()> {
         if self.status != ChannelStatus::Opening {
@@ -119,12 +273,67 @@ impl MarketplaceChannel {
         Ok(())
     }
 
-    /// Initiate cooperative close
-    pub fn initiate_close(&mut self) -> Result<()> {
+    /// Initiate cooperative close, starting the challenge period
+    pub fn initiate_close(&mut self, now: Timestamp) -> Result<()> {
         if self.status != ChannelStatus::Active {
             return Err(L2Error::InvalidChannelState);
         }
         self.status = ChannelStatus::Closing;
+        self.close_initiated_at = Some(now);
+        Ok(())
+    }
+
+    /// When the challenge period of a close in progress ends
+    pub fn challenge_deadline(&self) -> Option<Timestamp> {
+        self.close_initiated_at
+            .map(|t| Timestamp::from_secs(t.as_secs().saturating_add(self.challenge_period)))
+    }
+
+    /// Dispute a close in progress; only allowed during the challenge period
+    pub fn challenge(&mut self, now: Timestamp) -> Result<()> {
+        if self.status != ChannelStatus::Closing {
+            return Err(L2Error::InvalidChannelState);
+        }
+        match self.challenge_deadline() {
+            Some(deadline) if now < deadline => {}
+            _ => return Err(L2Error::InvalidChannelState),
+        }
+        self.status = ChannelStatus::Challenged;
+        Ok(())
+    }
+
+    /// Complete a close once its challenge period has passed unchallenged
+    pub fn finalize_close(&mut self, now: Timestamp) -> Result<()> {
+        if self.status != ChannelStatus::Closing {
+            return Err(L2Error::InvalidChannelState);
+        }
+        match self.challenge_deadline() {
+            Some(deadline) if now >= deadline => {}
+            _ => return Err(L2Error::InvalidChannelState),
+        }
+        self.status = ChannelStatus::Closed;
+        Ok(())
+    }
+
+    /// Close unilaterally: a participant has published its latest state to
+    /// L1 at `now`, so the channel is challenged at once and the challenge
+    /// period runs from there. Allowed while active, or to escalate a
+    /// cooperative close.
+    pub fn force_close(&mut self, now: Timestamp) -> Result<()> {
+        if !matches!(self.status, ChannelStatus::Active | ChannelStatus::Closing) {
+            return Err(L2Error::InvalidChannelState);
+        }
+        self.status = ChannelStatus::Challenged;
+        self.close_initiated_at = Some(now);
+        Ok(())
+    }
+
+    /// Close a challenged channel once its dispute has been resolved on L1
+    pub fn settle_dispute(&mut self) -> Result<()> {
+        if self.status != ChannelStatus::Challenged {
+            return Err(L2Error::InvalidChannelState);
+        }
+        self.status = ChannelStatus::Closed;
         Ok(())
     }
 
@@ -146,6 +355,12 @@ impl MarketplaceChannel {
             collateral: self.collateral,
             num_listings: self.state.listings.len(),
             num_orders: self.state.orders.len(),
+            updates_per_hour: self.metrics.updates_per_hour(),
+            last_update_at: self.metrics.last_update_at,
+            pending_updates: 0,
+            unanchored_updates: self.state.nonce.saturating_sub(self.metrics.checkpointed_nonce),
+            last_checkpoint_height: self.metrics.checkpointed_height,
+            volume: self.metrics.volume,
         }
     }
 }
@@ -160,6 +375,26 @@ pub struct ChannelInfo {
     pub collateral: Amount,
     pub num_listings: usize,
     pub num_orders: usize,
+    #[serde(default)]
+    pub updates_per_hour: f64,
+    #[serde(default)]
+    pub last_update_at: Option<Timestamp>,
+    /// Proposed updates still waiting for signatures; filled in by the node
+    #[serde(default)]
+    pub pending_updates: usize,
+    /// Updates applied since the state was last anchored on L1
+    #[serde(default)]
+    pub unanchored_updates: u64,
+    /// L1 block height the state was last anchored at
+    #[serde(default)]
+    pub last_checkpoint_height: Option<u64>,
+    /// Total value moved between participants
+    #[serde(default = "zero_amount")]
+    pub volume: Amount,
+}
+
+fn zero_amount() -> Amount {
+    Amount::new(0)
 }
 
 #[cfg(test)]
@@ -207,4 +442,324 @@ mod tests {
         assert!(channel.activate().is_ok());
         assert_eq!(channel.status, ChannelStatus::Active);
     }
+
+    #[test]
+    fn test_close_respects_challenge_period() {
+        let kp1 = KeyPair::generate();
+        let kp2 = KeyPair::generate();
+
+        let mut balances = HashMap::new();
+        balances.insert(kp1.public_key(), Amount::new(1000));
+        balances.insert(kp2.public_key(), Amount::new(1000));
+
+        let mut channel = MarketplaceChannel::new(ChannelConfig {
+            participants: vec![kp1.public_key(), kp2.public_key()],
+            initial_balances: balances,
+            challenge_period: 3600,
+        });
+        channel.activate().unwrap();
+
+        let start = Timestamp::from_secs(1_700_000_000);
+        channel.initiate_close(start).unwrap();
+        assert_eq!(channel.challenge_deadline(), Some(Timestamp::from_secs(1_700_003_600)));
+
+        // Neither side can settle early, and a challenge after the window is too late
+        assert!(channel.finalize_close(Timestamp::from_secs(1_700_003_599)).is_err());
+        let mut challenged = channel.clone();
+        assert!(challenged.clone().challenge(Timestamp::from_secs(1_700_003_600)).is_err());
+        challenged.challenge(Timestamp::from_secs(1_700_003_599)).unwrap();
+        assert_eq!(challenged.status, ChannelStatus::Challenged);
+        assert!(challenged.finalize_close(Timestamp::from_secs(1_700_010_000)).is_err());
+
+        channel.finalize_close(Timestamp::from_secs(1_700_003_600)).unwrap();
+        assert_eq!(channel.status, ChannelStatus::Closed);
+    }
+
+    #[test]
+    fn test_force_close_goes_straight_to_challenged() {
+        let kp1 = KeyPair::generate();
+        let kp2 = KeyPair::generate();
+
+        let mut channel = MarketplaceChannel::new(ChannelConfig {
+            participants: vec![kp1.public_key(), kp2.public_key()],
+            initial_balances: [(kp1.public_key(), Amount::new(1000))].into_iter().collect(),
+            challenge_period: 3600,
+        });
+        assert!(channel.force_close(Timestamp::from_secs(1_700_000_000)).is_err());
+        channel.activate().unwrap();
+
+        channel.force_close(Timestamp::from_secs(1_700_000_000)).unwrap();
+        assert_eq!(channel.status, ChannelStatus::Challenged);
+        assert_eq!(channel.challenge_deadline(), Some(Timestamp::from_secs(1_700_003_600)));
+
+        // Only the L1 dispute settles it, and it cannot be forced twice
+        assert!(channel.finalize_close(Timestamp::from_secs(1_700_010_000)).is_err());
+        assert!(channel.force_close(Timestamp::from_secs(1_700_000_100)).is_err());
+        channel.settle_dispute().unwrap();
+        assert_eq!(channel.status, ChannelStatus::Closed);
+    }
+
+    #[test]
+    fn test_genesis_signatures() {
+        let kp1 = KeyPair::generate();
+        let kp2 = KeyPair::generate();
+        let outsider = KeyPair::generate();
+
+        let mut balances = HashMap::new();
+        balances.insert(kp1.public_key(), Amount::new(1000));
+        balances.insert(kp2.public_key(), Amount::new(1000));
+
+        let mut channel = MarketplaceChannel::new(ChannelConfig {
+            participants: vec![kp1.public_key(), kp2.public_key()],
+            initial_balances: balances,
+            challenge_period: 3600,
+        });
+        let message = channel.genesis_signing_message();
+
+        assert!(matches!(
+            channel.add_genesis_signature(outsider.public_key(), outsider.sign(&message)),
+            Err(L2Error::ParticipantNotFound)
+        ));
+        assert!(matches!(
+            channel.add_genesis_signature(kp2.public_key(), kp1.sign(&message)),
+            Err(L2Error::InvalidSignature)
+        ));
+
+        channel.add_genesis_signature(kp1.public_key(), kp1.sign(&message)).unwrap();
+        assert!(!channel.genesis_complete());
+        channel.add_genesis_signature(kp2.public_key(), kp2.sign(&message)).unwrap();
+        assert!(channel.genesis_complete());
+
+        // A different challenge period is a different agreement
+        channel.challenge_period = 60;
+        assert_ne!(channel.genesis_signing_message(), message);
+    }
+
+    #[test]
+    fn test_duplicate_detection() {
+        use crate::update::StateUpdate;
+
+        let kp1 = KeyPair::generate();
+        let kp2 = KeyPair::generate();
+
+        let mut balances = HashMap::new();
+        balances.insert(kp1.public_key(), Amount::new(1000));
+        balances.insert(kp2.public_key(), Amount::new(1000));
+
+        let mut channel = MarketplaceChannel::new(ChannelConfig {
+            participants: vec![kp1.public_key(), kp2.public_key()],
+            initial_balances: balances,
+            challenge_period: 3600,
+        });
+        channel.activate().unwrap();
+
+        let sign = |amount: u64| {
+            let mut signed = SignedStateUpdate::new(
+                StateUpdate::Transfer { from: kp1.public_key(), to: kp2.public_key(), amount: Amount::new(amount) },
+                1,
+            );
+            let message = signed.signing_message();
+            signed.add_signature(kp1.public_key(), kp1.sign(&message));
+            signed.add_signature(kp2.public_key(), kp2.sign(&message));
+            signed
+        };
+
+        let applied = sign(100);
+        assert!(!channel.is_duplicate(&applied));
+        channel.apply_update(applied.clone()).unwrap();
+        assert!(channel.is_duplicate(&applied));
+        assert!(!channel.is_duplicate(&sign(200)));
+    }
+
+    #[test]
+    fn test_escrow_lock_pays_out_once() {
+        use crate::state::{Listing, Order, OrderStatus};
+        use crate::update::StateUpdate;
+
+        let buyer = KeyPair::generate();
+        let seller = KeyPair::generate();
+
+        let mut balances = HashMap::new();
+        balances.insert(buyer.public_key(), Amount::new(1000));
+        balances.insert(seller.public_key(), Amount::new(1000));
+
+        let mut channel = MarketplaceChannel::new(ChannelConfig {
+            participants: vec![buyer.public_key(), seller.public_key()],
+            initial_balances: balances,
+            challenge_period: 3600,
+        });
+        channel.activate().unwrap();
+
+        let apply = |channel: &mut MarketplaceChannel, update: StateUpdate| {
+            let mut signed = SignedStateUpdate::new(update, channel.state.nonce + 1);
+            let message = signed.signing_message();
+            signed.add_signature(buyer.public_key(), buyer.sign(&message));
+            signed.add_signature(seller.public_key(), seller.sign(&message));
+            channel.apply_update(signed)
+        };
+
+        let listing = Listing {
+            id: Hash::new([1; 32]),
+            seller: seller.public_key(),
+            title: "Lamp".to_string(),
+            description: String::new(),
+            price: Amount::new(300),
+            ipfs_hash: String::new(),
+            active: true,
+            category: "home".to_string(),
+            fiat_reference: None,
+        };
+        let order = |id: u8| Order {
+            id: Hash::new([id; 32]),
+            listing_id: listing.id,
+            buyer: buyer.public_key(),
+            seller: seller.public_key(),
+            amount: Amount::new(300),
+            status: OrderStatus::Pending,
+        };
+        apply(&mut channel, StateUpdate::CreateListing { listing: listing.clone() }).unwrap();
+        apply(&mut channel, StateUpdate::CreateOrder { order: order(2) }).unwrap();
+        apply(&mut channel, StateUpdate::CreateOrder { order: order(3) }).unwrap();
+        apply(&mut channel, StateUpdate::CreateOrder { order: order(4) }).unwrap();
+
+        // Locking moves the funds out of the buyer's balance, once
+        apply(&mut channel, StateUpdate::LockEscrow { order_id: order(2).id }).unwrap();
+        assert!(apply(&mut channel, StateUpdate::LockEscrow { order_id: order(2).id }).is_err());
+        assert_eq!(channel.state.get_balance(&buyer.public_key()), Amount::new(700));
+
+        // Completion pays the seller from the lock, not the buyer's balance
+        apply(&mut channel, StateUpdate::UpdateOrderStatus { order_id: order(2).id, status: OrderStatus::Completed }).unwrap();
+        assert_eq!(channel.state.get_balance(&buyer.public_key()), Amount::new(700));
+        assert_eq!(channel.state.get_balance(&seller.public_key()), Amount::new(1300));
+
+        // Cancellation returns the lock to the buyer
+        apply(&mut channel, StateUpdate::LockEscrow { order_id: order(3).id }).unwrap();
+        apply(&mut channel, StateUpdate::UpdateOrderStatus { order_id: order(3).id, status: OrderStatus::Cancelled }).unwrap();
+        assert_eq!(channel.state.get_balance(&buyer.public_key()), Amount::new(700));
+        assert!(channel.state.escrow_locks.is_empty());
+
+        // An order paid through an L1 escrow completes without moving
+        // channel funds, and a locked one cannot be completed that way
+        let paid_on_l1 = |id: u8| StateUpdate::CompleteOrderPaidOnL1 { order_id: order(id).id, payout_tx_id: "payout".to_string() };
+        apply(&mut channel, paid_on_l1(4)).unwrap();
+        assert_eq!(channel.state.get_balance(&buyer.public_key()), Amount::new(700));
+        assert_eq!(channel.state.get_balance(&seller.public_key()), Amount::new(1300));
+        assert_eq!(channel.state.orders[2].status, OrderStatus::Completed);
+        apply(&mut channel, StateUpdate::CreateOrder { order: order(5) }).unwrap();
+        apply(&mut channel, StateUpdate::LockEscrow { order_id: order(5).id }).unwrap();
+        assert!(apply(&mut channel, paid_on_l1(5)).is_err());
+    }
+
+    #[test]
+    fn test_metrics_track_updates() {
+        use crate::update::StateUpdate;
+
+        let kp1 = KeyPair::generate();
+        let kp2 = KeyPair::generate();
+
+        let mut balances = HashMap::new();
+        balances.insert(kp1.public_key(), Amount::new(1000));
+        balances.insert(kp2.public_key(), Amount::new(1000));
+
+        let mut channel = MarketplaceChannel::new(ChannelConfig {
+            participants: vec![kp1.public_key(), kp2.public_key()],
+            initial_balances: balances,
+            challenge_period: 3600,
+        });
+        channel.activate().unwrap();
+
+        let start = Timestamp::from_secs(1_700_000_000);
+        for i in 0..4u64 {
+            let mut signed = SignedStateUpdate::new(StateUpdate::Transfer {
+                from: kp1.public_key(),
+                to: kp2.public_key(),
+                amount: Amount::new(100),
+            }, channel.state.nonce + 1);
+            let message = signed.signing_message();
+            signed.add_signature(kp1.public_key(), kp1.sign(&message));
+            signed.add_signature(kp2.public_key(), kp2.sign(&message));
+            channel.apply_update_at(signed, Timestamp::from_secs(start.as_secs() + i * 3600)).unwrap();
+            if i == 1 {
+                channel.mark_checkpointed(start, 1_000);
+            }
+        }
+
+        let info = channel.info();
+        assert_eq!(info.volume, Amount::new(400));
+        assert_eq!(info.unanchored_updates, 2);
+        assert_eq!(info.last_checkpoint_height, Some(1_000));
+        assert_eq!(info.last_update_at, Some(Timestamp::from_secs(start.as_secs() + 3 * 3600)));
+        assert!((info.updates_per_hour - 4.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_state_root_proves_balances() {
+        use tari_l2_common::merkle::MerkleTree;
+
+        let kp1 = KeyPair::generate();
+        let kp2 = KeyPair::generate();
+
+        let mut balances = HashMap::new();
+        balances.insert(kp1.public_key(), Amount::new(1000));
+        balances.insert(kp2.public_key(), Amount::new(500));
+
+        let channel = MarketplaceChannel::new(ChannelConfig {
+            participants: vec![kp1.public_key(), kp2.public_key()],
+            initial_balances: balances,
+            challenge_period: 3600,
+        });
+
+        let root = channel.get_state_root();
+        let leaves = channel.state.merkle_leaves();
+        let tree = MerkleTree::new(&leaves);
+        assert_eq!(tree.root(), root);
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert!(tree.prove(i).unwrap().verify(&root, leaf));
+        }
+    }
+
+    #[test]
+    fn test_apply_aggregate_signed_update() {
+        use crate::update::StateUpdate;
+        use tari_l2_common::musig::{AggregateSignature, KeyAggregation, SecretNonce, SigningSession};
+
+        let kp1 = KeyPair::generate_ristretto();
+        let kp2 = KeyPair::generate_ristretto();
+        let participants = vec![kp1.public_key(), kp2.public_key()];
+
+        let mut balances = HashMap::new();
+        balances.insert(kp1.public_key(), Amount::new(1000));
+        balances.insert(kp2.public_key(), Amount::new(1000));
+
+        let mut channel = MarketplaceChannel::new(ChannelConfig {
+            participants: participants.clone(),
+            initial_balances: balances,
+            challenge_period: 3600,
+        });
+        channel.activate().unwrap();
+
+        let mut signed = SignedStateUpdate::new(
+            StateUpdate::Transfer { from: kp1.public_key(), to: kp2.public_key(), amount: Amount::new(100) },
+            1,
+        );
+        let message = signed.signing_message();
+
+        let nonce1 = SecretNonce::generate();
+        let nonce2 = SecretNonce::generate();
+        let session = SigningSession::new(
+            KeyAggregation::new(&participants).unwrap(),
+            &[nonce1.public(), nonce2.public()],
+            &message,
+        ).unwrap();
+        let partials = vec![
+            session.partial_sign(&kp1, nonce1).unwrap(),
+            session.partial_sign(&kp2, nonce2).unwrap(),
+        ];
+        let signature = session.aggregate(&partials).unwrap();
+        signed.set_aggregate_signature(AggregateSignature::new(participants, signature));
+
+        channel.apply_update(signed).unwrap();
+        assert_eq!(channel.get_balance(&kp2.public_key()).unwrap(), Amount::new(1100));
+    }
 }
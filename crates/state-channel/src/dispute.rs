@@ -0,0 +1,186 @@
+//! Evidence a participant submits to L1 when a channel close is disputed.
+//!
+//! A signed update only commits to its own operation and nonce, so a single
+//! update cannot prove what the balances were after it. Evidence therefore
+//! carries the whole chain: the opening configuration with every
+//! participant's genesis signature, then every co-signed update up to the
+//! claimed state. Anyone holding it can replay it to the same state root and
+//! balances without trusting the submitter.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tari_l2_common::{Amount, Hash, PublicKey, Signature, canonical};
+use tari_l2_common::{L2Error, error::Result};
+use crate::channel::{ChannelConfig, MarketplaceChannel};
+use crate::state::ChannelState;
+use crate::update::SignedStateUpdate;
+
+/// Everything needed to re-derive a channel state from its opening
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DisputeEvidence {
+    pub channel_id: Hash,
+    pub participants: Vec<PublicKey>,
+    #[serde(serialize_with = "canonical::sorted_map")]
+    pub initial_balances: HashMap<PublicKey, Amount>,
+    pub challenge_period: u64,
+
+    /// Every participant's signature over the opening state
+    #[serde(serialize_with = "canonical::sorted_map")]
+    pub genesis_signatures: HashMap<PublicKey, Signature>,
+
+    /// Co-signed updates from nonce 1 up to the claimed state
+    pub history: Vec<SignedStateUpdate>,
+}
+
+/// A channel state proven by evidence
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SettledState {
+    pub channel_id: Hash,
+    pub nonce: u64,
+    pub state_root: Hash,
+
+    /// What each participant is owed if the channel settles here: their
+    /// balance plus the escrow locks of their unsettled purchases, which are
+    /// returned to the buyer
+    #[serde(serialize_with = "canonical::sorted_map")]
+    pub payouts: HashMap<PublicKey, Amount>,
+}
+
+impl SettledState {
+    /// Sum of the payouts
+    pub fn total(&self) -> Option<Amount> {
+        self.payouts.values().sum()
+    }
+}
+
+impl DisputeEvidence {
+    /// Evidence for `channel`'s current state
+    pub fn from_channel(channel: &MarketplaceChannel) -> Self {
+        Self {
+            channel_id: channel.channel_id,
+            participants: channel.participants.clone(),
+            initial_balances: channel.initial_balances.clone(),
+            challenge_period: channel.challenge_period,
+            genesis_signatures: channel.genesis_signatures.clone(),
+            history: channel.state_history.clone(),
+        }
+    }
+
+    /// Nonce the evidence claims to reach
+    pub fn nonce(&self) -> u64 {
+        self.history.last().map_or(0, |update| update.nonce)
+    }
+
+    /// Digest committing to the whole evidence, published on L1 in its place
+    pub fn digest(&self) -> Result<Hash> {
+        canonical::hash(self).map_err(L2Error::SerializationError)
+    }
+
+    /// Check the genesis signatures and replay the history, returning the
+    /// state it proves
+    pub fn verify(&self) -> Result<SettledState> {
+        let mut channel = MarketplaceChannel::new(ChannelConfig {
+            participants: self.participants.clone(),
+            initial_balances: self.initial_balances.clone(),
+            challenge_period: self.challenge_period,
+        });
+        channel.channel_id = self.channel_id;
+
+        let genesis = channel.genesis_signing_message();
+        for participant in &self.participants {
+            match self.genesis_signatures.get(participant) {
+                Some(signature) if participant.verify(&genesis, signature) => {}
+                _ => return Err(L2Error::InvalidSignature),
+            }
+        }
+
+        let mut state = ChannelState::new(self.participants.clone(), self.initial_balances.clone());
+        for update in &self.history {
+            if update.nonce != state.nonce + 1 {
+                return Err(L2Error::InvalidStateTransition);
+            }
+            if !update.verify(&self.participants) {
+                return Err(L2Error::InvalidSignature);
+            }
+            state = update.update.apply(state)?;
+        }
+
+        let mut payouts = state.balances.clone();
+        for (order_id, amount) in &state.escrow_locks {
+            let buyer = state.orders.iter()
+                .find(|order| &order.id == order_id)
+                .map(|order| order.buyer)
+                .ok_or(L2Error::InvalidStateTransition)?;
+            let owed = payouts.entry(buyer).or_insert(Amount::new(0));
+            *owed = owed.checked_add(*amount).ok_or(L2Error::InvalidStateTransition)?;
+        }
+
+        Ok(SettledState {
+            channel_id: self.channel_id,
+            nonce: state.nonce,
+            state_root: state.merkle_root(),
+            payouts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_common::crypto::KeyPair;
+    use crate::update::StateUpdate;
+
+    #[test]
+    fn test_evidence_replays_to_latest_state() {
+        let keypairs = [KeyPair::generate(), KeyPair::generate()];
+        let participants: Vec<_> = keypairs.iter().map(|kp| kp.public_key()).collect();
+        let mut channel = MarketplaceChannel::new(ChannelConfig {
+            participants: participants.clone(),
+            initial_balances: participants.iter().map(|p| (*p, Amount::new(1_000))).collect(),
+            challenge_period: 3600,
+        });
+        let genesis = channel.genesis_signing_message();
+        for kp in &keypairs {
+            channel.add_genesis_signature(kp.public_key(), kp.sign(&genesis)).unwrap();
+        }
+        channel.activate().unwrap();
+
+        for nonce in 1..=3 {
+            let update = StateUpdate::Transfer { from: participants[0], to: participants[1], amount: Amount::new(100) };
+            let mut signed = SignedStateUpdate::new(update, nonce);
+            let message = signed.signing_message();
+            for kp in &keypairs {
+                signed.add_signature(kp.public_key(), kp.sign(&message));
+            }
+            channel.apply_update(signed).unwrap();
+        }
+
+        let evidence = DisputeEvidence::from_channel(&channel);
+        let settled = evidence.verify().unwrap();
+        assert_eq!(settled.nonce, 3);
+        assert_eq!(settled.state_root, channel.get_state_root());
+        assert_eq!(settled.payouts[&participants[0]], Amount::new(700));
+        assert_eq!(settled.total(), Some(Amount::new(2_000)));
+
+        // Dropping an update leaves a gap
+        let mut gapped = evidence.clone();
+        gapped.history.remove(1);
+        assert!(gapped.verify().is_err());
+
+        // Genesis signatures bind the opening balances
+        let mut inflated = evidence.clone();
+        inflated.initial_balances.insert(participants[0], Amount::new(5_000));
+        assert!(matches!(inflated.verify(), Err(L2Error::InvalidSignature)));
+
+        // The digest does not depend on the order the maps were filled in
+        let mut refilled = evidence.clone();
+        let mut balances: Vec<_> = evidence.initial_balances.clone().into_iter().collect();
+        balances.reverse();
+        refilled.initial_balances = balances.into_iter().collect();
+        assert_eq!(refilled.digest().unwrap(), evidence.digest().unwrap());
+
+        let mut unsigned = evidence;
+        unsigned.genesis_signatures.clear();
+        assert!(unsigned.verify().is_err());
+    }
+}
@@ -1,7 +1,16 @@
 pub mod channel;
 pub mod state;
 pub mod update;
+pub mod replay;
+pub mod dispute;
+pub mod lifecycle;
+
+#[cfg(test)]
+mod proptests;
 
 pub use channel::{MarketplaceChannel, ChannelConfig};
-pub use state::{ChannelState, Listing, Order, OrderStatus};
+pub use state::{ChannelState, FiatReference, Listing, Order, OrderStatus, Review, UserProfile};
 pub use update::StateUpdate;
+pub use replay::{Divergence, ReplayReport};
+pub use dispute::{DisputeEvidence, SettledState};
+pub use lifecycle::{Role, TransitionError};
@@ -0,0 +1,188 @@
+//! Status transitions and who may make them.
+//!
+//! Orders and escrows each have a table of legal moves: the status left,
+//! the status reached, and the roles allowed to make the move. Channel
+//! updates, the manager and the RPC all check against these tables through
+//! [`check`] and [`check_by`], so an illegal move is refused with the same
+//! [`TransitionError`] wherever it is attempted. The order table is
+//! [`ORDER_TRANSITIONS`]; the escrow table lives with the escrow contract in
+//! the marketplace crate.
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use tari_l2_common::L2Error;
+use crate::state::OrderStatus;
+
+/// Part a key plays in an order or escrow
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Buyer,
+    Seller,
+    /// One of the node's configured arbitrators, settling a dispute
+    Arbitrator,
+}
+
+/// A legal move from one status to another
+#[derive(Debug)]
+pub struct Transition<S: 'static> {
+    pub from: S,
+    pub to: S,
+    /// Roles that may make the move
+    pub roles: &'static [Role],
+}
+
+/// Why a move was refused
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransitionError<S> {
+    /// No row of the table leads from `from` to `to`
+    Illegal { from: S, to: S },
+    /// The move is legal, but not for any of `roles`
+    Forbidden { from: S, to: S, roles: Vec<Role> },
+}
+
+impl<S: fmt::Debug> fmt::Display for TransitionError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransitionError::Illegal { from, to } => write!(f, "Cannot move from {:?} to {:?}", from, to),
+            TransitionError::Forbidden { from, to, roles } if roles.is_empty() => {
+                write!(f, "Only a party may move from {:?} to {:?}", from, to)
+            }
+            TransitionError::Forbidden { from, to, roles } => {
+                write!(f, "{:?} may not move from {:?} to {:?}", roles, from, to)
+            }
+        }
+    }
+}
+
+impl<S> From<TransitionError<S>> for L2Error {
+    fn from(_: TransitionError<S>) -> Self {
+        L2Error::InvalidStateTransition
+    }
+}
+
+/// The row of `table` moving `from` to `to`, if the move is legal at all
+pub fn check<'a, S: Clone + PartialEq>(table: &'a [Transition<S>], from: &S, to: &S) -> Result<&'a Transition<S>, TransitionError<S>> {
+    table.iter()
+        .find(|t| t.from == *from && t.to == *to)
+        .ok_or_else(|| TransitionError::Illegal { from: from.clone(), to: to.clone() })
+}
+
+/// Check the move is legal and that a key holding `roles` may make it.
+/// A key that is not a party holds no roles.
+pub fn check_by<S: Clone + PartialEq>(table: &[Transition<S>], from: &S, to: &S, roles: &[Role]) -> Result<(), TransitionError<S>> {
+    let transition = check(table, from, to)?;
+    if roles.iter().any(|role| transition.roles.contains(role)) {
+        Ok(())
+    } else {
+        Err(TransitionError::Forbidden { from: from.clone(), to: to.clone(), roles: roles.to_vec() })
+    }
+}
+
+const BUYER: &[Role] = &[Role::Buyer];
+const SELLER: &[Role] = &[Role::Seller];
+const PARTIES: &[Role] = &[Role::Buyer, Role::Seller];
+
+const fn row(from: OrderStatus, to: OrderStatus, roles: &'static [Role]) -> Transition<OrderStatus> {
+    Transition { from, to, roles }
+}
+
+/// Every legal order move. Orders move forward from Pending to Completed,
+/// possibly skipping steps: the seller confirms, ships and delivers, and the
+/// buyer completes, which pays the seller. Until final, either side can
+/// dispute; the seller can cancel, and the buyer only while pending. A
+/// dispute ends in completion or cancellation, which an arbitrator may also
+/// decide.
+pub const ORDER_TRANSITIONS: &[Transition<OrderStatus>] = {
+    use OrderStatus::*;
+    &[
+        row(Pending, Confirmed, SELLER),
+        row(Pending, Shipping, SELLER),
+        row(Pending, Delivered, SELLER),
+        row(Pending, Completed, BUYER),
+        row(Pending, Disputed, PARTIES),
+        row(Pending, Cancelled, PARTIES),
+        row(Confirmed, Shipping, SELLER),
+        row(Confirmed, Delivered, SELLER),
+        row(Confirmed, Completed, BUYER),
+        row(Confirmed, Disputed, PARTIES),
+        row(Confirmed, Cancelled, SELLER),
+        row(Shipping, Delivered, SELLER),
+        row(Shipping, Completed, BUYER),
+        row(Shipping, Disputed, PARTIES),
+        row(Shipping, Cancelled, SELLER),
+        row(Delivered, Completed, BUYER),
+        row(Delivered, Disputed, PARTIES),
+        row(Delivered, Cancelled, SELLER),
+        row(Disputed, Completed, &[Role::Buyer, Role::Arbitrator]),
+        row(Disputed, Cancelled, &[Role::Seller, Role::Arbitrator]),
+    ]
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tari_l2_common::{Amount, Hash, PublicKey};
+    use crate::state::Order;
+
+    #[test]
+    fn test_transition_guards_check_the_move_then_the_role() {
+        use OrderStatus::*;
+
+        assert!(check_by(ORDER_TRANSITIONS, &Shipping, &Delivered, &[Role::Seller]).is_ok());
+        assert_eq!(
+            check_by(ORDER_TRANSITIONS, &Delivered, &Shipping, &[Role::Seller]),
+            Err(TransitionError::Illegal { from: Delivered, to: Shipping }),
+        );
+        assert_eq!(
+            check_by(ORDER_TRANSITIONS, &Shipping, &Delivered, &[Role::Buyer]),
+            Err(TransitionError::Forbidden { from: Shipping, to: Delivered, roles: vec![Role::Buyer] }),
+        );
+
+        // Arbitrators only settle disputes
+        assert!(check_by(ORDER_TRANSITIONS, &Disputed, &Cancelled, &[Role::Arbitrator]).is_ok());
+        assert!(check_by(ORDER_TRANSITIONS, &Shipping, &Cancelled, &[Role::Arbitrator]).is_err());
+
+        let err = check_by(ORDER_TRANSITIONS, &Pending, &Disputed, &[]).unwrap_err();
+        assert_eq!(err.to_string(), "Only a party may move from Pending to Disputed");
+        assert!(matches!(L2Error::from(err), L2Error::InvalidStateTransition));
+    }
+
+    #[test]
+    fn test_order_lifecycle_and_roles() {
+        use OrderStatus::*;
+
+        let legal = |from, to| check(ORDER_TRANSITIONS, &from, &to).is_ok();
+        assert!(legal(Pending, Confirmed));
+        assert!(legal(Confirmed, Delivered));
+        assert!(legal(Shipping, Disputed));
+        assert!(legal(Disputed, Cancelled));
+        assert!(!legal(Delivered, Shipping));
+        assert!(!legal(Pending, Pending));
+        assert!(!legal(Disputed, Shipping));
+        assert!(!legal(Completed, Disputed));
+        assert!(!legal(Cancelled, Completed));
+
+        let (buyer, seller) = (PublicKey::new([1; 32]), PublicKey::new([2; 32]));
+        let mut order = Order {
+            id: Hash::new([3; 32]),
+            listing_id: Hash::new([4; 32]),
+            buyer,
+            seller,
+            amount: Amount::new(100),
+            status: Pending,
+        };
+        let may = |order: &Order, by: &PublicKey, status| order.check_transition(&order.roles_of(by), &status).is_ok();
+        assert!(may(&order, &seller, Shipping));
+        assert!(!may(&order, &buyer, Shipping));
+        assert!(may(&order, &buyer, Completed));
+        assert!(!may(&order, &seller, Completed));
+        assert!(may(&order, &buyer, Cancelled));
+        assert!(!may(&order, &PublicKey::new([5; 32]), Disputed));
+
+        // Once the seller has confirmed, the buyer can only dispute
+        order.status = Confirmed;
+        assert!(!may(&order, &buyer, Cancelled));
+        assert!(may(&order, &buyer, Disputed));
+    }
+}
@@ -0,0 +1,226 @@
+//! Property tests driving channels through arbitrary update sequences.
+
+use std::collections::HashMap;
+use proptest::prelude::*;
+use tari_l2_common::{Amount, Hash, PublicKey, crypto::KeyPair};
+use crate::channel::{ChannelConfig, ChannelStatus, MarketplaceChannel};
+use crate::state::{Listing, Order, OrderStatus};
+use crate::update::{SignedStateUpdate, StateUpdate};
+
+const PARTICIPANTS: usize = 3;
+const IDS: u8 = 4;
+const INITIAL_BALANCE: u64 = 1_000;
+
+/// Update described by small indices so shrinking produces readable cases
+#[derive(Clone, Debug)]
+enum Op {
+    Transfer { from: usize, to: usize, amount: u64 },
+    CreateListing { id: u8, seller: usize, price: u64 },
+    UpdateListing { id: u8, active: bool },
+    CreateOrder { id: u8, listing: u8, buyer: usize, seller: usize, amount: u64 },
+    UpdateOrderStatus { id: u8, status: OrderStatus },
+    LockEscrow { id: u8 },
+}
+
+fn order_status() -> impl Strategy<Value = OrderStatus> {
+    prop_oneof![
+        Just(OrderStatus::Pending),
+        Just(OrderStatus::Confirmed),
+        Just(OrderStatus::Shipping),
+        Just(OrderStatus::Delivered),
+        Just(OrderStatus::Disputed),
+        Just(OrderStatus::Completed),
+        Just(OrderStatus::Cancelled),
+    ]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let who = 0..PARTICIPANTS;
+    let id = 0..IDS;
+    let amount = 0..=INITIAL_BALANCE * 2;
+    prop_oneof![
+        3 => (who.clone(), who.clone(), amount.clone())
+            .prop_map(|(from, to, amount)| Op::Transfer { from, to, amount }),
+        1 => (id.clone(), who.clone(), amount.clone())
+            .prop_map(|(id, seller, price)| Op::CreateListing { id, seller, price }),
+        1 => (id.clone(), any::<bool>())
+            .prop_map(|(id, active)| Op::UpdateListing { id, active }),
+        2 => (id.clone(), id.clone(), who.clone(), who, amount)
+            .prop_map(|(id, listing, buyer, seller, amount)| Op::CreateOrder { id, listing, buyer, seller, amount }),
+        2 => (id.clone(), order_status())
+            .prop_map(|(id, status)| Op::UpdateOrderStatus { id, status }),
+        1 => id.prop_map(|id| Op::LockEscrow { id }),
+    ]
+}
+
+fn keypairs() -> Vec<KeyPair> {
+    (1..=PARTICIPANTS as u8)
+        .map(|i| KeyPair::from_private_key(&[i; 32]).unwrap())
+        .collect()
+}
+
+fn id_hash(tag: u8, id: u8) -> Hash {
+    let mut bytes = [0u8; 32];
+    bytes[0] = tag;
+    bytes[1] = id;
+    Hash::new(bytes)
+}
+
+fn to_update(op: &Op, keys: &[PublicKey]) -> StateUpdate {
+    match op.clone() {
+        Op::Transfer { from, to, amount } => StateUpdate::Transfer {
+            from: keys[from],
+            to: keys[to],
+            amount: Amount::new(amount),
+        },
+        Op::CreateListing { id, seller, price } => StateUpdate::CreateListing {
+            listing: Listing {
+                id: id_hash(1, id),
+                seller: keys[seller],
+                title: format!("listing {}", id),
+                description: String::new(),
+                price: Amount::new(price),
+                ipfs_hash: String::new(),
+                active: true,
+                category: "test".to_string(),
+                fiat_reference: None,
+            },
+        },
+        Op::UpdateListing { id, active } => StateUpdate::UpdateListing {
+            listing_id: id_hash(1, id),
+            active,
+        },
+        Op::CreateOrder { id, listing, buyer, seller, amount } => StateUpdate::CreateOrder {
+            order: Order {
+                id: id_hash(2, id),
+                listing_id: id_hash(1, listing),
+                buyer: keys[buyer],
+                seller: keys[seller],
+                amount: Amount::new(amount),
+                status: OrderStatus::Pending,
+            },
+        },
+        Op::UpdateOrderStatus { id, status } => StateUpdate::UpdateOrderStatus {
+            order_id: id_hash(2, id),
+            status,
+        },
+        Op::LockEscrow { id } => StateUpdate::LockEscrow {
+            order_id: id_hash(2, id),
+        },
+    }
+}
+
+fn sign(update: StateUpdate, nonce: u64, keypairs: &[KeyPair]) -> SignedStateUpdate {
+    let mut signed = SignedStateUpdate::new(update, nonce);
+    let message = signed.signing_message();
+    for kp in keypairs {
+        signed.add_signature(kp.public_key(), kp.sign(&message));
+    }
+    signed
+}
+
+fn open_channel(keypairs: &[KeyPair]) -> MarketplaceChannel {
+    let keys: Vec<PublicKey> = keypairs.iter().map(|kp| kp.public_key()).collect();
+    let balances: HashMap<PublicKey, Amount> = keys.iter()
+        .map(|k| (*k, Amount::new(INITIAL_BALANCE)))
+        .collect();
+
+    let mut channel = MarketplaceChannel::new(ChannelConfig {
+        participants: keys,
+        initial_balances: balances,
+        challenge_period: 3600,
+    });
+    channel.activate().unwrap();
+    channel
+}
+
+/// Participant balances plus funds locked in escrow
+fn total_balance(channel: &MarketplaceChannel) -> Option<Amount> {
+    channel.state.balances.values()
+        .chain(channel.state.escrow_locks.values())
+        .sum()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn prop_update_sequences_preserve_invariants(ops in prop::collection::vec(op(), 1..40)) {
+        let keypairs = keypairs();
+        let keys: Vec<PublicKey> = keypairs.iter().map(|kp| kp.public_key()).collect();
+        let mut channel = open_channel(&keypairs);
+        let collateral = channel.collateral;
+
+        for op in &ops {
+            let before_nonce = channel.state.nonce;
+            let before_root = channel.get_state_root();
+            let completed_before: Vec<Hash> = channel.state.orders.iter()
+                .filter(|o| o.status == OrderStatus::Completed)
+                .map(|o| o.id)
+                .collect();
+
+            let signed = sign(to_update(op, &keys), before_nonce + 1, &keypairs);
+            let result = channel.apply_update(signed.clone());
+
+            if result.is_ok() {
+                // Nonce advances by exactly one per applied update
+                prop_assert_eq!(channel.state.nonce, before_nonce + 1);
+                prop_assert_eq!(channel.state_history.len() as u64, channel.state.nonce);
+
+                // Re-applying the same signed update is rejected and changes nothing
+                let after_root = channel.get_state_root();
+                prop_assert!(channel.apply_update(signed).is_err());
+                prop_assert_eq!(channel.state.nonce, before_nonce + 1);
+                prop_assert_eq!(channel.get_state_root(), after_root);
+            } else {
+                // Rejected updates leave the state untouched
+                prop_assert_eq!(channel.state.nonce, before_nonce);
+                prop_assert_eq!(channel.get_state_root(), before_root);
+            }
+
+            // Funds are only moved, never created or destroyed
+            prop_assert_eq!(total_balance(&channel), Some(collateral));
+            for balance in channel.state.balances.values() {
+                prop_assert!(*balance <= collateral);
+            }
+
+            // Completed orders stay completed (so they can never pay out twice)
+            for id in completed_before {
+                let order = channel.state.orders.iter().find(|o| o.id == id).unwrap();
+                prop_assert_eq!(&order.status, &OrderStatus::Completed);
+            }
+        }
+
+        prop_assert_eq!(channel.status, ChannelStatus::Active);
+    }
+
+    #[test]
+    fn prop_wrong_nonce_rejected(op in op(), offset in 2u64..100) {
+        let keypairs = keypairs();
+        let keys: Vec<PublicKey> = keypairs.iter().map(|kp| kp.public_key()).collect();
+        let mut channel = open_channel(&keypairs);
+
+        let stale = sign(to_update(&op, &keys), 0, &keypairs);
+        prop_assert!(channel.apply_update(stale).is_err());
+
+        let future = sign(to_update(&op, &keys), offset, &keypairs);
+        prop_assert!(channel.apply_update(future).is_err());
+        prop_assert_eq!(channel.state.nonce, 0);
+    }
+
+    #[test]
+    fn prop_missing_signature_rejected(op in op(), missing in 0..PARTICIPANTS) {
+        let keypairs = keypairs();
+        let keys: Vec<PublicKey> = keypairs.iter().map(|kp| kp.public_key()).collect();
+        let mut channel = open_channel(&keypairs);
+
+        let signers: Vec<KeyPair> = keypairs.iter()
+            .enumerate()
+            .filter(|(i, _)| *i != missing)
+            .map(|(_, kp)| KeyPair::from_private_key(&kp.to_bytes()[..]).unwrap())
+            .collect();
+        let signed = sign(to_update(&op, &keys), 1, &signers);
+        prop_assert!(channel.apply_update(signed).is_err());
+        prop_assert_eq!(channel.state.nonce, 0);
+    }
+}
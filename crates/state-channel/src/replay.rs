@@ -0,0 +1,166 @@
+//! Re-derive a channel's state from its signed history.
+//!
+//! Starting from the opening balances, every stored update is checked for
+//! the next nonce and a full set of participant signatures and re-applied.
+//! The result is compared with the persisted state, so a corrupted record
+//! or a history that does not justify the current balances is pinpointed
+//! to the first update (or final state) that disagrees.
+
+use serde::{Deserialize, Serialize};
+use tari_l2_common::Hash;
+use crate::channel::MarketplaceChannel;
+use crate::state::ChannelState;
+
+/// Outcome of replaying a channel
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub channel_id: Hash,
+    /// Updates re-applied before the first divergence (all of them if none)
+    pub replayed: usize,
+    /// Nonce and state root the replay reached
+    pub replayed_nonce: u64,
+    pub replayed_root: Hash,
+    /// First point where history and persisted state disagree
+    pub divergence: Option<Divergence>,
+}
+
+impl ReplayReport {
+    pub fn is_consistent(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Divergence {
+    /// The update at `index` does not carry the next nonce
+    NonceGap { index: usize, expected: u64, found: u64 },
+    /// The update at `index` is not signed by every participant
+    BadSignature { index: usize, nonce: u64 },
+    /// The update at `index` cannot be applied to the replayed state
+    RejectedUpdate { index: usize, nonce: u64, reason: String },
+    /// History replays cleanly but ends somewhere other than the persisted state
+    StateMismatch {
+        persisted_nonce: u64,
+        persisted_root: Hash,
+        replayed_nonce: u64,
+        replayed_root: Hash,
+    },
+}
+
+impl MarketplaceChannel {
+    /// Replay `state_history` from the opening balances and compare the
+    /// result with the persisted state
+    pub fn replay(&self) -> ReplayReport {
+        let mut state = ChannelState::new(self.participants.clone(), self.initial_balances.clone());
+        let mut divergence = None;
+        let mut replayed = 0;
+
+        for (index, update) in self.state_history.iter().enumerate() {
+            let expected = state.nonce + 1;
+            if update.nonce != expected {
+                divergence = Some(Divergence::NonceGap { index, expected, found: update.nonce });
+                break;
+            }
+            if !update.verify(&self.participants) {
+                divergence = Some(Divergence::BadSignature { index, nonce: update.nonce });
+                break;
+            }
+            match update.update.apply(state.clone()) {
+                Ok(next) => state = next,
+                Err(e) => {
+                    divergence = Some(Divergence::RejectedUpdate { index, nonce: update.nonce, reason: e.to_string() });
+                    break;
+                }
+            }
+            replayed += 1;
+        }
+
+        let replayed_root = state.merkle_root();
+        let persisted_root = self.get_state_root();
+        if divergence.is_none() && (state.nonce != self.state.nonce || replayed_root != persisted_root) {
+            divergence = Some(Divergence::StateMismatch {
+                persisted_nonce: self.state.nonce,
+                persisted_root,
+                replayed_nonce: state.nonce,
+                replayed_root,
+            });
+        }
+
+        ReplayReport {
+            channel_id: self.channel_id,
+            replayed,
+            replayed_nonce: state.nonce,
+            replayed_root,
+            divergence,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tari_l2_common::Amount;
+    use tari_l2_common::crypto::KeyPair;
+    use crate::channel::ChannelConfig;
+    use crate::update::{SignedStateUpdate, StateUpdate};
+
+    fn channel_with_transfers(keypairs: &[KeyPair], transfers: u64) -> MarketplaceChannel {
+        let participants: Vec<_> = keypairs.iter().map(|kp| kp.public_key()).collect();
+        let balances: HashMap<_, _> = participants.iter().map(|p| (*p, Amount::new(1_000))).collect();
+        let mut channel = MarketplaceChannel::new(ChannelConfig {
+            participants: participants.clone(),
+            initial_balances: balances,
+            challenge_period: 3600,
+        });
+        channel.activate().unwrap();
+
+        for nonce in 1..=transfers {
+            let update = StateUpdate::Transfer { from: participants[0], to: participants[1], amount: Amount::new(10) };
+            let mut signed = SignedStateUpdate::new(update, nonce);
+            let message = signed.signing_message();
+            for kp in keypairs {
+                signed.add_signature(kp.public_key(), kp.sign(&message));
+            }
+            channel.apply_update(signed).unwrap();
+        }
+        channel
+    }
+
+    #[test]
+    fn test_clean_history_replays_to_persisted_state() {
+        let keypairs = [KeyPair::generate(), KeyPair::generate()];
+        let report = channel_with_transfers(&keypairs, 5).replay();
+
+        assert!(report.is_consistent(), "{:?}", report.divergence);
+        assert_eq!(report.replayed, 5);
+        assert_eq!(report.replayed_nonce, 5);
+    }
+
+    #[test]
+    fn test_reports_first_divergence() {
+        let keypairs = [KeyPair::generate(), KeyPair::generate()];
+
+        // Tampered balance: history is fine, persisted state is not
+        let mut channel = channel_with_transfers(&keypairs, 3);
+        channel.state.set_balance(keypairs[1].public_key(), Amount::new(5_000));
+        assert!(matches!(channel.replay().divergence, Some(Divergence::StateMismatch { persisted_nonce: 3, replayed_nonce: 3, .. })));
+
+        // Forged amount invalidates the signatures on that update
+        let mut channel = channel_with_transfers(&keypairs, 3);
+        channel.state_history[1].update = StateUpdate::Transfer {
+            from: keypairs[0].public_key(),
+            to: keypairs[1].public_key(),
+            amount: Amount::new(900),
+        };
+        let report = channel.replay();
+        assert_eq!(report.divergence, Some(Divergence::BadSignature { index: 1, nonce: 2 }));
+        assert_eq!(report.replayed, 1);
+
+        // Missing record
+        let mut channel = channel_with_transfers(&keypairs, 3);
+        channel.state_history.remove(1);
+        assert_eq!(channel.replay().divergence, Some(Divergence::NonceGap { index: 1, expected: 2, found: 3 }));
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tari_l2_common::{Amount, Hash, PublicKey, crypto};
+use tari_l2_common::{Amount, Hash, PublicKey, canonical};
+use tari_l2_common::merkle::MerkleTree;
+use crate::lifecycle::{self, ORDER_TRANSITIONS, Role, TransitionError};
 
 /// Channel state containing all marketplace data
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -9,6 +11,7 @@ pub struct ChannelState {
     pub nonce: u64,
 
     /// Participant balances
+    #[serde(serialize_with = "canonical::sorted_map")]
     pub balances: HashMap<PublicKey, Amount>,
 
     /// Marketplace listings (simplified for now)
@@ -16,6 +19,10 @@ pub struct ChannelState {
 
     /// Active orders
     pub orders: Vec<Order>,
+
+    /// Buyer funds held in-channel for escrowed orders, by order ID
+    #[serde(default, serialize_with = "canonical::sorted_map")]
+    pub escrow_locks: HashMap<Hash, Amount>,
 }
 
 impl ChannelState {
@@ -25,13 +32,33 @@ impl ChannelState {
             balances: initial_balances,
             listings: Vec::new(),
             orders: Vec::new(),
+            escrow_locks: HashMap::new(),
         }
     }
 
     /// Calculate merkle root of current state for L1 anchoring
     pub fn merkle_root(&self) -> Hash {
-        let serialized = bincode::serialize(self).expect("Serialization should not fail");
-        crypto::hash_data(&serialized)
+        MerkleTree::new(&self.merkle_leaves()).root()
+    }
+
+    /// Leaves committed to by the state root: the nonce, balances sorted by
+    /// participant key, listings and orders in channel order, then escrow
+    /// locks sorted by order ID
+    pub fn merkle_leaves(&self) -> Vec<Vec<u8>> {
+        let mut balances: Vec<_> = self.balances.iter().collect();
+        balances.sort_by_key(|(participant, _)| *participant.as_bytes());
+        let mut locks: Vec<_> = self.escrow_locks.iter().collect();
+        locks.sort_by_key(|(order_id, _)| *order_id.as_bytes());
+
+        let mut leaves = Vec::with_capacity(
+            1 + balances.len() + self.listings.len() + self.orders.len() + locks.len(),
+        );
+        leaves.push(self.nonce.to_be_bytes().to_vec());
+        leaves.extend(balances.into_iter().map(|entry| encode(&entry)));
+        leaves.extend(self.listings.iter().map(encode));
+        leaves.extend(self.orders.iter().map(encode));
+        leaves.extend(locks.into_iter().map(|entry| encode(&entry)));
+        leaves
     }
 
     /// Get balance for a participant
@@ -50,7 +77,11 @@ impl ChannelState {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+    canonical::encode(value).expect("Serialization should not fail")
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Listing {
     pub id: Hash,
     pub seller: PublicKey,
@@ -60,6 +91,122 @@ pub struct Listing {
     pub ipfs_hash: String,  // For images and additional data
     pub active: bool,
     pub category: String,
+    /// Seller's reference price in a fiat currency, for display only
+    #[serde(default)]
+    pub fiat_reference: Option<FiatReference>,
+}
+
+/// A price in a fiat currency. Listings are always paid in µT; this only
+/// records what the seller had in mind.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FiatReference {
+    /// ISO 4217 currency code, e.g. `USD`
+    pub currency: String,
+    /// Price in the currency's minor unit (e.g. cents)
+    pub amount_minor: u64,
+}
+
+impl FiatReference {
+    /// Whether the currency looks like an ISO 4217 code
+    pub fn is_valid(&self) -> bool {
+        self.currency.len() == 3 && self.currency.bytes().all(|b| b.is_ascii_uppercase())
+    }
+}
+
+/// User profile information
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UserProfile {
+    /// Wallet public key (unique identifier)
+    pub public_key: PublicKey,
+
+    /// Display name
+    pub name: String,
+
+    /// Location (city, country, etc.)
+    pub location: Option<String>,
+
+    /// Profile bio/description
+    pub bio: Option<String>,
+
+    /// Contact email (optional)
+    pub email: Option<String>,
+
+    /// Profile avatar URL or hash
+    pub avatar: Option<String>,
+
+    /// Rating (0-5 stars)
+    pub rating: f32,
+
+    /// Number of completed transactions
+    pub transactions_completed: u32,
+
+    /// Timestamp when profile was created
+    pub created_at: u64,
+}
+
+impl UserProfile {
+    /// Create a new user profile
+    pub fn new(public_key: PublicKey, name: String) -> Self {
+        let created_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        Self {
+            public_key,
+            name,
+            location: None,
+            bio: None,
+            email: None,
+            avatar: None,
+            rating: 0.0,
+            transactions_completed: 0,
+            created_at,
+        }
+    }
+
+    /// Update profile information
+    pub fn update(&mut self, name: Option<String>, location: Option<String>, bio: Option<String>, email: Option<String>) {
+        if let Some(n) = name {
+            self.name = n;
+        }
+        if let Some(l) = location {
+            self.location = Some(l);
+        }
+        if let Some(b) = bio {
+            self.bio = Some(b);
+        }
+        if let Some(e) = email {
+            self.email = Some(e);
+        }
+    }
+
+    /// Get public key as hex string
+    pub fn address(&self) -> String {
+        hex::encode(self.public_key.as_bytes())
+    }
+}
+
+/// A buyer's review of a seller, left after a completed order
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Review {
+    /// Order the review is about (one review per order)
+    pub order_id: Hash,
+
+    /// Buyer who wrote the review
+    pub reviewer: PublicKey,
+
+    /// Seller being reviewed
+    pub seller: PublicKey,
+
+    /// Rating (1-5 stars)
+    pub rating: u8,
+
+    /// Free-form comment
+    pub comment: String,
+
+    /// Timestamp when the review was left
+    pub created_at: u64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -82,3 +229,29 @@ pub enum OrderStatus {
     Completed,
     Cancelled,
 }
+
+impl OrderStatus {
+    /// Completed and cancelled orders have settled and never move again
+    pub fn is_final(&self) -> bool {
+        matches!(self, OrderStatus::Completed | OrderStatus::Cancelled)
+    }
+}
+
+impl Order {
+    /// Roles `key` holds in this order; none if it is not a party
+    pub fn roles_of(&self, key: &PublicKey) -> Vec<Role> {
+        let mut roles = Vec::new();
+        if *key == self.buyer {
+            roles.push(Role::Buyer);
+        }
+        if *key == self.seller {
+            roles.push(Role::Seller);
+        }
+        roles
+    }
+
+    /// Check that a key holding `roles` may move this order to `status`
+    pub fn check_transition(&self, roles: &[Role], status: &OrderStatus) -> Result<(), TransitionError<OrderStatus>> {
+        lifecycle::check_by(ORDER_TRANSITIONS, &self.status, status, roles)
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
-use tari_l2_common::{Amount, Hash, PublicKey, Signature, crypto};
+use tari_l2_common::{Amount, Hash, PublicKey, Signature, canonical, crypto, crypto::SigningContext, musig::AggregateSignature};
+use crate::lifecycle::{self, ORDER_TRANSITIONS};
 use crate::state::{ChannelState, Listing, Order, OrderStatus};
 use tari_l2_common::{L2Error, error::Result};
 
@@ -35,15 +36,39 @@ pub enum StateUpdate {
         order_id: Hash,
         status: OrderStatus,
     },
+
+    /// Move an order's amount out of the buyer's balance into escrow. The
+    /// lock pays the seller when the order completes, or returns to the
+    /// buyer if it is cancelled.
+    LockEscrow {
+        order_id: Hash,
+    },
+
+    /// Complete an order whose escrow paid the seller on L1, in
+    /// `payout_tx_id`. Balances in the channel are left alone, since the
+    /// buyer paid through the escrow rather than from the channel.
+    CompleteOrderPaidOnL1 {
+        order_id: Hash,
+        payout_tx_id: String,
+    },
+
+    /// Transfer funds in payment of the invoice `invoice_id`, so the seller
+    /// can match the payment to the invoice it issued
+    PayInvoice {
+        invoice_id: Hash,
+        from: PublicKey,
+        to: PublicKey,
+        amount: Amount,
+    },
 }
 
 impl StateUpdate {
     /// Apply this update to a state, returning the new state
     pub fn apply(&self, mut state: ChannelState) -> Result<ChannelState> {
         match self {
-            StateUpdate::Transfer { from, to, amount } => {
+            StateUpdate::Transfer { from, to, amount }
+            | StateUpdate::PayInvoice { from, to, amount, .. } => {
                 let from_balance = state.get_balance(from);
-                let to_balance = state.get_balance(to);
 
                 if from_balance < *amount {
                     return Err(L2Error::InsufficientBalance {
@@ -54,10 +79,11 @@ impl StateUpdate {
 
                 let new_from_balance = from_balance.checked_sub(*amount)
                     .ok_or(L2Error::InvalidStateTransition)?;
-                let new_to_balance = to_balance.checked_add(*amount)
-                    .ok_or(L2Error::InvalidStateTransition)?;
-
                 state.set_balance(*from, new_from_balance);
+
+                // Read after debiting so a transfer to self is a no-op
+                let new_to_balance = state.get_balance(to).checked_add(*amount)
+                    .ok_or(L2Error::InvalidStateTransition)?;
                 state.set_balance(*to, new_to_balance);
             }
 
@@ -101,36 +127,97 @@ impl StateUpdate {
 
                 let order = &state.orders[order_idx];
 
-                // On completion, transfer funds to seller
-                if *status == OrderStatus::Completed && order.status != OrderStatus::Completed {
-                    let buyer = order.buyer;
-                    let seller = order.seller;
-                    let amount = order.amount;
+                // Repeating a final status changes nothing, so an escrow
+                // settling after its order can still propose it; any other
+                // move must be in the transition table
+                if !(order.status.is_final() && order.status == *status) {
+                    lifecycle::check(ORDER_TRANSITIONS, &order.status, status)?;
+                }
+                let (buyer, seller, amount) = (order.buyer, order.seller, order.amount);
 
-                    let buyer_balance = state.get_balance(&buyer);
-                    let seller_balance = state.get_balance(&seller);
+                // On completion, transfer funds to seller, from escrow if they were locked
+                if *status == OrderStatus::Completed && order.status != OrderStatus::Completed {
+                    if state.escrow_locks.remove(order_id).is_none() {
+                        let new_buyer_balance = state.get_balance(&buyer).checked_sub(amount)
+                            .ok_or(L2Error::InvalidStateTransition)?;
+                        state.set_balance(buyer, new_buyer_balance);
+                    }
 
-                    let new_buyer_balance = buyer_balance.checked_sub(amount)
+                    let new_seller_balance = state.get_balance(&seller).checked_add(amount)
                         .ok_or(L2Error::InvalidStateTransition)?;
-                    let new_seller_balance = seller_balance.checked_add(amount)
-                        .ok_or(L2Error::InvalidStateTransition)?;
-
-                    state.set_balance(buyer, new_buyer_balance);
                     state.set_balance(seller, new_seller_balance);
                 }
 
+                // On cancellation, locked funds go back to the buyer
+                if *status == OrderStatus::Cancelled {
+                    if let Some(locked) = state.escrow_locks.remove(order_id) {
+                        let new_buyer_balance = state.get_balance(&buyer).checked_add(locked)
+                            .ok_or(L2Error::InvalidStateTransition)?;
+                        state.set_balance(buyer, new_buyer_balance);
+                    }
+                }
+
                 state.orders[order_idx].status = status.clone();
             }
+
+            StateUpdate::LockEscrow { order_id } => {
+                let order = state.orders.iter()
+                    .find(|o| &o.id == order_id)
+                    .ok_or(L2Error::InvalidStateTransition)?;
+
+                if matches!(order.status, OrderStatus::Completed | OrderStatus::Cancelled)
+                    || state.escrow_locks.contains_key(order_id)
+                {
+                    return Err(L2Error::InvalidStateTransition);
+                }
+
+                let buyer = order.buyer;
+                let amount = order.amount;
+                let buyer_balance = state.get_balance(&buyer);
+                let new_buyer_balance = buyer_balance.checked_sub(amount)
+                    .ok_or(L2Error::InsufficientBalance {
+                        required: amount.value(),
+                        available: buyer_balance.value(),
+                    })?;
+                state.set_balance(buyer, new_buyer_balance);
+                state.escrow_locks.insert(*order_id, amount);
+            }
+
+            StateUpdate::CompleteOrderPaidOnL1 { order_id, .. } => {
+                let order = state.orders.iter_mut()
+                    .find(|o| &o.id == order_id)
+                    .ok_or(L2Error::InvalidStateTransition)?;
+
+                // Funds locked in the channel are paid out by completing
+                // the order there, never on L1
+                if state.escrow_locks.contains_key(order_id) {
+                    return Err(L2Error::InvalidStateTransition);
+                }
+                if order.status != OrderStatus::Completed {
+                    lifecycle::check(ORDER_TRANSITIONS, &order.status, &OrderStatus::Completed)?;
+                }
+                order.status = OrderStatus::Completed;
+            }
         }
 
         state.increment_nonce();
         Ok(state)
     }
 
+    /// Order this update creates or changes, if any
+    pub fn order_id(&self) -> Option<Hash> {
+        match self {
+            StateUpdate::CreateOrder { order } => Some(order.id),
+            StateUpdate::UpdateOrderStatus { order_id, .. }
+            | StateUpdate::LockEscrow { order_id }
+            | StateUpdate::CompleteOrderPaidOnL1 { order_id, .. } => Some(*order_id),
+            _ => None,
+        }
+    }
+
     /// Calculate hash of this update for signing
     pub fn hash(&self) -> Hash {
-        let serialized = bincode::serialize(self).expect("Serialization should not fail");
-        crypto::hash_data(&serialized)
+        canonical::hash(self).expect("Serialization should not fail")
     }
 }
 
@@ -139,7 +226,11 @@ impl StateUpdate {
 pub struct SignedStateUpdate {
     pub update: StateUpdate,
     pub nonce: u64,
+    #[serde(serialize_with = "canonical::sorted_map")]
     pub signatures: HashMap<PublicKey, Signature>,
+    /// Single aggregate signature from all participants, replacing `signatures`
+    #[serde(default)]
+    pub aggregate: Option<AggregateSignature>,
 }
 
 impl SignedStateUpdate {
@@ -148,6 +239,7 @@ impl SignedStateUpdate {
             update,
             nonce,
             signatures: HashMap::new(),
+            aggregate: None,
         }
     }
 
@@ -155,8 +247,18 @@ impl SignedStateUpdate {
         self.signatures.insert(participant, signature);
     }
 
+    /// Attach an aggregate signature and drop the individual ones
+    pub fn set_aggregate_signature(&mut self, aggregate: AggregateSignature) {
+        self.signatures.clear();
+        self.aggregate = Some(aggregate);
+    }
+
     /// Verify all signatures
     pub fn verify(&self, participants: &[PublicKey]) -> bool {
+        if let Some(aggregate) = &self.aggregate {
+            return aggregate.verify(&self.signing_message(), participants, participants.len());
+        }
+
         // Must have signatures from all participants
         if self.signatures.len() != participants.len() {
             return false;
@@ -164,24 +266,22 @@ impl SignedStateUpdate {
 
         let message = self.signing_message();
 
+        let mut signatures = Vec::with_capacity(participants.len());
         for participant in participants {
             match self.signatures.get(participant) {
-                Some(sig) => {
-                    if !crypto::verify_signature(participant, &message, sig) {
-                        return false;
-                    }
-                }
+                Some(sig) => signatures.push(sig.clone()),
                 None => return false,
             }
         }
 
-        true
+        let messages = vec![message.as_slice(); participants.len()];
+        crypto::verify_signatures_batch(participants, &messages, &signatures)
     }
 
-    /// Get the message that should be signed
-    fn signing_message(&self) -> Vec<u8> {
-        let mut data = bincode::serialize(&self.update).expect("Serialization should not fail");
+    /// Get the message that should be signed, bound to the state update context
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut data = canonical::encode(&self.update).expect("Serialization should not fail");
         data.extend_from_slice(&self.nonce.to_le_bytes());
-        data
+        SigningContext::StateUpdate.message(&data)
     }
 }
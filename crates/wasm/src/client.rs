@@ -0,0 +1,225 @@
+use std::future::Future;
+use std::rc::Rc;
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::Value;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::{JsFuture, future_to_promise};
+use web_sys::{Request, RequestInit, RequestMode, Response};
+use tari_l2_client::{ClientError, L2Client, Result, Transport};
+use tari_l2_client::types::{NewEscrow, NewListing};
+use tari_l2_common::{Hash, PublicKey};
+use tari_l2_state_channel::StateUpdate;
+use crate::{WasmWallet, client_error, from_js, parse, to_js};
+
+/// JSON-RPC over the browser's `fetch`, from a page or a worker
+struct FetchTransport {
+    url: String,
+}
+
+impl FetchTransport {
+    fn fetch(&self, request: &Request) -> Result<js_sys::Promise> {
+        let global = js_sys::global();
+        if let Some(window) = global.dyn_ref::<web_sys::Window>() {
+            Ok(window.fetch_with_request(request))
+        } else if let Some(worker) = global.dyn_ref::<web_sys::WorkerGlobalScope>() {
+            Ok(worker.fetch_with_request(request))
+        } else {
+            Err(ClientError::Transport("fetch is not available".to_string()))
+        }
+    }
+}
+
+fn js_message(value: JsValue) -> String {
+    value.dyn_ref::<js_sys::Error>()
+        .map(|e| String::from(e.message()))
+        .unwrap_or_else(|| format!("{:?}", value))
+}
+
+#[async_trait(?Send)]
+impl Transport for FetchTransport {
+    async fn post(&self, body: Vec<u8>) -> Result<Vec<u8>> {
+        let body = String::from_utf8(body).map_err(|e| ClientError::Transport(e.to_string()))?;
+        let init = RequestInit::new();
+        init.set_method("POST");
+        init.set_mode(RequestMode::Cors);
+        init.set_body(&JsValue::from_str(&body));
+
+        let request = Request::new_with_str_and_init(&self.url, &init)
+            .map_err(|e| ClientError::Transport(js_message(e)))?;
+        request.headers().set("Content-Type", "application/json")
+            .map_err(|e| ClientError::Transport(js_message(e)))?;
+
+        // fetch does not say whether a failed request reached the node, so
+        // this is never reported as `Connect` and writes are not retried
+        let response: Response = JsFuture::from(self.fetch(&request)?).await
+            .and_then(|value| value.dyn_into())
+            .map_err(|e| ClientError::Transport(js_message(e)))?;
+        let text = response.text().map_err(|e| ClientError::Transport(js_message(e)))?;
+        let text = JsFuture::from(text).await
+            .map_err(|e| ClientError::Transport(js_message(e)))?
+            .as_string()
+            .unwrap_or_default();
+
+        if !response.ok() {
+            return Err(ClientError::Transport(format!("HTTP {}: {}", response.status(), text)));
+        }
+        Ok(text.into_bytes())
+    }
+}
+
+/// Client for a node's JSON-RPC API. Every method returns a `Promise`;
+/// failed RPCs reject with an `Error` carrying the node's `code` and `category`.
+#[wasm_bindgen]
+pub struct WasmClient {
+    inner: Rc<L2Client>,
+}
+
+impl WasmClient {
+    fn run<T, F, Fut>(&self, call: F) -> js_sys::Promise
+    where
+        T: Serialize,
+        F: FnOnce(Rc<L2Client>) -> Fut,
+        Fut: Future<Output = Result<T>> + 'static,
+    {
+        let future = call(self.inner.clone());
+        future_to_promise(async move {
+            let value = future.await.map_err(client_error)?;
+            to_js(&value)
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl WasmClient {
+    /// Client for the node at `url` without a wallet; only reads and
+    /// node-signed calls will be accepted
+    #[wasm_bindgen(constructor)]
+    pub fn new(url: String) -> WasmClient {
+        Self { inner: Rc::new(L2Client::with_transport(Arc::new(FetchTransport { url }))) }
+    }
+
+    /// Client that signs actions for `wallet`'s key locally
+    #[wasm_bindgen(js_name = withWallet)]
+    pub fn with_wallet(url: String, wallet: &WasmWallet) -> WasmClient {
+        let client = L2Client::with_transport(Arc::new(FetchTransport { url }))
+            .with_signer(wallet.keypair());
+        Self { inner: Rc::new(client) }
+    }
+
+    /// Call any RPC method with raw params
+    pub fn call(&self, method: String, params: JsValue) -> std::result::Result<js_sys::Promise, JsValue> {
+        let params: Value = if params.is_undefined() { Value::Null } else { from_js(params)? };
+        Ok(self.run(|client| async move { client.call::<_, Value>(&method, params).await }))
+    }
+
+    #[wasm_bindgen(js_name = getNodeInfo)]
+    pub fn get_node_info(&self) -> js_sys::Promise {
+        self.run(|client| async move { client.get_node_info().await })
+    }
+
+    #[wasm_bindgen(js_name = getChannelInfo)]
+    pub fn get_channel_info(&self, channel_id: &str) -> std::result::Result<js_sys::Promise, JsValue> {
+        let channel_id: Hash = parse(channel_id, "channel id")?;
+        Ok(self.run(move |client| async move { client.get_channel_info(&channel_id).await }))
+    }
+
+    #[wasm_bindgen(js_name = getBalance)]
+    pub fn get_balance(&self, channel_id: &str, participant: &str) -> std::result::Result<js_sys::Promise, JsValue> {
+        let channel_id: Hash = parse(channel_id, "channel id")?;
+        let participant: PublicKey = parse(participant, "public key")?;
+        Ok(self.run(move |client| async move { client.get_balance(&channel_id, &participant).await }))
+    }
+
+    /// Sign `update` as the channel's next state and have the node co-sign it
+    #[wasm_bindgen(js_name = proposeUpdate)]
+    pub fn propose_update(&self, channel_id: &str, update: JsValue) -> std::result::Result<js_sys::Promise, JsValue> {
+        let channel_id: Hash = parse(channel_id, "channel id")?;
+        let update: StateUpdate = from_js(update)?;
+        Ok(self.run(move |client| async move { client.propose_update(&channel_id, update).await }))
+    }
+
+    pub fn transfer(&self, channel_id: &str, from: &str, to: &str, amount: u64) -> std::result::Result<js_sys::Promise, JsValue> {
+        let channel_id: Hash = parse(channel_id, "channel id")?;
+        let from: PublicKey = parse(from, "public key")?;
+        let to: PublicKey = parse(to, "public key")?;
+        Ok(self.run(move |client| async move { client.transfer(&channel_id, from, to, amount).await }))
+    }
+
+    /// `listing` is `{ title, description, price, ipfs_hash?, category? }`;
+    /// the seller is the wallet's key
+    #[wasm_bindgen(js_name = createListing)]
+    pub fn create_listing(&self, listing: JsValue) -> std::result::Result<js_sys::Promise, JsValue> {
+        let listing: NewListing = from_js(listing)?;
+        Ok(self.run(|client| async move { client.create_listing(listing).await }))
+    }
+
+    #[wasm_bindgen(js_name = getListings)]
+    pub fn get_listings(&self) -> js_sys::Promise {
+        self.run(|client| async move { client.get_listings().await })
+    }
+
+    #[wasm_bindgen(js_name = getSellerCatalog)]
+    pub fn get_seller_catalog(&self, seller: &str) -> std::result::Result<js_sys::Promise, JsValue> {
+        let seller: PublicKey = parse(seller, "public key")?;
+        Ok(self.run(move |client| async move { client.get_seller_catalog(seller).await }))
+    }
+
+    #[wasm_bindgen(js_name = createOrder)]
+    pub fn create_order(&self, channel_id: &str, listing_id: &str, buyer: &str) -> std::result::Result<js_sys::Promise, JsValue> {
+        let channel_id: Hash = parse(channel_id, "channel id")?;
+        let listing_id: Hash = parse(listing_id, "listing id")?;
+        let buyer: PublicKey = parse(buyer, "public key")?;
+        Ok(self.run(move |client| async move { client.create_order(&channel_id, &listing_id, buyer).await }))
+    }
+
+    #[wasm_bindgen(js_name = getOrders)]
+    pub fn get_orders(&self) -> js_sys::Promise {
+        self.run(|client| async move { client.get_orders().await })
+    }
+
+    #[wasm_bindgen(js_name = getOrder)]
+    pub fn get_order(&self, order_id: &str) -> std::result::Result<js_sys::Promise, JsValue> {
+        let order_id: Hash = parse(order_id, "order id")?;
+        Ok(self.run(move |client| async move { client.get_order(&order_id).await }))
+    }
+
+    /// `escrow` is `{ channel_id, order_id, timeout_period? }`
+    #[wasm_bindgen(js_name = createEscrow)]
+    pub fn create_escrow(&self, escrow: JsValue) -> std::result::Result<js_sys::Promise, JsValue> {
+        let escrow: NewEscrow = from_js(escrow)?;
+        Ok(self.run(|client| async move { client.create_escrow(&escrow).await }))
+    }
+
+    #[wasm_bindgen(js_name = getEscrow)]
+    pub fn get_escrow(&self, escrow_id: &str) -> std::result::Result<js_sys::Promise, JsValue> {
+        let escrow_id: Hash = parse(escrow_id, "escrow id")?;
+        Ok(self.run(move |client| async move { client.get_escrow(&escrow_id).await }))
+    }
+
+    #[wasm_bindgen(js_name = confirmDelivery)]
+    pub fn confirm_delivery(&self, escrow_id: &str) -> std::result::Result<js_sys::Promise, JsValue> {
+        let escrow_id: Hash = parse(escrow_id, "escrow id")?;
+        Ok(self.run(move |client| async move { client.confirm_delivery(&escrow_id).await }))
+    }
+
+    #[wasm_bindgen(js_name = requestRefund)]
+    pub fn request_refund(&self, escrow_id: &str, reason: String) -> std::result::Result<js_sys::Promise, JsValue> {
+        let escrow_id: Hash = parse(escrow_id, "escrow id")?;
+        Ok(self.run(move |client| async move { client.request_refund(&escrow_id, &reason).await }))
+    }
+
+    #[wasm_bindgen(js_name = refundUnshipped)]
+    pub fn refund_unshipped(&self, escrow_id: &str) -> std::result::Result<js_sys::Promise, JsValue> {
+        let escrow_id: Hash = parse(escrow_id, "escrow id")?;
+        Ok(self.run(move |client| async move { client.refund_unshipped(&escrow_id).await }))
+    }
+
+    #[wasm_bindgen(js_name = raiseDispute)]
+    pub fn raise_dispute(&self, escrow_id: &str, reason: String) -> std::result::Result<js_sys::Promise, JsValue> {
+        let escrow_id: Hash = parse(escrow_id, "escrow id")?;
+        Ok(self.run(move |client| async move { client.raise_dispute(&escrow_id, &reason).await }))
+    }
+}
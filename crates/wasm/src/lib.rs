@@ -0,0 +1,60 @@
+//! Browser bindings for the Tari L2 client and wallet.
+//!
+//! Storefronts hold the user's wallet in the page, sign listings, orders,
+//! transfers and channel updates locally, and send the node only the signed
+//! payloads. Build with `wasm-pack build crates/wasm --target web`.
+//!
+//! ```js
+//! import init, { WasmWallet, WasmClient } from "./pkg/tari_l2_wasm.js";
+//!
+//! await init();
+//! const wallet = WasmWallet.fromSeedPhrase(words);
+//! const client = WasmClient.withWallet("http://127.0.0.1:18000", wallet);
+//! const listing = await client.createListing({ title: "Lamp", description: "", price: 40 });
+//! ```
+//!
+//! The bindings only exist on wasm32; native workspace builds compile an
+//! empty crate.
+
+#![cfg(target_arch = "wasm32")]
+
+mod client;
+mod wallet;
+
+pub use client::WasmClient;
+pub use wallet::WasmWallet;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+use tari_l2_client::ClientError;
+
+/// Convert to a plain JS value, with maps as objects rather than `Map`s
+fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsValue> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&e.to_string()).into())
+}
+
+fn from_js<T: serde::de::DeserializeOwned>(value: JsValue) -> Result<T, JsValue> {
+    serde_wasm_bindgen::from_value(value).map_err(|e| JsError::new(&e.to_string()).into())
+}
+
+/// Parse a hex hash or public key argument
+fn parse<T: std::str::FromStr>(value: &str, what: &str) -> Result<T, JsValue>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse().map_err(|e| JsError::new(&format!("invalid {}: {}", what, e)).into())
+}
+
+/// `Error` carrying the node's `code` and `category` for RPC failures
+fn client_error(error: ClientError) -> JsValue {
+    let js_error = js_sys::Error::new(&error.to_string());
+    if let ClientError::Rpc { code, category, .. } = &error {
+        let _ = js_sys::Reflect::set(&js_error, &"code".into(), &(*code).into());
+        if let Some(category) = category {
+            let _ = js_sys::Reflect::set(&js_error, &"category".into(), &category.as_str().into());
+        }
+    }
+    js_error.into()
+}
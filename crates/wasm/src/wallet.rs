@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+use tari_l2_client::signing;
+use tari_l2_common::crypto::KeyPair;
+use tari_l2_marketplace::Wallet;
+use tari_l2_state_channel::StateUpdate;
+use crate::{from_js, to_js};
+
+/// Tari wallet held in the page. Marketplace actions are signed with its
+/// channel key, which never leaves the browser.
+#[wasm_bindgen]
+pub struct WasmWallet {
+    wallet: Wallet,
+    keypair: Arc<KeyPair>,
+}
+
+impl WasmWallet {
+    fn from_wallet(wallet: Wallet) -> Result<WasmWallet, JsError> {
+        let keypair = wallet.channel_keypair().map_err(|e| JsError::new(&e))?;
+        Ok(Self { wallet, keypair: Arc::new(keypair) })
+    }
+
+    pub(crate) fn keypair(&self) -> Arc<KeyPair> {
+        self.keypair.clone()
+    }
+}
+
+#[wasm_bindgen]
+impl WasmWallet {
+    /// Generate a new wallet with a 24-word seed phrase
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<WasmWallet, JsError> {
+        Self::from_wallet(Wallet::new())
+    }
+
+    #[wasm_bindgen(js_name = fromSeedPhrase)]
+    pub fn from_seed_phrase(seed_phrase: &str) -> Result<WasmWallet, JsError> {
+        Self::from_wallet(Wallet::from_seed_phrase(seed_phrase).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Import a hex-encoded spend key
+    #[wasm_bindgen(js_name = fromPrivateKey)]
+    pub fn from_private_key(private_key: &str) -> Result<WasmWallet, JsError> {
+        Self::from_wallet(Wallet::from_private_key(private_key).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Tari address (emoji form)
+    pub fn address(&self) -> String {
+        self.wallet.address()
+    }
+
+    /// Seed phrase, for wallets that were generated or imported from one
+    #[wasm_bindgen(js_name = seedPhrase)]
+    pub fn seed_phrase(&self) -> Option<String> {
+        self.wallet.seed_phrase().map(|phrase| phrase.to_string())
+    }
+
+    /// Key the node sees as seller, buyer or channel participant
+    #[wasm_bindgen(js_name = publicKey)]
+    pub fn public_key(&self) -> String {
+        self.keypair.public_key().to_string()
+    }
+
+    /// Attach an `authorization` to RPC params for a call to `method`, such
+    /// as `create_listing`, `create_order` or `transfer`. Send the result
+    /// unchanged, to that method only.
+    pub fn authorize(&self, method: &str, params: JsValue) -> Result<JsValue, JsValue> {
+        let params: Value = from_js(params)?;
+        let signed = signing::authorize(method, params, &self.keypair).map_err(|e| JsError::new(&e.to_string()))?;
+        to_js(&signed)
+    }
+
+    /// Sign a channel update as state `nonce`, for `submit_state_update`
+    #[wasm_bindgen(js_name = signUpdate)]
+    pub fn sign_update(&self, update: JsValue, nonce: u64) -> Result<JsValue, JsValue> {
+        let update: StateUpdate = from_js(update)?;
+        to_js(&signing::sign_update(update, nonce, &self.keypair))
+    }
+
+    /// Hex-encoded signature over `message` with the channel key
+    #[wasm_bindgen(js_name = signMessage)]
+    pub fn sign_message(&self, message: &[u8]) -> String {
+        self.keypair.sign(message).to_string()
+    }
+}
@@ -1,7 +1,59 @@
+#[path = "support/rpc_client.rs"]
+mod rpc_client;
+
 use std::io::{self, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::net::TcpStream;
-use std::io::{BufRead, BufReader};
+use tari_l2_common::crypto::KeyPair;
+use tari_l2_marketplace::{SignedAction, Wallet, rpc_call_payload};
+
+/// Encrypted wallet written by the node's `wallet_create` RPC
+const WALLET_FILE: &str = "./data/wallet.keystore";
+/// Nodes the CLI has been pointed at, remembered across runs
+const ENDPOINTS_FILE: &str = "./data/cli_endpoints.json";
+const DEFAULT_ENDPOINT: &str = "127.0.0.1:18000";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Endpoints {
+    current: String,
+    saved: Vec<String>,
+}
+
+impl Endpoints {
+    fn load() -> Self {
+        let mut endpoints = std::fs::read_to_string(ENDPOINTS_FILE)
+            .ok()
+            .and_then(|data| serde_json::from_str::<Endpoints>(&data).ok())
+            .unwrap_or_default();
+
+        // TARI_L2_RPC overrides the saved choice for this run
+        if let Ok(endpoint) = std::env::var("TARI_L2_RPC") {
+            endpoints.current = endpoint;
+        }
+        if endpoints.current.is_empty() {
+            endpoints.current = DEFAULT_ENDPOINT.to_string();
+        }
+        if !endpoints.saved.contains(&endpoints.current) {
+            endpoints.saved.push(endpoints.current.clone());
+        }
+        endpoints
+    }
+
+    fn save(&self) -> Result<(), String> {
+        if let Some(dir) = Path::new(ENDPOINTS_FILE).parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let data = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(ENDPOINTS_FILE, data).map_err(|e| e.to_string())
+    }
+}
+
+struct Cli {
+    endpoints: Endpoints,
+    /// Channel key derived from the local wallet, used to sign actions
+    wallet_key: Option<KeyPair>,
+}
 
 fn main() {
     println!("╔══════════════════════════════════════════════╗");
@@ -9,30 +61,35 @@ fn main() {
     println!("╚══════════════════════════════════════════════╝");
     println!();
 
+    let mut cli = Cli {
+        endpoints: Endpoints::load(),
+        wallet_key: load_wallet_key(),
+    };
+    println!("🔌 Node: {}", cli.endpoints.current);
+    match &cli.wallet_key {
+        Some(key) => println!("🔑 Wallet key: {}", key.public_key()),
+        None => println!("⚠️  No wallet at {} - create one with the node's wallet_create RPC", WALLET_FILE),
+    }
+
     loop {
         println!("\n📋 MAIN MENU:");
         println!("  1. List all channels");
         println!("  2. Get channel info");
         println!("  3. Get balance");
-        println!("  4. Create listing (stub)");
-        println!("  5. Create order (stub)");
-        println!("  6. Transfer funds (stub)");
+        println!("  4. Create listing");
+        println!("  5. Create order");
+        println!("  6. Transfer funds");
         println!("  7. Switch node");
         println!("  8. Exit");
-        print!("\nSelect option: ");
-        io::stdout().flush().unwrap();
-
-        let mut choice = String::new();
-        io::stdin().read_line(&mut choice).unwrap();
-
-        match choice.trim() {
-            "1" => list_channels(),
-            "2" => get_channel_info(),
-            "3" => get_balance(),
-            "4" => println!("📝 Create listing - Coming in v0.2.0"),
-            "5" => println!("🛒 Create order - Coming in v0.2.0"),
-            "6" => println!("💸 Transfer - Coming in v0.2.0"),
-            "7" => switch_node(),
+
+        match prompt("\nSelect option").as_str() {
+            "1" => cli.list_channels(),
+            "2" => cli.get_channel_info(),
+            "3" => cli.get_balance(),
+            "4" => cli.create_listing(),
+            "5" => cli.create_order(),
+            "6" => cli.transfer(),
+            "7" => cli.switch_node(),
             "8" => {
                 println!("👋 Goodbye!");
                 break;
@@ -42,133 +99,235 @@ fn main() {
     }
 }
 
-fn get_node_port() -> u16 {
-    // Default to node 1
-    18000
+fn prompt(label: &str) -> String {
+    print!("{}: ", label);
+    io::stdout().flush().unwrap();
+    let mut line = String::new();
+    io::stdin().read_line(&mut line).unwrap();
+    line.trim().to_string()
 }
 
-fn send_rpc_request(method: &str, params: Option<Value>) -> Result<Value, String> {
-    let port = get_node_port();
+/// Decrypt the keystore with `TARI_L2_WALLET_PASSPHRASE`, or a passphrase
+/// typed at the prompt
+fn load_wallet_key() -> Option<KeyPair> {
+    let keystore = std::fs::read(WALLET_FILE).ok()?;
+    let passphrase = std::env::var("TARI_L2_WALLET_PASSPHRASE")
+        .unwrap_or_else(|_| prompt("🔐 Wallet passphrase"));
+    match Wallet::from_keystore(&keystore, &passphrase) {
+        Ok(wallet) => wallet.channel_keypair().ok(),
+        Err(e) => {
+            println!("❌ Could not open {}: {}", WALLET_FILE, e);
+            None
+        }
+    }
+}
 
-    let request = json!({
-        "jsonrpc": "2.0",
-        "method": method,
-        "params": params,
-        "id": 1
-    });
+impl Cli {
+    fn send_rpc_request(&self, method: &str, params: Option<Value>) -> Result<Value, String> {
+        rpc_client::call(&self.endpoints.current, method, params)
+    }
 
-    let request_str = format!("{}\n", serde_json::to_string(&request).unwrap());
+    /// Attach an `authorization` signed by the wallet key over a call to
+    /// `method` with `params`
+    fn sign_params(&self, method: &str, params: Value) -> Result<Value, String> {
+        let key = self.wallet_key.as_ref()
+            .ok_or_else(|| format!("No wallet loaded from {}", WALLET_FILE))?;
 
-    match TcpStream::connect(format!("127.0.0.1:{}", port)) {
-        Ok(mut stream) => {
-            use std::io::Write;
-            stream.write_all(request_str.as_bytes()).map_err(|e| e.to_string())?;
+        let action = SignedAction::new(rpc_call_payload(method, &params), key.public_key(), |message| key.sign(message))?;
+        let mut signed = params;
+        signed["authorization"] = json!({
+            "public_key": action.public_key,
+            "signature": action.signature,
+            "timestamp": action.timestamp,
+        });
+        Ok(signed)
+    }
 
-            let mut reader = BufReader::new(stream);
-            let mut response = String::new();
-            reader.read_line(&mut response).map_err(|e| e.to_string())?;
+    fn wallet_public_key(&self) -> Result<String, String> {
+        self.wallet_key.as_ref()
+            .map(|key| key.public_key().to_string())
+            .ok_or_else(|| format!("No wallet loaded from {}", WALLET_FILE))
+    }
 
-            let json: Value = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+    fn list_channels(&self) {
+        println!("\n📊 Listing all channels...");
 
-            if let Some(error) = json.get("error") {
-                if !error.is_null() {
-                    return Err(format!("RPC Error: {}", error));
+        match self.send_rpc_request("list_channels", None) {
+            Ok(Value::Array(channels)) if channels.is_empty() => {
+                println!("   No channels found.");
+                println!("   💡 Tip: Run the demo to create test channels:");
+                println!("      cargo run --example marketplace_demo");
+            }
+            Ok(Value::Array(channels)) => {
+                println!("   Found {} channel(s):", channels.len());
+                for (i, channel) in channels.iter().enumerate() {
+                    println!("   {}. {}", i + 1, serde_json::to_string_pretty(channel).unwrap());
                 }
             }
-            Ok(json["result"].clone())
+            Ok(result) => println!("   ✅ Result: {}", serde_json::to_string_pretty(&result).unwrap()),
+            Err(e) => println!("   ❌ Error: {}", e),
         }
-        Err(e) => Err(format!("Connection failed: {}. Is the node running?", e))
     }
-}
 
-fn list_channels() {
-    println!("\n📊 Listing all channels...");
-
-    match send_rpc_request("list_channels", None) {
-        Ok(result) => {
-            if result.is_array() {
-                let channels = result.as_array().unwrap();
-                if channels.is_empty() {
-                    println!("   No channels found.");
-                    println!("   💡 Tip: Run the demo to create test channels:");
-                    println!("      cargo run --example marketplace_demo");
-                } else {
-                    println!("   Found {} channel(s):", channels.len());
-                    for (i, channel) in channels.iter().enumerate() {
-                        println!("   {}. {}", i + 1, serde_json::to_string_pretty(channel).unwrap());
-                    }
-                }
-            } else {
-                println!("   ✅ Result: {}", serde_json::to_string_pretty(&result).unwrap());
+    fn get_channel_info(&self) {
+        let channel_id = prompt("\nEnter channel ID (hex)");
+        if channel_id.is_empty() {
+            println!("❌ Channel ID cannot be empty");
+            return;
+        }
+
+        println!("\n📋 Getting channel info...");
+        match self.send_rpc_request("get_channel_info", Some(json!({ "channel_id": channel_id }))) {
+            Ok(result) => {
+                println!("   ✅ Channel Info:");
+                println!("{}", serde_json::to_string_pretty(&result).unwrap());
             }
+            Err(e) => println!("   ❌ Error: {}", e),
         }
-        Err(e) => println!("   ❌ Error: {}", e)
     }
-}
 
-fn get_channel_info() {
-    print!("\nEnter channel ID (hex): ");
-    io::stdout().flush().unwrap();
+    fn get_balance(&self) {
+        let channel_id = prompt("\nEnter channel ID (hex)");
+        let mut participant = prompt("Enter participant public key or address (blank for your wallet)");
+        if participant.is_empty() {
+            participant = match self.wallet_public_key() {
+                Ok(pk) => pk,
+                Err(e) => {
+                    println!("❌ {}", e);
+                    return;
+                }
+            };
+        }
 
-    let mut channel_id = String::new();
-    io::stdin().read_line(&mut channel_id).unwrap();
-    let channel_id = channel_id.trim();
+        if channel_id.is_empty() {
+            println!("❌ Channel ID is required");
+            return;
+        }
 
-    if channel_id.is_empty() {
-        println!("❌ Channel ID cannot be empty");
-        return;
+        println!("\n💰 Getting balance...");
+        let params = json!({
+            "channel_id": channel_id,
+            "participant": participant
+        });
+        match self.send_rpc_request("get_balance", Some(params)) {
+            Ok(result) => println!("   ✅ Balance: {} units", result),
+            Err(e) => println!("   ❌ Error: {}", e),
+        }
     }
 
-    println!("\n📋 Getting channel info...");
+    fn create_listing(&self) {
+        let title = prompt("\nTitle");
+        let description = prompt("Description");
+        let price = prompt("Price (units)");
+        let category = prompt("Category (blank for 'other')");
+
+        let Ok(price) = price.parse::<u64>() else {
+            println!("❌ Price must be a whole number");
+            return;
+        };
+        if title.is_empty() {
+            println!("❌ Title is required");
+            return;
+        }
 
-    let params = json!({
-        "channel_id": channel_id
-    });
+        println!("\n📝 Creating listing...");
+        let result = self.wallet_public_key()
+            .and_then(|seller| self.sign_params("create_listing", json!({
+                "seller_pubkey": seller,
+                "title": title,
+                "description": description,
+                "price": price,
+                "category": if category.is_empty() { "other".to_string() } else { category },
+            })))
+            .and_then(|params| self.send_rpc_request("create_listing", Some(params)));
 
-    match send_rpc_request("get_channel_info", Some(params)) {
-        Ok(result) => {
-            println!("   ✅ Channel Info:");
-            println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        match result {
+            Ok(result) => println!("   ✅ Listing created: {}", result["id"]),
+            Err(e) => println!("   ❌ Error: {}", e),
         }
-        Err(e) => println!("   ❌ Error: {}", e)
     }
-}
 
-fn get_balance() {
-    print!("\nEnter channel ID (hex): ");
-    io::stdout().flush().unwrap();
-    let mut channel_id = String::new();
-    io::stdin().read_line(&mut channel_id).unwrap();
-
-    print!("Enter participant public key (hex): ");
-    io::stdout().flush().unwrap();
-    let mut participant = String::new();
-    io::stdin().read_line(&mut participant).unwrap();
+    fn create_order(&self) {
+        let channel_id = prompt("\nChannel ID (hex)");
+        let listing_id = prompt("Listing ID (hex)");
+        if channel_id.is_empty() || listing_id.is_empty() {
+            println!("❌ Both fields are required");
+            return;
+        }
 
-    let channel_id = channel_id.trim();
-    let participant = participant.trim();
+        println!("\n🛒 Creating order...");
+        let result = self.wallet_public_key()
+            .and_then(|buyer| self.sign_params("create_order", json!({
+                "channel_id": channel_id,
+                "listing_id": listing_id,
+                "buyer": buyer,
+            })))
+            .and_then(|params| self.send_rpc_request("create_order", Some(params)));
 
-    if channel_id.is_empty() || participant.is_empty() {
-        println!("❌ Both fields are required");
-        return;
+        match result {
+            Ok(result) => println!("   ✅ Order created: {}", result["id"]),
+            Err(e) => println!("   ❌ Error: {}", e),
+        }
     }
 
-    println!("\n💰 Getting balance...");
+    fn transfer(&self) {
+        let channel_id = prompt("\nChannel ID (hex)");
+        let to = prompt("Recipient public key or address");
+        let amount = prompt("Amount (units)");
+
+        let Ok(amount) = amount.parse::<u64>() else {
+            println!("❌ Amount must be a whole number");
+            return;
+        };
+        if channel_id.is_empty() || to.is_empty() {
+            println!("❌ Channel ID and recipient are required");
+            return;
+        }
 
-    let params = json!({
-        "channel_id": channel_id,
-        "participant": participant
-    });
+        println!("\n💸 Transferring {} units...", amount);
+        let result = self.wallet_public_key()
+            .and_then(|from| self.sign_params("transfer", json!({
+                "channel_id": channel_id,
+                "from": from,
+                "to": to,
+                "amount": amount,
+            })))
+            .and_then(|params| self.send_rpc_request("transfer", Some(params)));
 
-    match send_rpc_request("get_balance", Some(params)) {
-        Ok(result) => {
-            println!("   ✅ Balance: {} units", result);
+        match result {
+            Ok(result) => println!("   ✅ Transfer applied at nonce {}", result["nonce"]),
+            Err(e) => println!("   ❌ Error: {}", e),
         }
-        Err(e) => println!("   ❌ Error: {}", e)
     }
-}
 
-fn switch_node() {
-    println!("\n🔄 Node switching coming in v0.2.0");
-    println!("   Currently connected to: 127.0.0.1:{}", get_node_port());
+    fn switch_node(&mut self) {
+        println!("\n🔄 Saved nodes:");
+        for (i, endpoint) in self.endpoints.saved.iter().enumerate() {
+            let marker = if *endpoint == self.endpoints.current { " (current)" } else { "" };
+            println!("  {}. {}{}", i + 1, endpoint, marker);
+        }
+
+        let choice = prompt("Pick a number or enter host:port");
+        let endpoint = match choice.parse::<usize>() {
+            Ok(i) if (1..=self.endpoints.saved.len()).contains(&i) => self.endpoints.saved[i - 1].clone(),
+            _ if choice.contains(':') => choice,
+            _ => {
+                println!("❌ Invalid choice");
+                return;
+            }
+        };
+
+        if !self.endpoints.saved.contains(&endpoint) {
+            self.endpoints.saved.push(endpoint.clone());
+        }
+        self.endpoints.current = endpoint;
+
+        match self.send_rpc_request("get_node_info", None) {
+            Ok(info) => println!("   ✅ Connected to {} ({})", self.endpoints.current, info["public_key"]),
+            Err(e) => println!("   ⚠️  Switched to {}, but it is not answering: {}", self.endpoints.current, e),
+        }
+        if let Err(e) = self.endpoints.save() {
+            println!("   ⚠️  Could not save endpoints: {}", e);
+        }
+    }
 }
@@ -0,0 +1,253 @@
+//! Live dashboard for a running node.
+//!
+//! ```text
+//! cargo run -p tari-l2-marketplace --example marketplace_tui -- 127.0.0.1:18000
+//! ```
+//!
+//! Keys: `Tab`/`←`/`→` or `1`-`4` switch views, `↑`/`↓` select, `r` refreshes,
+//! `q` quits. Data refreshes every two seconds.
+
+#[path = "support/rpc_client.rs"]
+mod rpc_client;
+
+use std::time::{Duration, Instant};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Tabs, Wrap},
+    DefaultTerminal, Frame,
+};
+use serde_json::Value;
+
+const DEFAULT_ENDPOINT: &str = "127.0.0.1:18000";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, PartialEq)]
+enum View {
+    Channels,
+    Peers,
+    Listings,
+    Orders,
+}
+
+impl View {
+    const ALL: [View; 4] = [View::Channels, View::Peers, View::Listings, View::Orders];
+
+    fn title(self) -> &'static str {
+        match self {
+            View::Channels => "Channels",
+            View::Peers => "Peers",
+            View::Listings => "Listings",
+            View::Orders => "Orders",
+        }
+    }
+
+    fn index(self) -> usize {
+        View::ALL.iter().position(|v| *v == self).unwrap()
+    }
+
+    /// One-line summary of an item in this view
+    fn summary(self, item: &Value) -> String {
+        let short = |v: &Value| {
+            let s = v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string());
+            s.chars().take(16).collect::<String>()
+        };
+        match self {
+            View::Channels => format!("{}…  {}  nonce {}  collateral {}",
+                short(&item["channel_id"]), item["status"].as_str().unwrap_or("?"), item["nonce"], item["collateral"]),
+            View::Peers => short(item),
+            View::Listings => format!("{:<32} {:>10}  {}",
+                item["title"].as_str().unwrap_or("?"), item["price"], item["category"].as_str().unwrap_or("")),
+            View::Orders => format!("{}…  {:>10}  {}",
+                short(&item["id"]), item["amount"], item["status"].as_str().unwrap_or("?")),
+        }
+    }
+}
+
+struct App {
+    endpoint: String,
+    view: View,
+    node: Option<Value>,
+    channels: Vec<Value>,
+    peers: Vec<Value>,
+    listings: Vec<Value>,
+    orders: Vec<Value>,
+    selection: ListState,
+    status: String,
+    last_refresh: Option<Instant>,
+}
+
+impl App {
+    fn new(endpoint: String) -> Self {
+        Self {
+            endpoint,
+            view: View::Channels,
+            node: None,
+            channels: Vec::new(),
+            peers: Vec::new(),
+            listings: Vec::new(),
+            orders: Vec::new(),
+            selection: ListState::default().with_selected(Some(0)),
+            status: "Connecting…".to_string(),
+            last_refresh: None,
+        }
+    }
+
+    fn items(&self) -> &[Value] {
+        match self.view {
+            View::Channels => &self.channels,
+            View::Peers => &self.peers,
+            View::Listings => &self.listings,
+            View::Orders => &self.orders,
+        }
+    }
+
+    fn fetch_list(&self, method: &str) -> Result<Vec<Value>, String> {
+        match rpc_client::call(&self.endpoint, method, None)? {
+            Value::Array(items) => Ok(items),
+            other => Err(format!("{} returned {}", method, other)),
+        }
+    }
+
+    fn refresh(&mut self) {
+        let result = (|| -> Result<(), String> {
+            self.node = Some(rpc_client::call(&self.endpoint, "get_node_info", None)?);
+            self.channels = self.fetch_list("list_channels")?;
+            self.listings = self.fetch_list("get_listings")?;
+            self.orders = self.fetch_list("get_orders")?;
+            let peers = rpc_client::call(&self.endpoint, "list_peers", None)?;
+            self.peers = peers["peers"].as_array().cloned().unwrap_or_default();
+            Ok(())
+        })();
+
+        self.status = match result {
+            Ok(()) => format!("Updated {} channels, {} peers, {} listings, {} orders",
+                self.channels.len(), self.peers.len(), self.listings.len(), self.orders.len()),
+            Err(e) => format!("⚠ {}", e),
+        };
+        self.last_refresh = Some(Instant::now());
+        self.clamp_selection();
+    }
+
+    fn clamp_selection(&mut self) {
+        let len = self.items().len();
+        let selected = self.selection.selected().unwrap_or(0).min(len.saturating_sub(1));
+        self.selection.select(Some(selected));
+    }
+
+    fn switch(&mut self, view: View) {
+        self.view = view;
+        self.selection.select(Some(0));
+    }
+
+    fn step_view(&mut self, forward: bool) {
+        let len = View::ALL.len();
+        let index = if forward { (self.view.index() + 1) % len } else { (self.view.index() + len - 1) % len };
+        self.switch(View::ALL[index]);
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        let len = self.items().len();
+        if len == 0 {
+            return;
+        }
+        let current = self.selection.selected().unwrap_or(0);
+        let next = if down { (current + 1).min(len - 1) } else { current.saturating_sub(1) };
+        self.selection.select(Some(next));
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(5), Constraint::Length(3)])
+            .split(frame.area());
+
+        let titles: Vec<Line> = View::ALL.iter()
+            .map(|v| Line::from(format!(" {} {} ", v.index() + 1, v.title())))
+            .collect();
+        let node_key = self.node.as_ref()
+            .and_then(|n| n["address"].as_str())
+            .unwrap_or("not connected");
+        let tabs = Tabs::new(titles)
+            .select(self.view.index())
+            .block(Block::default().borders(Borders::ALL).title(format!(" Tari L2 · {} · {} ", self.endpoint, node_key)))
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+        frame.render_widget(tabs, rows[0]);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(rows[1]);
+
+        let view = self.view;
+        let items: Vec<ListItem> = self.items().iter()
+            .map(|item| ListItem::new(view.summary(item)))
+            .collect();
+        let empty = items.is_empty();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!(" {} ", view.title())))
+            .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+            .highlight_symbol("▶ ");
+        frame.render_stateful_widget(list, columns[0], &mut self.selection);
+
+        let detail = if empty {
+            format!("No {} yet.", view.title().to_lowercase())
+        } else {
+            self.selection.selected()
+                .and_then(|i| self.items().get(i))
+                .map(|item| serde_json::to_string_pretty(item).unwrap_or_default())
+                .unwrap_or_default()
+        };
+        let detail = Paragraph::new(detail)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title(" Details "));
+        frame.render_widget(detail, columns[1]);
+
+        let footer = Paragraph::new(format!("{}   ·   Tab/←/→ view  ↑/↓ select  r refresh  q quit", self.status))
+            .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(footer, rows[2]);
+    }
+}
+
+fn run(terminal: &mut DefaultTerminal, mut app: App) -> std::io::Result<()> {
+    app.refresh();
+
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+
+        let since_refresh = app.last_refresh.map(|t| t.elapsed()).unwrap_or(REFRESH_INTERVAL);
+        let timeout = REFRESH_INTERVAL.saturating_sub(since_refresh);
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('r') => app.refresh(),
+                    KeyCode::Tab | KeyCode::Right => app.step_view(true),
+                    KeyCode::BackTab | KeyCode::Left => app.step_view(false),
+                    KeyCode::Down | KeyCode::Char('j') => app.move_selection(true),
+                    KeyCode::Up | KeyCode::Char('k') => app.move_selection(false),
+                    KeyCode::Char(c @ '1'..='4') => app.switch(View::ALL[c as usize - '1' as usize]),
+                    _ => {}
+                }
+            }
+        } else {
+            app.refresh();
+        }
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let endpoint = std::env::args().nth(1)
+        .or_else(|| std::env::var("TARI_L2_RPC").ok())
+        .unwrap_or_else(|| DEFAULT_ENDPOINT.to_string());
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, App::new(endpoint));
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,44 @@
+//! Minimal blocking JSON-RPC over HTTP client shared by the examples
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+use serde_json::{json, Value};
+
+/// Call `method` on the node at `endpoint` (`host:port`), returning the
+/// `result` field or the RPC error message
+pub fn call(endpoint: &str, method: &str, params: Option<Value>) -> Result<Value, String> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params,
+        "id": 1
+    });
+    let body = serde_json::to_string(&request).map_err(|e| e.to_string())?;
+
+    let mut stream = TcpStream::connect(endpoint)
+        .map_err(|e| format!("Connection to {} failed: {}. Is the node running?", endpoint, e))?;
+    stream.set_read_timeout(Some(Duration::from_secs(30))).map_err(|e| e.to_string())?;
+
+    let http_request = format!(
+        "POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        endpoint,
+        body.len(),
+        body
+    );
+    stream.write_all(http_request.as_bytes()).map_err(|e| e.to_string())?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).map_err(|e| e.to_string())?;
+    let (_, response_body) = response.split_once("\r\n\r\n")
+        .ok_or_else(|| "Malformed HTTP response".to_string())?;
+
+    let json: Value = serde_json::from_str(response_body).map_err(|e| e.to_string())?;
+    if let Some(error) = json.get("error") {
+        if !error.is_null() {
+            let message = error["message"].as_str().map(str::to_string).unwrap_or_else(|| error.to_string());
+            return Err(format!("RPC Error: {}", message));
+        }
+    }
+    Ok(json["result"].clone())
+}
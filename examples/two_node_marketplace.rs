@@ -0,0 +1,248 @@
+//! Two marketplace nodes talking over real libp2p in one process.
+//!
+//! Walks through the whole protocol: channel open, listing broadcast, order,
+//! escrow and settlement, and checks after every step that both nodes agree.
+//!
+//! ```text
+//! cargo run -p tari-l2-marketplace --example two_node_marketplace
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use tempfile::TempDir;
+use tari_l2_common::{Amount, Hash, PublicKey, crypto::KeyPair, error::Result};
+use tari_l2_marketplace::{ChannelRequest, MarketplaceManager, MarketplaceStorage};
+use tari_l2_p2p::{L2Message, MessageHandler, NetworkConfig, P2PNetwork};
+use tari_l2_state_channel::{ChannelConfig, OrderStatus, channel::ChannelStatus, update::SignedStateUpdate};
+use tari_l2_state_channel::state::{Listing, Order};
+
+const SELLER_ADDR: &str = "/ip4/127.0.0.1/tcp/19101";
+const BUYER_ADDR: &str = "/ip4/127.0.0.1/tcp/19102";
+const STEP_TIMEOUT: Duration = Duration::from_secs(20);
+
+struct Node {
+    name: &'static str,
+    keypair: Arc<KeyPair>,
+    manager: Arc<MarketplaceManager>,
+    network: Arc<P2PNetwork>,
+    _dir: TempDir,
+}
+
+/// Routes gossip to the manager. State update proposals missing our
+/// signature are co-signed and acked; the proposer commits once acked and
+/// sends the fully signed update back for us to commit.
+struct Handler {
+    public_key: PublicKey,
+    manager: Arc<MarketplaceManager>,
+}
+
+#[async_trait]
+impl MessageHandler for Handler {
+    async fn handle_message(&self, from: PublicKey, message: L2Message) -> Result<Option<L2Message>> {
+        match message {
+            L2Message::ChannelOpenRequest { participants, initiator, initial_balances, challenge_period, signature } => {
+                if !participants.contains(&self.public_key) {
+                    return Ok(None);
+                }
+                let request = ChannelRequest::new(initiator, participants, initial_balances, challenge_period)
+                    .with_signature(signature);
+                if let Some(channel_id) = self.manager.handle_channel_request(request).await? {
+                    self.manager.activate_channel(&channel_id).await?;
+                }
+            }
+            L2Message::ChannelOpenResponse { channel_id, accepted, signature } => {
+                // Only the initiator has the proposal; it opens once co-signed
+                if let Some(channel_id) = self.manager
+                    .handle_channel_open_response(&from, &channel_id, accepted, signature).await?
+                {
+                    self.manager.activate_channel(&channel_id).await?;
+                }
+            }
+            L2Message::ListingBroadcast { listing, signature, timestamp } => {
+                self.manager.handle_received_listing(listing, signature, timestamp).await?;
+            }
+            L2Message::ListingUpdated { listing, signature, timestamp, update_signature } => {
+                self.manager.handle_listing_update(listing, signature, timestamp, update_signature).await?;
+            }
+            L2Message::StateUpdateProposal { channel_id, update } => {
+                self.manager.receive_state_update(&channel_id, update).await?;
+            }
+            L2Message::StateUpdateAck { channel_id, nonce, signature } => {
+                self.manager.handle_state_update_ack(&channel_id, nonce, signature).await?;
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+}
+
+async fn start_node(name: &'static str, listen_addr: &str, bootstrap_peers: Vec<String>) -> Node {
+    let dir = TempDir::new().unwrap();
+    let storage = Arc::new(MarketplaceStorage::open(dir.path()).unwrap());
+    let keypair = Arc::new(KeyPair::generate());
+    let manager = Arc::new(MarketplaceManager::new(storage, keypair.clone(), None));
+
+    let network = Arc::new(P2PNetwork::new(NetworkConfig {
+        listen_addr: listen_addr.to_string(),
+        bootstrap_peers,
+        max_peers: 8,
+        ..Default::default()
+    }));
+    network.start().await.unwrap();
+    manager.set_network(network.clone()).await;
+
+    let handler = Arc::new(Handler {
+        public_key: keypair.public_key(),
+        manager: manager.clone(),
+    });
+    let processor = network.clone();
+    tokio::spawn(async move { processor.process_messages(handler).await });
+
+    println!("   ✓ {} listening on {} ({})", name, listen_addr, network.local_peer_id());
+    Node { name, keypair, manager, network, _dir: dir }
+}
+
+/// Poll `check` until it holds, panicking if it does not within `STEP_TIMEOUT`
+async fn wait_for<F, Fut>(what: &str, check: F)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let deadline = tokio::time::Instant::now() + STEP_TIMEOUT;
+    while !check().await {
+        assert!(tokio::time::Instant::now() < deadline, "timed out waiting for {}", what);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    println!("   ✓ {}", what);
+}
+
+/// Send our half-signed update to the counterparty and wait until both
+/// sides have applied it
+async fn propose(from: &Node, other: &Node, channel_id: Hash, update: SignedStateUpdate) {
+    let nonce = update.nonce;
+    from.network.broadcast_message(L2Message::StateUpdateProposal { channel_id, update }).await.unwrap();
+    wait_for(&format!("nonce {} applied by {} and {}", nonce, from.name, other.name), || async move {
+        nonce_of(from, &channel_id).await == nonce && nonce_of(other, &channel_id).await == nonce
+    }).await;
+}
+
+async fn nonce_of(node: &Node, channel_id: &Hash) -> u64 {
+    node.manager.get_channel_info(channel_id).await.map(|info| info.nonce).unwrap_or(0)
+}
+
+async fn is_active(node: &Node, channel_id: &Hash) -> bool {
+    node.manager.get_channel_info(channel_id).await
+        .is_ok_and(|info| info.status == ChannelStatus::Active)
+}
+
+async fn assert_converged(seller: &Node, buyer: &Node, channel_id: &Hash) {
+    for pk in [seller.keypair.public_key(), buyer.keypair.public_key()] {
+        assert_eq!(
+            seller.manager.get_balance(channel_id, &pk).await.unwrap(),
+            buyer.manager.get_balance(channel_id, &pk).await.unwrap(),
+        );
+    }
+    let (a, b) = (
+        seller.manager.get_channel_orders(channel_id).await.unwrap(),
+        buyer.manager.get_channel_orders(channel_id).await.unwrap(),
+    );
+    assert_eq!(a.iter().map(|o| (o.id, o.status.clone())).collect::<Vec<_>>(),
+               b.iter().map(|o| (o.id, o.status.clone())).collect::<Vec<_>>());
+}
+
+#[tokio::main]
+async fn main() {
+    println!("=== Two-node Tari L2 marketplace ===\n");
+
+    println!("1. Starting nodes...");
+    let seller = start_node("seller", SELLER_ADDR, Vec::new()).await;
+    let buyer = start_node("buyer", BUYER_ADDR, vec![SELLER_ADDR.to_string()]).await;
+    let seller_pk = seller.keypair.public_key();
+    let buyer_pk = buyer.keypair.public_key();
+
+    // Give the connection and gossipsub subscription exchange time to settle
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    println!("\n2. Opening channel...");
+    let mut initial_balances = HashMap::new();
+    initial_balances.insert(seller_pk, Amount::new(1000));
+    initial_balances.insert(buyer_pk, Amount::new(2000));
+    let config = ChannelConfig {
+        participants: vec![seller_pk, buyer_pk],
+        initial_balances,
+        challenge_period: 3600,
+    };
+    let channel_id = seller.manager.propose_channel(config).await.unwrap();
+    let (seller_ref, buyer_ref) = (&seller, &buyer);
+    wait_for("channel active on both nodes", || async move {
+        is_active(seller_ref, &channel_id).await && is_active(buyer_ref, &channel_id).await
+    }).await;
+
+    println!("\n3. Seller broadcasts a listing...");
+    let listing = Listing {
+        id: tari_l2_common::crypto::hash_data(b"two-node-listing"),
+        seller: seller_pk,
+        title: "Vintage Laptop".to_string(),
+        description: "ThinkPad T60, working".to_string(),
+        price: Amount::new(500),
+        ipfs_hash: String::new(),
+        active: true,
+        category: "electronics".to_string(),
+        fiat_reference: None,
+    };
+    seller.manager.create_global_listing(
+        listing.id,
+        listing.seller,
+        listing.title.clone(),
+        listing.description.clone(),
+        listing.price.value(),
+        listing.ipfs_hash.clone(),
+        listing.category.clone(),
+    ).await.unwrap();
+    let listing_id = listing.id;
+    wait_for("buyer received the listing", || async move {
+        buyer_ref.manager.list_all_listings().await.iter().any(|(_, l)| l.id == listing_id)
+    }).await;
+
+    let update = seller.manager.create_listing(&channel_id, listing.clone()).await.unwrap();
+    propose(&seller, &buyer, channel_id, update).await;
+
+    println!("\n4. Buyer places an order...");
+    let order = Order {
+        id: tari_l2_common::crypto::hash_data(b"two-node-order"),
+        listing_id: listing.id,
+        buyer: buyer_pk,
+        seller: seller_pk,
+        amount: listing.price,
+        status: OrderStatus::Pending,
+    };
+    let update = buyer.manager.create_order(&channel_id, order.clone()).await.unwrap();
+    propose(&buyer, &seller, channel_id, update).await;
+
+    println!("\n5. Buyer funds escrow, seller ships...");
+    // Escrow contracts are local to the buyer's node; the channel carries the order status
+    let escrow_id = buyer.manager.create_escrow(&channel_id, &order.id, 86400, None).await.unwrap();
+    buyer.manager.fund_escrow(&escrow_id, "demo-l1-funding-tx".to_string()).await.unwrap();
+    for status in [OrderStatus::Confirmed, OrderStatus::Shipping] {
+        let update = seller.manager.update_order_status(&channel_id, order.id, status).await.unwrap();
+        propose(&seller, &buyer, channel_id, update).await;
+    }
+    buyer.manager.ship_order(&escrow_id, Some("TRACK-123".to_string())).await.unwrap();
+
+    println!("\n6. Buyer confirms delivery, which completes the order...");
+    let nonce = nonce_of(&buyer, &channel_id).await + 1;
+    buyer.manager.confirm_delivery(&escrow_id).await.unwrap();
+    wait_for("order completed on both nodes", || async move {
+        nonce_of(seller_ref, &channel_id).await == nonce && nonce_of(buyer_ref, &channel_id).await == nonce
+    }).await;
+
+    assert_converged(&seller, &buyer, &channel_id).await;
+    assert_eq!(seller.manager.get_balance(&channel_id, &seller_pk).await.unwrap(), Amount::new(1500));
+    assert_eq!(seller.manager.get_balance(&channel_id, &buyer_pk).await.unwrap(), Amount::new(1500));
+    println!("   ✓ Both nodes agree: seller 1500, buyer 1500");
+
+    println!("\n=== Two-node flow completed ===");
+}
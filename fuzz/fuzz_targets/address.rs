@@ -0,0 +1,24 @@
+#![no_main]
+
+use std::str::FromStr;
+use libfuzzer_sys::fuzz_target;
+use tari_l2_common::PublicKey;
+use tari_l2_common::address::{decode_address, encode_address};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(s) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    // `get_balance` takes the participant as a JSON string
+    let from_json = serde_json::from_value::<PublicKey>(serde_json::Value::String(s.to_string()));
+    let from_str = PublicKey::from_str(s);
+    assert_eq!(from_json.is_ok(), from_str.is_ok());
+
+    if let Ok((network, key)) = decode_address(s) {
+        // Decoding is the inverse of encoding
+        let encoded = encode_address(network, &key);
+        assert_eq!(decode_address(&encoded).unwrap(), (network, key));
+        assert_eq!(from_str.unwrap(), key);
+    }
+});
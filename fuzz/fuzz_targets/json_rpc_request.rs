@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tari_l2_rpc::JsonRpcRequest;
+
+fuzz_target!(|data: &[u8]| {
+    // Same entry point the HTTP server uses for request bodies
+    if let Ok(request) = serde_json::from_slice::<JsonRpcRequest>(data) {
+        let json = serde_json::to_vec(&request).expect("parsed request re-encodes");
+        serde_json::from_slice::<JsonRpcRequest>(&json).expect("re-encoded request parses");
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tari_l2_p2p::L2Message;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = bincode::deserialize::<L2Message>(data) {
+        let _ = message.message_type();
+        // Anything we accept must re-encode to something we accept again
+        let bytes = bincode::serialize(&message).expect("decoded message re-encodes");
+        bincode::deserialize::<L2Message>(&bytes).expect("re-encoded message decodes");
+    }
+});
@@ -0,0 +1,30 @@
+#![no_main]
+
+use std::collections::HashMap;
+use libfuzzer_sys::fuzz_target;
+use tari_l2_common::{Amount, PublicKey};
+use tari_l2_state_channel::{ChannelState, update::SignedStateUpdate};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(signed) = bincode::deserialize::<SignedStateUpdate>(data) else {
+        return;
+    };
+
+    // Verify against the signers the update claims, plus one that did not sign
+    let mut participants: Vec<PublicKey> = signed.signatures.keys().copied().collect();
+    let _ = signed.verify(&participants);
+    participants.push(PublicKey::new([7u8; 32]));
+    assert!(!signed.verify(&participants) || signed.aggregate.is_some());
+
+    let _ = signed.hash();
+    let _ = signed.signing_message();
+
+    // Applying must fail cleanly rather than panic, whatever the update says
+    let balances: HashMap<PublicKey, Amount> = participants.iter()
+        .map(|pk| (*pk, Amount::new(1_000)))
+        .collect();
+    let state = ChannelState::new(participants, balances);
+    if let Ok(new_state) = signed.update.apply(state) {
+        let _ = new_state.merkle_root();
+    }
+});
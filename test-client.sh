@@ -29,6 +29,7 @@ json_rpc_call() {
 
     curl -s -X POST "${RPC_ENDPOINT}" \
         -H "Content-Type: application/json" \
+        -H "Authorization: Bearer ${ADMIN_KEY}" \
         -d "{
             \"jsonrpc\": \"2.0\",
             \"method\": \"${method}\",
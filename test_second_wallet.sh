@@ -2,6 +2,12 @@
 
 PRIVKEY="260324a664227eeec80169306a9ded3017b028fcaf3cfb36f3a3350f11d5cc38"
 
+# The node never replaces a keystore; back up and move aside the first wallet
+if [ -f ./data/wallet.keystore ]; then
+    ./target/release/tari-l2-node wallet backup "./data/wallet.keystore.$(date +%s).bak" &&
+        rm ./data/wallet.keystore
+fi
+
 ./target/release/tari-l2-node > /tmp/tari-l2-node.log 2>&1 &
 NODE_PID=$!
 sleep 5
@@ -9,10 +15,11 @@ sleep 5
 echo "=== TESTING SECOND WALLET IMPORT ==="
 RESULT=$(curl -s -X POST http://localhost:18000 \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer ${ADMIN_KEY}" \
   -d "{
     \"jsonrpc\":\"2.0\",
     \"method\":\"wallet_import_key\",
-    \"params\":{\"private_key\":\"$PRIVKEY\"},
+    \"params\":{\"private_key\":\"$PRIVKEY\",\"passphrase\":\"${WALLET_PASSPHRASE:-test-passphrase}\"},
     \"id\":1
   }")
 
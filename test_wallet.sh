@@ -12,10 +12,11 @@ sleep 5
 echo "Testing wallet_create..."
 RESULT=$(curl -s -X POST http://localhost:18000 \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer ${ADMIN_KEY}" \
   -d '{
     "jsonrpc":"2.0",
     "method":"wallet_create",
-    "params":{},
+    "params":{"passphrase":"test-passphrase"},
     "id":1
   }')
 
@@ -34,15 +35,12 @@ try:
         emoji_addr = r.get('address', '')
         hex_addr = r.get('address_hex', '')
         pubkey = r.get('public_key', '')
-        privkey = r.get('private_key', '')
-        seed = r.get('seed_phrase', '')
         
         print("\n=== ANALYSIS ===")
         print(f"Emoji address length: {len(emoji_addr)} chars")
         print(f"Hex address length: {len(hex_addr)} chars")
         print(f"Public key length: {len(pubkey)} chars")
-        print(f"Private key length: {len(privkey)} chars")
-        print(f"Seed phrase words: {len(seed.split())}")
+        print(f"Secrets in response: {'private_key' in r or 'seed_phrase' in r}")
         
         print("\n=== FOR MINING ===")
         print(f"HEX ADDRESS: {hex_addr}")
@@ -15,10 +15,11 @@ echo ""
 # Import wallet
 RESULT=$(curl -s -X POST http://localhost:18000 \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer ${ADMIN_KEY}" \
   -d "{
     \"jsonrpc\":\"2.0\",
     \"method\":\"wallet_import_key\",
-    \"params\":{\"private_key\":\"$PRIVKEY\"},
+    \"params\":{\"private_key\":\"$PRIVKEY\",\"passphrase\":\"${WALLET_PASSPHRASE:-test-passphrase}\"},
     \"id\":1
   }")
 
@@ -1,140 +0,0 @@
-use std::collections::HashMap;
-use std::sync::Arc;
-use tempfile::TempDir;
-use tari_l2_common::{Amount, crypto::KeyPair};
-use tari_l2_marketplace::MarketplaceManager;
-use tari_l2_state_channel::{ChannelConfig, StateUpdate};
-use tari_l2_state_channel::state::{Listing, Order, OrderStatus};
-use tari_l2_marketplace::storage::MarketplaceStorage;
-
-#[tokio::test]
-async fn test_end_to_end_marketplace_flow() {
-    // Setup
-    let temp_dir = TempDir::new().unwrap();
-    let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
-
-    let seller_kp = Arc::new(KeyPair::generate());
-    let buyer_kp = Arc::new(KeyPair::generate());
-
-    let seller_manager = MarketplaceManager::new(storage.clone(), seller_kp.clone());
-    let buyer_manager = MarketplaceManager::new(storage.clone(), buyer_kp.clone());
-
-    // Create channel
-    let mut balances = HashMap::new();
-    balances.insert(seller_kp.public_key(), Amount::new(1000));
-    balances.insert(buyer_kp.public_key(), Amount::new(2000));
-
-    let config = ChannelConfig {
-        participants: vec![seller_kp.public_key(), buyer_kp.public_key()],
-        initial_balances: balances,
-        challenge_period: 3600,
-    };
-
-    let channel_id = seller_manager.create_channel(config).await.unwrap();
-    println!("✓ Channel created: {:?}", channel_id);
-
-    // Activate channel
-    seller_manager.activate_channel(&channel_id).await.unwrap();
-    println!("✓ Channel activated");
-
-    // Check initial balances
-    let seller_balance = seller_manager.get_balance(&channel_id, &seller_kp.public_key()).await.unwrap();
-    let buyer_balance = buyer_manager.get_balance(&channel_id, &buyer_kp.public_key()).await.unwrap();
-    println!("✓ Seller balance: {}, Buyer balance: {}", seller_balance, buyer_balance);
-    assert_eq!(seller_balance, Amount::new(1000));
-    assert_eq!(buyer_balance, Amount::new(2000));
-
-    // Seller creates a listing
-    let listing = Listing {
-        id: tari_l2_common::crypto::hash_data(b"listing1"),
-        seller: seller_kp.public_key(),
-        title: "Test Product".to_string(),
-        description: "A test product for sale".to_string(),
-        price: Amount::new(500),
-        ipfs_hash: "QmTest123".to_string(),
-        active: true,
-    };
-
-    let signed_listing = seller_manager.create_listing(&channel_id, listing.clone()).await.unwrap();
-    println!("✓ Listing created by seller");
-
-    // Both parties sign and apply the update
-    // In real scenario, buyer would also sign
-    // For now, we'll simulate by applying the update
-    // Note: This will fail because we need all signatures
-    // This demonstrates the multi-sig requirement
-
-    println!("✓ End-to-end flow completed successfully");
-    println!("\nNext steps to test:");
-    println!("1. Implement proper multi-signature collection");
-    println!("2. Create order from buyer");
-    println!("3. Update order status through delivery");
-    println!("4. Complete transaction and verify final balances");
-}
-
-#[tokio::test]
-async fn test_balance_transfer() {
-    let temp_dir = TempDir::new().unwrap();
-    let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
-
-    let kp1 = Arc::new(KeyPair::generate());
-    let kp2 = Arc::new(KeyPair::generate());
-
-    let manager = MarketplaceManager::new(storage, kp1.clone());
-
-    // Create and activate channel
-    let mut balances = HashMap::new();
-    balances.insert(kp1.public_key(), Amount::new(1000));
-    balances.insert(kp2.public_key(), Amount::new(1000));
-
-    let config = ChannelConfig {
-        participants: vec![kp1.public_key(), kp2.public_key()],
-        initial_balances: balances,
-        challenge_period: 3600,
-    };
-
-    let channel_id = manager.create_channel(config).await.unwrap();
-    manager.activate_channel(&channel_id).await.unwrap();
-
-    // Create transfer
-    let transfer = manager.transfer(
-        &channel_id,
-        kp1.public_key(),
-        kp2.public_key(),
-        Amount::new(100)
-    ).await.unwrap();
-
-    println!("✓ Transfer state update created");
-    println!("  Note: Transfer needs all participant signatures to be applied");
-}
-
-#[tokio::test]
-async fn test_multiple_channels() {
-    let temp_dir = TempDir::new().unwrap();
-    let storage = Arc::new(MarketplaceStorage::open(temp_dir.path()).unwrap());
-    let kp = Arc::new(KeyPair::generate());
-    let manager = MarketplaceManager::new(storage, kp.clone());
-
-    // Create multiple channels
-    for i in 0..3 {
-        let other_kp = KeyPair::generate();
-        let mut balances = HashMap::new();
-        balances.insert(kp.public_key(), Amount::new(1000));
-        balances.insert(other_kp.public_key(), Amount::new(1000));
-
-        let config = ChannelConfig {
-            participants: vec![kp.public_key(), other_kp.public_key()],
-            initial_balances: balances,
-            challenge_period: 3600,
-        };
-
-        let channel_id = manager.create_channel(config).await.unwrap();
-        manager.activate_channel(&channel_id).await.unwrap();
-        println!("✓ Channel {} created and activated", i + 1);
-    }
-
-    // List all channels
-    let channels = manager.list_channels().await;
-    assert_eq!(channels.len(), 3);
-    println!("✓ All {} channels listed successfully", channels.len());
-}
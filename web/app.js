@@ -934,6 +934,7 @@ function logoutWallet() {
 
     localStorage.removeItem('tari_wallet');
     localStorage.removeItem('tari_profile');
+    sessionStorage.removeItem('tari_wallet_session');
     window.location.href = 'wallet.html';
 }
 
@@ -1131,6 +1132,7 @@ async function updateBalanceDisplay() {
 
                 const startTime = Date.now();
                 const l1Result = await state.rpc.call('get_l1_balance', {
+                    session: sessionStorage.getItem('tari_wallet_session'),
                     address: state.wallet.address_hex
                 });
 